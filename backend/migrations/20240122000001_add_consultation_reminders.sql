@@ -0,0 +1,7 @@
+-- 视频问诊开始前提醒
+ALTER TABLE video_consultations
+    ADD COLUMN reminded BOOLEAN NOT NULL DEFAULT FALSE COMMENT '是否已发送开始前提醒' AFTER metadata,
+    ADD INDEX idx_video_consultations_reminder (status, reminded, scheduled_start_time);
+
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('video_call', 'reminder_window_minutes', '10', 'number', '问诊开始前提醒窗口（分钟）');
@@ -0,0 +1,94 @@
+-- 审计日志表
+CREATE TABLE audit_logs (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    actor_id CHAR(36) NOT NULL COMMENT '操作人ID',
+    action VARCHAR(100) NOT NULL COMMENT '操作类型',
+    target_type VARCHAR(50) NOT NULL COMMENT '操作对象类型',
+    target_id CHAR(36) NOT NULL COMMENT '操作对象ID',
+    details JSON COMMENT '操作详情',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    -- 索引
+    INDEX idx_audit_logs_actor_id (actor_id),
+    INDEX idx_audit_logs_target (target_type, target_id),
+    INDEX idx_audit_logs_created_at (created_at DESC)
+) COMMENT='审计日志表';
+
+-- 医生收款账户表
+CREATE TABLE doctor_payout_accounts (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    doctor_id CHAR(36) NOT NULL COMMENT '医生ID',
+    bank_name VARCHAR(100) NOT NULL COMMENT '开户银行',
+    account_holder VARCHAR(100) NOT NULL COMMENT '账户持有人',
+    account_number_encrypted TEXT NOT NULL COMMENT '加密后的银行账号',
+    account_number_last4 VARCHAR(4) NOT NULL COMMENT '账号末四位',
+    verification_status ENUM('pending', 'verified', 'rejected') NOT NULL DEFAULT 'pending' COMMENT '审核状态',
+    verified_by CHAR(36) COMMENT '审核人ID',
+    verified_at TIMESTAMP NULL COMMENT '审核时间',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+    -- 索引
+    INDEX idx_doctor_payout_accounts_doctor_id (doctor_id),
+
+    -- 外键
+    FOREIGN KEY (doctor_id) REFERENCES doctors(id) ON DELETE CASCADE
+) COMMENT='医生收款账户表';
+
+-- 医生提现申请表（pending 状态的金额即为冻结金额）
+CREATE TABLE doctor_withdrawals (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    withdrawal_no VARCHAR(50) UNIQUE NOT NULL COMMENT '提现单号',
+    doctor_id CHAR(36) NOT NULL COMMENT '医生ID',
+    payout_account_id CHAR(36) NOT NULL COMMENT '收款账户ID',
+    amount DECIMAL(10, 2) NOT NULL COMMENT '提现金额',
+    status ENUM('pending', 'paid', 'rejected') NOT NULL DEFAULT 'pending' COMMENT '提现状态',
+    transfer_reference VARCHAR(100) COMMENT '转账流水号',
+    reject_reason VARCHAR(500) COMMENT '驳回原因',
+    processed_by CHAR(36) COMMENT '处理人ID',
+    processed_at TIMESTAMP NULL COMMENT '处理时间',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+    -- 索引
+    INDEX idx_doctor_withdrawals_doctor_id (doctor_id),
+    INDEX idx_doctor_withdrawals_status (status),
+    INDEX idx_doctor_withdrawals_created_at (created_at DESC),
+
+    -- 外键
+    FOREIGN KEY (doctor_id) REFERENCES doctors(id) ON DELETE CASCADE,
+    FOREIGN KEY (payout_account_id) REFERENCES doctor_payout_accounts(id)
+) COMMENT='医生提现申请表';
+
+-- 支付/结算类通知
+ALTER TABLE notifications
+    MODIFY COLUMN type ENUM(
+        'appointment_reminder',
+        'appointment_confirmed',
+        'appointment_cancelled',
+        'prescription_ready',
+        'doctor_reply',
+        'system_announcement',
+        'review_reply',
+        'live_stream_reminder',
+        'group_message',
+        'payment_update'
+    ) NOT NULL;
+
+ALTER TABLE notification_settings
+    MODIFY COLUMN notification_type ENUM(
+        'appointment_reminder',
+        'appointment_confirmed',
+        'appointment_cancelled',
+        'prescription_ready',
+        'doctor_reply',
+        'system_announcement',
+        'review_reply',
+        'live_stream_reminder',
+        'group_message',
+        'payment_update'
+    ) NOT NULL;
+
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('withdrawal', 'min_amount', '100', 'number', '单笔最低提现金额（元）'),
+('withdrawal', 'monthly_limit', '2', 'number', '每月最多提现次数');
@@ -0,0 +1,20 @@
+-- 支付配置软删除
+ALTER TABLE payment_configs
+    ADD COLUMN deleted_at TIMESTAMP NULL COMMENT '删除时间（软删除）' AFTER description;
+
+-- 支付配置变更历史
+CREATE TABLE payment_config_history (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    payment_method ENUM('wechat', 'alipay') NOT NULL COMMENT '支付方式',
+    config_key VARCHAR(50) NOT NULL COMMENT '配置键',
+    action ENUM('create', 'update', 'delete') NOT NULL COMMENT '变更类型',
+    old_value TEXT COMMENT '变更前的值',
+    new_value TEXT COMMENT '变更后的值',
+    is_encrypted BOOLEAN NOT NULL DEFAULT FALSE COMMENT '是否为加密配置',
+    changed_by CHAR(36) NOT NULL COMMENT '操作人ID',
+    changed_at TIMESTAMP(6) NOT NULL DEFAULT CURRENT_TIMESTAMP(6),
+
+    -- 索引
+    INDEX idx_payment_config_history_key (payment_method, config_key),
+    INDEX idx_payment_config_history_changed_at (changed_at DESC)
+) COMMENT='支付配置变更历史表';
@@ -0,0 +1,19 @@
+-- 诊断关键词与内容分类映射（用于个性化内容推荐，管理员维护）
+CREATE TABLE diagnosis_category_mappings (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    keyword VARCHAR(100) NOT NULL COMMENT '诊断关键词',
+    category VARCHAR(50) NOT NULL COMMENT '内容分类',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+    UNIQUE KEY uk_keyword_category (keyword, category),
+    INDEX idx_category (category)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='诊断-内容分类映射表';
+
+INSERT INTO diagnosis_category_mappings (keyword, category) VALUES
+('失眠', '中医养生'),
+('脾胃', '中医养生'),
+('气虚', '中医养生'),
+('高血压', '健康科普'),
+('糖尿病', '健康科普'),
+('感冒', '健康科普');
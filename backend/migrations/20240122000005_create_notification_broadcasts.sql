@@ -0,0 +1,39 @@
+-- 广播人群分组表
+CREATE TABLE broadcast_segments (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    name VARCHAR(100) NOT NULL COMMENT '分组名称',
+    description VARCHAR(500) COMMENT '分组描述',
+    definition JSON NOT NULL COMMENT '分组条件定义',
+    created_by CHAR(36) NOT NULL COMMENT '创建人ID',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+    -- 索引
+    INDEX idx_broadcast_segments_created_by (created_by)
+) COMMENT='广播人群分组表';
+
+-- 广播发送记录表
+CREATE TABLE notification_broadcasts (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    title VARCHAR(200) NOT NULL COMMENT '通知标题',
+    content TEXT NOT NULL COMMENT '通知内容',
+    related_id CHAR(36) COMMENT '关联ID',
+    segment_id CHAR(36) COMMENT '使用的已保存分组ID',
+    definition JSON NOT NULL COMMENT '发送时的分组条件快照',
+    status ENUM('pending_approval', 'sent', 'rejected') NOT NULL COMMENT '广播状态',
+    targeted_count INT NOT NULL DEFAULT 0 COMMENT '命中人数',
+    delivered_count INT NOT NULL DEFAULT 0 COMMENT '送达人数',
+    skipped_count INT NOT NULL DEFAULT 0 COMMENT '因通知偏好跳过人数',
+    created_by CHAR(36) NOT NULL COMMENT '发起人ID',
+    reviewed_by CHAR(36) COMMENT '审批人ID',
+    reviewed_at TIMESTAMP NULL COMMENT '审批时间',
+    sent_at TIMESTAMP NULL COMMENT '发送时间',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    -- 索引
+    INDEX idx_notification_broadcasts_status (status),
+    INDEX idx_notification_broadcasts_created_at (created_at DESC),
+
+    -- 外键
+    FOREIGN KEY (segment_id) REFERENCES broadcast_segments(id) ON DELETE SET NULL
+) COMMENT='广播发送记录表';
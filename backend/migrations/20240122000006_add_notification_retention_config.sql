@@ -0,0 +1,3 @@
+-- 通知保留期配置（天），小于等于0表示不自动清理
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('notification', 'retention_days', '90', 'number', '已读通知保留天数，超过后自动清理');
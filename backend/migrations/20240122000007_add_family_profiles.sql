@@ -0,0 +1,19 @@
+-- 家庭成员就诊档案：预约、问诊、处方记录实际就诊人
+-- 代管档案可不填写身份证号
+ALTER TABLE patient_profiles
+    MODIFY COLUMN id_number VARCHAR(18) NULL;
+
+ALTER TABLE appointments
+    ADD COLUMN patient_profile_id CHAR(36) NULL COMMENT '就诊人档案ID，为空表示账号本人' AFTER patient_id,
+    ADD INDEX idx_appointments_patient_profile_id (patient_profile_id),
+    ADD CONSTRAINT fk_appointments_patient_profile FOREIGN KEY (patient_profile_id) REFERENCES patient_profiles(id);
+
+ALTER TABLE video_consultations
+    ADD COLUMN patient_profile_id CHAR(36) NULL COMMENT '就诊人档案ID，随预约带入' AFTER patient_id,
+    ADD INDEX idx_video_consultations_patient_profile_id (patient_profile_id),
+    ADD CONSTRAINT fk_video_consultations_patient_profile FOREIGN KEY (patient_profile_id) REFERENCES patient_profiles(id);
+
+ALTER TABLE prescriptions
+    ADD COLUMN patient_profile_id CHAR(36) NULL COMMENT '就诊人档案ID' AFTER patient_id,
+    ADD INDEX idx_prescriptions_patient_profile_id (patient_profile_id),
+    ADD CONSTRAINT fk_prescriptions_patient_profile FOREIGN KEY (patient_profile_id) REFERENCES patient_profiles(id);
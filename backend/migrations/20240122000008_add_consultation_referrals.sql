@@ -0,0 +1,5 @@
+-- 视频问诊转线下就诊：记录转诊来源问诊及转诊说明
+ALTER TABLE appointments
+    ADD COLUMN referred_from_consultation_id CHAR(36) NULL COMMENT '转诊来源的视频问诊ID' AFTER status,
+    ADD COLUMN referral_notes TEXT NULL COMMENT '医生转诊说明' AFTER referred_from_consultation_id,
+    ADD UNIQUE KEY uk_appointments_referred_from (referred_from_consultation_id);
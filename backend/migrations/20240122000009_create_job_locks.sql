@@ -0,0 +1,9 @@
+-- 后台任务锁（Redis 不可用时的数据库兜底，保证多实例下定时任务只在一个实例运行）
+CREATE TABLE job_locks (
+    name VARCHAR(100) PRIMARY KEY COMMENT '锁名称，如 job:consultation_reminders',
+    token CHAR(36) NOT NULL COMMENT '持有者标识，续期与释放时校验',
+    expires_at DATETIME(3) NOT NULL COMMENT '过期时间，持有者崩溃后锁自动失效',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    INDEX idx_expires_at (expires_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='后台任务锁表';
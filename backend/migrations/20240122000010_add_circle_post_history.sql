@@ -0,0 +1,17 @@
+-- 圈子帖子编辑历史：每次编辑前保存原内容，防止编辑后偷换讨论内容
+ALTER TABLE circle_posts
+    ADD COLUMN edited_at DATETIME NULL COMMENT '最后一次编辑时间，未编辑为空' AFTER status;
+
+CREATE TABLE circle_post_history (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    post_id CHAR(36) NOT NULL COMMENT '帖子ID',
+    editor_id CHAR(36) NOT NULL COMMENT '编辑人ID',
+    title VARCHAR(200) NOT NULL COMMENT '编辑前标题',
+    content TEXT NOT NULL COMMENT '编辑前内容',
+    images JSON NOT NULL COMMENT '编辑前图片',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '编辑时间',
+
+    FOREIGN KEY (post_id) REFERENCES circle_posts(id) ON DELETE CASCADE,
+    FOREIGN KEY (editor_id) REFERENCES users(id),
+    INDEX idx_post_created (post_id, created_at)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='圈子帖子编辑历史表';
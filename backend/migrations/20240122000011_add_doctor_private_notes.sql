@@ -0,0 +1,3 @@
+-- 医生私密备注（如确诊前的疑似诊断），仅接诊医生可见，不出现在患者端预约数据中
+ALTER TABLE appointments
+    ADD COLUMN doctor_private_notes TEXT NULL COMMENT '医生私密备注，仅接诊医生可见' AFTER referral_notes;
@@ -0,0 +1,15 @@
+-- 医生对患者的问诊反馈（爽约、辱骂等），仅医生和管理员可见，不对患者展示
+CREATE TABLE doctor_patient_feedback (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    consultation_id CHAR(36) NOT NULL COMMENT '问诊ID',
+    doctor_id CHAR(36) NOT NULL COMMENT '医生ID',
+    patient_id CHAR(36) NOT NULL COMMENT '患者ID',
+    rating INT NOT NULL COMMENT '患者配合度评分 1-5',
+    notes TEXT NULL COMMENT '医生备注',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (consultation_id) REFERENCES video_consultations(id) ON DELETE CASCADE,
+    UNIQUE KEY uk_feedback_consultation (consultation_id),
+    INDEX idx_feedback_patient (patient_id),
+    CONSTRAINT chk_feedback_rating CHECK (rating BETWEEN 1 AND 5)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='医生对患者反馈表';
@@ -0,0 +1,10 @@
+-- 各订单类型的金额上下限，防止 0 元或金额异常巨大的订单进入下游系统
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('order_amount', 'appointment_min', '1', 'number', '预约订单最低金额（元）'),
+('order_amount', 'appointment_max', '5000', 'number', '预约订单最高金额（元）'),
+('order_amount', 'consultation_min', '1', 'number', '咨询订单最低金额（元）'),
+('order_amount', 'consultation_max', '5000', 'number', '咨询订单最高金额（元）'),
+('order_amount', 'prescription_min', '1', 'number', '处方订单最低金额（元）'),
+('order_amount', 'prescription_max', '20000', 'number', '处方订单最高金额（元）'),
+('order_amount', 'other_min', '0.01', 'number', '其他订单最低金额（元）'),
+('order_amount', 'other_max', '50000', 'number', '其他订单最高金额（元）');
@@ -0,0 +1,6 @@
+-- 重新发送问诊入会邀请，记录最近一次发送时间用于限频
+ALTER TABLE video_consultations
+    ADD COLUMN invite_sent_at DATETIME NULL COMMENT '最近一次发送入会邀请的时间' AFTER reminded;
+
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('video_call', 'invite_resend_cooldown_seconds', '60', 'number', '重新发送入会邀请的最小间隔（秒）');
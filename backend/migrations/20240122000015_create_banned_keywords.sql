@@ -0,0 +1,21 @@
+-- 屏蔽词库：取代原圈子专用的 sensitive_words 表，供评价、圈子帖子和评论共用
+CREATE TABLE banned_keywords (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    word VARCHAR(100) NOT NULL UNIQUE COMMENT '屏蔽词，匹配时不区分大小写',
+    category VARCHAR(50) NULL COMMENT '分类',
+    severity ENUM('low', 'medium', 'high') NOT NULL DEFAULT 'medium',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    INDEX idx_banned_keywords_active (is_active)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='屏蔽词表';
+
+INSERT INTO banned_keywords (id, word, category, severity, is_active, created_at)
+SELECT id, word, category, severity, is_active, created_at FROM sensitive_words;
+
+DROP TABLE sensitive_words;
+
+-- 各类内容命中屏蔽词后的处理方式：reject 拒绝提交，mask 打码后保存
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('content_filter', 'review', 'mask', 'string', '评价内容命中屏蔽词的处理方式（reject/mask）'),
+('content_filter', 'circle_post', 'reject', 'string', '圈子帖子命中屏蔽词的处理方式（reject/mask）'),
+('content_filter', 'comment', 'reject', 'string', '帖子评论命中屏蔽词的处理方式（reject/mask）');
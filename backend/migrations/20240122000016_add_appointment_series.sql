@@ -0,0 +1,4 @@
+-- 周期预约：同一系列的预约共享 series_id，取消系列时一并取消未来的预约
+ALTER TABLE appointments
+    ADD COLUMN series_id CHAR(36) NULL COMMENT '周期预约系列ID' AFTER referral_notes,
+    ADD INDEX idx_appointments_series (series_id);
@@ -0,0 +1,5 @@
+-- 医生停诊设置：停诊期间拒绝预约、自动回复患者消息，并从医生列表中隐藏
+ALTER TABLE doctors
+    ADD COLUMN ooo_start DATETIME NULL COMMENT '停诊开始时间',
+    ADD COLUMN ooo_end DATETIME NULL COMMENT '停诊结束时间',
+    ADD COLUMN ooo_message VARCHAR(500) NULL COMMENT '停诊提示语';
@@ -0,0 +1,13 @@
+-- 视频问诊文字消息：问诊过程中的聊天记录，与通话事件一起生成问诊记录
+CREATE TABLE IF NOT EXISTS consultation_messages (
+    id CHAR(36) PRIMARY KEY,
+    consultation_id CHAR(36) NOT NULL COMMENT '问诊会话ID',
+    sender_id CHAR(36) NOT NULL COMMENT '发送者用户ID',
+    content TEXT NOT NULL COMMENT '消息内容',
+    created_at TIMESTAMP(3) NOT NULL DEFAULT CURRENT_TIMESTAMP(3),
+
+    INDEX idx_consultation_messages_consultation (consultation_id, created_at),
+
+    FOREIGN KEY (consultation_id) REFERENCES video_consultations(id),
+    FOREIGN KEY (sender_id) REFERENCES users(id)
+) COMMENT='视频问诊聊天消息表';
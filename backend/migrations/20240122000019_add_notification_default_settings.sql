@@ -0,0 +1,15 @@
+-- 新用户默认通知设置模板（按通知类型），注册时写入 notification_settings；
+-- 用户缺少某类型设置时也以此模板为准。模板中未列出的类型默认启用通知和推送
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('notification', 'default_settings', '{
+  "appointment_reminder": {"enabled": true, "email_enabled": false, "sms_enabled": true, "push_enabled": true},
+  "appointment_confirmed": {"enabled": true, "email_enabled": false, "sms_enabled": false, "push_enabled": true},
+  "appointment_cancelled": {"enabled": true, "email_enabled": false, "sms_enabled": true, "push_enabled": true},
+  "prescription_ready": {"enabled": true, "email_enabled": false, "sms_enabled": false, "push_enabled": true},
+  "doctor_reply": {"enabled": true, "email_enabled": false, "sms_enabled": false, "push_enabled": true},
+  "system_announcement": {"enabled": true, "email_enabled": false, "sms_enabled": false, "push_enabled": true},
+  "review_reply": {"enabled": true, "email_enabled": false, "sms_enabled": false, "push_enabled": true},
+  "live_stream_reminder": {"enabled": false, "email_enabled": false, "sms_enabled": false, "push_enabled": false},
+  "group_message": {"enabled": true, "email_enabled": false, "sms_enabled": false, "push_enabled": true},
+  "payment_update": {"enabled": true, "email_enabled": false, "sms_enabled": false, "push_enabled": true}
+}', 'json', '新用户默认通知设置模板');
@@ -0,0 +1,16 @@
+-- 医生贝叶斯加权评分：评价较少时向平台平均分收敛，用于医生列表按评分排序；
+-- average_rating 仍保留原始平均分用于展示
+ALTER TABLE doctors
+    ADD COLUMN weighted_rating DECIMAL(3,2) NOT NULL DEFAULT 0.00 COMMENT '加权评分（排序用）',
+    ADD INDEX idx_weighted_rating (weighted_rating DESC);
+
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('review', 'rating_prior_weight', '10', 'number', '加权评分的先验评价数，评价数越少越接近平台平均分');
+
+-- 按现有评价回填
+UPDATE doctors d
+CROSS JOIN (
+    SELECT COALESCE(AVG(rating), 0) AS mean FROM patient_reviews WHERE is_visible = TRUE
+) p
+SET d.weighted_rating = (d.total_reviews * d.average_rating + 10 * p.mean) / (d.total_reviews + 10)
+WHERE d.total_reviews > 0;
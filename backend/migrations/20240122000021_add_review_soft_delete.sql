@@ -0,0 +1,3 @@
+-- 患者可在修改时限内删除自己的评价（软删除），删除后评价隐藏且不计入医生统计
+ALTER TABLE patient_reviews
+    ADD COLUMN deleted_at TIMESTAMP NULL DEFAULT NULL COMMENT '删除时间';
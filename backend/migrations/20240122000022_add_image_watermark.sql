@@ -0,0 +1,9 @@
+-- 医疗图片水印：开启后上传完成的图片对外提供带水印的访问地址（由 OSS 图片处理生成），
+-- 原图地址仅保存在服务端
+ALTER TABLE file_uploads
+    ADD COLUMN original_url VARCHAR(500) NULL COMMENT '未加水印的原图URL（不对外返回）' AFTER file_url;
+
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('file_upload', 'enable_image_watermark', 'false', 'boolean', '是否为上传的图片添加水印'),
+('file_upload', 'image_watermark_text', '{user_id} {timestamp}', 'string', '水印文字模板，支持 {user_id}、{timestamp} 占位符'),
+('file_upload', 'keep_original_image', 'true', 'boolean', '加水印时是否保留原图地址（仅服务端可见）');
@@ -0,0 +1,17 @@
+-- 需要审核的圈子：用户申请加入后由圈主/圈管理员审批
+ALTER TABLE circles
+    ADD COLUMN requires_approval BOOLEAN NOT NULL DEFAULT FALSE COMMENT '加入是否需要审核' AFTER is_active;
+
+CREATE TABLE circle_join_requests (
+    id CHAR(36) PRIMARY KEY,
+    circle_id CHAR(36) NOT NULL,
+    user_id CHAR(36) NOT NULL,
+    status ENUM('pending', 'approved', 'rejected') NOT NULL DEFAULT 'pending',
+    handled_by CHAR(36) NULL COMMENT '审批人',
+    handled_at DATETIME NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (circle_id) REFERENCES circles(id) ON DELETE CASCADE,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    INDEX idx_circle_status (circle_id, status),
+    INDEX idx_user (user_id)
+);
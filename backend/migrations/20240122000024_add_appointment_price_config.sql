@@ -0,0 +1,4 @@
+-- 按就诊类型配置挂号费：为 0 时预约无需支付直接确认；留空表示仍通过支付回调确认
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('appointment_price', 'online_video', '', 'number', '线上视频问诊挂号费，0 表示免费并直接确认预约，留空表示需支付'),
+('appointment_price', 'offline', '', 'number', '线下门诊挂号费，0 表示免费并直接确认预约，留空表示需支付');
@@ -0,0 +1,3 @@
+-- 支付后可申请退款的最长天数，0 表示不限制；管理员发起的退款不受此限制
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('refund', 'window_days', '30', 'number', '支付后可申请退款的天数（0 为不限制）');
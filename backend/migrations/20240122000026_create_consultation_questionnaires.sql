@@ -0,0 +1,20 @@
+-- 视频问诊前问卷：医生为问诊附加症状问卷，患者入会前填写
+CREATE TABLE consultation_questionnaires (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    consultation_id CHAR(36) NOT NULL COMMENT '问诊ID',
+    doctor_id CHAR(36) NOT NULL COMMENT '医生ID',
+    title VARCHAR(200) NOT NULL COMMENT '问卷标题',
+    questions JSON NOT NULL COMMENT '问题列表',
+    is_required BOOLEAN NOT NULL DEFAULT TRUE COMMENT '是否需在问诊开始前填写',
+    answers JSON NULL COMMENT '患者答案，按问题 key 存储',
+    submitted_at DATETIME NULL COMMENT '患者提交时间',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+    FOREIGN KEY (consultation_id) REFERENCES video_consultations(id) ON DELETE CASCADE,
+    INDEX idx_questionnaires_consultation (consultation_id)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='问诊前问卷表';
+
+-- 开启后，必填问卷未填写完成时医生不能开始问诊
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('video_call', 'require_questionnaire_before_start', 'false', 'boolean', '必填问诊问卷未填写时禁止开始问诊');
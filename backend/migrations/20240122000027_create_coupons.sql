@@ -0,0 +1,19 @@
+-- 下单抵扣优惠券，used 通过条件更新递增，防止并发下超额核销
+CREATE TABLE coupons (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    code VARCHAR(50) NOT NULL COMMENT '优惠码',
+    discount_amount DECIMAL(10, 2) NOT NULL COMMENT '抵扣金额（元）',
+    usage_limit INT NOT NULL COMMENT '可使用次数',
+    used INT NOT NULL DEFAULT 0 COMMENT '已使用次数',
+    is_active BOOLEAN NOT NULL DEFAULT TRUE COMMENT '是否启用',
+    expires_at TIMESTAMP NULL COMMENT '过期时间',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+    UNIQUE KEY uk_coupons_code (code),
+    CONSTRAINT chk_coupons_used CHECK (used <= usage_limit)
+) COMMENT='优惠券表';
+
+ALTER TABLE payment_orders
+    ADD COLUMN coupon_id CHAR(36) NULL COMMENT '使用的优惠券ID' AFTER metadata,
+    ADD COLUMN discount_amount DECIMAL(10, 2) NOT NULL DEFAULT 0 COMMENT '优惠抵扣金额' AFTER coupon_id;
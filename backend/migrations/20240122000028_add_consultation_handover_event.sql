@@ -0,0 +1,10 @@
+-- 问诊转交给其他医生时记录 handover 事件
+ALTER TABLE video_call_events
+    MODIFY COLUMN event_type ENUM(
+        'joined', 'left', 'reconnected', 'disconnected',
+        'camera_on', 'camera_off', 'mic_on', 'mic_off',
+        'screen_share_start', 'screen_share_end',
+        'recording_start', 'recording_end',
+        'network_poor', 'network_recovered',
+        'handover'
+    ) NOT NULL COMMENT '事件类型';
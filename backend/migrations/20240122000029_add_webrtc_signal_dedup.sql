@@ -0,0 +1,7 @@
+-- 信令去重与限频：记录信令内容哈希，按房间和发送者统计发送频率
+ALTER TABLE webrtc_signals
+    ADD COLUMN payload_hash CHAR(64) NULL COMMENT '信令类型与内容的SHA-256哈希' AFTER payload,
+    ADD INDEX idx_webrtc_signals_sender (room_id, from_user_id, created_at);
+
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('video_call', 'signal_rate_limit_per_minute', '300', 'number', '每个用户在单个房间内每分钟最多发送的信令数');
@@ -0,0 +1,3 @@
+-- 医生回复后锁定评价，防止患者修改内容与回复不符
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('review', 'lock_after_reply', 'true', 'boolean', '医生回复后患者不能再修改评价');
@@ -0,0 +1,4 @@
+-- 便于口头告知和手动输入的短房间码，问诊结束后清空以便复用
+ALTER TABLE video_consultations
+    ADD COLUMN room_code CHAR(6) NULL COMMENT '短房间码' AFTER room_id,
+    ADD UNIQUE KEY uk_video_consultations_room_code (room_code);
@@ -0,0 +1,20 @@
+-- 问诊预计时长：医生可单独设置，未设置时使用全局默认值；进行中的问诊超时后提醒医生一次
+ALTER TABLE doctors
+    ADD COLUMN consultation_duration_minutes INT NULL COMMENT '视频问诊预计时长（分钟），为空时使用系统默认值';
+
+ALTER TABLE video_consultations
+    ADD COLUMN expected_duration INT NULL COMMENT '预计时长（分钟）' AFTER duration,
+    ADD COLUMN overtime_notified BOOLEAN NOT NULL DEFAULT FALSE COMMENT '是否已发送超时提醒' AFTER expected_duration;
+
+ALTER TABLE video_call_events
+    MODIFY COLUMN event_type ENUM(
+        'joined', 'left', 'reconnected', 'disconnected',
+        'camera_on', 'camera_off', 'mic_on', 'mic_off',
+        'screen_share_start', 'screen_share_end',
+        'recording_start', 'recording_end',
+        'network_poor', 'network_recovered',
+        'handover', 'overtime'
+    ) NOT NULL COMMENT '事件类型';
+
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('video_call', 'default_consultation_minutes', '30', 'number', '视频问诊默认预计时长（分钟）');
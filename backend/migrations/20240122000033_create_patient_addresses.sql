@@ -0,0 +1,22 @@
+-- 患者常用地址：上门问诊时选择；有地址时必须且只能有一个默认地址
+CREATE TABLE patient_addresses (
+    id CHAR(36) PRIMARY KEY,
+    user_id CHAR(36) NOT NULL,
+    recipient_name VARCHAR(50) NOT NULL COMMENT '联系人',
+    phone VARCHAR(11) NOT NULL COMMENT '联系电话',
+    province VARCHAR(50) NOT NULL,
+    city VARCHAR(50) NOT NULL,
+    district VARCHAR(50) NOT NULL,
+    detail VARCHAR(200) NOT NULL COMMENT '详细地址',
+    is_default BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+    FOREIGN KEY (user_id) REFERENCES users(id) ON DELETE CASCADE,
+    INDEX idx_patient_addresses_user_id (user_id)
+);
+
+ALTER TABLE appointments
+    MODIFY COLUMN visit_type ENUM('online_video', 'offline', 'home_visit') NOT NULL,
+    ADD COLUMN address_id CHAR(36) NULL COMMENT '上门问诊地址ID' AFTER visit_type,
+    ADD INDEX idx_appointments_address_id (address_id),
+    ADD CONSTRAINT fk_appointments_address FOREIGN KEY (address_id) REFERENCES patient_addresses(id);
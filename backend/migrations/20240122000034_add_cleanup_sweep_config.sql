@@ -0,0 +1,5 @@
+-- 数据清理任务：按批删除，批与批之间短暂停顿，所有清理任务共用一个运行间隔依次执行
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('cleanup', 'interval_seconds', '3600', 'number', '数据清理任务的运行间隔（秒），最小 60'),
+('cleanup', 'batch_size', '500', 'number', '每批最多删除或更新的行数'),
+('cleanup', 'batch_pause_ms', '100', 'number', '两批之间的暂停时间（毫秒）');
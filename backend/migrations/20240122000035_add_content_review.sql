@@ -0,0 +1,15 @@
+-- 内容发布审核：开启后医生发布的文章、视频需管理员审核通过才会上线
+ALTER TABLE articles
+    MODIFY COLUMN status ENUM('draft', 'pending_review', 'published', 'offline') NOT NULL DEFAULT 'draft' COMMENT '状态',
+    ADD COLUMN review_notes VARCHAR(500) NULL COMMENT '审核意见' AFTER publish_channels,
+    ADD COLUMN reviewed_by CHAR(36) NULL COMMENT '审核人' AFTER review_notes,
+    ADD COLUMN reviewed_at DATETIME NULL AFTER reviewed_by;
+
+ALTER TABLE videos
+    MODIFY COLUMN status ENUM('draft', 'processing', 'pending_review', 'published', 'offline') NOT NULL DEFAULT 'draft' COMMENT '状态',
+    ADD COLUMN review_notes VARCHAR(500) NULL COMMENT '审核意见' AFTER publish_channels,
+    ADD COLUMN reviewed_by CHAR(36) NULL COMMENT '审核人' AFTER review_notes,
+    ADD COLUMN reviewed_at DATETIME NULL AFTER reviewed_by;
+
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('content', 'require_publish_approval', 'false', 'boolean', '医生发布文章、视频是否需要管理员审核');
@@ -0,0 +1,7 @@
+-- 余额充值：充值订单经微信/支付宝支付成功后计入用户余额
+ALTER TABLE payment_orders
+    MODIFY COLUMN order_type ENUM('appointment', 'consultation', 'prescription', 'recharge', 'other') NOT NULL COMMENT '订单类型';
+
+INSERT INTO price_configs (service_type, service_name, price, description) VALUES
+('recharge_min', '单笔充值下限', 10.00, '单笔余额充值的最低金额（元）'),
+('recharge_max', '单笔充值上限', 5000.00, '单笔余额充值的最高金额（元）');
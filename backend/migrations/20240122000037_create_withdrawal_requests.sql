@@ -0,0 +1,23 @@
+-- 余额提现申请表（pending 状态的金额已从可用余额转入冻结余额）
+CREATE TABLE withdrawal_requests (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    withdrawal_no VARCHAR(50) UNIQUE NOT NULL COMMENT '提现单号',
+    user_id CHAR(36) NOT NULL COMMENT '用户ID',
+    amount DECIMAL(10, 2) NOT NULL COMMENT '提现金额',
+    payout_method ENUM('wechat', 'alipay') NOT NULL COMMENT '到账方式',
+    payout_account VARCHAR(100) NOT NULL COMMENT '到账账户',
+    status ENUM('pending', 'approved', 'rejected') NOT NULL DEFAULT 'pending' COMMENT '审核状态',
+    reviewed_by CHAR(36) COMMENT '审核人ID',
+    reviewed_at TIMESTAMP NULL COMMENT '审核时间',
+    review_notes VARCHAR(500) COMMENT '审核备注',
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+    -- 索引
+    INDEX idx_withdrawal_requests_user_id (user_id),
+    INDEX idx_withdrawal_requests_status (status),
+    INDEX idx_withdrawal_requests_created_at (created_at DESC),
+
+    -- 外键
+    FOREIGN KEY (user_id) REFERENCES users(id)
+) COMMENT='余额提现申请表';
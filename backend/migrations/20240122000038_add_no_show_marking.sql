@@ -0,0 +1,6 @@
+-- 爽约标记：超过预约开始时间仍未开始的视频问诊及其预约标记为 no_show
+ALTER TABLE appointments
+    MODIFY COLUMN status ENUM('pending', 'confirmed', 'completed', 'cancelled', 'no_show') NOT NULL DEFAULT 'pending';
+
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('video_call', 'no_show_grace_minutes', '15', 'number', '超过预约开始时间多少分钟仍未开始的视频问诊记为爽约，0 表示不自动标记');
@@ -0,0 +1,6 @@
+-- 文章、视频全文检索：ngram 分词以支持中文，最短可检索词长度由 ngram_token_size 决定（默认 2）
+ALTER TABLE articles
+    ADD FULLTEXT INDEX ft_articles_search (title, summary, content) WITH PARSER ngram;
+
+ALTER TABLE videos
+    ADD FULLTEXT INDEX ft_videos_search (title, description) WITH PARSER ngram;
@@ -0,0 +1,8 @@
+-- 定时发布：到期前保持草稿，由后台任务在到期后发布（需审核时转为待审核）
+ALTER TABLE articles
+    ADD COLUMN scheduled_publish_at DATETIME NULL COMMENT '定时发布时间' AFTER publish_channels,
+    ADD INDEX idx_scheduled_publish_at (scheduled_publish_at);
+
+ALTER TABLE videos
+    ADD COLUMN scheduled_publish_at DATETIME NULL COMMENT '定时发布时间' AFTER publish_channels,
+    ADD INDEX idx_scheduled_publish_at (scheduled_publish_at);
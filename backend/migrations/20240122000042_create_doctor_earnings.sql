@@ -0,0 +1,24 @@
+-- 医生收益明细：预约订单支付时记入收益，退款时记入负数冲减，平台按比例抽成
+CREATE TABLE doctor_earnings (
+    id CHAR(36) PRIMARY KEY,
+    doctor_id CHAR(36) NOT NULL COMMENT '医生ID',
+    order_id CHAR(36) NOT NULL COMMENT '订单ID',
+    appointment_id CHAR(36) NOT NULL COMMENT '预约ID',
+    entry_type ENUM('income', 'refund_adjustment') NOT NULL COMMENT '明细类型',
+    source_id CHAR(36) NOT NULL COMMENT '来源：收益为订单ID，退款冲减为退款记录ID',
+    gross_amount DECIMAL(10, 2) NOT NULL COMMENT '订单金额，冲减为负数',
+    commission_rate DECIMAL(5, 2) NOT NULL COMMENT '平台抽成比例（%）',
+    commission_amount DECIMAL(10, 2) NOT NULL COMMENT '平台抽成金额，冲减为负数',
+    net_amount DECIMAL(10, 2) NOT NULL COMMENT '医生实得金额，冲减为负数',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+
+    UNIQUE KEY uk_entry_source (entry_type, source_id),
+    INDEX idx_doctor_created (doctor_id, created_at),
+    INDEX idx_order_id (order_id),
+    INDEX idx_appointment_id (appointment_id),
+    FOREIGN KEY (doctor_id) REFERENCES doctors(id) ON DELETE CASCADE,
+    FOREIGN KEY (order_id) REFERENCES payment_orders(id) ON DELETE CASCADE
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='医生收益明细表';
+
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('payment', 'doctor_commission_rate', '20', 'number', '预约订单平台抽成比例（%），其余计入医生收益');
@@ -0,0 +1,4 @@
+-- 上传校验读取的通用限制此前没有配置行，管理员无法修改
+INSERT INTO system_configs (category, config_key, config_value, value_type, description) VALUES
+('file_upload', 'max_file_size', '104857600', 'number', '单个文件最大大小（字节），图片、视频另受各自上限限制'),
+('file_upload', 'allowed_mime_types', '["image/jpeg","image/png","image/gif","image/webp","video/mp4","video/webm","video/quicktime","application/pdf","application/msword","application/vnd.openxmlformats-officedocument.wordprocessingml.document","audio/mpeg","audio/wav","audio/ogg"]', 'json', '允许上传的 MIME 类型，为空数组时不限制');
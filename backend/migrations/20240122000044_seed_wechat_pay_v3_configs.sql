@@ -0,0 +1,4 @@
+-- 微信支付 APIv3 请求签名需要商户API证书私钥及其序列号
+INSERT INTO payment_configs (payment_method, config_key, config_value, is_encrypted, description) VALUES
+('wechat', 'private_key', '', TRUE, '微信支付商户API证书私钥（PEM）'),
+('wechat', 'serial_no', '', FALSE, '微信支付商户API证书序列号');
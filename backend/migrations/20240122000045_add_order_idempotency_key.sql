@@ -0,0 +1,4 @@
+-- 客户端超时重试下单时，凭幂等键返回已创建的订单而不是重复下单
+ALTER TABLE payment_orders
+    ADD COLUMN idempotency_key VARCHAR(64) NULL COMMENT '客户端幂等键，24小时内同一用户重复提交返回原订单' AFTER order_no,
+    ADD UNIQUE KEY uk_payment_orders_user_idempotency (user_id, idempotency_key);
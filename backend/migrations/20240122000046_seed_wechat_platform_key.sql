@@ -0,0 +1,4 @@
+-- 校验微信支付回调签名所需的平台公钥
+INSERT INTO payment_configs (payment_method, config_key, config_value, is_encrypted, description) VALUES
+('wechat', 'platform_public_key', '', FALSE, '微信支付平台公钥（PEM），用于校验回调签名'),
+('wechat', 'platform_serial_no', '', FALSE, '微信支付平台公钥ID/证书序列号，配置后校验回调的 Wechatpay-Serial');
@@ -0,0 +1,16 @@
+-- 支付渠道会重试回调通知，每次投递都留档以便审计
+CREATE TABLE payment_callback_logs (
+    id CHAR(36) PRIMARY KEY DEFAULT (UUID()),
+    payment_method ENUM('wechat', 'alipay', 'bank_card', 'balance') NOT NULL COMMENT '支付方式',
+    order_no VARCHAR(50) NULL COMMENT '回调中的订单号',
+    external_transaction_id VARCHAR(100) NULL COMMENT '第三方交易ID',
+    transaction_id CHAR(36) NULL COMMENT '匹配到的支付流水ID',
+    callback_status VARCHAR(20) NULL COMMENT '回调报告的支付结果',
+    outcome ENUM('processed', 'duplicate', 'rejected', 'error') NOT NULL COMMENT '处理结果：已处理、重复通知、验签失败、处理出错',
+    error_message VARCHAR(500) NULL COMMENT '错误信息',
+    raw_data JSON NULL COMMENT '回调原始报文',
+    received_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP COMMENT '接收时间',
+
+    INDEX idx_payment_callback_logs_order_no (order_no),
+    INDEX idx_payment_callback_logs_received_at (received_at)
+) COMMENT='支付回调投递日志';
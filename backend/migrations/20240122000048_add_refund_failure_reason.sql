@@ -0,0 +1,7 @@
+-- 第三方退款失败时记录渠道返回的原因
+ALTER TABLE refund_records
+    ADD COLUMN failure_reason VARCHAR(500) NULL COMMENT '退款失败原因' AFTER review_notes;
+
+-- 微信退款结果通知地址
+INSERT INTO payment_configs (payment_method, config_key, config_value, is_encrypted, description) VALUES
+('wechat', 'refund_notify_url', 'https://your-domain.com/api/v1/payment/refund/callback?method=wechat', FALSE, '微信支付退款结果通知地址');
@@ -0,0 +1,10 @@
+-- 优惠券支持按比例折扣、使用门槛和生效时间；订单记录优惠前金额
+ALTER TABLE coupons
+    ADD COLUMN discount_type ENUM('fixed', 'percent') NOT NULL DEFAULT 'fixed' COMMENT '优惠方式：固定金额或按比例' AFTER code,
+    MODIFY COLUMN discount_amount DECIMAL(10, 2) NOT NULL DEFAULT 0 COMMENT '抵扣金额（元），固定金额券使用',
+    ADD COLUMN discount_percent DECIMAL(5, 2) NULL COMMENT '折扣比例（%），按比例券使用' AFTER discount_amount,
+    ADD COLUMN min_order_amount DECIMAL(10, 2) NOT NULL DEFAULT 0 COMMENT '使用门槛（元）' AFTER discount_percent,
+    ADD COLUMN starts_at TIMESTAMP NULL COMMENT '生效时间' AFTER is_active;
+
+ALTER TABLE payment_orders
+    ADD COLUMN original_amount DECIMAL(10, 2) GENERATED ALWAYS AS (amount + discount_amount) STORED COMMENT '优惠前金额' AFTER discount_amount;
@@ -0,0 +1,4 @@
+-- 预约订单使用余额支付时先冻结，就诊完成后结算，就诊前取消则解冻退回
+ALTER TABLE payment_orders
+    ADD COLUMN balance_hold_status ENUM('held', 'settled', 'released') NULL COMMENT '余额托管状态：冻结中、已结算、已解冻退回' AFTER payment_time,
+    ADD INDEX idx_payment_orders_balance_hold (appointment_id, balance_hold_status);
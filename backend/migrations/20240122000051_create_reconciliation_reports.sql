@@ -0,0 +1,18 @@
+-- 每日对账结果：渠道账单与本地支付流水逐笔比对，重跑同一天会覆盖原报告
+CREATE TABLE reconciliation_reports (
+    id CHAR(36) PRIMARY KEY,
+    bill_date DATE NOT NULL COMMENT '账单日期（北京时间）',
+    status ENUM('matched', 'mismatched', 'failed') NOT NULL COMMENT '对账结果：一致、存在差异、账单获取失败',
+    local_count INT NOT NULL DEFAULT 0 COMMENT '本地成功支付笔数',
+    local_amount DECIMAL(12, 2) NOT NULL DEFAULT 0 COMMENT '本地成功支付金额',
+    remote_count INT NOT NULL DEFAULT 0 COMMENT '渠道账单笔数',
+    remote_amount DECIMAL(12, 2) NOT NULL DEFAULT 0 COMMENT '渠道账单金额',
+    missing_local JSON NOT NULL COMMENT '渠道有、本地无的交易',
+    missing_remote JSON NOT NULL COMMENT '本地有、渠道无的交易',
+    amount_mismatches JSON NOT NULL COMMENT '金额不一致的交易',
+    error_message VARCHAR(500) NULL COMMENT '账单获取失败原因',
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP ON UPDATE CURRENT_TIMESTAMP,
+
+    UNIQUE KEY uk_reconciliation_bill_date (bill_date)
+) ENGINE=InnoDB DEFAULT CHARSET=utf8mb4 COLLATE=utf8mb4_unicode_ci COMMENT='支付对账报告';
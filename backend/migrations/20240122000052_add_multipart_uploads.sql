@@ -0,0 +1,16 @@
+-- 大文件分片上传：记录对象存储的分片上传 ID 及各分片状态，中断后可续传
+ALTER TABLE file_uploads
+    ADD COLUMN multipart_upload_id VARCHAR(255) NULL COMMENT '对象存储分片上传 ID，普通上传为空' AFTER etag,
+    ADD COLUMN part_size BIGINT NULL COMMENT '分片大小（字节）' AFTER multipart_upload_id,
+    ADD COLUMN part_count INT NULL COMMENT '分片数量' AFTER part_size;
+
+CREATE TABLE file_upload_parts (
+    upload_id CHAR(36) NOT NULL,
+    part_number INT NOT NULL COMMENT '分片序号，从 1 开始',
+    size BIGINT NOT NULL COMMENT '分片大小（字节）',
+    etag VARCHAR(255) NULL COMMENT '上传分片后对象存储返回的 ETag',
+    status ENUM('pending', 'uploaded') NOT NULL DEFAULT 'pending',
+    uploaded_at TIMESTAMP NULL,
+    PRIMARY KEY (upload_id, part_number),
+    FOREIGN KEY (upload_id) REFERENCES file_uploads(id) ON DELETE CASCADE
+);
@@ -1,8 +1,10 @@
-use sqlx::{MySql, MySqlPool, Pool};
+use sqlx::{migrate::Migrator, MySql, MySqlPool, Pool};
 use std::env;
 
 pub type DbPool = Pool<MySql>;
 
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
 pub async fn create_pool() -> Result<DbPool, sqlx::Error> {
     let database_url = env::var("DATABASE_URL").expect("DATABASE_URL must be set");
 
@@ -10,5 +12,5 @@ pub async fn create_pool() -> Result<DbPool, sqlx::Error> {
 }
 
 pub async fn run_migrations(pool: &DbPool) -> Result<(), sqlx::migrate::MigrateError> {
-    sqlx::migrate!("./migrations").run(pool).await
+    MIGRATOR.run(pool).await
 }
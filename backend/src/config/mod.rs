@@ -11,6 +11,8 @@ pub struct Config {
     pub jwt_secret: String,
     pub jwt_expiration: i64,
     pub server_port: u16,
+    /// 未支付订单过期检查的运行间隔（秒）
+    pub order_expiry_interval_secs: u64,
 }
 
 impl Config {
@@ -26,6 +28,10 @@ impl Config {
                 .unwrap_or_else(|_| "3000".to_string())
                 .parse()
                 .unwrap_or(3000),
+            order_expiry_interval_secs: env::var("ORDER_EXPIRY_INTERVAL_SECS")
+                .unwrap_or_else(|_| "60".to_string())
+                .parse()
+                .unwrap_or(60),
         })
     }
 }
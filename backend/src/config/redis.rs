@@ -1,5 +1,7 @@
-use redis::{aio::ConnectionManager, Client};
+use redis::{aio::ConnectionManager, Client, RedisError, Script};
 use std::env;
+use std::time::Duration;
+use uuid::Uuid;
 
 pub type RedisPool = ConnectionManager;
 
@@ -27,3 +29,83 @@ pub async fn create_redis_pool_optional() -> Option<RedisPool> {
         }
     }
 }
+
+/// 分布式锁，持有者以随机 token 标识，只有持有者能续期或释放
+#[derive(Debug, Clone)]
+pub struct RedisLock {
+    pub key: String,
+    pub token: String,
+}
+
+/// 仅当锁仍属于当前持有者时才删除
+const RELEASE_LOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("DEL", KEYS[1])
+else
+    return 0
+end
+"#;
+
+/// 仅当锁仍属于当前持有者时才延长过期时间
+const EXTEND_LOCK_SCRIPT: &str = r#"
+if redis.call("GET", KEYS[1]) == ARGV[1] then
+    return redis.call("PEXPIRE", KEYS[1], ARGV[2])
+else
+    return 0
+end
+"#;
+
+/// 尝试获取锁（SET NX PX），锁被其他持有者占用时返回 None
+pub async fn acquire_lock(
+    redis: &RedisPool,
+    key: &str,
+    ttl: Duration,
+) -> Result<Option<RedisLock>, RedisError> {
+    let mut conn = redis.clone();
+    let token = Uuid::new_v4().to_string();
+
+    let result: Option<String> = redis::cmd("SET")
+        .arg(key)
+        .arg(&token)
+        .arg("NX")
+        .arg("PX")
+        .arg(ttl.as_millis() as u64)
+        .query_async(&mut conn)
+        .await?;
+
+    Ok(result.map(|_| RedisLock {
+        key: key.to_string(),
+        token,
+    }))
+}
+
+/// 释放锁，锁已过期或被他人持有时返回 false
+pub async fn release_lock(redis: &RedisPool, lock: &RedisLock) -> Result<bool, RedisError> {
+    let mut conn = redis.clone();
+
+    let deleted: i64 = Script::new(RELEASE_LOCK_SCRIPT)
+        .key(&lock.key)
+        .arg(&lock.token)
+        .invoke_async(&mut conn)
+        .await?;
+
+    Ok(deleted == 1)
+}
+
+/// 续期锁，锁已丢失时返回 false
+pub async fn extend_lock(
+    redis: &RedisPool,
+    lock: &RedisLock,
+    ttl: Duration,
+) -> Result<bool, RedisError> {
+    let mut conn = redis.clone();
+
+    let extended: i64 = Script::new(EXTEND_LOCK_SCRIPT)
+        .key(&lock.key)
+        .arg(&lock.token)
+        .arg(ttl.as_millis() as u64)
+        .invoke_async(&mut conn)
+        .await?;
+
+    Ok(extended == 1)
+}
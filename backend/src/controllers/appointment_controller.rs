@@ -1,7 +1,7 @@
 use crate::{
     middleware::auth::AuthUser,
-    models::{appointment::*, ApiResponse},
-    services::appointment_service,
+    models::{appointment::*, patient_profile::AppointmentPatientInfo, ApiResponse},
+    services::{appointment_service, doctor_service},
     AppState,
 };
 use axum::{
@@ -9,12 +9,14 @@ use axum::{
     http::StatusCode,
     Extension, Json,
 };
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
 use serde::Deserialize;
+use utoipa::IntoParams;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListQuery {
     page: Option<u32>,
     per_page: Option<u32>,
@@ -23,12 +25,34 @@ pub struct ListQuery {
     date_to: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ScheduleQuery {
+    /// 日期，如 `2024-01-20`，默认今天
+    date: Option<NaiveDate>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct AvailableSlotsQuery {
     doctor_id: Uuid,
     date: DateTime<Utc>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/appointments",
+    tag = "appointments",
+    params(
+        ListQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "预约列表", body = ApiResponseAppointmentList),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 500, description = "查询失败", body = ApiMessage)
+    )
+)]
 pub async fn list_appointments(
     Extension(_auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -61,11 +85,26 @@ pub async fn list_appointments(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/appointments/{id}",
+    tag = "appointments",
+    params(
+        ("id" = Uuid, Path, description = "预约 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "预约详情，接诊医生查看时含私密备注", body = ApiResponseAppointmentDetail),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权查看该预约", body = ApiMessage),
+        (status = 404, description = "预约不存在", body = ApiMessage)
+    )
+)]
 pub async fn get_appointment(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
     Path(id): Path<Uuid>,
-) -> Result<Json<ApiResponse<Appointment>>, (StatusCode, Json<ApiResponse<()>>)> {
+) -> Result<Json<ApiResponse<AppointmentDetail>>, (StatusCode, Json<ApiResponse<()>>)> {
     let appointment = match appointment_service::get_appointment_by_id(&app_state.pool, id).await {
         Ok(apt) => apt,
         Err(e) => {
@@ -76,27 +115,128 @@ pub async fn get_appointment(
         }
     };
 
+    let is_treating_doctor =
+        appointment_service::get_doctor_user_id(&app_state.pool, appointment.doctor_id)
+            .await
+            .ok()
+            == Some(auth_user.user_id);
+
     // Check if user has permission to view this appointment
-    if auth_user.user_id != appointment.patient_id && auth_user.role != "admin" {
-        // Check if user is the doctor
-        let doctor =
-            appointment_service::get_doctor_user_id(&app_state.pool, appointment.doctor_id)
-                .await
-                .ok();
-        if doctor != Some(auth_user.user_id) {
-            return Err((
-                StatusCode::FORBIDDEN,
-                Json(ApiResponse::error("Insufficient permissions")),
-            ));
-        }
+    if auth_user.user_id != appointment.patient_id
+        && auth_user.role != "admin"
+        && !is_treating_doctor
+    {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Insufficient permissions")),
+        ));
     }
 
+    // Private notes are only attached to the treating doctor's view
+    let doctor_private_notes = if is_treating_doctor {
+        appointment_service::get_doctor_private_notes(&app_state.pool, id)
+            .await
+            .map_err(|e| {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiResponse::error(&format!(
+                        "Failed to get private notes: {}",
+                        e
+                    ))),
+                )
+            })?
+    } else {
+        None
+    };
+
     Ok(Json(ApiResponse::success(
         "Appointment retrieved successfully",
-        appointment,
+        AppointmentDetail {
+            appointment,
+            doctor_private_notes,
+        },
     )))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/appointments/{id}/private-notes",
+    tag = "appointments",
+    request_body = UpdatePrivateNotesDto,
+    params(
+        ("id" = Uuid, Path, description = "预约 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "私密备注已保存", body = ApiResponseAppointmentDetail),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅接诊医生可编辑", body = ApiMessage),
+        (status = 404, description = "预约不存在", body = ApiMessage)
+    )
+)]
+pub async fn update_private_notes(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<UpdatePrivateNotesDto>,
+) -> Result<Json<ApiResponse<AppointmentDetail>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role != "doctor" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error(
+                "Only the treating doctor can edit private notes",
+            )),
+        ));
+    }
+
+    if let Err(e) = dto.validate() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&format!("Validation error: {}", e))),
+        ));
+    }
+
+    match appointment_service::update_doctor_private_notes(
+        &app_state.pool,
+        id,
+        auth_user.user_id,
+        dto,
+    )
+    .await
+    {
+        Ok(detail) => Ok(Json(ApiResponse::success(
+            "Private notes updated successfully",
+            detail,
+        ))),
+        Err(e) => {
+            let message = e.to_string();
+            let status = if message.contains("Appointment not found") {
+                StatusCode::NOT_FOUND
+            } else if message.contains("Only the treating doctor") {
+                StatusCode::FORBIDDEN
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+
+            Err((status, Json(ApiResponse::error(&message))))
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/appointments",
+    tag = "appointments",
+    request_body = CreateAppointmentDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "预约创建成功", body = ApiResponseAppointment),
+        (status = 400, description = "参数校验失败或医生停诊", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅患者本人可预约", body = ApiMessage)
+    )
+)]
 pub async fn create_appointment(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -124,6 +264,19 @@ pub async fn create_appointment(
             "Appointment created successfully",
             appointment,
         ))),
+        Err(e) if e.to_string().contains("out of office") => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&e.to_string())),
+        )),
+        Err(e)
+            if e.to_string().contains("Patient profile not found")
+                || e.to_string().contains("visit address") =>
+        {
+            Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::error(&e.to_string())),
+            ))
+        }
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ApiResponse::error(&format!(
@@ -134,6 +287,128 @@ pub async fn create_appointment(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/appointments/series",
+    tag = "appointments",
+    request_body = CreateAppointmentSeriesDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "周期预约已生成，冲突的日期在 skipped 中列出", body = ApiResponseAppointmentSeries),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅患者本人可预约", body = ApiMessage)
+    )
+)]
+pub async fn create_appointment_series(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Json(mut dto): Json<CreateAppointmentSeriesDto>,
+) -> Result<Json<ApiResponse<AppointmentSeriesResult>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role == "patient" {
+        dto.patient_id = auth_user.user_id;
+    } else if auth_user.role != "admin" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Only patients can create appointments")),
+        ));
+    }
+
+    dto.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&format!("Validation error: {}", e))),
+        )
+    })?;
+
+    match appointment_service::create_appointment_series(&app_state.pool, dto).await {
+        Ok(result) => Ok(Json(ApiResponse::success(
+            "Appointment series created successfully",
+            result,
+        ))),
+        Err(e)
+            if e.to_string().contains("Patient profile not found")
+                || e.to_string().contains("visit address") =>
+        {
+            Err((
+                StatusCode::BAD_REQUEST,
+                Json(ApiResponse::error(&e.to_string())),
+            ))
+        }
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to create appointment series: {}",
+                e
+            ))),
+        )),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/appointments/series/{series_id}/cancel",
+    tag = "appointments",
+    params(
+        ("series_id" = Uuid, Path, description = "周期预约系列 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "未来的预约已取消，返回系列内全部预约", body = ApiResponseAppointmentList),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅患者本人或管理员可取消", body = ApiMessage),
+        (status = 404, description = "系列不存在", body = ApiMessage)
+    )
+)]
+pub async fn cancel_appointment_series(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(series_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<Appointment>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    match appointment_service::cancel_appointment_series(
+        &app_state.pool,
+        series_id,
+        auth_user.user_id,
+        auth_user.role == "admin",
+    )
+    .await
+    {
+        Ok(appointments) => Ok(Json(ApiResponse::success(
+            "Appointment series cancelled successfully",
+            appointments,
+        ))),
+        Err(e) => {
+            let message = e.to_string();
+            let status = if message.contains("not found") {
+                StatusCode::NOT_FOUND
+            } else if message.contains("Insufficient permissions") {
+                StatusCode::FORBIDDEN
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+
+            Err((status, Json(ApiResponse::error(&message))))
+        }
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/appointments/{id}",
+    tag = "appointments",
+    request_body = UpdateAppointmentDto,
+    params(
+        ("id" = Uuid, Path, description = "预约 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "预约更新成功", body = ApiResponseAppointment),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权修改该预约", body = ApiMessage),
+        (status = 404, description = "预约不存在", body = ApiMessage)
+    )
+)]
 pub async fn update_appointment(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -180,6 +455,21 @@ pub async fn update_appointment(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/appointments/{id}/cancel",
+    tag = "appointments",
+    params(
+        ("id" = Uuid, Path, description = "预约 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "预约已取消", body = ApiResponseAppointment),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权取消该预约", body = ApiMessage),
+        (status = 404, description = "预约不存在", body = ApiMessage)
+    )
+)]
 pub async fn cancel_appointment(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -220,6 +510,131 @@ pub async fn cancel_appointment(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/appointments/{id}/transfer",
+    tag = "appointments",
+    request_body = TransferAppointmentDto,
+    params(
+        ("id" = Uuid, Path, description = "预约 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "预约已转给指定就诊人", body = ApiResponseAppointment),
+        (status = 400, description = "就诊已开始或档案无效", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅预约账号本人可转让", body = ApiMessage),
+        (status = 404, description = "预约不存在", body = ApiMessage)
+    )
+)]
+pub async fn transfer_appointment(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<TransferAppointmentDto>,
+) -> Result<Json<ApiResponse<Appointment>>, (StatusCode, Json<ApiResponse<()>>)> {
+    match appointment_service::transfer_appointment(&app_state.pool, id, auth_user.user_id, dto)
+        .await
+    {
+        Ok(appointment) => Ok(Json(ApiResponse::success(
+            "Appointment transferred successfully",
+            appointment,
+        ))),
+        Err(e) => {
+            let message = e.to_string();
+            let status = if message.contains("Appointment not found") {
+                StatusCode::NOT_FOUND
+            } else if message.contains("Insufficient permissions") {
+                StatusCode::FORBIDDEN
+            } else if message.contains("Patient profile not found")
+                || message.contains("already booked")
+                || message.contains("cannot be transferred")
+                || message.contains("Only upcoming")
+            {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+
+            Err((status, Json(ApiResponse::error(&message))))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/appointments/{id}/patient",
+    tag = "appointments",
+    params(
+        ("id" = Uuid, Path, description = "预约 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "就诊人信息，代管档案附带管理账号联系方式", body = ApiResponseAppointmentPatient),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅预约账号、接诊医生或管理员可查看", body = ApiMessage),
+        (status = 404, description = "预约不存在", body = ApiMessage)
+    )
+)]
+pub async fn get_appointment_patient(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<AppointmentPatientInfo>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let appointment = match appointment_service::get_appointment_by_id(&app_state.pool, id).await {
+        Ok(apt) => apt,
+        Err(_) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(ApiResponse::error("Appointment not found")),
+            ))
+        }
+    };
+
+    // The managing account, the treating doctor and admins may see who the visit is for
+    if auth_user.user_id != appointment.patient_id && auth_user.role != "admin" {
+        let doctor =
+            appointment_service::get_doctor_user_id(&app_state.pool, appointment.doctor_id)
+                .await
+                .ok();
+        if doctor != Some(auth_user.user_id) {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ApiResponse::error("Insufficient permissions")),
+            ));
+        }
+    }
+
+    match appointment_service::get_appointment_patient_info(&app_state.pool, &appointment).await {
+        Ok(info) => Ok(Json(ApiResponse::success(
+            "Appointment patient retrieved successfully",
+            info,
+        ))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to retrieve appointment patient: {}",
+                e
+            ))),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/appointments/doctor/{doctor_id}",
+    tag = "appointments",
+    params(
+        ("doctor_id" = Uuid, Path, description = "医生 ID"),
+        ListQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "医生的预约列表", body = ApiResponseAppointmentList),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权查看", body = ApiMessage)
+    )
+)]
 pub async fn get_doctor_appointments(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -263,18 +678,97 @@ pub async fn get_doctor_appointments(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/appointments/doctor/{doctor_id}/schedule",
+    tag = "appointments",
+    params(
+        ("doctor_id" = Uuid, Path, description = "医生 ID"),
+        ScheduleQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "医生当日日程，按时间段排序", body = ApiResponseDoctorSchedule),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权查看", body = ApiMessage)
+    )
+)]
+pub async fn get_doctor_schedule(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(doctor_id): Path<Uuid>,
+    Query(query): Query<ScheduleQuery>,
+) -> Result<Json<ApiResponse<Vec<DoctorScheduleItem>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    // Only the doctor themselves or an admin can see the agenda
+    let doctor_user_id = appointment_service::get_doctor_user_id(&app_state.pool, doctor_id)
+        .await
+        .ok();
+    if auth_user.role != "admin" && doctor_user_id != Some(auth_user.user_id) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Insufficient permissions")),
+        ));
+    }
+
+    let date = query.date.unwrap_or_else(|| Utc::now().date_naive());
+
+    match appointment_service::get_doctor_day_schedule(&app_state.pool, doctor_id, date).await {
+        Ok(schedule) => Ok(Json(ApiResponse::success(
+            "Doctor schedule retrieved successfully",
+            schedule,
+        ))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to retrieve doctor schedule: {}",
+                e
+            ))),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/appointments/patient/{patient_id}",
+    tag = "appointments",
+    params(
+        ("patient_id" = Uuid, Path, description = "患者用户 ID"),
+        ListQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "患者的预约列表", body = ApiResponseAppointmentList),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权查看", body = ApiMessage)
+    )
+)]
 pub async fn get_patient_appointments(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
     Path(patient_id): Path<Uuid>,
     Query(query): Query<ListQuery>,
 ) -> Result<Json<ApiResponse<Vec<Appointment>>>, (StatusCode, Json<ApiResponse<()>>)> {
-    // Users can view their own appointments, admins can view any
+    // Users can view their own appointments, admins can view any, and doctors can
+    // view patients they have a non-cancelled appointment with
     if auth_user.user_id != patient_id && auth_user.role != "admin" {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(ApiResponse::error("Insufficient permissions")),
-        ));
+        let is_treating = auth_user.role == "doctor"
+            && doctor_service::is_treating_doctor(&app_state.pool, patient_id, auth_user.user_id)
+                .await
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiResponse::error(&format!(
+                            "Failed to check treating doctor: {}",
+                            e
+                        ))),
+                    )
+                })?;
+        if !is_treating {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ApiResponse::error("Insufficient permissions")),
+            ));
+        }
     }
 
     let page = query.page.unwrap_or(1);
@@ -303,6 +797,20 @@ pub async fn get_patient_appointments(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/appointments/available-slots",
+    tag = "appointments",
+    params(
+        AvailableSlotsQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "可预约时间段", body = ApiResponseTimeSlots),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 500, description = "查询失败", body = ApiMessage)
+    )
+)]
 pub async fn get_available_slots(
     State(app_state): State<AppState>,
     Query(query): Query<AvailableSlotsQuery>,
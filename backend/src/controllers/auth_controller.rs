@@ -8,6 +8,17 @@ use axum::{extract::State, http::StatusCode, Extension, Json};
 use axum_extra::{headers, TypedHeader};
 use validator::Validate;
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/register",
+    tag = "auth",
+    request_body = CreateUserDto,
+    responses(
+        (status = 200, description = "注册成功", body = ApiResponseUser),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 500, description = "注册失败", body = ApiMessage)
+    )
+)]
 pub async fn register(
     State(app_state): State<AppState>,
     Json(dto): Json<CreateUserDto>,
@@ -31,6 +42,17 @@ pub async fn register(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/login",
+    tag = "auth",
+    request_body = LoginDto,
+    responses(
+        (status = 200, description = "登录成功，返回访问令牌", body = ApiResponseLogin),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "账号或密码错误", body = ApiMessage)
+    )
+)]
 pub async fn login(
     State(app_state): State<AppState>,
     Json(dto): Json<LoginDto>,
@@ -53,6 +75,16 @@ pub async fn login(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/auth/logout",
+    tag = "auth",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "登出成功，令牌失效", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage)
+    )
+)]
 pub async fn logout(
     State(app_state): State<AppState>,
     Extension(_auth_user): Extension<AuthUser>,
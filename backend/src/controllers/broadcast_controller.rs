@@ -0,0 +1,164 @@
+use crate::{
+    middleware::auth::AuthUser,
+    models::{broadcast::*, ApiResponse},
+    services::broadcast_service::{BroadcastLimits, BroadcastService},
+    utils::errors::AppError,
+    AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use serde::Deserialize;
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Deserialize)]
+pub struct BroadcastListQuery {
+    pub status: Option<String>,
+}
+
+fn require_admin(auth_user: &AuthUser) -> Result<(), AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+    Ok(())
+}
+
+// Segment endpoints
+pub async fn create_segment(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<CreateSegmentDto>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&auth_user)?;
+    dto.validate()?;
+
+    let segment = BroadcastService::create_segment(&state.pool, auth_user.user_id, dto).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success("分组创建成功", segment)),
+    ))
+}
+
+pub async fn list_segments(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&auth_user)?;
+
+    let segments = BroadcastService::list_segments(&state.pool).await?;
+
+    Ok(Json(ApiResponse::success("获取分组列表成功", segments)))
+}
+
+pub async fn delete_segment(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(segment_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&auth_user)?;
+
+    BroadcastService::delete_segment(&state.pool, segment_id).await?;
+
+    Ok(Json(ApiResponse::success("分组删除成功", ())))
+}
+
+pub async fn preview_segment(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<PreviewSegmentDto>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&auth_user)?;
+
+    let preview = BroadcastService::preview_segment(&state.pool, dto).await?;
+
+    Ok(Json(ApiResponse::success("分组预览成功", preview)))
+}
+
+// Broadcast endpoints
+pub async fn create_broadcast(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<CreateBroadcastDto>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&auth_user)?;
+    dto.validate()?;
+
+    let broadcast = BroadcastService::create_broadcast(
+        &state.pool,
+        auth_user.user_id,
+        dto,
+        &BroadcastLimits::from_env(),
+    )
+    .await?;
+
+    let message = if broadcast.status == BroadcastStatus::PendingApproval {
+        "目标人数较多，广播已提交等待其他管理员审批"
+    } else {
+        "广播发送成功"
+    };
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success(message, broadcast)),
+    ))
+}
+
+pub async fn list_broadcasts(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<BroadcastListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&auth_user)?;
+
+    let broadcasts = BroadcastService::list_broadcasts(&state.pool, query.status).await?;
+
+    Ok(Json(ApiResponse::success("获取广播列表成功", broadcasts)))
+}
+
+pub async fn get_broadcast(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(broadcast_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&auth_user)?;
+
+    let broadcast = BroadcastService::get_broadcast(&state.pool, broadcast_id).await?;
+
+    Ok(Json(ApiResponse::success("获取广播详情成功", broadcast)))
+}
+
+pub async fn approve_broadcast(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(broadcast_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&auth_user)?;
+
+    let broadcast = BroadcastService::approve_broadcast(
+        &state.pool,
+        broadcast_id,
+        auth_user.user_id,
+        &BroadcastLimits::from_env(),
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success("广播已审批并发送", broadcast)))
+}
+
+pub async fn reject_broadcast(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(broadcast_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    require_admin(&auth_user)?;
+
+    let broadcast =
+        BroadcastService::reject_broadcast(&state.pool, broadcast_id, auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success("广播已驳回", broadcast)))
+}
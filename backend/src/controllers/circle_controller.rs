@@ -1,5 +1,7 @@
 use crate::middleware::auth::AuthUser;
-use crate::models::{ApiResponse, CreateCircleDto, UpdateCircleDto, UpdateMemberRoleDto};
+use crate::models::{
+    ApiResponse, BulkApproveJoinRequestsDto, CreateCircleDto, UpdateCircleDto, UpdateMemberRoleDto,
+};
 use crate::services::circle_service::CircleService;
 use crate::AppState;
 use axum::{
@@ -205,7 +207,7 @@ pub async fn join_circle(
     State(state): State<AppState>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
-    CircleService::join_circle(&state.pool, id, auth_user.user_id)
+    let joined = CircleService::join_circle(&state.pool, id, auth_user.user_id)
         .await
         .map_err(|e| {
             if e.to_string().contains("Already joined") {
@@ -213,6 +215,11 @@ pub async fn join_circle(
                     StatusCode::BAD_REQUEST,
                     Json(ApiResponse::error("Already joined this circle")),
                 )
+            } else if e.to_string().contains("already pending") {
+                (
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiResponse::error("Join request already pending")),
+                )
             } else {
                 (
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -221,7 +228,11 @@ pub async fn join_circle(
             }
         })?;
 
-    Ok(Json(ApiResponse::success("Joined circle successfully", ())))
+    if joined {
+        Ok(Json(ApiResponse::success("Joined circle successfully", ())))
+    } else {
+        Ok(Json(ApiResponse::success("Join request submitted", ())))
+    }
 }
 
 pub async fn leave_circle(
@@ -365,6 +376,82 @@ pub async fn remove_member(
     )))
 }
 
+pub async fn get_join_requests(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let is_admin = auth_user.role == "admin";
+    let requests = CircleService::get_join_requests(&state.pool, id, auth_user.user_id, is_admin)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("No permission") {
+                (
+                    StatusCode::FORBIDDEN,
+                    Json(ApiResponse::error("No permission to manage join requests")),
+                )
+            } else {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiResponse::error(&format!(
+                        "Failed to get join requests: {}",
+                        e
+                    ))),
+                )
+            }
+        })?;
+
+    Ok(Json(ApiResponse::success(
+        "Join requests retrieved successfully",
+        serde_json::to_value(&requests).unwrap(),
+    )))
+}
+
+pub async fn bulk_approve_join_requests(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<BulkApproveJoinRequestsDto>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if let Err(e) = dto.validate() {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&format!("Validation error: {}", e))),
+        ));
+    }
+
+    let is_admin = auth_user.role == "admin";
+    let results = CircleService::bulk_approve_join_requests(
+        &state.pool,
+        id,
+        dto.request_ids,
+        auth_user.user_id,
+        is_admin,
+    )
+    .await
+    .map_err(|e| {
+        if e.to_string().contains("No permission") {
+            (
+                StatusCode::FORBIDDEN,
+                Json(ApiResponse::error("No permission to manage join requests")),
+            )
+        } else {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error(&format!(
+                    "Failed to approve join requests: {}",
+                    e
+                ))),
+            )
+        }
+    })?;
+
+    Ok(Json(ApiResponse::success(
+        "Join requests processed",
+        serde_json::to_value(&results).unwrap(),
+    )))
+}
+
 pub async fn get_user_circles(
     Extension(auth_user): Extension<AuthUser>,
     State(state): State<AppState>,
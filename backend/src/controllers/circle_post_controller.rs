@@ -174,6 +174,42 @@ pub async fn update_post(
     )))
 }
 
+pub async fn get_post_history(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let is_admin = auth_user.role == "admin";
+    let history = CirclePostService::get_post_history(&state.pool, id, auth_user.user_id, is_admin)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("No permission") {
+                (
+                    StatusCode::FORBIDDEN,
+                    Json(ApiResponse::error("No permission to view post history")),
+                )
+            } else if e.to_string().contains("no rows") {
+                (
+                    StatusCode::NOT_FOUND,
+                    Json(ApiResponse::error("Post not found")),
+                )
+            } else {
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiResponse::error(&format!(
+                        "Failed to get post history: {}",
+                        e
+                    ))),
+                )
+            }
+        })?;
+
+    Ok(Json(ApiResponse::success(
+        "Post history retrieved successfully",
+        serde_json::to_value(&history).unwrap(),
+    )))
+}
+
 pub async fn delete_post(
     Extension(auth_user): Extension<AuthUser>,
     State(state): State<AppState>,
@@ -275,6 +311,59 @@ pub async fn get_circle_posts(
     )))
 }
 
+pub async fn get_member_posts(
+    Extension(auth_user): Extension<AuthUser>,
+    State(state): State<AppState>,
+    Path((circle_id, user_id)): Path<(Uuid, Uuid)>,
+    Query(query): Query<PaginationQuery>,
+) -> Result<Json<ApiResponse<serde_json::Value>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query.page_size.unwrap_or(10).min(100);
+    let is_admin = auth_user.role == "admin";
+
+    let (posts, total) = CirclePostService::get_member_posts(
+        &state.pool,
+        circle_id,
+        user_id,
+        auth_user.user_id,
+        is_admin,
+        page,
+        page_size,
+    )
+    .await
+    .map_err(|e| {
+        if e.to_string().contains("No permission") {
+            (
+                StatusCode::FORBIDDEN,
+                Json(ApiResponse::error(
+                    "No permission to view this member's posts",
+                )),
+            )
+        } else {
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::error(&format!(
+                    "Failed to get member posts: {}",
+                    e
+                ))),
+            )
+        }
+    })?;
+
+    Ok(Json(ApiResponse::success(
+        "Member posts retrieved successfully",
+        serde_json::json!({
+            "posts": posts,
+            "pagination": {
+                "page": page,
+                "page_size": page_size,
+                "total": total,
+                "total_pages": (total as f64 / page_size as f64).ceil() as i64,
+            }
+        }),
+    )))
+}
+
 // Like endpoints
 pub async fn toggle_like(
     Extension(auth_user): Extension<AuthUser>,
@@ -1,7 +1,10 @@
 use crate::{
     middleware::auth::AuthUser,
     models::{content::*, ApiResponse},
-    services::content_service,
+    services::{
+        cache_service::{CacheKeys, CacheService},
+        content_service,
+    },
     AppState,
 };
 use axum::{
@@ -10,24 +13,48 @@ use axum::{
     Extension, Json,
 };
 use serde::Deserialize;
+use utoipa::IntoParams;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListQuery {
     page: Option<u32>,
     per_page: Option<u32>,
     category: Option<String>,
     status: Option<String>,
     search: Option<String>,
+    /// 排序方式：relevance 按全文检索相关度（需配合 search），默认按发布时间
+    sort: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct RecommendedQuery {
+    page: Option<u32>,
+    per_page: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct CategoryQuery {
     content_type: Option<String>,
 }
 
 // Article controllers
+#[utoipa::path(
+    get,
+    path = "/api/v1/content/articles",
+    tag = "content",
+    params(
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "文章列表", body = ApiResponseArticleList),
+        (status = 500, description = "查询失败", body = ApiMessage)
+    )
+)]
 pub async fn list_articles(
     State(app_state): State<AppState>,
     Query(query): Query<ListQuery>,
@@ -42,6 +69,7 @@ pub async fn list_articles(
         query.category,
         query.status,
         query.search,
+        query.sort,
     )
     .await
     {
@@ -59,11 +87,26 @@ pub async fn list_articles(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/content/articles/{id}",
+    tag = "content",
+    params(
+        ("id" = Uuid, Path, description = "文章 ID")
+    ),
+    responses(
+        (status = 200, description = "文章详情，携带登录令牌时返回 has_liked", body = ApiResponseArticle),
+        (status = 404, description = "文章不存在", body = ApiMessage)
+    )
+)]
 pub async fn get_article(
+    auth_user: Option<Extension<AuthUser>>,
     State(app_state): State<AppState>,
     Path(id): Path<Uuid>,
 ) -> Result<Json<ApiResponse<Article>>, (StatusCode, Json<ApiResponse<()>>)> {
-    match content_service::get_article_by_id(&app_state.pool, id).await {
+    let viewer_id = auth_user.map(|Extension(user)| user.user_id);
+
+    match content_service::get_article_by_id(&app_state.pool, id, true, viewer_id).await {
         Ok(article) => Ok(Json(ApiResponse::success(
             "Article retrieved successfully",
             article,
@@ -75,6 +118,67 @@ pub async fn get_article(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/content/articles/{id}/preview",
+    tag = "content",
+    params(
+        ("id" = Uuid, Path, description = "文章 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "文章预览（含草稿，不计浏览量）", body = ApiResponseArticle),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅作者或管理员可预览", body = ApiMessage),
+        (status = 404, description = "文章不存在", body = ApiMessage)
+    )
+)]
+pub async fn preview_article(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Article>>, (StatusCode, Json<ApiResponse<()>>)> {
+    match content_service::get_article_preview(
+        &app_state.pool,
+        id,
+        auth_user.user_id,
+        &auth_user.role,
+    )
+    .await
+    {
+        Ok(article) => Ok(Json(ApiResponse::success(
+            "Article preview retrieved successfully",
+            article,
+        ))),
+        Err(e) => {
+            if e.to_string().contains("Insufficient permissions") {
+                Err((
+                    StatusCode::FORBIDDEN,
+                    Json(ApiResponse::error("Insufficient permissions")),
+                ))
+            } else {
+                Err((
+                    StatusCode::NOT_FOUND,
+                    Json(ApiResponse::error(&format!("Article not found: {}", e))),
+                ))
+            }
+        }
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/articles",
+    tag = "content",
+    request_body = CreateArticleDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "文章创建成功", body = ApiResponseArticle),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅医生或管理员可发布", body = ApiMessage)
+    )
+)]
 pub async fn create_article(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -127,6 +231,23 @@ pub async fn create_article(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/content/articles/{id}",
+    tag = "content",
+    request_body = UpdateArticleDto,
+    params(
+        ("id" = Uuid, Path, description = "文章 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "文章更新成功", body = ApiResponseArticle),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权修改", body = ApiMessage),
+        (status = 404, description = "文章不存在", body = ApiMessage)
+    )
+)]
 pub async fn update_article(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -172,6 +293,22 @@ pub async fn update_article(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/articles/{id}/publish",
+    tag = "content",
+    request_body = PublishArticleDto,
+    params(
+        ("id" = Uuid, Path, description = "文章 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "文章已发布；开启发布审核时进入待审核状态", body = ApiResponseArticle),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权发布", body = ApiMessage),
+        (status = 404, description = "文章不存在", body = ApiMessage)
+    )
+)]
 pub async fn publish_article(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -210,6 +347,21 @@ pub async fn publish_article(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/articles/{id}/unpublish",
+    tag = "content",
+    params(
+        ("id" = Uuid, Path, description = "文章 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "文章已下架", body = ApiResponseArticle),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权下架", body = ApiMessage),
+        (status = 404, description = "文章不存在", body = ApiMessage)
+    )
+)]
 pub async fn unpublish_article(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -246,6 +398,94 @@ pub async fn unpublish_article(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/articles/{id}/like",
+    tag = "content",
+    params(
+        ("id" = Uuid, Path, description = "文章 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "已点赞，重复点赞不重复计数", body = ApiResponseArticleLikeStatus),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 404, description = "文章不存在", body = ApiMessage)
+    )
+)]
+pub async fn like_article(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<ArticleLikeStatus>>, (StatusCode, Json<ApiResponse<()>>)> {
+    match content_service::like_article(&app_state.pool, auth_user.user_id, id).await {
+        Ok(status) => Ok(Json(ApiResponse::success(
+            "Article liked successfully",
+            status,
+        ))),
+        Err(e) => Err(like_error_response(e)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/content/articles/{id}/like",
+    tag = "content",
+    params(
+        ("id" = Uuid, Path, description = "文章 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "已取消点赞", body = ApiResponseArticleLikeStatus),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 404, description = "文章不存在", body = ApiMessage)
+    )
+)]
+pub async fn unlike_article(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<ArticleLikeStatus>>, (StatusCode, Json<ApiResponse<()>>)> {
+    match content_service::unlike_article(&app_state.pool, auth_user.user_id, id).await {
+        Ok(status) => Ok(Json(ApiResponse::success(
+            "Article unliked successfully",
+            status,
+        ))),
+        Err(e) => Err(like_error_response(e)),
+    }
+}
+
+fn like_error_response(e: anyhow::Error) -> (StatusCode, Json<ApiResponse<()>>) {
+    if e.to_string().contains("Article not found") {
+        (
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::error("Article not found")),
+        )
+    } else {
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to update article like: {}",
+                e
+            ))),
+        )
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/content/articles/{id}",
+    tag = "content",
+    params(
+        ("id" = Uuid, Path, description = "文章 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "文章已删除", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权删除", body = ApiMessage),
+        (status = 404, description = "文章不存在", body = ApiMessage)
+    )
+)]
 pub async fn delete_article(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -277,7 +517,128 @@ pub async fn delete_article(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/articles/{id}/approve",
+    tag = "content",
+    request_body = ReviewContentDto,
+    params(
+        ("id" = Uuid, Path, description = "文章 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "文章已审核通过并发布", body = ApiMessage),
+        (status = 400, description = "文章不在待审核状态", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可审核", body = ApiMessage),
+        (status = 404, description = "文章不存在", body = ApiMessage)
+    )
+)]
+pub async fn approve_article(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<ReviewContentDto>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    review_content(auth_user, app_state, ContentKind::Article, id, dto, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/articles/{id}/reject",
+    tag = "content",
+    request_body = ReviewContentDto,
+    params(
+        ("id" = Uuid, Path, description = "文章 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "文章已驳回并退回草稿", body = ApiMessage),
+        (status = 400, description = "文章不在待审核状态或缺少驳回原因", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可审核", body = ApiMessage),
+        (status = 404, description = "文章不存在", body = ApiMessage)
+    )
+)]
+pub async fn reject_article(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<ReviewContentDto>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    review_content(auth_user, app_state, ContentKind::Article, id, dto, false).await
+}
+
+/// Shared by the approve/reject endpoints for articles and videos.
+async fn review_content(
+    auth_user: AuthUser,
+    app_state: AppState,
+    kind: ContentKind,
+    id: Uuid,
+    dto: ReviewContentDto,
+    approved: bool,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    // Only admin can review content
+    if auth_user.role != "admin" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Insufficient permissions")),
+        ));
+    }
+
+    dto.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&format!("Validation error: {}", e))),
+        )
+    })?;
+
+    let result = if approved {
+        content_service::approve_content(&app_state.pool, kind, id, auth_user.user_id, dto.notes)
+            .await
+    } else {
+        content_service::reject_content(&app_state.pool, kind, id, auth_user.user_id, dto.notes)
+            .await
+    };
+
+    match result {
+        Ok(()) if approved => Ok(Json(ApiResponse::success(
+            "Content approved and published",
+            (),
+        ))),
+        Ok(()) => Ok(Json(ApiResponse::success(
+            "Content rejected and returned to draft",
+            (),
+        ))),
+        Err(e) => {
+            let message = e.to_string();
+            let status = if message.contains("not found") {
+                StatusCode::NOT_FOUND
+            } else if message.contains("not pending review")
+                || message.contains("notes are required")
+            {
+                StatusCode::BAD_REQUEST
+            } else {
+                StatusCode::INTERNAL_SERVER_ERROR
+            };
+            Err((status, Json(ApiResponse::error(&message))))
+        }
+    }
+}
+
 // Video controllers
+#[utoipa::path(
+    get,
+    path = "/api/v1/content/videos",
+    tag = "content",
+    params(
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "视频列表", body = ApiResponseVideoList),
+        (status = 500, description = "查询失败", body = ApiMessage)
+    )
+)]
 pub async fn list_videos(
     State(app_state): State<AppState>,
     Query(query): Query<ListQuery>,
@@ -292,6 +653,7 @@ pub async fn list_videos(
         query.category,
         query.status,
         query.search,
+        query.sort,
     )
     .await
     {
@@ -309,6 +671,18 @@ pub async fn list_videos(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/content/videos/{id}",
+    tag = "content",
+    params(
+        ("id" = Uuid, Path, description = "视频 ID")
+    ),
+    responses(
+        (status = 200, description = "视频详情", body = ApiResponseVideo),
+        (status = 404, description = "视频不存在", body = ApiMessage)
+    )
+)]
 pub async fn get_video(
     State(app_state): State<AppState>,
     Path(id): Path<Uuid>,
@@ -325,6 +699,19 @@ pub async fn get_video(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/videos",
+    tag = "content",
+    request_body = CreateVideoDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "视频创建成功", body = ApiResponseVideo),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅医生或管理员可上传", body = ApiMessage)
+    )
+)]
 pub async fn create_video(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -377,6 +764,23 @@ pub async fn create_video(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/content/videos/{id}",
+    tag = "content",
+    request_body = UpdateVideoDto,
+    params(
+        ("id" = Uuid, Path, description = "视频 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "视频更新成功", body = ApiResponseVideo),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权修改", body = ApiMessage),
+        (status = 404, description = "视频不存在", body = ApiMessage)
+    )
+)]
 pub async fn update_video(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -422,6 +826,22 @@ pub async fn update_video(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/videos/{id}/publish",
+    tag = "content",
+    request_body = PublishVideoDto,
+    params(
+        ("id" = Uuid, Path, description = "视频 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "视频已发布；开启发布审核时进入待审核状态", body = ApiResponseVideo),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权发布", body = ApiMessage),
+        (status = 404, description = "视频不存在", body = ApiMessage)
+    )
+)]
 pub async fn publish_video(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -460,6 +880,21 @@ pub async fn publish_video(
     }
 }
 
+#[utoipa::path(
+    delete,
+    path = "/api/v1/content/videos/{id}",
+    tag = "content",
+    params(
+        ("id" = Uuid, Path, description = "视频 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "视频已删除", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权删除", body = ApiMessage),
+        (status = 404, description = "视频不存在", body = ApiMessage)
+    )
+)]
 pub async fn delete_video(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -488,7 +923,71 @@ pub async fn delete_video(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/videos/{id}/approve",
+    tag = "content",
+    request_body = ReviewContentDto,
+    params(
+        ("id" = Uuid, Path, description = "视频 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "视频已审核通过并发布", body = ApiMessage),
+        (status = 400, description = "视频不在待审核状态", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可审核", body = ApiMessage),
+        (status = 404, description = "视频不存在", body = ApiMessage)
+    )
+)]
+pub async fn approve_video(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<ReviewContentDto>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    review_content(auth_user, app_state, ContentKind::Video, id, dto, true).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/videos/{id}/reject",
+    tag = "content",
+    request_body = ReviewContentDto,
+    params(
+        ("id" = Uuid, Path, description = "视频 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "视频已驳回并退回草稿", body = ApiMessage),
+        (status = 400, description = "视频不在待审核状态或缺少驳回原因", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可审核", body = ApiMessage),
+        (status = 404, description = "视频不存在", body = ApiMessage)
+    )
+)]
+pub async fn reject_video(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<ReviewContentDto>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    review_content(auth_user, app_state, ContentKind::Video, id, dto, false).await
+}
+
 // Category controllers
+#[utoipa::path(
+    get,
+    path = "/api/v1/content/categories",
+    tag = "content",
+    params(
+        CategoryQuery
+    ),
+    responses(
+        (status = 200, description = "分类列表", body = ApiResponseCategoryList),
+        (status = 500, description = "查询失败", body = ApiMessage)
+    )
+)]
 pub async fn list_categories(
     State(app_state): State<AppState>,
     Query(query): Query<CategoryQuery>,
@@ -508,6 +1007,19 @@ pub async fn list_categories(
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/categories",
+    tag = "content",
+    request_body = CreateCategoryDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "分类创建成功", body = ApiResponseCategory),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可创建", body = ApiMessage)
+    )
+)]
 pub async fn create_category(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -551,3 +1063,209 @@ pub async fn create_category(
         }
     }
 }
+
+// Recommendation controllers
+#[utoipa::path(
+    get,
+    path = "/api/v1/content/recommended",
+    tag = "content",
+    params(
+        RecommendedQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "个性化推荐内容", body = ApiResponseRecommendedFeed),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅患者可获取推荐", body = ApiMessage)
+    )
+)]
+pub async fn get_recommended_content(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Query(query): Query<RecommendedQuery>,
+) -> Result<Json<ApiResponse<RecommendedFeed>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role != "patient" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Only patients can view recommendations")),
+        ));
+    }
+
+    let page = query.page.unwrap_or(1).max(1);
+    let per_page = query.per_page.unwrap_or(20).clamp(1, 50);
+    let cache_key =
+        CacheKeys::content_recommendations(&auth_user.user_id.to_string(), page, per_page);
+
+    if let Some(feed) = CacheService::get::<RecommendedFeed>(&app_state.redis, &cache_key).await {
+        return Ok(Json(ApiResponse::success(
+            "Recommendations retrieved successfully",
+            feed,
+        )));
+    }
+
+    match content_service::get_recommended_content(
+        &app_state.pool,
+        auth_user.user_id,
+        page,
+        per_page,
+    )
+    .await
+    {
+        Ok(feed) => {
+            let _ = CacheService::set(
+                &app_state.redis,
+                &cache_key,
+                &feed,
+                std::time::Duration::from_secs(600),
+            )
+            .await;
+
+            Ok(Json(ApiResponse::success(
+                "Recommendations retrieved successfully",
+                feed,
+            )))
+        }
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to retrieve recommendations: {}",
+                e
+            ))),
+        )),
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/content/diagnosis-mappings",
+    tag = "content",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "诊断与内容分类映射列表", body = ApiResponseDiagnosisMappingList),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可访问", body = ApiMessage)
+    )
+)]
+pub async fn list_diagnosis_mappings(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<DiagnosisCategoryMapping>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role != "admin" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Insufficient permissions")),
+        ));
+    }
+
+    match content_service::list_diagnosis_mappings(&app_state.pool).await {
+        Ok(mappings) => Ok(Json(ApiResponse::success(
+            "Diagnosis mappings retrieved successfully",
+            mappings,
+        ))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to retrieve diagnosis mappings: {}",
+                e
+            ))),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/content/diagnosis-mappings",
+    tag = "content",
+    request_body = CreateDiagnosisMappingDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "映射创建成功", body = ApiResponseDiagnosisMapping),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可访问", body = ApiMessage)
+    )
+)]
+pub async fn create_diagnosis_mapping(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Json(dto): Json<CreateDiagnosisMappingDto>,
+) -> Result<Json<ApiResponse<DiagnosisCategoryMapping>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role != "admin" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Insufficient permissions")),
+        ));
+    }
+
+    dto.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&format!("Validation error: {}", e))),
+        )
+    })?;
+
+    match content_service::create_diagnosis_mapping(&app_state.pool, dto).await {
+        Ok(mapping) => Ok(Json(ApiResponse::success(
+            "Diagnosis mapping created successfully",
+            mapping,
+        ))),
+        Err(e) => {
+            if e.to_string().contains("already exists") {
+                Err((
+                    StatusCode::CONFLICT,
+                    Json(ApiResponse::error("Mapping already exists")),
+                ))
+            } else {
+                Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiResponse::error(&format!(
+                        "Failed to create diagnosis mapping: {}",
+                        e
+                    ))),
+                ))
+            }
+        }
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/content/diagnosis-mappings/{id}",
+    tag = "content",
+    params(
+        ("id" = Uuid, Path, description = "映射 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "映射已删除", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可访问", body = ApiMessage),
+        (status = 404, description = "映射不存在", body = ApiMessage)
+    )
+)]
+pub async fn delete_diagnosis_mapping(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role != "admin" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Insufficient permissions")),
+        ));
+    }
+
+    match content_service::delete_diagnosis_mapping(&app_state.pool, id).await {
+        Ok(_) => Ok(Json(ApiResponse::success(
+            "Diagnosis mapping deleted successfully",
+            (),
+        ))),
+        Err(e) => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::error(&format!(
+                "Failed to delete diagnosis mapping: {}",
+                e
+            ))),
+        )),
+    }
+}
@@ -1,7 +1,8 @@
 use crate::{
     middleware::auth::AuthUser,
     models::{doctor::*, ApiResponse},
-    services::doctor_service,
+    services::{doctor_earnings_service::DoctorEarningsService, doctor_service},
+    utils::errors::AppError,
     AppState,
 };
 use axum::{
@@ -9,18 +10,35 @@ use axum::{
     http::StatusCode,
     Extension, Json,
 };
+use chrono::{Datelike, NaiveDate, Utc};
 use serde::Deserialize;
+use utoipa::IntoParams;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct ListQuery {
     page: Option<u32>,
     per_page: Option<u32>,
     department: Option<String>,
     search: Option<String>,
+    /// 排序方式：rating 按加权评分，默认按创建时间
+    sort: Option<String>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/doctors",
+    tag = "doctors",
+    params(
+        ListQuery
+    ),
+    responses(
+        (status = 200, description = "医生列表", body = ApiResponseDoctorList),
+        (status = 500, description = "查询失败", body = ApiMessage)
+    )
+)]
 pub async fn list_doctors(
     State(app_state): State<AppState>,
     Query(query): Query<ListQuery>,
@@ -34,6 +52,7 @@ pub async fn list_doctors(
         per_page,
         query.department,
         query.search,
+        query.sort,
     )
     .await
     {
@@ -51,6 +70,18 @@ pub async fn list_doctors(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/doctors/{id}",
+    tag = "doctors",
+    params(
+        ("id" = Uuid, Path, description = "医生 ID")
+    ),
+    responses(
+        (status = 200, description = "医生详情", body = ApiResponseDoctor),
+        (status = 404, description = "医生不存在", body = ApiMessage)
+    )
+)]
 pub async fn get_doctor(
     State(app_state): State<AppState>,
     Path(id): Path<Uuid>,
@@ -67,6 +98,21 @@ pub async fn get_doctor(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/doctors/by-user/{user_id}",
+    tag = "doctors",
+    params(
+        ("user_id" = Uuid, Path, description = "医生对应的用户 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "医生详情", body = ApiResponseDoctor),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权查看", body = ApiMessage),
+        (status = 404, description = "医生不存在", body = ApiMessage)
+    )
+)]
 pub async fn get_doctor_by_user_id(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -92,6 +138,179 @@ pub async fn get_doctor_by_user_id(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/doctors/treating/{patient_id}",
+    tag = "doctors",
+    params(
+        ("patient_id" = Uuid, Path, description = "患者用户 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "与该患者存在有效预约的医生列表", body = ApiResponseTreatingDoctorList),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权查看", body = ApiMessage)
+    )
+)]
+pub async fn get_treating_doctors(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(patient_id): Path<Uuid>,
+) -> Result<Json<ApiResponse<Vec<TreatingDoctor>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    // Patients can view their own treating doctors, admins can view any
+    if auth_user.user_id != patient_id && auth_user.role != "admin" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Insufficient permissions")),
+        ));
+    }
+
+    match doctor_service::get_treating_doctors(&app_state.pool, patient_id).await {
+        Ok(doctors) => Ok(Json(ApiResponse::success(
+            "Treating doctors retrieved successfully",
+            doctors,
+        ))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to retrieve treating doctors: {}",
+                e
+            ))),
+        )),
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MonthlyReportQuery {
+    /// 年份，默认当年
+    year: Option<i32>,
+    /// 月份 1-12，默认当月
+    month: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EarningsQuery {
+    /// 起始日期（含），如 `2024-01-01`
+    start_date: Option<NaiveDate>,
+    /// 截止日期（含）
+    end_date: Option<NaiveDate>,
+    page: Option<i64>,
+    /// 每页条数，最大 100
+    page_size: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/doctors/me/earnings",
+    tag = "doctors",
+    params(
+        EarningsQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "当前医生的收益报表：待结算/已结算金额、按日汇总与明细", body = ApiResponseDoctorEarningsReport),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅医生可查看", body = ApiMessage),
+        (status = 404, description = "医生资料不存在", body = ApiMessage)
+    )
+)]
+pub async fn get_my_earnings(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Query(query): Query<EarningsQuery>,
+) -> Result<Json<ApiResponse<DoctorEarningsReport>>, AppError> {
+    if auth_user.role != "doctor" {
+        return Err(AppError::Forbidden);
+    }
+
+    let doctor = doctor_service::get_doctor_by_user_id(&app_state.pool, auth_user.user_id)
+        .await
+        .map_err(|_| AppError::NotFound("医生信息不存在".to_string()))?;
+
+    let report = DoctorEarningsService::get_report(
+        &app_state.pool,
+        doctor.id,
+        query.start_date,
+        query.end_date,
+        query.page.unwrap_or(1),
+        query.page_size.unwrap_or(20),
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success("获取收益报表成功", report)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/doctors/me/report",
+    tag = "doctors",
+    params(
+        MonthlyReportQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "当前医生的月度汇总报告", body = ApiResponseDoctorMonthlyReport),
+        (status = 400, description = "月份无效", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅医生可查看", body = ApiMessage),
+        (status = 404, description = "医生资料不存在", body = ApiMessage)
+    )
+)]
+pub async fn get_my_monthly_report(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Query(query): Query<MonthlyReportQuery>,
+) -> Result<Json<ApiResponse<DoctorMonthlyReport>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role != "doctor" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Insufficient permissions")),
+        ));
+    }
+
+    let today = Utc::now();
+    let year = query.year.unwrap_or(today.year());
+    let month = query.month.unwrap_or(today.month());
+
+    match doctor_service::get_monthly_report(&app_state.pool, auth_user.user_id, year, month).await
+    {
+        Ok(report) => Ok(Json(ApiResponse::success(
+            "Monthly report retrieved successfully",
+            report,
+        ))),
+        Err(e) if e.to_string().contains("Invalid report month") => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&e.to_string())),
+        )),
+        Err(e) if e.to_string().contains("Doctor not found") => Err((
+            StatusCode::NOT_FOUND,
+            Json(ApiResponse::error(&e.to_string())),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to build monthly report: {}",
+                e
+            ))),
+        )),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/doctors",
+    tag = "doctors",
+    request_body = CreateDoctorDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "医生资料创建成功", body = ApiResponseDoctor),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可创建", body = ApiMessage)
+    )
+)]
 pub async fn create_doctor(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -127,6 +346,23 @@ pub async fn create_doctor(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/doctors/{id}",
+    tag = "doctors",
+    request_body = UpdateDoctorDto,
+    params(
+        ("id" = Uuid, Path, description = "医生 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "医生资料更新成功", body = ApiResponseDoctor),
+        (status = 400, description = "参数校验失败", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权修改", body = ApiMessage),
+        (status = 404, description = "医生不存在", body = ApiMessage)
+    )
+)]
 pub async fn update_doctor(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -174,6 +410,135 @@ pub async fn update_doctor(
     }
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/doctors/{id}/out-of-office",
+    tag = "doctors",
+    request_body = SetOutOfOfficeDto,
+    params(
+        ("id" = Uuid, Path, description = "医生 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "停诊设置已保存", body = ApiResponseDoctorOutOfOffice),
+        (status = 400, description = "参数校验失败或时间段无效", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权修改", body = ApiMessage),
+        (status = 404, description = "医生不存在", body = ApiMessage)
+    )
+)]
+pub async fn set_out_of_office(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+    Json(dto): Json<SetOutOfOfficeDto>,
+) -> Result<Json<ApiResponse<DoctorOutOfOffice>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let doctor = match doctor_service::get_doctor_by_id(&app_state.pool, id).await {
+        Ok(d) => d,
+        Err(_) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(ApiResponse::error("Doctor not found")),
+            ))
+        }
+    };
+
+    // Doctors manage their own availability, admins can set it for anyone
+    if doctor.user_id != auth_user.user_id && auth_user.role != "admin" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Insufficient permissions")),
+        ));
+    }
+
+    dto.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&format!("Validation error: {}", e))),
+        )
+    })?;
+
+    match doctor_service::set_out_of_office(&app_state.pool, id, dto).await {
+        Ok(ooo) => Ok(Json(ApiResponse::success("Out of office set", ooo))),
+        Err(e) if e.to_string().contains("Invalid out-of-office range") => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&e.to_string())),
+        )),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to set out of office: {}",
+                e
+            ))),
+        )),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/doctors/{id}/out-of-office",
+    tag = "doctors",
+    params(
+        ("id" = Uuid, Path, description = "医生 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "停诊设置已清除", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权修改", body = ApiMessage),
+        (status = 404, description = "医生不存在", body = ApiMessage)
+    )
+)]
+pub async fn clear_out_of_office(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    let doctor = match doctor_service::get_doctor_by_id(&app_state.pool, id).await {
+        Ok(d) => d,
+        Err(_) => {
+            return Err((
+                StatusCode::NOT_FOUND,
+                Json(ApiResponse::error("Doctor not found")),
+            ))
+        }
+    };
+
+    if doctor.user_id != auth_user.user_id && auth_user.role != "admin" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Insufficient permissions")),
+        ));
+    }
+
+    match doctor_service::clear_out_of_office(&app_state.pool, id).await {
+        Ok(()) => Ok(Json(ApiResponse::success("Out of office cleared", ()))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to clear out of office: {}",
+                e
+            ))),
+        )),
+    }
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/doctors/{id}/photos",
+    tag = "doctors",
+    request_body = DoctorPhotos,
+    params(
+        ("id" = Uuid, Path, description = "医生 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "照片更新成功", body = ApiResponseDoctor),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权修改", body = ApiMessage),
+        (status = 404, description = "医生不存在", body = ApiMessage)
+    )
+)]
 pub async fn update_doctor_photos(
     Extension(auth_user): Extension<AuthUser>,
     State(app_state): State<AppState>,
@@ -12,13 +12,20 @@ use axum::{
 };
 use serde_json::json;
 use uuid::Uuid;
+use validator::Validate;
 
 pub async fn create_upload(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Json(dto): Json<CreateFileUploadDto>,
 ) -> Result<impl IntoResponse, AppError> {
-    let response = FileUploadService::create_upload(&state.pool, auth_user.user_id, dto).await?;
+    let response = FileUploadService::create_upload(
+        &state.pool,
+        state.s3_client.as_ref(),
+        auth_user.user_id,
+        dto,
+    )
+    .await?;
 
     Ok((
         StatusCode::CREATED,
@@ -26,6 +33,25 @@ pub async fn create_upload(
     ))
 }
 
+pub async fn refresh_upload_url(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(upload_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let response = FileUploadService::refresh_upload_url(
+        &state.pool,
+        state.s3_client.as_ref(),
+        upload_id,
+        auth_user.user_id,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("获取上传链接成功", response)),
+    ))
+}
+
 pub async fn complete_upload(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -41,6 +67,105 @@ pub async fn complete_upload(
     ))
 }
 
+pub async fn init_multipart_upload(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<CreateFileUploadDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let response = FileUploadService::init_multipart_upload(
+        &state.pool,
+        state.s3_client.as_ref(),
+        auth_user.user_id,
+        dto,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success("创建分片上传成功", response)),
+    ))
+}
+
+pub async fn get_multipart_upload(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(upload_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let response = FileUploadService::get_multipart_upload(
+        &state.pool,
+        state.s3_client.as_ref(),
+        upload_id,
+        auth_user.user_id,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("获取分片上传进度成功", response)),
+    ))
+}
+
+pub async fn record_multipart_part(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((upload_id, part_number)): Path<(Uuid, i32)>,
+    Json(dto): Json<RecordUploadPartDto>,
+) -> Result<impl IntoResponse, AppError> {
+    dto.validate()?;
+
+    let part = FileUploadService::record_multipart_part(
+        &state.pool,
+        upload_id,
+        auth_user.user_id,
+        part_number,
+        dto,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("分片上传已记录", part)),
+    ))
+}
+
+pub async fn complete_multipart_upload(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(upload_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let file = FileUploadService::complete_multipart_upload(
+        &state.pool,
+        state.s3_client.as_ref(),
+        upload_id,
+        auth_user.user_id,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("文件上传完成", file)),
+    ))
+}
+
+pub async fn abort_multipart_upload(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(upload_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    FileUploadService::abort_multipart_upload(
+        &state.pool,
+        state.s3_client.as_ref(),
+        upload_id,
+        auth_user.user_id,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("分片上传已取消", json!({}))),
+    ))
+}
+
 pub async fn get_file(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -84,7 +209,14 @@ pub async fn delete_file(
     Path(file_id): Path<Uuid>,
 ) -> Result<impl IntoResponse, AppError> {
     let is_admin = auth_user.role == "admin";
-    FileUploadService::delete_file(&state.pool, file_id, auth_user.user_id, is_admin).await?;
+    FileUploadService::delete_file(
+        &state.pool,
+        state.s3_client.as_ref(),
+        file_id,
+        auth_user.user_id,
+        is_admin,
+    )
+    .await?;
 
     Ok((
         StatusCode::OK,
@@ -92,6 +224,22 @@ pub async fn delete_file(
     ))
 }
 
+pub async fn delete_files(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<BatchDeleteFilesDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let is_admin = auth_user.role == "admin";
+    let results =
+        FileUploadService::delete_files(&state.pool, &dto.file_ids, auth_user.user_id, is_admin)
+            .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("批量删除完成", results)),
+    ))
+}
+
 pub async fn get_file_stats(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -110,6 +258,24 @@ pub async fn get_file_stats(
     ))
 }
 
+pub async fn get_top_storage_users(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<TopStorageUsersQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let limit = query.limit.unwrap_or(10).clamp(1, 100);
+    let users = FileUploadService::get_top_storage_users(&state.pool, limit).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("获取存储占用排行成功", users)),
+    ))
+}
+
 // Configuration endpoints (admin only)
 pub async fn get_upload_config(
     State(state): State<AppState>,
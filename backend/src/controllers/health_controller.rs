@@ -0,0 +1,21 @@
+use crate::{services::health_service::HealthService, AppState};
+use axum::{extract::State, http::StatusCode, response::IntoResponse, Json};
+use serde_json::json;
+
+/// 存活探针：进程能处理请求即返回 200，不检查任何外部依赖
+pub async fn liveness() -> impl IntoResponse {
+    Json(json!({ "status": "ok" }))
+}
+
+/// 就绪探针：数据库、Redis（如已配置）与数据库迁移全部正常时返回 200，否则返回 503
+pub async fn readiness(State(state): State<AppState>) -> impl IntoResponse {
+    let report = HealthService::check_readiness(&state.pool, state.redis.as_ref()).await;
+
+    let status = if report.ready {
+        StatusCode::OK
+    } else {
+        StatusCode::SERVICE_UNAVAILABLE
+    };
+
+    (status, Json(report))
+}
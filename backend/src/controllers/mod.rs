@@ -1,5 +1,6 @@
 pub mod appointment_controller;
 pub mod auth_controller;
+pub mod broadcast_controller;
 pub mod circle_controller;
 pub mod circle_post_controller;
 pub mod content_controller;
@@ -7,6 +8,7 @@ pub mod department_controller;
 pub mod doctor_controller;
 pub mod file_upload_controller;
 // pub mod file_upload_controller_enhanced;
+pub mod health_controller;
 pub mod live_stream_controller;
 pub mod notification_controller;
 pub mod patient_group_controller;
@@ -15,6 +17,8 @@ pub mod payment_controller;
 pub mod prescription_controller;
 pub mod review_controller;
 pub mod statistics_controller;
+pub mod system_config_controller;
 pub mod template_controller;
 pub mod user_controller;
 pub mod video_consultation_controller;
+pub mod withdrawal_controller;
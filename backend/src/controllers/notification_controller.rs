@@ -17,6 +17,9 @@ use uuid::Uuid;
 #[derive(Debug, Deserialize)]
 pub struct NotificationQuery {
     pub status: Option<String>,
+    #[serde(rename = "type")]
+    pub notification_type: Option<NotificationType>,
+    pub related_id: Option<Uuid>,
     pub page: Option<i64>,
     pub page_size: Option<i64>,
 }
@@ -49,6 +52,8 @@ pub async fn get_user_notifications(
         &state.pool,
         auth_user.user_id,
         status,
+        query.notification_type,
+        query.related_id,
         page,
         page_size,
     )
@@ -152,6 +157,28 @@ pub async fn mark_all_as_read(
     }
 }
 
+/// 清空所有已读通知
+pub async fn clear_read_notifications(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> impl IntoResponse {
+    match NotificationService::clear_read_notifications(&state.pool, auth_user.user_id).await {
+        Ok(count) => Json(ApiResponse::success(
+            "清空已读通知成功",
+            json!({ "count": count }),
+        ))
+        .into_response(),
+        Err(e) => {
+            eprintln!("清空已读通知失败: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("清空已读通知失败")),
+            )
+                .into_response()
+        }
+    }
+}
+
 /// 删除通知
 pub async fn delete_notification(
     State(state): State<AppState>,
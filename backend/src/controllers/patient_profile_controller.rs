@@ -139,11 +139,23 @@ pub async fn create_profile(
                     StatusCode::BAD_REQUEST,
                     Json(ApiResponse::error("Invalid ID number format")),
                 ))
+            } else if e.to_string().contains("Invalid phone")
+                || e.to_string().contains("Invalid birthday")
+            {
+                Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiResponse::error(&e.to_string())),
+                ))
             } else if e.to_string().contains("already registered") {
                 Err((
                     StatusCode::CONFLICT,
                     Json(ApiResponse::error("This ID number is already registered")),
                 ))
+            } else if e.to_string().contains("Profile limit reached") {
+                Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiResponse::error(&e.to_string())),
+                ))
             } else {
                 Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -191,6 +203,13 @@ pub async fn update_profile(
                     StatusCode::NOT_FOUND,
                     Json(ApiResponse::error("Patient profile not found")),
                 ))
+            } else if e.to_string().contains("Invalid phone")
+                || e.to_string().contains("Invalid birthday")
+            {
+                Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiResponse::error(&e.to_string())),
+                ))
             } else {
                 Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -234,6 +253,13 @@ pub async fn delete_profile(
                     StatusCode::BAD_REQUEST,
                     Json(ApiResponse::error("Cannot delete self profile")),
                 ))
+            } else if e.to_string().contains("has appointments") {
+                Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiResponse::error(
+                        "Cannot delete a profile that has appointments",
+                    )),
+                ))
             } else {
                 Err((
                     StatusCode::INTERNAL_SERVER_ERROR,
@@ -285,3 +311,149 @@ pub async fn set_default(
         }
     }
 }
+
+pub async fn list_addresses(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+) -> Result<Json<ApiResponse<Vec<PatientAddress>>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role != "patient" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Only patients can manage addresses")),
+        ));
+    }
+
+    match patient_profile_service::list_addresses(&app_state.pool, auth_user.user_id).await {
+        Ok(addresses) => Ok(Json(ApiResponse::success(
+            "Addresses retrieved successfully",
+            addresses,
+        ))),
+        Err(e) => Err((
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Json(ApiResponse::error(&format!(
+                "Failed to retrieve addresses: {}",
+                e
+            ))),
+        )),
+    }
+}
+
+pub async fn add_address(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Json(dto): Json<CreatePatientAddressDto>,
+) -> Result<Json<ApiResponse<PatientAddress>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role != "patient" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Only patients can manage addresses")),
+        ));
+    }
+
+    dto.validate().map_err(|e| {
+        (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&format!("Validation error: {}", e))),
+        )
+    })?;
+
+    match patient_profile_service::add_address(&app_state.pool, auth_user.user_id, dto).await {
+        Ok(address) => Ok(Json(ApiResponse::success(
+            "Address added successfully",
+            address,
+        ))),
+        Err(e) => {
+            if e.to_string().contains("Invalid phone")
+                || e.to_string().contains("Address limit reached")
+            {
+                Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiResponse::error(&e.to_string())),
+                ))
+            } else {
+                Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiResponse::error(&format!("Failed to add address: {}", e))),
+                ))
+            }
+        }
+    }
+}
+
+pub async fn set_default_address(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role != "patient" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Only patients can manage addresses")),
+        ));
+    }
+
+    match patient_profile_service::set_default_address(&app_state.pool, id, auth_user.user_id).await
+    {
+        Ok(_) => Ok(Json(ApiResponse::success(
+            "Default address set successfully",
+            (),
+        ))),
+        Err(e) => {
+            if e.to_string().contains("not found") {
+                Err((
+                    StatusCode::NOT_FOUND,
+                    Json(ApiResponse::error("Address not found")),
+                ))
+            } else {
+                Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiResponse::error(&format!(
+                        "Failed to set default address: {}",
+                        e
+                    ))),
+                ))
+            }
+        }
+    }
+}
+
+pub async fn delete_address(
+    Extension(auth_user): Extension<AuthUser>,
+    State(app_state): State<AppState>,
+    Path(id): Path<Uuid>,
+) -> Result<Json<ApiResponse<()>>, (StatusCode, Json<ApiResponse<()>>)> {
+    if auth_user.role != "patient" {
+        return Err((
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::error("Only patients can manage addresses")),
+        ));
+    }
+
+    match patient_profile_service::delete_address(&app_state.pool, id, auth_user.user_id).await {
+        Ok(_) => Ok(Json(ApiResponse::success(
+            "Address deleted successfully",
+            (),
+        ))),
+        Err(e) => {
+            if e.to_string().contains("not found") {
+                Err((
+                    StatusCode::NOT_FOUND,
+                    Json(ApiResponse::error("Address not found")),
+                ))
+            } else if e.to_string().contains("Cannot delete") {
+                Err((
+                    StatusCode::BAD_REQUEST,
+                    Json(ApiResponse::error(&e.to_string())),
+                ))
+            } else {
+                Err((
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    Json(ApiResponse::error(&format!(
+                        "Failed to delete address: {}",
+                        e
+                    ))),
+                ))
+            }
+        }
+    }
+}
@@ -1,20 +1,35 @@
 use crate::{
     middleware::auth::AuthUser,
     models::{payment::*, ApiResponse},
-    services::payment_service::PaymentService,
+    services::{payment_metrics_service::PaymentMetrics, payment_service::PaymentService},
     utils::errors::AppError,
     AppState,
 };
 use axum::{
     extract::{Path, Query, State},
-    http::StatusCode,
+    http::{HeaderMap, StatusCode},
     response::IntoResponse,
     Extension, Json,
 };
 use serde::Deserialize;
+use std::collections::HashMap;
+use utoipa::IntoParams;
 use uuid::Uuid;
+use validator::Validate;
 
 // Order endpoints
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/orders",
+    tag = "payment",
+    request_body = CreateOrderDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 201, description = "订单创建成功", body = ApiResponseOrder),
+        (status = 400, description = "请求参数错误", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage)
+    )
+)]
 pub async fn create_order(
     State(state): State<AppState>,
     Extension(_auth_user): Extension<AuthUser>,
@@ -28,6 +43,46 @@ pub async fn create_order(
     ))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/orders/quote",
+    tag = "payment",
+    request_body = CreateOrderDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "订单价格预览", body = ApiResponseOrderQuote),
+        (status = 400, description = "订单金额不合法", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage)
+    )
+)]
+pub async fn quote_order(
+    State(state): State<AppState>,
+    Extension(_auth_user): Extension<AuthUser>,
+    Json(dto): Json<CreateOrderDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let quote = PaymentService::quote_order(&state.pool, &dto).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("获取订单报价成功", quote)),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/orders/{id}",
+    tag = "payment",
+    params(
+        ("id" = Uuid, Path, description = "订单 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "订单详情", body = ApiResponseOrder),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权访问", body = ApiMessage),
+        (status = 404, description = "订单不存在", body = ApiMessage)
+    )
+)]
 pub async fn get_order(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -43,6 +98,51 @@ pub async fn get_order(
     Ok(Json(ApiResponse::success("获取订单成功", order)))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/orders/{order_no}/status",
+    tag = "payment",
+    params(
+        ("order_no" = String, Path, description = "订单号")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "订单支付状态，待支付的渠道订单会先向支付渠道核实", body = ApiResponseOrderPaymentStatus),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权查看该订单", body = ApiMessage),
+        (status = 404, description = "订单不存在", body = ApiMessage)
+    )
+)]
+pub async fn get_order_status(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(order_no): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let order = PaymentService::get_order_by_no(&state.pool, &order_no).await?;
+    if order.user_id != auth_user.user_id && auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let status =
+        PaymentService::query_order_status(&state.pool, state.payment_gateway.as_ref(), &order_no)
+            .await?;
+
+    Ok(Json(ApiResponse::success("获取支付状态成功", status)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/orders",
+    tag = "payment",
+    params(
+        OrderListQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "订单列表，非管理员仅返回本人订单", body = ApiResponseOrderList),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage)
+    )
+)]
 pub async fn list_orders(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -59,6 +159,22 @@ pub async fn list_orders(
     Ok(Json(ApiResponse::success("获取订单列表成功", response)))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/payment/orders/{id}/cancel",
+    tag = "payment",
+    params(
+        ("id" = Uuid, Path, description = "订单 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "订单已取消", body = ApiResponseOrder),
+        (status = 400, description = "当前状态不可取消", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权访问", body = ApiMessage),
+        (status = 404, description = "订单不存在", body = ApiMessage)
+    )
+)]
 pub async fn cancel_order(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -77,6 +193,20 @@ pub async fn cancel_order(
 }
 
 // Payment endpoints
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/pay",
+    tag = "payment",
+    request_body = InitiatePaymentDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "返回支付参数", body = ApiResponsePayment),
+        (status = 400, description = "请求参数错误", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权访问", body = ApiMessage),
+        (status = 404, description = "订单不存在", body = ApiMessage)
+    )
+)]
 pub async fn initiate_payment(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -89,20 +219,37 @@ pub async fn initiate_payment(
         return Err(AppError::Forbidden);
     }
 
-    let response = PaymentService::initiate_payment(&state.pool, dto).await?;
+    let response =
+        PaymentService::initiate_payment(&state.pool, state.payment_gateway.as_ref(), dto).await?;
 
     Ok(Json(ApiResponse::success("支付发起成功", response)))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PaymentCallbackQuery {
     pub method: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/payment/callback",
+    tag = "payment",
+    request_body = Object,
+    params(
+        PaymentCallbackQuery
+    ),
+    responses(
+        (status = 200, description = "回调处理成功，按支付渠道要求的格式应答", body = Object),
+        (status = 400, description = "请求参数错误或回调签名校验失败", body = ApiMessage),
+        (status = 404, description = "订单不存在", body = ApiMessage)
+    )
+)]
 pub async fn payment_callback(
     State(state): State<AppState>,
     Query(query): Query<PaymentCallbackQuery>,
-    Json(data): Json<serde_json::Value>,
+    headers: HeaderMap,
+    body: String,
 ) -> Result<impl IntoResponse, AppError> {
     // Parse payment method
     let payment_method = match query.method.as_str() {
@@ -111,62 +258,12 @@ pub async fn payment_callback(
         _ => return Err(AppError::BadRequest("无效的支付方式".to_string())),
     };
 
-    // TODO: Validate callback signature based on payment method
-
-    // Parse callback data based on payment method
-    let callback_data = match &payment_method {
-        PaymentMethod::Wechat => {
-            // Parse WeChat callback
-            PaymentCallbackData {
-                order_no: data["out_trade_no"]
-                    .as_str()
-                    .ok_or_else(|| AppError::BadRequest("缺少订单号".to_string()))?
-                    .to_string(),
-                external_transaction_id: data["transaction_id"]
-                    .as_str()
-                    .ok_or_else(|| AppError::BadRequest("缺少交易ID".to_string()))?
-                    .to_string(),
-                amount: rust_decimal::Decimal::from_str_exact(
-                    &(data["amount"]["total"].as_i64().unwrap_or(0) as f64 / 100.0).to_string(),
-                )
-                .map_err(|_| AppError::BadRequest("金额格式错误".to_string()))?,
-                status: if data["trade_state"].as_str() == Some("SUCCESS") {
-                    "success".to_string()
-                } else {
-                    "failed".to_string()
-                },
-                payment_time: chrono::Utc::now(), // TODO: Parse from callback
-                raw_data: data.clone(),
-            }
-        }
-        PaymentMethod::Alipay => {
-            // Parse Alipay callback
-            PaymentCallbackData {
-                order_no: data["out_trade_no"]
-                    .as_str()
-                    .ok_or_else(|| AppError::BadRequest("缺少订单号".to_string()))?
-                    .to_string(),
-                external_transaction_id: data["trade_no"]
-                    .as_str()
-                    .ok_or_else(|| AppError::BadRequest("缺少交易ID".to_string()))?
-                    .to_string(),
-                amount: rust_decimal::Decimal::from_str_exact(
-                    data["total_amount"]
-                        .as_str()
-                        .ok_or_else(|| AppError::BadRequest("缺少金额".to_string()))?,
-                )
-                .map_err(|_| AppError::BadRequest("金额格式错误".to_string()))?,
-                status: if data["trade_status"].as_str() == Some("TRADE_SUCCESS") {
-                    "success".to_string()
-                } else {
-                    "failed".to_string()
-                },
-                payment_time: chrono::Utc::now(), // TODO: Parse from callback
-                raw_data: data.clone(),
-            }
-        }
-        _ => return Err(AppError::BadRequest("不支持的支付方式".to_string())),
-    };
+    // The signature covers the raw body, so verify before touching any state
+    let headers = callback_headers(&headers);
+    let data =
+        PaymentService::verify_callback(&state.pool, &payment_method, &headers, &body).await?;
+
+    let callback_data = PaymentService::parse_callback(&payment_method, &data)?;
 
     PaymentService::handle_payment_callback(&state.pool, payment_method.clone(), callback_data)
         .await?;
@@ -182,7 +279,72 @@ pub async fn payment_callback(
     }
 }
 
+/// 请求头转为小写键名，供验签使用
+fn callback_headers(headers: &HeaderMap) -> HashMap<String, String> {
+    headers
+        .iter()
+        .filter_map(|(name, value)| {
+            value
+                .to_str()
+                .ok()
+                .map(|value| (name.as_str().to_ascii_lowercase(), value.to_string()))
+        })
+        .collect()
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/refund/callback",
+    tag = "payment",
+    request_body = Object,
+    params(
+        PaymentCallbackQuery
+    ),
+    responses(
+        (status = 200, description = "退款结果通知处理成功", body = Object),
+        (status = 400, description = "请求参数错误或回调签名校验失败", body = ApiMessage),
+        (status = 404, description = "退款记录不存在", body = ApiMessage)
+    )
+)]
+pub async fn refund_callback(
+    State(state): State<AppState>,
+    Query(query): Query<PaymentCallbackQuery>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<impl IntoResponse, AppError> {
+    // Only WeChat notifies refund results; Alipay refunds complete synchronously
+    if query.method != "wechat" {
+        return Err(AppError::BadRequest("无效的支付方式".to_string()));
+    }
+    let payment_method = PaymentMethod::Wechat;
+
+    let headers = callback_headers(&headers);
+    let data =
+        PaymentService::verify_callback(&state.pool, &payment_method, &headers, &body).await?;
+
+    PaymentService::handle_refund_callback(&state.pool, &payment_method, &data).await?;
+
+    Ok(Json(serde_json::json!({
+        "code": "SUCCESS",
+        "message": "成功"
+    })))
+}
+
 // Refund endpoints
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/refunds",
+    tag = "payment",
+    request_body = CreateRefundDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 201, description = "退款申请已提交", body = ApiResponseRefund),
+        (status = 400, description = "请求参数错误", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权访问", body = ApiMessage),
+        (status = 404, description = "订单不存在", body = ApiMessage)
+    )
+)]
 pub async fn create_refund(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -195,7 +357,13 @@ pub async fn create_refund(
         return Err(AppError::Forbidden);
     }
 
-    let refund = PaymentService::create_refund(&state.pool, dto, auth_user.user_id).await?;
+    let refund = PaymentService::create_refund(
+        &state.pool,
+        dto,
+        auth_user.user_id,
+        auth_user.role == "admin",
+    )
+    .await?;
 
     Ok((
         StatusCode::CREATED,
@@ -203,6 +371,21 @@ pub async fn create_refund(
     ))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/refunds/{id}",
+    tag = "payment",
+    params(
+        ("id" = Uuid, Path, description = "退款 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "退款详情", body = ApiResponseRefund),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权访问", body = ApiMessage),
+        (status = 404, description = "退款记录不存在", body = ApiMessage)
+    )
+)]
 pub async fn get_refund(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -218,6 +401,23 @@ pub async fn get_refund(
     Ok(Json(ApiResponse::success("获取退款记录成功", refund)))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/payment/admin/refunds/{id}/review",
+    tag = "payment",
+    request_body = ReviewRefundDto,
+    params(
+        ("id" = Uuid, Path, description = "退款 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "退款审核完成", body = ApiResponseRefund),
+        (status = 400, description = "请求参数错误", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可审核", body = ApiMessage),
+        (status = 404, description = "退款记录不存在", body = ApiMessage)
+    )
+)]
 pub async fn review_refund(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -229,12 +429,179 @@ pub async fn review_refund(
         return Err(AppError::Forbidden);
     }
 
-    PaymentService::review_refund(&state.pool, refund_id, dto, auth_user.user_id).await?;
+    PaymentService::review_refund(
+        &state.pool,
+        state.payment_gateway.as_ref(),
+        refund_id,
+        dto,
+        auth_user.user_id,
+    )
+    .await?;
 
     Ok(Json(ApiResponse::success("退款审核完成", ())))
 }
 
+#[utoipa::path(
+    put,
+    path = "/api/v1/payment/admin/refunds/{id}/retry",
+    tag = "payment",
+    params(
+        ("id" = Uuid, Path, description = "退款 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "已重新向支付渠道发起退款", body = ApiResponseRefund),
+        (status = 400, description = "仅失败的退款可以重试", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可重试", body = ApiMessage),
+        (status = 404, description = "退款记录不存在", body = ApiMessage)
+    )
+)]
+pub async fn retry_refund(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(refund_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can retry refunds
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let refund = PaymentService::retry_refund(
+        &state.pool,
+        state.payment_gateway.as_ref(),
+        refund_id,
+        auth_user.user_id,
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success("退款重试成功", refund)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/payment/admin/orders/{id}/status",
+    tag = "payment",
+    request_body = AdjustOrderStatusDto,
+    params(
+        ("id" = Uuid, Path, description = "订单 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "订单状态已调整", body = ApiResponseOrder),
+        (status = 400, description = "不允许的状态流转或缺少原因", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可调整", body = ApiMessage),
+        (status = 404, description = "订单不存在", body = ApiMessage)
+    )
+)]
+pub async fn adjust_order_status(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(order_id): Path<Uuid>,
+    Json(dto): Json<AdjustOrderStatusDto>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can force order status changes
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    dto.validate()?;
+
+    let order = PaymentService::adjust_order_status(
+        &state.pool,
+        order_id,
+        dto.status,
+        &dto.reason,
+        auth_user.user_id,
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success("订单状态已调整", order)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/admin/orders/{id}/verify-callback",
+    tag = "payment",
+    request_body = VerifyCallbackDto,
+    params(
+        ("id" = Uuid, Path, description = "订单 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "回调校验结果", body = ApiResponseCallbackVerification),
+        (status = 400, description = "不允许的状态流转", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可校验", body = ApiMessage),
+        (status = 404, description = "订单不存在或没有已保存的回调", body = ApiMessage)
+    )
+)]
+pub async fn verify_order_callback(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(order_id): Path<Uuid>,
+    Json(dto): Json<VerifyCallbackDto>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let result =
+        PaymentService::verify_stored_callback(&state.pool, order_id, dto.apply, auth_user.user_id)
+            .await?;
+
+    let message = if result.applied {
+        "回调已重新应用，订单已标记为已支付"
+    } else {
+        "回调校验完成"
+    };
+    Ok(Json(ApiResponse::success(message, result)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/transactions",
+    tag = "payment",
+    params(
+        TransactionListQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "支付/退款交易记录", body = ApiResponseTransactionList),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可查看", body = ApiMessage)
+    )
+)]
+pub async fn list_transactions(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<TransactionListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let response = PaymentService::list_transactions(&state.pool, query).await?;
+
+    Ok(Json(ApiResponse::success("获取交易记录成功", response)))
+}
+
 // Balance endpoints
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/balance/{user_id}",
+    tag = "payment",
+    params(
+        ("user_id" = Uuid, Path, description = "用户 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "用户余额", body = ApiResponseBalance),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权访问", body = ApiMessage)
+    )
+)]
 pub async fn get_user_balance(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -253,12 +620,58 @@ pub async fn get_user_balance(
     Ok(Json(ApiResponse::success("获取余额成功", balance)))
 }
 
-#[derive(Deserialize)]
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/balance/recharge",
+    tag = "payment",
+    request_body = RechargeBalanceDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "充值订单已创建，返回支付参数", body = ApiResponsePayment),
+        (status = 400, description = "充值金额超出限制或支付方式不支持", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage)
+    )
+)]
+pub async fn recharge_balance(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<RechargeBalanceDto>,
+) -> Result<impl IntoResponse, AppError> {
+    dto.validate()?;
+
+    let response = PaymentService::recharge_balance(
+        &state.pool,
+        state.payment_gateway.as_ref(),
+        auth_user.user_id,
+        dto,
+    )
+    .await?;
+
+    Ok(Json(ApiResponse::success("充值订单创建成功", response)))
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct BalanceTransactionsQuery {
     pub page: Option<i64>,
     pub page_size: Option<i64>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/balance/{user_id}/transactions",
+    tag = "payment",
+    params(
+        ("user_id" = Uuid, Path, description = "用户 ID"),
+        BalanceTransactionsQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "余额变动记录", body = ApiResponseBalanceTransactions),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "无权访问", body = ApiMessage)
+    )
+)]
 pub async fn get_balance_transactions(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -282,7 +695,113 @@ pub async fn get_balance_transactions(
     )))
 }
 
+// Balance withdrawal endpoints
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/withdrawals/balance",
+    tag = "payment",
+    request_body = CreateBalanceWithdrawalDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 201, description = "提现申请已提交，金额已冻结", body = ApiResponseBalanceWithdrawal),
+        (status = 400, description = "可用余额不足或参数错误", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage)
+    )
+)]
+pub async fn create_balance_withdrawal(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<CreateBalanceWithdrawalDto>,
+) -> Result<impl IntoResponse, AppError> {
+    dto.validate()?;
+
+    let withdrawal = PaymentService::create_withdrawal(&state.pool, auth_user.user_id, dto).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success("提现申请已提交", withdrawal)),
+    ))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/withdrawals/balance",
+    tag = "payment",
+    params(
+        BalanceWithdrawalQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "提现申请列表，非管理员仅返回本人申请", body = ApiResponseBalanceWithdrawalList),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage)
+    )
+)]
+pub async fn list_balance_withdrawals(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<BalanceWithdrawalQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    // Filter by user unless admin
+    let mut filtered_query = query;
+    if auth_user.role != "admin" {
+        filtered_query.user_id = Some(auth_user.user_id);
+    }
+
+    let response = PaymentService::list_withdrawals(&state.pool, filtered_query).await?;
+
+    Ok(Json(ApiResponse::success("获取提现申请列表成功", response)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/payment/withdrawals/balance/{id}/review",
+    tag = "payment",
+    params(
+        ("id" = Uuid, Path, description = "提现申请 ID")
+    ),
+    request_body = ReviewBalanceWithdrawalDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "审核完成", body = ApiResponseBalanceWithdrawal),
+        (status = 400, description = "提现申请已处理", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可操作", body = ApiMessage),
+        (status = 404, description = "提现申请不存在", body = ApiMessage)
+    )
+)]
+pub async fn review_balance_withdrawal(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(withdrawal_id): Path<Uuid>,
+    Json(dto): Json<ReviewBalanceWithdrawalDto>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can review withdrawals
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    dto.validate()?;
+
+    let withdrawal =
+        PaymentService::review_withdrawal(&state.pool, withdrawal_id, dto, auth_user.user_id)
+            .await?;
+
+    Ok(Json(ApiResponse::success("提现审核完成", withdrawal)))
+}
+
 // Price configuration endpoints
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/prices/{service_type}",
+    tag = "payment",
+    params(
+        ("service_type" = String, Path, description = "服务类型")
+    ),
+    responses(
+        (status = 200, description = "价格配置", body = ApiResponsePriceConfig),
+        (status = 404, description = "价格配置不存在", body = ApiMessage)
+    )
+)]
 pub async fn get_price_config(
     State(state): State<AppState>,
     Path(service_type): Path<String>,
@@ -295,6 +814,17 @@ pub async fn get_price_config(
     }
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/prices",
+    tag = "payment",
+    params(
+        ("is_active" = Option<bool>, Query, description = "按启用状态过滤")
+    ),
+    responses(
+        (status = 200, description = "价格配置列表", body = ApiResponsePriceConfigList)
+    )
+)]
 pub async fn list_price_configs(
     State(state): State<AppState>,
     Query(query): Query<std::collections::HashMap<String, String>>,
@@ -306,14 +836,273 @@ pub async fn list_price_configs(
     Ok(Json(ApiResponse::success("获取价格配置列表成功", configs)))
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/price-configs",
+    tag = "payment",
+    request_body = CreatePriceConfigDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "价格配置已创建", body = ApiResponsePriceConfig),
+        (status = 400, description = "日期无效或与已有配置重叠", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可操作", body = ApiMessage)
+    )
+)]
+pub async fn create_price_config(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<CreatePriceConfigDto>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can manage prices
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    dto.validate()?;
+
+    let config = PaymentService::create_price_config(&state.pool, dto, auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success("价格配置创建成功", config)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/payment/price-configs/{id}",
+    tag = "payment",
+    params(
+        ("id" = Uuid, Path, description = "价格配置 ID")
+    ),
+    request_body = UpdatePriceConfigDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "价格配置已更新", body = ApiResponsePriceConfig),
+        (status = 400, description = "日期无效或与已有配置重叠", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可操作", body = ApiMessage),
+        (status = 404, description = "价格配置不存在", body = ApiMessage)
+    )
+)]
+pub async fn update_price_config(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(config_id): Path<Uuid>,
+    Json(dto): Json<UpdatePriceConfigDto>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can manage prices
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    dto.validate()?;
+
+    let config =
+        PaymentService::update_price_config(&state.pool, config_id, dto, auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success("价格配置更新成功", config)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/payment/price-configs/{id}",
+    tag = "payment",
+    params(
+        ("id" = Uuid, Path, description = "价格配置 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "价格配置已停用", body = ApiResponsePriceConfig),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可操作", body = ApiMessage),
+        (status = 404, description = "价格配置不存在", body = ApiMessage)
+    )
+)]
+pub async fn deactivate_price_config(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(config_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can manage prices
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let config =
+        PaymentService::deactivate_price_config(&state.pool, config_id, auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success("价格配置已停用", config)))
+}
+
+// Coupon endpoints
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/coupons/validate",
+    tag = "payment",
+    request_body = ValidateCouponDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "优惠券可用，返回抵扣后的金额", body = ApiResponseCouponPreview),
+        (status = 400, description = "优惠券不存在、已过期、已用完或未达使用门槛", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage)
+    )
+)]
+pub async fn validate_coupon(
+    State(state): State<AppState>,
+    Json(dto): Json<ValidateCouponDto>,
+) -> Result<impl IntoResponse, AppError> {
+    dto.validate()?;
+
+    let preview = PaymentService::validate_coupon(&state.pool, &dto).await?;
+
+    Ok(Json(ApiResponse::success("优惠券可用", preview)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/coupons",
+    tag = "payment",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "优惠券列表", body = ApiResponseCouponList),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可操作", body = ApiMessage)
+    )
+)]
+pub async fn list_coupons(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can manage coupons
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let coupons = PaymentService::list_coupons(&state.pool).await?;
+
+    Ok(Json(ApiResponse::success("获取优惠券列表成功", coupons)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/coupons",
+    tag = "payment",
+    request_body = CreateCouponDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "优惠券已创建", body = ApiResponseCoupon),
+        (status = 400, description = "优惠规则无效或优惠码已存在", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可操作", body = ApiMessage)
+    )
+)]
+pub async fn create_coupon(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<CreateCouponDto>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can manage coupons
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    dto.validate()?;
+
+    let coupon = PaymentService::create_coupon(&state.pool, dto, auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success("优惠券创建成功", coupon)))
+}
+
+#[utoipa::path(
+    put,
+    path = "/api/v1/payment/coupons/{id}",
+    tag = "payment",
+    params(
+        ("id" = Uuid, Path, description = "优惠券 ID")
+    ),
+    request_body = UpdateCouponDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "优惠券已更新", body = ApiResponseCoupon),
+        (status = 400, description = "优惠规则无效", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可操作", body = ApiMessage),
+        (status = 404, description = "优惠券不存在", body = ApiMessage)
+    )
+)]
+pub async fn update_coupon(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(coupon_id): Path<Uuid>,
+    Json(dto): Json<UpdateCouponDto>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can manage coupons
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    dto.validate()?;
+
+    let coupon =
+        PaymentService::update_coupon(&state.pool, coupon_id, dto, auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success("优惠券更新成功", coupon)))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/payment/coupons/{id}",
+    tag = "payment",
+    params(
+        ("id" = Uuid, Path, description = "优惠券 ID")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "优惠券已停用", body = ApiResponseCoupon),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可操作", body = ApiMessage),
+        (status = 404, description = "优惠券不存在", body = ApiMessage)
+    )
+)]
+pub async fn deactivate_coupon(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(coupon_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can manage coupons
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let coupon =
+        PaymentService::deactivate_coupon(&state.pool, coupon_id, auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success("优惠券已停用", coupon)))
+}
+
 // Statistics endpoints
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PaymentStatisticsQuery {
     pub user_id: Option<Uuid>,
     pub start_date: Option<chrono::DateTime<chrono::Utc>>,
     pub end_date: Option<chrono::DateTime<chrono::Utc>>,
+    /// 按 day/week/month 拆分统计，返回 buckets
+    pub group_by: Option<StatisticsGroupBy>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/statistics",
+    tag = "payment",
+    params(
+        PaymentStatisticsQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "支付统计，非管理员仅统计本人", body = ApiResponsePaymentStatistics),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage)
+    )
+)]
 pub async fn get_payment_statistics(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -326,7 +1115,7 @@ pub async fn get_payment_statistics(
         Some(auth_user.user_id)
     };
 
-    let statistics = PaymentService::get_payment_statistics(
+    let mut statistics = PaymentService::get_payment_statistics(
         &state.pool,
         user_id,
         query.start_date,
@@ -334,17 +1123,58 @@ pub async fn get_payment_statistics(
     )
     .await?;
 
+    if let Some(group_by) = query.group_by {
+        statistics.buckets = Some(
+            PaymentService::get_payment_statistics_buckets(
+                &state.pool,
+                user_id,
+                query.start_date,
+                query.end_date,
+                group_by,
+            )
+            .await?,
+        );
+    }
+
     Ok(Json(ApiResponse::success("获取支付统计成功", statistics)))
 }
 
-// Admin endpoints
-#[derive(Deserialize)]
-pub struct UpdatePaymentConfigDto {
-    pub config_key: String,
-    pub config_value: String,
-    pub is_encrypted: bool,
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/summary",
+    tag = "payment",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "本人支付概览", body = ApiResponseUserPaymentSummary),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage)
+    )
+)]
+pub async fn get_user_payment_summary(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, AppError> {
+    let summary = PaymentService::get_user_payment_summary(&state.pool, auth_user.user_id).await?;
+
+    Ok(Json(ApiResponse::success("获取支付概览成功", summary)))
 }
 
+// Admin endpoints
+#[utoipa::path(
+    put,
+    path = "/api/v1/payment/admin/config/{payment_method}",
+    tag = "payment",
+    request_body = UpdatePaymentConfigDto,
+    params(
+        ("payment_method" = String, Path, description = "支付方式：wechat 或 alipay")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "支付配置更新成功", body = ApiMessage),
+        (status = 400, description = "无效的支付方式", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可修改", body = ApiMessage)
+    )
+)]
 pub async fn update_payment_config(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -368,8 +1198,195 @@ pub async fn update_payment_config(
         &dto.config_key,
         &dto.config_value,
         dto.is_encrypted,
+        auth_user.user_id,
     )
     .await?;
 
     Ok(Json(ApiResponse::success("支付配置更新成功", ())))
 }
+
+#[utoipa::path(
+    delete,
+    path = "/api/v1/payment/admin/config/{payment_method}/{config_key}",
+    tag = "payment",
+    params(
+        ("payment_method" = String, Path, description = "支付方式：wechat 或 alipay"),
+        ("config_key" = String, Path, description = "配置项")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "支付配置删除成功", body = ApiMessage),
+        (status = 400, description = "无效的支付方式", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可删除", body = ApiMessage),
+        (status = 404, description = "配置项不存在", body = ApiMessage)
+    )
+)]
+pub async fn delete_payment_config(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((payment_method, config_key)): Path<(String, String)>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only admin can delete payment config
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let method = match payment_method.as_str() {
+        "wechat" => PaymentMethod::Wechat,
+        "alipay" => PaymentMethod::Alipay,
+        _ => return Err(AppError::BadRequest("无效的支付方式".to_string())),
+    };
+
+    PaymentService::delete_payment_config(&state.pool, method, &config_key, auth_user.user_id)
+        .await?;
+
+    Ok(Json(ApiResponse::success("支付配置删除成功", ())))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/admin/config-history",
+    tag = "payment",
+    params(
+        PaymentConfigHistoryQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "支付配置变更历史，按变更时间倒序", body = ApiResponsePaymentConfigHistory),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可查看", body = ApiMessage)
+    )
+)]
+pub async fn get_payment_config_history(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<PaymentConfigHistoryQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let history = PaymentService::get_payment_config_history(&state.pool, query).await?;
+
+    Ok(Json(ApiResponse::success(
+        "获取支付配置变更历史成功",
+        history,
+    )))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/admin/orders/by-external/{external_id}",
+    tag = "payment",
+    params(
+        ("external_id" = String, Path, description = "第三方支付平台（微信/支付宝）交易号")
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "订单及其全部支付/退款交易记录", body = ApiResponseOrderWithTransactions),
+        (status = 400, description = "交易号不能为空", body = ApiMessage),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可查询", body = ApiMessage),
+        (status = 404, description = "未找到该交易号对应的订单", body = ApiMessage)
+    )
+)]
+pub async fn get_order_by_external_transaction(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(external_id): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let external_id = external_id.trim();
+    if external_id.is_empty() {
+        return Err(AppError::BadRequest("交易号不能为空".to_string()));
+    }
+
+    let order = PaymentService::get_order_by_external_transaction(&state.pool, external_id).await?;
+
+    Ok(Json(ApiResponse::success("获取订单成功", order)))
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/admin/metrics",
+    tag = "payment",
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "本进程的支付成功/失败计数与窗口失败率", body = ApiResponsePaymentMetrics),
+        (status = 401, description = "未登录或令牌无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可查看", body = ApiMessage)
+    )
+)]
+pub async fn get_payment_metrics(
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    Ok(Json(ApiResponse::success(
+        "获取支付指标成功",
+        PaymentMetrics::global().snapshot(),
+    )))
+}
+
+// Reconciliation endpoints
+#[utoipa::path(
+    get,
+    path = "/api/v1/payment/reconciliation",
+    tag = "payment",
+    params(
+        ReconciliationQuery
+    ),
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "对账报告", body = ApiResponseReconciliationReport),
+        (status = 403, description = "仅管理员可查看", body = ApiMessage),
+        (status = 404, description = "该日期暂无对账报告", body = ApiMessage)
+    )
+)]
+pub async fn get_reconciliation_report(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<ReconciliationQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let report = PaymentService::get_reconciliation_report(&state.pool, query.date).await?;
+
+    Ok(Json(ApiResponse::success("获取对账报告成功", report)))
+}
+
+#[utoipa::path(
+    post,
+    path = "/api/v1/payment/reconciliation/run",
+    tag = "payment",
+    request_body = RunReconciliationDto,
+    security(("bearer_auth" = [])),
+    responses(
+        (status = 200, description = "对账完成，账单获取失败时报告状态为 failed", body = ApiResponseReconciliationReport),
+        (status = 400, description = "账单日期无效", body = ApiMessage),
+        (status = 403, description = "仅管理员可触发", body = ApiMessage)
+    )
+)]
+pub async fn run_reconciliation(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<RunReconciliationDto>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let report =
+        PaymentService::run_reconciliation(&state.pool, state.payment_gateway.as_ref(), dto)
+            .await?;
+
+    Ok(Json(ApiResponse::success("对账完成", report)))
+}
@@ -1,7 +1,7 @@
 use crate::{
     middleware::auth::AuthUser,
     models::{prescription::*, ApiResponse},
-    services::prescription_service,
+    services::{doctor_service, prescription_service},
     AppState,
 };
 use axum::{
@@ -175,6 +175,10 @@ pub async fn create_prescription(
             "Prescription created successfully",
             prescription,
         ))),
+        Err(e) if e.to_string().contains("Patient profile not found") => Err((
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::error(&e.to_string())),
+        )),
         Err(e) => Err((
             StatusCode::INTERNAL_SERVER_ERROR,
             Json(ApiResponse::error(&format!(
@@ -228,12 +232,27 @@ pub async fn get_patient_prescriptions(
     Path(patient_id): Path<Uuid>,
     Query(query): Query<ListQuery>,
 ) -> Result<Json<ApiResponse<Vec<Prescription>>>, (StatusCode, Json<ApiResponse<()>>)> {
-    // Users can view their own prescriptions, admins can view any
+    // Users can view their own prescriptions, admins can view any, and doctors can
+    // view patients they have a non-cancelled appointment with
     if auth_user.user_id != patient_id && auth_user.role != "admin" {
-        return Err((
-            StatusCode::FORBIDDEN,
-            Json(ApiResponse::error("Insufficient permissions")),
-        ));
+        let is_treating = auth_user.role == "doctor"
+            && doctor_service::is_treating_doctor(&app_state.pool, patient_id, auth_user.user_id)
+                .await
+                .map_err(|e| {
+                    (
+                        StatusCode::INTERNAL_SERVER_ERROR,
+                        Json(ApiResponse::error(&format!(
+                            "Failed to check treating doctor: {}",
+                            e
+                        ))),
+                    )
+                })?;
+        if !is_treating {
+            return Err((
+                StatusCode::FORBIDDEN,
+                Json(ApiResponse::error("Insufficient permissions")),
+            ));
+        }
     }
 
     let page = query.page.unwrap_or(1);
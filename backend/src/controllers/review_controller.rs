@@ -1,14 +1,14 @@
 use crate::middleware::auth::AuthUser;
 use crate::models::{
-    ApiResponse, CreateReviewDto, CreateTagDto, ReplyReviewDto, ReviewQuery, UpdateReviewDto,
-    UpdateReviewVisibilityDto,
+    ApiResponse, CreateReviewDto, CreateTagDto, ReplyReviewDto, ReviewQuery, UnrepliedReviewQuery,
+    UpdateReviewDto, UpdateReviewVisibilityDto,
 };
 use crate::services::review_service::{ReviewQueryParams, ReviewService};
 use crate::AppState;
 use axum::{
     extract::{Extension, Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use uuid::Uuid;
@@ -160,6 +160,40 @@ pub async fn update_review(
     }
 }
 
+pub async fn delete_review(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(id): Path<Uuid>,
+) -> impl IntoResponse {
+    // 患者可删除自己的评价，管理员可删除任意评价
+    let result = match auth_user.role.as_str() {
+        "patient" => ReviewService::delete_review(&state.pool, id, auth_user.user_id).await,
+        "admin" => ReviewService::admin_delete_review(&state.pool, id).await,
+        _ => {
+            return (
+                StatusCode::FORBIDDEN,
+                Json(ApiResponse::<serde_json::Value>::error(
+                    "Only patients and admins can delete reviews",
+                )),
+            );
+        }
+    };
+
+    match result {
+        Ok(_) => (
+            StatusCode::OK,
+            Json(ApiResponse::success(
+                "Review deleted successfully",
+                serde_json::json!(()),
+            )),
+        ),
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<serde_json::Value>::error(&e.to_string())),
+        ),
+    }
+}
+
 pub async fn reply_to_review(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -201,6 +235,57 @@ pub async fn reply_to_review(
     }
 }
 
+pub async fn get_unreplied_reviews(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<UnrepliedReviewQuery>,
+) -> impl IntoResponse {
+    if auth_user.role != "doctor" {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::<serde_json::Value>::error(
+                "Only doctors can view their unreplied reviews",
+            )),
+        );
+    }
+
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query.page_size.unwrap_or(10).clamp(1, 100);
+
+    match ReviewService::get_unreplied_reviews(
+        &state.pool,
+        auth_user.user_id,
+        query.sort_by.unwrap_or_default(),
+        page,
+        page_size,
+    )
+    .await
+    {
+        Ok((reviews, total)) => {
+            let response = serde_json::json!({
+                "reviews": reviews,
+                "pagination": {
+                    "page": page,
+                    "page_size": page_size,
+                    "total": total,
+                    "total_pages": (total as f64 / page_size as f64).ceil() as i64,
+                }
+            });
+            (
+                StatusCode::OK,
+                Json(ApiResponse::success(
+                    "Unreplied reviews retrieved successfully",
+                    response,
+                )),
+            )
+        }
+        Err(e) => (
+            StatusCode::BAD_REQUEST,
+            Json(ApiResponse::<serde_json::Value>::error(&e.to_string())),
+        ),
+    }
+}
+
 pub async fn update_review_visibility(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -380,6 +465,46 @@ pub async fn get_patient_reviews(
 }
 
 // 获取医生的评价列表
+pub async fn export_doctor_reviews(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(doctor_id): Path<Uuid>,
+) -> Response {
+    match ReviewService::export_doctor_reviews_csv(
+        &state.pool,
+        doctor_id,
+        auth_user.user_id,
+        &auth_user.role,
+    )
+    .await
+    {
+        Ok(csv_data) => {
+            let disposition = format!("attachment; filename=\"reviews-{}.csv\"", doctor_id);
+            (
+                StatusCode::OK,
+                [
+                    (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                    (header::CONTENT_DISPOSITION, disposition),
+                ],
+                csv_data,
+            )
+                .into_response()
+        }
+        Err(e) => {
+            let status = match e.to_string().as_str() {
+                "Insufficient permissions" => StatusCode::FORBIDDEN,
+                "Doctor not found" => StatusCode::NOT_FOUND,
+                _ => StatusCode::BAD_REQUEST,
+            };
+            (
+                status,
+                Json(ApiResponse::<serde_json::Value>::error(&e.to_string())),
+            )
+                .into_response()
+        }
+    }
+}
+
 pub async fn get_doctor_reviews(
     State(state): State<AppState>,
     Path(doctor_id): Path<Uuid>,
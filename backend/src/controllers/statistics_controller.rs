@@ -368,6 +368,33 @@ pub async fn get_appointment_heatmap(
     }
 }
 
+/// 分页查询审计日志（管理员）
+pub async fn get_audit_logs(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<AuditLogQuery>,
+) -> impl IntoResponse {
+    if auth_user.role != "admin" {
+        return (
+            StatusCode::FORBIDDEN,
+            Json(ApiResponse::<()>::error("无权限访问")),
+        )
+            .into_response();
+    }
+
+    match StatisticsService::list_audit_logs(&state.pool, query).await {
+        Ok(logs) => Json(ApiResponse::success("获取审计日志成功", logs)).into_response(),
+        Err(e) => {
+            eprintln!("获取审计日志失败: {:?}", e);
+            (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Json(ApiResponse::<()>::error("获取审计日志失败")),
+            )
+                .into_response()
+        }
+    }
+}
+
 /// 导出数据（管理员）
 pub async fn export_data(
     State(state): State<AppState>,
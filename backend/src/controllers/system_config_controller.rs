@@ -0,0 +1,14 @@
+use crate::models::ApiResponse;
+use crate::services::system_config_service::SystemConfigService;
+use crate::utils::errors::AppError;
+use crate::AppState;
+use axum::{extract::State, response::IntoResponse, Json};
+
+/// 客户端可读取的公开配置（上传限制、通话参数、订单金额范围等），无需登录
+pub async fn get_public_config(
+    State(state): State<AppState>,
+) -> Result<impl IntoResponse, AppError> {
+    let configs = SystemConfigService::get_public_configs(&state.pool).await?;
+
+    Ok(Json(ApiResponse::success("获取公开配置成功", configs)))
+}
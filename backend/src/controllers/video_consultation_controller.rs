@@ -7,12 +7,13 @@ use crate::utils::errors::AppError;
 use crate::AppState;
 use axum::{
     extract::{Extension, Path, Query, State},
-    http::StatusCode,
-    response::IntoResponse,
+    http::{header, StatusCode},
+    response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
 use uuid::Uuid;
+use validator::Validate;
 
 /// Helper function to check if a user is authorized to access a consultation
 async fn is_user_authorized_for_consultation(
@@ -71,6 +72,35 @@ pub async fn get_consultation(
         return Err(AppError::Forbidden);
     }
 
+    let questionnaires =
+        VideoConsultationService::get_consultation_questionnaires(&state.pool, consultation_id)
+            .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "获取视频问诊成功",
+            ConsultationDetail {
+                consultation,
+                questionnaires,
+            },
+        )),
+    ))
+}
+
+pub async fn get_consultation_by_appointment(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(appointment_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let consultation = VideoConsultationService::get_consultation_by_appointment(
+        &state.pool,
+        appointment_id,
+        auth_user.user_id,
+        auth_user.role == "admin",
+    )
+    .await?;
+
     Ok((
         StatusCode::OK,
         Json(ApiResponse::success("获取视频问诊成功", consultation)),
@@ -97,13 +127,116 @@ pub async fn list_consultations(
     ))
 }
 
+pub async fn admin_list_consultations(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<AdminConsultationListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query.page_size.unwrap_or(20).clamp(1, 100);
+    let filters = AdminConsultationFilters {
+        doctor_id: query.doctor_id,
+        patient_id: query.patient_id,
+        status: query.status,
+        date_from: query.date_from,
+        date_to: query.date_to,
+        min_rating: query.min_rating,
+        max_rating: query.max_rating,
+    };
+
+    let (items, total) =
+        VideoConsultationService::admin_list_consultations(&state.pool, filters, page, page_size)
+            .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "获取问诊列表成功",
+            AdminConsultationListPage {
+                items,
+                total,
+                page,
+                page_size,
+            },
+        )),
+    ))
+}
+
+pub async fn get_doctor_action_items(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "doctor" {
+        return Err(AppError::Forbidden);
+    }
+
+    let doctor = doctor_service::get_doctor_by_user_id(&state.pool, auth_user.user_id)
+        .await
+        .map_err(|_| AppError::NotFound("医生信息不存在".to_string()))?;
+
+    let items = VideoConsultationService::get_doctor_action_items(&state.pool, doctor.id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("获取待处理问诊成功", items)),
+    ))
+}
+
+pub async fn get_patient_consultation_summary(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<PatientConsultationSummaryQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "doctor" {
+        return Err(AppError::Forbidden);
+    }
+
+    let doctor = doctor_service::get_doctor_by_user_id(&state.pool, auth_user.user_id)
+        .await
+        .map_err(|_| AppError::NotFound("医生信息不存在".to_string()))?;
+
+    let page = query.page.unwrap_or(1).max(1);
+    let page_size = query.page_size.unwrap_or(20).clamp(1, 100);
+    let (items, total) = VideoConsultationService::get_patient_consultation_summary(
+        &state.pool,
+        doctor.id,
+        page,
+        page_size,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "获取患者问诊汇总成功",
+            PatientConsultationSummaryPage {
+                items,
+                total,
+                page,
+                page_size,
+            },
+        )),
+    ))
+}
+
 pub async fn join_room(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Path(room_id): Path<String>,
+    Query(query): Query<JoinRoomQuery>,
 ) -> Result<impl IntoResponse, AppError> {
-    let response =
-        VideoConsultationService::join_room(&state.pool, &room_id, auth_user.user_id).await?;
+    let response = VideoConsultationService::join_room(
+        &state.pool,
+        &state.config.jwt_secret,
+        &room_id,
+        auth_user.user_id,
+        query.token.as_deref(),
+    )
+    .await?;
 
     Ok((
         StatusCode::OK,
@@ -111,6 +244,59 @@ pub async fn join_room(
     ))
 }
 
+pub async fn resolve_room_code(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(room_code): Path<String>,
+) -> Result<impl IntoResponse, AppError> {
+    let consultation =
+        VideoConsultationService::get_consultation_by_room_code(&state.pool, &room_code).await?;
+
+    if !is_user_authorized_for_consultation(&state.pool, &auth_user, &consultation).await {
+        return Err(AppError::Forbidden);
+    }
+
+    let join_url = format!(
+        "/api/v1/video-consultations/room/{}/join",
+        consultation.room_id
+    );
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success(
+            "获取房间信息成功",
+            RoomCodeResolution {
+                consultation_id: consultation.id,
+                room_code: consultation.room_code.unwrap_or_default(),
+                room_id: consultation.room_id,
+                status: consultation.status,
+                scheduled_start_time: consultation.scheduled_start_time,
+                join_url,
+            },
+        )),
+    ))
+}
+
+pub async fn resend_invite(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(consultation_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    let response = VideoConsultationService::resend_invite(
+        &state.pool,
+        &state.ws_manager,
+        &state.config.jwt_secret,
+        consultation_id,
+        auth_user.user_id,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("邀请已重新发送", response)),
+    ))
+}
+
 pub async fn start_consultation(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -155,6 +341,78 @@ pub async fn end_consultation(
     ))
 }
 
+pub async fn refer_to_offline(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(consultation_id): Path<Uuid>,
+    Json(dto): Json<ReferToOfflineDto>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only doctors can refer patients to in-person visits
+    if auth_user.role != "doctor" {
+        return Err(AppError::Forbidden);
+    }
+
+    dto.validate()?;
+
+    let doctor = doctor_service::get_doctor_by_user_id(&state.pool, auth_user.user_id)
+        .await
+        .map_err(|_| AppError::NotFound("医生信息不存在".to_string()))?;
+
+    let appointment = VideoConsultationService::refer_to_offline(
+        &state.pool,
+        consultation_id,
+        doctor.id,
+        dto.notes,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success("已转线下就诊", appointment)),
+    ))
+}
+
+pub async fn handover_consultation(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(consultation_id): Path<Uuid>,
+    Json(dto): Json<HandoverConsultationDto>,
+) -> Result<impl IntoResponse, AppError> {
+    dto.validate()?;
+
+    // The scheduled doctor hands over their own consultation; admins may
+    // reassign any consultation on the doctor's behalf
+    let is_admin = auth_user.role == "admin";
+    let from_doctor_id = if is_admin {
+        VideoConsultationService::get_consultation(&state.pool, consultation_id)
+            .await?
+            .doctor_id
+    } else if auth_user.role == "doctor" {
+        doctor_service::get_doctor_by_user_id(&state.pool, auth_user.user_id)
+            .await
+            .map_err(|_| AppError::NotFound("医生信息不存在".to_string()))?
+            .id
+    } else {
+        return Err(AppError::Forbidden);
+    };
+
+    let consultation = VideoConsultationService::handover_consultation(
+        &state.pool,
+        consultation_id,
+        from_doctor_id,
+        dto.to_doctor_id,
+        auth_user.user_id,
+        is_admin,
+        dto.reason,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("问诊已转交", consultation)),
+    ))
+}
+
 pub async fn update_consultation(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
@@ -205,17 +463,199 @@ pub async fn rate_consultation(
     ))
 }
 
+pub async fn attach_questionnaire(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(consultation_id): Path<Uuid>,
+    Json(dto): Json<AttachQuestionnaireDto>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "doctor" {
+        return Err(AppError::Forbidden);
+    }
+
+    dto.validate()?;
+
+    let doctor = doctor_service::get_doctor_by_user_id(&state.pool, auth_user.user_id)
+        .await
+        .map_err(|_| AppError::NotFound("医生信息不存在".to_string()))?;
+
+    let questionnaire = VideoConsultationService::attach_questionnaire(
+        &state.pool,
+        consultation_id,
+        doctor.id,
+        dto,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success("问卷已添加", questionnaire)),
+    ))
+}
+
+pub async fn submit_questionnaire_answers(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path((consultation_id, questionnaire_id)): Path<(Uuid, Uuid)>,
+    Json(dto): Json<SubmitQuestionnaireAnswersDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let questionnaire = VideoConsultationService::submit_questionnaire_answers(
+        &state.pool,
+        consultation_id,
+        questionnaire_id,
+        auth_user.user_id,
+        dto.answers,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("问卷已提交", questionnaire)),
+    ))
+}
+
+pub async fn submit_patient_feedback(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(consultation_id): Path<Uuid>,
+    Json(dto): Json<SubmitPatientFeedbackDto>,
+) -> Result<impl IntoResponse, AppError> {
+    // Only doctors can leave feedback about patients
+    if auth_user.role != "doctor" {
+        return Err(AppError::Forbidden);
+    }
+
+    dto.validate()?;
+
+    let doctor = doctor_service::get_doctor_by_user_id(&state.pool, auth_user.user_id)
+        .await
+        .map_err(|_| AppError::NotFound("医生信息不存在".to_string()))?;
+
+    let feedback = VideoConsultationService::submit_patient_feedback(
+        &state.pool,
+        consultation_id,
+        doctor.id,
+        dto.rating,
+        dto.notes,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success("患者反馈已提交", feedback)),
+    ))
+}
+
+pub async fn get_patient_reliability(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(patient_id): Path<Uuid>,
+) -> Result<impl IntoResponse, AppError> {
+    // Internal score, never exposed to patients
+    if auth_user.role != "doctor" && auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let reliability =
+        VideoConsultationService::get_patient_reliability(&state.pool, patient_id).await?;
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("获取患者可靠度成功", reliability)),
+    ))
+}
+
+// Consultation Chat
+pub async fn send_consultation_message(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(consultation_id): Path<Uuid>,
+    Json(dto): Json<SendConsultationMessageDto>,
+) -> Result<impl IntoResponse, AppError> {
+    dto.validate()?;
+
+    let message = VideoConsultationService::send_message(
+        &state.pool,
+        consultation_id,
+        auth_user.user_id,
+        dto.content,
+    )
+    .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success("消息已发送", message)),
+    ))
+}
+
+pub async fn get_consultation_transcript(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(consultation_id): Path<Uuid>,
+    Query(query): Query<TranscriptQuery>,
+) -> Result<Response, AppError> {
+    let transcript = VideoConsultationService::render_transcript(
+        &state.pool,
+        consultation_id,
+        auth_user.user_id,
+        auth_user.role == "admin",
+    )
+    .await?;
+
+    if query.format.as_deref() == Some("text") {
+        let disposition = format!(
+            "attachment; filename=\"consultation-{}.txt\"",
+            consultation_id
+        );
+        return Ok((
+            StatusCode::OK,
+            [
+                (
+                    header::CONTENT_TYPE,
+                    "text/plain; charset=utf-8".to_string(),
+                ),
+                (header::CONTENT_DISPOSITION, disposition),
+            ],
+            transcript.to_text(),
+        )
+            .into_response());
+    }
+
+    Ok((
+        StatusCode::OK,
+        Json(ApiResponse::success("获取问诊记录成功", transcript)),
+    )
+        .into_response())
+}
+
 // WebRTC Signaling
 pub async fn send_signal(
     State(state): State<AppState>,
     Extension(auth_user): Extension<AuthUser>,
     Json(dto): Json<SendSignalDto>,
 ) -> Result<impl IntoResponse, AppError> {
-    VideoConsultationService::send_signal(&state.pool, auth_user.user_id, dto).await?;
+    let stored = VideoConsultationService::send_signal(
+        &state.pool,
+        &state.ws_manager,
+        &state.config.jwt_secret,
+        auth_user.user_id,
+        dto,
+    )
+    .await?;
+
+    // Duplicates are acknowledged so clients don't retry them
+    let message = if stored {
+        "信令已发送"
+    } else {
+        "重复信令已忽略"
+    };
 
     Ok((
         StatusCode::OK,
-        Json(ApiResponse::success("信令已发送", json!({}))),
+        Json(ApiResponse::success(
+            message,
+            json!({ "duplicate": !stored }),
+        )),
     ))
 }
 
@@ -0,0 +1,151 @@
+use crate::{
+    middleware::auth::AuthUser,
+    models::{withdrawal::*, ApiResponse},
+    services::{doctor_service, withdrawal_service::WithdrawalService},
+    utils::errors::AppError,
+    AppState,
+};
+use axum::{
+    extract::{Path, Query, State},
+    http::StatusCode,
+    response::IntoResponse,
+    Extension, Json,
+};
+use uuid::Uuid;
+use validator::Validate;
+
+async fn current_doctor_id(state: &AppState, auth_user: &AuthUser) -> Result<Uuid, AppError> {
+    if auth_user.role != "doctor" {
+        return Err(AppError::Forbidden);
+    }
+
+    doctor_service::get_doctor_by_user_id(&state.pool, auth_user.user_id)
+        .await
+        .map(|doctor| doctor.id)
+        .map_err(|_| AppError::NotFound("医生信息不存在".to_string()))
+}
+
+// Payout account endpoints
+pub async fn create_payout_account(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<CreatePayoutAccountDto>,
+) -> Result<impl IntoResponse, AppError> {
+    dto.validate()?;
+    let doctor_id = current_doctor_id(&state, &auth_user).await?;
+
+    let account = WithdrawalService::create_payout_account(&state.pool, doctor_id, dto).await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success("收款账户添加成功", account)),
+    ))
+}
+
+pub async fn list_payout_accounts(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, AppError> {
+    let doctor_id = current_doctor_id(&state, &auth_user).await?;
+
+    let accounts = WithdrawalService::list_payout_accounts(&state.pool, doctor_id).await?;
+
+    Ok(Json(ApiResponse::success("获取收款账户成功", accounts)))
+}
+
+// Earnings endpoints
+pub async fn get_earnings_summary(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+) -> Result<impl IntoResponse, AppError> {
+    let doctor_id = current_doctor_id(&state, &auth_user).await?;
+
+    let summary = WithdrawalService::get_earnings_summary(&state.pool, doctor_id).await?;
+
+    Ok(Json(ApiResponse::success("获取收益概览成功", summary)))
+}
+
+// Withdrawal endpoints
+pub async fn create_withdrawal(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Json(dto): Json<CreateWithdrawalDto>,
+) -> Result<impl IntoResponse, AppError> {
+    let doctor_id = current_doctor_id(&state, &auth_user).await?;
+
+    let withdrawal =
+        WithdrawalService::create_withdrawal(&state.pool, doctor_id, auth_user.user_id, dto)
+            .await?;
+
+    Ok((
+        StatusCode::CREATED,
+        Json(ApiResponse::success("提现申请提交成功", withdrawal)),
+    ))
+}
+
+pub async fn list_my_withdrawals(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(query): Query<WithdrawalListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    let doctor_id = current_doctor_id(&state, &auth_user).await?;
+
+    let withdrawals =
+        WithdrawalService::list_withdrawals(&state.pool, Some(doctor_id), query).await?;
+
+    Ok(Json(ApiResponse::success("获取提现记录成功", withdrawals)))
+}
+
+// Admin endpoints
+pub async fn list_withdrawal_queue(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Query(mut query): Query<WithdrawalListQuery>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    if query.status.is_none() {
+        query.status = Some("pending".to_string());
+    }
+
+    let withdrawals = WithdrawalService::list_withdrawals(&state.pool, None, query).await?;
+
+    Ok(Json(ApiResponse::success("获取提现队列成功", withdrawals)))
+}
+
+pub async fn process_withdrawal(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(withdrawal_id): Path<Uuid>,
+    Json(dto): Json<ProcessWithdrawalDto>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+    dto.validate()?;
+
+    let withdrawal =
+        WithdrawalService::process_withdrawal(&state.pool, withdrawal_id, dto, auth_user.user_id)
+            .await?;
+
+    Ok(Json(ApiResponse::success("提现处理完成", withdrawal)))
+}
+
+pub async fn verify_payout_account(
+    State(state): State<AppState>,
+    Extension(auth_user): Extension<AuthUser>,
+    Path(account_id): Path<Uuid>,
+    Json(dto): Json<VerifyPayoutAccountDto>,
+) -> Result<impl IntoResponse, AppError> {
+    if auth_user.role != "admin" {
+        return Err(AppError::Forbidden);
+    }
+
+    let account =
+        WithdrawalService::verify_payout_account(&state.pool, account_id, dto, auth_user.user_id)
+            .await?;
+
+    Ok(Json(ApiResponse::success("收款账户审核完成", account)))
+}
@@ -2,6 +2,7 @@ pub mod config;
 pub mod controllers;
 pub mod middleware;
 pub mod models;
+pub mod openapi;
 pub mod routes;
 pub mod services;
 pub mod utils;
@@ -9,7 +10,7 @@ pub mod utils;
 pub use config::{database, redis, storage, Config};
 
 use aws_sdk_s3::Client as S3Client;
-use services::websocket_service::WebSocketManager;
+use services::{payment_gateway::PaymentGateway, websocket_service::WebSocketManager};
 use std::sync::Arc;
 
 #[derive(Clone)]
@@ -19,4 +20,5 @@ pub struct AppState {
     pub redis: Option<redis::RedisPool>,
     pub ws_manager: Arc<WebSocketManager>,
     pub s3_client: Option<S3Client>,
+    pub payment_gateway: Arc<dyn PaymentGateway>,
 }
@@ -5,11 +5,18 @@ use tower_http::cors::CorsLayer;
 
 use backend::{
     config::{database, redis, storage, Config},
-    routes,
-    services::websocket_service::WebSocketManager,
+    openapi, routes,
+    services::{
+        payment_gateway::{HttpPaymentGateway, PaymentGateway},
+        scheduler_service::SchedulerService,
+        websocket_service::WebSocketManager,
+    },
+    utils::crypto,
     AppState,
 };
 use std::sync::Arc;
+use std::time::Duration;
+use utoipa_swagger_ui::SwaggerUi;
 
 #[tokio::main]
 async fn main() {
@@ -21,6 +28,8 @@ async fn main() {
         .init();
 
     let config = Config::from_env().expect("Failed to load configuration");
+    crypto::data_encryption_key()
+        .expect("DATA_ENCRYPTION_KEY must be a base64-encoded 32-byte key");
     let pool = database::create_pool()
         .await
         .expect("Failed to create database pool");
@@ -39,8 +48,28 @@ async fn main() {
     // Create WebSocket manager
     let ws_manager = Arc::new(WebSocketManager::new());
 
+    let payment_gateway: Arc<dyn PaymentGateway> = Arc::new(HttpPaymentGateway::new());
+
+    // Start background jobs
+    SchedulerService::start(
+        pool.clone(),
+        redis_pool.clone(),
+        ws_manager.clone(),
+        Duration::from_secs(config.order_expiry_interval_secs.max(1)),
+        s3_client.clone(),
+        payment_gateway.clone(),
+    );
+
     let server_port = config.server_port;
-    let app = create_app(config, pool, redis_pool, ws_manager, s3_client).await;
+    let app = create_app(
+        config,
+        pool,
+        redis_pool,
+        ws_manager,
+        s3_client,
+        payment_gateway,
+    )
+    .await;
 
     let addr = SocketAddr::from(([127, 0, 0, 1], server_port));
     tracing::info!("TCM Telemedicine Platform listening on {}", addr);
@@ -60,6 +89,7 @@ async fn create_app(
     redis: Option<redis::RedisPool>,
     ws_manager: Arc<WebSocketManager>,
     s3_client: Option<aws_sdk_s3::Client>,
+    payment_gateway: Arc<dyn PaymentGateway>,
 ) -> Router {
     let state = AppState {
         config,
@@ -67,20 +97,24 @@ async fn create_app(
         redis,
         ws_manager,
         s3_client,
+        payment_gateway,
     };
 
-    Router::new()
+    let mut router = Router::new()
         .route("/", get(root))
-        .route("/health", get(health_check))
-        .nest("/api/v1", routes::create_routes())
-        .layer(CorsLayer::permissive())
-        .with_state(state)
+        .merge(routes::health::routes())
+        .nest("/api/v1", routes::create_routes());
+
+    if openapi::swagger_ui_enabled() {
+        router = router.merge(
+            SwaggerUi::new("/swagger-ui")
+                .config(utoipa_swagger_ui::Config::from("/api/v1/openapi.json")),
+        );
+    }
+
+    router.layer(CorsLayer::permissive()).with_state(state)
 }
 
 async fn root() -> &'static str {
     "TCM Telemedicine Platform API"
 }
-
-async fn health_check() -> &'static str {
-    "OK"
-}
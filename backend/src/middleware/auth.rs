@@ -1,9 +1,9 @@
-use crate::utils::jwt::decode_token;
+use crate::utils::{errors::AppError, jwt::decode_token};
 use axum::{
     extract::Request,
     http::{header, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
@@ -14,10 +14,7 @@ pub struct AuthUser {
     pub role: String,
 }
 
-pub async fn auth_middleware(
-    mut req: Request,
-    next: Next,
-) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+pub async fn auth_middleware(mut req: Request, next: Next) -> Result<Response, Response> {
     let auth_header = req
         .headers()
         .get(header::AUTHORIZATION)
@@ -34,7 +31,8 @@ pub async fn auth_middleware(
                     "success": false,
                     "message": "Missing or invalid authorization header"
                 })),
-            ));
+            )
+                .into_response());
         }
     };
 
@@ -51,16 +49,35 @@ pub async fn auth_middleware(
             req.extensions_mut().insert(auth_user);
             Ok(next.run(req).await)
         }
-        Err(_) => Err((
-            StatusCode::UNAUTHORIZED,
-            Json(json!({
-                "success": false,
-                "message": "Invalid or expired token"
-            })),
-        )),
+        // 区分过期与无效令牌，客户端据此选择刷新令牌或重新登录
+        Err(e) => Err(AppError::from(e).into_response()),
     }
 }
 
+/// 可选认证：携带有效令牌时注入 `AuthUser`，否则按匿名请求继续处理
+pub async fn optional_auth_middleware(mut req: Request, next: Next) -> Response {
+    let auth_user = req
+        .headers()
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .and_then(|token| {
+            let jwt_secret =
+                std::env::var("JWT_SECRET").unwrap_or_else(|_| "default_jwt_secret".to_string());
+            decode_token(token, &jwt_secret).ok()
+        })
+        .map(|claims| AuthUser {
+            user_id: claims.sub,
+            role: claims.role,
+        });
+
+    if let Some(auth_user) = auth_user {
+        req.extensions_mut().insert(auth_user);
+    }
+
+    next.run(req).await
+}
+
 type BoxedFuture = std::pin::Pin<
     Box<
         dyn std::future::Future<Output = Result<Response, (StatusCode, Json<serde_json::Value>)>>
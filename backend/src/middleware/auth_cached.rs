@@ -1,9 +1,13 @@
-use crate::{services::session_service::SessionService, utils::jwt::decode_token, AppState};
+use crate::{
+    services::session_service::SessionService,
+    utils::{errors::AppError, jwt::decode_token},
+    AppState,
+};
 use axum::{
     extract::{Request, State},
     http::{header, StatusCode},
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
     Json,
 };
 use serde_json::json;
@@ -18,7 +22,7 @@ pub async fn auth_middleware_cached(
     State(app_state): State<AppState>,
     mut req: Request,
     next: Next,
-) -> Result<Response, (StatusCode, Json<serde_json::Value>)> {
+) -> Result<Response, Response> {
     let auth_header = req
         .headers()
         .get(header::AUTHORIZATION)
@@ -35,7 +39,8 @@ pub async fn auth_middleware_cached(
                     "success": false,
                     "message": "Missing or invalid authorization header"
                 })),
-            ));
+            )
+                .into_response());
         }
     };
 
@@ -71,13 +76,8 @@ pub async fn auth_middleware_cached(
                 Ok(next.run(req).await)
             }
         }
-        Err(_) => Err((
-            StatusCode::UNAUTHORIZED,
-            Json(json!({
-                "success": false,
-                "message": "Invalid or expired token"
-            })),
-        )),
+        // 区分过期与无效令牌，客户端据此选择刷新令牌或重新登录
+        Err(e) => Err(AppError::from(e).into_response()),
     }
 }
 
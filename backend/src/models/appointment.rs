@@ -1,33 +1,45 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Appointment {
     pub id: Uuid,
     pub patient_id: Uuid,
+    /// 就诊人档案ID，为空表示账号本人就诊
+    pub patient_profile_id: Option<Uuid>,
     pub doctor_id: Uuid,
     pub appointment_date: DateTime<Utc>,
     pub time_slot: String,
     pub visit_type: VisitType,
+    /// 上门问诊地址ID，仅上门问诊有值
+    pub address_id: Option<Uuid>,
     pub symptoms: String,
     pub has_visited_before: bool,
     pub status: AppointmentStatus,
+    /// 由视频问诊转线下时，来源问诊ID
+    pub referred_from_consultation_id: Option<Uuid>,
+    /// 医生转诊说明
+    pub referral_notes: Option<String>,
+    /// 周期预约系列ID，单次预约为空
+    pub series_id: Option<Uuid>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "visit_type", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum VisitType {
     OnlineVideo,
     Offline,
+    HomeVisit,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq, ToSchema)]
 #[sqlx(type_name = "appointment_status", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum AppointmentStatus {
@@ -35,23 +47,139 @@ pub enum AppointmentStatus {
     Confirmed,
     Completed,
     Cancelled,
+    /// 视频问诊超过预约时间仍未开始，由定时任务标记
+    #[serde(rename = "no_show")]
+    #[sqlx(rename = "no_show")]
+    NoShow,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateAppointmentDto {
+    /// 患者ID，患者本人预约时以登录用户为准
     pub patient_id: Uuid,
+    /// 为家人预约时指定就诊人档案，档案须属于该患者账号
+    #[serde(default, alias = "profile_id")]
+    pub patient_profile_id: Option<Uuid>,
     pub doctor_id: Uuid,
     pub appointment_date: DateTime<Utc>,
+    /// 预约时间段，如 `09:00-10:00`
     pub time_slot: String,
     pub visit_type: VisitType,
+    /// 上门问诊地址，须为该患者保存的地址；不填时使用默认地址
+    #[serde(default)]
+    pub address_id: Option<Uuid>,
+    /// 症状描述，最多100字
     #[validate(length(max = 100))]
     pub symptoms: String,
+    /// 是否曾在该医生处就诊
     pub has_visited_before: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct UpdateAppointmentDto {
     pub appointment_date: Option<DateTime<Utc>>,
     pub time_slot: Option<String>,
     pub status: Option<AppointmentStatus>,
 }
+
+/// 预约详情；接诊医生查看时附带私密备注，其他人查看时不含该字段
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AppointmentDetail {
+    #[serde(flatten)]
+    pub appointment: Appointment,
+    /// 医生私密备注，仅接诊医生可见
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub doctor_private_notes: Option<String>,
+}
+
+/// 更新医生私密备注，传 null 清空
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdatePrivateNotesDto {
+    #[validate(length(max = 2000))]
+    pub doctor_private_notes: Option<String>,
+}
+
+/// 医生日程中的一条预约，附带就诊人姓名和关联视频问诊的状态
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DoctorScheduleItem {
+    pub appointment_id: Uuid,
+    pub appointment_date: DateTime<Utc>,
+    pub time_slot: String,
+    pub visit_type: VisitType,
+    pub status: AppointmentStatus,
+    pub symptoms: String,
+    pub patient_id: Uuid,
+    pub patient_profile_id: Option<Uuid>,
+    /// 就诊人姓名，代管档案取档案姓名
+    pub patient_name: String,
+    /// 关联的视频问诊，线下预约或尚未创建问诊时为空
+    pub consultation_id: Option<Uuid>,
+    pub consultation_status: Option<String>,
+}
+
+/// 将未开始的预约转给账号下的另一就诊人档案
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransferAppointmentDto {
+    #[serde(alias = "profile_id")]
+    pub patient_profile_id: Uuid,
+}
+
+/// 周期预约的重复规则
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AppointmentRecurrence {
+    Daily,
+    Weekly,
+    Biweekly,
+}
+
+impl AppointmentRecurrence {
+    pub fn interval_days(&self) -> i64 {
+        match self {
+            AppointmentRecurrence::Daily => 1,
+            AppointmentRecurrence::Weekly => 7,
+            AppointmentRecurrence::Biweekly => 14,
+        }
+    }
+}
+
+/// 创建周期预约，从 `start_date` 起按重复规则生成 `count` 次预约
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateAppointmentSeriesDto {
+    /// 患者ID，患者本人预约时以登录用户为准
+    pub patient_id: Uuid,
+    #[serde(default, alias = "profile_id")]
+    pub patient_profile_id: Option<Uuid>,
+    pub doctor_id: Uuid,
+    /// 第一次预约的时间
+    pub start_date: DateTime<Utc>,
+    /// 每次预约的时间段，如 `09:00-10:00`
+    pub time_slot: String,
+    pub visit_type: VisitType,
+    /// 上门问诊地址，不填时使用默认地址
+    #[serde(default)]
+    pub address_id: Option<Uuid>,
+    #[validate(length(max = 100))]
+    pub symptoms: String,
+    pub has_visited_before: bool,
+    pub recurrence: AppointmentRecurrence,
+    /// 生成次数，2-12 次
+    #[validate(range(min = 2, max = 12))]
+    pub count: u32,
+}
+
+/// 因冲突被跳过的一次预约
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct SkippedOccurrence {
+    pub appointment_date: DateTime<Utc>,
+    /// 跳过原因：`doctor_unavailable` 医生该时段已约满，`out_of_office` 医生停诊，`patient_conflict` 患者该时段已有预约，`in_past` 时间已过
+    pub reason: String,
+}
+
+/// 周期预约的生成结果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AppointmentSeriesResult {
+    pub series_id: Uuid,
+    pub created: Vec<Appointment>,
+    pub skipped: Vec<SkippedOccurrence>,
+}
@@ -0,0 +1,98 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+/// 广播人群分组条件，所有条件之间为 AND 关系
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SegmentDefinition {
+    /// 用户角色：admin / doctor / patient
+    pub roles: Option<Vec<String>>,
+    /// 最近 N 天内有过未取消的预约
+    pub last_appointment_within_days: Option<i64>,
+    /// 曾就诊医生所属科室
+    pub doctor_departments: Option<Vec<String>>,
+    /// 对系统公告开启的渠道：push / email / sms
+    pub channels: Option<Vec<String>>,
+    pub registered_after: Option<DateTime<Utc>>,
+    pub registered_before: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BroadcastSegment {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub definition: SegmentDefinition,
+    pub created_by: Uuid,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateSegmentDto {
+    #[validate(length(min = 1, max = 100))]
+    pub name: String,
+    #[validate(length(max = 500))]
+    pub description: Option<String>,
+    pub definition: SegmentDefinition,
+}
+
+/// 预览或发送时指定人群：已保存分组或临时条件二选一
+#[derive(Debug, Deserialize)]
+pub struct PreviewSegmentDto {
+    pub segment_id: Option<Uuid>,
+    pub definition: Option<SegmentDefinition>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SegmentMember {
+    pub id: Uuid,
+    pub name: String,
+    pub role: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SegmentPreview {
+    pub size: i64,
+    pub sample: Vec<SegmentMember>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BroadcastStatus {
+    PendingApproval,
+    Sent,
+    Rejected,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationBroadcast {
+    pub id: Uuid,
+    pub title: String,
+    pub content: String,
+    pub related_id: Option<Uuid>,
+    pub segment_id: Option<Uuid>,
+    pub definition: SegmentDefinition,
+    pub status: BroadcastStatus,
+    pub targeted_count: i32,
+    pub delivered_count: i32,
+    pub skipped_count: i32,
+    pub created_by: Uuid,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub sent_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Deserialize, Validate)]
+pub struct CreateBroadcastDto {
+    #[validate(length(min = 1, max = 200))]
+    pub title: String,
+    #[validate(length(min = 1))]
+    pub content: String,
+    pub related_id: Option<Uuid>,
+    pub segment_id: Option<Uuid>,
+    pub definition: Option<SegmentDefinition>,
+}
@@ -15,6 +15,8 @@ pub struct Circle {
     pub member_count: i32,
     pub post_count: i32,
     pub is_active: bool,
+    /// 加入是否需要圈主/圈管理员审核
+    pub requires_approval: bool,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -58,6 +60,8 @@ pub struct CreateCircleDto {
     pub avatar: Option<String>,
     #[validate(length(min = 1, max = 50))]
     pub category: String,
+    #[serde(default)]
+    pub requires_approval: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize, Validate)]
@@ -68,6 +72,7 @@ pub struct UpdateCircleDto {
     pub description: Option<String>,
     pub avatar: Option<String>,
     pub is_active: Option<bool>,
+    pub requires_approval: Option<bool>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -91,3 +96,38 @@ pub struct CircleMemberInfo {
 pub struct UpdateMemberRoleDto {
     pub role: MemberRole,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JoinRequestStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CircleJoinRequest {
+    pub id: Uuid,
+    pub circle_id: Uuid,
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub status: JoinRequestStatus,
+    pub handled_by: Option<Uuid>,
+    pub handled_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct BulkApproveJoinRequestsDto {
+    #[validate(length(min = 1, max = 100))]
+    pub request_ids: Vec<Uuid>,
+}
+
+/// 批量审批中单个申请的处理结果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct JoinRequestApprovalResult {
+    pub request_id: Uuid,
+    pub approved: bool,
+    /// 未通过时的原因，如申请不存在或已处理
+    pub reason: Option<String>,
+}
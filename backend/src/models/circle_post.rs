@@ -15,6 +15,7 @@ pub struct CirclePost {
     pub likes: i64,
     pub comments: i64,
     pub status: PostStatus,
+    pub edited_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
@@ -61,10 +62,25 @@ pub struct CirclePostWithAuthor {
     pub likes: i64,
     pub comments: i64,
     pub is_liked: bool,
+    /// 帖子发布后是否被编辑过
+    pub is_edited: bool,
+    pub edited_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+/// 帖子编辑前的内容快照
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CirclePostHistory {
+    pub id: Uuid,
+    pub post_id: Uuid,
+    pub editor_id: Uuid,
+    pub title: String,
+    pub content: String,
+    pub images: Vec<String>,
+    pub edited_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct PostLike {
     pub id: Uuid,
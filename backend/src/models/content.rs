@@ -1,10 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
 // Article models
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Article {
     pub id: Uuid,
     pub title: String,
@@ -20,12 +21,26 @@ pub struct Article {
     pub like_count: u32,
     pub status: ContentStatus,
     pub publish_channels: Option<Vec<String>>,
+    /// 定时发布时间，到期前保持草稿
+    pub scheduled_publish_at: Option<DateTime<Utc>>,
+    /// 最近一次审核的意见，驳回时说明原因
+    pub review_notes: Option<String>,
     pub published_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// 当前用户是否已点赞，仅登录用户查看详情时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub has_liked: Option<bool>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// 点赞或取消点赞后的状态
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ArticleLikeStatus {
+    pub liked: bool,
+    pub like_count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ArticleListItem {
     pub id: Uuid,
     pub title: String,
@@ -37,10 +52,13 @@ pub struct ArticleListItem {
     pub status: ContentStatus,
     pub published_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// 全文检索相关度，仅 `sort=relevance` 时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relevance_score: Option<f64>,
 }
 
 // Video models
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct Video {
     pub id: Uuid,
     pub title: String,
@@ -58,12 +76,16 @@ pub struct Video {
     pub like_count: u32,
     pub status: VideoStatus,
     pub publish_channels: Option<Vec<String>>,
+    /// 定时发布时间，到期前保持草稿
+    pub scheduled_publish_at: Option<DateTime<Utc>>,
+    /// 最近一次审核的意见，驳回时说明原因
+    pub review_notes: Option<String>,
     pub published_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct VideoListItem {
     pub id: Uuid,
     pub title: String,
@@ -76,10 +98,13 @@ pub struct VideoListItem {
     pub status: VideoStatus,
     pub published_at: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
+    /// 全文检索相关度，仅 `sort=relevance` 时返回
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub relevance_score: Option<f64>,
 }
 
 // Category model
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct ContentCategory {
     pub id: Uuid,
     pub name: String,
@@ -91,7 +116,7 @@ pub struct ContentCategory {
 }
 
 // Enums
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "author_type", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum AuthorType {
@@ -99,26 +124,41 @@ pub enum AuthorType {
     Doctor,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "content_status", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum ContentStatus {
     Draft,
+    /// 已提交发布，等待管理员审核
+    #[serde(rename = "pending_review")]
+    #[sqlx(rename = "pending_review")]
+    PendingReview,
     Published,
     Offline,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "video_status", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum VideoStatus {
     Draft,
     Processing,
+    /// 已提交发布，等待管理员审核
+    #[serde(rename = "pending_review")]
+    #[sqlx(rename = "pending_review")]
+    PendingReview,
     Published,
     Offline,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
+/// 需要发布审核的内容类型
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentKind {
+    Article,
+    Video,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "category_type", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum CategoryType {
@@ -128,7 +168,7 @@ pub enum CategoryType {
 }
 
 // DTOs for Article
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateArticleDto {
     #[validate(length(min = 1, max = 200))]
     pub title: String,
@@ -138,13 +178,17 @@ pub struct CreateArticleDto {
     pub summary: Option<String>,
     #[validate(length(min = 1))]
     pub content: String,
+    /// 文章分类名称
     #[validate(length(min = 1, max = 50))]
     pub category: String,
     pub tags: Option<Vec<String>>,
+    /// 发布渠道，如 `web`、`app`
     pub publish_channels: Option<Vec<String>>,
+    /// 定时发布时间，到期后自动发布
+    pub scheduled_publish_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UpdateArticleDto {
     #[validate(length(min = 1, max = 200))]
     pub title: Option<String>,
@@ -160,13 +204,15 @@ pub struct UpdateArticleDto {
     pub publish_channels: Option<Vec<String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PublishArticleDto {
     pub publish_channels: Vec<String>,
+    /// 定时发布时间；为空或已过期时立即发布
+    pub scheduled_publish_at: Option<DateTime<Utc>>,
 }
 
 // DTOs for Video
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateVideoDto {
     #[validate(length(min = 1, max = 200))]
     pub title: String,
@@ -181,9 +227,11 @@ pub struct CreateVideoDto {
     pub category: String,
     pub tags: Option<Vec<String>>,
     pub publish_channels: Option<Vec<String>>,
+    /// 定时发布时间，到期后自动发布
+    pub scheduled_publish_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UpdateVideoDto {
     #[validate(length(min = 1, max = 200))]
     pub title: Option<String>,
@@ -200,16 +248,68 @@ pub struct UpdateVideoDto {
     pub publish_channels: Option<Vec<String>>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PublishVideoDto {
     pub publish_channels: Vec<String>,
+    /// 定时发布时间；为空或已过期时立即发布
+    pub scheduled_publish_at: Option<DateTime<Utc>>,
+}
+
+/// 管理员审核文章或视频的发布申请
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ReviewContentDto {
+    /// 审核意见，驳回时必填
+    #[validate(length(max = 500))]
+    pub notes: Option<String>,
 }
 
 // DTO for Category
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateCategoryDto {
     #[validate(length(min = 1, max = 50))]
     pub name: String,
     pub r#type: CategoryType,
     pub sort_order: Option<i32>,
 }
+
+// Recommendation models
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct RecommendedContent {
+    pub content_type: String,
+    pub id: Uuid,
+    pub title: String,
+    pub cover_image: Option<String>,
+    pub summary: Option<String>,
+    pub author_name: String,
+    pub category: String,
+    pub view_count: u32,
+    pub like_count: u32,
+    pub published_at: Option<DateTime<Utc>>,
+    pub score: f64,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct RecommendedFeed {
+    pub items: Vec<RecommendedContent>,
+    pub page: u32,
+    pub per_page: u32,
+    pub personalized: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DiagnosisCategoryMapping {
+    pub id: Uuid,
+    pub keyword: String,
+    pub category: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateDiagnosisMappingDto {
+    #[validate(length(min = 1, max = 100))]
+    pub keyword: String,
+    #[validate(length(min = 1, max = 50))]
+    pub category: String,
+}
@@ -1,10 +1,13 @@
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct Doctor {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -25,7 +28,21 @@ pub struct Doctor {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+/// 患者可见的接诊医生资料，不含身份证号和证件照片
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TreatingDoctor {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub hospital: String,
+    pub department: String,
+    pub title: String,
+    pub introduction: Option<String>,
+    pub specialties: Vec<String>,
+    pub experience: Option<String>,
+    pub avatar: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateDoctorDto {
     pub user_id: Uuid,
     pub certificate_type: String,
@@ -39,7 +56,7 @@ pub struct CreateDoctorDto {
     pub experience: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct UpdateDoctorDto {
     pub hospital: Option<String>,
     pub department: Option<String>,
@@ -47,9 +64,12 @@ pub struct UpdateDoctorDto {
     pub introduction: Option<String>,
     pub specialties: Option<Vec<String>>,
     pub experience: Option<String>,
+    /// 视频问诊预计时长（分钟）
+    #[validate(range(min = 5, max = 240))]
+    pub consultation_duration_minutes: Option<i32>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct DoctorPhotos {
     pub avatar: Option<String>,
     pub license_photo: Option<String>,
@@ -57,3 +77,95 @@ pub struct DoctorPhotos {
     pub id_card_back: Option<String>,
     pub title_cert: Option<String>,
 }
+
+/// 设置停诊时间段和提示语，`end` 须晚于 `start`
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct SetOutOfOfficeDto {
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// 停诊期间预约和患者消息收到的自动回复
+    #[validate(length(min = 1, max = 500))]
+    pub message: String,
+}
+
+/// 医生停诊设置
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DoctorOutOfOffice {
+    pub doctor_id: Uuid,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub message: String,
+}
+
+/// 医生月度汇总：预约、视频问诊、收入和评价，均按所选自然月统计
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DoctorMonthlyReport {
+    pub doctor_id: Uuid,
+    pub year: i32,
+    pub month: u32,
+    /// 预约日期在当月的预约总数
+    pub total_appointments: i64,
+    /// 按状态统计的预约数：pending、confirmed、completed、cancelled
+    pub appointments_by_status: BTreeMap<String, i64>,
+    /// 当月结束的已完成视频问诊数
+    pub completed_consultations: i64,
+    /// 已完成问诊的平均通话时长（秒），无问诊时为空
+    pub average_consultation_duration: Option<f64>,
+    /// 已完成问诊的患者平均评分，无评分时为空
+    pub average_consultation_rating: Option<f64>,
+    /// 当月余额收入合计（元）
+    pub earnings: Decimal,
+    /// 当月新增的评价数
+    pub new_reviews: i64,
+    /// 当月新增评价的平均评分，无评价时为空
+    pub average_review_rating: Option<f64>,
+}
+
+/// 收益明细类型：订单支付记入收益，退款记入负数冲减
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DoctorEarningType {
+    Income,
+    RefundAdjustment,
+}
+
+/// 一条收益明细，金额单位为元
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DoctorEarning {
+    pub id: Uuid,
+    pub order_id: Uuid,
+    pub appointment_id: Uuid,
+    pub entry_type: DoctorEarningType,
+    pub gross_amount: Decimal,
+    /// 平台抽成比例（%）
+    pub commission_rate: Decimal,
+    pub commission_amount: Decimal,
+    /// 医生实得金额，冲减为负数
+    pub net_amount: Decimal,
+    /// 关联预约已完成即视为已结算
+    pub settled: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 按日汇总的收益
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DoctorDailyEarnings {
+    pub date: NaiveDate,
+    pub gross_amount: Decimal,
+    pub commission_amount: Decimal,
+    pub net_amount: Decimal,
+}
+
+/// 医生收益结算报表，汇总与明细均按查询的日期范围统计
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct DoctorEarningsReport {
+    /// 预约未完成、尚待结算的实得金额
+    pub pending_amount: Decimal,
+    /// 预约已完成、已结算的实得金额
+    pub settled_amount: Decimal,
+    pub daily: Vec<DoctorDailyEarnings>,
+    pub entries: Vec<DoctorEarning>,
+    pub total: i64,
+    pub page: i64,
+    pub page_size: i64,
+}
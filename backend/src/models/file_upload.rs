@@ -33,6 +33,9 @@ pub struct FileUpload {
     pub file_name: String,
     pub file_path: String,
     pub file_url: String,
+    /// 加水印前的原图地址，仅服务端保存
+    #[serde(skip_serializing)]
+    pub original_url: Option<String>,
     pub file_size: i64,
     pub mime_type: Option<String>,
     pub related_type: Option<String>,
@@ -91,6 +94,32 @@ pub struct UploadUrlResponse {
     pub expires_at: DateTime<Utc>,
 }
 
+/// 分片上传中单个分片的状态，未上传的分片附带预签名上传地址
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultipartUploadPart {
+    pub part_number: i32,
+    pub size: i64,
+    pub uploaded: bool,
+    pub etag: Option<String>,
+    pub upload_url: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MultipartUploadResponse {
+    pub upload_id: Uuid,
+    pub part_size: i64,
+    pub part_count: i32,
+    pub parts: Vec<MultipartUploadPart>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// 客户端上传分片后回报对象存储返回的 ETag
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct RecordUploadPartDto {
+    #[validate(length(min = 1, max = 255))]
+    pub etag: String,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct FileListQuery {
     pub user_id: Option<Uuid>,
@@ -126,6 +155,39 @@ pub struct TypeStats {
     pub total_size: i64,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UserStorageUsage {
+    pub user_id: Uuid,
+    pub user_name: String,
+    pub total_files: i64,
+    pub total_size: i64,
+    pub by_type: Vec<TypeStats>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TopStorageUsersQuery {
+    pub limit: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BatchDeleteFilesDto {
+    pub file_ids: Vec<Uuid>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum FileDeleteOutcome {
+    Deleted,
+    Forbidden,
+    NotFound,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FileDeleteResult {
+    pub file_id: Uuid,
+    pub outcome: FileDeleteOutcome,
+}
+
 // Configuration DTOs
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UploadConfig {
@@ -139,12 +201,16 @@ pub struct UploadConfig {
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ImageConfig {
+    pub max_file_size: i64,
     pub max_width: i32,
     pub max_height: i32,
     pub thumbnail_width: i32,
     pub thumbnail_height: i32,
     pub compression_quality: u8,
     pub allowed_formats: Vec<String>,
+    pub enable_watermark: bool,
+    pub watermark_text: String,
+    pub keep_original: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
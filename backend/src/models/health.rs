@@ -0,0 +1,43 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyCheck {
+    pub status: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl DependencyCheck {
+    pub fn ok() -> Self {
+        Self {
+            status: "ok".to_string(),
+            error: None,
+        }
+    }
+
+    pub fn disabled() -> Self {
+        Self {
+            status: "disabled".to_string(),
+            error: None,
+        }
+    }
+
+    pub fn failed(error: impl Into<String>) -> Self {
+        Self {
+            status: "error".to_string(),
+            error: Some(error.into()),
+        }
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.status != "error"
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReadinessReport {
+    pub ready: bool,
+    pub database: DependencyCheck,
+    pub redis: DependencyCheck,
+    pub migrations: DependencyCheck,
+}
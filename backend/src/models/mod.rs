@@ -1,12 +1,16 @@
+use crate::services::payment_metrics_service::PaymentMetricsSnapshot;
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 pub mod appointment;
+pub mod broadcast;
 pub mod circle;
 pub mod circle_post;
 pub mod content;
 pub mod department;
 pub mod doctor;
 pub mod file_upload;
+pub mod health;
 pub mod live_stream;
 pub mod notification;
 pub mod patient_group;
@@ -18,14 +22,17 @@ pub mod statistics;
 pub mod template;
 pub mod user;
 pub mod video_consultation;
+pub mod withdrawal;
 
 pub use appointment::*;
+pub use broadcast::*;
 pub use circle::*;
 pub use circle_post::*;
 pub use content::*;
 pub use department::*;
 pub use doctor::*;
 pub use file_upload::*;
+pub use health::*;
 pub use live_stream::*;
 pub use notification::*;
 pub use patient_group::*;
@@ -37,8 +44,60 @@ pub use statistics::*;
 pub use template::*;
 pub use user::*;
 pub use video_consultation::*;
+pub use withdrawal::*;
 
-#[derive(Debug, Serialize, Deserialize)]
+/// 统一响应结构，`data` 在失败时为 null
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[aliases(
+    ApiResponseUser = ApiResponse<User>,
+    ApiResponseLogin = ApiResponse<LoginResponse>,
+    ApiResponseAppointment = ApiResponse<Appointment>,
+    ApiResponseAppointmentDetail = ApiResponse<AppointmentDetail>,
+    ApiResponseAppointmentList = ApiResponse<Vec<Appointment>>,
+    ApiResponseAppointmentSeries = ApiResponse<AppointmentSeriesResult>,
+    ApiResponseAppointmentPatient = ApiResponse<AppointmentPatientInfo>,
+    ApiResponseDoctorSchedule = ApiResponse<Vec<DoctorScheduleItem>>,
+    ApiResponseTimeSlots = ApiResponse<Vec<String>>,
+    ApiResponseDoctor = ApiResponse<Doctor>,
+    ApiResponseDoctorList = ApiResponse<Vec<Doctor>>,
+    ApiResponseTreatingDoctorList = ApiResponse<Vec<TreatingDoctor>>,
+    ApiResponseDoctorOutOfOffice = ApiResponse<DoctorOutOfOffice>,
+    ApiResponseDoctorMonthlyReport = ApiResponse<DoctorMonthlyReport>,
+    ApiResponseDoctorEarningsReport = ApiResponse<DoctorEarningsReport>,
+    ApiResponseOrder = ApiResponse<PaymentOrder>,
+    ApiResponseOrderList = ApiResponse<OrderListResponse>,
+    ApiResponseOrderWithTransactions = ApiResponse<OrderWithTransactions>,
+    ApiResponseTransactionList = ApiResponse<TransactionListResponse>,
+    ApiResponseOrderQuote = ApiResponse<OrderQuote>,
+    ApiResponseCallbackVerification = ApiResponse<CallbackVerification>,
+    ApiResponsePayment = ApiResponse<PaymentResponse>,
+    ApiResponseOrderPaymentStatus = ApiResponse<OrderPaymentStatus>,
+    ApiResponseReconciliationReport = ApiResponse<ReconciliationReport>,
+    ApiResponseRefund = ApiResponse<RefundRecord>,
+    ApiResponseBalance = ApiResponse<UserBalance>,
+    ApiResponseBalanceTransactions = ApiResponse<Vec<BalanceTransaction>>,
+    ApiResponseBalanceWithdrawal = ApiResponse<BalanceWithdrawal>,
+    ApiResponseBalanceWithdrawalList = ApiResponse<BalanceWithdrawalListResponse>,
+    ApiResponsePriceConfig = ApiResponse<PriceConfig>,
+    ApiResponsePriceConfigList = ApiResponse<Vec<PriceConfig>>,
+    ApiResponseCoupon = ApiResponse<Coupon>,
+    ApiResponseCouponList = ApiResponse<Vec<Coupon>>,
+    ApiResponseCouponPreview = ApiResponse<CouponPreview>,
+    ApiResponsePaymentStatistics = ApiResponse<PaymentStatistics>,
+    ApiResponseUserPaymentSummary = ApiResponse<UserPaymentSummary>,
+    ApiResponsePaymentConfigHistory = ApiResponse<Vec<PaymentConfigHistory>>,
+    ApiResponsePaymentMetrics = ApiResponse<PaymentMetricsSnapshot>,
+    ApiResponseArticle = ApiResponse<Article>,
+    ApiResponseArticleList = ApiResponse<Vec<ArticleListItem>>,
+    ApiResponseArticleLikeStatus = ApiResponse<ArticleLikeStatus>,
+    ApiResponseVideo = ApiResponse<Video>,
+    ApiResponseVideoList = ApiResponse<Vec<VideoListItem>>,
+    ApiResponseCategory = ApiResponse<ContentCategory>,
+    ApiResponseCategoryList = ApiResponse<Vec<ContentCategory>>,
+    ApiResponseRecommendedFeed = ApiResponse<RecommendedFeed>,
+    ApiResponseDiagnosisMapping = ApiResponse<DiagnosisCategoryMapping>,
+    ApiResponseDiagnosisMappingList = ApiResponse<Vec<DiagnosisCategoryMapping>>
+)]
 pub struct ApiResponse<T> {
     pub success: bool,
     pub message: String,
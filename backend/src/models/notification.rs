@@ -18,6 +18,7 @@ pub enum NotificationType {
     ReviewReply,
     LiveStreamReminder,
     GroupMessage,
+    PaymentUpdate,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
@@ -161,6 +162,27 @@ pub struct RegisterPushTokenDto {
     pub device_info: Option<serde_json::Value>,
 }
 
+/// 默认通知设置模板中的单个通知类型配置，新用户注册时按模板初始化
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct NotificationChannelDefaults {
+    pub enabled: bool,
+    pub email_enabled: bool,
+    pub sms_enabled: bool,
+    pub push_enabled: bool,
+}
+
+impl Default for NotificationChannelDefaults {
+    // 未配置模板时：启用通知和推送，禁用邮件和短信
+    fn default() -> Self {
+        NotificationChannelDefaults {
+            enabled: true,
+            email_enabled: false,
+            sms_enabled: false,
+            push_enabled: true,
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NotificationStats {
     pub total_count: i64,
@@ -185,6 +207,21 @@ impl From<Notification> for NotificationResponse {
     }
 }
 
+impl NotificationType {
+    pub const ALL: [NotificationType; 10] = [
+        NotificationType::AppointmentReminder,
+        NotificationType::AppointmentConfirmed,
+        NotificationType::AppointmentCancelled,
+        NotificationType::PrescriptionReady,
+        NotificationType::DoctorReply,
+        NotificationType::SystemAnnouncement,
+        NotificationType::ReviewReply,
+        NotificationType::LiveStreamReminder,
+        NotificationType::GroupMessage,
+        NotificationType::PaymentUpdate,
+    ];
+}
+
 impl fmt::Display for NotificationType {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -197,6 +234,7 @@ impl fmt::Display for NotificationType {
             NotificationType::ReviewReply => write!(f, "review_reply"),
             NotificationType::LiveStreamReminder => write!(f, "live_stream_reminder"),
             NotificationType::GroupMessage => write!(f, "group_message"),
+            NotificationType::PaymentUpdate => write!(f, "payment_update"),
         }
     }
 }
@@ -1,5 +1,6 @@
 use chrono::{DateTime, NaiveDate, Utc};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 use uuid::Uuid;
 use validator::Validate;
 
@@ -8,7 +9,7 @@ pub struct PatientProfile {
     pub id: Uuid,
     pub user_id: Uuid,
     pub name: String,
-    pub id_number: String,
+    pub id_number: Option<String>,
     pub phone: String,
     pub gender: Gender,
     pub birthday: Option<NaiveDate>,
@@ -18,7 +19,7 @@ pub struct PatientProfile {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Gender {
     #[serde(rename = "男")]
@@ -27,7 +28,7 @@ pub enum Gender {
     Female,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
 #[serde(rename_all = "lowercase")]
 pub enum Relationship {
     #[serde(rename = "self")]
@@ -41,8 +42,9 @@ pub enum Relationship {
 pub struct CreatePatientProfileDto {
     #[validate(length(min = 2, max = 50))]
     pub name: String,
+    /// 身份证号，为家人代管的档案可不填写
     #[validate(length(min = 15, max = 18))]
-    pub id_number: String,
+    pub id_number: Option<String>,
     #[validate(length(min = 11, max = 11))]
     pub phone: String,
     pub gender: Gender,
@@ -61,6 +63,73 @@ pub struct UpdatePatientProfileDto {
     pub relationship: Option<Relationship>,
 }
 
+/// 患者保存的上门问诊地址
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatientAddress {
+    pub id: Uuid,
+    pub user_id: Uuid,
+    pub recipient_name: String,
+    pub phone: String,
+    pub province: String,
+    pub city: String,
+    pub district: String,
+    pub detail: String,
+    pub is_default: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreatePatientAddressDto {
+    #[validate(length(min = 2, max = 50))]
+    pub recipient_name: String,
+    #[validate(length(min = 11, max = 11))]
+    pub phone: String,
+    #[validate(length(min = 1, max = 50))]
+    pub province: String,
+    #[validate(length(min = 1, max = 50))]
+    pub city: String,
+    #[validate(length(min = 1, max = 50))]
+    pub district: String,
+    #[validate(length(min = 1, max = 200))]
+    pub detail: String,
+    /// 设为默认地址；第一个地址总是默认地址
+    #[serde(default)]
+    pub is_default: bool,
+}
+
+/// 医生查看预约时的就诊人信息；代管档案附带管理账号的联系方式
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct AppointmentPatientInfo {
+    pub appointment_id: Uuid,
+    pub patient_profile_id: Option<Uuid>,
+    pub name: String,
+    pub gender: Option<Gender>,
+    pub birthday: Option<NaiveDate>,
+    pub relationship: Relationship,
+    /// 是否为他人代管的档案（非账号本人）
+    pub is_managed: bool,
+    pub managing_account: ManagingAccount,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ManagingAccount {
+    pub user_id: Uuid,
+    pub name: String,
+    pub phone: String,
+}
+
+/// 出生日期不能晚于今天，也不能早于 150 年前
+pub fn validate_birthday(birthday: NaiveDate) -> bool {
+    let today = Utc::now().date_naive();
+    birthday <= today && today.years_since(birthday).is_some_and(|age| age <= 150)
+}
+
+/// 手机号为 1 开头的 11 位数字
+pub fn validate_phone(phone: &str) -> bool {
+    phone.len() == 11 && phone.starts_with('1') && phone.chars().all(|c| c.is_ascii_digit())
+}
+
 // Helper function to validate Chinese ID card number
 pub fn validate_id_number(id_number: &str) -> bool {
     // Basic validation - should be 15 or 18 characters
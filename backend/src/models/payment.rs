@@ -2,16 +2,19 @@ use chrono::{DateTime, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 use validator::Validate;
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "order_type", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum OrderType {
     Appointment,
     Consultation,
     Prescription,
+    Recharge,
     Other,
 }
 
@@ -21,12 +24,13 @@ impl std::fmt::Display for OrderType {
             OrderType::Appointment => write!(f, "Appointment"),
             OrderType::Consultation => write!(f, "Consultation"),
             OrderType::Prescription => write!(f, "Prescription"),
+            OrderType::Recharge => write!(f, "Recharge"),
             OrderType::Other => write!(f, "Other"),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq, ToSchema)]
 #[sqlx(type_name = "order_status", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum OrderStatus {
@@ -38,7 +42,7 @@ pub enum OrderStatus {
     Expired,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "payment_method", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum PaymentMethod {
@@ -48,7 +52,16 @@ pub enum PaymentMethod {
     Balance,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
+/// 预约订单余额支付的托管状态：支付时冻结，就诊完成结算，就诊前取消解冻退回
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum BalanceHoldStatus {
+    Held,
+    Settled,
+    Released,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "transaction_type", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum TransactionType {
@@ -56,7 +69,7 @@ pub enum TransactionType {
     Refund,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq, ToSchema)]
 #[sqlx(type_name = "transaction_status", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum TransactionStatus {
@@ -65,7 +78,7 @@ pub enum TransactionStatus {
     Failed,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq, ToSchema)]
 #[sqlx(type_name = "refund_status", rename_all = "lowercase")]
 #[serde(rename_all = "lowercase")]
 pub enum RefundStatus {
@@ -76,7 +89,7 @@ pub enum RefundStatus {
     Cancelled,
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, ToSchema)]
 #[sqlx(type_name = "balance_transaction_type", rename_all = "snake_case")]
 #[serde(rename_all = "snake_case")]
 pub enum BalanceTransactionType {
@@ -86,7 +99,7 @@ pub enum BalanceTransactionType {
     Unfreeze,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct PaymentOrder {
     pub id: Uuid,
     pub order_no: String,
@@ -101,23 +114,132 @@ pub struct PaymentOrder {
     pub expire_time: DateTime<Utc>,
     pub description: Option<String>,
     pub metadata: Option<serde_json::Value>,
+    pub coupon_id: Option<Uuid>,
+    /// 优惠券抵扣金额，`amount` 为抵扣后的应付金额
+    pub discount_amount: Decimal,
+    /// 优惠前金额，等于 amount + discount_amount
+    pub original_amount: Decimal,
+    /// 余额支付的预约订单的托管状态，其他订单为空
+    pub balance_hold_status: Option<BalanceHoldStatus>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateOrderDto {
     pub user_id: Uuid,
+    /// 关联的预约ID（预约类订单）
     pub appointment_id: Option<Uuid>,
     pub order_type: OrderType,
     // TODO: Add custom validation for Decimal
+    /// 订单金额（元）
     pub amount: Decimal,
     #[validate(length(max = 500))]
     pub description: Option<String>,
     pub metadata: Option<serde_json::Value>,
+    /// 优惠码，抵扣后的金额仍需满足订单金额下限
+    #[serde(default)]
+    #[validate(length(max = 50))]
+    pub coupon_code: Option<String>,
+    /// 客户端生成的幂等键，24 小时内同一用户重复提交会返回已创建的订单
+    #[serde(default)]
+    #[validate(length(min = 1, max = 64))]
+    pub idempotency_key: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum CouponDiscountType {
+    /// 固定金额抵扣
+    Fixed,
+    /// 按订单金额比例抵扣
+    Percent,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Coupon {
+    pub id: Uuid,
+    pub code: String,
+    pub discount_type: CouponDiscountType,
+    /// 固定金额券的抵扣金额（元）
+    pub discount_amount: Decimal,
+    /// 按比例券的折扣比例（%），如 15 表示减免 15%
+    pub discount_percent: Option<Decimal>,
+    /// 订单金额达到该值才可使用
+    pub min_order_amount: Decimal,
+    pub usage_limit: i32,
+    pub used: i32,
+    pub is_active: bool,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateCouponDto {
+    #[validate(length(min = 1, max = 50))]
+    pub code: String,
+    pub discount_type: CouponDiscountType,
+    /// 固定金额券必填
+    pub discount_amount: Option<Decimal>,
+    /// 按比例券必填，取值 (0, 100]
+    pub discount_percent: Option<Decimal>,
+    pub min_order_amount: Option<Decimal>,
+    #[validate(range(min = 1))]
+    pub usage_limit: i32,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// 修改优惠券，未提供的字段保持不变；优惠方式创建后不可修改
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdateCouponDto {
+    pub discount_amount: Option<Decimal>,
+    pub discount_percent: Option<Decimal>,
+    pub min_order_amount: Option<Decimal>,
+    /// 不能低于已使用次数
+    #[validate(range(min = 1))]
+    pub usage_limit: Option<i32>,
+    pub is_active: Option<bool>,
+    pub starts_at: Option<DateTime<Utc>>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// 下单前试算优惠券，不会核销
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ValidateCouponDto {
+    #[validate(length(min = 1, max = 50))]
+    pub code: String,
+    pub order_type: OrderType,
+    /// 优惠前的订单金额（元）
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CouponPreview {
+    pub coupon_id: Uuid,
+    pub code: String,
+    pub original_amount: Decimal,
+    pub discount_amount: Decimal,
+    /// 抵扣后的应付金额
+    pub final_amount: Decimal,
+}
+
+/// 下单前的价格预览，与创建订单走相同的金额校验，不会落库
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OrderQuote {
+    pub order_type: OrderType,
+    /// 应付金额（元），即创建订单时写入的金额
+    pub amount: Decimal,
+    pub currency: String,
+    /// 该订单类型允许的金额下限（元）
+    pub min_amount: Decimal,
+    /// 该订单类型允许的金额上限（元）
+    pub max_amount: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct PaymentTransaction {
     pub id: Uuid,
     pub transaction_no: String,
@@ -138,15 +260,41 @@ pub struct PaymentTransaction {
     pub completed_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+/// 余额充值，仅支持微信或支付宝支付
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct RechargeBalanceDto {
+    /// 充值金额（元），需在 `recharge_min` 与 `recharge_max` 价格配置之间
+    pub amount: Decimal,
+    pub payment_method: PaymentMethod,
+    /// 支付完成后的跳转地址（网页支付）
+    #[validate(length(max = 100))]
+    pub return_url: Option<String>,
+    /// 微信 JSAPI 支付的付款人 openid，不传时返回 Native 扫码链接
+    #[serde(default)]
+    #[validate(length(max = 128))]
+    pub openid: Option<String>,
+    /// 支付宝使用手机网站支付（alipay.trade.wap.pay），默认电脑网站支付
+    #[serde(default)]
+    pub wap: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct InitiatePaymentDto {
     pub order_id: Uuid,
     pub payment_method: PaymentMethod,
+    /// 支付完成后的跳转地址（网页支付）
     #[validate(length(max = 100))]
     pub return_url: Option<String>,
+    /// 微信 JSAPI 支付的付款人 openid，不传时返回 Native 扫码链接
+    #[serde(default)]
+    #[validate(length(max = 128))]
+    pub openid: Option<String>,
+    /// 支付宝使用手机网站支付（alipay.trade.wap.pay），默认电脑网站支付
+    #[serde(default)]
+    pub wap: bool,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct RefundRecord {
     pub id: Uuid,
     pub refund_no: String,
@@ -159,6 +307,8 @@ pub struct RefundRecord {
     pub reviewed_by: Option<Uuid>,
     pub reviewed_at: Option<DateTime<Utc>>,
     pub review_notes: Option<String>,
+    /// 渠道退款失败的原因
+    pub failure_reason: Option<String>,
     pub external_refund_id: Option<String>,
     pub refund_response: Option<serde_json::Value>,
     pub created_at: DateTime<Utc>,
@@ -166,7 +316,7 @@ pub struct RefundRecord {
     pub completed_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct CreateRefundDto {
     pub order_id: Uuid,
     // TODO: Add custom validation for Decimal
@@ -175,13 +325,53 @@ pub struct CreateRefundDto {
     pub refund_reason: String,
 }
 
-#[derive(Debug, Serialize, Deserialize, Validate)]
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
 pub struct ReviewRefundDto {
     pub approved: bool,
     #[validate(length(max = 500))]
     pub review_notes: Option<String>,
 }
 
+/// 管理员手工调整订单状态，用于处理支付网关异常导致的卡单
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct AdjustOrderStatusDto {
+    /// 目标状态：paid、cancelled、expired 或 refunded
+    pub status: OrderStatus,
+    /// 调整原因，写入审计日志
+    #[validate(length(min = 1, max = 500))]
+    pub reason: String,
+}
+
+/// 重新校验订单已保存的支付回调
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct VerifyCallbackDto {
+    /// 为 true 时，若回调有效且表示支付成功，则将订单标记为已支付；默认只校验不修改
+    #[serde(default)]
+    pub apply: bool,
+}
+
+/// 支付回调校验结果
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct CallbackVerification {
+    pub order_id: Uuid,
+    pub order_no: String,
+    /// 保存该回调的支付流水 ID
+    pub transaction_id: Uuid,
+    pub payment_method: PaymentMethod,
+    /// 回调签名有效，且订单号、金额、交易号均与记录一致
+    pub valid: bool,
+    /// 校验未通过的原因
+    pub issues: Vec<String>,
+    /// 回调中的支付结果：success 或 failed，无法解析时为空
+    pub callback_status: Option<String>,
+    /// 校验时订单的状态
+    pub order_status: OrderStatus,
+    /// 重新应用该回调是否会将订单标记为已支付
+    pub would_mark_paid: bool,
+    /// 本次请求是否已将订单标记为已支付
+    pub applied: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct PaymentConfig {
     pub id: Uuid,
@@ -194,7 +384,36 @@ pub struct PaymentConfig {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct UpdatePaymentConfigDto {
+    pub config_key: String,
+    pub config_value: String,
+    pub is_encrypted: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
+pub struct PaymentConfigHistory {
+    pub id: Uuid,
+    pub payment_method: PaymentMethod,
+    pub config_key: String,
+    pub action: String,
+    pub old_value: Option<String>,
+    pub new_value: Option<String>,
+    pub is_encrypted: bool,
+    pub changed_by: Uuid,
+    pub changed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PaymentConfigHistoryQuery {
+    pub payment_method: Option<String>,
+    pub config_key: Option<String>,
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct PriceConfig {
     pub id: Uuid,
     pub service_type: String,
@@ -209,7 +428,36 @@ pub struct PriceConfig {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+/// 新建价格配置，同一服务类型的有效期不能与其他启用中的配置重叠
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreatePriceConfigDto {
+    #[validate(length(min = 1, max = 50))]
+    pub service_type: String,
+    #[validate(length(min = 1, max = 100))]
+    pub service_name: String,
+    pub price: Decimal,
+    /// 不能高于原价
+    pub discount_price: Option<Decimal>,
+    /// 为空表示立即生效
+    pub effective_date: Option<NaiveDate>,
+    /// 为空表示长期有效
+    pub expiry_date: Option<NaiveDate>,
+    pub description: Option<String>,
+}
+
+/// 修改价格配置，未提供的字段保持不变
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct UpdatePriceConfigDto {
+    #[validate(length(min = 1, max = 100))]
+    pub service_name: Option<String>,
+    pub price: Option<Decimal>,
+    pub discount_price: Option<Decimal>,
+    pub effective_date: Option<NaiveDate>,
+    pub expiry_date: Option<NaiveDate>,
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct UserBalance {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -221,7 +469,7 @@ pub struct UserBalance {
     pub updated_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize, FromRow)]
+#[derive(Debug, Serialize, Deserialize, FromRow, ToSchema)]
 pub struct BalanceTransaction {
     pub id: Uuid,
     pub user_id: Uuid,
@@ -235,7 +483,70 @@ pub struct BalanceTransaction {
     pub created_at: DateTime<Utc>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq, ToSchema)]
+#[sqlx(type_name = "balance_withdrawal_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum BalanceWithdrawalStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+/// 余额提现申请，待审核期间提现金额处于冻结状态
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BalanceWithdrawal {
+    pub id: Uuid,
+    pub withdrawal_no: String,
+    pub user_id: Uuid,
+    pub amount: Decimal,
+    pub payout_method: PaymentMethod,
+    pub payout_account: String,
+    pub status: BalanceWithdrawalStatus,
+    pub reviewed_by: Option<Uuid>,
+    pub reviewed_at: Option<DateTime<Utc>>,
+    pub review_notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct CreateBalanceWithdrawalDto {
+    /// 提现金额（元），不能超过可用余额
+    pub amount: Decimal,
+    /// 到账方式：wechat 或 alipay
+    pub payout_method: PaymentMethod,
+    /// 到账账户（微信号或支付宝账号）
+    #[validate(length(min = 1, max = 100))]
+    pub payout_account: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate, ToSchema)]
+pub struct ReviewBalanceWithdrawalDto {
+    /// 通过则从冻结余额中扣除，驳回则解冻退回可用余额
+    pub approved: bool,
+    #[validate(length(max = 500))]
+    pub review_notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BalanceWithdrawalQuery {
+    /// 仅管理员可按用户筛选，其他用户只能查看本人的申请
+    pub user_id: Option<Uuid>,
+    pub status: Option<BalanceWithdrawalStatus>,
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct BalanceWithdrawalListResponse {
+    pub withdrawals: Vec<BalanceWithdrawal>,
+    pub total: i64,
+    pub page: i64,
+    pub page_size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PaymentResponse {
     pub order_id: Uuid,
     pub order_no: String,
@@ -245,6 +556,18 @@ pub struct PaymentResponse {
     pub prepay_data: Option<serde_json::Value>, // For SDK payments
 }
 
+/// 订单支付状态，待支付的渠道订单会先向支付渠道核实
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OrderPaymentStatus {
+    pub order_id: Uuid,
+    pub order_no: String,
+    pub status: OrderStatus,
+    pub payment_method: Option<PaymentMethod>,
+    pub payment_time: Option<DateTime<Utc>>,
+    /// 本次查询是否向支付渠道核实过
+    pub gateway_checked: bool,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct PaymentCallbackData {
     pub order_no: String,
@@ -255,7 +578,8 @@ pub struct PaymentCallbackData {
     pub raw_data: serde_json::Value,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct OrderListQuery {
     pub user_id: Option<Uuid>,
     pub status: Option<OrderStatus>,
@@ -266,7 +590,30 @@ pub struct OrderListQuery {
     pub page_size: Option<i64>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+/// 管理员查询支付/退款交易记录，时间范围按发起时间筛选
+#[derive(Debug, Serialize, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TransactionListQuery {
+    pub order_id: Option<Uuid>,
+    pub payment_method: Option<PaymentMethod>,
+    pub transaction_type: Option<TransactionType>,
+    pub status: Option<TransactionStatus>,
+    pub start_date: Option<DateTime<Utc>>,
+    pub end_date: Option<DateTime<Utc>>,
+    pub page: Option<i64>,
+    /// 每页条数，默认 20，最大 100
+    pub page_size: Option<i64>,
+}
+
+/// 订单及其全部支付/退款交易记录（客服按第三方交易号查单使用）
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct OrderWithTransactions {
+    #[serde(flatten)]
+    pub order: PaymentOrder,
+    pub transactions: Vec<PaymentTransaction>,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct OrderListResponse {
     pub orders: Vec<PaymentOrder>,
     pub total: i64,
@@ -274,7 +621,15 @@ pub struct OrderListResponse {
     pub page_size: i64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TransactionListResponse {
+    pub transactions: Vec<PaymentTransaction>,
+    pub total: i64,
+    pub page: i64,
+    pub page_size: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
 pub struct PaymentStatistics {
     pub total_orders: i64,
     pub total_amount: Decimal,
@@ -282,6 +637,146 @@ pub struct PaymentStatistics {
     pub paid_amount: Decimal,
     pub refunded_orders: i64,
     pub refunded_amount: Decimal,
+    /// 指定 group_by 时按周期拆分的明细
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub buckets: Option<Vec<PaymentStatisticsBucket>>,
+}
+
+/// 支付统计的分组周期，周以周一为起点
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum StatisticsGroupBy {
+    Day,
+    Week,
+    Month,
+}
+
+impl StatisticsGroupBy {
+    /// 将 created_at 归并到周期起始日期（YYYY-MM-DD）的 SQL 表达式
+    pub fn period_expr(&self) -> &'static str {
+        match self {
+            StatisticsGroupBy::Day => "DATE_FORMAT(created_at, '%Y-%m-%d')",
+            StatisticsGroupBy::Week => {
+                "DATE_FORMAT(DATE_SUB(created_at, INTERVAL WEEKDAY(created_at) DAY), '%Y-%m-%d')"
+            }
+            StatisticsGroupBy::Month => "DATE_FORMAT(created_at, '%Y-%m-01')",
+        }
+    }
+}
+
+/// 单个统计周期的订单数与金额
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct PaymentStatisticsBucket {
+    pub period_start: NaiveDate,
+    pub total_orders: i64,
+    pub paid_orders: i64,
+    pub paid_amount: Decimal,
+    pub refunded_orders: i64,
+    pub refunded_amount: Decimal,
+    /// 已支付订单按支付方式汇总的金额
+    pub method_amounts: BTreeMap<String, Decimal>,
+}
+
+/// 用户支付概览：累计支付、累计退款与当前余额
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct UserPaymentSummary {
+    pub user_id: Uuid,
+    /// 已支付过的订单金额合计（含之后退款的订单）
+    pub total_spent: Decimal,
+    /// 退款成功的金额合计
+    pub total_refunded: Decimal,
+    /// 无余额记录时为 0
+    pub balance: Decimal,
+    pub frozen_balance: Decimal,
+    pub statistics: PaymentStatistics,
+}
+
+/// 对账报告状态：一致、存在差异、账单获取失败
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReconciliationStatus {
+    Matched,
+    Mismatched,
+    Failed,
+}
+
+/// 参与对账的一笔支付交易，来自渠道账单或本地支付流水
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct BillEntry {
+    pub payment_method: PaymentMethod,
+    pub order_no: String,
+    /// 渠道交易号，本地流水未收到回调时可能为空
+    pub external_transaction_id: Option<String>,
+    pub amount: Decimal,
+}
+
+/// 本地与渠道金额不一致的交易
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct AmountMismatch {
+    pub payment_method: PaymentMethod,
+    pub order_no: String,
+    pub external_transaction_id: Option<String>,
+    pub local_amount: Decimal,
+    pub remote_amount: Decimal,
+}
+
+/// 账单比对结果
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct BillComparison {
+    /// 渠道账单中有、本地没有成功流水的交易
+    pub missing_local: Vec<BillEntry>,
+    /// 本地有成功流水、渠道账单中没有的交易
+    pub missing_remote: Vec<BillEntry>,
+    pub amount_mismatches: Vec<AmountMismatch>,
+}
+
+impl BillComparison {
+    pub fn is_balanced(&self) -> bool {
+        self.missing_local.is_empty()
+            && self.missing_remote.is_empty()
+            && self.amount_mismatches.is_empty()
+    }
+}
+
+/// 某一天的对账报告
+#[derive(Debug, Serialize, Deserialize, Clone, ToSchema)]
+pub struct ReconciliationReport {
+    pub id: Uuid,
+    pub bill_date: NaiveDate,
+    pub status: ReconciliationStatus,
+    pub local_count: i64,
+    pub local_amount: Decimal,
+    pub remote_count: i64,
+    pub remote_amount: Decimal,
+    pub missing_local: Vec<BillEntry>,
+    pub missing_remote: Vec<BillEntry>,
+    pub amount_mismatches: Vec<AmountMismatch>,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ReconciliationReport {
+    /// 差异笔数：本地缺失、渠道缺失与金额不一致之和
+    pub fn discrepancy_count(&self) -> u64 {
+        (self.missing_local.len() + self.missing_remote.len() + self.amount_mismatches.len()) as u64
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ReconciliationQuery {
+    /// 账单日期（北京时间），如 2024-01-22
+    pub date: NaiveDate,
+}
+
+/// 重新对账某一天。可直接提供渠道账单明细文本（如手工下载的支付宝账单），
+/// 未提供的渠道按已配置的商户信息自动下载
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RunReconciliationDto {
+    pub date: NaiveDate,
+    pub wechat_bill: Option<String>,
+    pub alipay_bill: Option<String>,
 }
 
 // WeChat Pay specific structures
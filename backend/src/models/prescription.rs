@@ -10,6 +10,7 @@ pub struct Prescription {
     pub code: String,
     pub doctor_id: Uuid,
     pub patient_id: Uuid,
+    pub patient_profile_id: Option<Uuid>,
     pub patient_name: String,
     pub diagnosis: String,
     pub medicines: Vec<Medicine>,
@@ -31,6 +32,9 @@ pub struct Medicine {
 pub struct CreatePrescriptionDto {
     pub doctor_id: Uuid,
     pub patient_id: Uuid,
+    /// 为家人开具处方时的就诊人档案，处方姓名以档案为准
+    #[serde(default)]
+    pub patient_profile_id: Option<Uuid>,
     pub patient_name: String,
     pub diagnosis: String,
     pub medicines: Vec<Medicine>,
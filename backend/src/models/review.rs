@@ -156,3 +156,22 @@ pub struct CreateTagDto {
 pub struct UpdateReviewVisibilityDto {
     pub is_visible: bool,
 }
+
+// 待回复评价排序方式
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum UnrepliedReviewSort {
+    #[default]
+    Oldest,
+    Newest,
+    LowestRating,
+    HighestRating,
+}
+
+// 待回复评价查询参数
+#[derive(Debug, Deserialize)]
+pub struct UnrepliedReviewQuery {
+    pub sort_by: Option<UnrepliedReviewSort>,
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+}
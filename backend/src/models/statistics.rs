@@ -161,3 +161,36 @@ pub struct HeatmapData {
     pub day_of_week: i32, // 0 = Sunday, 6 = Saturday
     pub count: i64,
 }
+
+/// 审计日志查询条件，所有条件均可选
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogQuery {
+    pub actor_id: Option<Uuid>,
+    pub action: Option<String>,
+    pub target_type: Option<String>,
+    pub start_date: Option<DateTime<Utc>>,
+    pub end_date: Option<DateTime<Utc>>,
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogEntry {
+    pub id: Uuid,
+    pub actor_id: Uuid,
+    /// 操作人姓名，用户已删除时为空
+    pub actor_name: Option<String>,
+    pub action: String,
+    pub target_type: String,
+    pub target_id: Uuid,
+    pub details: Option<serde_json::Value>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AuditLogListResponse {
+    pub logs: Vec<AuditLogEntry>,
+    pub total: i64,
+    pub page: i64,
+    pub page_size: i64,
+}
@@ -1,6 +1,7 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
+use std::collections::HashMap;
 use uuid::Uuid;
 use validator::Validate;
 
@@ -65,6 +66,8 @@ pub enum VideoEventType {
     RecordingEnd,
     NetworkPoor,
     NetworkRecovered,
+    Handover,
+    Overtime,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -73,12 +76,18 @@ pub struct VideoConsultation {
     pub appointment_id: Uuid,
     pub doctor_id: Uuid,
     pub patient_id: Uuid,
+    /// 就诊人档案ID，随预约带入
+    pub patient_profile_id: Option<Uuid>,
     pub room_id: String,
+    /// 6位短房间码，问诊结束后失效
+    pub room_code: Option<String>,
     pub status: ConsultationStatus,
     pub scheduled_start_time: DateTime<Utc>,
     pub actual_start_time: Option<DateTime<Utc>>,
     pub end_time: Option<DateTime<Utc>>,
     pub duration: Option<i32>,
+    /// 预计时长（分钟），取自医生设置或系统默认值
+    pub expected_duration: Option<i32>,
     pub doctor_token: Option<String>,
     pub patient_token: Option<String>,
     pub ice_servers: Option<serde_json::Value>,
@@ -126,6 +135,22 @@ pub struct CompleteConsultationDto {
     pub notes: Option<String>,
 }
 
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ReferToOfflineDto {
+    /// 转线下就诊的原因及建议
+    #[validate(length(min = 1, max = 1000))]
+    pub notes: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct HandoverConsultationDto {
+    /// 接手问诊的医生ID
+    pub to_doctor_id: Uuid,
+    /// 转交原因
+    #[validate(length(max = 500))]
+    pub reason: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Validate)]
 pub struct RateConsultationDto {
     #[validate(range(min = 1, max = 5))]
@@ -134,6 +159,148 @@ pub struct RateConsultationDto {
     pub feedback: Option<String>,
 }
 
+/// 医生对患者的反馈，仅医生和管理员可见
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SubmitPatientFeedbackDto {
+    #[validate(range(min = 1, max = 5))]
+    pub rating: i32,
+    #[validate(length(max = 1000))]
+    pub notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow)]
+pub struct PatientFeedback {
+    pub id: Uuid,
+    pub consultation_id: Uuid,
+    pub doctor_id: Uuid,
+    pub patient_id: Uuid,
+    pub rating: i32,
+    pub notes: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 患者可靠度，由医生反馈汇总而来，供医生接诊前参考
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatientReliability {
+    pub patient_id: Uuid,
+    pub feedback_count: i64,
+    pub average_rating: Option<f64>,
+    /// 评分不高于 2 的反馈次数
+    pub low_rating_count: i64,
+    /// 0-100，无反馈时为空
+    pub reliability_score: Option<i32>,
+}
+
+/// 问诊前问卷中的一道题，答案按 `key` 提交
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct QuestionnaireQuestion {
+    pub key: String,
+    pub question: String,
+    #[serde(default)]
+    pub required: bool,
+}
+
+/// 医生附加到问诊上的问诊前问卷，患者入会前填写
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsultationQuestionnaire {
+    pub id: Uuid,
+    pub consultation_id: Uuid,
+    pub doctor_id: Uuid,
+    pub title: String,
+    pub questions: Vec<QuestionnaireQuestion>,
+    /// 开启 video_call.require_questionnaire_before_start 时，未填写将无法开始问诊
+    pub is_required: bool,
+    pub answers: Option<HashMap<String, String>>,
+    pub submitted_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct AttachQuestionnaireDto {
+    #[validate(length(min = 1, max = 200))]
+    pub title: String,
+    #[validate(length(min = 1, max = 50))]
+    pub questions: Vec<QuestionnaireQuestion>,
+    /// 默认为必填
+    pub is_required: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SubmitQuestionnaireAnswersDto {
+    pub answers: HashMap<String, String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct SendConsultationMessageDto {
+    #[validate(length(min = 1, max = 2000))]
+    pub content: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsultationMessage {
+    pub id: Uuid,
+    pub consultation_id: Uuid,
+    pub sender_id: Uuid,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 问诊记录中的一条：聊天消息或通话事件
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    pub timestamp: DateTime<Utc>,
+    pub kind: String, // "message" or "event"
+    pub user_id: Uuid,
+    pub role: String, // "doctor", "patient" or "other"
+    pub content: Option<String>,
+    pub event_type: Option<String>,
+    pub event_data: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsultationTranscript {
+    pub consultation_id: Uuid,
+    pub doctor_id: Uuid,
+    pub patient_id: Uuid,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub entries: Vec<TranscriptEntry>,
+}
+
+impl ConsultationTranscript {
+    /// 纯文本格式，便于归档到病历
+    pub fn to_text(&self) -> String {
+        let mut text = format!("问诊记录 {}\n", self.consultation_id);
+        if let Some(started_at) = self.started_at {
+            text.push_str(&format!("开始时间: {}\n", started_at.to_rfc3339()));
+        }
+        if let Some(ended_at) = self.ended_at {
+            text.push_str(&format!("结束时间: {}\n", ended_at.to_rfc3339()));
+        }
+        text.push('\n');
+
+        for entry in &self.entries {
+            let detail = match entry.kind.as_str() {
+                "message" => entry.content.clone().unwrap_or_default(),
+                _ => format!("[{}]", entry.event_type.as_deref().unwrap_or("event")),
+            };
+            text.push_str(&format!(
+                "{} {}: {}\n",
+                entry.timestamp.to_rfc3339(),
+                entry.role,
+                detail
+            ));
+        }
+
+        text
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TranscriptQuery {
+    pub format: Option<String>, // "json" (default) or "text"
+}
+
 #[derive(Debug, Serialize, Deserialize, FromRow)]
 pub struct VideoRecording {
     pub id: Uuid,
@@ -170,6 +337,9 @@ pub struct SendSignalDto {
     pub to_user_id: Uuid,
     pub signal_type: SignalType,
     pub payload: serde_json::Value,
+    /// 加入房间时签发的房间令牌
+    #[validate(length(min = 1))]
+    pub token: String,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow)]
@@ -229,6 +399,20 @@ pub struct VideoConsultationResponse {
     pub can_join: bool,
 }
 
+/// 问诊详情，附带问诊前问卷及患者的答案
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConsultationDetail {
+    #[serde(flatten)]
+    pub consultation: VideoConsultation,
+    pub questionnaires: Vec<ConsultationQuestionnaire>,
+}
+
+/// 加入房间时可附带入会邀请中的房间令牌
+#[derive(Debug, Deserialize)]
+pub struct JoinRoomQuery {
+    pub token: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct JoinRoomResponse {
     pub room_id: String,
@@ -237,6 +421,24 @@ pub struct JoinRoomResponse {
     pub role: String, // "doctor" or "patient"
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoomCodeResolution {
+    pub consultation_id: Uuid,
+    pub room_id: String,
+    pub room_code: String,
+    pub status: ConsultationStatus,
+    pub scheduled_start_time: DateTime<Utc>,
+    pub join_url: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResendInviteResponse {
+    pub consultation_id: Uuid,
+    pub room_id: String,
+    pub join_url: String,
+    pub sent_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConsultationListQuery {
     pub doctor_id: Option<Uuid>,
@@ -248,11 +450,92 @@ pub struct ConsultationListQuery {
     pub page_size: Option<i64>,
 }
 
+/// 管理员问诊列表的筛选条件，各条件可任意组合
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct AdminConsultationFilters {
+    pub doctor_id: Option<Uuid>,
+    pub patient_id: Option<Uuid>,
+    pub status: Option<ConsultationStatus>,
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+    /// 患者评分下限（含），未评分的问诊不会匹配
+    pub min_rating: Option<i32>,
+    /// 患者评分上限（含），未评分的问诊不会匹配
+    pub max_rating: Option<i32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminConsultationListQuery {
+    pub doctor_id: Option<Uuid>,
+    pub patient_id: Option<Uuid>,
+    pub status: Option<ConsultationStatus>,
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+    pub min_rating: Option<i32>,
+    pub max_rating: Option<i32>,
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+}
+
+/// 管理员问诊列表中的一行，附带医生、科室和患者信息
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminConsultationListItem {
+    #[serde(flatten)]
+    pub consultation: VideoConsultation,
+    pub doctor_name: Option<String>,
+    pub department: Option<String>,
+    pub patient_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AdminConsultationListPage {
+    pub items: Vec<AdminConsultationListItem>,
+    pub total: i64,
+    pub page: i64,
+    pub page_size: i64,
+}
+
+/// 医生待处理的问诊：候诊中的，以及已结束但诊断或治疗方案未填写的
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DoctorActionItems {
+    pub waiting: Vec<VideoConsultation>,
+    pub missing_records: Vec<VideoConsultation>,
+}
+
+/// 医生名下单个患者的问诊汇总
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatientConsultationSummary {
+    pub patient_id: Uuid,
+    pub patient_name: Option<String>,
+    pub consultation_count: i64,
+    pub completed_count: i64,
+    pub last_visit_at: Option<DateTime<Utc>>,
+    pub average_rating: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatientConsultationSummaryQuery {
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PatientConsultationSummaryPage {
+    pub items: Vec<PatientConsultationSummary>,
+    pub total: i64,
+    pub page: i64,
+    pub page_size: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ConsultationStatistics {
     pub total_consultations: i64,
     pub completed_consultations: i64,
     pub average_duration: Option<f64>,
+    /// 已完成问诊的平均预计时长（秒），与 average_duration 对比
+    pub average_expected_duration: Option<f64>,
+    /// 实际时长超过预计时长的已完成问诊数
+    pub overtime_count: i64,
     pub average_rating: Option<f64>,
     pub no_show_rate: f64,
 }
@@ -0,0 +1,100 @@
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+use validator::Validate;
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "payout_account_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum PayoutAccountStatus {
+    Pending,
+    Verified,
+    Rejected,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, sqlx::Type, PartialEq)]
+#[sqlx(type_name = "withdrawal_status", rename_all = "lowercase")]
+#[serde(rename_all = "lowercase")]
+pub enum WithdrawalStatus {
+    Pending,
+    Paid,
+    Rejected,
+}
+
+/// 医生收款账户，账号密文不对外输出
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PayoutAccount {
+    pub id: Uuid,
+    pub doctor_id: Uuid,
+    pub bank_name: String,
+    pub account_holder: String,
+    pub masked_account_number: String,
+    pub verification_status: PayoutAccountStatus,
+    pub verified_by: Option<Uuid>,
+    pub verified_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct CreatePayoutAccountDto {
+    #[validate(length(min = 1, max = 100))]
+    pub bank_name: String,
+    #[validate(length(min = 1, max = 100))]
+    pub account_holder: String,
+    #[validate(length(min = 8, max = 32))]
+    pub account_number: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyPayoutAccountDto {
+    pub approved: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DoctorWithdrawal {
+    pub id: Uuid,
+    pub withdrawal_no: String,
+    pub doctor_id: Uuid,
+    pub payout_account_id: Uuid,
+    pub amount: Decimal,
+    pub status: WithdrawalStatus,
+    pub transfer_reference: Option<String>,
+    pub reject_reason: Option<String>,
+    pub processed_by: Option<Uuid>,
+    pub processed_at: Option<DateTime<Utc>>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateWithdrawalDto {
+    pub payout_account_id: Uuid,
+    pub amount: Decimal,
+}
+
+#[derive(Debug, Serialize, Deserialize, Validate)]
+pub struct ProcessWithdrawalDto {
+    pub approved: bool,
+    #[validate(length(min = 1, max = 100))]
+    pub transfer_reference: Option<String>,
+    #[validate(length(max = 500))]
+    pub reject_reason: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WithdrawalListQuery {
+    pub status: Option<String>,
+    pub page: Option<i64>,
+    pub page_size: Option<i64>,
+}
+
+/// 医生收益概览
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DoctorEarningsSummary {
+    pub settled_earnings: Decimal,
+    pub frozen_amount: Decimal,
+    pub withdrawn_amount: Decimal,
+    pub available_amount: Decimal,
+}
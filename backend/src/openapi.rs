@@ -0,0 +1,336 @@
+use crate::controllers::{
+    appointment_controller, auth_controller, content_controller, doctor_controller,
+    payment_controller,
+};
+use crate::models::*;
+use crate::services::payment_metrics_service::{
+    PaymentAlertConfig, PaymentMethodCounters, PaymentMetricsSnapshot,
+};
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+/// 不携带数据的响应；所有错误响应（400/401/403/404/500）同样使用该结构
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct ApiMessage {
+    /// 成功为 true，失败为 false
+    pub success: bool,
+    /// 提示信息或错误原因
+    pub message: String,
+    /// 认证失败时的错误码：TOKEN_EXPIRED 需刷新令牌，INVALID_TOKEN 需重新登录
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "TCM Telemedicine Platform API",
+        description = "中医远程医疗平台接口文档。需要登录的接口在请求头中携带 `Authorization: Bearer <token>`。"
+    ),
+    paths(
+        auth_controller::register,
+        auth_controller::login,
+        auth_controller::logout,
+        appointment_controller::list_appointments,
+        appointment_controller::get_appointment,
+        appointment_controller::create_appointment,
+        appointment_controller::create_appointment_series,
+        appointment_controller::cancel_appointment_series,
+        appointment_controller::update_appointment,
+        appointment_controller::cancel_appointment,
+        appointment_controller::transfer_appointment,
+        appointment_controller::update_private_notes,
+        appointment_controller::get_appointment_patient,
+        appointment_controller::get_doctor_appointments,
+        appointment_controller::get_doctor_schedule,
+        appointment_controller::get_patient_appointments,
+        appointment_controller::get_available_slots,
+        doctor_controller::list_doctors,
+        doctor_controller::get_doctor,
+        doctor_controller::get_doctor_by_user_id,
+        doctor_controller::get_treating_doctors,
+        doctor_controller::get_my_monthly_report,
+        doctor_controller::get_my_earnings,
+        doctor_controller::create_doctor,
+        doctor_controller::update_doctor,
+        doctor_controller::update_doctor_photos,
+        doctor_controller::set_out_of_office,
+        doctor_controller::clear_out_of_office,
+        payment_controller::create_order,
+        payment_controller::quote_order,
+        payment_controller::get_order,
+        payment_controller::get_order_status,
+        payment_controller::list_orders,
+        payment_controller::cancel_order,
+        payment_controller::initiate_payment,
+        payment_controller::list_transactions,
+        payment_controller::payment_callback,
+        payment_controller::refund_callback,
+        payment_controller::create_refund,
+        payment_controller::get_refund,
+        payment_controller::review_refund,
+        payment_controller::retry_refund,
+        payment_controller::adjust_order_status,
+        payment_controller::verify_order_callback,
+        payment_controller::get_order_by_external_transaction,
+        payment_controller::get_reconciliation_report,
+        payment_controller::run_reconciliation,
+        payment_controller::get_user_balance,
+        payment_controller::recharge_balance,
+        payment_controller::get_balance_transactions,
+        payment_controller::create_balance_withdrawal,
+        payment_controller::list_balance_withdrawals,
+        payment_controller::review_balance_withdrawal,
+        payment_controller::get_price_config,
+        payment_controller::list_price_configs,
+        payment_controller::create_price_config,
+        payment_controller::update_price_config,
+        payment_controller::deactivate_price_config,
+        payment_controller::validate_coupon,
+        payment_controller::list_coupons,
+        payment_controller::create_coupon,
+        payment_controller::update_coupon,
+        payment_controller::deactivate_coupon,
+        payment_controller::get_payment_statistics,
+        payment_controller::get_user_payment_summary,
+        payment_controller::update_payment_config,
+        payment_controller::delete_payment_config,
+        payment_controller::get_payment_config_history,
+        payment_controller::get_payment_metrics,
+        content_controller::list_articles,
+        content_controller::get_article,
+        content_controller::preview_article,
+        content_controller::create_article,
+        content_controller::update_article,
+        content_controller::publish_article,
+        content_controller::unpublish_article,
+        content_controller::delete_article,
+        content_controller::approve_article,
+        content_controller::reject_article,
+        content_controller::like_article,
+        content_controller::unlike_article,
+        content_controller::list_videos,
+        content_controller::get_video,
+        content_controller::create_video,
+        content_controller::update_video,
+        content_controller::publish_video,
+        content_controller::delete_video,
+        content_controller::approve_video,
+        content_controller::reject_video,
+        content_controller::list_categories,
+        content_controller::create_category,
+        content_controller::get_recommended_content,
+        content_controller::list_diagnosis_mappings,
+        content_controller::create_diagnosis_mapping,
+        content_controller::delete_diagnosis_mapping,
+    ),
+    components(schemas(
+        ApiMessage,
+        // Envelopes
+        ApiResponseUser,
+        ApiResponseLogin,
+        ApiResponseAppointment,
+        ApiResponseAppointmentDetail,
+        ApiResponseAppointmentList,
+        ApiResponseAppointmentSeries,
+        ApiResponseAppointmentPatient,
+        ApiResponseDoctorSchedule,
+        ApiResponseTimeSlots,
+        ApiResponseDoctor,
+        ApiResponseDoctorList,
+        ApiResponseTreatingDoctorList,
+        ApiResponseDoctorOutOfOffice,
+        ApiResponseDoctorMonthlyReport,
+        ApiResponseDoctorEarningsReport,
+        ApiResponseOrder,
+        ApiResponseOrderList,
+        ApiResponseOrderWithTransactions,
+        ApiResponseTransactionList,
+        ApiResponseOrderQuote,
+        ApiResponseCallbackVerification,
+        ApiResponsePayment,
+        ApiResponseOrderPaymentStatus,
+        ApiResponseReconciliationReport,
+        ApiResponseRefund,
+        ApiResponseBalance,
+        ApiResponseBalanceTransactions,
+        ApiResponseBalanceWithdrawal,
+        ApiResponseBalanceWithdrawalList,
+        ApiResponsePriceConfig,
+        ApiResponsePriceConfigList,
+        ApiResponseCoupon,
+        ApiResponseCouponList,
+        ApiResponseCouponPreview,
+        ApiResponsePaymentStatistics,
+        ApiResponseUserPaymentSummary,
+        ApiResponsePaymentConfigHistory,
+        ApiResponsePaymentMetrics,
+        ApiResponseArticle,
+        ApiResponseArticleList,
+        ApiResponseArticleLikeStatus,
+        ApiResponseVideo,
+        ApiResponseVideoList,
+        ApiResponseCategory,
+        ApiResponseCategoryList,
+        ApiResponseRecommendedFeed,
+        ApiResponseDiagnosisMapping,
+        ApiResponseDiagnosisMappingList,
+        // Auth
+        User,
+        UserRole,
+        UserStatus,
+        CreateUserDto,
+        LoginDto,
+        LoginResponse,
+        // Appointments
+        Appointment,
+        VisitType,
+        AppointmentStatus,
+        CreateAppointmentDto,
+        UpdateAppointmentDto,
+        AppointmentDetail,
+        UpdatePrivateNotesDto,
+        TransferAppointmentDto,
+        DoctorScheduleItem,
+        AppointmentRecurrence,
+        CreateAppointmentSeriesDto,
+        SkippedOccurrence,
+        AppointmentSeriesResult,
+        AppointmentPatientInfo,
+        ManagingAccount,
+        Gender,
+        Relationship,
+        // Doctors
+        Doctor,
+        TreatingDoctor,
+        CreateDoctorDto,
+        UpdateDoctorDto,
+        DoctorPhotos,
+        SetOutOfOfficeDto,
+        DoctorOutOfOffice,
+        DoctorMonthlyReport,
+        DoctorEarningType,
+        DoctorEarning,
+        DoctorDailyEarnings,
+        DoctorEarningsReport,
+        // Payment
+        OrderType,
+        OrderStatus,
+        PaymentMethod,
+        BalanceHoldStatus,
+        RefundStatus,
+        BalanceTransactionType,
+        BalanceWithdrawalStatus,
+        PaymentOrder,
+        CreateOrderDto,
+        OrderQuote,
+        InitiatePaymentDto,
+        RechargeBalanceDto,
+        PaymentResponse,
+        OrderPaymentStatus,
+        ReconciliationStatus,
+        BillEntry,
+        AmountMismatch,
+        ReconciliationReport,
+        RunReconciliationDto,
+        OrderListResponse,
+        TransactionType,
+        TransactionStatus,
+        PaymentTransaction,
+        TransactionListResponse,
+        OrderWithTransactions,
+        RefundRecord,
+        CreateRefundDto,
+        ReviewRefundDto,
+        AdjustOrderStatusDto,
+        VerifyCallbackDto,
+        CallbackVerification,
+        UserBalance,
+        BalanceTransaction,
+        BalanceWithdrawal,
+        CreateBalanceWithdrawalDto,
+        ReviewBalanceWithdrawalDto,
+        BalanceWithdrawalListResponse,
+        PriceConfig,
+        CreatePriceConfigDto,
+        UpdatePriceConfigDto,
+        Coupon,
+        CouponDiscountType,
+        CreateCouponDto,
+        UpdateCouponDto,
+        ValidateCouponDto,
+        CouponPreview,
+        PaymentStatistics,
+        PaymentStatisticsBucket,
+        StatisticsGroupBy,
+        UserPaymentSummary,
+        UpdatePaymentConfigDto,
+        PaymentConfigHistory,
+        PaymentMetricsSnapshot,
+        PaymentMethodCounters,
+        PaymentAlertConfig,
+        // Content
+        Article,
+        ArticleListItem,
+        ArticleLikeStatus,
+        Video,
+        VideoListItem,
+        ContentCategory,
+        AuthorType,
+        ContentStatus,
+        VideoStatus,
+        CategoryType,
+        CreateArticleDto,
+        UpdateArticleDto,
+        PublishArticleDto,
+        CreateVideoDto,
+        UpdateVideoDto,
+        PublishVideoDto,
+        ReviewContentDto,
+        CreateCategoryDto,
+        RecommendedContent,
+        RecommendedFeed,
+        DiagnosisCategoryMapping,
+        CreateDiagnosisMappingDto,
+    )),
+    modifiers(&SecurityAddon),
+    tags(
+        (name = "auth", description = "注册、登录与登出"),
+        (name = "appointments", description = "预约挂号"),
+        (name = "doctors", description = "医生信息"),
+        (name = "payment", description = "订单、支付、退款与余额"),
+        (name = "content", description = "文章、视频与内容推荐"),
+    )
+)]
+pub struct ApiDoc;
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer_auth",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// 返回 OpenAPI 文档
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+/// Swagger UI 仅在设置 SWAGGER_UI_ENABLED=true 时开放
+pub fn swagger_ui_enabled() -> bool {
+    std::env::var("SWAGGER_UI_ENABLED")
+        .map(|v| v == "true" || v == "1")
+        .unwrap_or(false)
+}
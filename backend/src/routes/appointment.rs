@@ -10,15 +10,39 @@ pub fn routes() -> Router<AppState> {
         .route("/", get(appointment_controller::list_appointments))
         .route("/:id", get(appointment_controller::get_appointment))
         .route("/", post(appointment_controller::create_appointment))
+        .route(
+            "/series",
+            post(appointment_controller::create_appointment_series),
+        )
+        .route(
+            "/series/:series_id/cancel",
+            put(appointment_controller::cancel_appointment_series),
+        )
         .route("/:id", put(appointment_controller::update_appointment))
         .route(
             "/:id/cancel",
             put(appointment_controller::cancel_appointment),
         )
+        .route(
+            "/:id/transfer",
+            put(appointment_controller::transfer_appointment),
+        )
+        .route(
+            "/:id/private-notes",
+            put(appointment_controller::update_private_notes),
+        )
+        .route(
+            "/:id/patient",
+            get(appointment_controller::get_appointment_patient),
+        )
         .route(
             "/doctor/:doctor_id",
             get(appointment_controller::get_doctor_appointments),
         )
+        .route(
+            "/doctor/:doctor_id/schedule",
+            get(appointment_controller::get_doctor_schedule),
+        )
         .route(
             "/patient/:patient_id",
             get(appointment_controller::get_patient_appointments),
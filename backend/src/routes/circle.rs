@@ -23,6 +23,11 @@ pub fn circle_routes() -> Router<AppState> {
             put(update_member_role),
         )
         .route("/circles/:id/members/:user_id", delete(remove_member))
+        .route("/circles/:id/join-requests", get(get_join_requests))
+        .route(
+            "/circles/:id/join-requests/approve",
+            post(bulk_approve_join_requests),
+        )
         .route("/my-circles", get(get_user_circles))
         .layer(middleware::from_fn(auth_middleware))
 }
@@ -15,8 +15,13 @@ pub fn circle_post_routes() -> Router<AppState> {
         .route("/posts/:id", get(get_post_by_id))
         .route("/posts/:id", put(update_post))
         .route("/posts/:id", delete(delete_post))
+        .route("/posts/:id/history", get(get_post_history))
         .route("/users/:user_id/posts", get(get_user_posts))
         .route("/circles/:circle_id/posts", get(get_circle_posts))
+        .route(
+            "/circles/:circle_id/members/:user_id/posts",
+            get(get_member_posts),
+        )
         // Like routes
         .route("/posts/:post_id/like", post(toggle_like))
         // Comment routes
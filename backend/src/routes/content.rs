@@ -1,4 +1,8 @@
-use crate::{controllers::content_controller, middleware::auth::auth_middleware, AppState};
+use crate::{
+    controllers::content_controller,
+    middleware::auth::{auth_middleware, optional_auth_middleware},
+    AppState,
+};
 use axum::{
     middleware,
     routing::{delete, get, post, put},
@@ -9,7 +13,11 @@ pub fn routes() -> Router<AppState> {
     Router::new()
         // Article routes
         .route("/articles", get(content_controller::list_articles))
-        .route("/articles/:id", get(content_controller::get_article))
+        .route(
+            "/articles/:id",
+            get(content_controller::get_article)
+                .layer(middleware::from_fn(optional_auth_middleware)),
+        )
         .route(
             "/articles",
             post(content_controller::create_article).layer(middleware::from_fn(auth_middleware)),
@@ -18,6 +26,10 @@ pub fn routes() -> Router<AppState> {
             "/articles/:id",
             put(content_controller::update_article).layer(middleware::from_fn(auth_middleware)),
         )
+        .route(
+            "/articles/:id/preview",
+            get(content_controller::preview_article).layer(middleware::from_fn(auth_middleware)),
+        )
         .route(
             "/articles/:id/publish",
             post(content_controller::publish_article).layer(middleware::from_fn(auth_middleware)),
@@ -30,6 +42,20 @@ pub fn routes() -> Router<AppState> {
             "/articles/:id",
             delete(content_controller::delete_article).layer(middleware::from_fn(auth_middleware)),
         )
+        .route(
+            "/articles/:id/like",
+            post(content_controller::like_article)
+                .delete(content_controller::unlike_article)
+                .layer(middleware::from_fn(auth_middleware)),
+        )
+        .route(
+            "/articles/:id/approve",
+            post(content_controller::approve_article).layer(middleware::from_fn(auth_middleware)),
+        )
+        .route(
+            "/articles/:id/reject",
+            post(content_controller::reject_article).layer(middleware::from_fn(auth_middleware)),
+        )
         // Video routes
         .route("/videos", get(content_controller::list_videos))
         .route("/videos/:id", get(content_controller::get_video))
@@ -49,6 +75,31 @@ pub fn routes() -> Router<AppState> {
             "/videos/:id",
             delete(content_controller::delete_video).layer(middleware::from_fn(auth_middleware)),
         )
+        .route(
+            "/videos/:id/approve",
+            post(content_controller::approve_video).layer(middleware::from_fn(auth_middleware)),
+        )
+        .route(
+            "/videos/:id/reject",
+            post(content_controller::reject_video).layer(middleware::from_fn(auth_middleware)),
+        )
+        // Recommendation routes
+        .route(
+            "/recommended",
+            get(content_controller::get_recommended_content)
+                .layer(middleware::from_fn(auth_middleware)),
+        )
+        .route(
+            "/diagnosis-mappings",
+            get(content_controller::list_diagnosis_mappings)
+                .post(content_controller::create_diagnosis_mapping)
+                .layer(middleware::from_fn(auth_middleware)),
+        )
+        .route(
+            "/diagnosis-mappings/:id",
+            delete(content_controller::delete_diagnosis_mapping)
+                .layer(middleware::from_fn(auth_middleware)),
+        )
         // Category routes
         .route("/categories", get(content_controller::list_categories))
         .route(
@@ -29,4 +29,24 @@ pub fn routes() -> Router<AppState> {
             get(doctor_controller::get_doctor_by_user_id)
                 .layer(middleware::from_fn(auth_middleware)),
         )
+        .route(
+            "/:id/out-of-office",
+            put(doctor_controller::set_out_of_office)
+                .delete(doctor_controller::clear_out_of_office)
+                .layer(middleware::from_fn(auth_middleware)),
+        )
+        .route(
+            "/me/earnings",
+            get(doctor_controller::get_my_earnings).layer(middleware::from_fn(auth_middleware)),
+        )
+        .route(
+            "/me/report",
+            get(doctor_controller::get_my_monthly_report)
+                .layer(middleware::from_fn(auth_middleware)),
+        )
+        .route(
+            "/treating/:patient_id",
+            get(doctor_controller::get_treating_doctors)
+                .layer(middleware::from_fn(auth_middleware)),
+        )
 }
@@ -11,11 +11,23 @@ pub fn file_upload_routes() -> Router<AppState> {
     Router::new()
         // File Management
         .route("/upload", post(create_upload))
+        .route("/upload/:id/refresh", post(refresh_upload_url))
         .route("/upload/:id/complete", put(complete_upload))
+        // Multipart uploads for large files
+        .route("/multipart", post(init_multipart_upload))
+        .route("/multipart/:id", get(get_multipart_upload))
+        .route("/multipart/:id", delete(abort_multipart_upload))
+        .route(
+            "/multipart/:id/parts/:part_number",
+            put(record_multipart_part),
+        )
+        .route("/multipart/:id/complete", post(complete_multipart_upload))
         .route("/", get(list_files))
         .route("/:id", get(get_file))
         .route("/:id", delete(delete_file))
+        .route("/batch/delete", delete(delete_files))
         .route("/stats", get(get_file_stats))
+        .route("/storage/top", get(get_top_storage_users))
         // Configuration (admin only)
         .route("/config/upload", get(get_upload_config))
         .route("/config/image", get(get_image_config))
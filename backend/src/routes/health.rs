@@ -0,0 +1,10 @@
+use crate::{controllers::health_controller, AppState};
+use axum::{routing::get, Router};
+
+pub fn routes() -> Router<AppState> {
+    Router::new()
+        .route("/health/live", get(health_controller::liveness))
+        .route("/health/ready", get(health_controller::readiness))
+        // Kept for backward compatibility, behaves as the readiness probe
+        .route("/health", get(health_controller::readiness))
+}
@@ -1,5 +1,5 @@
-use crate::AppState;
-use axum::Router;
+use crate::{openapi, AppState};
+use axum::{routing::get, Router};
 
 pub mod appointment;
 pub mod auth;
@@ -9,6 +9,7 @@ pub mod content;
 pub mod department;
 pub mod doctor;
 pub mod file_upload;
+pub mod health;
 pub mod live_stream;
 pub mod notification;
 pub mod patient_group;
@@ -17,6 +18,7 @@ pub mod payment;
 pub mod prescription;
 pub mod review;
 pub mod statistics;
+pub mod system_config;
 pub mod template;
 pub mod user;
 pub mod video_consultation;
@@ -24,6 +26,7 @@ pub mod websocket;
 
 pub fn create_routes() -> Router<AppState> {
     Router::new()
+        .route("/openapi.json", get(openapi::openapi_json))
         .nest("/auth", auth::routes())
         .nest("/users", user::routes())
         .nest("/doctors", doctor::routes())
@@ -37,6 +40,7 @@ pub fn create_routes() -> Router<AppState> {
         .nest("/reviews", review::routes())
         .nest("/notifications", notification::routes())
         .nest("/statistics", statistics::routes())
+        .nest("/config", system_config::routes())
         .nest("/payment", payment::routes())
         .nest(
             "/video-consultations",
@@ -1,4 +1,8 @@
-use crate::{controllers::notification_controller::*, middleware::auth::auth_middleware, AppState};
+use crate::{
+    controllers::{broadcast_controller, notification_controller::*},
+    middleware::auth::auth_middleware,
+    AppState,
+};
 use axum::{
     middleware,
     routing::{delete, get, post, put},
@@ -12,6 +16,7 @@ pub fn routes() -> Router<AppState> {
         .route("/:id", get(get_notification_detail))
         .route("/:id/read", put(mark_notification_as_read))
         .route("/read-all", put(mark_all_as_read))
+        .route("/read", delete(clear_read_notifications))
         .route("/:id", delete(delete_notification))
         .route("/stats", get(get_notification_stats))
         // 通知设置
@@ -21,6 +26,32 @@ pub fn routes() -> Router<AppState> {
         .route("/push-token", post(register_push_token))
         // 系统公告（管理员）
         .route("/announcement", post(send_system_announcement))
+        // 分组广播（管理员）
+        .route(
+            "/segments",
+            get(broadcast_controller::list_segments).post(broadcast_controller::create_segment),
+        )
+        .route(
+            "/segments/preview",
+            post(broadcast_controller::preview_segment),
+        )
+        .route(
+            "/segments/:id",
+            delete(broadcast_controller::delete_segment),
+        )
+        .route(
+            "/broadcasts",
+            get(broadcast_controller::list_broadcasts).post(broadcast_controller::create_broadcast),
+        )
+        .route("/broadcasts/:id", get(broadcast_controller::get_broadcast))
+        .route(
+            "/broadcasts/:id/approve",
+            put(broadcast_controller::approve_broadcast),
+        )
+        .route(
+            "/broadcasts/:id/reject",
+            put(broadcast_controller::reject_broadcast),
+        )
         // 所有路由都需要认证
         .layer(middleware::from_fn(auth_middleware))
 }
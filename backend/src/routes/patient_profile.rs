@@ -1,6 +1,6 @@
 use crate::{controllers::patient_profile_controller, middleware::auth::auth_middleware, AppState};
 use axum::{
-    routing::{get, put},
+    routing::{delete, get, put},
     Router,
 };
 
@@ -20,8 +20,23 @@ pub fn routes() -> Router<AppState> {
             "/:id",
             get(patient_profile_controller::get_profile)
                 .put(patient_profile_controller::update_profile)
+                .patch(patient_profile_controller::update_profile)
                 .delete(patient_profile_controller::delete_profile),
         )
         .route("/:id/default", put(patient_profile_controller::set_default))
+        // Saved addresses for home visits
+        .route(
+            "/addresses",
+            get(patient_profile_controller::list_addresses)
+                .post(patient_profile_controller::add_address),
+        )
+        .route(
+            "/addresses/:id",
+            delete(patient_profile_controller::delete_address),
+        )
+        .route(
+            "/addresses/:id/default",
+            put(patient_profile_controller::set_default_address),
+        )
         .layer(axum::middleware::from_fn(auth_middleware))
 }
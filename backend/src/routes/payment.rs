@@ -1,7 +1,11 @@
-use crate::{controllers::payment_controller::*, middleware::auth::auth_middleware, AppState};
+use crate::{
+    controllers::{payment_controller::*, withdrawal_controller::*},
+    middleware::auth::auth_middleware,
+    AppState,
+};
 use axum::{
     middleware,
-    routing::{get, post, put},
+    routing::{delete, get, post, put},
     Router,
 };
 
@@ -10,24 +14,83 @@ pub fn routes() -> Router<AppState> {
         // Order management routes
         .route("/orders", post(create_order))
         .route("/orders", get(list_orders))
+        .route("/orders/quote", post(quote_order))
         .route("/orders/:id", get(get_order))
         .route("/orders/:id/cancel", put(cancel_order))
+        // Same segment name as /orders/:id; the value is the order number
+        .route("/orders/:id/status", get(get_order_status))
         // Payment routes
         .route("/pay", post(initiate_payment))
+        // Transaction routes (admin only)
+        .route("/transactions", get(list_transactions))
         // Refund routes
         .route("/refunds", post(create_refund))
         .route("/refunds/:id", get(get_refund))
         // Balance routes
+        .route("/balance/recharge", post(recharge_balance))
         .route("/balance/:user_id", get(get_user_balance))
         .route(
             "/balance/:user_id/transactions",
             get(get_balance_transactions),
         )
+        // Price configuration management (admin only)
+        .route("/price-configs", post(create_price_config))
+        .route(
+            "/price-configs/:id",
+            put(update_price_config).delete(deactivate_price_config),
+        )
+        // Coupon routes
+        .route("/coupons/validate", post(validate_coupon))
+        .route("/coupons", post(create_coupon).get(list_coupons))
+        .route("/coupons/:id", put(update_coupon).delete(deactivate_coupon))
         // Statistics routes
         .route("/statistics", get(get_payment_statistics))
+        .route("/summary", get(get_user_payment_summary))
+        // Doctor earnings & withdrawal routes
+        .route("/earnings", get(get_earnings_summary))
+        .route(
+            "/payout-accounts",
+            post(create_payout_account).get(list_payout_accounts),
+        )
+        .route(
+            "/withdrawals",
+            post(create_withdrawal).get(list_my_withdrawals),
+        )
+        .route(
+            "/withdrawals/balance",
+            post(create_balance_withdrawal).get(list_balance_withdrawals),
+        )
+        .route(
+            "/withdrawals/balance/:id/review",
+            put(review_balance_withdrawal),
+        )
         // Admin only routes
         .route("/admin/refunds/:id/review", put(review_refund))
+        .route("/admin/refunds/:id/retry", put(retry_refund))
         .route("/admin/config/:payment_method", put(update_payment_config))
+        .route(
+            "/admin/config/:payment_method/:config_key",
+            delete(delete_payment_config),
+        )
+        .route("/admin/config-history", get(get_payment_config_history))
+        .route("/admin/metrics", get(get_payment_metrics))
+        .route("/reconciliation", get(get_reconciliation_report))
+        .route("/reconciliation/run", post(run_reconciliation))
+        .route("/admin/orders/:id/status", put(adjust_order_status))
+        .route(
+            "/admin/orders/:id/verify-callback",
+            post(verify_order_callback),
+        )
+        .route(
+            "/admin/orders/by-external/:external_id",
+            get(get_order_by_external_transaction),
+        )
+        .route("/admin/withdrawals", get(list_withdrawal_queue))
+        .route("/admin/withdrawals/:id/process", put(process_withdrawal))
+        .route(
+            "/admin/payout-accounts/:id/verify",
+            put(verify_payout_account),
+        )
         // Apply auth middleware to most routes
         .layer(middleware::from_fn(auth_middleware))
 }
@@ -36,6 +99,7 @@ pub fn public_routes() -> Router<AppState> {
     Router::new()
         // Payment callback route (no auth required)
         .route("/payment/callback", post(payment_callback))
+        .route("/refund/callback", post(refund_callback))
         // Price configuration routes (public)
         .route("/prices/:service_type", get(get_price_config))
         .route("/prices", get(list_price_configs))
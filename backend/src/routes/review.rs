@@ -17,8 +17,15 @@ pub fn routes() -> Router<AppState> {
     let protected_routes = Router::new()
         // 需要认证的路由
         .route("/", post(create_review).get(get_reviews))
-        .route("/:id", get(get_review_by_id).put(update_review))
+        .route(
+            "/:id",
+            get(get_review_by_id)
+                .put(update_review)
+                .delete(delete_review),
+        )
         .route("/:id/reply", post(reply_to_review))
+        .route("/doctor/unreplied", get(get_unreplied_reviews))
+        .route("/doctor/:doctor_id/export", get(export_doctor_reviews))
         .route("/:id/visibility", put(update_review_visibility))
         .route("/patient/:patient_id/reviews", get(get_patient_reviews))
         .route("/tags", post(create_tag))
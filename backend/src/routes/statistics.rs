@@ -19,6 +19,7 @@ pub fn routes() -> Router<AppState> {
         .route("/user-growth", get(get_user_growth_statistics))
         .route("/appointment-heatmap", get(get_appointment_heatmap))
         .route("/export", get(export_data))
+        .route("/audit-logs", get(get_audit_logs))
         // 医生统计
         .route("/doctor/:doctor_id", get(get_doctor_statistics))
         // 患者统计
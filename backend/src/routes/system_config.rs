@@ -0,0 +1,6 @@
+use crate::{controllers::system_config_controller, AppState};
+use axum::{routing::get, Router};
+
+pub fn routes() -> Router<AppState> {
+    Router::new().route("/public", get(system_config_controller::get_public_config))
+}
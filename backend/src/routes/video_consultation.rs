@@ -12,13 +12,39 @@ pub fn video_consultation_routes() -> Router<AppState> {
         // Consultation Management
         .route("/", post(create_consultation))
         .route("/", get(list_consultations))
+        .route("/admin/list", get(admin_list_consultations))
+        .route("/doctor/action-items", get(get_doctor_action_items))
+        .route("/doctor/patients", get(get_patient_consultation_summary))
         .route("/:id", get(get_consultation))
+        .route(
+            "/appointment/:appointment_id",
+            get(get_consultation_by_appointment),
+        )
         .route("/:id", put(update_consultation))
         .route("/:id/start", put(start_consultation))
         .route("/:id/end", put(end_consultation))
+        .route("/:id/refer-offline", post(refer_to_offline))
+        .route("/:id/handover", post(handover_consultation))
         .route("/:id/rate", post(rate_consultation))
+        // Pre-visit questionnaires
+        .route("/:id/questionnaires", post(attach_questionnaire))
+        .route(
+            "/:id/questionnaires/:questionnaire_id/answers",
+            post(submit_questionnaire_answers),
+        )
+        // Doctor feedback about patients (internal)
+        .route("/:id/patient-feedback", post(submit_patient_feedback))
+        .route(
+            "/patients/:patient_id/reliability",
+            get(get_patient_reliability),
+        )
+        // Consultation chat and transcript
+        .route("/:id/messages", post(send_consultation_message))
+        .route("/:id/transcript", get(get_consultation_transcript))
         // Room Management
         .route("/room/:room_id/join", post(join_room))
+        .route("/room-code/:room_code", get(resolve_room_code))
+        .route("/:id/resend-invite", post(resend_invite))
         // WebRTC Signaling
         .route("/signal", post(send_signal))
         .route("/signal/:room_id", get(receive_signals))
@@ -0,0 +1,386 @@
+use crate::models::payment::{BillEntry, PaymentMethod, PaymentOrder, RefundRecord};
+use crate::services::payment_gateway::{GatewayRequest, PaymentGateway};
+use crate::utils::{crypto, errors::AppError};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, Utc};
+use reqwest::Method;
+use rust_decimal::Decimal;
+use serde_json::json;
+use std::collections::{BTreeMap, HashMap};
+use std::str::FromStr;
+
+/// 支付宝请求签名器，默认实现为 RSA2，测试中可替换为固定输出
+pub trait AlipaySigner: Send + Sync {
+    fn sign_type(&self) -> &'static str;
+    fn sign(&self, content: &str) -> Result<String, AppError>;
+}
+
+/// 使用应用私钥做 SHA256withRSA 签名
+pub struct Rsa2Signer {
+    private_key: String,
+}
+
+impl Rsa2Signer {
+    pub fn new(private_key: impl Into<String>) -> Self {
+        Self {
+            private_key: private_key.into(),
+        }
+    }
+}
+
+impl AlipaySigner for Rsa2Signer {
+    fn sign_type(&self) -> &'static str {
+        "RSA2"
+    }
+
+    fn sign(&self, content: &str) -> Result<String, AppError> {
+        crypto::rsa_sign_sha256(&self.private_key, content)
+    }
+}
+
+/// 支付宝应用配置，取自 payment_configs
+#[derive(Debug, Clone)]
+pub struct AlipayConfig {
+    pub app_id: String,
+    /// 应用私钥（PEM 或不带头尾的 Base64）
+    pub private_key: String,
+    pub notify_url: Option<String>,
+    pub return_url: Option<String>,
+}
+
+impl AlipayConfig {
+    pub fn from_map(config: &HashMap<String, String>) -> Result<Self, AppError> {
+        let optional = |key: &str| {
+            config
+                .get(key)
+                .filter(|value| !value.trim().is_empty())
+                .cloned()
+        };
+        let required = |key: &str| {
+            optional(key)
+                .ok_or_else(|| AppError::InternalServerError(format!("支付宝未配置 {}", key)))
+        };
+
+        Ok(Self {
+            app_id: required("app_id")?,
+            private_key: required("private_key")?,
+            notify_url: optional("notify_url"),
+            return_url: optional("return_url"),
+        })
+    }
+}
+
+/// 电脑网站支付或手机网站支付
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlipayTradeType {
+    Page,
+    Wap,
+}
+
+impl AlipayTradeType {
+    pub fn method(&self) -> &'static str {
+        match self {
+            AlipayTradeType::Page => "alipay.trade.page.pay",
+            AlipayTradeType::Wap => "alipay.trade.wap.pay",
+        }
+    }
+
+    fn product_code(&self) -> &'static str {
+        match self {
+            AlipayTradeType::Page => "FAST_INSTANT_TRADE_PAY",
+            AlipayTradeType::Wap => "QUICK_WAP_WAY",
+        }
+    }
+}
+
+pub struct AlipayService;
+
+impl AlipayService {
+    pub const GATEWAY_URL: &'static str = "https://openapi.alipay.com/gateway.do";
+    const REFUND_METHOD: &'static str = "alipay.trade.refund";
+    const QUERY_METHOD: &'static str = "alipay.trade.query";
+    const BILL_URL_METHOD: &'static str = "alipay.data.dataservice.bill.downloadurl.query";
+    /// 接口调用成功的返回码
+    const SUCCESS_CODE: &'static str = "10000";
+
+    /// 支付宝接口使用北京时间
+    fn beijing_time(time: DateTime<Utc>, format: &str) -> String {
+        let offset = FixedOffset::east_opt(8 * 3600).expect("valid offset");
+        time.with_timezone(&offset).format(format).to_string()
+    }
+
+    /// 解析通知或查询结果中的北京时间（如 gmt_payment）
+    pub fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+        let offset = FixedOffset::east_opt(8 * 3600).expect("valid offset");
+        NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+            .ok()?
+            .and_local_timezone(offset)
+            .single()
+            .map(|time| time.with_timezone(&Utc))
+    }
+
+    /// 公共请求参数（未签名）
+    fn common_params(
+        config: &AlipayConfig,
+        method: &str,
+        biz_content: serde_json::Value,
+        now: DateTime<Utc>,
+    ) -> BTreeMap<String, String> {
+        let mut params = BTreeMap::new();
+        params.insert("app_id".to_string(), config.app_id.clone());
+        params.insert("method".to_string(), method.to_string());
+        params.insert("format".to_string(), "JSON".to_string());
+        params.insert("charset".to_string(), "utf-8".to_string());
+        params.insert(
+            "timestamp".to_string(),
+            Self::beijing_time(now, "%Y-%m-%d %H:%M:%S"),
+        );
+        params.insert("version".to_string(), "1.0".to_string());
+        params.insert("biz_content".to_string(), biz_content.to_string());
+        params
+    }
+
+    /// 组装下单的公共参数与 biz_content（未签名）
+    pub fn trade_params(
+        config: &AlipayConfig,
+        order: &PaymentOrder,
+        trade_type: AlipayTradeType,
+        return_url: Option<&str>,
+        now: DateTime<Utc>,
+    ) -> BTreeMap<String, String> {
+        let biz_content = json!({
+            "out_trade_no": order.order_no,
+            "total_amount": format!("{:.2}", order.amount),
+            "subject": order
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("订单{}", order.order_no)),
+            "product_code": trade_type.product_code(),
+            "time_expire": Self::beijing_time(order.expire_time, "%Y-%m-%d %H:%M"),
+        });
+
+        let mut params = Self::common_params(config, trade_type.method(), biz_content, now);
+        if let Some(notify_url) = &config.notify_url {
+            params.insert("notify_url".to_string(), notify_url.clone());
+        }
+        if let Some(return_url) = return_url.or(config.return_url.as_deref()) {
+            params.insert("return_url".to_string(), return_url.to_string());
+        }
+
+        params
+    }
+
+    /// 组装统一收单交易退款（alipay.trade.refund）参数，out_request_no 取退款单号以支持多次部分退款
+    pub fn refund_params(
+        config: &AlipayConfig,
+        order: &PaymentOrder,
+        trade_no: Option<&str>,
+        refund: &RefundRecord,
+        now: DateTime<Utc>,
+    ) -> BTreeMap<String, String> {
+        let mut biz_content = json!({
+            "out_trade_no": order.order_no,
+            "refund_amount": format!("{:.2}", refund.refund_amount),
+            "refund_reason": refund.refund_reason,
+            "out_request_no": refund.refund_no,
+        });
+        if let Some(trade_no) = trade_no {
+            biz_content["trade_no"] = json!(trade_no);
+        }
+
+        Self::common_params(config, Self::REFUND_METHOD, biz_content, now)
+    }
+
+    /// 组装统一收单交易查询（alipay.trade.query）参数，响应字段与异步通知一致
+    pub fn query_params(
+        config: &AlipayConfig,
+        order: &PaymentOrder,
+        now: DateTime<Utc>,
+    ) -> BTreeMap<String, String> {
+        let biz_content = json!({ "out_trade_no": order.order_no });
+        Self::common_params(config, Self::QUERY_METHOD, biz_content, now)
+    }
+
+    /// 组装查询对账单下载地址（alipay.data.dataservice.bill.downloadurl.query）参数，账单类型为交易账单
+    pub fn bill_url_params(
+        config: &AlipayConfig,
+        bill_date: NaiveDate,
+        now: DateTime<Utc>,
+    ) -> BTreeMap<String, String> {
+        let biz_content = json!({
+            "bill_type": "trade",
+            "bill_date": bill_date.format("%Y-%m-%d").to_string(),
+        });
+        Self::common_params(config, Self::BILL_URL_METHOD, biz_content, now)
+    }
+
+    /// 解析交易账单的业务明细 CSV：# 开头的为说明与汇总行，首个非注释行为表头。
+    /// 只保留业务类型为"交易"的记录，金额取订单金额
+    pub fn parse_trade_bill(content: &str) -> Result<Vec<BillEntry>, AppError> {
+        let invalid = |message: String| AppError::PaymentGatewayError {
+            code: "INVALID_BILL".to_string(),
+            message,
+        };
+
+        let mut lines = content
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .filter(|line| !line.trim().is_empty() && !line.starts_with('#'));
+        let Some(header) = lines.next() else {
+            return Ok(Vec::new());
+        };
+        let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+        let column = |name: &str| {
+            columns
+                .iter()
+                .position(|column| *column == name)
+                .ok_or_else(|| invalid(format!("账单缺少列 {}", name)))
+        };
+        let trade_col = column("支付宝交易号")?;
+        let order_col = column("商户订单号")?;
+        let type_col = column("业务类型")?;
+        let amount_col = column("订单金额（元）")?;
+
+        let mut entries = Vec::new();
+        for line in lines {
+            let fields: Vec<&str> = line.split(',').map(str::trim).collect();
+            let field = |index: usize| {
+                fields
+                    .get(index)
+                    .copied()
+                    .ok_or_else(|| invalid(format!("账单行格式错误: {}", line)))
+            };
+            if field(type_col)? != "交易" {
+                continue;
+            }
+
+            let amount = field(amount_col)?;
+            entries.push(BillEntry {
+                payment_method: PaymentMethod::Alipay,
+                order_no: field(order_col)?.to_string(),
+                external_transaction_id: Some(field(trade_col)?.to_string()),
+                amount: Decimal::from_str(amount)
+                    .map_err(|_| invalid(format!("账单金额格式错误: {}", amount)))?,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// 待签名字符串：排除指定键及空值后按键名排序，以 key=value 和 & 拼接
+    pub fn sign_content(params: &BTreeMap<String, String>, excluded: &[&str]) -> String {
+        params
+            .iter()
+            .filter(|(key, value)| !value.is_empty() && !excluded.contains(&key.as_str()))
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join("&")
+    }
+
+    /// 补充 sign_type 并签名
+    pub fn sign_params(
+        mut params: BTreeMap<String, String>,
+        signer: &dyn AlipaySigner,
+    ) -> Result<BTreeMap<String, String>, AppError> {
+        params.insert("sign_type".to_string(), signer.sign_type().to_string());
+        let sign = signer.sign(&Self::sign_content(&params, &["sign"]))?;
+        params.insert("sign".to_string(), sign);
+        Ok(params)
+    }
+
+    /// 已签名参数拼成跳转到支付宝收银台的地址
+    pub fn payment_url(params: &BTreeMap<String, String>) -> String {
+        let query = params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+            .collect::<Vec<_>>()
+            .join("&");
+        format!("{}?{}", Self::GATEWAY_URL, query)
+    }
+
+    /// 以表单提交已签名参数调用开放平台接口，返回对应的 `xxx_response` 节点；
+    /// 返回码不是 10000 时转为 PaymentGatewayError
+    pub async fn execute(
+        gateway: &dyn PaymentGateway,
+        params: &BTreeMap<String, String>,
+    ) -> Result<serde_json::Value, AppError> {
+        let body = params
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value)))
+            .collect::<Vec<_>>()
+            .join("&");
+
+        let response = gateway
+            .send(GatewayRequest {
+                method: Method::POST,
+                url: Self::GATEWAY_URL.to_string(),
+                headers: vec![(
+                    "Content-Type".to_string(),
+                    "application/x-www-form-urlencoded;charset=utf-8".to_string(),
+                )],
+                body: Some(body),
+            })
+            .await?;
+
+        if !response.is_success() {
+            return Err(AppError::PaymentGatewayError {
+                code: format!("HTTP_{}", response.status),
+                message: response.body,
+            });
+        }
+
+        let payload: serde_json::Value =
+            serde_json::from_str(&response.body).map_err(|_| AppError::PaymentGatewayError {
+                code: "INVALID_RESPONSE".to_string(),
+                message: response.body.clone(),
+            })?;
+        let method = params.get("method").map(String::as_str).unwrap_or_default();
+        let result = payload[format!("{}_response", method.replace('.', "_"))].clone();
+
+        if result["code"].as_str() != Some(Self::SUCCESS_CODE) {
+            let field = |primary: &str, fallback: &str| {
+                result[primary]
+                    .as_str()
+                    .or_else(|| result[fallback].as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            };
+            return Err(AppError::PaymentGatewayError {
+                code: field("sub_code", "code"),
+                message: field("sub_msg", "msg"),
+            });
+        }
+
+        Ok(result)
+    }
+
+    /// 解析异步通知的 application/x-www-form-urlencoded 报文为参数表，
+    /// 签名针对解码后的参数值计算
+    pub fn notification_params(body: &str) -> Result<BTreeMap<String, String>, AppError> {
+        let decode = |value: &str| {
+            urlencoding::decode(&value.replace('+', " "))
+                .map(|value| value.into_owned())
+                .map_err(|_| AppError::BadRequest("回调报文格式错误".to_string()))
+        };
+
+        body.trim()
+            .split('&')
+            .filter(|pair| !pair.is_empty())
+            .map(|pair| {
+                let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+                Ok((decode(key)?, decode(value)?))
+            })
+            .collect()
+    }
+
+    /// 使用支付宝公钥校验异步通知，sign 与 sign_type 不参与签名
+    pub fn verify_notification(
+        public_key: &str,
+        params: &BTreeMap<String, String>,
+    ) -> Result<bool, AppError> {
+        let Some(sign) = params.get("sign") else {
+            return Ok(false);
+        };
+        let content = Self::sign_content(params, &["sign", "sign_type"]);
+        crypto::rsa_verify_sha256(public_key, &content, sign)
+    }
+}
@@ -1,6 +1,18 @@
-use crate::{config::database::DbPool, models::appointment::*};
+use crate::{
+    config::database::DbPool,
+    models::{
+        appointment::*,
+        doctor::DoctorOutOfOffice,
+        patient_profile::{AppointmentPatientInfo, Gender, ManagingAccount, Relationship},
+    },
+    services::{
+        audit_service::AuditService, doctor_service, patient_profile_service,
+        payment_service::PaymentService, system_config_service::SystemConfigService,
+    },
+};
 use anyhow::{anyhow, Result};
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use rust_decimal::Decimal;
 use uuid::Uuid;
 
 pub async fn list_appointments(
@@ -15,8 +27,9 @@ pub async fn list_appointments(
 
     let mut query = String::from(
         r#"
-        SELECT id, patient_id, doctor_id, appointment_date, time_slot, visit_type, 
-               symptoms, has_visited_before, status, created_at, updated_at
+        SELECT id, patient_id, patient_profile_id, doctor_id, appointment_date, time_slot, visit_type, 
+               symptoms, has_visited_before, status, referred_from_consultation_id, referral_notes,
+               series_id, address_id, created_at, updated_at
         FROM appointments
         WHERE 1=1
     "#,
@@ -60,8 +73,9 @@ pub async fn list_appointments(
 
 pub async fn get_appointment_by_id(pool: &DbPool, id: Uuid) -> Result<Appointment> {
     let query = r#"
-        SELECT id, patient_id, doctor_id, appointment_date, time_slot, visit_type, 
-               symptoms, has_visited_before, status, created_at, updated_at
+        SELECT id, patient_id, patient_profile_id, doctor_id, appointment_date, time_slot, visit_type, 
+               symptoms, has_visited_before, status, referred_from_consultation_id, referral_notes,
+               series_id, address_id, created_at, updated_at
         FROM appointments
         WHERE id = ?
     "#;
@@ -76,41 +90,207 @@ pub async fn get_appointment_by_id(pool: &DbPool, id: Uuid) -> Result<Appointmen
 }
 
 pub async fn create_appointment(pool: &DbPool, dto: CreateAppointmentDto) -> Result<Appointment> {
+    // A family member's profile must be managed by the booking account
+    if let Some(profile_id) = dto.patient_profile_id {
+        patient_profile_service::get_profile_by_id(pool, profile_id, dto.patient_id)
+            .await
+            .map_err(|_| anyhow!("Patient profile not found or access denied"))?;
+    }
+
+    let address_id =
+        resolve_visit_address(pool, dto.patient_id, &dto.visit_type, dto.address_id).await?;
+
+    if let Some(ooo) = out_of_office_for_booking(pool, dto.doctor_id, dto.appointment_date).await? {
+        return Err(anyhow!("Doctor is out of office: {}", ooo.message));
+    }
+
     // Check if the time slot is available
     if !is_slot_available(pool, dto.doctor_id, dto.appointment_date, &dto.time_slot).await? {
         return Err(anyhow!("Time slot is not available"));
     }
 
+    let status = initial_status(pool, &dto.visit_type).await?;
     let appointment_id = Uuid::new_v4();
+    insert_appointment(
+        pool,
+        appointment_id,
+        &dto.patient_id,
+        dto.patient_profile_id,
+        &dto.doctor_id,
+        dto.appointment_date,
+        &dto.time_slot,
+        &dto.visit_type,
+        address_id,
+        &dto.symptoms,
+        dto.has_visited_before,
+        None,
+        status,
+    )
+    .await?;
+
+    get_appointment_by_id(pool, appointment_id).await
+}
+
+/// Books a recurring series. Occurrences that clash with the doctor's or the patient's
+/// existing bookings are skipped and reported instead of failing the whole series.
+pub async fn create_appointment_series(
+    pool: &DbPool,
+    dto: CreateAppointmentSeriesDto,
+) -> Result<AppointmentSeriesResult> {
+    if let Some(profile_id) = dto.patient_profile_id {
+        patient_profile_service::get_profile_by_id(pool, profile_id, dto.patient_id)
+            .await
+            .map_err(|_| anyhow!("Patient profile not found or access denied"))?;
+    }
+
+    let address_id =
+        resolve_visit_address(pool, dto.patient_id, &dto.visit_type, dto.address_id).await?;
+
+    let series_id = Uuid::new_v4();
+    let status = initial_status(pool, &dto.visit_type).await?;
     let now = Utc::now();
+    let mut created = Vec::new();
+    let mut skipped = Vec::new();
+
+    for i in 0..dto.count {
+        let appointment_date =
+            dto.start_date + Duration::days(i as i64 * dto.recurrence.interval_days());
 
+        let reason = if appointment_date <= now {
+            Some("in_past")
+        } else if out_of_office_for_booking(pool, dto.doctor_id, appointment_date)
+            .await?
+            .is_some()
+        {
+            Some("out_of_office")
+        } else if !is_slot_available(pool, dto.doctor_id, appointment_date, &dto.time_slot).await? {
+            Some("doctor_unavailable")
+        } else if has_patient_conflict(pool, dto.patient_id, appointment_date, &dto.time_slot)
+            .await?
+        {
+            Some("patient_conflict")
+        } else {
+            None
+        };
+
+        if let Some(reason) = reason {
+            skipped.push(SkippedOccurrence {
+                appointment_date,
+                reason: reason.to_string(),
+            });
+            continue;
+        }
+
+        let appointment_id = Uuid::new_v4();
+        insert_appointment(
+            pool,
+            appointment_id,
+            &dto.patient_id,
+            dto.patient_profile_id,
+            &dto.doctor_id,
+            appointment_date,
+            &dto.time_slot,
+            &dto.visit_type,
+            address_id,
+            &dto.symptoms,
+            dto.has_visited_before,
+            Some(series_id),
+            status,
+        )
+        .await?;
+
+        created.push(get_appointment_by_id(pool, appointment_id).await?);
+    }
+
+    Ok(AppointmentSeriesResult {
+        series_id,
+        created,
+        skipped,
+    })
+}
+
+pub async fn get_series_appointments(pool: &DbPool, series_id: Uuid) -> Result<Vec<Appointment>> {
     let query = r#"
-        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot, 
-                                visit_type, symptoms, has_visited_before, status, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, 'pending', ?, ?)
+        SELECT id, patient_id, patient_profile_id, doctor_id, appointment_date, time_slot, visit_type, 
+               symptoms, has_visited_before, status, referred_from_consultation_id, referral_notes,
+               series_id, address_id, created_at, updated_at
+        FROM appointments
+        WHERE series_id = ?
+        ORDER BY appointment_date ASC
     "#;
 
-    sqlx::query(query)
-        .bind(appointment_id.to_string())
-        .bind(dto.patient_id.to_string())
-        .bind(dto.doctor_id.to_string())
-        .bind(dto.appointment_date)
-        .bind(&dto.time_slot)
-        .bind(match dto.visit_type {
-            VisitType::OnlineVideo => "online_video",
-            VisitType::Offline => "offline",
-        })
-        .bind(&dto.symptoms)
-        .bind(dto.has_visited_before)
-        .bind(now)
-        .bind(now)
-        .execute(pool)
+    let rows = sqlx::query(query)
+        .bind(series_id.to_string())
+        .fetch_all(pool)
         .await
-        .map_err(|e| anyhow!("Failed to create appointment: {}", e))?;
+        .map_err(|e| anyhow!("Failed to fetch appointment series: {}", e))?;
 
-    get_appointment_by_id(pool, appointment_id).await
+    let mut appointments = Vec::new();
+    for row in rows {
+        appointments.push(parse_appointment_row(row)?);
+    }
+
+    Ok(appointments)
 }
 
+/// Cancels the occurrences of a series that have not happened yet; past visits are kept.
+pub async fn cancel_appointment_series(
+    pool: &DbPool,
+    series_id: Uuid,
+    user_id: Uuid,
+    is_admin: bool,
+) -> Result<Vec<Appointment>> {
+    let appointments = get_series_appointments(pool, series_id).await?;
+    let Some(first) = appointments.first() else {
+        return Err(anyhow!("Appointment series not found"));
+    };
+
+    if !is_admin && first.patient_id != user_id {
+        return Err(anyhow!("Insufficient permissions"));
+    }
+
+    let now = Utc::now();
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+    let cancelled: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM appointments
+        WHERE series_id = ? AND appointment_date > ? AND status IN ('pending', 'confirmed')
+        FOR UPDATE
+        "#,
+    )
+    .bind(series_id.to_string())
+    .bind(now)
+    .fetch_all(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Failed to cancel appointment series: {}", e))?;
+
+    for id in &cancelled {
+        sqlx::query("UPDATE appointments SET status = 'cancelled', updated_at = ? WHERE id = ?")
+            .bind(now)
+            .bind(id)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to cancel appointment series: {}", e))?;
+
+        let id = Uuid::parse_str(id).map_err(|e| anyhow!("Invalid appointment id: {}", e))?;
+        PaymentService::release_balance_hold_tx(&mut tx, id)
+            .await
+            .map_err(|e| anyhow!("Failed to release held payment: {}", e))?;
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Failed to commit transaction: {}", e))?;
+
+    get_series_appointments(pool, series_id).await
+}
+
+/// Updates date, slot or status. Completing the visit settles a balance payment held for it;
+/// cancelling it or marking a no-show returns the held amount.
 pub async fn update_appointment(
     pool: &DbPool,
     id: Uuid,
@@ -149,6 +329,7 @@ pub async fn update_appointment(
         return get_appointment_by_id(pool, id).await;
     }
 
+    let new_status = dto.status.clone();
     let mut query_builder = sqlx::query(&query);
 
     if let Some(date) = dto.appointment_date {
@@ -165,6 +346,7 @@ pub async fn update_appointment(
             AppointmentStatus::Confirmed => "confirmed",
             AppointmentStatus::Completed => "completed",
             AppointmentStatus::Cancelled => "cancelled",
+            AppointmentStatus::NoShow => "no_show",
         };
         query_builder = query_builder.bind(status_str);
     }
@@ -172,27 +354,242 @@ pub async fn update_appointment(
     query_builder = query_builder.bind(Utc::now());
     query_builder = query_builder.bind(id.to_string());
 
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
     query_builder
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(|e| anyhow!("Failed to update appointment: {}", e))?;
 
+    // A balance payment held for the visit is settled or returned with the status change
+    match new_status {
+        Some(AppointmentStatus::Completed) => {
+            PaymentService::settle_balance_hold_tx(pool, &mut tx, id)
+                .await
+                .map_err(|e| anyhow!("Failed to settle held payment: {}", e))?;
+        }
+        Some(AppointmentStatus::Cancelled | AppointmentStatus::NoShow) => {
+            PaymentService::release_balance_hold_tx(&mut tx, id)
+                .await
+                .map_err(|e| anyhow!("Failed to release held payment: {}", e))?;
+        }
+        _ => {}
+    }
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Failed to commit transaction: {}", e))?;
+
     get_appointment_by_id(pool, id).await
 }
 
+/// Cancels an appointment and returns any balance payment still held for it.
 pub async fn cancel_appointment(pool: &DbPool, id: Uuid) -> Result<Appointment> {
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
     let query = "UPDATE appointments SET status = 'cancelled', updated_at = ? WHERE id = ?";
 
     sqlx::query(query)
         .bind(Utc::now())
         .bind(id.to_string())
-        .execute(pool)
+        .execute(&mut *tx)
         .await
         .map_err(|e| anyhow!("Failed to cancel appointment: {}", e))?;
 
+    PaymentService::release_balance_hold_tx(&mut tx, id)
+        .await
+        .map_err(|e| anyhow!("Failed to release held payment: {}", e))?;
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Failed to commit transaction: {}", e))?;
+
     get_appointment_by_id(pool, id).await
 }
 
+/// Moves an upcoming appointment to another profile managed by the same account.
+pub async fn transfer_appointment(
+    pool: &DbPool,
+    id: Uuid,
+    user_id: Uuid,
+    dto: TransferAppointmentDto,
+) -> Result<Appointment> {
+    let appointment = get_appointment_by_id(pool, id).await?;
+
+    if appointment.patient_id != user_id {
+        return Err(anyhow!("Insufficient permissions"));
+    }
+
+    patient_profile_service::get_profile_by_id(pool, dto.patient_profile_id, user_id)
+        .await
+        .map_err(|_| anyhow!("Patient profile not found or access denied"))?;
+
+    if appointment.patient_profile_id == Some(dto.patient_profile_id) {
+        return Err(anyhow!("Appointment is already booked for this profile"));
+    }
+
+    if !matches!(
+        appointment.status,
+        AppointmentStatus::Pending | AppointmentStatus::Confirmed
+    ) {
+        return Err(anyhow!("Only upcoming appointments can be transferred"));
+    }
+
+    let now = Utc::now();
+    if appointment.appointment_date <= now || has_consultation_started(pool, id).await? {
+        return Err(anyhow!(
+            "Appointment has already started and cannot be transferred"
+        ));
+    }
+
+    let mut tx = pool
+        .begin()
+        .await
+        .map_err(|e| anyhow!("Failed to start transaction: {}", e))?;
+
+    let result = sqlx::query(
+        r#"
+        UPDATE appointments
+        SET patient_profile_id = ?, updated_at = ?
+        WHERE id = ? AND appointment_date > ? AND status IN ('pending', 'confirmed')
+        "#,
+    )
+    .bind(dto.patient_profile_id.to_string())
+    .bind(now)
+    .bind(id.to_string())
+    .bind(now)
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Failed to transfer appointment: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(anyhow!(
+            "Appointment has already started and cannot be transferred"
+        ));
+    }
+
+    // Keep a not-yet-started consultation pointing at the same person
+    sqlx::query(
+        r#"
+        UPDATE video_consultations
+        SET patient_profile_id = ?, updated_at = ?
+        WHERE appointment_id = ? AND status = 'waiting'
+        "#,
+    )
+    .bind(dto.patient_profile_id.to_string())
+    .bind(now)
+    .bind(id.to_string())
+    .execute(&mut *tx)
+    .await
+    .map_err(|e| anyhow!("Failed to update consultation: {}", e))?;
+
+    AuditService::log(
+        &mut *tx,
+        user_id,
+        "appointment.transfer",
+        "appointment",
+        id,
+        Some(serde_json::json!({
+            "from_profile_id": appointment.patient_profile_id,
+            "to_profile_id": dto.patient_profile_id,
+        })),
+    )
+    .await?;
+
+    tx.commit()
+        .await
+        .map_err(|e| anyhow!("Failed to commit transfer: {}", e))?;
+
+    get_appointment_by_id(pool, id).await
+}
+
+/// Who the visit is for. Managed profiles carry the managing account's contact details
+/// so the doctor knows whom to reach.
+pub async fn get_appointment_patient_info(
+    pool: &DbPool,
+    appointment: &Appointment,
+) -> Result<AppointmentPatientInfo> {
+    use sqlx::Row;
+
+    let account = sqlx::query("SELECT name, phone, gender, birthday FROM users WHERE id = ?")
+        .bind(appointment.patient_id.to_string())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow!("Patient not found: {}", e))?;
+
+    let managing_account = ManagingAccount {
+        user_id: appointment.patient_id,
+        name: account.get("name"),
+        phone: account.get("phone"),
+    };
+
+    match appointment.patient_profile_id {
+        Some(profile_id) => {
+            let profile = patient_profile_service::get_profile_by_id(
+                pool,
+                profile_id,
+                appointment.patient_id,
+            )
+            .await?;
+            let is_managed = !matches!(profile.relationship, Relationship::MySelf);
+
+            Ok(AppointmentPatientInfo {
+                appointment_id: appointment.id,
+                patient_profile_id: Some(profile.id),
+                name: profile.name,
+                gender: Some(profile.gender),
+                birthday: profile.birthday,
+                relationship: profile.relationship,
+                is_managed,
+                managing_account,
+            })
+        }
+        None => {
+            let gender = match account.get::<&str, _>("gender") {
+                "男" => Some(Gender::Male),
+                "女" => Some(Gender::Female),
+                _ => None,
+            };
+            let birthday: Option<DateTime<Utc>> = account.get("birthday");
+
+            Ok(AppointmentPatientInfo {
+                appointment_id: appointment.id,
+                patient_profile_id: None,
+                name: managing_account.name.clone(),
+                gender,
+                birthday: birthday.map(|b| b.date_naive()),
+                relationship: Relationship::MySelf,
+                is_managed: false,
+                managing_account,
+            })
+        }
+    }
+}
+
+async fn has_consultation_started(pool: &DbPool, appointment_id: Uuid) -> Result<bool> {
+    let row = sqlx::query(
+        r#"
+        SELECT COUNT(*) as count
+        FROM video_consultations
+        WHERE appointment_id = ?
+        AND (status IN ('in_progress', 'completed', 'no_show') OR actual_start_time IS NOT NULL)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Failed to check consultation status: {}", e))?;
+
+    let count: i64 = sqlx::Row::get(&row, "count");
+    Ok(count > 0)
+}
+
 pub async fn get_doctor_appointments(
     pool: &DbPool,
     doctor_id: Uuid,
@@ -204,8 +601,9 @@ pub async fn get_doctor_appointments(
 
     let mut query = format!(
         r#"
-        SELECT id, patient_id, doctor_id, appointment_date, time_slot, visit_type, 
-               symptoms, has_visited_before, status, created_at, updated_at
+        SELECT id, patient_id, patient_profile_id, doctor_id, appointment_date, time_slot, visit_type, 
+               symptoms, has_visited_before, status, referred_from_consultation_id, referral_notes,
+               series_id, address_id, created_at, updated_at
         FROM appointments
         WHERE doctor_id = '{}'
     "#,
@@ -234,6 +632,76 @@ pub async fn get_doctor_appointments(
     Ok(appointments)
 }
 
+/// A doctor's agenda for one day: active appointments ordered by time slot
+pub async fn get_doctor_day_schedule(
+    pool: &DbPool,
+    doctor_id: Uuid,
+    date: NaiveDate,
+) -> Result<Vec<DoctorScheduleItem>> {
+    use sqlx::Row;
+
+    let query = r#"
+        SELECT a.id, a.appointment_date, a.time_slot, a.visit_type, a.status, a.symptoms,
+               a.patient_id, a.patient_profile_id,
+               COALESCE(pp.name, u.name) as patient_name,
+               (SELECT vc.id FROM video_consultations vc
+                WHERE vc.appointment_id = a.id
+                ORDER BY vc.created_at DESC LIMIT 1) as consultation_id,
+               (SELECT vc.status FROM video_consultations vc
+                WHERE vc.appointment_id = a.id
+                ORDER BY vc.created_at DESC LIMIT 1) as consultation_status
+        FROM appointments a
+        JOIN users u ON u.id = a.patient_id
+        LEFT JOIN patient_profiles pp ON pp.id = a.patient_profile_id
+        WHERE a.doctor_id = ?
+        AND DATE(a.appointment_date) = ?
+        AND a.status IN ('pending', 'confirmed')
+        ORDER BY a.time_slot ASC, a.appointment_date ASC
+    "#;
+
+    let rows = sqlx::query(query)
+        .bind(doctor_id.to_string())
+        .bind(date)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch doctor schedule: {}", e))?;
+
+    let mut schedule = Vec::new();
+    for row in rows {
+        let visit_type = match row.get::<&str, _>("visit_type") {
+            "online_video" => VisitType::OnlineVideo,
+            "offline" => VisitType::Offline,
+            "home_visit" => VisitType::HomeVisit,
+            _ => return Err(anyhow!("Invalid visit type")),
+        };
+        let status = match row.get::<&str, _>("status") {
+            "pending" => AppointmentStatus::Pending,
+            "confirmed" => AppointmentStatus::Confirmed,
+            _ => return Err(anyhow!("Invalid appointment status")),
+        };
+
+        schedule.push(DoctorScheduleItem {
+            appointment_id: Uuid::parse_str(row.get("id"))?,
+            appointment_date: row.get("appointment_date"),
+            time_slot: row.get("time_slot"),
+            visit_type,
+            status,
+            symptoms: row.get("symptoms"),
+            patient_id: Uuid::parse_str(row.get("patient_id"))?,
+            patient_profile_id: row
+                .get::<Option<String>, _>("patient_profile_id")
+                .and_then(|id| Uuid::parse_str(&id).ok()),
+            patient_name: row.get("patient_name"),
+            consultation_id: row
+                .get::<Option<String>, _>("consultation_id")
+                .and_then(|id| Uuid::parse_str(&id).ok()),
+            consultation_status: row.get("consultation_status"),
+        });
+    }
+
+    Ok(schedule)
+}
+
 pub async fn get_patient_appointments(
     pool: &DbPool,
     patient_id: Uuid,
@@ -245,8 +713,9 @@ pub async fn get_patient_appointments(
 
     let mut query = format!(
         r#"
-        SELECT id, patient_id, doctor_id, appointment_date, time_slot, visit_type, 
-               symptoms, has_visited_before, status, created_at, updated_at
+        SELECT id, patient_id, patient_profile_id, doctor_id, appointment_date, time_slot, visit_type, 
+               symptoms, has_visited_before, status, referred_from_consultation_id, referral_notes,
+               series_id, address_id, created_at, updated_at
         FROM appointments
         WHERE patient_id = '{}'
     "#,
@@ -317,6 +786,48 @@ pub async fn get_available_slots(
     Ok(available_slots)
 }
 
+/// Private notes live outside `Appointment` so no shared query or serializer can expose them
+pub async fn get_doctor_private_notes(pool: &DbPool, id: Uuid) -> Result<Option<String>> {
+    let notes: Option<Option<String>> =
+        sqlx::query_scalar("SELECT doctor_private_notes FROM appointments WHERE id = ?")
+            .bind(id.to_string())
+            .fetch_optional(pool)
+            .await?;
+
+    notes.ok_or_else(|| anyhow!("Appointment not found"))
+}
+
+pub async fn update_doctor_private_notes(
+    pool: &DbPool,
+    id: Uuid,
+    doctor_user_id: Uuid,
+    dto: UpdatePrivateNotesDto,
+) -> Result<AppointmentDetail> {
+    let appointment = get_appointment_by_id(pool, id).await?;
+
+    // Only the treating doctor may write private notes
+    let treating_user_id = get_doctor_user_id(pool, appointment.doctor_id).await?;
+    if treating_user_id != doctor_user_id {
+        return Err(anyhow!("Only the treating doctor can edit private notes"));
+    }
+
+    let notes = dto
+        .doctor_private_notes
+        .map(|notes| notes.trim().to_string())
+        .filter(|notes| !notes.is_empty());
+
+    sqlx::query("UPDATE appointments SET doctor_private_notes = ? WHERE id = ?")
+        .bind(&notes)
+        .bind(id.to_string())
+        .execute(pool)
+        .await?;
+
+    Ok(AppointmentDetail {
+        appointment: get_appointment_by_id(pool, id).await?,
+        doctor_private_notes: notes,
+    })
+}
+
 pub async fn get_doctor_user_id(pool: &DbPool, doctor_id: Uuid) -> Result<Uuid> {
     let query = "SELECT user_id FROM doctors WHERE id = ?";
 
@@ -330,6 +841,119 @@ pub async fn get_doctor_user_id(pool: &DbPool, doctor_id: Uuid) -> Result<Uuid>
     Uuid::parse_str(&user_id_str).map_err(|e| anyhow!("Invalid UUID: {}", e))
 }
 
+/// Bookings are refused while the doctor is away, and for visits that fall inside the window
+async fn out_of_office_for_booking(
+    pool: &DbPool,
+    doctor_id: Uuid,
+    appointment_date: DateTime<Utc>,
+) -> Result<Option<DoctorOutOfOffice>> {
+    if let Some(ooo) = doctor_service::get_active_out_of_office(pool, doctor_id, Utc::now()).await?
+    {
+        return Ok(Some(ooo));
+    }
+
+    doctor_service::get_active_out_of_office(pool, doctor_id, appointment_date).await
+}
+
+/// Home visits need one of the patient's saved addresses, falling back to the default one.
+/// Other visit types take no address.
+async fn resolve_visit_address(
+    pool: &DbPool,
+    patient_id: Uuid,
+    visit_type: &VisitType,
+    address_id: Option<Uuid>,
+) -> Result<Option<Uuid>> {
+    if !matches!(visit_type, VisitType::HomeVisit) {
+        if address_id.is_some() {
+            return Err(anyhow!("A visit address is only accepted for home visits"));
+        }
+        return Ok(None);
+    }
+
+    let address = match address_id {
+        Some(address_id) => {
+            patient_profile_service::get_address_by_id(pool, address_id, patient_id)
+                .await
+                .map_err(|_| anyhow!("Visit address not found or access denied"))?
+        }
+        None => patient_profile_service::get_default_address(pool, patient_id)
+            .await?
+            .ok_or_else(|| anyhow!("Home visits require a visit address"))?,
+    };
+
+    Ok(Some(address.id))
+}
+
+/// Free visits (configured price of zero) skip the payment step and are confirmed on booking.
+/// Without a configured price the appointment stays pending until the payment callback.
+async fn initial_status(pool: &DbPool, visit_type: &VisitType) -> Result<&'static str> {
+    let key = match visit_type {
+        VisitType::OnlineVideo => "online_video",
+        VisitType::Offline => "offline",
+        VisitType::HomeVisit => "home_visit",
+    };
+
+    let price = SystemConfigService::get_value(pool, "appointment_price", key)
+        .await?
+        .and_then(|v| v.trim().parse::<Decimal>().ok());
+
+    Ok(match price {
+        Some(price) if price.is_zero() => "confirmed",
+        _ => "pending",
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn insert_appointment(
+    pool: &DbPool,
+    id: Uuid,
+    patient_id: &Uuid,
+    patient_profile_id: Option<Uuid>,
+    doctor_id: &Uuid,
+    appointment_date: DateTime<Utc>,
+    time_slot: &str,
+    visit_type: &VisitType,
+    address_id: Option<Uuid>,
+    symptoms: &str,
+    has_visited_before: bool,
+    series_id: Option<Uuid>,
+    status: &str,
+) -> Result<()> {
+    let now = Utc::now();
+
+    let query = r#"
+        INSERT INTO appointments (id, patient_id, patient_profile_id, doctor_id, appointment_date, time_slot, 
+                                visit_type, address_id, symptoms, has_visited_before, status, series_id,
+                                created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    "#;
+
+    sqlx::query(query)
+        .bind(id.to_string())
+        .bind(patient_id.to_string())
+        .bind(patient_profile_id.map(|id| id.to_string()))
+        .bind(doctor_id.to_string())
+        .bind(appointment_date)
+        .bind(time_slot)
+        .bind(match visit_type {
+            VisitType::OnlineVideo => "online_video",
+            VisitType::Offline => "offline",
+            VisitType::HomeVisit => "home_visit",
+        })
+        .bind(address_id.map(|id| id.to_string()))
+        .bind(symptoms)
+        .bind(has_visited_before)
+        .bind(status)
+        .bind(series_id.map(|id| id.to_string()))
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to create appointment: {}", e))?;
+
+    Ok(())
+}
+
 async fn is_slot_available(
     pool: &DbPool,
     doctor_id: Uuid,
@@ -357,6 +981,34 @@ async fn is_slot_available(
     Ok(count == 0)
 }
 
+/// The patient already holds an active booking (with any doctor) in the same slot
+async fn has_patient_conflict(
+    pool: &DbPool,
+    patient_id: Uuid,
+    date: DateTime<Utc>,
+    time_slot: &str,
+) -> Result<bool> {
+    let query = r#"
+        SELECT COUNT(*) as count
+        FROM appointments
+        WHERE patient_id = ?
+        AND DATE(appointment_date) = DATE(?)
+        AND time_slot = ?
+        AND status IN ('pending', 'confirmed')
+    "#;
+
+    let row = sqlx::query(query)
+        .bind(patient_id.to_string())
+        .bind(date)
+        .bind(time_slot)
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to check patient appointments: {}", e))?;
+
+    let count: i64 = sqlx::Row::get(&row, "count");
+    Ok(count > 0)
+}
+
 fn parse_appointment_row(row: sqlx::mysql::MySqlRow) -> Result<Appointment> {
     use sqlx::Row;
 
@@ -364,6 +1016,7 @@ fn parse_appointment_row(row: sqlx::mysql::MySqlRow) -> Result<Appointment> {
     let visit_type = match visit_type_str.as_str() {
         "online_video" => VisitType::OnlineVideo,
         "offline" => VisitType::Offline,
+        "home_visit" => VisitType::HomeVisit,
         _ => return Err(anyhow!("Invalid visit type")),
     };
 
@@ -373,19 +1026,33 @@ fn parse_appointment_row(row: sqlx::mysql::MySqlRow) -> Result<Appointment> {
         "confirmed" => AppointmentStatus::Confirmed,
         "completed" => AppointmentStatus::Completed,
         "cancelled" => AppointmentStatus::Cancelled,
+        "no_show" => AppointmentStatus::NoShow,
         _ => return Err(anyhow!("Invalid appointment status")),
     };
 
     Ok(Appointment {
         id: Uuid::parse_str(row.get("id")).unwrap(),
         patient_id: Uuid::parse_str(row.get("patient_id")).unwrap(),
+        patient_profile_id: row
+            .get::<Option<String>, _>("patient_profile_id")
+            .and_then(|id| Uuid::parse_str(&id).ok()),
         doctor_id: Uuid::parse_str(row.get("doctor_id")).unwrap(),
         appointment_date: row.get("appointment_date"),
         time_slot: row.get("time_slot"),
         visit_type,
+        address_id: row
+            .get::<Option<String>, _>("address_id")
+            .and_then(|id| Uuid::parse_str(&id).ok()),
         symptoms: row.get("symptoms"),
         has_visited_before: row.get("has_visited_before"),
         status,
+        referred_from_consultation_id: row
+            .get::<Option<String>, _>("referred_from_consultation_id")
+            .and_then(|id| Uuid::parse_str(&id).ok()),
+        referral_notes: row.get("referral_notes"),
+        series_id: row
+            .get::<Option<String>, _>("series_id")
+            .and_then(|id| Uuid::parse_str(&id).ok()),
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
     })
@@ -0,0 +1,40 @@
+use crate::utils::errors::AppError;
+use chrono::Utc;
+use sqlx::{Executor, MySql};
+use uuid::Uuid;
+
+pub struct AuditService;
+
+impl AuditService {
+    /// 记录一条审计日志，可在事务内调用以保证与业务变更同时提交
+    pub async fn log<'e, E>(
+        executor: E,
+        actor_id: Uuid,
+        action: &str,
+        target_type: &str,
+        target_id: Uuid,
+        details: Option<serde_json::Value>,
+    ) -> Result<(), AppError>
+    where
+        E: Executor<'e, Database = MySql>,
+    {
+        let query = r#"
+            INSERT INTO audit_logs (id, actor_id, action, target_type, target_id, details, created_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(Uuid::new_v4().to_string())
+            .bind(actor_id.to_string())
+            .bind(action)
+            .bind(target_type)
+            .bind(target_id.to_string())
+            .bind(details)
+            .bind(Utc::now())
+            .execute(executor)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+}
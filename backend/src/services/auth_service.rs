@@ -1,6 +1,7 @@
 use crate::{
     config::{database::DbPool, Config},
     models::user::*,
+    services::user_service,
     utils::{
         jwt::create_token,
         password::{hash_password, verify_password},
@@ -41,6 +42,8 @@ pub async fn register_user(pool: &DbPool, dto: CreateUserDto) -> Result<User> {
         .await
         .map_err(|e| anyhow!("Failed to create user: {}", e))?;
 
+    user_service::on_user_registered(pool, user_id).await;
+
     get_user_by_id(pool, user_id).await
 }
 
@@ -0,0 +1,689 @@
+use crate::config::database::DbPool;
+use crate::models::broadcast::*;
+use crate::services::audit_service::AuditService;
+use crate::utils::errors::AppError;
+use chrono::{DateTime, Utc};
+use serde_json::json;
+use sqlx::mysql::MySqlArguments;
+use sqlx::query::Query;
+use sqlx::{MySql, Row, Transaction};
+use uuid::Uuid;
+
+const VALID_ROLES: [&str; 3] = ["admin", "doctor", "patient"];
+const VALID_CHANNELS: [&str; 3] = ["push", "email", "sms"];
+const MAX_APPOINTMENT_WINDOW_DAYS: i64 = 3650;
+const MAX_DEPARTMENTS: usize = 20;
+const PREVIEW_SAMPLE_SIZE: i64 = 20;
+
+/// 用户对系统公告的总开关，未设置时默认开启
+const ANNOUNCEMENT_ENABLED: &str = "COALESCE((SELECT ns.enabled FROM notification_settings ns \
+     WHERE ns.user_id = u.id AND ns.notification_type = 'system_announcement'), TRUE)";
+
+/// 分组条件中的绑定参数，按出现顺序绑定
+#[derive(Debug, Clone, PartialEq)]
+pub enum SegmentParam {
+    Text(String),
+    Int(i64),
+    Time(DateTime<Utc>),
+}
+
+/// 由分组条件组合出的 `FROM ... WHERE ...` 片段，别名 `u` 指向 users 表
+#[derive(Debug, Clone)]
+pub struct SegmentQuery {
+    pub from_where: String,
+    pub params: Vec<SegmentParam>,
+}
+
+impl SegmentQuery {
+    fn bind<'q>(
+        &'q self,
+        mut query: Query<'q, MySql, MySqlArguments>,
+    ) -> Query<'q, MySql, MySqlArguments> {
+        for param in &self.params {
+            query = match param {
+                SegmentParam::Text(value) => query.bind(value.as_str()),
+                SegmentParam::Int(value) => query.bind(*value),
+                SegmentParam::Time(value) => query.bind(*value),
+            };
+        }
+        query
+    }
+}
+
+/// 广播发送限制
+#[derive(Debug, Clone)]
+pub struct BroadcastLimits {
+    /// 单次广播允许命中的最大人数
+    pub max_segment_size: i64,
+    /// 超过该人数需第二位管理员确认
+    pub approval_threshold: i64,
+}
+
+impl Default for BroadcastLimits {
+    fn default() -> Self {
+        Self {
+            max_segment_size: 50_000,
+            approval_threshold: 10_000,
+        }
+    }
+}
+
+impl BroadcastLimits {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            max_segment_size: std::env::var("BROADCAST_MAX_SEGMENT_SIZE")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(defaults.max_segment_size),
+            ..defaults
+        }
+    }
+}
+
+pub struct BroadcastService;
+
+impl BroadcastService {
+    /// 校验分组条件
+    pub fn validate_definition(definition: &SegmentDefinition) -> Result<(), AppError> {
+        if definition.roles.is_none()
+            && definition.last_appointment_within_days.is_none()
+            && definition.doctor_departments.is_none()
+            && definition.channels.is_none()
+            && definition.registered_after.is_none()
+            && definition.registered_before.is_none()
+        {
+            return Err(AppError::ValidationError("分组条件不能为空".to_string()));
+        }
+
+        if let Some(roles) = &definition.roles {
+            if roles.is_empty() {
+                return Err(AppError::ValidationError("角色列表不能为空".to_string()));
+            }
+            if let Some(role) = roles.iter().find(|r| !VALID_ROLES.contains(&r.as_str())) {
+                return Err(AppError::ValidationError(format!("无效的角色: {}", role)));
+            }
+        }
+
+        if let Some(days) = definition.last_appointment_within_days {
+            if !(1..=MAX_APPOINTMENT_WINDOW_DAYS).contains(&days) {
+                return Err(AppError::ValidationError(format!(
+                    "预约时间范围需在1到{}天之间",
+                    MAX_APPOINTMENT_WINDOW_DAYS
+                )));
+            }
+        }
+
+        if let Some(departments) = &definition.doctor_departments {
+            if departments.is_empty() || departments.len() > MAX_DEPARTMENTS {
+                return Err(AppError::ValidationError(format!(
+                    "科室数量需在1到{}个之间",
+                    MAX_DEPARTMENTS
+                )));
+            }
+            if departments.iter().any(|d| d.trim().is_empty()) {
+                return Err(AppError::ValidationError("科室名称不能为空".to_string()));
+            }
+        }
+
+        if let Some(channels) = &definition.channels {
+            if channels.is_empty() {
+                return Err(AppError::ValidationError("渠道列表不能为空".to_string()));
+            }
+            if let Some(channel) = channels
+                .iter()
+                .find(|c| !VALID_CHANNELS.contains(&c.as_str()))
+            {
+                return Err(AppError::ValidationError(format!(
+                    "无效的通知渠道: {}",
+                    channel
+                )));
+            }
+        }
+
+        if let (Some(after), Some(before)) =
+            (definition.registered_after, definition.registered_before)
+        {
+            if after >= before {
+                return Err(AppError::ValidationError(
+                    "注册开始时间必须早于结束时间".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// 将分组条件组合为一条 SQL 过滤语句，调用前需先通过 validate_definition 校验
+    pub fn compose_segment_query(definition: &SegmentDefinition) -> SegmentQuery {
+        let mut from_where = String::from("FROM users u WHERE u.status = 'active'");
+        let mut params = Vec::new();
+
+        if let Some(roles) = &definition.roles {
+            from_where.push_str(&format!(" AND u.role IN ({})", placeholders(roles.len())));
+            params.extend(roles.iter().cloned().map(SegmentParam::Text));
+        }
+
+        if let Some(days) = definition.last_appointment_within_days {
+            from_where.push_str(
+                " AND EXISTS (SELECT 1 FROM appointments sa WHERE sa.patient_id = u.id \
+                 AND sa.status != 'cancelled' \
+                 AND sa.appointment_date >= DATE_SUB(NOW(), INTERVAL ? DAY))",
+            );
+            params.push(SegmentParam::Int(days));
+        }
+
+        if let Some(departments) = &definition.doctor_departments {
+            from_where.push_str(&format!(
+                " AND EXISTS (SELECT 1 FROM appointments da JOIN doctors dd ON da.doctor_id = dd.id \
+                 WHERE da.patient_id = u.id AND dd.department IN ({}))",
+                placeholders(departments.len())
+            ));
+            params.extend(
+                departments
+                    .iter()
+                    .map(|d| SegmentParam::Text(d.trim().to_string())),
+            );
+        }
+
+        if let Some(channels) = &definition.channels {
+            for channel in channels {
+                // 列名来自白名单，未配置时沿用通知设置的默认值
+                let (column, default) = match channel.as_str() {
+                    "push" => ("push_enabled", "TRUE"),
+                    "email" => ("email_enabled", "FALSE"),
+                    _ => ("sms_enabled", "FALSE"),
+                };
+                from_where.push_str(&format!(
+                    " AND COALESCE((SELECT ns.{} FROM notification_settings ns \
+                     WHERE ns.user_id = u.id AND ns.notification_type = 'system_announcement'), {})",
+                    column, default
+                ));
+            }
+        }
+
+        if let Some(after) = definition.registered_after {
+            from_where.push_str(" AND u.created_at >= ?");
+            params.push(SegmentParam::Time(after));
+        }
+
+        if let Some(before) = definition.registered_before {
+            from_where.push_str(" AND u.created_at < ?");
+            params.push(SegmentParam::Time(before));
+        }
+
+        SegmentQuery { from_where, params }
+    }
+
+    // Saved segments
+    pub async fn create_segment(
+        db: &DbPool,
+        admin_id: Uuid,
+        dto: CreateSegmentDto,
+    ) -> Result<BroadcastSegment, AppError> {
+        Self::validate_definition(&dto.definition)?;
+
+        let segment_id = Uuid::new_v4();
+        let definition = serde_json::to_value(&dto.definition)
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO broadcast_segments (id, name, description, definition, created_by)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(segment_id.to_string())
+        .bind(&dto.name)
+        .bind(&dto.description)
+        .bind(definition)
+        .bind(admin_id.to_string())
+        .execute(db)
+        .await?;
+
+        Self::get_segment(db, segment_id).await
+    }
+
+    pub async fn get_segment(db: &DbPool, segment_id: Uuid) -> Result<BroadcastSegment, AppError> {
+        let row = sqlx::query("SELECT * FROM broadcast_segments WHERE id = ?")
+            .bind(segment_id.to_string())
+            .fetch_optional(db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("分组不存在".to_string()))?;
+
+        Self::parse_segment_row(row)
+    }
+
+    pub async fn list_segments(db: &DbPool) -> Result<Vec<BroadcastSegment>, AppError> {
+        let rows = sqlx::query("SELECT * FROM broadcast_segments ORDER BY created_at DESC")
+            .fetch_all(db)
+            .await?;
+
+        rows.into_iter().map(Self::parse_segment_row).collect()
+    }
+
+    pub async fn delete_segment(db: &DbPool, segment_id: Uuid) -> Result<(), AppError> {
+        let result = sqlx::query("DELETE FROM broadcast_segments WHERE id = ?")
+            .bind(segment_id.to_string())
+            .execute(db)
+            .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("分组不存在".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// 预览分组命中人数及部分用户
+    pub async fn preview_segment(
+        db: &DbPool,
+        dto: PreviewSegmentDto,
+    ) -> Result<SegmentPreview, AppError> {
+        let (_, definition) = Self::resolve_definition(db, dto.segment_id, dto.definition).await?;
+        let segment = Self::compose_segment_query(&definition);
+
+        let count_sql = format!("SELECT COUNT(*) AS count {}", segment.from_where);
+        let size: i64 = segment
+            .bind(sqlx::query(&count_sql))
+            .fetch_one(db)
+            .await?
+            .get("count");
+
+        let sample_sql = format!(
+            "SELECT u.id, u.name, u.role {} ORDER BY u.created_at DESC LIMIT {}",
+            segment.from_where, PREVIEW_SAMPLE_SIZE
+        );
+        let rows = segment.bind(sqlx::query(&sample_sql)).fetch_all(db).await?;
+
+        let sample = rows
+            .into_iter()
+            .map(|row| {
+                Ok(SegmentMember {
+                    id: Uuid::parse_str(row.get("id"))
+                        .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+                    name: row.get("name"),
+                    role: row.get("role"),
+                })
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        Ok(SegmentPreview { size, sample })
+    }
+
+    // Broadcasts
+    /// 创建广播：命中人数超过审批阈值时进入待审批状态，否则立即发送
+    pub async fn create_broadcast(
+        db: &DbPool,
+        admin_id: Uuid,
+        dto: CreateBroadcastDto,
+        limits: &BroadcastLimits,
+    ) -> Result<NotificationBroadcast, AppError> {
+        let (segment_id, definition) =
+            Self::resolve_definition(db, dto.segment_id, dto.definition).await?;
+        let segment = Self::compose_segment_query(&definition);
+        let definition_json = serde_json::to_value(&definition)
+            .map_err(|e| AppError::InternalServerError(e.to_string()))?;
+
+        let mut tx = db.begin().await?;
+
+        let targeted = Self::count_targeted(&mut tx, &segment).await?;
+        if targeted > limits.max_segment_size {
+            return Err(AppError::BadRequest(format!(
+                "目标人数{}超过单次广播上限{}",
+                targeted, limits.max_segment_size
+            )));
+        }
+
+        let broadcast_id = Uuid::new_v4();
+        let needs_approval = targeted > limits.approval_threshold;
+
+        sqlx::query(
+            r#"
+            INSERT INTO notification_broadcasts
+                (id, title, content, related_id, segment_id, definition, status, targeted_count, created_by)
+            VALUES (?, ?, ?, ?, ?, ?, 'pending_approval', ?, ?)
+            "#,
+        )
+        .bind(broadcast_id.to_string())
+        .bind(&dto.title)
+        .bind(&dto.content)
+        .bind(dto.related_id.map(|id| id.to_string()))
+        .bind(segment_id.map(|id| id.to_string()))
+        .bind(definition_json)
+        .bind(targeted)
+        .bind(admin_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        if needs_approval {
+            AuditService::log(
+                &mut *tx,
+                admin_id,
+                "broadcast.request_approval",
+                "notification_broadcast",
+                broadcast_id,
+                Some(json!({ "targeted": targeted })),
+            )
+            .await?;
+        } else {
+            let (delivered, skipped) = Self::deliver(
+                &mut tx,
+                broadcast_id,
+                &dto.title,
+                &dto.content,
+                dto.related_id,
+                &segment,
+                targeted,
+            )
+            .await?;
+
+            AuditService::log(
+                &mut *tx,
+                admin_id,
+                "broadcast.send",
+                "notification_broadcast",
+                broadcast_id,
+                Some(json!({ "delivered": delivered, "skipped": skipped })),
+            )
+            .await?;
+        }
+
+        tx.commit().await?;
+
+        Self::get_broadcast(db, broadcast_id).await
+    }
+
+    /// 第二位管理员确认后发送待审批广播，发送时重新计算目标人群
+    pub async fn approve_broadcast(
+        db: &DbPool,
+        broadcast_id: Uuid,
+        approver_id: Uuid,
+        limits: &BroadcastLimits,
+    ) -> Result<NotificationBroadcast, AppError> {
+        let mut tx = db.begin().await?;
+
+        let broadcast = Self::lock_pending_broadcast(&mut tx, broadcast_id, approver_id).await?;
+        let segment = Self::compose_segment_query(&broadcast.definition);
+
+        let targeted = Self::count_targeted(&mut tx, &segment).await?;
+        if targeted > limits.max_segment_size {
+            return Err(AppError::BadRequest(format!(
+                "目标人数{}超过单次广播上限{}",
+                targeted, limits.max_segment_size
+            )));
+        }
+
+        let (delivered, skipped) = Self::deliver(
+            &mut tx,
+            broadcast_id,
+            &broadcast.title,
+            &broadcast.content,
+            broadcast.related_id,
+            &segment,
+            targeted,
+        )
+        .await?;
+
+        sqlx::query(
+            "UPDATE notification_broadcasts SET reviewed_by = ?, reviewed_at = NOW() WHERE id = ?",
+        )
+        .bind(approver_id.to_string())
+        .bind(broadcast_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        AuditService::log(
+            &mut *tx,
+            approver_id,
+            "broadcast.approve",
+            "notification_broadcast",
+            broadcast_id,
+            Some(json!({ "delivered": delivered, "skipped": skipped })),
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Self::get_broadcast(db, broadcast_id).await
+    }
+
+    pub async fn reject_broadcast(
+        db: &DbPool,
+        broadcast_id: Uuid,
+        reviewer_id: Uuid,
+    ) -> Result<NotificationBroadcast, AppError> {
+        let mut tx = db.begin().await?;
+
+        Self::lock_pending_broadcast(&mut tx, broadcast_id, reviewer_id).await?;
+
+        sqlx::query(
+            r#"
+            UPDATE notification_broadcasts
+            SET status = 'rejected', reviewed_by = ?, reviewed_at = NOW()
+            WHERE id = ?
+            "#,
+        )
+        .bind(reviewer_id.to_string())
+        .bind(broadcast_id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        AuditService::log(
+            &mut *tx,
+            reviewer_id,
+            "broadcast.reject",
+            "notification_broadcast",
+            broadcast_id,
+            None,
+        )
+        .await?;
+
+        tx.commit().await?;
+
+        Self::get_broadcast(db, broadcast_id).await
+    }
+
+    pub async fn get_broadcast(
+        db: &DbPool,
+        broadcast_id: Uuid,
+    ) -> Result<NotificationBroadcast, AppError> {
+        let row = sqlx::query("SELECT * FROM notification_broadcasts WHERE id = ?")
+            .bind(broadcast_id.to_string())
+            .fetch_optional(db)
+            .await?
+            .ok_or_else(|| AppError::NotFound("广播不存在".to_string()))?;
+
+        Self::parse_broadcast_row(row)
+    }
+
+    pub async fn list_broadcasts(
+        db: &DbPool,
+        status: Option<String>,
+    ) -> Result<Vec<NotificationBroadcast>, AppError> {
+        let mut sql = String::from("SELECT * FROM notification_broadcasts WHERE 1=1");
+        if status.is_some() {
+            sql.push_str(" AND status = ?");
+        }
+        sql.push_str(" ORDER BY created_at DESC LIMIT 100");
+
+        let mut query = sqlx::query(&sql);
+        if let Some(status) = &status {
+            query = query.bind(status);
+        }
+
+        let rows = query.fetch_all(db).await?;
+
+        rows.into_iter().map(Self::parse_broadcast_row).collect()
+    }
+
+    // Helpers
+    async fn resolve_definition(
+        db: &DbPool,
+        segment_id: Option<Uuid>,
+        definition: Option<SegmentDefinition>,
+    ) -> Result<(Option<Uuid>, SegmentDefinition), AppError> {
+        let resolved = match (segment_id, definition) {
+            (Some(segment_id), None) => {
+                let segment = Self::get_segment(db, segment_id).await?;
+                (Some(segment.id), segment.definition)
+            }
+            (None, Some(definition)) => (None, definition),
+            _ => {
+                return Err(AppError::BadRequest(
+                    "请指定已保存的分组或分组条件（二选一）".to_string(),
+                ))
+            }
+        };
+
+        Self::validate_definition(&resolved.1)?;
+
+        Ok(resolved)
+    }
+
+    async fn lock_pending_broadcast(
+        tx: &mut Transaction<'_, MySql>,
+        broadcast_id: Uuid,
+        reviewer_id: Uuid,
+    ) -> Result<NotificationBroadcast, AppError> {
+        let row = sqlx::query("SELECT * FROM notification_broadcasts WHERE id = ? FOR UPDATE")
+            .bind(broadcast_id.to_string())
+            .fetch_optional(&mut **tx)
+            .await?
+            .ok_or_else(|| AppError::NotFound("广播不存在".to_string()))?;
+        let broadcast = Self::parse_broadcast_row(row)?;
+
+        if broadcast.status != BroadcastStatus::PendingApproval {
+            return Err(AppError::BadRequest("该广播不在待审批状态".to_string()));
+        }
+
+        if broadcast.created_by == reviewer_id {
+            return Err(AppError::BadRequest("广播需由另一位管理员审批".to_string()));
+        }
+
+        Ok(broadcast)
+    }
+
+    async fn count_targeted(
+        tx: &mut Transaction<'_, MySql>,
+        segment: &SegmentQuery,
+    ) -> Result<i64, AppError> {
+        let sql = format!("SELECT COUNT(*) AS count {}", segment.from_where);
+        let count: i64 = segment
+            .bind(sqlx::query(&sql))
+            .fetch_one(&mut **tx)
+            .await?
+            .get("count");
+
+        Ok(count)
+    }
+
+    /// 一次性写入整个分组的通知，关闭了系统公告的用户计为跳过，返回 (送达数, 跳过数)
+    #[allow(clippy::too_many_arguments)]
+    async fn deliver(
+        tx: &mut Transaction<'_, MySql>,
+        broadcast_id: Uuid,
+        title: &str,
+        content: &str,
+        related_id: Option<Uuid>,
+        segment: &SegmentQuery,
+        targeted: i64,
+    ) -> Result<(i64, i64), AppError> {
+        let insert_sql = format!(
+            r#"
+            INSERT INTO notifications (id, user_id, type, title, content, related_id, metadata, status, created_at)
+            SELECT UUID(), u.id, 'system_announcement', ?, ?, ?, JSON_OBJECT('broadcast_id', ?), 'unread', NOW()
+            {} AND {}
+            "#,
+            segment.from_where, ANNOUNCEMENT_ENABLED
+        );
+        let broadcast_id_str = broadcast_id.to_string();
+        let query = sqlx::query(&insert_sql)
+            .bind(title)
+            .bind(content)
+            .bind(related_id.map(|id| id.to_string()))
+            .bind(broadcast_id_str.as_str());
+        let delivered = segment
+            .bind(query)
+            .execute(&mut **tx)
+            .await?
+            .rows_affected() as i64;
+        let skipped = targeted - delivered;
+
+        sqlx::query(
+            r#"
+            UPDATE notification_broadcasts
+            SET status = 'sent', targeted_count = ?, delivered_count = ?, skipped_count = ?, sent_at = NOW()
+            WHERE id = ?
+            "#,
+        )
+        .bind(targeted)
+        .bind(delivered)
+        .bind(skipped)
+        .bind(broadcast_id.to_string())
+        .execute(&mut **tx)
+        .await?;
+
+        Ok((delivered, skipped))
+    }
+
+    fn parse_definition(value: serde_json::Value) -> Result<SegmentDefinition, AppError> {
+        serde_json::from_value(value).map_err(|e| {
+            AppError::InternalServerError(format!("Invalid segment definition: {}", e))
+        })
+    }
+
+    fn parse_segment_row(row: sqlx::mysql::MySqlRow) -> Result<BroadcastSegment, AppError> {
+        Ok(BroadcastSegment {
+            id: Uuid::parse_str(row.get("id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            name: row.get("name"),
+            description: row.get("description"),
+            definition: Self::parse_definition(row.get("definition"))?,
+            created_by: Uuid::parse_str(row.get("created_by"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    fn parse_broadcast_row(row: sqlx::mysql::MySqlRow) -> Result<NotificationBroadcast, AppError> {
+        let status_str: String = row.get("status");
+        let status = match status_str.as_str() {
+            "pending_approval" => BroadcastStatus::PendingApproval,
+            "sent" => BroadcastStatus::Sent,
+            "rejected" => BroadcastStatus::Rejected,
+            _ => return Err(AppError::BadRequest("Invalid broadcast status".to_string())),
+        };
+
+        Ok(NotificationBroadcast {
+            id: Uuid::parse_str(row.get("id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            title: row.get("title"),
+            content: row.get("content"),
+            related_id: row
+                .get::<Option<String>, _>("related_id")
+                .and_then(|s| Uuid::parse_str(&s).ok()),
+            segment_id: row
+                .get::<Option<String>, _>("segment_id")
+                .and_then(|s| Uuid::parse_str(&s).ok()),
+            definition: Self::parse_definition(row.get("definition"))?,
+            status,
+            targeted_count: row.get("targeted_count"),
+            delivered_count: row.get("delivered_count"),
+            skipped_count: row.get("skipped_count"),
+            created_by: Uuid::parse_str(row.get("created_by"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            reviewed_by: row
+                .get::<Option<String>, _>("reviewed_by")
+                .and_then(|s| Uuid::parse_str(&s).ok()),
+            reviewed_at: row.get("reviewed_at"),
+            sent_at: row.get("sent_at"),
+            created_at: row.get("created_at"),
+        })
+    }
+}
+
+fn placeholders(count: usize) -> String {
+    vec!["?"; count].join(", ")
+}
@@ -180,6 +180,10 @@ impl CacheKeys {
         format!("content:video:{}", video_id)
     }
 
+    pub fn content_recommendations(user_id: &str, page: u32, per_page: u32) -> String {
+        format!("content:recommended:{}:{}:{}", user_id, page, per_page)
+    }
+
     pub fn statistics_dashboard() -> String {
         "statistics:dashboard".to_string()
     }
@@ -1,10 +1,12 @@
 use crate::config::database::DbPool;
 use crate::models::{
-    CirclePost, CirclePostWithAuthor, CreateCirclePostDto, CreateCommentDto, PostComment,
-    PostCommentWithAuthor, PostStatus, UpdateCirclePostDto,
+    CirclePost, CirclePostHistory, CirclePostWithAuthor, CreateCirclePostDto, CreateCommentDto,
+    PostComment, PostCommentWithAuthor, PostStatus, UpdateCirclePostDto,
 };
 use crate::services::circle_service::CircleService;
+use crate::utils::content_filter::{filter_text, ContentKind};
 use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
 use serde_json;
 use sqlx::Row;
 use uuid::Uuid;
@@ -16,7 +18,7 @@ impl CirclePostService {
     pub async fn create_post(
         pool: &DbPool,
         author_id: Uuid,
-        dto: CreateCirclePostDto,
+        mut dto: CreateCirclePostDto,
     ) -> Result<CirclePost> {
         // Check if user is a member of the circle
         let is_member = Self::is_circle_member(pool, dto.circle_id, author_id).await?;
@@ -25,8 +27,8 @@ impl CirclePostService {
         }
 
         // Check for sensitive words
-        Self::check_sensitive_words(pool, &dto.title).await?;
-        Self::check_sensitive_words(pool, &dto.content).await?;
+        dto.title = filter_text(pool, ContentKind::CirclePost, &dto.title).await?;
+        dto.content = filter_text(pool, ContentKind::CirclePost, &dto.content).await?;
 
         let mut tx = pool.begin().await?;
 
@@ -56,7 +58,7 @@ impl CirclePostService {
         let post = sqlx::query(
             r#"
             SELECT id, author_id, circle_id, title, content, images, likes, comments,
-                   status, created_at, updated_at
+                   status, edited_at, created_at, updated_at
             FROM circle_posts
             WHERE id = ?
             "#,
@@ -88,7 +90,7 @@ impl CirclePostService {
         let mut list_query = String::from(
             r#"
             SELECT p.id, p.author_id, p.circle_id, p.title, p.content, p.images,
-                   p.likes, p.comments, p.created_at, p.updated_at,
+                   p.likes, p.comments, p.edited_at, p.created_at, p.updated_at,
                    u.name as author_name, c.name as circle_name,
                    CASE WHEN pl.id IS NOT NULL THEN TRUE ELSE FALSE END as is_liked
             FROM circle_posts p
@@ -148,7 +150,7 @@ impl CirclePostService {
         let row = sqlx::query(
             r#"
             SELECT p.id, p.author_id, p.circle_id, p.title, p.content, p.images,
-                   p.likes, p.comments, p.created_at, p.updated_at,
+                   p.likes, p.comments, p.edited_at, p.created_at, p.updated_at,
                    u.name as author_name, c.name as circle_name,
                    CASE WHEN pl.id IS NOT NULL THEN TRUE ELSE FALSE END as is_liked
             FROM circle_posts p
@@ -171,7 +173,7 @@ impl CirclePostService {
         pool: &DbPool,
         id: Uuid,
         author_id: Uuid,
-        dto: UpdateCirclePostDto,
+        mut dto: UpdateCirclePostDto,
     ) -> Result<CirclePost> {
         // Check if user is the author
         let post = Self::get_post_simple(pool, id).await?;
@@ -180,11 +182,11 @@ impl CirclePostService {
         }
 
         // Check for sensitive words if updating title or content
-        if let Some(ref title) = dto.title {
-            Self::check_sensitive_words(pool, title).await?;
+        if let Some(title) = dto.title.take() {
+            dto.title = Some(filter_text(pool, ContentKind::CirclePost, &title).await?);
         }
-        if let Some(ref content) = dto.content {
-            Self::check_sensitive_words(pool, content).await?;
+        if let Some(content) = dto.content.take() {
+            dto.content = Some(filter_text(pool, ContentKind::CirclePost, &content).await?);
         }
 
         // Build dynamic update query
@@ -219,7 +221,9 @@ impl CirclePostService {
             return Err(anyhow!("No fields to update"));
         }
 
-        query.push_str(", updated_at = CURRENT_TIMESTAMP WHERE id = ?");
+        query.push_str(
+            ", edited_at = CURRENT_TIMESTAMP, updated_at = CURRENT_TIMESTAMP WHERE id = ?",
+        );
 
         // Bind parameters
         let mut query_builder = sqlx::query(&query);
@@ -239,11 +243,69 @@ impl CirclePostService {
 
         query_builder = query_builder.bind(id.to_string());
 
-        query_builder.execute(pool).await?;
+        let mut tx = pool.begin().await?;
+
+        // Keep the content as it was before this edit
+        sqlx::query(
+            r#"
+            INSERT INTO circle_post_history (id, post_id, editor_id, title, content, images)
+            VALUES (?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(id.to_string())
+        .bind(author_id.to_string())
+        .bind(&post.title)
+        .bind(&post.content)
+        .bind(serde_json::to_string(&post.images)?)
+        .execute(&mut *tx)
+        .await?;
+
+        query_builder.execute(&mut *tx).await?;
+
+        tx.commit().await?;
 
         Self::get_post_simple(pool, id).await
     }
 
+    /// 帖子编辑历史（最新的在前），仅作者、圈主、圈子管理员和平台管理员可见
+    pub async fn get_post_history(
+        pool: &DbPool,
+        post_id: Uuid,
+        requester_id: Uuid,
+        is_admin: bool,
+    ) -> Result<Vec<CirclePostHistory>> {
+        let post = Self::get_post_simple(pool, post_id).await?;
+
+        if !is_admin && post.author_id != requester_id {
+            let role: Option<String> = sqlx::query_scalar(
+                "SELECT role FROM circle_members WHERE circle_id = ? AND user_id = ?",
+            )
+            .bind(post.circle_id.to_string())
+            .bind(requester_id.to_string())
+            .fetch_optional(pool)
+            .await?;
+
+            if !matches!(role.as_deref(), Some("owner") | Some("admin")) {
+                return Err(anyhow!("No permission to view post history"));
+            }
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, post_id, editor_id, title, content, images, created_at
+            FROM circle_post_history
+            WHERE post_id = ?
+            ORDER BY created_at DESC
+            "#,
+        )
+        .bind(post_id.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        rows.iter().map(parse_history_row).collect()
+    }
+
     pub async fn delete_post(pool: &DbPool, id: Uuid, user_id: Uuid, is_admin: bool) -> Result<()> {
         let mut tx = pool.begin().await?;
 
@@ -320,10 +382,10 @@ impl CirclePostService {
         pool: &DbPool,
         post_id: Uuid,
         user_id: Uuid,
-        dto: CreateCommentDto,
+        mut dto: CreateCommentDto,
     ) -> Result<PostComment> {
         // Check for sensitive words
-        Self::check_sensitive_words(pool, &dto.content).await?;
+        dto.content = filter_text(pool, ContentKind::Comment, &dto.content).await?;
 
         let mut tx = pool.begin().await?;
 
@@ -451,6 +513,65 @@ impl CirclePostService {
         Ok(())
     }
 
+    /// 圈子内某成员的帖子，圈主/圈管理员及平台管理员可看到已删除的帖子，成员本人只能看到正常帖子
+    pub async fn get_member_posts(
+        pool: &DbPool,
+        circle_id: Uuid,
+        member_user_id: Uuid,
+        requester_id: Uuid,
+        is_admin: bool,
+        page: i64,
+        page_size: i64,
+    ) -> Result<(Vec<CirclePost>, i64)> {
+        let is_moderator =
+            is_admin || Self::is_circle_moderator(pool, circle_id, requester_id).await?;
+        if !is_moderator && requester_id != member_user_id {
+            return Err(anyhow!("No permission to view this member's posts"));
+        }
+
+        let offset = (page - 1) * page_size;
+        let status_filter = if is_moderator {
+            ""
+        } else {
+            " AND status = 'active'"
+        };
+
+        let total: i64 = sqlx::query(&format!(
+            "SELECT COUNT(*) FROM circle_posts WHERE circle_id = ? AND author_id = ?{}",
+            status_filter
+        ))
+        .bind(circle_id.to_string())
+        .bind(member_user_id.to_string())
+        .fetch_one(pool)
+        .await?
+        .get::<i64, _>(0);
+
+        let rows = sqlx::query(&format!(
+            r#"
+            SELECT id, author_id, circle_id, title, content, images, likes, comments,
+                   status, edited_at, created_at, updated_at
+            FROM circle_posts
+            WHERE circle_id = ? AND author_id = ?{}
+            ORDER BY created_at DESC
+            LIMIT ? OFFSET ?
+            "#,
+            status_filter
+        ))
+        .bind(circle_id.to_string())
+        .bind(member_user_id.to_string())
+        .bind(page_size)
+        .bind(offset)
+        .fetch_all(pool)
+        .await?;
+
+        let posts = rows
+            .iter()
+            .map(parse_post_row)
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok((posts, total))
+    }
+
     // Helper methods
     async fn is_circle_member(pool: &DbPool, circle_id: Uuid, user_id: Uuid) -> Result<bool> {
         let result =
@@ -463,11 +584,23 @@ impl CirclePostService {
         Ok(result.is_some())
     }
 
+    async fn is_circle_moderator(pool: &DbPool, circle_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "SELECT id FROM circle_members WHERE circle_id = ? AND user_id = ? AND role IN ('owner', 'admin')",
+        )
+        .bind(circle_id.to_string())
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(result.is_some())
+    }
+
     async fn get_post_simple(pool: &DbPool, id: Uuid) -> Result<CirclePost> {
         let row = sqlx::query(
             r#"
             SELECT id, author_id, circle_id, title, content, images, likes, comments,
-                   status, created_at, updated_at
+                   status, edited_at, created_at, updated_at
             FROM circle_posts
             WHERE id = ?
             "#,
@@ -479,25 +612,6 @@ impl CirclePostService {
         parse_post_row(&row)
     }
 
-    async fn check_sensitive_words(pool: &DbPool, text: &str) -> Result<()> {
-        let sensitive_words: Vec<String> =
-            sqlx::query("SELECT word FROM sensitive_words WHERE is_active = TRUE")
-                .fetch_all(pool)
-                .await?
-                .into_iter()
-                .map(|row| row.get::<String, _>("word"))
-                .collect::<Vec<String>>();
-
-        let lower_text = text.to_lowercase();
-        for word in sensitive_words {
-            if lower_text.contains(&word.to_lowercase()) {
-                return Err(anyhow!("Content contains sensitive words"));
-            }
-        }
-
-        Ok(())
-    }
-
     pub async fn get_user_posts(
         pool: &DbPool,
         user_id: Uuid,
@@ -539,6 +653,7 @@ fn parse_post_row(row: &sqlx::mysql::MySqlRow) -> Result<CirclePost> {
             "deleted" => PostStatus::Deleted,
             _ => return Err(anyhow!("Invalid post status")),
         },
+        edited_at: row.get("edited_at"),
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
     })
@@ -549,6 +664,7 @@ fn parse_post_with_author_row(row: &sqlx::mysql::MySqlRow) -> Result<CirclePostW
     let author_id_str: String = row.get("author_id");
     let circle_id_str: String = row.get("circle_id");
     let images: serde_json::Value = row.get("images");
+    let edited_at: Option<DateTime<Utc>> = row.get("edited_at");
 
     Ok(CirclePostWithAuthor {
         id: Uuid::parse_str(&id_str)?,
@@ -562,11 +678,30 @@ fn parse_post_with_author_row(row: &sqlx::mysql::MySqlRow) -> Result<CirclePostW
         likes: row.get("likes"),
         comments: row.get("comments"),
         is_liked: row.get("is_liked"),
+        is_edited: edited_at.is_some(),
+        edited_at,
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
     })
 }
 
+fn parse_history_row(row: &sqlx::mysql::MySqlRow) -> Result<CirclePostHistory> {
+    let id_str: String = row.get("id");
+    let post_id_str: String = row.get("post_id");
+    let editor_id_str: String = row.get("editor_id");
+    let images: serde_json::Value = row.get("images");
+
+    Ok(CirclePostHistory {
+        id: Uuid::parse_str(&id_str)?,
+        post_id: Uuid::parse_str(&post_id_str)?,
+        editor_id: Uuid::parse_str(&editor_id_str)?,
+        title: row.get("title"),
+        content: row.get("content"),
+        images: serde_json::from_value(images)?,
+        edited_at: row.get("created_at"),
+    })
+}
+
 fn parse_comment_row(row: &sqlx::mysql::MySqlRow) -> Result<PostComment> {
     let id_str: String = row.get("id");
     let post_id_str: String = row.get("post_id");
@@ -1,7 +1,8 @@
 use crate::config::database::DbPool;
 use crate::models::{
-    Circle, CircleListItem, CircleMemberInfo, CircleWithMemberInfo, CreateCircleDto, MemberRole,
-    UpdateCircleDto, UpdateMemberRoleDto,
+    Circle, CircleJoinRequest, CircleListItem, CircleMemberInfo, CircleWithMemberInfo,
+    CreateCircleDto, JoinRequestApprovalResult, JoinRequestStatus, MemberRole, UpdateCircleDto,
+    UpdateMemberRoleDto,
 };
 use anyhow::{anyhow, Result};
 use sqlx::{MySql, Row, Transaction};
@@ -21,8 +22,9 @@ impl CircleService {
         let circle_id = Uuid::new_v4();
         sqlx::query(
             r#"
-            INSERT INTO circles (id, name, description, avatar, category, creator_id, member_count)
-            VALUES (?, ?, ?, ?, ?, ?, 1)
+            INSERT INTO circles (id, name, description, avatar, category, creator_id, member_count,
+                                 requires_approval)
+            VALUES (?, ?, ?, ?, ?, ?, 1, ?)
             "#,
         )
         .bind(circle_id.to_string())
@@ -31,6 +33,7 @@ impl CircleService {
         .bind(&dto.avatar)
         .bind(&dto.category)
         .bind(creator_id.to_string())
+        .bind(dto.requires_approval)
         .execute(&mut *tx)
         .await?;
 
@@ -52,7 +55,7 @@ impl CircleService {
         let circle = sqlx::query(
             r#"
             SELECT id, name, description, avatar, category, creator_id, 
-                   member_count, post_count, is_active, created_at, updated_at
+                   member_count, post_count, is_active, requires_approval, created_at, updated_at
             FROM circles
             WHERE id = ?
             "#,
@@ -158,7 +161,8 @@ impl CircleService {
         let row = sqlx::query(
             r#"
             SELECT c.id, c.name, c.description, c.avatar, c.category, c.creator_id,
-                   c.member_count, c.post_count, c.is_active, c.created_at, c.updated_at,
+                   c.member_count, c.post_count, c.is_active, c.requires_approval,
+                   c.created_at, c.updated_at,
                    cm.id as member_id, cm.role as member_role
             FROM circles c
             LEFT JOIN circle_members cm ON c.id = cm.circle_id AND cm.user_id = ?
@@ -184,6 +188,7 @@ impl CircleService {
             member_count: row.get("member_count"),
             post_count: row.get("post_count"),
             is_active: row.get("is_active"),
+            requires_approval: row.get("requires_approval"),
             created_at: row.get("created_at"),
             updated_at: row.get("updated_at"),
         };
@@ -255,6 +260,14 @@ impl CircleService {
             first = false;
         }
 
+        if dto.requires_approval.is_some() {
+            if !first {
+                query.push_str(", ");
+            }
+            query.push_str("requires_approval = ?");
+            first = false;
+        }
+
         if first {
             return Err(anyhow!("No fields to update"));
         }
@@ -280,6 +293,10 @@ impl CircleService {
             query_builder = query_builder.bind(is_active);
         }
 
+        if let Some(requires_approval) = dto.requires_approval {
+            query_builder = query_builder.bind(requires_approval);
+        }
+
         query_builder = query_builder.bind(id.to_string());
 
         query_builder.execute(pool).await?;
@@ -310,7 +327,9 @@ impl CircleService {
         Ok(())
     }
 
-    pub async fn join_circle(pool: &DbPool, circle_id: Uuid, user_id: Uuid) -> Result<()> {
+    /// Returns `true` when the user joined directly, `false` when a join request
+    /// was submitted for a circle that requires approval.
+    pub async fn join_circle(pool: &DbPool, circle_id: Uuid, user_id: Uuid) -> Result<bool> {
         // Check if already joined
         let existing =
             sqlx::query("SELECT id FROM circle_members WHERE circle_id = ? AND user_id = ?")
@@ -323,6 +342,32 @@ impl CircleService {
             return Err(anyhow!("Already joined this circle"));
         }
 
+        let circle = Self::get_circle_simple(pool, circle_id).await?;
+        if circle.requires_approval {
+            let pending = sqlx::query(
+                "SELECT id FROM circle_join_requests WHERE circle_id = ? AND user_id = ? AND status = 'pending'",
+            )
+            .bind(circle_id.to_string())
+            .bind(user_id.to_string())
+            .fetch_optional(pool)
+            .await?;
+
+            if pending.is_some() {
+                return Err(anyhow!("Join request already pending"));
+            }
+
+            sqlx::query(
+                "INSERT INTO circle_join_requests (id, circle_id, user_id, status) VALUES (?, ?, ?, 'pending')",
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(circle_id.to_string())
+            .bind(user_id.to_string())
+            .execute(pool)
+            .await?;
+
+            return Ok(false);
+        }
+
         let mut tx = pool.begin().await?;
 
         // Add member
@@ -347,7 +392,7 @@ impl CircleService {
 
         tx.commit().await?;
 
-        Ok(())
+        Ok(true)
     }
 
     pub async fn leave_circle(pool: &DbPool, circle_id: Uuid, user_id: Uuid) -> Result<()> {
@@ -535,6 +580,123 @@ impl CircleService {
         Ok(())
     }
 
+    pub async fn get_join_requests(
+        pool: &DbPool,
+        circle_id: Uuid,
+        operator_id: Uuid,
+        is_admin: bool,
+    ) -> Result<Vec<CircleJoinRequest>> {
+        if !is_admin && !Self::is_circle_moderator(pool, circle_id, operator_id).await? {
+            return Err(anyhow!("No permission to manage join requests"));
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT r.id, r.circle_id, r.user_id, r.status, r.handled_by, r.handled_at,
+                   r.created_at, u.name as user_name
+            FROM circle_join_requests r
+            JOIN users u ON r.user_id = u.id
+            WHERE r.circle_id = ? AND r.status = 'pending'
+            ORDER BY r.created_at ASC
+            "#,
+        )
+        .bind(circle_id.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        rows.iter().map(parse_join_request_row).collect()
+    }
+
+    pub async fn bulk_approve_join_requests(
+        pool: &DbPool,
+        circle_id: Uuid,
+        request_ids: Vec<Uuid>,
+        operator_id: Uuid,
+        is_admin: bool,
+    ) -> Result<Vec<JoinRequestApprovalResult>> {
+        if !is_admin && !Self::is_circle_moderator(pool, circle_id, operator_id).await? {
+            return Err(anyhow!("No permission to manage join requests"));
+        }
+
+        let mut tx = pool.begin().await?;
+        let mut results = Vec::with_capacity(request_ids.len());
+        let mut joined = 0;
+
+        for request_id in request_ids {
+            let row = sqlx::query(
+                "SELECT user_id, status FROM circle_join_requests WHERE id = ? AND circle_id = ? FOR UPDATE",
+            )
+            .bind(request_id.to_string())
+            .bind(circle_id.to_string())
+            .fetch_optional(&mut *tx)
+            .await?;
+
+            let Some(row) = row else {
+                results.push(JoinRequestApprovalResult {
+                    request_id,
+                    approved: false,
+                    reason: Some("Join request not found".to_string()),
+                });
+                continue;
+            };
+
+            let status: String = row.get("status");
+            if status != "pending" {
+                results.push(JoinRequestApprovalResult {
+                    request_id,
+                    approved: false,
+                    reason: Some(format!("Join request already {}", status)),
+                });
+                continue;
+            }
+
+            let user_id: String = row.get("user_id");
+            let inserted = sqlx::query(
+                r#"
+                INSERT IGNORE INTO circle_members (id, circle_id, user_id, role)
+                VALUES (?, ?, ?, 'member')
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(circle_id.to_string())
+            .bind(&user_id)
+            .execute(&mut *tx)
+            .await?
+            .rows_affected();
+            joined += inserted as i32;
+
+            sqlx::query(
+                r#"
+                UPDATE circle_join_requests
+                SET status = 'approved', handled_by = ?, handled_at = CURRENT_TIMESTAMP
+                WHERE id = ?
+                "#,
+            )
+            .bind(operator_id.to_string())
+            .bind(request_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+
+            results.push(JoinRequestApprovalResult {
+                request_id,
+                approved: true,
+                reason: None,
+            });
+        }
+
+        if joined > 0 {
+            sqlx::query("UPDATE circles SET member_count = member_count + ? WHERE id = ?")
+                .bind(joined)
+                .bind(circle_id.to_string())
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        tx.commit().await?;
+
+        Ok(results)
+    }
+
     pub async fn get_user_circles(
         pool: &DbPool,
         user_id: Uuid,
@@ -625,11 +787,23 @@ impl CircleService {
         parse_member_role(&role_str)
     }
 
+    async fn is_circle_moderator(pool: &DbPool, circle_id: Uuid, user_id: Uuid) -> Result<bool> {
+        let result = sqlx::query(
+            "SELECT id FROM circle_members WHERE circle_id = ? AND user_id = ? AND role IN ('owner', 'admin')",
+        )
+        .bind(circle_id.to_string())
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(result.is_some())
+    }
+
     async fn get_circle_simple(pool: &DbPool, id: Uuid) -> Result<Circle> {
         let row = sqlx::query(
             r#"
             SELECT id, name, description, avatar, category, creator_id,
-                   member_count, post_count, is_active, created_at, updated_at
+                   member_count, post_count, is_active, requires_approval, created_at, updated_at
             FROM circles
             WHERE id = ?
             "#,
@@ -679,11 +853,36 @@ fn parse_circle_row(row: &sqlx::mysql::MySqlRow) -> Result<Circle> {
         member_count: row.get("member_count"),
         post_count: row.get("post_count"),
         is_active: row.get("is_active"),
+        requires_approval: row.get("requires_approval"),
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
     })
 }
 
+fn parse_join_request_row(row: &sqlx::mysql::MySqlRow) -> Result<CircleJoinRequest> {
+    let id_str: String = row.get("id");
+    let circle_id_str: String = row.get("circle_id");
+    let user_id_str: String = row.get("user_id");
+    let handled_by: Option<String> = row.get("handled_by");
+    let status_str: String = row.get("status");
+
+    Ok(CircleJoinRequest {
+        id: Uuid::parse_str(&id_str)?,
+        circle_id: Uuid::parse_str(&circle_id_str)?,
+        user_id: Uuid::parse_str(&user_id_str)?,
+        user_name: row.get("user_name"),
+        status: match status_str.as_str() {
+            "pending" => JoinRequestStatus::Pending,
+            "approved" => JoinRequestStatus::Approved,
+            "rejected" => JoinRequestStatus::Rejected,
+            _ => return Err(anyhow!("Invalid join request status: {}", status_str)),
+        },
+        handled_by: handled_by.map(|id| Uuid::parse_str(&id)).transpose()?,
+        handled_at: row.get("handled_at"),
+        created_at: row.get("created_at"),
+    })
+}
+
 fn parse_member_role(role_str: &str) -> Result<MemberRole> {
     match role_str {
         "owner" => Ok(MemberRole::Owner),
@@ -0,0 +1,130 @@
+use crate::config::database::DbPool;
+use crate::services::system_config_service::SystemConfigService;
+use crate::utils::errors::AppError;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// 清理任务的分批参数，取自 `cleanup` 分类的系统配置
+#[derive(Debug, Clone)]
+pub struct SweepSettings {
+    /// 每批最多处理的行数
+    pub batch_size: i64,
+    /// 两批之间的暂停时间，给其他写入让出锁
+    pub batch_pause: Duration,
+}
+
+impl Default for SweepSettings {
+    fn default() -> Self {
+        Self {
+            batch_size: 500,
+            batch_pause: Duration::from_millis(100),
+        }
+    }
+}
+
+impl SweepSettings {
+    pub async fn load(db: &DbPool) -> Result<Self, AppError> {
+        let default = Self::default();
+
+        let batch_size =
+            SystemConfigService::get_i64(db, "cleanup", "batch_size", default.batch_size).await?;
+        let batch_pause_ms = SystemConfigService::get_i64(
+            db,
+            "cleanup",
+            "batch_pause_ms",
+            default.batch_pause.as_millis() as i64,
+        )
+        .await?;
+
+        Ok(Self {
+            batch_size: batch_size.max(1),
+            batch_pause: Duration::from_millis(batch_pause_ms.max(0) as u64),
+        })
+    }
+}
+
+/// 最近一次清理的统计
+#[derive(Debug, Clone, Serialize)]
+pub struct SweepMetrics {
+    pub rows: u64,
+    pub batches: u64,
+    pub batch_size: i64,
+    pub duration_ms: u64,
+    pub finished_at: DateTime<Utc>,
+}
+
+static LAST_SWEEPS: OnceLock<Mutex<BTreeMap<String, SweepMetrics>>> = OnceLock::new();
+
+pub struct CleanupService;
+
+impl CleanupService {
+    /// 分批执行一次清理：`step` 每次最多处理 `batch_size` 行并返回处理数，
+    /// 不足一批时说明已清理完毕。返回处理的总行数，并记录本次统计。
+    pub async fn run_batched<F, Fut>(
+        name: &str,
+        settings: &SweepSettings,
+        mut step: F,
+    ) -> Result<u64, AppError>
+    where
+        F: FnMut(i64) -> Fut,
+        Fut: Future<Output = Result<u64, AppError>>,
+    {
+        let started = Instant::now();
+        let mut rows = 0;
+        let mut batches = 0;
+
+        loop {
+            let processed = step(settings.batch_size).await?;
+            rows += processed;
+            batches += 1;
+
+            if processed < settings.batch_size as u64 {
+                break;
+            }
+            if !settings.batch_pause.is_zero() {
+                tokio::time::sleep(settings.batch_pause).await;
+            }
+        }
+
+        let metrics = SweepMetrics {
+            rows,
+            batches,
+            batch_size: settings.batch_size,
+            duration_ms: started.elapsed().as_millis() as u64,
+            finished_at: Utc::now(),
+        };
+        if rows > 0 {
+            tracing::info!(
+                "Sweep {} removed {} rows in {} batches ({} ms)",
+                name,
+                metrics.rows,
+                metrics.batches,
+                metrics.duration_ms
+            );
+        }
+        Self::sweeps()
+            .lock()
+            .unwrap()
+            .insert(name.to_string(), metrics);
+
+        Ok(rows)
+    }
+
+    /// 指定清理任务最近一次运行的统计
+    pub fn last_sweep(name: &str) -> Option<SweepMetrics> {
+        Self::sweeps().lock().unwrap().get(name).cloned()
+    }
+
+    /// 所有清理任务最近一次运行的统计
+    pub fn sweep_metrics() -> BTreeMap<String, SweepMetrics> {
+        Self::sweeps().lock().unwrap().clone()
+    }
+
+    fn sweeps() -> &'static Mutex<BTreeMap<String, SweepMetrics>> {
+        LAST_SWEEPS.get_or_init(|| Mutex::new(BTreeMap::new()))
+    }
+}
@@ -1,9 +1,33 @@
-use crate::{config::database::DbPool, models::content::*};
+use crate::{
+    config::database::DbPool,
+    models::{content::*, notification::*},
+    services::{
+        notification_service::NotificationService, system_config_service::SystemConfigService,
+    },
+};
 use anyhow::{anyhow, Result};
 use chrono::Utc;
 use serde_json::to_string;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
 use uuid::Uuid;
 
+/// Shortest search term the ngram FULLTEXT parser can match (`ngram_token_size`)
+const FULLTEXT_MIN_TERM_CHARS: usize = 2;
+
+/// Returns the term to rank by when `sort=relevance` and the term is long
+/// enough for fulltext matching; otherwise the caller falls back to LIKE.
+fn relevance_search_term(sort: Option<&str>, search: &Option<String>) -> Option<String> {
+    match (sort, search) {
+        (Some("relevance"), Some(term))
+            if term.trim().chars().count() >= FULLTEXT_MIN_TERM_CHARS =>
+        {
+            Some(term.trim().to_string())
+        }
+        _ => None,
+    }
+}
+
 // Article services
 pub async fn list_articles(
     pool: &DbPool,
@@ -12,39 +36,67 @@ pub async fn list_articles(
     category: Option<String>,
     status: Option<String>,
     search: Option<String>,
+    sort: Option<String>,
 ) -> Result<Vec<ArticleListItem>> {
     let offset = (page - 1) * per_page;
+    let relevance_term = relevance_search_term(sort.as_deref(), &search);
 
-    let mut query = String::from(
+    let score_column = if relevance_term.is_some() {
+        ", MATCH(title, summary, content) AGAINST (? IN NATURAL LANGUAGE MODE) AS relevance_score"
+    } else {
+        ""
+    };
+
+    let mut query = format!(
         r#"
         SELECT id, title, cover_image, summary, author_name, category, 
-               view_count, status, published_at, created_at
+               view_count, status, published_at, created_at{}
         FROM articles
         WHERE 1=1
     "#,
+        score_column
     );
 
-    if let Some(cat) = &category {
-        query.push_str(&format!(" AND category = '{}'", cat));
+    let mut params = vec![];
+    if let Some(term) = &relevance_term {
+        params.push(term.clone());
     }
 
-    if let Some(s) = &status {
-        query.push_str(&format!(" AND status = '{}'", s));
+    if let Some(cat) = category {
+        query.push_str(" AND category = ?");
+        params.push(cat);
     }
 
-    if let Some(search_term) = &search {
-        query.push_str(&format!(
-            " AND (title LIKE '%{}%' OR summary LIKE '%{}%')",
-            search_term, search_term
-        ));
+    if let Some(s) = status {
+        query.push_str(" AND status = ?");
+        params.push(s);
     }
 
-    query.push_str(&format!(
-        " ORDER BY published_at DESC, created_at DESC LIMIT {} OFFSET {}",
-        per_page, offset
-    ));
+    if let Some(term) = &relevance_term {
+        query.push_str(" AND MATCH(title, summary, content) AGAINST (? IN NATURAL LANGUAGE MODE)");
+        params.push(term.clone());
+    } else if let Some(search_term) = &search {
+        query.push_str(" AND (title LIKE ? OR summary LIKE ?)");
+        let like_pattern = format!("%{}%", search_term);
+        params.push(like_pattern.clone());
+        params.push(like_pattern);
+    }
 
-    let rows = sqlx::query(&query)
+    let order_by = if relevance_term.is_some() {
+        "relevance_score DESC, published_at DESC, created_at DESC"
+    } else {
+        "published_at DESC, created_at DESC"
+    };
+    query.push_str(&format!(" ORDER BY {} LIMIT ? OFFSET ?", order_by));
+
+    let mut query_builder = sqlx::query(&query);
+    for param in params {
+        query_builder = query_builder.bind(param);
+    }
+
+    let rows = query_builder
+        .bind(per_page)
+        .bind(offset)
         .fetch_all(pool)
         .await
         .map_err(|e| anyhow!("Failed to fetch articles: {}", e))?;
@@ -57,11 +109,100 @@ pub async fn list_articles(
     Ok(articles)
 }
 
-pub async fn get_article_by_id(pool: &DbPool, id: Uuid) -> Result<Article> {
+/// Fetches an article. Only reader-facing fetches should pass `record_view`;
+/// internal reads (edits, status changes) must not inflate the counter.
+/// `viewer_id` fills in `has_liked` for a signed-in reader.
+pub async fn get_article_by_id(
+    pool: &DbPool,
+    id: Uuid,
+    record_view: bool,
+    viewer_id: Option<Uuid>,
+) -> Result<Article> {
+    let mut article = fetch_article(pool, id).await?;
+
+    // Views are buffered in memory and flushed periodically; include the
+    // unflushed ones so the reader sees their own view counted
+    if record_view {
+        article.view_count += record_article_view(id);
+    }
+
+    if let Some(viewer_id) = viewer_id {
+        let liked: Option<String> =
+            sqlx::query_scalar("SELECT id FROM article_likes WHERE article_id = ? AND user_id = ?")
+                .bind(id.to_string())
+                .bind(viewer_id.to_string())
+                .fetch_optional(pool)
+                .await
+                .map_err(|e| anyhow!("Failed to check article like: {}", e))?;
+        article.has_liked = Some(liked.is_some());
+    }
+
+    Ok(article)
+}
+
+/// Article views recorded since the last flush. Counts are per process, so
+/// every instance flushes its own buffer.
+static PENDING_ARTICLE_VIEWS: OnceLock<Mutex<HashMap<Uuid, u32>>> = OnceLock::new();
+
+fn pending_article_views() -> &'static Mutex<HashMap<Uuid, u32>> {
+    PENDING_ARTICLE_VIEWS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// Buffers one view of the article and returns its unflushed view count.
+fn record_article_view(id: Uuid) -> u32 {
+    let mut pending = pending_article_views().lock().unwrap();
+    let count = pending.entry(id).or_insert(0);
+    *count += 1;
+    *count
+}
+
+/// Writes buffered article views to the database with one update per article
+/// and returns the number of articles updated. Counts that fail to write are
+/// put back for the next flush.
+pub async fn flush_article_views(pool: &DbPool) -> Result<u64> {
+    let pending: Vec<(Uuid, u32)> = pending_article_views().lock().unwrap().drain().collect();
+
+    for (index, (id, count)) in pending.iter().enumerate() {
+        let result = sqlx::query("UPDATE articles SET view_count = view_count + ? WHERE id = ?")
+            .bind(*count)
+            .bind(id.to_string())
+            .execute(pool)
+            .await;
+
+        if let Err(e) = result {
+            let mut buffer = pending_article_views().lock().unwrap();
+            for (id, count) in &pending[index..] {
+                *buffer.entry(*id).or_insert(0) += count;
+            }
+            return Err(anyhow!("Failed to flush article views: {}", e));
+        }
+    }
+
+    Ok(pending.len() as u64)
+}
+
+/// Returns an article in any status for its author or an admin to preview,
+/// without counting the request as a view.
+pub async fn get_article_preview(
+    pool: &DbPool,
+    id: Uuid,
+    requester_id: Uuid,
+    requester_role: &str,
+) -> Result<Article> {
+    let article = fetch_article(pool, id).await?;
+    if article.author_id != requester_id && requester_role != "admin" {
+        return Err(anyhow!("Insufficient permissions"));
+    }
+
+    Ok(article)
+}
+
+async fn fetch_article(pool: &DbPool, id: Uuid) -> Result<Article> {
     let query = r#"
         SELECT id, title, cover_image, summary, content, author_id, author_name, 
                author_type, category, tags, view_count, like_count, status, 
-               publish_channels, published_at, created_at, updated_at
+               publish_channels, scheduled_publish_at, review_notes, published_at,
+               created_at, updated_at
         FROM articles
         WHERE id = ?
     "#;
@@ -72,12 +213,6 @@ pub async fn get_article_by_id(pool: &DbPool, id: Uuid) -> Result<Article> {
         .await
         .map_err(|e| anyhow!("Article not found: {}", e))?;
 
-    // Increment view count
-    sqlx::query("UPDATE articles SET view_count = view_count + 1 WHERE id = ?")
-        .bind(id.to_string())
-        .execute(pool)
-        .await?;
-
     parse_article_from_row(&row)
 }
 
@@ -106,8 +241,8 @@ pub async fn create_article(
     let query = r#"
         INSERT INTO articles (id, title, cover_image, summary, content, author_id, 
                             author_name, author_type, category, tags, status, 
-                            publish_channels, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'draft', ?, ?, ?)
+                            publish_channels, scheduled_publish_at, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'draft', ?, ?, ?, ?)
     "#;
 
     sqlx::query(query)
@@ -122,13 +257,14 @@ pub async fn create_article(
         .bind(&dto.category)
         .bind(tags_json)
         .bind(channels_json)
+        .bind(dto.scheduled_publish_at)
         .bind(now)
         .bind(now)
         .execute(pool)
         .await
         .map_err(|e| anyhow!("Failed to create article: {}", e))?;
 
-    get_article_by_id(pool, article_id).await
+    get_article_by_id(pool, article_id, false, None).await
 }
 
 pub async fn update_article(
@@ -139,7 +275,7 @@ pub async fn update_article(
     dto: UpdateArticleDto,
 ) -> Result<Article> {
     // Check permissions
-    let existing = get_article_by_id(pool, id).await?;
+    let existing = get_article_by_id(pool, id, false, None).await?;
     if existing.author_id != author_id && author_role != "admin" {
         return Err(anyhow!("Insufficient permissions"));
     }
@@ -181,7 +317,7 @@ pub async fn update_article(
     update_fields.push("updated_at = ?");
 
     if update_fields.is_empty() {
-        return get_article_by_id(pool, id).await;
+        return get_article_by_id(pool, id, false, None).await;
     }
 
     let query = format!(
@@ -221,7 +357,7 @@ pub async fn update_article(
         .await
         .map_err(|e| anyhow!("Failed to update article: {}", e))?;
 
-    get_article_by_id(pool, id).await
+    get_article_by_id(pool, id, false, None).await
 }
 
 pub async fn publish_article(
@@ -232,7 +368,7 @@ pub async fn publish_article(
     dto: PublishArticleDto,
 ) -> Result<Article> {
     // Check permissions
-    let existing = get_article_by_id(pool, id).await?;
+    let existing = get_article_by_id(pool, id, false, None).await?;
     if existing.author_id != author_id && author_role != "admin" {
         return Err(anyhow!("Insufficient permissions"));
     }
@@ -240,9 +376,55 @@ pub async fn publish_article(
     let channels_json = to_string(&dto.publish_channels).unwrap_or_else(|_| "[]".to_string());
     let now = Utc::now();
 
+    // A future schedule keeps the article in draft until `publish_scheduled` picks it up
+    if let Some(scheduled_at) = dto.scheduled_publish_at.filter(|at| *at > now) {
+        if !matches!(existing.status, ContentStatus::Draft) {
+            return Err(anyhow!("Only draft articles can be scheduled"));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE articles
+            SET publish_channels = ?, scheduled_publish_at = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(channels_json)
+        .bind(scheduled_at)
+        .bind(now)
+        .bind(id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to schedule article: {}", e))?;
+
+        return get_article_by_id(pool, id, false, None).await;
+    }
+
+    // Publishing now clears any pending schedule. With approval required the
+    // article waits for an admin instead of going live
+    if publish_requires_approval(pool, author_role).await? {
+        sqlx::query(
+            r#"
+            UPDATE articles
+            SET status = 'pending_review', publish_channels = ?, scheduled_publish_at = NULL,
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(channels_json)
+        .bind(now)
+        .bind(id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to submit article for review: {}", e))?;
+
+        return get_article_by_id(pool, id, false, None).await;
+    }
+
     let query = r#"
         UPDATE articles 
-        SET status = 'published', publish_channels = ?, published_at = ?, updated_at = ?
+        SET status = 'published', publish_channels = ?, scheduled_publish_at = NULL,
+            published_at = ?, updated_at = ?
         WHERE id = ?
     "#;
 
@@ -255,7 +437,7 @@ pub async fn publish_article(
         .await
         .map_err(|e| anyhow!("Failed to publish article: {}", e))?;
 
-    get_article_by_id(pool, id).await
+    get_article_by_id(pool, id, false, None).await
 }
 
 pub async fn unpublish_article(
@@ -265,7 +447,7 @@ pub async fn unpublish_article(
     author_role: &str,
 ) -> Result<Article> {
     // Check permissions
-    let existing = get_article_by_id(pool, id).await?;
+    let existing = get_article_by_id(pool, id, false, None).await?;
     if existing.author_id != author_id && author_role != "admin" {
         return Err(anyhow!("Insufficient permissions"));
     }
@@ -283,7 +465,7 @@ pub async fn unpublish_article(
         .await
         .map_err(|e| anyhow!("Failed to unpublish article: {}", e))?;
 
-    get_article_by_id(pool, id).await
+    get_article_by_id(pool, id, false, None).await
 }
 
 pub async fn delete_article(
@@ -293,7 +475,7 @@ pub async fn delete_article(
     author_role: &str,
 ) -> Result<()> {
     // Check permissions
-    let existing = get_article_by_id(pool, id).await?;
+    let existing = get_article_by_id(pool, id, false, None).await?;
     if existing.author_id != author_id && author_role != "admin" {
         return Err(anyhow!("Insufficient permissions"));
     }
@@ -309,6 +491,258 @@ pub async fn delete_article(
     Ok(())
 }
 
+/// Likes an article for the user. Liking it again is a no-op, enforced by the
+/// unique key so concurrent requests cannot double count.
+pub async fn like_article(
+    pool: &DbPool,
+    user_id: Uuid,
+    article_id: Uuid,
+) -> Result<ArticleLikeStatus> {
+    let mut tx = pool.begin().await?;
+
+    let like_count: Option<i32> =
+        sqlx::query_scalar("SELECT like_count FROM articles WHERE id = ? FOR UPDATE")
+            .bind(article_id.to_string())
+            .fetch_optional(&mut *tx)
+            .await?;
+    let mut like_count = like_count.ok_or_else(|| anyhow!("Article not found"))?;
+
+    let result =
+        sqlx::query("INSERT IGNORE INTO article_likes (id, article_id, user_id) VALUES (?, ?, ?)")
+            .bind(Uuid::new_v4().to_string())
+            .bind(article_id.to_string())
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to like article: {}", e))?;
+
+    if result.rows_affected() == 1 {
+        sqlx::query("UPDATE articles SET like_count = like_count + 1 WHERE id = ?")
+            .bind(article_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        like_count += 1;
+    }
+
+    tx.commit().await?;
+
+    Ok(ArticleLikeStatus {
+        liked: true,
+        like_count: like_count as u32,
+    })
+}
+
+/// Removes the user's like from an article; unliking an article that was not
+/// liked is a no-op.
+pub async fn unlike_article(
+    pool: &DbPool,
+    user_id: Uuid,
+    article_id: Uuid,
+) -> Result<ArticleLikeStatus> {
+    let mut tx = pool.begin().await?;
+
+    let like_count: Option<i32> =
+        sqlx::query_scalar("SELECT like_count FROM articles WHERE id = ? FOR UPDATE")
+            .bind(article_id.to_string())
+            .fetch_optional(&mut *tx)
+            .await?;
+    let mut like_count = like_count.ok_or_else(|| anyhow!("Article not found"))?;
+
+    let result = sqlx::query("DELETE FROM article_likes WHERE article_id = ? AND user_id = ?")
+        .bind(article_id.to_string())
+        .bind(user_id.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to unlike article: {}", e))?;
+
+    if result.rows_affected() == 1 && like_count > 0 {
+        sqlx::query("UPDATE articles SET like_count = like_count - 1 WHERE id = ?")
+            .bind(article_id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        like_count -= 1;
+    }
+
+    tx.commit().await?;
+
+    Ok(ArticleLikeStatus {
+        liked: false,
+        like_count: like_count as u32,
+    })
+}
+
+/// Doctors' articles and videos wait for admin approval when `content/require_publish_approval`
+/// is on. Admins publish directly since they are the reviewers.
+async fn publish_requires_approval(pool: &DbPool, author_role: &str) -> Result<bool> {
+    if author_role == "admin" {
+        return Ok(false);
+    }
+
+    SystemConfigService::get_bool(pool, "content", "require_publish_approval", false)
+        .await
+        .map_err(|e| anyhow!("Failed to load content config: {}", e))
+}
+
+/// Approves content waiting for review and publishes it.
+pub async fn approve_content(
+    pool: &DbPool,
+    kind: ContentKind,
+    id: Uuid,
+    reviewer_id: Uuid,
+    notes: Option<String>,
+) -> Result<()> {
+    review_content(pool, kind, id, reviewer_id, true, notes).await
+}
+
+/// Rejects content waiting for review and returns it to draft; the notes tell the author why.
+pub async fn reject_content(
+    pool: &DbPool,
+    kind: ContentKind,
+    id: Uuid,
+    reviewer_id: Uuid,
+    notes: Option<String>,
+) -> Result<()> {
+    let notes = notes.filter(|n| !n.trim().is_empty());
+    if notes.is_none() {
+        return Err(anyhow!("Review notes are required when rejecting content"));
+    }
+
+    review_content(pool, kind, id, reviewer_id, false, notes).await
+}
+
+async fn review_content(
+    pool: &DbPool,
+    kind: ContentKind,
+    id: Uuid,
+    reviewer_id: Uuid,
+    approved: bool,
+    notes: Option<String>,
+) -> Result<()> {
+    let (table, label) = match kind {
+        ContentKind::Article => ("articles", "文章"),
+        ContentKind::Video => ("videos", "视频"),
+    };
+
+    let row: Option<(String, String)> = sqlx::query_as(&format!(
+        "SELECT author_id, title FROM {} WHERE id = ?",
+        table
+    ))
+    .bind(id.to_string())
+    .fetch_optional(pool)
+    .await?;
+    let (author_id, title) = row.ok_or_else(|| anyhow!("Content not found"))?;
+
+    let now = Utc::now();
+    let (status, published_at) = if approved {
+        ("published", Some(now))
+    } else {
+        ("draft", None)
+    };
+
+    let result = sqlx::query(&format!(
+        r#"
+        UPDATE {}
+        SET status = ?, published_at = COALESCE(?, published_at), review_notes = ?,
+            reviewed_by = ?, reviewed_at = ?, updated_at = ?
+        WHERE id = ? AND status = 'pending_review'
+        "#,
+        table
+    ))
+    .bind(status)
+    .bind(published_at)
+    .bind(&notes)
+    .bind(reviewer_id.to_string())
+    .bind(now)
+    .bind(now)
+    .bind(id.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Failed to review content: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(anyhow!("Content is not pending review"));
+    }
+
+    let (notification_title, content) = if approved {
+        (
+            "内容审核通过",
+            format!("你的{}《{}》已通过审核并发布", label, title),
+        )
+    } else {
+        (
+            "内容审核未通过",
+            format!(
+                "你的{}《{}》未通过审核，已退回草稿：{}",
+                label,
+                title,
+                notes.as_deref().unwrap_or_default()
+            ),
+        )
+    };
+    let author_id = Uuid::parse_str(&author_id)?;
+    if let Err(e) = NotificationService::create_notification(
+        pool,
+        CreateNotificationDto {
+            user_id: author_id,
+            notification_type: NotificationType::SystemAnnouncement,
+            title: notification_title.to_string(),
+            content,
+            related_id: Some(id),
+            metadata: Some(serde_json::json!({
+                "content_type": match kind {
+                    ContentKind::Article => "article",
+                    ContentKind::Video => "video",
+                },
+                "approved": approved,
+                "notes": notes,
+            })),
+        },
+    )
+    .await
+    {
+        tracing::warn!(
+            "Failed to notify author {} of content review: {}",
+            author_id,
+            e
+        );
+    }
+
+    Ok(())
+}
+
+/// Publishes drafts whose scheduled time has passed and returns how many were
+/// processed. Doctors' content goes to review instead when approval is required.
+pub async fn publish_scheduled(pool: &DbPool) -> Result<u64> {
+    let requires_approval = publish_requires_approval(pool, "doctor").await?;
+    let now = Utc::now();
+
+    let mut processed = 0;
+    for table in ["articles", "videos"] {
+        let result = sqlx::query(&format!(
+            r#"
+            UPDATE {}
+            SET status = IF(author_type = 'doctor' AND ?, 'pending_review', 'published'),
+                published_at = IF(author_type = 'doctor' AND ?, published_at, ?),
+                scheduled_publish_at = NULL, updated_at = ?
+            WHERE status = 'draft' AND scheduled_publish_at <= ?
+            "#,
+            table
+        ))
+        .bind(requires_approval)
+        .bind(requires_approval)
+        .bind(now)
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to publish scheduled {}: {}", table, e))?;
+
+        processed += result.rows_affected();
+    }
+
+    Ok(processed)
+}
+
 // Video services
 pub async fn list_videos(
     pool: &DbPool,
@@ -317,39 +751,67 @@ pub async fn list_videos(
     category: Option<String>,
     status: Option<String>,
     search: Option<String>,
+    sort: Option<String>,
 ) -> Result<Vec<VideoListItem>> {
     let offset = (page - 1) * per_page;
+    let relevance_term = relevance_search_term(sort.as_deref(), &search);
 
-    let mut query = String::from(
+    let score_column = if relevance_term.is_some() {
+        ", MATCH(title, description) AGAINST (? IN NATURAL LANGUAGE MODE) AS relevance_score"
+    } else {
+        ""
+    };
+
+    let mut query = format!(
         r#"
         SELECT id, title, cover_image, video_url, duration, author_name, 
-               category, view_count, status, published_at, created_at
+               category, view_count, status, published_at, created_at{}
         FROM videos
         WHERE 1=1
     "#,
+        score_column
     );
 
-    if let Some(cat) = &category {
-        query.push_str(&format!(" AND category = '{}'", cat));
+    let mut params = vec![];
+    if let Some(term) = &relevance_term {
+        params.push(term.clone());
     }
 
-    if let Some(s) = &status {
-        query.push_str(&format!(" AND status = '{}'", s));
+    if let Some(cat) = category {
+        query.push_str(" AND category = ?");
+        params.push(cat);
     }
 
-    if let Some(search_term) = &search {
-        query.push_str(&format!(
-            " AND (title LIKE '%{}%' OR description LIKE '%{}%')",
-            search_term, search_term
-        ));
+    if let Some(s) = status {
+        query.push_str(" AND status = ?");
+        params.push(s);
     }
 
-    query.push_str(&format!(
-        " ORDER BY published_at DESC, created_at DESC LIMIT {} OFFSET {}",
-        per_page, offset
-    ));
+    if let Some(term) = &relevance_term {
+        query.push_str(" AND MATCH(title, description) AGAINST (? IN NATURAL LANGUAGE MODE)");
+        params.push(term.clone());
+    } else if let Some(search_term) = &search {
+        query.push_str(" AND (title LIKE ? OR description LIKE ?)");
+        let like_pattern = format!("%{}%", search_term);
+        params.push(like_pattern.clone());
+        params.push(like_pattern);
+    }
 
-    let rows = sqlx::query(&query)
+    let order_by = if relevance_term.is_some() {
+        "relevance_score DESC, published_at DESC, created_at DESC"
+    } else {
+        "published_at DESC, created_at DESC"
+    };
+    query.push_str(&format!(" ORDER BY {} LIMIT ? OFFSET ?", order_by));
+
+    let mut query_builder = sqlx::query(&query);
+    for param in params {
+        query_builder = query_builder.bind(param);
+    }
+
+    let rows = query_builder
+        .bind(per_page)
+        .bind(offset)
         .fetch_all(pool)
         .await
         .map_err(|e| anyhow!("Failed to fetch videos: {}", e))?;
@@ -366,7 +828,8 @@ pub async fn get_video_by_id(pool: &DbPool, id: Uuid) -> Result<Video> {
     let query = r#"
         SELECT id, title, cover_image, video_url, duration, file_size, description,
                author_id, author_name, author_type, category, tags, view_count, 
-               like_count, status, publish_channels, published_at, created_at, updated_at
+               like_count, status, publish_channels, scheduled_publish_at, review_notes,
+               published_at, created_at, updated_at
         FROM videos
         WHERE id = ?
     "#;
@@ -411,8 +874,9 @@ pub async fn create_video(
     let query = r#"
         INSERT INTO videos (id, title, cover_image, video_url, duration, file_size,
                           description, author_id, author_name, author_type, category, 
-                          tags, status, publish_channels, created_at, updated_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'draft', ?, ?, ?)
+                          tags, status, publish_channels, scheduled_publish_at, created_at,
+                          updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, 'draft', ?, ?, ?, ?)
     "#;
 
     sqlx::query(query)
@@ -429,6 +893,7 @@ pub async fn create_video(
         .bind(&dto.category)
         .bind(tags_json)
         .bind(channels_json)
+        .bind(dto.scheduled_publish_at)
         .bind(now)
         .bind(now)
         .execute(pool)
@@ -563,9 +1028,55 @@ pub async fn publish_video(
     let channels_json = to_string(&dto.publish_channels).unwrap_or_else(|_| "[]".to_string());
     let now = Utc::now();
 
+    // A future schedule keeps the video in draft until `publish_scheduled` picks it up
+    if let Some(scheduled_at) = dto.scheduled_publish_at.filter(|at| *at > now) {
+        if !matches!(existing.status, VideoStatus::Draft) {
+            return Err(anyhow!("Only draft videos can be scheduled"));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE videos
+            SET publish_channels = ?, scheduled_publish_at = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(channels_json)
+        .bind(scheduled_at)
+        .bind(now)
+        .bind(id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to schedule video: {}", e))?;
+
+        return get_video_by_id(pool, id).await;
+    }
+
+    // Publishing now clears any pending schedule. With approval required the
+    // video waits for an admin instead of going live
+    if publish_requires_approval(pool, author_role).await? {
+        sqlx::query(
+            r#"
+            UPDATE videos
+            SET status = 'pending_review', publish_channels = ?, scheduled_publish_at = NULL,
+                updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(channels_json)
+        .bind(now)
+        .bind(id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to submit video for review: {}", e))?;
+
+        return get_video_by_id(pool, id).await;
+    }
+
     let query = r#"
         UPDATE videos 
-        SET status = 'published', publish_channels = ?, published_at = ?, updated_at = ?
+        SET status = 'published', publish_channels = ?, scheduled_publish_at = NULL,
+            published_at = ?, updated_at = ?
         WHERE id = ?
     "#;
 
@@ -617,13 +1128,18 @@ pub async fn list_categories(
     "#,
     );
 
-    if let Some(ct) = &content_type {
-        query.push_str(&format!(" AND (type = '{}' OR type = 'both')", ct));
+    if content_type.is_some() {
+        query.push_str(" AND (type = ? OR type = 'both')");
     }
 
     query.push_str(" ORDER BY sort_order ASC, name ASC");
 
-    let rows = sqlx::query(&query)
+    let mut query_builder = sqlx::query(&query);
+    if let Some(ct) = content_type {
+        query_builder = query_builder.bind(ct);
+    }
+
+    let rows = query_builder
         .fetch_all(pool)
         .await
         .map_err(|e| anyhow!("Failed to fetch categories: {}", e))?;
@@ -686,6 +1202,229 @@ async fn get_category_by_id(pool: &DbPool, id: Uuid) -> Result<ContentCategory>
     parse_category_from_row(&row)
 }
 
+// Recommendation services
+const RECOMMENDATION_CTE: &str = r#"
+    WITH feed AS (
+        SELECT 'article' AS content_type, id, title, cover_image, summary, author_id,
+               author_name, category, view_count, like_count,
+               COALESCE(published_at, created_at) AS published_at
+        FROM articles
+        WHERE status = 'published'
+        UNION ALL
+        SELECT 'video' AS content_type, id, title, cover_image, LEFT(description, 500) AS summary,
+               author_id, author_name, category, view_count, like_count,
+               COALESCE(published_at, created_at) AS published_at
+        FROM videos
+        WHERE status = 'published'
+    )
+"#;
+
+// Recency decays over 30 days, popularity is log-scaled so viral items don't swamp relevance
+const POPULARITY_SCORE: &str = r#"
+    GREATEST(0, 30 - DATEDIFF(NOW(), f.published_at)) * 0.5
+    + LOG10(1 + f.view_count + 2 * f.like_count) * 5
+"#;
+
+/// Personalized feed for a patient: content in categories mapped from the patient's own
+/// diagnoses and content authored by doctors the patient has seen, mixed with popular items.
+/// Falls back to the trending feed when the patient has no history.
+pub async fn get_recommended_content(
+    pool: &DbPool,
+    patient_id: Uuid,
+    page: u32,
+    per_page: u32,
+) -> Result<RecommendedFeed> {
+    let page = page.max(1);
+    let per_page = per_page.clamp(1, 50);
+    let offset = (page - 1) * per_page;
+
+    let history_count: i64 = sqlx::query_scalar(
+        r#"
+        SELECT (SELECT COUNT(*) FROM appointments WHERE patient_id = ? AND status != 'cancelled')
+             + (SELECT COUNT(*) FROM prescriptions WHERE patient_id = ?)
+        "#,
+    )
+    .bind(patient_id.to_string())
+    .bind(patient_id.to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Failed to load patient history: {}", e))?;
+
+    if history_count == 0 {
+        return get_trending_content(pool, page, per_page).await;
+    }
+
+    // Only the requesting patient's own appointments and prescriptions are consulted
+    let query = format!(
+        r#"
+        {cte},
+        patient_categories AS (
+            SELECT DISTINCT m.category
+            FROM diagnosis_category_mappings m
+            JOIN prescriptions p ON p.diagnosis LIKE CONCAT('%', m.keyword, '%')
+            WHERE p.patient_id = ?
+        ),
+        patient_authors AS (
+            SELECT d.user_id AS author_id, MAX(u.name) AS doctor_name
+            FROM appointments a
+            JOIN doctors d ON a.doctor_id = d.id
+            JOIN users u ON d.user_id = u.id
+            WHERE a.patient_id = ? AND a.status != 'cancelled'
+            GROUP BY d.user_id
+        )
+        SELECT f.*, pa.doctor_name, pc.category AS matched_category,
+               (CASE WHEN pa.author_id IS NOT NULL THEN 50 ELSE 0 END
+                + CASE WHEN pc.category IS NOT NULL THEN 30 ELSE 0 END
+                + {popularity}) AS score
+        FROM feed f
+        LEFT JOIN patient_authors pa ON pa.author_id = f.author_id
+        LEFT JOIN patient_categories pc ON pc.category = f.category
+        ORDER BY score DESC, f.published_at DESC, f.id ASC
+        LIMIT ? OFFSET ?
+        "#,
+        cte = RECOMMENDATION_CTE,
+        popularity = POPULARITY_SCORE,
+    );
+
+    let rows = sqlx::query(&query)
+        .bind(patient_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(per_page)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch recommendations: {}", e))?;
+
+    let mut items = Vec::new();
+    for row in rows {
+        items.push(parse_recommended_content_from_row(&row)?);
+    }
+
+    Ok(RecommendedFeed {
+        items,
+        page,
+        per_page,
+        personalized: true,
+    })
+}
+
+pub async fn get_trending_content(
+    pool: &DbPool,
+    page: u32,
+    per_page: u32,
+) -> Result<RecommendedFeed> {
+    let offset = (page.max(1) - 1) * per_page;
+
+    let query = format!(
+        r#"
+        {cte}
+        SELECT f.*, NULL AS doctor_name, NULL AS matched_category,
+               ({popularity}) AS score
+        FROM feed f
+        ORDER BY score DESC, f.published_at DESC, f.id ASC
+        LIMIT ? OFFSET ?
+        "#,
+        cte = RECOMMENDATION_CTE,
+        popularity = POPULARITY_SCORE,
+    );
+
+    let rows = sqlx::query(&query)
+        .bind(per_page)
+        .bind(offset)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch trending content: {}", e))?;
+
+    let mut items = Vec::new();
+    for row in rows {
+        items.push(parse_recommended_content_from_row(&row)?);
+    }
+
+    Ok(RecommendedFeed {
+        items,
+        page: page.max(1),
+        per_page,
+        personalized: false,
+    })
+}
+
+pub async fn list_diagnosis_mappings(pool: &DbPool) -> Result<Vec<DiagnosisCategoryMapping>> {
+    let query = r#"
+        SELECT id, keyword, category, created_at, updated_at
+        FROM diagnosis_category_mappings
+        ORDER BY category ASC, keyword ASC
+    "#;
+
+    let rows = sqlx::query(query)
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch diagnosis mappings: {}", e))?;
+
+    let mut mappings = Vec::new();
+    for row in rows {
+        mappings.push(parse_diagnosis_mapping_from_row(&row)?);
+    }
+
+    Ok(mappings)
+}
+
+pub async fn create_diagnosis_mapping(
+    pool: &DbPool,
+    dto: CreateDiagnosisMappingDto,
+) -> Result<DiagnosisCategoryMapping> {
+    let mapping_id = Uuid::new_v4();
+    let now = Utc::now();
+
+    let query = r#"
+        INSERT INTO diagnosis_category_mappings (id, keyword, category, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?)
+    "#;
+
+    sqlx::query(query)
+        .bind(mapping_id.to_string())
+        .bind(dto.keyword.trim())
+        .bind(&dto.category)
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("Duplicate entry") {
+                anyhow!("Mapping already exists")
+            } else {
+                anyhow!("Failed to create diagnosis mapping: {}", e)
+            }
+        })?;
+
+    let row = sqlx::query(
+        r#"
+        SELECT id, keyword, category, created_at, updated_at
+        FROM diagnosis_category_mappings
+        WHERE id = ?
+        "#,
+    )
+    .bind(mapping_id.to_string())
+    .fetch_one(pool)
+    .await
+    .map_err(|e| anyhow!("Diagnosis mapping not found: {}", e))?;
+
+    parse_diagnosis_mapping_from_row(&row)
+}
+
+pub async fn delete_diagnosis_mapping(pool: &DbPool, id: Uuid) -> Result<()> {
+    let result = sqlx::query("DELETE FROM diagnosis_category_mappings WHERE id = ?")
+        .bind(id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to delete diagnosis mapping: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(anyhow!("Diagnosis mapping not found"));
+    }
+
+    Ok(())
+}
+
 // Helper functions for parsing
 fn parse_article_from_row(row: &sqlx::mysql::MySqlRow) -> Result<Article> {
     use sqlx::Row;
@@ -720,14 +1459,18 @@ fn parse_article_from_row(row: &sqlx::mysql::MySqlRow) -> Result<Article> {
         like_count: row.get::<i32, _>("like_count") as u32,
         status: match row.get::<&str, _>("status") {
             "draft" => ContentStatus::Draft,
+            "pending_review" => ContentStatus::PendingReview,
             "published" => ContentStatus::Published,
             "offline" => ContentStatus::Offline,
             _ => return Err(anyhow!("Invalid status")),
         },
         publish_channels,
+        scheduled_publish_at: row.get("scheduled_publish_at"),
+        review_notes: row.get("review_notes"),
         published_at: row.get("published_at"),
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
+        has_liked: None,
     })
 }
 
@@ -744,12 +1487,14 @@ fn parse_article_list_item_from_row(row: &sqlx::mysql::MySqlRow) -> Result<Artic
         view_count: row.get::<i32, _>("view_count") as u32,
         status: match row.get::<&str, _>("status") {
             "draft" => ContentStatus::Draft,
+            "pending_review" => ContentStatus::PendingReview,
             "published" => ContentStatus::Published,
             "offline" => ContentStatus::Offline,
             _ => return Err(anyhow!("Invalid status")),
         },
         published_at: row.get("published_at"),
         created_at: row.get("created_at"),
+        relevance_score: row.try_get::<f64, _>("relevance_score").ok(),
     })
 }
 
@@ -789,11 +1534,14 @@ fn parse_video_from_row(row: &sqlx::mysql::MySqlRow) -> Result<Video> {
         status: match row.get::<&str, _>("status") {
             "draft" => VideoStatus::Draft,
             "processing" => VideoStatus::Processing,
+            "pending_review" => VideoStatus::PendingReview,
             "published" => VideoStatus::Published,
             "offline" => VideoStatus::Offline,
             _ => return Err(anyhow!("Invalid status")),
         },
         publish_channels,
+        scheduled_publish_at: row.get("scheduled_publish_at"),
+        review_notes: row.get("review_notes"),
         published_at: row.get("published_at"),
         created_at: row.get("created_at"),
         updated_at: row.get("updated_at"),
@@ -815,12 +1563,14 @@ fn parse_video_list_item_from_row(row: &sqlx::mysql::MySqlRow) -> Result<VideoLi
         status: match row.get::<&str, _>("status") {
             "draft" => VideoStatus::Draft,
             "processing" => VideoStatus::Processing,
+            "pending_review" => VideoStatus::PendingReview,
             "published" => VideoStatus::Published,
             "offline" => VideoStatus::Offline,
             _ => return Err(anyhow!("Invalid status")),
         },
         published_at: row.get("published_at"),
         created_at: row.get("created_at"),
+        relevance_score: row.try_get::<f64, _>("relevance_score").ok(),
     })
 }
 
@@ -842,3 +1592,44 @@ fn parse_category_from_row(row: &sqlx::mysql::MySqlRow) -> Result<ContentCategor
         updated_at: row.get("updated_at"),
     })
 }
+
+fn parse_recommended_content_from_row(row: &sqlx::mysql::MySqlRow) -> Result<RecommendedContent> {
+    use sqlx::Row;
+
+    let doctor_name: Option<String> = row.get("doctor_name");
+    let matched_category: Option<String> = row.get("matched_category");
+    let reason = match (doctor_name, matched_category) {
+        (Some(name), _) => format!("因为您关注了{}医生", name),
+        (None, Some(_)) => "与您的就诊记录相关".to_string(),
+        (None, None) => "热门推荐".to_string(),
+    };
+
+    Ok(RecommendedContent {
+        content_type: row.get("content_type"),
+        id: Uuid::parse_str(row.get("id")).map_err(|e| anyhow!("Failed to parse UUID: {}", e))?,
+        title: row.get("title"),
+        cover_image: row.get("cover_image"),
+        summary: row.get("summary"),
+        author_name: row.get("author_name"),
+        category: row.get("category"),
+        view_count: row.get::<Option<i32>, _>("view_count").unwrap_or(0) as u32,
+        like_count: row.get::<Option<i32>, _>("like_count").unwrap_or(0) as u32,
+        published_at: row.get("published_at"),
+        score: row.get::<Option<f64>, _>("score").unwrap_or(0.0),
+        reason,
+    })
+}
+
+fn parse_diagnosis_mapping_from_row(
+    row: &sqlx::mysql::MySqlRow,
+) -> Result<DiagnosisCategoryMapping> {
+    use sqlx::Row;
+
+    Ok(DiagnosisCategoryMapping {
+        id: Uuid::parse_str(row.get("id")).map_err(|e| anyhow!("Failed to parse UUID: {}", e))?,
+        keyword: row.get("keyword"),
+        category: row.get("category"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
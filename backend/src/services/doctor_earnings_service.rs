@@ -0,0 +1,351 @@
+use crate::config::database::DbPool;
+use crate::models::doctor::*;
+use crate::models::payment::PaymentOrder;
+use crate::services::system_config_service::SystemConfigService;
+use crate::utils::errors::AppError;
+use chrono::{Duration, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::{MySql, Row, Transaction};
+use uuid::Uuid;
+
+pub struct DoctorEarningsService;
+
+impl DoctorEarningsService {
+    /// 未配置 `payment.doctor_commission_rate` 时的平台抽成比例（%）
+    const DEFAULT_COMMISSION_RATE: i64 = 20;
+
+    /// 平台抽成比例（%），限制在 0-100
+    pub async fn commission_rate(db: &DbPool) -> Result<Decimal, AppError> {
+        let rate = SystemConfigService::get_value(db, "payment", "doctor_commission_rate")
+            .await?
+            .and_then(|v| v.trim().parse::<Decimal>().ok())
+            .unwrap_or_else(|| Decimal::from(Self::DEFAULT_COMMISSION_RATE));
+
+        Ok(rate.clamp(Decimal::ZERO, Decimal::ONE_HUNDRED))
+    }
+
+    /// 按比例计算抽成金额，四舍五入到分；负数金额（冲减）对称舍入
+    pub fn commission_for(amount: Decimal, rate: Decimal) -> Decimal {
+        (amount * rate / Decimal::ONE_HUNDRED)
+            .round_dp_with_strategy(2, RoundingStrategy::MidpointAwayFromZero)
+    }
+
+    /// 预约订单支付成功时记入医生收益，须在更新订单状态的同一事务内调用。
+    /// 非预约订单忽略；每个订单只记一次，重复调用无副作用。
+    pub async fn record_income_tx(
+        db: &DbPool,
+        tx: &mut Transaction<'_, MySql>,
+        order: &PaymentOrder,
+    ) -> Result<(), AppError> {
+        let appointment_id = match order.appointment_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        let doctor_id: Option<String> =
+            sqlx::query_scalar("SELECT doctor_id FROM appointments WHERE id = ?")
+                .bind(appointment_id.to_string())
+                .fetch_optional(&mut **tx)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let doctor_id = match doctor_id {
+            Some(id) => id,
+            None => return Ok(()),
+        };
+
+        let commission_rate = Self::commission_rate(db).await?;
+        let commission_amount = Self::commission_for(order.amount, commission_rate);
+
+        sqlx::query(
+            r#"
+            INSERT IGNORE INTO doctor_earnings (
+                id, doctor_id, order_id, appointment_id, entry_type, source_id,
+                gross_amount, commission_rate, commission_amount, net_amount, created_at
+            ) VALUES (?, ?, ?, ?, 'income', ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&doctor_id)
+        .bind(order.id.to_string())
+        .bind(appointment_id.to_string())
+        .bind(order.id.to_string())
+        .bind(order.amount)
+        .bind(commission_rate)
+        .bind(commission_amount)
+        .bind(order.amount - commission_amount)
+        .bind(Utc::now())
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 退款成功时记入负数冲减，沿用该订单收益入账时的抽成比例。
+    /// `refund_id` 保证同一笔退款只冲减一次；订单没有收益记录时忽略。
+    pub async fn record_refund_tx(
+        tx: &mut Transaction<'_, MySql>,
+        order_id: Uuid,
+        refund_id: Uuid,
+        refund_amount: Decimal,
+    ) -> Result<(), AppError> {
+        let income = match Self::fetch_income_tx(tx, order_id).await? {
+            Some(row) => row,
+            None => return Ok(()),
+        };
+
+        let commission_rate: Decimal = income.get("commission_rate");
+        let gross_amount = -refund_amount;
+        let commission_amount = Self::commission_for(gross_amount, commission_rate);
+
+        Self::insert_adjustment_tx(
+            tx,
+            &income,
+            order_id,
+            refund_id,
+            (
+                gross_amount,
+                commission_amount,
+                gross_amount - commission_amount,
+            ),
+        )
+        .await
+    }
+
+    /// 订单被整单退款（如管理员手工调整为已退款）时，冲减该订单尚未冲减的全部收益，
+    /// 使订单的收益、抽成和实得金额合计归零
+    pub async fn reverse_remaining_tx(
+        tx: &mut Transaction<'_, MySql>,
+        order_id: Uuid,
+    ) -> Result<(), AppError> {
+        let income = match Self::fetch_income_tx(tx, order_id).await? {
+            Some(row) => row,
+            None => return Ok(()),
+        };
+
+        let remaining = sqlx::query(
+            r#"
+            SELECT COALESCE(SUM(gross_amount), 0) as gross_amount,
+                   COALESCE(SUM(commission_amount), 0) as commission_amount,
+                   COALESCE(SUM(net_amount), 0) as net_amount
+            FROM doctor_earnings
+            WHERE order_id = ?
+            "#,
+        )
+        .bind(order_id.to_string())
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let gross_amount: Decimal = remaining.get("gross_amount");
+        if gross_amount <= Decimal::ZERO {
+            return Ok(());
+        }
+        let commission_amount: Decimal = remaining.get("commission_amount");
+        let net_amount: Decimal = remaining.get("net_amount");
+
+        Self::insert_adjustment_tx(
+            tx,
+            &income,
+            order_id,
+            order_id,
+            (-gross_amount, -commission_amount, -net_amount),
+        )
+        .await
+    }
+
+    async fn fetch_income_tx(
+        tx: &mut Transaction<'_, MySql>,
+        order_id: Uuid,
+    ) -> Result<Option<sqlx::mysql::MySqlRow>, AppError> {
+        sqlx::query(
+            r#"
+            SELECT doctor_id, appointment_id, commission_rate
+            FROM doctor_earnings
+            WHERE order_id = ? AND entry_type = 'income'
+            "#,
+        )
+        .bind(order_id.to_string())
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 写入一条冲减明细，`amounts` 为 (订单金额, 抽成, 实得)，均为负数
+    async fn insert_adjustment_tx(
+        tx: &mut Transaction<'_, MySql>,
+        income: &sqlx::mysql::MySqlRow,
+        order_id: Uuid,
+        source_id: Uuid,
+        amounts: (Decimal, Decimal, Decimal),
+    ) -> Result<(), AppError> {
+        let (gross_amount, commission_amount, net_amount) = amounts;
+
+        sqlx::query(
+            r#"
+            INSERT IGNORE INTO doctor_earnings (
+                id, doctor_id, order_id, appointment_id, entry_type, source_id,
+                gross_amount, commission_rate, commission_amount, net_amount, created_at
+            ) VALUES (?, ?, ?, ?, 'refund_adjustment', ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(income.get::<String, _>("doctor_id"))
+        .bind(order_id.to_string())
+        .bind(income.get::<String, _>("appointment_id"))
+        .bind(source_id.to_string())
+        .bind(gross_amount)
+        .bind(income.get::<Decimal, _>("commission_rate"))
+        .bind(commission_amount)
+        .bind(net_amount)
+        .bind(Utc::now())
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 医生收益报表：待结算与已结算金额、按日汇总和分页明细，均限定在日期范围内。
+    /// 关联预约已完成的明细视为已结算。
+    pub async fn get_report(
+        db: &DbPool,
+        doctor_id: Uuid,
+        start_date: Option<NaiveDate>,
+        end_date: Option<NaiveDate>,
+        page: i64,
+        page_size: i64,
+    ) -> Result<DoctorEarningsReport, AppError> {
+        let page = page.max(1);
+        let page_size = page_size.clamp(1, 100);
+        let offset = (page - 1) * page_size;
+
+        let mut where_clauses = vec!["e.doctor_id = ?"];
+        let mut range: Vec<NaiveDateTime> = Vec::new();
+        if let Some(start) = start_date {
+            where_clauses.push("e.created_at >= ?");
+            range.push(start.and_time(NaiveTime::MIN));
+        }
+        if let Some(end) = end_date {
+            where_clauses.push("e.created_at < ?");
+            range.push((end + Duration::days(1)).and_time(NaiveTime::MIN));
+        }
+        let where_sql = where_clauses.join(" AND ");
+
+        let summary_query = format!(
+            r#"
+            SELECT
+                COALESCE(SUM(CASE WHEN a.status = 'completed' THEN e.net_amount END), 0) as settled_amount,
+                COALESCE(SUM(CASE WHEN a.status <> 'completed' THEN e.net_amount END), 0) as pending_amount,
+                COUNT(*) as total
+            FROM doctor_earnings e
+            JOIN appointments a ON a.id = e.appointment_id
+            WHERE {}
+            "#,
+            where_sql
+        );
+        let mut summary = sqlx::query(&summary_query).bind(doctor_id.to_string());
+        for bound in &range {
+            summary = summary.bind(*bound);
+        }
+        let summary = summary
+            .fetch_one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let daily_query = format!(
+            r#"
+            SELECT DATE(e.created_at) as day,
+                   SUM(e.gross_amount) as gross_amount,
+                   SUM(e.commission_amount) as commission_amount,
+                   SUM(e.net_amount) as net_amount
+            FROM doctor_earnings e
+            WHERE {}
+            GROUP BY DATE(e.created_at)
+            ORDER BY day DESC
+            "#,
+            where_sql
+        );
+        let mut daily = sqlx::query(&daily_query).bind(doctor_id.to_string());
+        for bound in &range {
+            daily = daily.bind(*bound);
+        }
+        let daily = daily
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .map(|row| DoctorDailyEarnings {
+                date: row.get("day"),
+                gross_amount: row.get("gross_amount"),
+                commission_amount: row.get("commission_amount"),
+                net_amount: row.get("net_amount"),
+            })
+            .collect();
+
+        let entries_query = format!(
+            r#"
+            SELECT e.id, e.order_id, e.appointment_id, e.entry_type, e.gross_amount,
+                   e.commission_rate, e.commission_amount, e.net_amount, e.created_at,
+                   CASE WHEN a.status = 'completed' THEN 1 ELSE 0 END as settled
+            FROM doctor_earnings e
+            JOIN appointments a ON a.id = e.appointment_id
+            WHERE {}
+            ORDER BY e.created_at DESC, e.id
+            LIMIT ? OFFSET ?
+            "#,
+            where_sql
+        );
+        let mut entries = sqlx::query(&entries_query).bind(doctor_id.to_string());
+        for bound in &range {
+            entries = entries.bind(*bound);
+        }
+        let entries = entries
+            .bind(page_size)
+            .bind(offset)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .map(Self::parse_earning_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(DoctorEarningsReport {
+            pending_amount: summary.get("pending_amount"),
+            settled_amount: summary.get("settled_amount"),
+            daily,
+            entries,
+            total: summary.get("total"),
+            page,
+            page_size,
+        })
+    }
+
+    fn parse_earning_row(row: sqlx::mysql::MySqlRow) -> Result<DoctorEarning, AppError> {
+        let parse_uuid = |column: &str| {
+            Uuid::parse_str(row.get(column)).map_err(|e| AppError::DatabaseError(e.to_string()))
+        };
+
+        Ok(DoctorEarning {
+            id: parse_uuid("id")?,
+            order_id: parse_uuid("order_id")?,
+            appointment_id: parse_uuid("appointment_id")?,
+            entry_type: match row.get::<&str, _>("entry_type") {
+                "income" => DoctorEarningType::Income,
+                "refund_adjustment" => DoctorEarningType::RefundAdjustment,
+                other => {
+                    return Err(AppError::DatabaseError(format!(
+                        "Invalid earning type: {}",
+                        other
+                    )))
+                }
+            },
+            gross_amount: row.get("gross_amount"),
+            commission_rate: row.get("commission_rate"),
+            commission_amount: row.get("commission_amount"),
+            net_amount: row.get("net_amount"),
+            settled: row.get::<i64, _>("settled") != 0,
+            created_at: row.get("created_at"),
+        })
+    }
+}
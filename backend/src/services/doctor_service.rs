@@ -1,8 +1,17 @@
-use crate::{config::database::DbPool, models::doctor::*};
+use crate::{
+    config::database::DbPool,
+    models::{
+        doctor::*,
+        notification::{CreateNotificationDto, Notification, NotificationType},
+    },
+    services::notification_service::NotificationService,
+};
 use anyhow::{anyhow, Result};
-use chrono::Utc;
+use chrono::{DateTime, NaiveDate, Utc};
+use rust_decimal::{prelude::ToPrimitive, Decimal};
 use serde_json;
 use sqlx::types::Json;
+use std::collections::BTreeMap;
 use uuid::Uuid;
 
 pub async fn list_doctors(
@@ -11,6 +20,7 @@ pub async fn list_doctors(
     per_page: u32,
     department: Option<String>,
     search: Option<String>,
+    sort: Option<String>,
 ) -> Result<Vec<Doctor>> {
     let offset = (page - 1) * per_page;
 
@@ -24,6 +34,12 @@ pub async fn list_doctors(
     "#,
     );
 
+    // Doctors who are out of office right now are hidden from search
+    query.push_str(&format!(
+        " AND NOT (ooo_start IS NOT NULL AND ooo_start <= '{0}' AND ooo_end > '{0}')",
+        Utc::now().format("%Y-%m-%d %H:%M:%S")
+    ));
+
     if let Some(dept) = &department {
         query.push_str(&format!(" AND department = '{}'", dept));
     }
@@ -35,9 +51,15 @@ pub async fn list_doctors(
         ));
     }
 
+    // "rating" ranks by the review-count-weighted rating, not the raw average
+    let order_by = match sort.as_deref() {
+        Some("rating") => "weighted_rating DESC, total_reviews DESC, created_at DESC",
+        _ => "created_at DESC",
+    };
+
     query.push_str(&format!(
-        " ORDER BY created_at DESC LIMIT {} OFFSET {}",
-        per_page, offset
+        " ORDER BY {} LIMIT {} OFFSET {}",
+        order_by, per_page, offset
     ));
 
     let rows = sqlx::query(&query)
@@ -219,6 +241,11 @@ pub async fn update_doctor(pool: &DbPool, id: Uuid, dto: UpdateDoctorDto) -> Res
         bindings.push(experience.clone());
     }
 
+    if let Some(minutes) = dto.consultation_duration_minutes {
+        update_fields.push("consultation_duration_minutes = ?");
+        bindings.push(minutes.to_string());
+    }
+
     update_fields.push("updated_at = ?");
 
     if update_fields.is_empty() {
@@ -303,3 +330,269 @@ pub async fn update_doctor_photos(pool: &DbPool, id: Uuid, photos: DoctorPhotos)
 
     get_doctor_by_id(pool, id).await
 }
+
+/// 与患者存在有效（未取消）预约关系的医生，用于判断医生能否查看该患者的档案和病历。
+/// 只返回公开资料，不含身份证号和证件照片
+pub async fn get_treating_doctors(pool: &DbPool, patient_id: Uuid) -> Result<Vec<TreatingDoctor>> {
+    let query = r#"
+        SELECT d.id, d.user_id, d.hospital, d.department, d.title, d.introduction,
+               d.specialties, d.experience, d.avatar
+        FROM doctors d
+        WHERE d.id IN (
+            SELECT a.doctor_id FROM appointments a
+            WHERE a.patient_id = ? AND a.status != 'cancelled'
+        )
+        ORDER BY d.created_at DESC
+    "#;
+
+    let rows = sqlx::query(query)
+        .bind(patient_id.to_string())
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch treating doctors: {}", e))?;
+
+    let mut doctors = Vec::new();
+    for row in rows {
+        doctors.push(TreatingDoctor {
+            id: Uuid::parse_str(sqlx::Row::get(&row, "id")).unwrap(),
+            user_id: Uuid::parse_str(sqlx::Row::get(&row, "user_id")).unwrap(),
+            hospital: sqlx::Row::get(&row, "hospital"),
+            department: sqlx::Row::get(&row, "department"),
+            title: sqlx::Row::get(&row, "title"),
+            introduction: sqlx::Row::get(&row, "introduction"),
+            specialties: {
+                let json_value: Json<Vec<String>> = sqlx::Row::get(&row, "specialties");
+                json_value.0
+            },
+            experience: sqlx::Row::get(&row, "experience"),
+            avatar: sqlx::Row::get(&row, "avatar"),
+        });
+    }
+
+    Ok(doctors)
+}
+
+/// 判断某个医生用户是否为该患者的接诊医生
+pub async fn is_treating_doctor(
+    pool: &DbPool,
+    patient_id: Uuid,
+    doctor_user_id: Uuid,
+) -> Result<bool> {
+    let doctors = get_treating_doctors(pool, patient_id).await?;
+    Ok(doctors.iter().any(|d| d.user_id == doctor_user_id))
+}
+
+pub async fn set_out_of_office(
+    pool: &DbPool,
+    doctor_id: Uuid,
+    dto: SetOutOfOfficeDto,
+) -> Result<DoctorOutOfOffice> {
+    if dto.end <= dto.start {
+        return Err(anyhow!(
+            "Invalid out-of-office range: end must be after start"
+        ));
+    }
+
+    let message = dto.message.trim().to_string();
+    let result = sqlx::query(
+        "UPDATE doctors SET ooo_start = ?, ooo_end = ?, ooo_message = ?, updated_at = ? WHERE id = ?",
+    )
+    .bind(dto.start)
+    .bind(dto.end)
+    .bind(&message)
+    .bind(Utc::now())
+    .bind(doctor_id.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Failed to set out of office: {}", e))?;
+
+    if result.rows_affected() == 0 {
+        return Err(anyhow!("Doctor not found"));
+    }
+
+    Ok(DoctorOutOfOffice {
+        doctor_id,
+        start: dto.start,
+        end: dto.end,
+        message,
+    })
+}
+
+pub async fn clear_out_of_office(pool: &DbPool, doctor_id: Uuid) -> Result<()> {
+    sqlx::query(
+        "UPDATE doctors SET ooo_start = NULL, ooo_end = NULL, ooo_message = NULL, updated_at = ? WHERE id = ?",
+    )
+    .bind(Utc::now())
+    .bind(doctor_id.to_string())
+    .execute(pool)
+    .await
+    .map_err(|e| anyhow!("Failed to clear out of office: {}", e))?;
+
+    Ok(())
+}
+
+/// 医生在 `at` 时刻处于停诊期时返回停诊设置
+pub async fn get_active_out_of_office(
+    pool: &DbPool,
+    doctor_id: Uuid,
+    at: DateTime<Utc>,
+) -> Result<Option<DoctorOutOfOffice>> {
+    let row = sqlx::query(
+        r#"
+        SELECT ooo_start, ooo_end, ooo_message
+        FROM doctors
+        WHERE id = ? AND ooo_start IS NOT NULL AND ooo_start <= ? AND ooo_end > ?
+        "#,
+    )
+    .bind(doctor_id.to_string())
+    .bind(at)
+    .bind(at)
+    .fetch_optional(pool)
+    .await
+    .map_err(|e| anyhow!("Failed to fetch out of office: {}", e))?;
+
+    Ok(row.map(|row| DoctorOutOfOffice {
+        doctor_id,
+        start: sqlx::Row::get(&row, "ooo_start"),
+        end: sqlx::Row::get(&row, "ooo_end"),
+        message: sqlx::Row::get::<Option<String>, _>(&row, "ooo_message").unwrap_or_default(),
+    }))
+}
+
+/// Auto-replies to a patient who messages a doctor that is out of office right now.
+pub async fn send_out_of_office_reply(
+    pool: &DbPool,
+    doctor_user_id: Uuid,
+    patient_id: Uuid,
+) -> Result<Option<Notification>> {
+    let doctor = match get_doctor_by_user_id(pool, doctor_user_id).await {
+        Ok(doctor) => doctor,
+        Err(_) => return Ok(None),
+    };
+
+    let Some(ooo) = get_active_out_of_office(pool, doctor.id, Utc::now()).await? else {
+        return Ok(None);
+    };
+
+    let notification = NotificationService::create_notification(
+        pool,
+        CreateNotificationDto {
+            user_id: patient_id,
+            notification_type: NotificationType::DoctorReply,
+            title: "医生停诊自动回复".to_string(),
+            content: ooo.message.clone(),
+            related_id: Some(doctor.id),
+            metadata: Some(serde_json::json!({
+                "doctor_id": doctor.id,
+                "out_of_office_until": ooo.end,
+            })),
+        },
+    )
+    .await
+    .map_err(|e| anyhow!("Failed to send out-of-office reply: {}", e))?;
+
+    Ok(Some(notification))
+}
+
+/// Builds a doctor's report for one calendar month: appointments by status, completed
+/// consultations, balance income and new reviews.
+pub async fn get_monthly_report(
+    pool: &DbPool,
+    doctor_user_id: Uuid,
+    year: i32,
+    month: u32,
+) -> Result<DoctorMonthlyReport> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .ok_or_else(|| anyhow!("Invalid report month"))?;
+    let (next_year, next_month) = if month == 12 {
+        (year + 1, 1)
+    } else {
+        (year, month + 1)
+    };
+    let end = NaiveDate::from_ymd_opt(next_year, next_month, 1)
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .ok_or_else(|| anyhow!("Invalid report month"))?;
+
+    let doctor = get_doctor_by_user_id(pool, doctor_user_id).await?;
+    let doctor_id = doctor.id.to_string();
+
+    let status_counts: Vec<(String, i64)> = sqlx::query_as(
+        r#"
+        SELECT status, COUNT(*)
+        FROM appointments
+        WHERE doctor_id = ? AND appointment_date >= ? AND appointment_date < ?
+        GROUP BY status
+        "#,
+    )
+    .bind(&doctor_id)
+    .bind(start)
+    .bind(end)
+    .fetch_all(pool)
+    .await?;
+
+    let mut appointments_by_status: BTreeMap<String, i64> =
+        ["pending", "confirmed", "completed", "cancelled"]
+            .into_iter()
+            .map(|status| (status.to_string(), 0))
+            .collect();
+    appointments_by_status.extend(status_counts);
+    let total_appointments = appointments_by_status.values().sum();
+
+    let (completed_consultations, average_duration, average_consultation_rating): (
+        i64,
+        Option<Decimal>,
+        Option<Decimal>,
+    ) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*), AVG(duration), AVG(patient_rating)
+        FROM video_consultations
+        WHERE doctor_id = ? AND status = 'completed' AND end_time >= ? AND end_time < ?
+        "#,
+    )
+    .bind(&doctor_id)
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    let earnings: Decimal = sqlx::query_scalar(
+        r#"
+        SELECT COALESCE(SUM(amount), 0)
+        FROM balance_transactions
+        WHERE user_id = ? AND transaction_type = 'income' AND created_at >= ? AND created_at < ?
+        "#,
+    )
+    .bind(doctor_user_id.to_string())
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    let (new_reviews, average_review_rating): (i64, Option<Decimal>) = sqlx::query_as(
+        r#"
+        SELECT COUNT(*), AVG(rating)
+        FROM patient_reviews
+        WHERE doctor_id = ? AND created_at >= ? AND created_at < ?
+        "#,
+    )
+    .bind(&doctor_id)
+    .bind(start)
+    .bind(end)
+    .fetch_one(pool)
+    .await?;
+
+    Ok(DoctorMonthlyReport {
+        doctor_id: doctor.id,
+        year,
+        month,
+        total_appointments,
+        appointments_by_status,
+        completed_consultations,
+        average_consultation_duration: average_duration.and_then(|d| d.to_f64()),
+        average_consultation_rating: average_consultation_rating.and_then(|d| d.to_f64()),
+        earnings,
+        new_reviews,
+        average_review_rating: average_review_rating.and_then(|d| d.to_f64()),
+    })
+}
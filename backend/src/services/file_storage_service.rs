@@ -4,8 +4,13 @@ use crate::{
     utils::errors::AppError,
 };
 use aws_sdk_s3::{
+    config::http::HttpResponse,
+    error::{ProvideErrorMetadata, SdkError},
+    operation::{
+        abort_multipart_upload::AbortMultipartUploadError, delete_object::DeleteObjectError,
+    },
     primitives::ByteStream,
-    types::{Delete, ObjectIdentifier},
+    types::{CompletedMultipartUpload, CompletedPart, Delete, ObjectIdentifier},
     Client as S3Client,
 };
 use chrono::Utc;
@@ -36,8 +41,14 @@ impl FileStorageService {
             .await
             .map_err(|e| AppError::InternalServerError(format!("Failed to upload to S3: {}", e)))?;
 
-        // Generate URL based on storage type
-        let url = match config.storage_type {
+        Ok(Self::public_url(file_path))
+    }
+
+    /// Public URL of an object in the configured bucket
+    pub fn public_url(file_path: &str) -> String {
+        let config = StorageConfig::from_env();
+
+        match config.storage_type {
             StorageType::S3 => {
                 if let Some(endpoint) = config.endpoint {
                     format!("{}/{}/{}", endpoint, config.bucket_name, file_path)
@@ -58,26 +69,52 @@ impl FileStorageService {
                     file_path
                 )
             }
-        };
-
-        Ok(url)
+        }
     }
 
     /// Delete file from S3 or OSS
     pub async fn delete_from_cloud(s3_client: &S3Client, file_path: &str) -> Result<(), AppError> {
         let config = StorageConfig::from_env();
 
-        s3_client
+        Self::delete_object(s3_client, &config.bucket_name, file_path).await
+    }
+
+    /// Delete an object from the given bucket. An object that is already gone
+    /// counts as deleted, so retries after a partial failure are safe.
+    pub async fn delete_object(
+        s3_client: &S3Client,
+        bucket: &str,
+        key: &str,
+    ) -> Result<(), AppError> {
+        match s3_client
             .delete_object()
-            .bucket(&config.bucket_name)
-            .key(file_path)
+            .bucket(bucket)
+            .key(key)
             .send()
             .await
-            .map_err(|e| {
-                AppError::InternalServerError(format!("Failed to delete from S3: {}", e))
-            })?;
+        {
+            Ok(_) => Ok(()),
+            Err(e) if Self::is_missing_object(&e) => {
+                tracing::debug!("Object {}/{} already deleted", bucket, key);
+                Ok(())
+            }
+            Err(e) => Err(AppError::InternalServerError(format!(
+                "Failed to delete from S3: {}",
+                e
+            ))),
+        }
+    }
 
-        Ok(())
+    /// S3 answers deletes of missing keys with 204, but some S3-compatible
+    /// stores reply 404 / NoSuchKey instead
+    fn is_missing_object(error: &SdkError<DeleteObjectError, HttpResponse>) -> bool {
+        error
+            .raw_response()
+            .is_some_and(|response| response.status().as_u16() == 404)
+            || error
+                .as_service_error()
+                .and_then(|e| e.code())
+                .is_some_and(|code| code == "NoSuchKey")
     }
 
     /// Batch delete files from S3 or OSS
@@ -145,6 +182,140 @@ impl FileStorageService {
         Ok(presigned_request.uri().to_string())
     }
 
+    /// Start a multipart upload and return the storage-side upload ID
+    pub async fn create_multipart_upload(
+        s3_client: &S3Client,
+        file_path: &str,
+        content_type: &str,
+    ) -> Result<String, AppError> {
+        let config = StorageConfig::from_env();
+
+        let output = s3_client
+            .create_multipart_upload()
+            .bucket(&config.bucket_name)
+            .key(file_path)
+            .content_type(content_type)
+            .send()
+            .await
+            .map_err(|e| {
+                AppError::InternalServerError(format!("Failed to create multipart upload: {}", e))
+            })?;
+
+        output.upload_id().map(str::to_string).ok_or_else(|| {
+            AppError::InternalServerError("Multipart upload response has no upload ID".to_string())
+        })
+    }
+
+    /// Generate pre-signed URL for uploading one part of a multipart upload
+    pub async fn generate_presigned_part_url(
+        s3_client: &S3Client,
+        file_path: &str,
+        upload_id: &str,
+        part_number: i32,
+        expires_in_seconds: u64,
+    ) -> Result<String, AppError> {
+        let config = StorageConfig::from_env();
+
+        let presigned_request = s3_client
+            .upload_part()
+            .bucket(&config.bucket_name)
+            .key(file_path)
+            .upload_id(upload_id)
+            .part_number(part_number)
+            .presigned(
+                aws_sdk_s3::presigning::PresigningConfig::expires_in(
+                    std::time::Duration::from_secs(expires_in_seconds),
+                )
+                .map_err(|e| {
+                    AppError::InternalServerError(format!(
+                        "Failed to create presigning config: {}",
+                        e
+                    ))
+                })?,
+            )
+            .await
+            .map_err(|e| {
+                AppError::InternalServerError(format!("Failed to generate presigned URL: {}", e))
+            })?;
+
+        Ok(presigned_request.uri().to_string())
+    }
+
+    /// Assemble the uploaded parts into the final object. `parts` are
+    /// (part number, ETag) pairs in ascending part order; returns the object's ETag.
+    pub async fn complete_multipart_upload(
+        s3_client: &S3Client,
+        file_path: &str,
+        upload_id: &str,
+        parts: &[(i32, String)],
+    ) -> Result<Option<String>, AppError> {
+        let config = StorageConfig::from_env();
+
+        let completed_parts = parts
+            .iter()
+            .map(|(part_number, etag)| {
+                CompletedPart::builder()
+                    .part_number(*part_number)
+                    .e_tag(etag)
+                    .build()
+            })
+            .collect();
+
+        let output = s3_client
+            .complete_multipart_upload()
+            .bucket(&config.bucket_name)
+            .key(file_path)
+            .upload_id(upload_id)
+            .multipart_upload(
+                CompletedMultipartUpload::builder()
+                    .set_parts(Some(completed_parts))
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(|e| {
+                AppError::InternalServerError(format!("Failed to complete multipart upload: {}", e))
+            })?;
+
+        Ok(output.e_tag().map(str::to_string))
+    }
+
+    /// Abort a multipart upload so storage discards the uploaded parts. An
+    /// upload that no longer exists counts as aborted.
+    pub async fn abort_multipart_upload(
+        s3_client: &S3Client,
+        file_path: &str,
+        upload_id: &str,
+    ) -> Result<(), AppError> {
+        let config = StorageConfig::from_env();
+
+        match s3_client
+            .abort_multipart_upload()
+            .bucket(&config.bucket_name)
+            .key(file_path)
+            .upload_id(upload_id)
+            .send()
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e) if Self::is_missing_upload(&e) => Ok(()),
+            Err(e) => Err(AppError::InternalServerError(format!(
+                "Failed to abort multipart upload: {}",
+                e
+            ))),
+        }
+    }
+
+    fn is_missing_upload(error: &SdkError<AbortMultipartUploadError, HttpResponse>) -> bool {
+        error
+            .raw_response()
+            .is_some_and(|response| response.status().as_u16() == 404)
+            || error
+                .as_service_error()
+                .and_then(|e| e.code())
+                .is_some_and(|code| code == "NoSuchUpload")
+    }
+
     /// Generate pre-signed URL for download
     pub async fn generate_presigned_download_url(
         s3_client: &S3Client,
@@ -1,13 +1,33 @@
 use crate::config::database::DbPool;
+use crate::config::storage::StorageConfig;
 use crate::models::file_upload::*;
+use crate::services::cleanup_service::{CleanupService, SweepSettings};
+use crate::services::file_storage_service::FileStorageService;
 use crate::utils::errors::AppError;
-use chrono::{Duration, Utc};
+use aws_sdk_s3::Client as S3Client;
+use chrono::{DateTime, Duration, Utc};
 use sqlx::{MySql, Row, Transaction};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+/// 单次批量删除的文件数上限
+const MAX_BATCH_DELETE_FILES: usize = 100;
+
+/// 上传链接有效期（秒）
+const UPLOAD_URL_EXPIRES_SECS: u64 = 30 * 60;
+
+/// 分片上传的分片大小（字节），对象存储要求除最后一片外不小于 5MB
+const MULTIPART_PART_SIZE: i64 = 10 * 1024 * 1024;
+
 pub struct FileUploadService;
 
+/// 分片记录，`etag` 非空表示该分片已上传
+struct MultipartPartRow {
+    part_number: i32,
+    size: i64,
+    etag: Option<String>,
+}
+
 impl FileUploadService {
     fn parse_system_config_from_row(row: &sqlx::mysql::MySqlRow) -> Result<SystemConfig, AppError> {
         let value_type_str: String = row.get("value_type");
@@ -62,6 +82,7 @@ impl FileUploadService {
             file_name: row.get("file_name"),
             file_path: row.get("file_path"),
             file_url: row.get("file_url"),
+            original_url: row.get("original_url"),
             file_size: row.get("file_size"),
             mime_type: row.get("mime_type"),
             related_type: row.get("related_type"),
@@ -85,19 +106,20 @@ impl FileUploadService {
     // File Upload Management
     pub async fn create_upload(
         db: &DbPool,
+        s3_client: Option<&S3Client>,
         user_id: Uuid,
         dto: CreateFileUploadDto,
     ) -> Result<UploadUrlResponse, AppError> {
         // Validate file type and size
-        Self::validate_upload(&dto).await?;
+        Self::validate_upload(db, &dto).await?;
 
         let upload_id = Uuid::new_v4();
         let now = Utc::now();
-        let expires_at = now + Duration::minutes(30);
+        let expires_at = now + Duration::seconds(UPLOAD_URL_EXPIRES_SECS as i64);
 
         // Generate upload URL and path
         let (file_path, upload_url, upload_method, upload_headers) =
-            Self::generate_upload_url(&upload_id, &dto).await?;
+            Self::generate_upload_url(s3_client, &upload_id, &dto).await?;
 
         let query = r#"
             INSERT INTO file_uploads (
@@ -152,16 +174,19 @@ impl FileUploadService {
             return Err(AppError::BadRequest("文件已完成上传".to_string()));
         }
 
+        let (file_url, original_url) = Self::watermark_file_url(db, &file, &dto.file_url).await?;
+
         let query = r#"
             UPDATE file_uploads
-            SET file_url = ?, bucket_name = ?, object_key = ?,
+            SET file_url = ?, original_url = ?, bucket_name = ?, object_key = ?,
                 etag = ?, width = ?, height = ?, thumbnail_url = ?,
                 status = 'completed'
             WHERE id = ?
         "#;
 
         sqlx::query(query)
-            .bind(&dto.file_url)
+            .bind(&file_url)
+            .bind(&original_url)
             .bind(&dto.bucket_name)
             .bind(&dto.object_key)
             .bind(&dto.etag)
@@ -180,6 +205,479 @@ impl FileUploadService {
         Self::get_file(db, upload_id).await
     }
 
+    /// 为上传中的文件重新签发上传链接，沿用原存储路径，不新建记录
+    pub async fn refresh_upload_url(
+        db: &DbPool,
+        s3_client: Option<&S3Client>,
+        upload_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<UploadUrlResponse, AppError> {
+        let file = Self::get_file(db, upload_id).await?;
+        if file.user_id != user_id {
+            return Err(AppError::Forbidden);
+        }
+
+        if file.status != UploadStatus::Uploading {
+            return Err(AppError::BadRequest("文件不在上传中".to_string()));
+        }
+
+        let expires_at = Utc::now() + Duration::seconds(UPLOAD_URL_EXPIRES_SECS as i64);
+        let (upload_url, upload_method, upload_headers) = Self::presign_upload(
+            s3_client,
+            &file.file_path,
+            file.mime_type.as_deref(),
+            file.related_type.is_some(),
+        )
+        .await?;
+
+        // 记录新的过期时间，避免上传超时清理误伤续期后的上传
+        sqlx::query("UPDATE file_uploads SET expires_at = ? WHERE id = ?")
+            .bind(expires_at)
+            .bind(upload_id.to_string())
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(UploadUrlResponse {
+            upload_id,
+            upload_url,
+            upload_method,
+            upload_headers,
+            expires_at,
+        })
+    }
+
+    /// 大文件分片上传：在对象存储发起分片上传，按固定分片大小拆分并返回各分片的预签名上传地址
+    pub async fn init_multipart_upload(
+        db: &DbPool,
+        s3_client: Option<&S3Client>,
+        user_id: Uuid,
+        dto: CreateFileUploadDto,
+    ) -> Result<MultipartUploadResponse, AppError> {
+        Self::validate_upload(db, &dto).await?;
+        let s3_client = s3_client.ok_or_else(|| {
+            AppError::InternalServerError("未配置对象存储，无法生成上传地址".to_string())
+        })?;
+
+        let upload_id = Uuid::new_v4();
+        let now = Utc::now();
+        let expires_at = now + Duration::seconds(UPLOAD_URL_EXPIRES_SECS as i64);
+        let file_path = Self::generate_file_path(&upload_id, &dto);
+        let content_type = dto
+            .mime_type
+            .as_deref()
+            .unwrap_or("application/octet-stream");
+
+        let part_count = (dto.file_size.max(1) as u64).div_ceil(MULTIPART_PART_SIZE as u64) as i32;
+        let parts: Vec<MultipartPartRow> = (1..=part_count)
+            .map(|part_number| MultipartPartRow {
+                part_number,
+                size: (dto.file_size - (part_number as i64 - 1) * MULTIPART_PART_SIZE)
+                    .clamp(0, MULTIPART_PART_SIZE),
+                etag: None,
+            })
+            .collect();
+
+        let multipart_upload_id =
+            FileStorageService::create_multipart_upload(s3_client, &file_path, content_type)
+                .await?;
+
+        // 记录写入失败时放弃存储端的分片上传，避免遗留无记录的分片
+        if let Err(e) = Self::insert_multipart_upload(
+            db,
+            upload_id,
+            user_id,
+            &dto,
+            &file_path,
+            &multipart_upload_id,
+            &parts,
+            now,
+            expires_at,
+        )
+        .await
+        {
+            if let Err(abort_err) = FileStorageService::abort_multipart_upload(
+                s3_client,
+                &file_path,
+                &multipart_upload_id,
+            )
+            .await
+            {
+                tracing::warn!(
+                    "Failed to abort multipart upload {}: {}",
+                    multipart_upload_id,
+                    abort_err
+                );
+            }
+            return Err(e);
+        }
+
+        Self::multipart_response(
+            s3_client,
+            upload_id,
+            &file_path,
+            &multipart_upload_id,
+            parts,
+            expires_at,
+        )
+        .await
+    }
+
+    /// 查询分片上传进度，为未上传的分片重新签发地址并延长过期时间，用于中断后续传
+    pub async fn get_multipart_upload(
+        db: &DbPool,
+        s3_client: Option<&S3Client>,
+        upload_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<MultipartUploadResponse, AppError> {
+        let (file, multipart_upload_id) =
+            Self::get_pending_multipart(db, upload_id, user_id).await?;
+        let s3_client = s3_client.ok_or_else(|| {
+            AppError::InternalServerError("未配置对象存储，无法生成上传地址".to_string())
+        })?;
+
+        let expires_at = Utc::now() + Duration::seconds(UPLOAD_URL_EXPIRES_SECS as i64);
+        sqlx::query("UPDATE file_uploads SET expires_at = ? WHERE id = ?")
+            .bind(expires_at)
+            .bind(upload_id.to_string())
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let parts = Self::get_multipart_parts(db, upload_id).await?;
+        Self::multipart_response(
+            s3_client,
+            upload_id,
+            &file.file_path,
+            &multipart_upload_id,
+            parts,
+            expires_at,
+        )
+        .await
+    }
+
+    /// 记录客户端已上传的分片及其 ETag，重复回报以最后一次为准
+    pub async fn record_multipart_part(
+        db: &DbPool,
+        upload_id: Uuid,
+        user_id: Uuid,
+        part_number: i32,
+        dto: RecordUploadPartDto,
+    ) -> Result<MultipartUploadPart, AppError> {
+        Self::get_pending_multipart(db, upload_id, user_id).await?;
+
+        let query = r#"
+            UPDATE file_upload_parts
+            SET etag = ?, status = 'uploaded', uploaded_at = NOW()
+            WHERE upload_id = ? AND part_number = ?
+        "#;
+
+        sqlx::query(query)
+            .bind(&dto.etag)
+            .bind(upload_id.to_string())
+            .bind(part_number)
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_multipart_parts(db, upload_id)
+            .await?
+            .into_iter()
+            .find(|part| part.part_number == part_number)
+            .map(|part| MultipartUploadPart {
+                part_number: part.part_number,
+                size: part.size,
+                uploaded: part.etag.is_some(),
+                etag: part.etag,
+                upload_url: None,
+            })
+            .ok_or_else(|| AppError::NotFound("分片不存在".to_string()))
+    }
+
+    /// 所有分片上传完成后在对象存储合并分片，并将上传记录标记为完成
+    pub async fn complete_multipart_upload(
+        db: &DbPool,
+        s3_client: Option<&S3Client>,
+        upload_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<FileUpload, AppError> {
+        let (file, multipart_upload_id) =
+            Self::get_pending_multipart(db, upload_id, user_id).await?;
+        let s3_client =
+            s3_client.ok_or_else(|| AppError::InternalServerError("未配置对象存储".to_string()))?;
+
+        let parts = Self::get_multipart_parts(db, upload_id).await?;
+        let pending = parts.iter().filter(|part| part.etag.is_none()).count();
+        if pending > 0 {
+            return Err(AppError::BadRequest(format!(
+                "还有 {} 个分片未上传",
+                pending
+            )));
+        }
+
+        let completed_parts: Vec<(i32, String)> = parts
+            .into_iter()
+            .filter_map(|part| part.etag.map(|etag| (part.part_number, etag)))
+            .collect();
+        let etag = FileStorageService::complete_multipart_upload(
+            s3_client,
+            &file.file_path,
+            &multipart_upload_id,
+            &completed_parts,
+        )
+        .await?;
+
+        let stored_url = FileStorageService::public_url(&file.file_path);
+        let (file_url, original_url) = Self::watermark_file_url(db, &file, &stored_url).await?;
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let query = r#"
+            UPDATE file_uploads
+            SET file_url = ?, original_url = ?, bucket_name = ?, object_key = ?,
+                etag = ?, status = 'completed'
+            WHERE id = ? AND status = 'uploading'
+        "#;
+
+        sqlx::query(query)
+            .bind(&file_url)
+            .bind(&original_url)
+            .bind(StorageConfig::from_env().bucket_name)
+            .bind(&file.file_path)
+            .bind(&etag)
+            .bind(upload_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("DELETE FROM file_upload_parts WHERE upload_id = ?")
+            .bind(upload_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_file(db, upload_id).await
+    }
+
+    /// 取消分片上传，对象存储丢弃已上传的分片
+    pub async fn abort_multipart_upload(
+        db: &DbPool,
+        s3_client: Option<&S3Client>,
+        upload_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(), AppError> {
+        let (file, multipart_upload_id) =
+            Self::get_pending_multipart(db, upload_id, user_id).await?;
+        let s3_client =
+            s3_client.ok_or_else(|| AppError::InternalServerError("未配置对象存储".to_string()))?;
+
+        FileStorageService::abort_multipart_upload(
+            s3_client,
+            &file.file_path,
+            &multipart_upload_id,
+        )
+        .await?;
+
+        Self::fail_upload(db, upload_id, "上传已取消").await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_multipart_upload(
+        db: &DbPool,
+        upload_id: Uuid,
+        user_id: Uuid,
+        dto: &CreateFileUploadDto,
+        file_path: &str,
+        multipart_upload_id: &str,
+        parts: &[MultipartPartRow],
+        now: DateTime<Utc>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let query = r#"
+            INSERT INTO file_uploads (
+                id, user_id, file_type, file_name, file_path,
+                file_url, file_size, mime_type, related_type, related_id,
+                multipart_upload_id, part_size, part_count,
+                status, uploaded_at, expires_at
+            ) VALUES (?, ?, ?, ?, ?, '', ?, ?, ?, ?, ?, ?, ?, 'uploading', ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(upload_id.to_string())
+            .bind(user_id.to_string())
+            .bind(dto.file_type.to_string())
+            .bind(&dto.file_name)
+            .bind(file_path)
+            .bind(dto.file_size)
+            .bind(&dto.mime_type)
+            .bind(&dto.related_type)
+            .bind(dto.related_id.map(|id| id.to_string()))
+            .bind(multipart_upload_id)
+            .bind(MULTIPART_PART_SIZE)
+            .bind(parts.len() as i32)
+            .bind(now)
+            .bind(expires_at)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        for part in parts {
+            sqlx::query(
+                "INSERT INTO file_upload_parts (upload_id, part_number, size) VALUES (?, ?, ?)",
+            )
+            .bind(upload_id.to_string())
+            .bind(part.part_number)
+            .bind(part.size)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
+    /// 校验上传归属与状态，返回上传记录及对象存储的分片上传 ID
+    async fn get_pending_multipart(
+        db: &DbPool,
+        upload_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<(FileUpload, String), AppError> {
+        let file = Self::get_file(db, upload_id).await?;
+        if file.user_id != user_id {
+            return Err(AppError::Forbidden);
+        }
+
+        if file.status != UploadStatus::Uploading {
+            return Err(AppError::BadRequest("文件不在上传中".to_string()));
+        }
+
+        let multipart_upload_id: Option<String> =
+            sqlx::query_scalar("SELECT multipart_upload_id FROM file_uploads WHERE id = ?")
+                .bind(upload_id.to_string())
+                .fetch_one(db)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let multipart_upload_id = multipart_upload_id
+            .ok_or_else(|| AppError::BadRequest("该上传不是分片上传".to_string()))?;
+
+        Ok((file, multipart_upload_id))
+    }
+
+    async fn get_multipart_parts(
+        db: &DbPool,
+        upload_id: Uuid,
+    ) -> Result<Vec<MultipartPartRow>, AppError> {
+        let query = r#"
+            SELECT part_number, size, etag
+            FROM file_upload_parts
+            WHERE upload_id = ?
+            ORDER BY part_number
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(upload_id.to_string())
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(rows
+            .iter()
+            .map(|row| MultipartPartRow {
+                part_number: row.get("part_number"),
+                size: row.get("size"),
+                etag: row.get("etag"),
+            })
+            .collect())
+    }
+
+    async fn multipart_response(
+        s3_client: &S3Client,
+        upload_id: Uuid,
+        file_path: &str,
+        multipart_upload_id: &str,
+        parts: Vec<MultipartPartRow>,
+        expires_at: DateTime<Utc>,
+    ) -> Result<MultipartUploadResponse, AppError> {
+        let part_count = parts.len() as i32;
+        let mut response_parts = Vec::with_capacity(parts.len());
+        for part in parts {
+            let upload_url = match part.etag {
+                Some(_) => None,
+                None => Some(
+                    FileStorageService::generate_presigned_part_url(
+                        s3_client,
+                        file_path,
+                        multipart_upload_id,
+                        part.part_number,
+                        UPLOAD_URL_EXPIRES_SECS,
+                    )
+                    .await?,
+                ),
+            };
+            response_parts.push(MultipartUploadPart {
+                part_number: part.part_number,
+                size: part.size,
+                uploaded: part.etag.is_some(),
+                etag: part.etag,
+                upload_url,
+            });
+        }
+
+        Ok(MultipartUploadResponse {
+            upload_id,
+            part_size: MULTIPART_PART_SIZE,
+            part_count,
+            parts: response_parts,
+            expires_at,
+        })
+    }
+
+    /// 将上传标记为失败并清除分片记录
+    async fn fail_upload(db: &DbPool, upload_id: Uuid, reason: &str) -> Result<(), AppError> {
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let query = r#"
+            UPDATE file_uploads
+            SET status = 'failed', error_message = ?
+            WHERE id = ? AND status = 'uploading'
+        "#;
+
+        sqlx::query(query)
+            .bind(reason)
+            .bind(upload_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query("DELETE FROM file_upload_parts WHERE upload_id = ?")
+            .bind(upload_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))
+    }
+
     pub async fn get_file(db: &DbPool, file_id: Uuid) -> Result<FileUpload, AppError> {
         let query = r#"
             SELECT * FROM file_uploads WHERE id = ?
@@ -341,6 +839,7 @@ impl FileUploadService {
 
     pub async fn delete_file(
         db: &DbPool,
+        s3_client: Option<&S3Client>,
         file_id: Uuid,
         user_id: Uuid,
         is_admin: bool,
@@ -365,11 +864,95 @@ impl FileUploadService {
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        // TODO: Schedule actual file deletion from OSS
+        // 存储删除失败不影响本次删除，记录保留为已删除状态，由定时清理重试
+        if let Some(s3_client) = s3_client {
+            if let Err(e) = Self::delete_stored_object(
+                s3_client,
+                file.bucket_name.as_deref(),
+                file.object_key.as_deref().unwrap_or(&file.file_path),
+            )
+            .await
+            {
+                tracing::warn!("Failed to delete stored object for file {}: {}", file_id, e);
+            }
+        }
 
         Ok(())
     }
 
+    /// 删除文件在对象存储中的对象，未记录桶名时使用默认桶；对象已不存在视为成功
+    async fn delete_stored_object(
+        s3_client: &S3Client,
+        bucket_name: Option<&str>,
+        object_key: &str,
+    ) -> Result<(), AppError> {
+        let bucket = match bucket_name {
+            Some(bucket) => bucket.to_string(),
+            None => StorageConfig::from_env().bucket_name,
+        };
+
+        FileStorageService::delete_object(s3_client, &bucket, object_key).await
+    }
+
+    /// 批量软删除文件，逐个校验权限，单个文件失败不影响其余文件
+    pub async fn delete_files(
+        db: &DbPool,
+        file_ids: &[Uuid],
+        user_id: Uuid,
+        is_admin: bool,
+    ) -> Result<Vec<FileDeleteResult>, AppError> {
+        if file_ids.is_empty() {
+            return Err(AppError::BadRequest("请选择要删除的文件".to_string()));
+        }
+        if file_ids.len() > MAX_BATCH_DELETE_FILES {
+            return Err(AppError::BadRequest(format!(
+                "单次最多删除{}个文件",
+                MAX_BATCH_DELETE_FILES
+            )));
+        }
+
+        let mut results = Vec::with_capacity(file_ids.len());
+        let mut seen = HashSet::new();
+
+        for &file_id in file_ids {
+            if !seen.insert(file_id) {
+                continue;
+            }
+
+            let outcome = match Self::get_file(db, file_id).await {
+                Ok(file) if file.deleted_at.is_some() => FileDeleteOutcome::NotFound,
+                Ok(file) if !is_admin && file.user_id != user_id => FileDeleteOutcome::Forbidden,
+                Ok(_) => {
+                    // Guard on deleted_at so a concurrent delete isn't reported twice
+                    let result = sqlx::query(
+                        r#"
+                        UPDATE file_uploads
+                        SET status = 'deleted', deleted_at = ?
+                        WHERE id = ? AND deleted_at IS NULL
+                        "#,
+                    )
+                    .bind(Utc::now())
+                    .bind(file_id.to_string())
+                    .execute(db)
+                    .await
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+                    if result.rows_affected() > 0 {
+                        FileDeleteOutcome::Deleted
+                    } else {
+                        FileDeleteOutcome::NotFound
+                    }
+                }
+                Err(AppError::NotFound(_)) => FileDeleteOutcome::NotFound,
+                Err(e) => return Err(e),
+            };
+
+            results.push(FileDeleteResult { file_id, outcome });
+        }
+
+        Ok(results)
+    }
+
     pub async fn get_file_stats(
         db: &DbPool,
         user_id: Option<Uuid>,
@@ -449,6 +1032,81 @@ impl FileUploadService {
         })
     }
 
+    /// 按已完成文件的总大小对用户排名，附带各文件类型的数量和大小
+    pub async fn get_top_storage_users(
+        db: &DbPool,
+        limit: i64,
+    ) -> Result<Vec<UserStorageUsage>, AppError> {
+        let query = r#"
+            SELECT f.user_id, u.name as user_name, f.file_type,
+                   COUNT(*) as count, SUM(f.file_size) as size, t.total_size
+            FROM file_uploads f
+            JOIN (
+                SELECT user_id, SUM(file_size) as total_size
+                FROM file_uploads
+                WHERE status = 'completed' AND deleted_at IS NULL
+                GROUP BY user_id
+                ORDER BY total_size DESC
+                LIMIT ?
+            ) t ON t.user_id = f.user_id
+            JOIN users u ON u.id = f.user_id
+            WHERE f.status = 'completed' AND f.deleted_at IS NULL
+            GROUP BY f.user_id, u.name, f.file_type, t.total_size
+            ORDER BY t.total_size DESC, f.user_id, f.file_type
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(limit)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let decimal_to_i64 = |value: Option<sqlx::types::Decimal>| -> i64 {
+            value
+                .unwrap_or(sqlx::types::Decimal::from(0))
+                .to_string()
+                .parse()
+                .unwrap_or(0)
+        };
+
+        // Rows arrive grouped by user in ranking order
+        let mut users: Vec<UserStorageUsage> = Vec::new();
+        for row in rows {
+            let user_id = Uuid::parse_str(row.get("user_id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?;
+            let file_type_str: String = row.get("file_type");
+            let file_type = match file_type_str.as_str() {
+                "image" => FileType::Image,
+                "video" => FileType::Video,
+                "document" => FileType::Document,
+                "audio" => FileType::Audio,
+                "other" => FileType::Other,
+                _ => continue, // Skip unknown types
+            };
+            let count = row.get::<i64, _>("count");
+
+            if users.last().map(|u| u.user_id) != Some(user_id) {
+                users.push(UserStorageUsage {
+                    user_id,
+                    user_name: row.get("user_name"),
+                    total_files: 0,
+                    total_size: decimal_to_i64(row.get("total_size")),
+                    by_type: Vec::new(),
+                });
+            }
+
+            let usage = users.last_mut().unwrap();
+            usage.total_files += count;
+            usage.by_type.push(TypeStats {
+                file_type,
+                count,
+                total_size: decimal_to_i64(row.get("size")),
+            });
+        }
+
+        Ok(users)
+    }
+
     // System Configuration
     pub async fn get_upload_config(db: &DbPool) -> Result<UploadConfig, AppError> {
         let configs = Self::get_system_configs(db, "file_upload").await?;
@@ -482,6 +1140,10 @@ impl FileUploadService {
         let configs = Self::get_system_configs(db, "file_upload").await?;
 
         Ok(ImageConfig {
+            max_file_size: configs
+                .get("max_image_size")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(10485760), // 10MB default
             max_width: configs
                 .get("max_image_width")
                 .and_then(|v| v.parse().ok())
@@ -514,6 +1176,18 @@ impl FileUploadService {
                         "webp".to_string(),
                     ]
                 }),
+            enable_watermark: configs
+                .get("enable_image_watermark")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(false),
+            watermark_text: configs
+                .get("image_watermark_text")
+                .cloned()
+                .unwrap_or_else(|| "{user_id} {timestamp}".to_string()),
+            keep_original: configs
+                .get("keep_original_image")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(true),
         })
     }
 
@@ -569,14 +1243,27 @@ impl FileUploadService {
     }
 
     // Helper methods
-    async fn validate_upload(dto: &CreateFileUploadDto) -> Result<(), AppError> {
-        // Validate file size based on type
-        let max_size = match &dto.file_type {
-            FileType::Image => 10 * 1024 * 1024,    // 10MB
-            FileType::Video => 100 * 1024 * 1024,   // 100MB
-            FileType::Document => 20 * 1024 * 1024, // 20MB
-            FileType::Audio => 50 * 1024 * 1024,    // 50MB
-            FileType::Other => 10 * 1024 * 1024,    // 10MB
+    /// 按 `file_upload` 配置校验上传：所有文件受 `max_file_size` 限制，图片、视频另受
+    /// 各自的大小上限和格式白名单限制；`allowed_mime_types` 为空时不限制 MIME 类型
+    async fn validate_upload(db: &DbPool, dto: &CreateFileUploadDto) -> Result<(), AppError> {
+        let upload_config = Self::get_upload_config(db).await?;
+
+        let (max_size, allowed_formats) = match &dto.file_type {
+            FileType::Image => {
+                let image_config = Self::get_image_config(db).await?;
+                (
+                    image_config.max_file_size.min(upload_config.max_file_size),
+                    Some(image_config.allowed_formats),
+                )
+            }
+            FileType::Video => {
+                let video_config = Self::get_video_config(db).await?;
+                (
+                    video_config.max_file_size.min(upload_config.max_file_size),
+                    Some(video_config.allowed_formats),
+                )
+            }
+            _ => (upload_config.max_file_size, None),
         };
 
         if dto.file_size > max_size {
@@ -588,34 +1275,61 @@ impl FileUploadService {
 
         // Validate MIME type
         if let Some(mime_type) = &dto.mime_type {
-            let allowed_types = match &dto.file_type {
-                FileType::Image => vec!["image/jpeg", "image/png", "image/gif", "image/webp"],
-                FileType::Video => vec!["video/mp4", "video/webm", "video/quicktime"],
-                FileType::Document => vec![
-                    "application/pdf",
-                    "application/msword",
-                    "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
-                ],
-                FileType::Audio => vec!["audio/mpeg", "audio/wav", "audio/ogg"],
-                FileType::Other => vec![], // Allow any for other
-            };
-
-            if !allowed_types.is_empty() && !allowed_types.contains(&mime_type.as_str()) {
+            if !upload_config.allowed_mime_types.is_empty()
+                && !upload_config.allowed_mime_types.contains(mime_type)
+            {
                 return Err(AppError::BadRequest("不支持的文件类型".to_string()));
             }
         }
 
+        // Validate file extension against the configured formats
+        if let Some(allowed_formats) = allowed_formats {
+            let extension = std::path::Path::new(&dto.file_name)
+                .extension()
+                .and_then(|e| e.to_str())
+                .map(|e| e.to_lowercase())
+                .unwrap_or_default();
+
+            if !allowed_formats
+                .iter()
+                .any(|format| format.eq_ignore_ascii_case(&extension))
+            {
+                return Err(AppError::BadRequest(format!(
+                    "不支持的文件格式: {}",
+                    if extension.is_empty() {
+                        "无扩展名"
+                    } else {
+                        &extension
+                    }
+                )));
+            }
+        }
+
         Ok(())
     }
 
     async fn generate_upload_url(
+        s3_client: Option<&S3Client>,
         upload_id: &Uuid,
         dto: &CreateFileUploadDto,
     ) -> Result<(String, String, String, Option<serde_json::Value>), AppError> {
-        // Generate file path
+        let file_path = Self::generate_file_path(upload_id, dto);
+
+        let (upload_url, upload_method, upload_headers) = Self::presign_upload(
+            s3_client,
+            &file_path,
+            dto.mime_type.as_deref(),
+            dto.related_type.is_some(),
+        )
+        .await?;
+
+        Ok((file_path, upload_url, upload_method, upload_headers))
+    }
+
+    fn generate_file_path(upload_id: &Uuid, dto: &CreateFileUploadDto) -> String {
         let date = Utc::now();
-        let extension = dto.file_name.split('.').last().unwrap_or("bin");
-        let file_path = format!(
+        let extension = dto.file_name.rsplit('.').next().unwrap_or("bin");
+        format!(
             "{}/{}/{}/{}_{}.{}",
             dto.file_type.to_string().to_lowercase(),
             date.format("%Y"),
@@ -623,18 +1337,79 @@ impl FileUploadService {
             upload_id,
             date.timestamp(),
             extension
-        );
+        )
+    }
 
-        // TODO: Integrate with actual OSS/S3 service to generate presigned URL
-        // For now, return a mock URL
-        let upload_url = format!("https://oss.example.com/upload/{}", file_path);
+    /// 通过对象存储签发 PUT 预签名地址，签名包含对象路径与 Content-Type，有效期与上传记录一致
+    async fn presign_upload(
+        s3_client: Option<&S3Client>,
+        file_path: &str,
+        mime_type: Option<&str>,
+        is_private: bool,
+    ) -> Result<(String, String, Option<serde_json::Value>), AppError> {
+        let s3_client = s3_client.ok_or_else(|| {
+            AppError::InternalServerError("未配置对象存储，无法生成上传地址".to_string())
+        })?;
+
+        let content_type = mime_type.unwrap_or("application/octet-stream");
+        let upload_url = FileStorageService::generate_presigned_upload_url(
+            s3_client,
+            file_path,
+            content_type,
+            UPLOAD_URL_EXPIRES_SECS,
+        )
+        .await?;
         let upload_method = "PUT".to_string();
         let upload_headers = Some(serde_json::json!({
-            "Content-Type": dto.mime_type.as_ref().unwrap_or(&"application/octet-stream".to_string()),
-            "x-oss-object-acl": if dto.related_type.is_some() { "private" } else { "public-read" }
+            "Content-Type": content_type,
+            "x-oss-object-acl": if is_private { "private" } else { "public-read" }
         }));
 
-        Ok((file_path, upload_url, upload_method, upload_headers))
+        Ok((upload_url, upload_method, upload_headers))
+    }
+
+    /// 图片水印：对外提供带水印的地址，原图地址按配置仅保存在服务端
+    async fn watermark_file_url(
+        db: &DbPool,
+        file: &FileUpload,
+        file_url: &str,
+    ) -> Result<(String, Option<String>), AppError> {
+        if file.file_type != FileType::Image {
+            return Ok((file_url.to_string(), None));
+        }
+
+        let image_config = Self::get_image_config(db).await?;
+        if !image_config.enable_watermark {
+            return Ok((file_url.to_string(), None));
+        }
+
+        let text = image_config
+            .watermark_text
+            .replace("{user_id}", &file.user_id.to_string())
+            .replace(
+                "{timestamp}",
+                &Utc::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            );
+        let original_url = if image_config.keep_original {
+            Some(file_url.to_string())
+        } else {
+            None
+        };
+
+        Ok((Self::watermark_url(file_url, &text), original_url))
+    }
+
+    /// 通过 OSS 图片处理参数生成带文字水印的访问地址，文字需 URL 安全的 Base64 编码
+    fn watermark_url(url: &str, text: &str) -> String {
+        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+        let separator = if url.contains('?') { '&' } else { '?' };
+        format!(
+            "{}{}x-oss-process=image/watermark,text_{},size_24,color_FFFFFF,t_60,g_se",
+            url,
+            separator,
+            URL_SAFE_NO_PAD.encode(text)
+        )
     }
 
     async fn get_system_configs(
@@ -686,58 +1461,144 @@ impl FileUploadService {
         Self::parse_system_config_from_row(&row)
     }
 
-    pub async fn clean_expired_uploads(db: &DbPool) -> Result<u64, AppError> {
-        let query = r#"
-            UPDATE file_uploads
-            SET status = 'failed', error_message = '上传超时'
-            WHERE status = 'uploading' 
+    /// 将超时未完成的上传标记为失败；分片上传同时在对象存储放弃已上传的分片，
+    /// 放弃失败时保留记录待下次重试，未配置存储客户端时跳过分片上传
+    pub async fn clean_expired_uploads(
+        db: &DbPool,
+        s3_client: Option<&S3Client>,
+    ) -> Result<u64, AppError> {
+        let query = if s3_client.is_some() {
+            r#"
+            SELECT id, file_path, multipart_upload_id
+            FROM file_uploads
+            WHERE status = 'uploading'
             AND uploaded_at < DATE_SUB(NOW(), INTERVAL 1 HOUR)
-        "#;
+            AND (expires_at IS NULL OR expires_at < NOW())
+            LIMIT ?
+        "#
+        } else {
+            r#"
+            SELECT id, file_path, multipart_upload_id
+            FROM file_uploads
+            WHERE status = 'uploading'
+            AND multipart_upload_id IS NULL
+            AND uploaded_at < DATE_SUB(NOW(), INTERVAL 1 HOUR)
+            AND (expires_at IS NULL OR expires_at < NOW())
+            LIMIT ?
+        "#
+        };
 
-        let result = sqlx::query(query)
-            .execute(db)
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let settings = SweepSettings::load(db).await?;
+        CleanupService::run_batched("expired_uploads", &settings, |limit| async move {
+            let uploads = sqlx::query(query)
+                .bind(limit)
+                .fetch_all(db)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        Ok(result.rows_affected())
+            let mut expired_count = 0;
+            for upload in uploads {
+                let upload_id_str: String = upload.get("id");
+                let upload_id = Uuid::parse_str(&upload_id_str)
+                    .map_err(|e| AppError::DatabaseError(format!("Invalid UUID: {}", e)))?;
+
+                let file_path: String = upload.get("file_path");
+                let multipart_upload_id: Option<String> = upload.get("multipart_upload_id");
+
+                if let (Some(client), Some(multipart_upload_id)) = (s3_client, &multipart_upload_id)
+                {
+                    if let Err(e) = FileStorageService::abort_multipart_upload(
+                        client,
+                        &file_path,
+                        multipart_upload_id,
+                    )
+                    .await
+                    {
+                        tracing::warn!(
+                            "Failed to abort multipart upload for file {}: {}",
+                            upload_id,
+                            e
+                        );
+                        continue;
+                    }
+                }
+
+                Self::fail_upload(db, upload_id, "上传超时").await?;
+                expired_count += 1;
+            }
+
+            Ok(expired_count)
+        })
+        .await
     }
 
-    pub async fn clean_deleted_files(db: &DbPool) -> Result<u64, AppError> {
-        // Get files deleted more than 30 days ago
+    /// 彻底清除删除超过 30 天的文件：先删除存储中的对象，成功后才删除数据库记录，
+    /// 未配置存储客户端时不清除，避免留下无记录的孤立对象
+    pub async fn clean_deleted_files(
+        db: &DbPool,
+        s3_client: Option<&S3Client>,
+    ) -> Result<u64, AppError> {
+        let s3_client = match s3_client {
+            Some(client) => client,
+            None => {
+                tracing::warn!("Storage client not configured, skipping deleted file cleanup");
+                return Ok(0);
+            }
+        };
+
+        // Get files deleted more than 30 days ago, one batch at a time
         let query = r#"
             SELECT id, file_path, bucket_name, object_key
             FROM file_uploads
             WHERE status = 'deleted' 
             AND deleted_at < DATE_SUB(NOW(), INTERVAL 30 DAY)
+            LIMIT ?
         "#;
 
-        let files = sqlx::query(query)
-            .fetch_all(db)
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        let mut deleted_count = 0;
-        for file in files {
-            let file_id_str: String = file.get("id");
-            let file_id = Uuid::parse_str(&file_id_str)
-                .map_err(|e| AppError::DatabaseError(format!("Invalid UUID: {}", e)))?;
-
-            // TODO: Delete from OSS/S3
-            // let file_path: String = file.get("file_path")?;
-            // oss_client.delete_object(&file_path).await?;
-
-            // Delete record from database
-            let delete_query = "DELETE FROM file_uploads WHERE id = ?";
-            sqlx::query(delete_query)
-                .bind(file_id.to_string())
-                .execute(db)
+        let settings = SweepSettings::load(db).await?;
+        CleanupService::run_batched("deleted_files", &settings, |limit| async move {
+            let files = sqlx::query(query)
+                .bind(limit)
+                .fetch_all(db)
                 .await
                 .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-            deleted_count += 1;
-        }
+            let mut deleted_count = 0;
+            for file in files {
+                let file_id_str: String = file.get("id");
+                let file_id = Uuid::parse_str(&file_id_str)
+                    .map_err(|e| AppError::DatabaseError(format!("Invalid UUID: {}", e)))?;
+
+                let file_path: String = file.get("file_path");
+                let bucket_name: Option<String> = file.get("bucket_name");
+                let object_key: Option<String> = file.get("object_key");
+
+                // Keep the record so the next sweep retries the storage delete
+                if let Err(e) = Self::delete_stored_object(
+                    s3_client,
+                    bucket_name.as_deref(),
+                    object_key.as_deref().unwrap_or(&file_path),
+                )
+                .await
+                {
+                    tracing::warn!("Failed to delete stored object for file {}: {}", file_id, e);
+                    continue;
+                }
+
+                // Delete record from database
+                let delete_query = "DELETE FROM file_uploads WHERE id = ?";
+                sqlx::query(delete_query)
+                    .bind(file_id.to_string())
+                    .execute(db)
+                    .await
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+                deleted_count += 1;
+            }
 
-        Ok(deleted_count)
+            Ok(deleted_count)
+        })
+        .await
     }
 }
 
@@ -0,0 +1,89 @@
+use crate::{
+    config::{
+        database::{DbPool, MIGRATOR},
+        redis::RedisPool,
+    },
+    models::health::{DependencyCheck, ReadinessReport},
+};
+use sqlx::Row;
+use std::time::Duration;
+
+/// 单项依赖检查的超时时间，避免探针因依赖挂起而阻塞
+const CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct HealthService;
+
+impl HealthService {
+    pub async fn check_readiness(db: &DbPool, redis: Option<&RedisPool>) -> ReadinessReport {
+        let database = Self::check_database(db).await;
+        // 数据库不可用时迁移状态无从检查，直接沿用数据库的错误
+        let migrations = if database.is_healthy() {
+            Self::check_migrations(db).await
+        } else {
+            DependencyCheck::failed("database unavailable")
+        };
+        let redis = match redis {
+            Some(redis) => Self::check_redis(redis).await,
+            None => DependencyCheck::disabled(),
+        };
+
+        ReadinessReport {
+            ready: database.is_healthy() && migrations.is_healthy() && redis.is_healthy(),
+            database,
+            redis,
+            migrations,
+        }
+    }
+
+    async fn check_database(db: &DbPool) -> DependencyCheck {
+        match tokio::time::timeout(CHECK_TIMEOUT, sqlx::query("SELECT 1").execute(db)).await {
+            Ok(Ok(_)) => DependencyCheck::ok(),
+            Ok(Err(e)) => DependencyCheck::failed(e.to_string()),
+            Err(_) => DependencyCheck::failed("database check timed out"),
+        }
+    }
+
+    async fn check_redis(redis: &RedisPool) -> DependencyCheck {
+        let mut conn = redis.clone();
+        let cmd = redis::cmd("PING");
+        let ping = cmd.query_async::<_, String>(&mut conn);
+
+        match tokio::time::timeout(CHECK_TIMEOUT, ping).await {
+            Ok(Ok(_)) => DependencyCheck::ok(),
+            Ok(Err(e)) => DependencyCheck::failed(e.to_string()),
+            Err(_) => DependencyCheck::failed("redis check timed out"),
+        }
+    }
+
+    /// 所有内置迁移都已成功执行才视为就绪
+    async fn check_migrations(db: &DbPool) -> DependencyCheck {
+        let query = sqlx::query("SELECT version, success FROM _sqlx_migrations").fetch_all(db);
+
+        let rows = match tokio::time::timeout(CHECK_TIMEOUT, query).await {
+            Ok(Ok(rows)) => rows,
+            Ok(Err(e)) => return DependencyCheck::failed(e.to_string()),
+            Err(_) => return DependencyCheck::failed("migration check timed out"),
+        };
+
+        let mut applied = Vec::with_capacity(rows.len());
+        for row in rows {
+            let version: i64 = row.get("version");
+            let success: bool = row.get("success");
+            if !success {
+                return DependencyCheck::failed(format!("migration {} is dirty", version));
+            }
+            applied.push(version);
+        }
+
+        let pending = MIGRATOR
+            .iter()
+            .filter(|migration| !applied.contains(&migration.version))
+            .count();
+
+        if pending > 0 {
+            DependencyCheck::failed(format!("{} migrations pending", pending))
+        } else {
+            DependencyCheck::ok()
+        }
+    }
+}
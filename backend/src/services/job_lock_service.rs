@@ -0,0 +1,163 @@
+use crate::config::database::DbPool;
+use crate::config::redis::{self, RedisLock, RedisPool};
+use crate::utils::errors::AppError;
+use chrono::Utc;
+use std::time::Duration;
+use tokio::task::JoinHandle;
+use uuid::Uuid;
+
+/// 已获取的任务锁
+#[derive(Debug, Clone)]
+pub enum JobLock {
+    Redis(RedisLock),
+    Database { name: String, token: String },
+}
+
+impl JobLock {
+    pub fn name(&self) -> &str {
+        match self {
+            JobLock::Redis(lock) => &lock.key,
+            JobLock::Database { name, .. } => name,
+        }
+    }
+}
+
+pub struct JobLockService;
+
+impl JobLockService {
+    /// 获取锁：优先使用 Redis，Redis 未配置或不可用时退回数据库锁
+    pub async fn acquire(
+        db: &DbPool,
+        redis: &Option<RedisPool>,
+        name: &str,
+        ttl: Duration,
+    ) -> Result<Option<JobLock>, AppError> {
+        if let Some(redis) = redis {
+            match redis::acquire_lock(redis, name, ttl).await {
+                Ok(lock) => return Ok(lock.map(JobLock::Redis)),
+                Err(e) => tracing::warn!(
+                    "Redis lock {} unavailable, falling back to database lock: {}",
+                    name,
+                    e
+                ),
+            }
+        }
+
+        Self::acquire_db_lock(db, name, ttl).await
+    }
+
+    /// 数据库锁：主键冲突即视为被占用，过期的锁先被清除
+    pub async fn acquire_db_lock(
+        db: &DbPool,
+        name: &str,
+        ttl: Duration,
+    ) -> Result<Option<JobLock>, AppError> {
+        let now = Utc::now();
+
+        // 持有者崩溃后遗留的锁、到期的周期标记一并清除，过期的锁可被抢占
+        sqlx::query("DELETE FROM job_locks WHERE expires_at < ?")
+            .bind(now)
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let token = Uuid::new_v4().to_string();
+        let result =
+            sqlx::query("INSERT IGNORE INTO job_locks (name, token, expires_at) VALUES (?, ?, ?)")
+                .bind(name)
+                .bind(&token)
+                .bind(now + chrono::Duration::milliseconds(ttl.as_millis() as i64))
+                .execute(db)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Ok(None);
+        }
+
+        Ok(Some(JobLock::Database {
+            name: name.to_string(),
+            token,
+        }))
+    }
+
+    /// 续期锁，锁已丢失时返回 false
+    pub async fn extend(
+        db: &DbPool,
+        redis: &Option<RedisPool>,
+        lock: &JobLock,
+        ttl: Duration,
+    ) -> Result<bool, AppError> {
+        match lock {
+            JobLock::Redis(lock) => match redis {
+                Some(redis) => redis::extend_lock(redis, lock, ttl)
+                    .await
+                    .map_err(|e| AppError::InternalServerError(e.to_string())),
+                None => Ok(false),
+            },
+            JobLock::Database { name, token } => {
+                let expires_at =
+                    Utc::now() + chrono::Duration::milliseconds(ttl.as_millis() as i64);
+                let result =
+                    sqlx::query("UPDATE job_locks SET expires_at = ? WHERE name = ? AND token = ?")
+                        .bind(expires_at)
+                        .bind(name)
+                        .bind(token)
+                        .execute(db)
+                        .await
+                        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+                Ok(result.rows_affected() == 1)
+            }
+        }
+    }
+
+    /// 释放锁，只会删除自己持有的锁；锁已过期或被他人持有时返回 false
+    pub async fn release(
+        db: &DbPool,
+        redis: &Option<RedisPool>,
+        lock: &JobLock,
+    ) -> Result<bool, AppError> {
+        match lock {
+            JobLock::Redis(lock) => match redis {
+                Some(redis) => redis::release_lock(redis, lock)
+                    .await
+                    .map_err(|e| AppError::InternalServerError(e.to_string())),
+                None => Ok(false),
+            },
+            JobLock::Database { name, token } => {
+                let result = sqlx::query("DELETE FROM job_locks WHERE name = ? AND token = ?")
+                    .bind(name)
+                    .bind(token)
+                    .execute(db)
+                    .await
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+                Ok(result.rows_affected() == 1)
+            }
+        }
+    }
+
+    /// 启动心跳，每隔 ttl/3 续期一次，直到任务结束后被 abort 或锁丢失
+    pub fn spawn_heartbeat(
+        db: DbPool,
+        redis: Option<RedisPool>,
+        lock: JobLock,
+        ttl: Duration,
+    ) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(ttl / 3).await;
+
+                match Self::extend(&db, &redis, &lock, ttl).await {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        tracing::warn!("Lost job lock {} while the job was running", lock.name());
+                        break;
+                    }
+                    Err(e) => tracing::warn!("Failed to extend job lock {}: {}", lock.name(), e),
+                }
+            }
+        })
+    }
+}
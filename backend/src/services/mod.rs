@@ -1,32 +1,43 @@
 pub mod appointment_service;
+pub mod audit_service;
 pub mod auth_service;
 pub mod auth_service_cached;
+pub mod broadcast_service;
 pub mod cache_service;
 pub mod circle_post_service;
 pub mod circle_service;
+pub mod cleanup_service;
 pub mod content_service;
 pub mod department_service;
 pub mod department_service_cached;
+pub mod doctor_earnings_service;
 pub mod doctor_service;
 pub mod file_storage_service;
 pub mod file_upload_service;
+pub mod health_service;
+pub mod job_lock_service;
 pub mod live_stream_service;
 pub mod notification_service;
 // pub mod notification_service_enhanced;
 pub mod patient_group_service;
 pub mod patient_profile_service;
+pub mod payment_gateway;
+pub mod payment_metrics_service;
 pub mod payment_service;
 pub mod prescription_service;
 pub mod review_service;
+pub mod scheduler_service;
 pub mod session_service;
 pub mod statistics_service;
+pub mod system_config_service;
 pub mod template_service;
 pub mod user_service;
 pub mod user_service_cached;
 pub mod video_consultation_service;
 pub mod websocket_service;
-// pub mod wechat_pay_service;
-// pub mod alipay_service;
+pub mod withdrawal_service;
+pub mod wechat_pay_service;
+pub mod alipay_service;
 pub mod email_service;
 pub mod push_notification_service;
 pub mod sms_service;
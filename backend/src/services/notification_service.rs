@@ -1,5 +1,14 @@
-use crate::{config::database::DbPool, models::notification::*};
+use crate::{
+    config::database::DbPool,
+    models::notification::*,
+    services::{
+        cleanup_service::{CleanupService, SweepSettings},
+        system_config_service::SystemConfigService,
+    },
+    utils::errors::AppError,
+};
 use chrono::Utc;
+use std::collections::HashMap;
 use uuid::Uuid;
 
 pub struct NotificationService;
@@ -39,6 +48,7 @@ impl NotificationService {
                     "review_reply" => NotificationType::ReviewReply,
                     "live_stream_reminder" => NotificationType::LiveStreamReminder,
                     "group_message" => NotificationType::GroupMessage,
+                    "payment_update" => NotificationType::PaymentUpdate,
                     _ => return Err(sqlx::Error::ColumnDecode {
                         index: "notification_type".to_string(),
                         source: Box::new(std::io::Error::new(
@@ -108,6 +118,7 @@ impl NotificationService {
                     "review_reply" => NotificationType::ReviewReply,
                     "live_stream_reminder" => NotificationType::LiveStreamReminder,
                     "group_message" => NotificationType::GroupMessage,
+                    "payment_update" => NotificationType::PaymentUpdate,
                     _ => return Err(sqlx::Error::ColumnDecode {
                         index: "notification_type".to_string(),
                         source: Box::new(std::io::Error::new(
@@ -201,13 +212,15 @@ impl NotificationService {
         pool: &DbPool,
         user_id: Uuid,
         status: Option<NotificationStatus>,
+        notification_type: Option<NotificationType>,
+        related_id: Option<Uuid>,
         page: i64,
         page_size: i64,
     ) -> Result<(Vec<Notification>, i64), sqlx::Error> {
         let offset = (page - 1) * page_size;
 
         // 构建查询条件
-        let status_condition = match status {
+        let mut conditions = match status {
             Some(s) => format!(
                 "AND status = '{}'",
                 match s {
@@ -219,15 +232,27 @@ impl NotificationService {
             None => "AND status != 'deleted'".to_string(),
         };
 
+        // 类型和关联对象条件使用绑定参数
+        let mut params = vec![];
+        if let Some(t) = notification_type {
+            conditions.push_str(" AND type = ?");
+            params.push(t.to_string());
+        }
+        if let Some(rid) = related_id {
+            conditions.push_str(" AND related_id = ?");
+            params.push(rid.to_string());
+        }
+
         // 获取总数
         let count_query = format!(
             "SELECT COUNT(*) as count FROM notifications WHERE user_id = ? {}",
-            status_condition
+            conditions
         );
-        let total: i64 = sqlx::query_scalar(&count_query)
-            .bind(user_id.to_string())
-            .fetch_one(pool)
-            .await?;
+        let mut count_builder = sqlx::query_scalar(&count_query).bind(user_id.to_string());
+        for param in &params {
+            count_builder = count_builder.bind(param);
+        }
+        let total: i64 = count_builder.fetch_one(pool).await?;
 
         // 获取通知列表
         let list_query = format!(
@@ -240,11 +265,14 @@ impl NotificationService {
             ORDER BY created_at DESC
             LIMIT ? OFFSET ?
             "#,
-            status_condition
+            conditions
         );
 
-        let rows = sqlx::query(&list_query)
-            .bind(user_id.to_string())
+        let mut list_builder = sqlx::query(&list_query).bind(user_id.to_string());
+        for param in &params {
+            list_builder = list_builder.bind(param);
+        }
+        let rows = list_builder
             .bind(page_size)
             .bind(offset)
             .fetch_all(pool)
@@ -340,6 +368,54 @@ impl NotificationService {
         Ok(result.rows_affected() > 0)
     }
 
+    /// 清空用户所有已读通知（软删除）
+    pub async fn clear_read_notifications(
+        pool: &DbPool,
+        user_id: Uuid,
+    ) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query(
+            r#"
+            UPDATE notifications
+            SET status = 'deleted'
+            WHERE user_id = ? AND status = 'read'
+            "#,
+        )
+        .bind(user_id.to_string())
+        .execute(pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// 物理删除阅读时间超过保留期的已读/已删除通知（未读即删除的按创建时间计），未读通知始终保留
+    pub async fn clean_old_notifications(pool: &DbPool) -> Result<u64, AppError> {
+        let retention_days =
+            SystemConfigService::get_i64(pool, "notification", "retention_days", 90).await?;
+        if retention_days <= 0 {
+            return Ok(0);
+        }
+
+        let settings = SweepSettings::load(pool).await?;
+        CleanupService::run_batched("old_notifications", &settings, |limit| async move {
+            let result = sqlx::query(
+                r#"
+                DELETE FROM notifications
+                WHERE status IN ('read', 'deleted')
+                AND COALESCE(read_at, created_at) < DATE_SUB(NOW(), INTERVAL ? DAY)
+                LIMIT ?
+                "#,
+            )
+            .bind(retention_days)
+            .bind(limit)
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            Ok(result.rows_affected())
+        })
+        .await
+    }
+
     /// 获取用户通知统计
     pub async fn get_notification_stats(
         pool: &DbPool,
@@ -465,8 +541,9 @@ impl NotificationService {
 
             Self::parse_notification_settings_from_row(&row)?
         } else {
-            // 创建新设置
+            // 创建新设置，未指定的字段按默认模板填充
             let settings_id = Uuid::new_v4();
+            let defaults = Self::default_settings_for(pool, &dto.notification_type).await?;
 
             sqlx::query(
                 r#"
@@ -478,10 +555,10 @@ impl NotificationService {
             .bind(settings_id.to_string())
             .bind(user_id.to_string())
             .bind(dto.notification_type.to_string())
-            .bind(dto.enabled.unwrap_or(true))
-            .bind(dto.email_enabled.unwrap_or(false))
-            .bind(dto.sms_enabled.unwrap_or(false))
-            .bind(dto.push_enabled.unwrap_or(true))
+            .bind(dto.enabled.unwrap_or(defaults.enabled))
+            .bind(dto.email_enabled.unwrap_or(defaults.email_enabled))
+            .bind(dto.sms_enabled.unwrap_or(defaults.sms_enabled))
+            .bind(dto.push_enabled.unwrap_or(defaults.push_enabled))
             .execute(pool)
             .await?;
 
@@ -754,7 +831,95 @@ impl NotificationService {
                     row.get("push_enabled"),
                 ))
             }
-            None => Ok((true, false, false, true)), // 默认启用通知和推送，禁用邮件和短信
+            None => {
+                // 没有设置记录时以默认模板为准
+                let defaults = Self::default_settings_for(pool, notification_type).await?;
+                Ok((
+                    defaults.enabled,
+                    defaults.email_enabled,
+                    defaults.sms_enabled,
+                    defaults.push_enabled,
+                ))
+            }
+        }
+    }
+
+    /// 按用户通知设置创建通知，用户关闭该类型通知时不创建并返回 None
+    pub async fn create_notification_if_enabled(
+        pool: &DbPool,
+        dto: CreateNotificationDto,
+    ) -> Result<Option<Notification>, sqlx::Error> {
+        let (enabled, _, _, _) =
+            Self::should_send_notification(pool, dto.user_id, &dto.notification_type).await?;
+
+        if !enabled {
+            return Ok(None);
         }
+
+        Self::create_notification(pool, dto).await.map(Some)
+    }
+
+    /// 读取默认通知设置模板（notification.default_settings），缺失或格式错误时返回空模板
+    pub async fn get_default_settings_template(
+        pool: &DbPool,
+    ) -> Result<HashMap<String, NotificationChannelDefaults>, sqlx::Error> {
+        let value: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT config_value
+            FROM system_configs
+            WHERE category = 'notification' AND config_key = 'default_settings'
+            "#,
+        )
+        .fetch_optional(pool)
+        .await?;
+
+        Ok(value
+            .and_then(|v| serde_json::from_str(&v).ok())
+            .unwrap_or_default())
+    }
+
+    /// 某通知类型的默认设置，模板中未配置时启用通知和推送
+    pub async fn default_settings_for(
+        pool: &DbPool,
+        notification_type: &NotificationType,
+    ) -> Result<NotificationChannelDefaults, sqlx::Error> {
+        let template = Self::get_default_settings_template(pool).await?;
+
+        Ok(template
+            .get(&notification_type.to_string())
+            .copied()
+            .unwrap_or_default())
+    }
+
+    /// 按默认模板初始化用户的全部通知设置，已存在的设置不会被覆盖
+    pub async fn seed_default_settings(pool: &DbPool, user_id: Uuid) -> Result<u64, sqlx::Error> {
+        let template = Self::get_default_settings_template(pool).await?;
+        let mut inserted = 0;
+
+        for notification_type in NotificationType::ALL.iter() {
+            let type_str = notification_type.to_string();
+            let defaults = template.get(&type_str).copied().unwrap_or_default();
+
+            let result = sqlx::query(
+                r#"
+                INSERT IGNORE INTO notification_settings
+                (id, user_id, notification_type, enabled, email_enabled, sms_enabled, push_enabled)
+                VALUES (?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(Uuid::new_v4().to_string())
+            .bind(user_id.to_string())
+            .bind(&type_str)
+            .bind(defaults.enabled)
+            .bind(defaults.email_enabled)
+            .bind(defaults.sms_enabled)
+            .bind(defaults.push_enabled)
+            .execute(pool)
+            .await?;
+
+            inserted += result.rows_affected();
+        }
+
+        Ok(inserted)
     }
 }
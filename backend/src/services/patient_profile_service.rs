@@ -3,6 +3,12 @@ use anyhow::{anyhow, Result};
 use chrono::Utc;
 use uuid::Uuid;
 
+/// 每个账号最多可代管的家人档案数（不含本人档案）
+pub const MAX_MANAGED_PROFILES: i64 = 5;
+
+/// 每个账号最多保存的上门地址数
+pub const MAX_ADDRESSES: i64 = 10;
+
 pub async fn list_user_profiles(pool: &DbPool, user_id: Uuid) -> Result<Vec<PatientProfile>> {
     let query = r#"
         SELECT id, user_id, name, id_number, phone, gender, birthday, 
@@ -70,25 +76,56 @@ pub async fn create_profile(
     user_id: Uuid,
     dto: CreatePatientProfileDto,
 ) -> Result<PatientProfile> {
-    // Validate ID number
-    if !validate_id_number(&dto.id_number) {
-        return Err(anyhow!("Invalid ID number format"));
+    use sqlx::Row;
+
+    if !validate_phone(&dto.phone) {
+        return Err(anyhow!("Invalid phone number format"));
     }
 
-    // Check if this ID number is already used by another profile
-    let check_query =
-        "SELECT COUNT(*) as count FROM patient_profiles WHERE id_number = ? AND user_id != ?";
-    let count_row = sqlx::query(check_query)
-        .bind(&dto.id_number)
+    if let Some(birthday) = dto.birthday {
+        if !validate_birthday(birthday) {
+            return Err(anyhow!("Invalid birthday"));
+        }
+    }
+
+    // ID number is optional for managed profiles, but must be valid when given
+    if let Some(id_number) = &dto.id_number {
+        if !validate_id_number(id_number) {
+            return Err(anyhow!("Invalid ID number format"));
+        }
+
+        // Check if this ID number is already used by another profile
+        let check_query =
+            "SELECT COUNT(*) as count FROM patient_profiles WHERE id_number = ? AND user_id != ?";
+        let count_row = sqlx::query(check_query)
+            .bind(id_number)
+            .bind(user_id.to_string())
+            .fetch_one(pool)
+            .await
+            .map_err(|e| anyhow!("Failed to check ID number: {}", e))?;
+
+        let count: i64 = count_row.get("count");
+        if count > 0 {
+            return Err(anyhow!("This ID number is already registered"));
+        }
+    }
+
+    if !matches!(dto.relationship, Relationship::MySelf) {
+        let managed_row = sqlx::query(
+            "SELECT COUNT(*) as count FROM patient_profiles WHERE user_id = ? AND relationship != 'self'",
+        )
         .bind(user_id.to_string())
         .fetch_one(pool)
         .await
-        .map_err(|e| anyhow!("Failed to check ID number: {}", e))?;
-
-    use sqlx::Row;
-    let count: i64 = count_row.get("count");
-    if count > 0 {
-        return Err(anyhow!("This ID number is already registered"));
+        .map_err(|e| anyhow!("Failed to count patient profiles: {}", e))?;
+
+        let managed: i64 = managed_row.get("count");
+        if managed >= MAX_MANAGED_PROFILES {
+            return Err(anyhow!(
+                "Profile limit reached: at most {} family profiles per account",
+                MAX_MANAGED_PROFILES
+            ));
+        }
     }
 
     let profile_id = Uuid::new_v4();
@@ -160,69 +197,53 @@ pub async fn update_profile(
         .await
         .map_err(|_| anyhow!("Patient profile not found or access denied"))?;
 
-    let mut update_fields = Vec::new();
-    let mut bindings: Vec<String> = Vec::new();
-
-    if let Some(name) = &dto.name {
-        update_fields.push("name = ?");
-        bindings.push(name.clone());
-    }
-
     if let Some(phone) = &dto.phone {
-        update_fields.push("phone = ?");
-        bindings.push(phone.clone());
+        if !validate_phone(phone) {
+            return Err(anyhow!("Invalid phone number format"));
+        }
     }
 
-    if let Some(gender) = &dto.gender {
-        update_fields.push("gender = ?");
-        let gender_str = match gender {
-            Gender::Male => "男",
-            Gender::Female => "女",
-        };
-        bindings.push(gender_str.to_string());
+    if let Some(birthday) = dto.birthday {
+        if !validate_birthday(birthday) {
+            return Err(anyhow!("Invalid birthday"));
+        }
     }
 
-    if dto.birthday.is_some() {
-        update_fields.push("birthday = ?");
-    }
+    let gender_str = dto.gender.as_ref().map(|gender| match gender {
+        Gender::Male => "男",
+        Gender::Female => "女",
+    });
 
-    if let Some(relationship) = &dto.relationship {
-        update_fields.push("relationship = ?");
-        let relationship_str = match relationship {
+    let relationship_str = dto
+        .relationship
+        .as_ref()
+        .map(|relationship| match relationship {
             Relationship::MySelf => "self",
             Relationship::Family => "family",
             Relationship::Friend => "friend",
             Relationship::Other => "other",
-        };
-        bindings.push(relationship_str.to_string());
-    }
-
-    update_fields.push("updated_at = ?");
-
-    if update_fields.len() == 1 {
-        // Only updated_at
-        return get_profile_by_id(pool, id, user_id).await;
-    }
-
-    let query = format!(
-        "UPDATE patient_profiles SET {} WHERE id = ?",
-        update_fields.join(", ")
-    );
+        });
 
-    let mut query_builder = sqlx::query(&query);
-
-    for binding in bindings {
-        query_builder = query_builder.bind(binding);
-    }
-
-    if dto.birthday.is_some() {
-        query_builder = query_builder.bind(dto.birthday);
-    }
-
-    query_builder = query_builder.bind(Utc::now());
-    query_builder = query_builder.bind(id.to_string());
+    // Omitted fields keep their current value
+    let query = r#"
+        UPDATE patient_profiles
+        SET name = COALESCE(?, name),
+            phone = COALESCE(?, phone),
+            gender = COALESCE(?, gender),
+            birthday = COALESCE(?, birthday),
+            relationship = COALESCE(?, relationship),
+            updated_at = ?
+        WHERE id = ?
+    "#;
 
-    query_builder
+    sqlx::query(query)
+        .bind(&dto.name)
+        .bind(&dto.phone)
+        .bind(gender_str)
+        .bind(dto.birthday)
+        .bind(relationship_str)
+        .bind(Utc::now())
+        .bind(id.to_string())
         .execute(pool)
         .await
         .map_err(|e| anyhow!("Failed to update patient profile: {}", e))?;
@@ -239,6 +260,19 @@ pub async fn delete_profile(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<()
         return Err(anyhow!("Cannot delete self profile"));
     }
 
+    // Visits booked for this profile keep pointing at it
+    let booked_row =
+        sqlx::query("SELECT COUNT(*) as count FROM appointments WHERE patient_profile_id = ?")
+            .bind(id.to_string())
+            .fetch_one(pool)
+            .await
+            .map_err(|e| anyhow!("Failed to check profile appointments: {}", e))?;
+
+    let booked: i64 = sqlx::Row::get(&booked_row, "count");
+    if booked > 0 {
+        return Err(anyhow!("Cannot delete a profile that has appointments"));
+    }
+
     let query = "DELETE FROM patient_profiles WHERE id = ? AND user_id = ?";
 
     let result = sqlx::query(query)
@@ -293,6 +327,212 @@ pub async fn set_default_profile(pool: &DbPool, id: Uuid, user_id: Uuid) -> Resu
     Ok(())
 }
 
+pub async fn list_addresses(pool: &DbPool, user_id: Uuid) -> Result<Vec<PatientAddress>> {
+    let query = r#"
+        SELECT id, user_id, recipient_name, phone, province, city, district, detail,
+               is_default, created_at, updated_at
+        FROM patient_addresses
+        WHERE user_id = ?
+        ORDER BY is_default DESC, created_at DESC
+    "#;
+
+    let rows = sqlx::query(query)
+        .bind(user_id.to_string())
+        .fetch_all(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch addresses: {}", e))?;
+
+    rows.iter().map(parse_address_from_row).collect()
+}
+
+pub async fn get_address_by_id(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<PatientAddress> {
+    let query = r#"
+        SELECT id, user_id, recipient_name, phone, province, city, district, detail,
+               is_default, created_at, updated_at
+        FROM patient_addresses
+        WHERE id = ? AND user_id = ?
+    "#;
+
+    let row = sqlx::query(query)
+        .bind(id.to_string())
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch address: {}", e))?
+        .ok_or_else(|| anyhow!("Address not found or access denied"))?;
+
+    parse_address_from_row(&row)
+}
+
+pub async fn get_default_address(pool: &DbPool, user_id: Uuid) -> Result<Option<PatientAddress>> {
+    let query = r#"
+        SELECT id, user_id, recipient_name, phone, province, city, district, detail,
+               is_default, created_at, updated_at
+        FROM patient_addresses
+        WHERE user_id = ? AND is_default = TRUE
+        LIMIT 1
+    "#;
+
+    let row = sqlx::query(query)
+        .bind(user_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to fetch default address: {}", e))?;
+
+    row.as_ref().map(parse_address_from_row).transpose()
+}
+
+/// The first address always becomes the default, so a default exists whenever any address does
+pub async fn add_address(
+    pool: &DbPool,
+    user_id: Uuid,
+    dto: CreatePatientAddressDto,
+) -> Result<PatientAddress> {
+    if !validate_phone(&dto.phone) {
+        return Err(anyhow!("Invalid phone number format"));
+    }
+
+    let existing: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM patient_addresses WHERE user_id = ?")
+            .bind(user_id.to_string())
+            .fetch_one(pool)
+            .await
+            .map_err(|e| anyhow!("Failed to count addresses: {}", e))?;
+
+    if existing >= MAX_ADDRESSES {
+        return Err(anyhow!(
+            "Address limit reached: at most {} addresses per account",
+            MAX_ADDRESSES
+        ));
+    }
+
+    let address_id = Uuid::new_v4();
+    let now = Utc::now();
+    let is_default = dto.is_default || existing == 0;
+
+    let mut tx = pool.begin().await?;
+
+    if is_default {
+        sqlx::query("UPDATE patient_addresses SET is_default = FALSE WHERE user_id = ?")
+            .bind(user_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| anyhow!("Failed to unset default address: {}", e))?;
+    }
+
+    let query = r#"
+        INSERT INTO patient_addresses (id, user_id, recipient_name, phone, province, city,
+                                       district, detail, is_default, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+    "#;
+
+    sqlx::query(query)
+        .bind(address_id.to_string())
+        .bind(user_id.to_string())
+        .bind(&dto.recipient_name)
+        .bind(&dto.phone)
+        .bind(&dto.province)
+        .bind(&dto.city)
+        .bind(&dto.district)
+        .bind(&dto.detail)
+        .bind(is_default)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to create address: {}", e))?;
+
+    tx.commit().await?;
+
+    get_address_by_id(pool, address_id, user_id).await
+}
+
+pub async fn set_default_address(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<()> {
+    get_address_by_id(pool, id, user_id).await?;
+
+    // Swap the default in one transaction so there is never a moment without one
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE patient_addresses SET is_default = FALSE WHERE user_id = ?")
+        .bind(user_id.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to unset default address: {}", e))?;
+
+    sqlx::query("UPDATE patient_addresses SET is_default = TRUE, updated_at = ? WHERE id = ?")
+        .bind(Utc::now())
+        .bind(id.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| anyhow!("Failed to set default address: {}", e))?;
+
+    tx.commit().await?;
+
+    Ok(())
+}
+
+/// The default address can only go once another one has been made default, or when it is the
+/// last address left
+pub async fn delete_address(pool: &DbPool, id: Uuid, user_id: Uuid) -> Result<()> {
+    let address = get_address_by_id(pool, id, user_id).await?;
+
+    if address.is_default {
+        let others: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM patient_addresses WHERE user_id = ? AND id != ?",
+        )
+        .bind(user_id.to_string())
+        .bind(id.to_string())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to count addresses: {}", e))?;
+
+        if others > 0 {
+            return Err(anyhow!(
+                "Cannot delete the default address: set another default first"
+            ));
+        }
+    }
+
+    // Home visits booked at this address keep pointing at it
+    let booked: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM appointments WHERE address_id = ?")
+        .bind(id.to_string())
+        .fetch_one(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to check address appointments: {}", e))?;
+
+    if booked > 0 {
+        return Err(anyhow!("Cannot delete an address that has appointments"));
+    }
+
+    sqlx::query("DELETE FROM patient_addresses WHERE id = ? AND user_id = ?")
+        .bind(id.to_string())
+        .bind(user_id.to_string())
+        .execute(pool)
+        .await
+        .map_err(|e| anyhow!("Failed to delete address: {}", e))?;
+
+    Ok(())
+}
+
+fn parse_address_from_row(row: &sqlx::mysql::MySqlRow) -> Result<PatientAddress> {
+    use sqlx::Row;
+
+    Ok(PatientAddress {
+        id: Uuid::parse_str(row.get("id")).map_err(|e| anyhow!("Failed to parse UUID: {}", e))?,
+        user_id: Uuid::parse_str(row.get("user_id"))
+            .map_err(|e| anyhow!("Failed to parse UUID: {}", e))?,
+        recipient_name: row.get("recipient_name"),
+        phone: row.get("phone"),
+        province: row.get("province"),
+        city: row.get("city"),
+        district: row.get("district"),
+        detail: row.get("detail"),
+        is_default: row.get("is_default"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    })
+}
+
 fn parse_patient_profile_from_row(row: &sqlx::mysql::MySqlRow) -> Result<PatientProfile> {
     use sqlx::Row;
 
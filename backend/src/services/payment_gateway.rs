@@ -0,0 +1,102 @@
+use crate::utils::errors::AppError;
+use futures_util::future::BoxFuture;
+use reqwest::Method;
+use std::time::Duration;
+
+/// 发往支付渠道的 HTTP 请求，签名由调用方完成
+#[derive(Debug, Clone)]
+pub struct GatewayRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+/// 支付渠道返回的原始响应
+#[derive(Debug, Clone)]
+pub struct GatewayResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl GatewayResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// 按名称（不区分大小写）取响应头
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// 支付渠道的 HTTP 传输层，测试中可替换为不访问网络的实现
+pub trait PaymentGateway: Send + Sync {
+    fn send(&self, request: GatewayRequest) -> BoxFuture<'_, Result<GatewayResponse, AppError>>;
+}
+
+/// 基于 reqwest 的默认实现
+pub struct HttpPaymentGateway {
+    client: reqwest::Client,
+}
+
+impl HttpPaymentGateway {
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    pub fn new() -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Self::TIMEOUT)
+            .build()
+            .unwrap_or_default();
+        Self { client }
+    }
+}
+
+impl Default for HttpPaymentGateway {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl PaymentGateway for HttpPaymentGateway {
+    fn send(&self, request: GatewayRequest) -> BoxFuture<'_, Result<GatewayResponse, AppError>> {
+        Box::pin(async move {
+            let mut builder = self.client.request(request.method, &request.url);
+            for (name, value) in &request.headers {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+            if let Some(body) = request.body {
+                builder = builder.body(body);
+            }
+
+            let network_error = |e: reqwest::Error| AppError::PaymentGatewayError {
+                code: "NETWORK_ERROR".to_string(),
+                message: e.to_string(),
+            };
+            let response = builder.send().await.map_err(network_error)?;
+
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    value
+                        .to_str()
+                        .ok()
+                        .map(|value| (name.to_string(), value.to_string()))
+                })
+                .collect();
+            let body = response.text().await.map_err(network_error)?;
+
+            Ok(GatewayResponse {
+                status,
+                headers,
+                body,
+            })
+        })
+    }
+}
@@ -0,0 +1,292 @@
+use crate::models::payment::PaymentMethod;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+/// 支付失败率告警配置
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PaymentAlertConfig {
+    /// 失败率阈值（0~1），滚动窗口内失败率超过该值时告警
+    pub failure_rate_threshold: f64,
+    /// 滚动窗口长度（秒）
+    pub window_secs: u64,
+    /// 窗口内至少有多少笔支付结果才参与判断，避免样本过少误报
+    pub min_samples: usize,
+    /// 告警 Webhook 地址，未配置时只记录日志
+    #[serde(skip_serializing)]
+    pub webhook_url: Option<String>,
+}
+
+impl Default for PaymentAlertConfig {
+    fn default() -> Self {
+        Self {
+            failure_rate_threshold: 0.5,
+            window_secs: 300,
+            min_samples: 10,
+            webhook_url: None,
+        }
+    }
+}
+
+impl PaymentAlertConfig {
+    pub fn from_env() -> Self {
+        let default = Self::default();
+
+        Self {
+            failure_rate_threshold: std::env::var("PAYMENT_FAILURE_ALERT_THRESHOLD")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.failure_rate_threshold),
+            window_secs: std::env::var("PAYMENT_FAILURE_ALERT_WINDOW_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.window_secs),
+            min_samples: std::env::var("PAYMENT_FAILURE_ALERT_MIN_SAMPLES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default.min_samples),
+            webhook_url: std::env::var("PAYMENT_ALERT_WEBHOOK_URL")
+                .ok()
+                .filter(|v| !v.is_empty()),
+        }
+    }
+}
+
+/// 单个支付方式的累计计数
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct PaymentMethodCounters {
+    pub initiated: u64,
+    pub succeeded: u64,
+    pub failed: u64,
+}
+
+/// 支付失败率告警内容
+#[derive(Debug, Clone, Serialize)]
+pub struct PaymentFailureAlert {
+    pub failure_rate: f64,
+    pub failed: usize,
+    pub total: usize,
+    pub window_secs: u64,
+    pub threshold: f64,
+    pub triggered_at: DateTime<Utc>,
+}
+
+/// 支付指标快照：按支付方式的累计计数与滚动窗口内的失败率
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PaymentMetricsSnapshot {
+    pub methods: BTreeMap<String, PaymentMethodCounters>,
+    pub window_total: usize,
+    pub window_failed: usize,
+    pub window_failure_rate: f64,
+    pub config: PaymentAlertConfig,
+    pub last_alert_at: Option<DateTime<Utc>>,
+}
+
+pub type PaymentAlertHook = Arc<dyn Fn(&PaymentFailureAlert) + Send + Sync>;
+
+#[derive(Default)]
+struct MetricsState {
+    methods: BTreeMap<String, PaymentMethodCounters>,
+    /// 窗口内的支付结果，true 表示失败
+    outcomes: VecDeque<(Instant, bool)>,
+    last_alert: Option<(Instant, DateTime<Utc>)>,
+}
+
+/// 进程内支付指标与失败率检测
+pub struct PaymentMetrics {
+    config: PaymentAlertConfig,
+    state: Mutex<MetricsState>,
+    hooks: Mutex<Vec<PaymentAlertHook>>,
+}
+
+static GLOBAL_METRICS: OnceLock<PaymentMetrics> = OnceLock::new();
+
+impl PaymentMetrics {
+    pub fn new(config: PaymentAlertConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(MetricsState::default()),
+            hooks: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 全局实例，配置取自环境变量
+    pub fn global() -> &'static PaymentMetrics {
+        GLOBAL_METRICS.get_or_init(|| PaymentMetrics::new(PaymentAlertConfig::from_env()))
+    }
+
+    /// 注册告警回调，告警触发时同步调用
+    pub fn add_alert_hook(&self, hook: PaymentAlertHook) {
+        self.hooks.lock().unwrap().push(hook);
+    }
+
+    pub fn record_initiated(&self, method: &PaymentMethod) {
+        let mut state = self.state.lock().unwrap();
+        state
+            .methods
+            .entry(method_key(method).to_string())
+            .or_default()
+            .initiated += 1;
+    }
+
+    pub fn record_success(&self, method: &PaymentMethod) {
+        self.record_outcome(method, false);
+    }
+
+    /// 记录一次失败，失败率越过阈值时返回触发的告警
+    pub fn record_failure(&self, method: &PaymentMethod) -> Option<PaymentFailureAlert> {
+        self.record_outcome(method, true)
+    }
+
+    pub fn snapshot(&self) -> PaymentMetricsSnapshot {
+        let mut state = self.state.lock().unwrap();
+        self.prune(&mut state, Instant::now());
+
+        let (total, failed) = window_counts(&state);
+
+        PaymentMetricsSnapshot {
+            methods: state.methods.clone(),
+            window_total: total,
+            window_failed: failed,
+            window_failure_rate: failure_rate(total, failed),
+            config: self.config.clone(),
+            last_alert_at: state.last_alert.map(|(_, at)| at),
+        }
+    }
+
+    fn record_outcome(
+        &self,
+        method: &PaymentMethod,
+        is_failure: bool,
+    ) -> Option<PaymentFailureAlert> {
+        let now = Instant::now();
+        let alert = {
+            let mut state = self.state.lock().unwrap();
+
+            let counters = state
+                .methods
+                .entry(method_key(method).to_string())
+                .or_default();
+            if is_failure {
+                counters.failed += 1;
+            } else {
+                counters.succeeded += 1;
+            }
+
+            state.outcomes.push_back((now, is_failure));
+            self.prune(&mut state, now);
+
+            if !is_failure {
+                return None;
+            }
+
+            let (total, failed) = window_counts(&state);
+            let rate = failure_rate(total, failed);
+            if total < self.config.min_samples || rate <= self.config.failure_rate_threshold {
+                return None;
+            }
+
+            // Alert at most once per window while the spike lasts
+            let window = Duration::from_secs(self.config.window_secs);
+            if let Some((last, _)) = state.last_alert {
+                if now.duration_since(last) < window {
+                    return None;
+                }
+            }
+
+            let alert = PaymentFailureAlert {
+                failure_rate: rate,
+                failed,
+                total,
+                window_secs: self.config.window_secs,
+                threshold: self.config.failure_rate_threshold,
+                triggered_at: Utc::now(),
+            };
+            state.last_alert = Some((now, alert.triggered_at));
+            alert
+        };
+
+        self.fire_alert(&alert);
+        Some(alert)
+    }
+
+    fn prune(&self, state: &mut MetricsState, now: Instant) {
+        let window = Duration::from_secs(self.config.window_secs);
+        while let Some((at, _)) = state.outcomes.front() {
+            if now.duration_since(*at) > window {
+                state.outcomes.pop_front();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn fire_alert(&self, alert: &PaymentFailureAlert) {
+        tracing::error!(
+            severity = "high",
+            failure_rate = alert.failure_rate,
+            failed = alert.failed,
+            total = alert.total,
+            window_secs = alert.window_secs,
+            threshold = alert.threshold,
+            "Payment failure rate exceeded threshold"
+        );
+
+        for hook in self.hooks.lock().unwrap().iter() {
+            hook(alert);
+        }
+
+        if let Some(url) = self.config.webhook_url.clone() {
+            // The webhook must never block or fail the payment request itself
+            if let Ok(handle) = tokio::runtime::Handle::try_current() {
+                let alert = alert.clone();
+                handle.spawn(async move {
+                    let result = Client::new()
+                        .post(&url)
+                        .timeout(Duration::from_secs(5))
+                        .json(&serde_json::json!({
+                            "event": "payment_failure_spike",
+                            "alert": alert,
+                        }))
+                        .send()
+                        .await;
+
+                    if let Err(e) = result {
+                        tracing::warn!("Failed to deliver payment alert webhook: {}", e);
+                    }
+                });
+            }
+        }
+    }
+}
+
+fn window_counts(state: &MetricsState) -> (usize, usize) {
+    let total = state.outcomes.len();
+    let failed = state
+        .outcomes
+        .iter()
+        .filter(|(_, is_failure)| *is_failure)
+        .count();
+    (total, failed)
+}
+
+fn failure_rate(total: usize, failed: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        failed as f64 / total as f64
+    }
+}
+
+fn method_key(method: &PaymentMethod) -> &'static str {
+    match method {
+        PaymentMethod::Wechat => "wechat",
+        PaymentMethod::Alipay => "alipay",
+        PaymentMethod::BankCard => "bank_card",
+        PaymentMethod::Balance => "balance",
+    }
+}
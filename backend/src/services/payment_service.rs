@@ -1,64 +1,639 @@
 use crate::config::database::DbPool;
 use crate::models::payment::*;
+use crate::services::alipay_service::{AlipayConfig, AlipayService, AlipayTradeType, Rsa2Signer};
+use crate::services::audit_service::AuditService;
+use crate::services::doctor_earnings_service::DoctorEarningsService;
+use crate::services::payment_gateway::{GatewayRequest, PaymentGateway};
+use crate::services::payment_metrics_service::PaymentMetrics;
+use crate::services::system_config_service::SystemConfigService;
+use crate::services::wechat_pay_service::{WechatPayConfig, WechatPayService, WechatTradeType};
 use crate::utils::errors::AppError;
-use chrono::{Duration, Utc};
-use rust_decimal::Decimal;
+use chrono::{DateTime, Duration, FixedOffset, NaiveDate, NaiveTime, Utc};
+use rand::Rng;
+use rust_decimal::{Decimal, RoundingStrategy};
+use sqlx::mysql::MySqlArguments;
+use sqlx::query::Query;
 use sqlx::{MySql, Transaction};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicU64, Ordering};
 use uuid::Uuid;
 
+/// 业务单号的进程内递增序号
+static SERIAL_SEQUENCE: AtomicU64 = AtomicU64::new(0);
+
 pub struct PaymentService;
 
+/// 一次回调投递的处理结果，附带匹配到的支付流水
+enum CallbackOutcome {
+    Processed(Uuid),
+    Duplicate(Option<Uuid>),
+}
+
 impl PaymentService {
+    /// 下单幂等键的有效期（小时），超过后同一个键可再次下单
+    const IDEMPOTENCY_WINDOW_HOURS: i64 = 24;
+    /// 订单号撞号时的最大尝试次数
+    const ORDER_NO_MAX_ATTEMPTS: usize = 3;
+
     // Order management
     pub async fn create_order(
         db: &DbPool,
         create_dto: CreateOrderDto,
     ) -> Result<PaymentOrder, AppError> {
+        let idempotency_key = create_dto
+            .idempotency_key
+            .as_deref()
+            .map(str::trim)
+            .filter(|key| !key.is_empty());
+        if let Some(key) = idempotency_key {
+            if let Some(order) = Self::find_idempotent_order(db, create_dto.user_id, key).await? {
+                return Ok(order);
+            }
+        }
+
+        let quote = Self::quote_order(db, &create_dto).await?;
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let (coupon_id, discount_amount) = match create_dto.coupon_code.as_deref() {
+            Some(code) => {
+                let (coupon_id, discount) =
+                    Self::apply_coupon(&mut tx, code.trim(), quote.amount).await?;
+                (Some(coupon_id), discount)
+            }
+            None => (None, Decimal::ZERO),
+        };
+        let amount = quote.amount - discount_amount;
+        if discount_amount > Decimal::ZERO {
+            Self::validate_order_amount(db, &create_dto.order_type, amount).await?;
+        }
+
         let order_id = Uuid::new_v4();
-        let order_no = Self::generate_order_no();
         let now = Utc::now();
         let expire_time = now + Duration::hours(2); // 2 hour expiration
 
         let query = r#"
             INSERT INTO payment_orders (
-                id, order_no, user_id, appointment_id, order_type,
+                id, order_no, idempotency_key, user_id, appointment_id, order_type,
                 amount, currency, status, expire_time, description,
-                metadata, created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, ?, 'CNY', 'pending', ?, ?, ?, ?, ?)
+                metadata, coupon_id, discount_amount, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, 'CNY', 'pending', ?, ?, ?, ?, ?, ?, ?)
         "#;
 
         let order_type_str = match create_dto.order_type {
             OrderType::Appointment => "appointment",
             OrderType::Consultation => "consultation",
             OrderType::Prescription => "prescription",
+            OrderType::Recharge => "recharge",
             OrderType::Other => "other",
         };
 
-        sqlx::query(query)
-            .bind(order_id.to_string())
-            .bind(&order_no)
-            .bind(create_dto.user_id.to_string())
-            .bind(create_dto.appointment_id.map(|id| id.to_string()))
-            .bind(order_type_str)
-            .bind(create_dto.amount)
-            .bind(expire_time)
-            .bind(create_dto.description.as_deref())
-            .bind(
-                create_dto
-                    .metadata
-                    .as_ref()
-                    .and_then(|m| serde_json::to_string(m).ok()),
-            )
-            .bind(now)
-            .bind(now)
-            .execute(db)
+        let metadata = create_dto
+            .metadata
+            .as_ref()
+            .and_then(|m| serde_json::to_string(m).ok());
+
+        let mut attempt = 1;
+        let inserted = loop {
+            let inserted = sqlx::query(query)
+                .bind(order_id.to_string())
+                .bind(Self::generate_order_no())
+                .bind(idempotency_key)
+                .bind(create_dto.user_id.to_string())
+                .bind(create_dto.appointment_id.map(|id| id.to_string()))
+                .bind(order_type_str)
+                .bind(amount)
+                .bind(expire_time)
+                .bind(create_dto.description.as_deref())
+                .bind(metadata.as_deref())
+                .bind(coupon_id.map(|id| id.to_string()))
+                .bind(discount_amount)
+                .bind(now)
+                .bind(now)
+                .execute(&mut *tx)
+                .await;
+
+            // An order_no collision only fails this statement, so retry with a fresh number
+            match inserted {
+                Err(e)
+                    if attempt < Self::ORDER_NO_MAX_ATTEMPTS && Self::is_duplicate_order_no(&e) =>
+                {
+                    attempt += 1;
+                }
+                result => break result,
+            }
+        };
+
+        if let Err(e) = inserted {
+            // A concurrent retry with the same key got there first; dropping the
+            // transaction also rolls back this attempt's coupon redemption
+            let duplicate = e.to_string().contains("Duplicate entry");
+            drop(tx);
+            if let (Some(key), true) = (idempotency_key, duplicate) {
+                if let Some(order) =
+                    Self::find_idempotent_order(db, create_dto.user_id, key).await?
+                {
+                    return Ok(order);
+                }
+            }
+            return Err(AppError::DatabaseError(e.to_string()));
+        }
+
+        tx.commit()
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         Self::get_order(db, order_id).await
     }
 
+    fn is_duplicate_order_no(error: &sqlx::Error) -> bool {
+        let message = error.to_string();
+        message.contains("Duplicate entry") && message.contains("order_no")
+    }
+
+    /// 查找同一用户在幂等窗口内以该键创建的订单，过期的键会先被释放以便复用
+    async fn find_idempotent_order(
+        db: &DbPool,
+        user_id: Uuid,
+        idempotency_key: &str,
+    ) -> Result<Option<PaymentOrder>, AppError> {
+        let window_start = Utc::now() - Duration::hours(Self::IDEMPOTENCY_WINDOW_HOURS);
+
+        sqlx::query(
+            r#"
+            UPDATE payment_orders SET idempotency_key = NULL
+            WHERE user_id = ? AND idempotency_key = ? AND created_at < ?
+            "#,
+        )
+        .bind(user_id.to_string())
+        .bind(idempotency_key)
+        .bind(window_start)
+        .execute(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let row =
+            sqlx::query("SELECT * FROM payment_orders WHERE user_id = ? AND idempotency_key = ?")
+                .bind(user_id.to_string())
+                .bind(idempotency_key)
+                .fetch_optional(db)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        row.map(Self::parse_order_row).transpose()
+    }
+
+    /// 在下单事务中核销优惠券，返回优惠券ID和实际抵扣金额（不超过订单金额）。
+    /// 使用次数通过条件更新递增，并发核销同一张优惠券时不会超过可用次数。
+    async fn apply_coupon(
+        tx: &mut Transaction<'_, MySql>,
+        code: &str,
+        order_amount: Decimal,
+    ) -> Result<(Uuid, Decimal), AppError> {
+        let row = sqlx::query("SELECT * FROM coupons WHERE code = ?")
+            .bind(code)
+            .fetch_optional(&mut **tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::BadRequest("优惠券不存在或已失效".to_string()))?;
+        let coupon = Self::parse_coupon_row(row)?;
+
+        Self::check_coupon_usable(&coupon, Utc::now())?;
+        let discount = Self::coupon_discount(&coupon, order_amount)?;
+
+        let result = sqlx::query(
+            "UPDATE coupons SET used = used + 1 WHERE id = ? AND is_active = TRUE AND used < usage_limit",
+        )
+        .bind(coupon.id.to_string())
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::BadRequest("优惠券已被用完".to_string()));
+        }
+
+        Ok((coupon.id, discount))
+    }
+
+    /// 订单取消或过期时归还核销的优惠券次数，与订单状态变更在同一事务中执行
+    async fn release_coupon(
+        tx: &mut Transaction<'_, MySql>,
+        coupon_id: Uuid,
+    ) -> Result<(), AppError> {
+        sqlx::query("UPDATE coupons SET used = used - 1 WHERE id = ? AND used > 0")
+            .bind(coupon_id.to_string())
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn check_coupon_usable(coupon: &Coupon, now: DateTime<Utc>) -> Result<(), AppError> {
+        if !coupon.is_active {
+            return Err(AppError::BadRequest("优惠券不存在或已失效".to_string()));
+        }
+        if coupon.starts_at.is_some_and(|starts_at| starts_at > now) {
+            return Err(AppError::BadRequest("优惠券尚未生效".to_string()));
+        }
+        if coupon
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= now)
+        {
+            return Err(AppError::BadRequest("优惠券已过期".to_string()));
+        }
+        if coupon.used >= coupon.usage_limit {
+            return Err(AppError::BadRequest("优惠券已被用完".to_string()));
+        }
+        Ok(())
+    }
+
+    /// 计算优惠券对该订单金额的抵扣，按比例抵扣向下取整到分，抵扣不超过订单金额
+    fn coupon_discount(coupon: &Coupon, order_amount: Decimal) -> Result<Decimal, AppError> {
+        if order_amount < coupon.min_order_amount {
+            return Err(AppError::BadRequest(format!(
+                "订单金额未达到优惠券使用门槛{}元",
+                coupon.min_order_amount
+            )));
+        }
+
+        let discount = match coupon.discount_type {
+            CouponDiscountType::Fixed => coupon.discount_amount,
+            CouponDiscountType::Percent => {
+                let percent = coupon.discount_percent.unwrap_or(Decimal::ZERO);
+                (order_amount * percent / Decimal::ONE_HUNDRED)
+                    .round_dp_with_strategy(2, RoundingStrategy::ToZero)
+            }
+        };
+        Ok(discount.min(order_amount))
+    }
+
+    /// 试算优惠券抵扣后的金额，校验规则与下单一致但不核销
+    pub async fn validate_coupon(
+        db: &DbPool,
+        dto: &ValidateCouponDto,
+    ) -> Result<CouponPreview, AppError> {
+        Self::validate_order_amount(db, &dto.order_type, dto.amount).await?;
+
+        let row = sqlx::query("SELECT * FROM coupons WHERE code = ?")
+            .bind(dto.code.trim())
+            .fetch_optional(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::BadRequest("优惠券不存在或已失效".to_string()))?;
+        let coupon = Self::parse_coupon_row(row)?;
+
+        Self::check_coupon_usable(&coupon, Utc::now())?;
+        let discount = Self::coupon_discount(&coupon, dto.amount)?;
+        let final_amount = dto.amount - discount;
+        if discount > Decimal::ZERO {
+            Self::validate_order_amount(db, &dto.order_type, final_amount).await?;
+        }
+
+        Ok(CouponPreview {
+            coupon_id: coupon.id,
+            code: coupon.code,
+            original_amount: dto.amount,
+            discount_amount: discount,
+            final_amount,
+        })
+    }
+
+    // Coupon management
+    pub async fn create_coupon(
+        db: &DbPool,
+        dto: CreateCouponDto,
+        admin_id: Uuid,
+    ) -> Result<Coupon, AppError> {
+        let discount_amount = dto.discount_amount.unwrap_or(Decimal::ZERO);
+        let min_order_amount = dto.min_order_amount.unwrap_or(Decimal::ZERO);
+        Self::validate_coupon_terms(
+            dto.discount_type,
+            discount_amount,
+            dto.discount_percent,
+            min_order_amount,
+            dto.starts_at,
+            dto.expires_at,
+        )?;
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let coupon_id = Uuid::new_v4();
+        let code = dto.code.trim();
+        sqlx::query(
+            r#"
+            INSERT INTO coupons (
+                id, code, discount_type, discount_amount, discount_percent,
+                min_order_amount, usage_limit, starts_at, expires_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(coupon_id.to_string())
+        .bind(code)
+        .bind(Self::coupon_discount_type_str(dto.discount_type))
+        .bind(discount_amount)
+        .bind(dto.discount_percent)
+        .bind(min_order_amount)
+        .bind(dto.usage_limit)
+        .bind(dto.starts_at)
+        .bind(dto.expires_at)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| {
+            if e.to_string().contains("Duplicate entry") {
+                AppError::BadRequest("优惠码已存在".to_string())
+            } else {
+                AppError::DatabaseError(e.to_string())
+            }
+        })?;
+
+        AuditService::log(
+            &mut *tx,
+            admin_id,
+            "coupon.create",
+            "coupon",
+            coupon_id,
+            Some(serde_json::json!({ "code": code })),
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_coupon(db, coupon_id).await
+    }
+
+    pub async fn update_coupon(
+        db: &DbPool,
+        coupon_id: Uuid,
+        dto: UpdateCouponDto,
+        admin_id: Uuid,
+    ) -> Result<Coupon, AppError> {
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let row = sqlx::query("SELECT * FROM coupons WHERE id = ? FOR UPDATE")
+            .bind(coupon_id.to_string())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("优惠券不存在".to_string()))?;
+        let current = Self::parse_coupon_row(row)?;
+
+        let discount_amount = dto.discount_amount.unwrap_or(current.discount_amount);
+        let discount_percent = dto.discount_percent.or(current.discount_percent);
+        let min_order_amount = dto.min_order_amount.unwrap_or(current.min_order_amount);
+        let usage_limit = dto.usage_limit.unwrap_or(current.usage_limit);
+        let is_active = dto.is_active.unwrap_or(current.is_active);
+        let starts_at = dto.starts_at.or(current.starts_at);
+        let expires_at = dto.expires_at.or(current.expires_at);
+
+        Self::validate_coupon_terms(
+            current.discount_type,
+            discount_amount,
+            discount_percent,
+            min_order_amount,
+            starts_at,
+            expires_at,
+        )?;
+        if usage_limit < current.used {
+            return Err(AppError::BadRequest(format!(
+                "可使用次数不能低于已使用次数{}",
+                current.used
+            )));
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE coupons
+            SET discount_amount = ?, discount_percent = ?, min_order_amount = ?,
+                usage_limit = ?, is_active = ?, starts_at = ?, expires_at = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(discount_amount)
+        .bind(discount_percent)
+        .bind(min_order_amount)
+        .bind(usage_limit)
+        .bind(is_active)
+        .bind(starts_at)
+        .bind(expires_at)
+        .bind(Utc::now())
+        .bind(coupon_id.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        AuditService::log(
+            &mut *tx,
+            admin_id,
+            "coupon.update",
+            "coupon",
+            coupon_id,
+            Some(serde_json::json!({
+                "code": current.code,
+                "usage_limit": usage_limit,
+                "is_active": is_active,
+            })),
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_coupon(db, coupon_id).await
+    }
+
+    /// 停用优惠券，已核销的订单不受影响
+    pub async fn deactivate_coupon(
+        db: &DbPool,
+        coupon_id: Uuid,
+        admin_id: Uuid,
+    ) -> Result<Coupon, AppError> {
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let result =
+            sqlx::query("UPDATE coupons SET is_active = FALSE, updated_at = ? WHERE id = ?")
+                .bind(Utc::now())
+                .bind(coupon_id.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("优惠券不存在".to_string()));
+        }
+
+        AuditService::log(
+            &mut *tx,
+            admin_id,
+            "coupon.deactivate",
+            "coupon",
+            coupon_id,
+            None,
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_coupon(db, coupon_id).await
+    }
+
+    pub async fn get_coupon(db: &DbPool, coupon_id: Uuid) -> Result<Coupon, AppError> {
+        let row = sqlx::query("SELECT * FROM coupons WHERE id = ?")
+            .bind(coupon_id.to_string())
+            .fetch_optional(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("优惠券不存在".to_string()))?;
+
+        Self::parse_coupon_row(row)
+    }
+
+    pub async fn list_coupons(db: &DbPool) -> Result<Vec<Coupon>, AppError> {
+        let rows = sqlx::query("SELECT * FROM coupons ORDER BY created_at DESC")
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        rows.into_iter().map(Self::parse_coupon_row).collect()
+    }
+
+    fn validate_coupon_terms(
+        discount_type: CouponDiscountType,
+        discount_amount: Decimal,
+        discount_percent: Option<Decimal>,
+        min_order_amount: Decimal,
+        starts_at: Option<DateTime<Utc>>,
+        expires_at: Option<DateTime<Utc>>,
+    ) -> Result<(), AppError> {
+        match discount_type {
+            CouponDiscountType::Fixed if discount_amount <= Decimal::ZERO => {
+                return Err(AppError::BadRequest("抵扣金额必须大于0".to_string()));
+            }
+            CouponDiscountType::Percent
+                if !discount_percent
+                    .is_some_and(|p| p > Decimal::ZERO && p <= Decimal::ONE_HUNDRED) =>
+            {
+                return Err(AppError::BadRequest("折扣比例必须在0到100之间".to_string()));
+            }
+            _ => {}
+        }
+        if min_order_amount < Decimal::ZERO {
+            return Err(AppError::BadRequest("使用门槛不能为负数".to_string()));
+        }
+        if let (Some(starts_at), Some(expires_at)) = (starts_at, expires_at) {
+            if starts_at >= expires_at {
+                return Err(AppError::BadRequest("生效时间必须早于过期时间".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// 计算订单应付金额并做与下单相同的校验，但不创建订单，供客户端下单前展示价格
+    pub async fn quote_order(
+        db: &DbPool,
+        create_dto: &CreateOrderDto,
+    ) -> Result<OrderQuote, AppError> {
+        let (min_amount, max_amount) =
+            Self::order_amount_limits(db, &create_dto.order_type).await?;
+        Self::check_amount_within(create_dto.amount, min_amount, max_amount)?;
+
+        Ok(OrderQuote {
+            order_type: create_dto.order_type.clone(),
+            amount: create_dto.amount,
+            currency: "CNY".to_string(),
+            min_amount,
+            max_amount,
+        })
+    }
+
+    /// 校验订单金额是否在该订单类型配置的上下限之内。
+    /// 任何会改变应付金额的逻辑（如优惠券抵扣）都应对最终金额再次调用，保证不低于下限。
+    pub async fn validate_order_amount(
+        db: &DbPool,
+        order_type: &OrderType,
+        amount: Decimal,
+    ) -> Result<(), AppError> {
+        let (min_amount, max_amount) = Self::order_amount_limits(db, order_type).await?;
+        Self::check_amount_within(amount, min_amount, max_amount)
+    }
+
+    fn check_amount_within(
+        amount: Decimal,
+        min_amount: Decimal,
+        max_amount: Decimal,
+    ) -> Result<(), AppError> {
+        if amount < min_amount {
+            return Err(AppError::BadRequest(format!(
+                "订单金额不能低于{}元",
+                min_amount
+            )));
+        }
+        if amount > max_amount {
+            return Err(AppError::BadRequest(format!(
+                "订单金额不能高于{}元",
+                max_amount
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// 订单类型的金额上下限，未配置时使用内置默认值
+    async fn order_amount_limits(
+        db: &DbPool,
+        order_type: &OrderType,
+    ) -> Result<(Decimal, Decimal), AppError> {
+        let (type_key, default_min, default_max) = match order_type {
+            OrderType::Appointment => ("appointment", Decimal::ONE, Decimal::from(5000)),
+            OrderType::Consultation => ("consultation", Decimal::ONE, Decimal::from(5000)),
+            OrderType::Prescription => ("prescription", Decimal::ONE, Decimal::from(20000)),
+            OrderType::Recharge => return Self::recharge_amount_limits(db).await,
+            OrderType::Other => ("other", Decimal::new(1, 2), Decimal::from(50000)),
+        };
+
+        let configs = SystemConfigService::get_category(db, "order_amount").await?;
+        let limit = |suffix: &str, default: Decimal| {
+            configs
+                .get(&format!("{}_{}", type_key, suffix))
+                .and_then(|v| v.trim().parse::<Decimal>().ok())
+                .unwrap_or(default)
+        };
+
+        Ok((limit("min", default_min), limit("max", default_max)))
+    }
+
+    /// 充值金额上下限取自 `recharge_min` / `recharge_max` 价格配置，未配置时使用内置默认值
+    async fn recharge_amount_limits(db: &DbPool) -> Result<(Decimal, Decimal), AppError> {
+        let min_amount = Self::get_price_config(db, "recharge_min")
+            .await?
+            .map(|config| config.price)
+            .unwrap_or(Decimal::from(10));
+        let max_amount = Self::get_price_config(db, "recharge_max")
+            .await?
+            .map(|config| config.price)
+            .unwrap_or(Decimal::from(5000));
+
+        Ok((min_amount, max_amount))
+    }
+
     pub async fn get_order(db: &DbPool, order_id: Uuid) -> Result<PaymentOrder, AppError> {
         let query = r#"
             SELECT * FROM payment_orders WHERE id = ?
@@ -76,6 +651,69 @@ impl PaymentService {
         Self::parse_order_row(row)
     }
 
+    /// 在事务内锁定订单行，同一订单的取消、退款和支付回调因此串行执行
+    async fn lock_order(
+        tx: &mut Transaction<'_, MySql>,
+        order_id: Uuid,
+    ) -> Result<PaymentOrder, AppError> {
+        let row = sqlx::query("SELECT * FROM payment_orders WHERE id = ? FOR UPDATE")
+            .bind(order_id.to_string())
+            .fetch_one(&mut **tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => AppError::NotFound("订单不存在".to_string()),
+                _ => AppError::DatabaseError(e.to_string()),
+            })?;
+
+        Self::parse_order_row(row)
+    }
+
+    /// 按第三方支付平台的交易号查找订单，并返回该订单的全部交易记录
+    pub async fn get_order_by_external_transaction(
+        db: &DbPool,
+        external_id: &str,
+    ) -> Result<OrderWithTransactions, AppError> {
+        let order_id: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT order_id FROM payment_transactions
+            WHERE external_transaction_id = ?
+            ORDER BY initiated_at DESC LIMIT 1
+            "#,
+        )
+        .bind(external_id)
+        .fetch_optional(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let order_id = order_id
+            .and_then(|id| Uuid::parse_str(&id).ok())
+            .ok_or_else(|| AppError::NotFound("未找到该交易号对应的订单".to_string()))?;
+
+        let order = Self::get_order(db, order_id).await?;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM payment_transactions
+            WHERE order_id = ?
+            ORDER BY initiated_at ASC
+            "#,
+        )
+        .bind(order_id.to_string())
+        .fetch_all(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let transactions = rows
+            .into_iter()
+            .map(Self::parse_transaction_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(OrderWithTransactions {
+            order,
+            transactions,
+        })
+    }
+
     pub async fn get_order_by_no(db: &DbPool, order_no: &str) -> Result<PaymentOrder, AppError> {
         let query = r#"
             SELECT * FROM payment_orders WHERE order_no = ?
@@ -204,36 +842,378 @@ impl PaymentService {
         })
     }
 
-    pub async fn cancel_order(db: &DbPool, order_id: Uuid) -> Result<(), AppError> {
-        let order = Self::get_order(db, order_id).await?;
+    pub async fn list_transactions(
+        db: &DbPool,
+        query: TransactionListQuery,
+    ) -> Result<TransactionListResponse, AppError> {
+        let page = query.page.unwrap_or(1).max(1);
+        let page_size = query.page_size.unwrap_or(20).clamp(1, 100);
+        let offset = (page - 1) * page_size;
 
-        if order.status != OrderStatus::Pending {
-            return Err(AppError::BadRequest("只能取消待支付的订单".to_string()));
+        let mut where_clauses = vec![];
+
+        if query.order_id.is_some() {
+            where_clauses.push("order_id = ?");
         }
 
-        let query = r#"
-            UPDATE payment_orders
+        if query.payment_method.is_some() {
+            where_clauses.push("payment_method = ?");
+        }
+
+        if query.transaction_type.is_some() {
+            where_clauses.push("transaction_type = ?");
+        }
+
+        if query.status.is_some() {
+            where_clauses.push("status = ?");
+        }
+
+        if query.start_date.is_some() {
+            where_clauses.push("initiated_at >= ?");
+        }
+
+        if query.end_date.is_some() {
+            where_clauses.push("initiated_at <= ?");
+        }
+
+        let where_clause = if where_clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", where_clauses.join(" AND "))
+        };
+
+        // Count total
+        let count_query = format!(
+            "SELECT COUNT(*) as count FROM payment_transactions {}",
+            where_clause
+        );
+
+        let mut count_query_builder = sqlx::query_scalar::<_, i64>(&count_query);
+
+        // Bind parameters in order
+        if let Some(order_id) = &query.order_id {
+            count_query_builder = count_query_builder.bind(order_id.to_string());
+        }
+        if let Some(payment_method) = &query.payment_method {
+            count_query_builder = count_query_builder.bind(payment_method);
+        }
+        if let Some(transaction_type) = &query.transaction_type {
+            count_query_builder = count_query_builder.bind(transaction_type);
+        }
+        if let Some(status) = &query.status {
+            count_query_builder = count_query_builder.bind(status);
+        }
+        if let Some(start_date) = &query.start_date {
+            count_query_builder = count_query_builder.bind(start_date);
+        }
+        if let Some(end_date) = &query.end_date {
+            count_query_builder = count_query_builder.bind(end_date);
+        }
+
+        let total = count_query_builder
+            .fetch_one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // Fetch transactions
+        let transactions_query = format!(
+            "SELECT * FROM payment_transactions {} ORDER BY initiated_at DESC LIMIT ? OFFSET ?",
+            where_clause
+        );
+
+        let mut transactions_query_builder = sqlx::query(&transactions_query);
+
+        // Bind parameters in the same order
+        if let Some(order_id) = &query.order_id {
+            transactions_query_builder = transactions_query_builder.bind(order_id.to_string());
+        }
+        if let Some(payment_method) = &query.payment_method {
+            transactions_query_builder = transactions_query_builder.bind(payment_method);
+        }
+        if let Some(transaction_type) = &query.transaction_type {
+            transactions_query_builder = transactions_query_builder.bind(transaction_type);
+        }
+        if let Some(status) = &query.status {
+            transactions_query_builder = transactions_query_builder.bind(status);
+        }
+        if let Some(start_date) = &query.start_date {
+            transactions_query_builder = transactions_query_builder.bind(start_date);
+        }
+        if let Some(end_date) = &query.end_date {
+            transactions_query_builder = transactions_query_builder.bind(end_date);
+        }
+
+        let rows = transactions_query_builder
+            .bind(page_size)
+            .bind(offset)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut transactions = Vec::new();
+        for row in rows {
+            transactions.push(Self::parse_transaction_row(row)?);
+        }
+
+        Ok(TransactionListResponse {
+            transactions,
+            total,
+            page,
+            page_size,
+        })
+    }
+
+    pub async fn cancel_order(db: &DbPool, order_id: Uuid) -> Result<(), AppError> {
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let order = Self::lock_order(&mut tx, order_id).await?;
+
+        if order.status != OrderStatus::Pending {
+            return Err(AppError::BadRequest("只能取消待支付的订单".to_string()));
+        }
+
+        let query = r#"
+            UPDATE payment_orders
             SET status = 'cancelled', updated_at = ?
-            WHERE id = ? AND status = 'pending'
+            WHERE id = ?
         "#;
 
-        let result = sqlx::query(query)
+        sqlx::query(query)
             .bind(Utc::now())
             .bind(order_id.to_string())
-            .execute(db)
+            .execute(&mut *tx)
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        if result.rows_affected() == 0 {
-            return Err(AppError::BadRequest("订单状态已变更".to_string()));
+        if let Some(coupon_id) = order.coupon_id {
+            Self::release_coupon(&mut tx, coupon_id).await?;
         }
 
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
         Ok(())
     }
 
+    /// 将超过支付期限仍未支付的订单标记为已过期，返回过期的订单数。由后台任务按
+    /// `ORDER_EXPIRY_INTERVAL_SECS` 的间隔调用
+    ///
+    /// 订单状态由一条 UPDATE 统一从 pending 置为 expired，已支付等其他状态的订单不受影响；
+    /// 关联的待支付流水标记为失败（ORDER_EXPIRED）；已确认但没有其他已支付订单的预约退回待确认状态；
+    /// 订单核销的优惠券次数被归还。
+    pub async fn expire_stale_orders(db: &DbPool) -> Result<u64, AppError> {
+        let now = Utc::now();
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // Lock the overdue orders so a payment callback racing the sweep waits for it
+        let overdue: Vec<(String, Option<String>)> = sqlx::query_as(
+            "SELECT id, coupon_id FROM payment_orders WHERE status = 'pending' AND expire_time < ? FOR UPDATE",
+        )
+        .bind(now)
+        .fetch_all(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if overdue.is_empty() {
+            return Ok(0);
+        }
+
+        for coupon_id in overdue
+            .iter()
+            .filter_map(|(_, coupon_id)| coupon_id.as_deref())
+        {
+            let coupon_id = Uuid::parse_str(coupon_id)
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?;
+            Self::release_coupon(&mut tx, coupon_id).await?;
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE payment_transactions t
+            JOIN payment_orders o ON t.order_id = o.id
+            SET t.status = 'failed', t.error_code = 'ORDER_EXPIRED',
+                t.error_message = '订单已过期', t.completed_at = ?
+            WHERE o.status = 'pending' AND o.expire_time < ?
+              AND t.transaction_type = 'payment' AND t.status = 'pending'
+            "#,
+        )
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            UPDATE appointments a
+            JOIN payment_orders o ON o.appointment_id = a.id
+            SET a.status = 'pending', a.updated_at = ?
+            WHERE o.status = 'pending' AND o.expire_time < ? AND a.status = 'confirmed'
+              AND NOT EXISTS (
+                  SELECT 1 FROM payment_orders paid
+                  WHERE paid.appointment_id = a.id
+                    AND paid.status IN ('paid', 'partial_refunded')
+              )
+            "#,
+        )
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE payment_orders
+            SET status = 'expired', updated_at = ?
+            WHERE status = 'pending' AND expire_time < ?
+            "#,
+        )
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// 管理员强制调整订单状态，只允许合法的状态流转，并执行与正常流程相同的附带操作
+    ///
+    /// 标记为已支付时确认关联预约；标记为已退款只记录状态，不会实际退回资金。
+    pub async fn adjust_order_status(
+        db: &DbPool,
+        order_id: Uuid,
+        new_status: OrderStatus,
+        reason: &str,
+        admin_id: Uuid,
+    ) -> Result<PaymentOrder, AppError> {
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let order = Self::lock_order(&mut tx, order_id).await?;
+
+        let allowed = matches!(
+            (&order.status, &new_status),
+            (
+                OrderStatus::Pending | OrderStatus::Cancelled | OrderStatus::Expired,
+                OrderStatus::Paid
+            ) | (OrderStatus::Pending, OrderStatus::Cancelled)
+                | (OrderStatus::Pending, OrderStatus::Expired)
+                | (
+                    OrderStatus::Paid | OrderStatus::PartialRefunded,
+                    OrderStatus::Refunded
+                )
+        );
+        if !allowed {
+            return Err(AppError::BadRequest(format!(
+                "不允许将订单从 {} 调整为 {}",
+                Self::order_status_str(&order.status),
+                Self::order_status_str(&new_status)
+            )));
+        }
+
+        let now = Utc::now();
+        if new_status == OrderStatus::Paid {
+            sqlx::query(
+                r#"
+                UPDATE payment_orders
+                SET status = 'paid', payment_time = COALESCE(payment_time, ?), updated_at = ?
+                WHERE id = ?
+                "#,
+            )
+            .bind(now)
+            .bind(now)
+            .bind(order_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            // The gateway charged the user but the callback never arrived
+            sqlx::query(
+                r#"
+                UPDATE payment_transactions
+                SET status = 'success', completed_at = ?
+                WHERE order_id = ? AND transaction_type = 'payment' AND status = 'pending'
+                "#,
+            )
+            .bind(now)
+            .bind(order_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            if matches!(order.order_type, OrderType::Recharge) {
+                Self::credit_recharge_tx(&mut tx, &order).await?;
+            }
+            DoctorEarningsService::record_income_tx(db, &mut tx, &order).await?;
+
+            if let Some(appointment_id) = order.appointment_id {
+                sqlx::query(
+                    r#"
+                    UPDATE appointments
+                    SET status = 'confirmed', updated_at = ?
+                    WHERE id = ? AND status = 'pending'
+                    "#,
+                )
+                .bind(now)
+                .bind(appointment_id.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            }
+        } else {
+            sqlx::query("UPDATE payment_orders SET status = ?, updated_at = ? WHERE id = ?")
+                .bind(Self::order_status_str(&new_status))
+                .bind(now)
+                .bind(order_id.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            if new_status == OrderStatus::Refunded {
+                DoctorEarningsService::reverse_remaining_tx(&mut tx, order_id).await?;
+            }
+        }
+
+        AuditService::log(
+            &mut *tx,
+            admin_id,
+            "order.adjust_status",
+            "payment_order",
+            order_id,
+            Some(serde_json::json!({
+                "from": Self::order_status_str(&order.status),
+                "to": Self::order_status_str(&new_status),
+                "reason": reason,
+            })),
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_order(db, order_id).await
+    }
+
     // Payment processing
     pub async fn initiate_payment(
         db: &DbPool,
+        gateway: &dyn PaymentGateway,
         dto: InitiatePaymentDto,
     ) -> Result<PaymentResponse, AppError> {
         let order = Self::get_order(db, dto.order_id).await?;
@@ -246,6 +1226,17 @@ impl PaymentService {
             return Err(AppError::BadRequest("订单已过期".to_string()));
         }
 
+        if matches!(order.order_type, OrderType::Recharge)
+            && !matches!(
+                dto.payment_method,
+                PaymentMethod::Wechat | PaymentMethod::Alipay
+            )
+        {
+            return Err(AppError::BadRequest(
+                "充值仅支持微信或支付宝支付".to_string(),
+            ));
+        }
+
         // Create transaction record
         let transaction_id = Uuid::new_v4();
         let transaction_no = Self::generate_transaction_no();
@@ -273,50 +1264,111 @@ impl PaymentService {
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
+        let metrics = PaymentMetrics::global();
+        metrics.record_initiated(&dto.payment_method);
+
         // Process payment based on method
-        match dto.payment_method {
+        let result = match dto.payment_method {
             PaymentMethod::Wechat => {
-                Self::process_wechat_payment(db, &order, &transaction_id).await
+                Self::process_wechat_payment(db, gateway, &order, &transaction_id, dto.openid).await
             }
             PaymentMethod::Alipay => {
-                Self::process_alipay_payment(db, &order, &transaction_id, dto.return_url).await
+                Self::process_alipay_payment(db, &order, &transaction_id, dto.return_url, dto.wap)
+                    .await
             }
             PaymentMethod::Balance => {
                 Self::process_balance_payment(db, &order, &transaction_id).await
             }
             _ => Err(AppError::BadRequest("不支持的支付方式".to_string())),
+        };
+
+        // Balance payments settle immediately; gateway payments settle in the callback
+        match &result {
+            Ok(_) if matches!(dto.payment_method, PaymentMethod::Balance) => {
+                metrics.record_success(&dto.payment_method);
+            }
+            Ok(_) => {}
+            Err(_) => {
+                metrics.record_failure(&dto.payment_method);
+            }
         }
+
+        result
     }
 
     async fn process_wechat_payment(
         db: &DbPool,
+        gateway: &dyn PaymentGateway,
         order: &PaymentOrder,
         transaction_id: &Uuid,
+        openid: Option<String>,
     ) -> Result<PaymentResponse, AppError> {
-        // Get WeChat Pay configuration
-        let config = Self::get_payment_config(db, PaymentMethod::Wechat).await?;
+        let config =
+            WechatPayConfig::from_map(&Self::get_payment_config(db, PaymentMethod::Wechat).await?)?;
+
+        // JSAPI when the client knows the payer's openid, otherwise a Native QR code
+        let trade_type = match openid {
+            Some(openid) => WechatTradeType::Jsapi { openid },
+            None => WechatTradeType::Native,
+        };
+        let request_data = WechatPayService::transaction_request(&config, order, &trade_type)?;
+
+        // JSAPI returns a prepay_id for the client SDK, Native a code_url to render as QR
+        let prepay_field = match trade_type {
+            WechatTradeType::Jsapi { .. } => "prepay_id",
+            WechatTradeType::Native => "code_url",
+        };
+        let result =
+            WechatPayService::create_transaction(gateway, &config, &trade_type, &request_data)
+                .await
+                .and_then(|response_data| {
+                    let prepay_id = Self::gateway_field(&response_data, prepay_field)?;
+                    Ok((prepay_id, response_data))
+                });
+
+        let (prepay_id, response_data) = match result {
+            Ok(prepared) => prepared,
+            Err(AppError::PaymentGatewayError { code, message }) => {
+                let query = r#"
+                    UPDATE payment_transactions
+                    SET status = 'failed', request_data = ?, error_code = ?,
+                        error_message = ?, completed_at = ?
+                    WHERE id = ?
+                "#;
+
+                sqlx::query(query)
+                    .bind(&request_data)
+                    .bind(&code)
+                    .bind(message.chars().take(500).collect::<String>())
+                    .bind(Utc::now())
+                    .bind(transaction_id.to_string())
+                    .execute(db)
+                    .await
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+                return Err(AppError::PaymentGatewayError { code, message });
+            }
+            Err(e) => return Err(e),
+        };
 
-        // TODO: Implement actual WeChat Pay API integration
-        // For now, return mock data
-        let prepay_id = format!("wx_prepay_{}", Uuid::new_v4());
+        let (qr_code, prepay_data) = match trade_type {
+            WechatTradeType::Jsapi { .. } => (
+                None,
+                Some(WechatPayService::jsapi_prepay_data(&config, &prepay_id)?),
+            ),
+            WechatTradeType::Native => (Some(prepay_id.clone()), None),
+        };
 
-        // Update transaction with prepay_id
         let query = r#"
             UPDATE payment_transactions
-            SET prepay_id = ?, request_data = ?
+            SET prepay_id = ?, request_data = ?, response_data = ?
             WHERE id = ?
         "#;
 
-        let request_data = serde_json::json!({
-            "appid": config.get("app_id"),
-            "mchid": config.get("mch_id"),
-            "out_trade_no": order.order_no,
-            "amount": order.amount,
-        });
-
         sqlx::query(query)
             .bind(&prepay_id)
             .bind(&request_data)
+            .bind(&response_data)
             .bind(transaction_id.to_string())
             .execute(db)
             .await
@@ -327,46 +1379,58 @@ impl PaymentService {
             order_no: order.order_no.clone(),
             payment_method: PaymentMethod::Wechat,
             payment_url: None,
-            qr_code: Some(format!("wxp://f2f0{}", prepay_id)), // Mock QR code
-            prepay_data: Some(serde_json::json!({
-                "prepay_id": prepay_id,
-                "appid": config.get("app_id"),
-                "timestamp": Utc::now().timestamp(),
-                "nonce_str": Uuid::new_v4().to_string(),
-            })),
+            qr_code,
+            prepay_data,
         })
     }
 
+    /// 取支付渠道响应中的必填字段，缺失视为渠道错误
+    fn gateway_field(response: &serde_json::Value, field: &str) -> Result<String, AppError> {
+        response[field]
+            .as_str()
+            .map(str::to_string)
+            .ok_or_else(|| AppError::PaymentGatewayError {
+                code: "INVALID_RESPONSE".to_string(),
+                message: format!("支付渠道响应缺少 {}", field),
+            })
+    }
+
     async fn process_alipay_payment(
         db: &DbPool,
         order: &PaymentOrder,
         transaction_id: &Uuid,
         return_url: Option<String>,
+        wap: bool,
     ) -> Result<PaymentResponse, AppError> {
-        // Get Alipay configuration
-        let config = Self::get_payment_config(db, PaymentMethod::Alipay).await?;
+        let config =
+            AlipayConfig::from_map(&Self::get_payment_config(db, PaymentMethod::Alipay).await?)?;
+        let signer = Rsa2Signer::new(config.private_key.clone());
+        let trade_type = if wap {
+            AlipayTradeType::Wap
+        } else {
+            AlipayTradeType::Page
+        };
 
-        // TODO: Implement actual Alipay API integration
-        // For now, return mock data
-        let trade_no = format!("alipay_{}", Uuid::new_v4());
+        let params = AlipayService::trade_params(
+            &config,
+            order,
+            trade_type,
+            return_url.as_deref(),
+            Utc::now(),
+        );
+        let params = AlipayService::sign_params(params, &signer)?;
+        let payment_url = AlipayService::payment_url(&params);
 
-        // Update transaction
+        // Page/wap pay is a browser redirect, so the "response" is the cashier URL itself
         let query = r#"
             UPDATE payment_transactions
-            SET trade_no = ?, request_data = ?
+            SET request_data = ?, response_data = ?
             WHERE id = ?
         "#;
 
-        let request_data = serde_json::json!({
-            "app_id": config.get("app_id"),
-            "out_trade_no": order.order_no,
-            "total_amount": order.amount,
-            "return_url": return_url,
-        });
-
         sqlx::query(query)
-            .bind(&trade_no)
-            .bind(&request_data)
+            .bind(serde_json::json!(params))
+            .bind(serde_json::json!({ "payment_url": payment_url }))
             .bind(transaction_id.to_string())
             .execute(db)
             .await
@@ -376,10 +1440,7 @@ impl PaymentService {
             order_id: order.id,
             order_no: order.order_no.clone(),
             payment_method: PaymentMethod::Alipay,
-            payment_url: Some(format!(
-                "https://openapi.alipay.com/gateway.do?trade_no={}",
-                trade_no
-            )),
+            payment_url: Some(payment_url),
             qr_code: None,
             prepay_data: None,
         })
@@ -395,6 +1456,12 @@ impl PaymentService {
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
+        // Lock the order so concurrent balance payments for it are serialised
+        let order = Self::lock_order(&mut tx, order.id).await?;
+        if order.status != OrderStatus::Pending {
+            return Err(AppError::BadRequest("订单状态不正确".to_string()));
+        }
+
         // Check user balance
         let balance = Self::get_user_balance_tx(&mut tx, order.user_id).await?;
 
@@ -402,17 +1469,31 @@ impl PaymentService {
             return Err(AppError::BadRequest("余额不足".to_string()));
         }
 
-        // Deduct balance
-        Self::update_balance_tx(
-            &mut tx,
-            order.user_id,
-            BalanceTransactionType::Expense,
-            order.amount,
-            Some("order".to_string()),
-            Some(order.id),
-            &format!("订单支付: {}", order.order_no),
-        )
-        .await?;
+        // Appointment payments are held until the visit is completed or cancelled
+        let escrow = order.appointment_id.is_some();
+        if escrow {
+            Self::update_balance_tx(
+                &mut tx,
+                order.user_id,
+                BalanceTransactionType::Freeze,
+                order.amount,
+                Some("order".to_string()),
+                Some(order.id),
+                &format!("预约订单支付，就诊完成后结算: {}", order.order_no),
+            )
+            .await?;
+        } else {
+            Self::update_balance_tx(
+                &mut tx,
+                order.user_id,
+                BalanceTransactionType::Expense,
+                order.amount,
+                Some("order".to_string()),
+                Some(order.id),
+                &format!("订单支付: {}", order.order_no),
+            )
+            .await?;
+        }
 
         // Update transaction status
         let query = r#"
@@ -431,19 +1512,25 @@ impl PaymentService {
         // Update order status
         let query = r#"
             UPDATE payment_orders
-            SET status = 'paid', payment_method = 'balance', payment_time = ?, updated_at = ?
-            WHERE id = ?
+            SET status = 'paid', payment_method = 'balance', payment_time = ?,
+                balance_hold_status = ?, updated_at = ?
+            WHERE id = ? AND status = 'pending'
         "#;
 
         let now = Utc::now();
-        sqlx::query(query)
+        let result = sqlx::query(query)
             .bind(now)
+            .bind(escrow.then_some("held"))
             .bind(now)
             .bind(order.id.to_string())
             .execute(&mut *tx)
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
+        if result.rows_affected() != 1 {
+            return Err(AppError::BadRequest("订单状态不正确".to_string()));
+        }
+
         // Update appointment status if applicable
         if let Some(appointment_id) = order.appointment_id {
             let query = r#"
@@ -460,6 +1547,11 @@ impl PaymentService {
                 .map_err(|e| AppError::DatabaseError(e.to_string()))?;
         }
 
+        // Held payments are credited to the doctor on settlement
+        if !escrow {
+            DoctorEarningsService::record_income_tx(db, &mut tx, &order).await?;
+        }
+
         tx.commit()
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
@@ -474,21 +1566,314 @@ impl PaymentService {
         })
     }
 
+    /// 就诊完成时结算预约的托管款项：解冻后扣款，并记入医生收益。
+    /// 须在更新问诊状态的同一事务内调用；订单行加锁且只处理 held 状态，重复调用不会重复扣款。
+    pub async fn settle_balance_hold_tx(
+        db: &DbPool,
+        tx: &mut Transaction<'_, MySql>,
+        appointment_id: Uuid,
+    ) -> Result<(), AppError> {
+        for order in Self::lock_held_orders_tx(tx, appointment_id).await? {
+            Self::update_balance_tx(
+                tx,
+                order.user_id,
+                BalanceTransactionType::Unfreeze,
+                order.amount,
+                Some("order".to_string()),
+                Some(order.id),
+                &format!("就诊完成，解冻订单款项: {}", order.order_no),
+            )
+            .await?;
+            Self::update_balance_tx(
+                tx,
+                order.user_id,
+                BalanceTransactionType::Expense,
+                order.amount,
+                Some("order".to_string()),
+                Some(order.id),
+                &format!("订单结算: {}", order.order_no),
+            )
+            .await?;
+
+            Self::finish_balance_hold_tx(tx, order.id, "settled", None).await?;
+            DoctorEarningsService::record_income_tx(db, tx, &order).await?;
+        }
+
+        Ok(())
+    }
+
+    /// 就诊前取消预约时将托管款项解冻退回可用余额，订单标记为已退款
+    pub async fn release_balance_hold_tx(
+        tx: &mut Transaction<'_, MySql>,
+        appointment_id: Uuid,
+    ) -> Result<(), AppError> {
+        for order in Self::lock_held_orders_tx(tx, appointment_id).await? {
+            Self::update_balance_tx(
+                tx,
+                order.user_id,
+                BalanceTransactionType::Unfreeze,
+                order.amount,
+                Some("order".to_string()),
+                Some(order.id),
+                &format!("预约取消，解冻退回: {}", order.order_no),
+            )
+            .await?;
+
+            Self::finish_balance_hold_tx(tx, order.id, "released", Some(OrderStatus::Refunded))
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    async fn lock_held_orders_tx(
+        tx: &mut Transaction<'_, MySql>,
+        appointment_id: Uuid,
+    ) -> Result<Vec<PaymentOrder>, AppError> {
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM payment_orders
+            WHERE appointment_id = ? AND balance_hold_status = 'held'
+            FOR UPDATE
+            "#,
+        )
+        .bind(appointment_id.to_string())
+        .fetch_all(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        rows.into_iter().map(Self::parse_order_row).collect()
+    }
+
+    async fn finish_balance_hold_tx(
+        tx: &mut Transaction<'_, MySql>,
+        order_id: Uuid,
+        hold_status: &str,
+        order_status: Option<OrderStatus>,
+    ) -> Result<(), AppError> {
+        let result = sqlx::query(
+            r#"
+            UPDATE payment_orders
+            SET balance_hold_status = ?, status = COALESCE(?, status), updated_at = ?
+            WHERE id = ? AND balance_hold_status = 'held'
+            "#,
+        )
+        .bind(hold_status)
+        .bind(order_status.as_ref().map(Self::order_status_str))
+        .bind(Utc::now())
+        .bind(order_id.to_string())
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::BadRequest("托管款项已处理".to_string()));
+        }
+        Ok(())
+    }
+
     // Payment callback handling
+    /// 查询订单支付状态。待支付且存在微信/支付宝流水时主动向渠道查询，渠道确认已支付
+    /// 则按回调流程入账，用于补偿丢失的回调；渠道查询失败时返回本地状态
+    pub async fn query_order_status(
+        db: &DbPool,
+        gateway: &dyn PaymentGateway,
+        order_no: &str,
+    ) -> Result<OrderPaymentStatus, AppError> {
+        let order = Self::get_order_by_no(db, order_no).await?;
+        if order.status != OrderStatus::Pending {
+            return Ok(Self::order_payment_status(order, false));
+        }
+
+        let Some(payment_method) = Self::pending_gateway_method(db, order.id).await? else {
+            return Ok(Self::order_payment_status(order, false));
+        };
+
+        match Self::query_gateway_trade(db, gateway, &order, &payment_method).await {
+            Ok(Some(callback_data)) if callback_data.amount != order.amount => {
+                tracing::warn!(
+                    "Gateway amount {} does not match order {} amount {}",
+                    callback_data.amount,
+                    order.order_no,
+                    order.amount
+                );
+            }
+            Ok(Some(callback_data)) => {
+                Self::handle_payment_callback(db, payment_method, callback_data).await?;
+            }
+            Ok(None) => {}
+            Err(e) => {
+                tracing::warn!(
+                    "Failed to query gateway for order {}: {}",
+                    order.order_no,
+                    e
+                );
+                return Ok(Self::order_payment_status(order, false));
+            }
+        }
+
+        let order = Self::get_order(db, order.id).await?;
+        Ok(Self::order_payment_status(order, true))
+    }
+
+    /// 订单最近一笔待处理的渠道支付流水的支付方式
+    async fn pending_gateway_method(
+        db: &DbPool,
+        order_id: Uuid,
+    ) -> Result<Option<PaymentMethod>, AppError> {
+        let method: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT payment_method FROM payment_transactions
+            WHERE order_id = ? AND transaction_type = 'payment' AND status = 'pending'
+              AND payment_method IN ('wechat', 'alipay')
+            ORDER BY initiated_at DESC LIMIT 1
+            "#,
+        )
+        .bind(order_id.to_string())
+        .fetch_optional(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(method.map(|method| match method.as_str() {
+            "wechat" => PaymentMethod::Wechat,
+            _ => PaymentMethod::Alipay,
+        }))
+    }
+
+    /// 调用渠道的订单查询接口，交易成功时转为回调数据，其余状态返回 None
+    async fn query_gateway_trade(
+        db: &DbPool,
+        gateway: &dyn PaymentGateway,
+        order: &PaymentOrder,
+        payment_method: &PaymentMethod,
+    ) -> Result<Option<PaymentCallbackData>, AppError> {
+        let config = Self::get_payment_config(db, payment_method.clone()).await?;
+
+        let response = match payment_method {
+            PaymentMethod::Wechat => {
+                let config = WechatPayConfig::from_map(&config)?;
+                let response =
+                    WechatPayService::query_transaction(gateway, &config, &order.order_no).await?;
+                if response["trade_state"].as_str() != Some("SUCCESS") {
+                    return Ok(None);
+                }
+                response
+            }
+            PaymentMethod::Alipay => {
+                let config = AlipayConfig::from_map(&config)?;
+                let params = AlipayService::sign_params(
+                    AlipayService::query_params(&config, order, Utc::now()),
+                    &Rsa2Signer::new(config.private_key.clone()),
+                )?;
+                let mut response = match AlipayService::execute(gateway, &params).await {
+                    Ok(response) => response,
+                    // The trade only exists once the payer opens the cashier
+                    Err(AppError::PaymentGatewayError { code, .. })
+                        if code == "ACQ.TRADE_NOT_EXIST" =>
+                    {
+                        return Ok(None)
+                    }
+                    Err(e) => return Err(e),
+                };
+                // TRADE_FINISHED is a paid trade past its refund window
+                if response["trade_status"].as_str() == Some("TRADE_FINISHED") {
+                    response["trade_status"] = serde_json::json!("TRADE_SUCCESS");
+                }
+                if response["trade_status"].as_str() != Some("TRADE_SUCCESS") {
+                    return Ok(None);
+                }
+                response
+            }
+            _ => return Ok(None),
+        };
+
+        Self::parse_callback(payment_method, &response).map(Some)
+    }
+
+    fn order_payment_status(order: PaymentOrder, gateway_checked: bool) -> OrderPaymentStatus {
+        OrderPaymentStatus {
+            order_id: order.id,
+            order_no: order.order_no,
+            status: order.status,
+            payment_method: order.payment_method,
+            payment_time: order.payment_time,
+            gateway_checked,
+        }
+    }
+
+    /// 处理支付渠道的回调通知。渠道会重试投递，已处理过的通知直接返回成功，
+    /// 每次投递都会记入 payment_callback_logs
     pub async fn handle_payment_callback(
         db: &DbPool,
         payment_method: PaymentMethod,
         callback_data: PaymentCallbackData,
     ) -> Result<(), AppError> {
+        let result = Self::apply_payment_callback(db, &payment_method, &callback_data).await;
+
+        let (outcome, transaction_id, error_message) = match &result {
+            Ok(CallbackOutcome::Processed(id)) => ("processed", Some(*id), None),
+            Ok(CallbackOutcome::Duplicate(id)) => ("duplicate", *id, None),
+            Err(e) => ("error", None, Some(e.to_string())),
+        };
+        Self::log_callback(
+            db,
+            &payment_method,
+            &callback_data.raw_data,
+            Some(&callback_data),
+            transaction_id,
+            outcome,
+            error_message.as_deref(),
+        )
+        .await;
+
+        result.map(|_| ())
+    }
+
+    async fn apply_payment_callback(
+        db: &DbPool,
+        payment_method: &PaymentMethod,
+        callback_data: &PaymentCallbackData,
+    ) -> Result<CallbackOutcome, AppError> {
         let mut tx = db
             .begin()
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        // Get order and transaction
+        // Get order and transaction; the order row stays locked until commit
         let order = Self::get_order_by_no(db, &callback_data.order_no).await?;
+        let order = Self::lock_order(&mut tx, order.id).await?;
+
+        // Duplicate callback for an order that was already settled
+        if matches!(
+            order.status,
+            OrderStatus::Paid | OrderStatus::PartialRefunded | OrderStatus::Refunded
+        ) {
+            return Ok(CallbackOutcome::Duplicate(None));
+        }
+
+        // A transaction failed by the expiry sweep can still receive a late successful
+        // callback; it is recorded so an admin can reconcile the captured payment.
+        // Any other final status means this notification was already handled.
+        let transaction = Self::lock_payment_transaction(&mut tx, order.id, payment_method).await?;
+        let processable = match transaction.status {
+            TransactionStatus::Pending => true,
+            TransactionStatus::Failed => transaction.error_code.as_deref() == Some("ORDER_EXPIRED"),
+            TransactionStatus::Success => false,
+        };
+        if !processable {
+            return Ok(CallbackOutcome::Duplicate(Some(transaction.id)));
+        }
 
-        let transaction = Self::get_transaction_by_order(db, order.id, &payment_method).await?;
+        // A successful notification for any other amount than the order's is not trusted
+        if callback_data.status == "success" && callback_data.amount != order.amount {
+            tracing::warn!(
+                "Payment callback amount {} does not match order {} amount {}",
+                callback_data.amount,
+                order.order_no,
+                order.amount
+            );
+            return Err(AppError::BadRequest("回调金额与订单金额不一致".to_string()));
+        }
 
         // Update transaction
         let query = r#"
@@ -514,16 +1899,23 @@ impl PaymentService {
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        // Update order if payment successful
-        if status == TransactionStatus::Success {
+        // Update order if payment successful. An order cancelled or expired in the
+        // meantime keeps its status; the captured payment is left for an admin to resolve.
+        if status == TransactionStatus::Success && order.status != OrderStatus::Pending {
+            tracing::warn!(
+                "Payment captured for order {} in status {}",
+                order.order_no,
+                Self::order_status_str(&order.status)
+            );
+        } else if status == TransactionStatus::Success {
             let query = r#"
                 UPDATE payment_orders
                 SET status = 'paid', payment_method = ?, payment_time = ?, updated_at = ?
-                WHERE id = ?
+                WHERE id = ? AND status = 'pending'
             "#;
 
-            sqlx::query(query)
-                .bind(match &payment_method {
+            let updated = sqlx::query(query)
+                .bind(match payment_method {
                     PaymentMethod::Wechat => "wechat",
                     PaymentMethod::Alipay => "alipay",
                     PaymentMethod::BankCard => "bank_card",
@@ -536,6 +1928,16 @@ impl PaymentService {
                 .await
                 .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
+            // Side effects run only for the delivery that actually moved the order
+            if updated.rows_affected() == 0 {
+                return Ok(CallbackOutcome::Duplicate(Some(transaction.id)));
+            }
+
+            if matches!(order.order_type, OrderType::Recharge) {
+                Self::credit_recharge_tx(&mut tx, &order).await?;
+            }
+            DoctorEarningsService::record_income_tx(db, &mut tx, &order).await?;
+
             // Update appointment status if applicable
             if let Some(appointment_id) = order.appointment_id {
                 let query = r#"
@@ -557,27 +1959,512 @@ impl PaymentService {
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
+        if status == TransactionStatus::Success {
+            PaymentMetrics::global().record_success(payment_method);
+        } else {
+            PaymentMetrics::global().record_failure(payment_method);
+        }
+
+        Ok(CallbackOutcome::Processed(transaction.id))
+    }
+
+    /// 记录一次回调投递，写入失败只记日志，不影响回调处理结果
+    async fn log_callback(
+        db: &DbPool,
+        payment_method: &PaymentMethod,
+        raw_data: &serde_json::Value,
+        callback_data: Option<&PaymentCallbackData>,
+        transaction_id: Option<Uuid>,
+        outcome: &str,
+        error_message: Option<&str>,
+    ) {
+        let order_no = callback_data
+            .map(|callback| callback.order_no.as_str())
+            .or_else(|| raw_data["out_trade_no"].as_str());
+
+        let query = r#"
+            INSERT INTO payment_callback_logs (
+                id, payment_method, order_no, external_transaction_id, transaction_id,
+                callback_status, outcome, error_message, raw_data, received_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        let result = sqlx::query(query)
+            .bind(Uuid::new_v4().to_string())
+            .bind(payment_method)
+            .bind(order_no)
+            .bind(callback_data.map(|callback| callback.external_transaction_id.as_str()))
+            .bind(transaction_id.map(|id| id.to_string()))
+            .bind(callback_data.map(|callback| callback.status.as_str()))
+            .bind(outcome)
+            .bind(error_message.map(|message| message.chars().take(500).collect::<String>()))
+            .bind(raw_data)
+            .bind(Utc::now())
+            .execute(db)
+            .await;
+
+        if let Err(e) = result {
+            tracing::warn!("Failed to log payment callback: {}", e);
+        }
+    }
+
+    /// 校验支付回调的渠道签名，返回解析后的回调报文。`headers` 的键为小写请求头名。
+    /// 微信支付的 JSON 报文用 APIv3 密钥解密，返回 resource 中的业务数据，并在 `notification`
+    /// 中附上签名原文（报文主体与 Wechatpay-* 请求头）供事后重新验签；支付宝的表单报文
+    /// 转为参数表（JSON 对象）返回。
+    ///
+    /// 签名无效时不改动订单，只把报文连同 `signature_verified: false` 记到对应交易的
+    /// callback_data 供排查，并返回 BadRequest
+    pub async fn verify_callback(
+        db: &DbPool,
+        payment_method: &PaymentMethod,
+        headers: &HashMap<String, String>,
+        body: &str,
+    ) -> Result<serde_json::Value, AppError> {
+        let config = Self::get_payment_config(db, payment_method.clone()).await?;
+        let config_value = |key: &str| {
+            config
+                .get(key)
+                .map(String::as_str)
+                .filter(|value| !value.trim().is_empty())
+        };
+
+        let (verified, data) = match payment_method {
+            PaymentMethod::Wechat => {
+                let public_key = config_value("platform_public_key").ok_or_else(|| {
+                    AppError::InternalServerError("微信支付未配置 platform_public_key".to_string())
+                })?;
+                let api_v3_key = config_value("api_v3_key").ok_or_else(|| {
+                    AppError::InternalServerError("微信支付未配置 api_v3_key".to_string())
+                })?;
+                let data: serde_json::Value = serde_json::from_str(body)
+                    .map_err(|_| AppError::BadRequest("回调报文格式错误".to_string()))?;
+                let verified = WechatPayService::verify_notification(
+                    public_key,
+                    config_value("platform_serial_no"),
+                    headers,
+                    body,
+                    Utc::now().timestamp(),
+                )?;
+                // The business fields are only trusted from the encrypted `resource`
+                let resource = WechatPayService::decrypt_resource(api_v3_key, &data["resource"]);
+                match resource {
+                    Ok(mut resource) => {
+                        if let Some(fields) = resource.as_object_mut() {
+                            let signed_headers: HashMap<&String, &String> = headers
+                                .iter()
+                                .filter(|(name, _)| name.starts_with("wechatpay-"))
+                                .collect();
+                            fields.insert(
+                                "notification".to_string(),
+                                serde_json::json!({ "headers": signed_headers, "body": body }),
+                            );
+                        }
+                        (verified, resource)
+                    }
+                    Err(e) if verified => return Err(e),
+                    Err(_) => (verified, data),
+                }
+            }
+            PaymentMethod::Alipay => {
+                let public_key = config_value("public_key").ok_or_else(|| {
+                    AppError::InternalServerError("支付宝未配置 public_key".to_string())
+                })?;
+                // Alipay posts the notification as a form, signed over the decoded values
+                let params = AlipayService::notification_params(body)?;
+                let verified = AlipayService::verify_notification(public_key, &params)?;
+                (verified, serde_json::json!(params))
+            }
+            _ => return Err(AppError::BadRequest("不支持的支付方式".to_string())),
+        };
+
+        if !verified {
+            tracing::warn!(
+                "Rejected {:?} payment callback with an invalid signature",
+                payment_method
+            );
+            Self::record_rejected_callback(db, payment_method, &data).await?;
+            Self::log_callback(
+                db,
+                payment_method,
+                &data,
+                None,
+                None,
+                "rejected",
+                Some("回调签名校验失败"),
+            )
+            .await;
+            return Err(AppError::BadRequest("回调签名校验失败".to_string()));
+        }
+
+        Ok(data)
+    }
+
+    /// 记录验签失败的回调，已成功的交易不会被覆盖
+    async fn record_rejected_callback(
+        db: &DbPool,
+        payment_method: &PaymentMethod,
+        data: &serde_json::Value,
+    ) -> Result<(), AppError> {
+        let Some(order_no) = data["out_trade_no"].as_str() else {
+            return Ok(());
+        };
+
+        let query = r#"
+            UPDATE payment_transactions t
+            JOIN payment_orders o ON o.id = t.order_id
+            SET t.callback_data = ?
+            WHERE o.order_no = ? AND t.payment_method = ?
+              AND t.transaction_type = 'payment' AND t.status <> 'success'
+        "#;
+
+        sqlx::query(query)
+            .bind(serde_json::json!({
+                "signature_verified": false,
+                "received_at": Utc::now(),
+                "payload": data,
+            }))
+            .bind(order_no)
+            .bind(payment_method)
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
         Ok(())
     }
 
+    /// 将支付渠道的回调报文解析为统一的回调数据
+    pub fn parse_callback(
+        payment_method: &PaymentMethod,
+        data: &serde_json::Value,
+    ) -> Result<PaymentCallbackData, AppError> {
+        let callback_data = match payment_method {
+            PaymentMethod::Wechat => {
+                // Decrypted notification resource or order query response; amounts are in fen
+                let total = data["amount"]["total"]
+                    .as_i64()
+                    .ok_or_else(|| AppError::BadRequest("缺少金额".to_string()))?;
+                PaymentCallbackData {
+                    order_no: data["out_trade_no"]
+                        .as_str()
+                        .ok_or_else(|| AppError::BadRequest("缺少订单号".to_string()))?
+                        .to_string(),
+                    external_transaction_id: data["transaction_id"]
+                        .as_str()
+                        .ok_or_else(|| AppError::BadRequest("缺少交易ID".to_string()))?
+                        .to_string(),
+                    amount: Decimal::new(total, 2),
+                    status: if data["trade_state"].as_str() == Some("SUCCESS") {
+                        "success".to_string()
+                    } else {
+                        "failed".to_string()
+                    },
+                    payment_time: data["success_time"]
+                        .as_str()
+                        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+                        .map(|time| time.with_timezone(&Utc))
+                        .unwrap_or_else(Utc::now),
+                    raw_data: data.clone(),
+                }
+            }
+            PaymentMethod::Alipay => {
+                // Parse Alipay callback
+                PaymentCallbackData {
+                    order_no: data["out_trade_no"]
+                        .as_str()
+                        .ok_or_else(|| AppError::BadRequest("缺少订单号".to_string()))?
+                        .to_string(),
+                    external_transaction_id: data["trade_no"]
+                        .as_str()
+                        .ok_or_else(|| AppError::BadRequest("缺少交易ID".to_string()))?
+                        .to_string(),
+                    amount: Decimal::from_str_exact(
+                        data["total_amount"]
+                            .as_str()
+                            .ok_or_else(|| AppError::BadRequest("缺少金额".to_string()))?,
+                    )
+                    .map_err(|_| AppError::BadRequest("金额格式错误".to_string()))?,
+                    status: if data["trade_status"].as_str() == Some("TRADE_SUCCESS") {
+                        "success".to_string()
+                    } else {
+                        "failed".to_string()
+                    },
+                    // Notifications carry gmt_payment, trade query responses send_pay_date
+                    payment_time: data["gmt_payment"]
+                        .as_str()
+                        .or_else(|| data["send_pay_date"].as_str())
+                        .and_then(AlipayService::parse_time)
+                        .unwrap_or_else(Utc::now),
+                    raw_data: data.clone(),
+                }
+            }
+            _ => return Err(AppError::BadRequest("不支持的支付方式".to_string())),
+        };
+
+        Ok(callback_data)
+    }
+
+    /// 重新校验订单最近一次保存的支付回调，用于对账。签名按当前配置的渠道公钥重新验证，
+    /// 微信支付还会解密签名原文，核对保存的业务数据未被改动
+    ///
+    /// 默认只返回校验结果，不修改任何数据；`apply` 为 true 且回调有效、表示支付成功、
+    /// 订单尚未支付时，按管理员调整的流程将订单标记为已支付。
+    pub async fn verify_stored_callback(
+        db: &DbPool,
+        order_id: Uuid,
+        apply: bool,
+        admin_id: Uuid,
+    ) -> Result<CallbackVerification, AppError> {
+        let order = Self::get_order(db, order_id).await?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT * FROM payment_transactions
+            WHERE order_id = ? AND transaction_type = 'payment' AND callback_data IS NOT NULL
+            ORDER BY initiated_at DESC LIMIT 1
+            "#,
+        )
+        .bind(order_id.to_string())
+        .fetch_optional(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?
+        .ok_or_else(|| AppError::NotFound("该订单没有已保存的支付回调".to_string()))?;
+        let transaction = Self::parse_transaction_row(row)?;
+
+        let raw_data = transaction.callback_data.clone().unwrap_or_default();
+        let mut issues = Vec::new();
+        let mut callback_status = None;
+
+        if raw_data["signature_verified"] == false {
+            issues.push("回调签名校验失败".to_string());
+        } else {
+            issues.extend(
+                Self::reverify_stored_callback(db, &transaction.payment_method, &raw_data).await?,
+            );
+        }
+
+        match Self::parse_callback(&transaction.payment_method, &raw_data) {
+            Ok(callback) => {
+                if callback.order_no != order.order_no {
+                    issues.push(format!(
+                        "回调订单号 {} 与订单 {} 不一致",
+                        callback.order_no, order.order_no
+                    ));
+                }
+                if callback.amount != transaction.amount {
+                    issues.push(format!(
+                        "回调金额 {} 与支付金额 {} 不一致",
+                        callback.amount, transaction.amount
+                    ));
+                }
+                if let Some(external_id) = &transaction.external_transaction_id {
+                    if &callback.external_transaction_id != external_id {
+                        issues.push(format!(
+                            "回调交易号 {} 与记录的交易号 {} 不一致",
+                            callback.external_transaction_id, external_id
+                        ));
+                    }
+                }
+                callback_status = Some(callback.status);
+            }
+            Err(AppError::BadRequest(msg)) => issues.push(format!("回调无法解析：{}", msg)),
+            Err(e) => return Err(e),
+        }
+
+        let valid = issues.is_empty();
+        let would_mark_paid = valid
+            && callback_status.as_deref() == Some("success")
+            && matches!(
+                order.status,
+                OrderStatus::Pending | OrderStatus::Cancelled | OrderStatus::Expired
+            );
+
+        let mut order_status = order.status;
+        let mut applied = false;
+        if apply && would_mark_paid {
+            let reason = format!(
+                "根据已保存的支付回调补记支付，交易号 {}",
+                transaction
+                    .external_transaction_id
+                    .as_deref()
+                    .unwrap_or("-")
+            );
+            let updated =
+                Self::adjust_order_status(db, order_id, OrderStatus::Paid, &reason, admin_id)
+                    .await?;
+            order_status = updated.status;
+            applied = true;
+        }
+
+        Ok(CallbackVerification {
+            order_id,
+            order_no: order.order_no,
+            transaction_id: transaction.id,
+            payment_method: transaction.payment_method,
+            valid,
+            issues,
+            callback_status,
+            order_status,
+            would_mark_paid,
+            applied,
+        })
+    }
+
+    /// 用渠道公钥重新验证已保存回调的签名，返回发现的问题
+    async fn reverify_stored_callback(
+        db: &DbPool,
+        payment_method: &PaymentMethod,
+        data: &serde_json::Value,
+    ) -> Result<Vec<String>, AppError> {
+        let config = Self::get_payment_config(db, payment_method.clone()).await?;
+        let config_value = |key: &str| {
+            config
+                .get(key)
+                .map(String::as_str)
+                .filter(|value| !value.trim().is_empty())
+        };
+        let strings = |value: &serde_json::Value| -> Option<BTreeMap<String, String>> {
+            value.as_object().map(|fields| {
+                fields
+                    .iter()
+                    .filter_map(|(key, value)| Some((key.clone(), value.as_str()?.to_string())))
+                    .collect()
+            })
+        };
+        let invalid_signature = || Ok(vec!["回调签名校验失败".to_string()]);
+
+        match payment_method {
+            PaymentMethod::Wechat => {
+                let public_key = config_value("platform_public_key").ok_or_else(|| {
+                    AppError::InternalServerError("微信支付未配置 platform_public_key".to_string())
+                })?;
+                let api_v3_key = config_value("api_v3_key").ok_or_else(|| {
+                    AppError::InternalServerError("微信支付未配置 api_v3_key".to_string())
+                })?;
+                let notification = &data["notification"];
+                let (Some(body), Some(headers)) = (
+                    notification["body"].as_str(),
+                    strings(&notification["headers"]),
+                ) else {
+                    return Ok(vec!["回调未保存签名原文，无法重新验签".to_string()]);
+                };
+                let headers: HashMap<String, String> = headers.into_iter().collect();
+
+                // The replay window was enforced on delivery, so check against the signed timestamp
+                let signed_at = headers
+                    .get("wechatpay-timestamp")
+                    .and_then(|timestamp| timestamp.parse().ok())
+                    .unwrap_or_default();
+                if !WechatPayService::verify_notification(
+                    public_key,
+                    config_value("platform_serial_no"),
+                    &headers,
+                    body,
+                    signed_at,
+                )? {
+                    return invalid_signature();
+                }
+
+                let envelope: serde_json::Value = serde_json::from_str(body).unwrap_or_default();
+                let Ok(resource) =
+                    WechatPayService::decrypt_resource(api_v3_key, &envelope["resource"])
+                else {
+                    return Ok(vec!["回调签名原文解密失败".to_string()]);
+                };
+                let mut stored = data.clone();
+                if let Some(fields) = stored.as_object_mut() {
+                    fields.remove("notification");
+                }
+                if stored != resource {
+                    return Ok(vec!["保存的回调数据与签名报文不一致".to_string()]);
+                }
+                Ok(Vec::new())
+            }
+            PaymentMethod::Alipay => {
+                let public_key = config_value("public_key").ok_or_else(|| {
+                    AppError::InternalServerError("支付宝未配置 public_key".to_string())
+                })?;
+                match strings(data) {
+                    Some(params) if AlipayService::verify_notification(public_key, &params)? => {
+                        Ok(Vec::new())
+                    }
+                    _ => invalid_signature(),
+                }
+            }
+            _ => Ok(vec!["不支持的支付方式".to_string()]),
+        }
+    }
+
     // Refund management
+    /// 创建退款申请。超过配置的退款期限（refund/window_days）的申请会被拒绝，
+    /// 管理员（`is_admin`）可绕过该限制处理特殊情况
     pub async fn create_refund(
         db: &DbPool,
         dto: CreateRefundDto,
         user_id: Uuid,
+        is_admin: bool,
     ) -> Result<RefundRecord, AppError> {
-        let order = Self::get_order(db, dto.order_id).await?;
+        let window_days = SystemConfigService::get_i64(db, "refund", "window_days", 30).await?;
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        // Validate order status
-        if order.status != OrderStatus::Paid {
+        let order = Self::lock_order(&mut tx, dto.order_id).await?;
+
+        // Validate order status; a partially refunded order can be refunded again
+        if !matches!(
+            order.status,
+            OrderStatus::Paid | OrderStatus::PartialRefunded
+        ) {
             return Err(AppError::BadRequest("只能退款已支付的订单".to_string()));
         }
 
+        // Held appointment payments are returned by cancelling the appointment
+        if order.balance_hold_status == Some(BalanceHoldStatus::Held) {
+            return Err(AppError::BadRequest(
+                "预约款项尚未结算，取消预约即可退回余额".to_string(),
+            ));
+        }
+
+        // The amount already sits in the user's balance
+        if matches!(order.order_type, OrderType::Recharge) {
+            return Err(AppError::BadRequest("充值订单不支持退款".to_string()));
+        }
+
+        if !is_admin && window_days > 0 {
+            if let Some(payment_time) = order.payment_time {
+                if Utc::now() > payment_time + Duration::days(window_days) {
+                    return Err(AppError::BadRequest(format!(
+                        "已超过退款期限（支付后{}天内可申请退款）",
+                        window_days
+                    )));
+                }
+            }
+        }
+
         // Validate refund amount
         if dto.refund_amount > order.amount {
             return Err(AppError::BadRequest("退款金额不能大于订单金额".to_string()));
         }
 
+        // Refunds already requested or paid out count against the order amount
+        let requested: Option<Decimal> = sqlx::query_scalar(
+            r#"
+            SELECT SUM(refund_amount) FROM refund_records
+            WHERE order_id = ? AND status IN ('pending', 'processing', 'success')
+            "#,
+        )
+        .bind(order.id.to_string())
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if requested.unwrap_or(Decimal::ZERO) + dto.refund_amount > order.amount {
+            return Err(AppError::BadRequest("退款金额超过订单可退金额".to_string()));
+        }
+
         // Get the successful transaction
         let transaction = Self::get_transaction_by_order_type(db, order.id, "payment").await?;
 
@@ -603,7 +2490,11 @@ impl PaymentService {
             .bind(&dto.refund_reason)
             .bind(now)
             .bind(now)
-            .execute(db)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
@@ -629,6 +2520,7 @@ impl PaymentService {
 
     pub async fn review_refund(
         db: &DbPool,
+        gateway: &dyn PaymentGateway,
         refund_id: Uuid,
         dto: ReviewRefundDto,
         reviewer_id: Uuid,
@@ -641,18 +2533,18 @@ impl PaymentService {
 
         if dto.approved {
             // Process refund
-            Self::process_refund(db, &refund, reviewer_id, dto.review_notes).await
+            Self::process_refund(db, gateway, &refund, reviewer_id, dto.review_notes).await
         } else {
             // Reject refund
             let query = r#"
                 UPDATE refund_records
                 SET status = 'cancelled', reviewed_by = ?, reviewed_at = ?,
                     review_notes = ?, updated_at = ?
-                WHERE id = ?
+                WHERE id = ? AND status = 'pending'
             "#;
 
             let now = Utc::now();
-            sqlx::query(query)
+            let result = sqlx::query(query)
                 .bind(reviewer_id.to_string())
                 .bind(now)
                 .bind(&dto.review_notes)
@@ -662,16 +2554,25 @@ impl PaymentService {
                 .await
                 .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
+            if result.rows_affected() == 0 {
+                return Err(AppError::BadRequest("退款申请已处理".to_string()));
+            }
+
             Ok(())
         }
     }
 
+    /// 审核通过后执行退款。余额退款在同一事务内完成；第三方退款先提交 processing 状态，
+    /// 再调用渠道退款接口，渠道确认成功后才更新订单
     async fn process_refund(
         db: &DbPool,
+        gateway: &dyn PaymentGateway,
         refund: &RefundRecord,
         reviewer_id: Uuid,
         review_notes: Option<String>,
     ) -> Result<(), AppError> {
+        let transaction = Self::get_transaction(db, refund.transaction_id).await?;
+
         let mut tx = db
             .begin()
             .await
@@ -682,11 +2583,11 @@ impl PaymentService {
             UPDATE refund_records
             SET status = 'processing', reviewed_by = ?, reviewed_at = ?,
                 review_notes = ?, updated_at = ?
-            WHERE id = ?
+            WHERE id = ? AND status = 'pending'
         "#;
 
         let now = Utc::now();
-        sqlx::query(query)
+        let result = sqlx::query(query)
             .bind(reviewer_id.to_string())
             .bind(now)
             .bind(&review_notes)
@@ -696,8 +2597,338 @@ impl PaymentService {
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        // Get original order and transaction
-        let order = Self::get_order(db, refund.order_id).await?;
+        // A concurrent review already moved this refund out of pending
+        if result.rows_affected() == 0 {
+            return Err(AppError::BadRequest("退款申请已处理".to_string()));
+        }
+
+        if matches!(transaction.payment_method, PaymentMethod::Balance) {
+            Self::settle_refund(db, &mut tx, refund, now).await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if !matches!(transaction.payment_method, PaymentMethod::Balance) {
+            Self::request_gateway_refund(db, gateway, refund, &transaction).await?;
+        }
+
+        Ok(())
+    }
+
+    /// 重新处理失败的退款，成功后退款状态变为 success，否则保持 failed
+    pub async fn retry_refund(
+        db: &DbPool,
+        gateway: &dyn PaymentGateway,
+        refund_id: Uuid,
+        admin_id: Uuid,
+    ) -> Result<RefundRecord, AppError> {
+        let refund = Self::get_refund(db, refund_id).await?;
+
+        match refund.status {
+            RefundStatus::Failed => {}
+            RefundStatus::Success => {
+                return Err(AppError::BadRequest("退款已成功，不能重复退款".to_string()))
+            }
+            _ => return Err(AppError::BadRequest("只能重试失败的退款".to_string())),
+        }
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // Claim the refund so concurrent retries cannot both settle it
+        let now = Utc::now();
+        let claimed = sqlx::query(
+            r#"
+            UPDATE refund_records
+            SET status = 'processing', updated_at = ?
+            WHERE id = ? AND status = 'failed'
+            "#,
+        )
+        .bind(now)
+        .bind(refund_id.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if claimed.rows_affected() == 0 {
+            return Err(AppError::BadRequest(
+                "退款状态已变化，请刷新后重试".to_string(),
+            ));
+        }
+
+        let transaction = Self::get_transaction(db, refund.transaction_id).await?;
+        if !matches!(transaction.payment_method, PaymentMethod::Balance) {
+            tx.commit()
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            // A gateway failure marks the refund `failed` again
+            let result = Self::request_gateway_refund(db, gateway, &refund, &transaction).await;
+            let details = match &result {
+                Ok(status) => serde_json::json!({ "result": status }),
+                Err(e) => serde_json::json!({ "result": "failed", "error": e.to_string() }),
+            };
+            AuditService::log(
+                db,
+                admin_id,
+                "refund.retry",
+                "refund_record",
+                refund_id,
+                Some(details),
+            )
+            .await?;
+            result?;
+
+            return Self::get_refund(db, refund_id).await;
+        }
+
+        match Self::settle_refund(db, &mut tx, &refund, now).await {
+            Ok(()) => {
+                AuditService::log(
+                    &mut *tx,
+                    admin_id,
+                    "refund.retry",
+                    "refund_record",
+                    refund_id,
+                    Some(serde_json::json!({ "result": "success" })),
+                )
+                .await?;
+
+                tx.commit()
+                    .await
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            }
+            Err(e) => {
+                // Rolling back leaves the refund in `failed`; only the attempt is recorded
+                drop(tx);
+                AuditService::log(
+                    db,
+                    admin_id,
+                    "refund.retry",
+                    "refund_record",
+                    refund_id,
+                    Some(serde_json::json!({ "result": "failed", "error": e.to_string() })),
+                )
+                .await?;
+                return Err(e);
+            }
+        }
+
+        Self::get_refund(db, refund_id).await
+    }
+
+    /// 调用渠道退款接口。渠道同步确认成功时立即结算；受理中则保持 processing，
+    /// 等待退款结果通知；失败时退款记为 failed，订单状态不变
+    async fn request_gateway_refund(
+        db: &DbPool,
+        gateway: &dyn PaymentGateway,
+        refund: &RefundRecord,
+        transaction: &PaymentTransaction,
+    ) -> Result<RefundStatus, AppError> {
+        let result = Self::call_refund_api(db, gateway, refund, transaction).await;
+
+        let (status, external_refund_id, response) = match result {
+            Ok(accepted) => accepted,
+            Err(e) => {
+                Self::fail_refund(db, refund.id, &e.to_string(), None).await?;
+                return Err(e);
+            }
+        };
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            UPDATE refund_records
+            SET external_refund_id = ?, refund_response = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&external_refund_id)
+        .bind(&response)
+        .bind(Utc::now())
+        .bind(refund.id.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if status == RefundStatus::Success {
+            Self::settle_refund(db, &mut tx, refund, Utc::now()).await?;
+        }
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(status)
+    }
+
+    /// 按原交易的支付方式调用退款接口，返回受理状态（success 或 processing）、
+    /// 渠道退款单号与原始响应
+    async fn call_refund_api(
+        db: &DbPool,
+        gateway: &dyn PaymentGateway,
+        refund: &RefundRecord,
+        transaction: &PaymentTransaction,
+    ) -> Result<(RefundStatus, Option<String>, serde_json::Value), AppError> {
+        let order = Self::get_order(db, refund.order_id).await?;
+        let config = Self::get_payment_config(db, transaction.payment_method.clone()).await?;
+        let external_id = transaction
+            .external_transaction_id
+            .as_deref()
+            .or(transaction.trade_no.as_deref());
+
+        match transaction.payment_method {
+            PaymentMethod::Wechat => {
+                let config = WechatPayConfig::from_map(&config)?;
+                let request =
+                    WechatPayService::refund_request(&config, &order, external_id, refund)?;
+                let response = WechatPayService::create_refund(gateway, &config, &request).await?;
+
+                let status = match response["status"].as_str() {
+                    Some("SUCCESS") => RefundStatus::Success,
+                    Some("PROCESSING") => RefundStatus::Processing,
+                    other => {
+                        return Err(AppError::PaymentGatewayError {
+                            code: other.unwrap_or("INVALID_RESPONSE").to_string(),
+                            message: response.to_string(),
+                        })
+                    }
+                };
+                let refund_id = response["refund_id"].as_str().map(str::to_string);
+                Ok((status, refund_id, response))
+            }
+            PaymentMethod::Alipay => {
+                let config = AlipayConfig::from_map(&config)?;
+                let params = AlipayService::sign_params(
+                    AlipayService::refund_params(&config, &order, external_id, refund, Utc::now()),
+                    &Rsa2Signer::new(config.private_key.clone()),
+                )?;
+                // Alipay refunds settle synchronously
+                let response = AlipayService::execute(gateway, &params).await?;
+                let trade_no = response["trade_no"].as_str().map(str::to_string);
+                Ok((RefundStatus::Success, trade_no, response))
+            }
+            _ => Err(AppError::BadRequest("不支持的支付方式".to_string())),
+        }
+    }
+
+    /// 将处理中的退款记为失败，保留失败原因与渠道响应
+    async fn fail_refund(
+        db: &DbPool,
+        refund_id: Uuid,
+        reason: &str,
+        response: Option<&serde_json::Value>,
+    ) -> Result<(), AppError> {
+        sqlx::query(
+            r#"
+            UPDATE refund_records
+            SET status = 'failed', failure_reason = ?,
+                refund_response = COALESCE(?, refund_response), updated_at = ?
+            WHERE id = ? AND status = 'processing'
+            "#,
+        )
+        .bind(reason.chars().take(500).collect::<String>())
+        .bind(response)
+        .bind(Utc::now())
+        .bind(refund_id.to_string())
+        .execute(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 处理微信退款结果通知。只处理仍在 processing 的退款，重复通知直接忽略
+    pub async fn handle_refund_callback(
+        db: &DbPool,
+        payment_method: &PaymentMethod,
+        data: &serde_json::Value,
+    ) -> Result<(), AppError> {
+        if !matches!(payment_method, PaymentMethod::Wechat) {
+            return Err(AppError::BadRequest("不支持的支付方式".to_string()));
+        }
+
+        let refund_no = data["out_refund_no"]
+            .as_str()
+            .ok_or_else(|| AppError::BadRequest("回调报文缺少退款单号".to_string()))?;
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let row = sqlx::query("SELECT * FROM refund_records WHERE refund_no = ? FOR UPDATE")
+            .bind(refund_no)
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => AppError::NotFound("退款记录不存在".to_string()),
+                _ => AppError::DatabaseError(e.to_string()),
+            })?;
+        let refund = Self::parse_refund_row(row)?;
+
+        if refund.status != RefundStatus::Processing {
+            return Ok(());
+        }
+
+        match data["refund_status"].as_str() {
+            Some("SUCCESS") => {
+                let now = Utc::now();
+                sqlx::query(
+                    r#"
+                    UPDATE refund_records
+                    SET external_refund_id = COALESCE(?, external_refund_id),
+                        refund_response = ?, updated_at = ?
+                    WHERE id = ?
+                    "#,
+                )
+                .bind(data["refund_id"].as_str())
+                .bind(data)
+                .bind(now)
+                .bind(refund.id.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+                Self::settle_refund(db, &mut tx, &refund, now).await?;
+
+                tx.commit()
+                    .await
+                    .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            }
+            Some(status @ ("ABNORMAL" | "CLOSED")) => {
+                drop(tx);
+                Self::fail_refund(
+                    db,
+                    refund.id,
+                    &format!("渠道退款失败：{}", status),
+                    Some(data),
+                )
+                .await?;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// 执行退款：退回余额或调用第三方退款，并更新订单状态、记录退款交易
+    async fn settle_refund(
+        db: &DbPool,
+        tx: &mut Transaction<'_, MySql>,
+        refund: &RefundRecord,
+        now: DateTime<Utc>,
+    ) -> Result<(), AppError> {
+        // Get original order and transaction
+        let order = Self::lock_order(tx, refund.order_id).await?;
         let transaction = Self::get_transaction(db, refund.transaction_id).await?;
 
         // Process refund based on payment method
@@ -705,7 +2936,7 @@ impl PaymentService {
             PaymentMethod::Balance => {
                 // Refund to balance
                 Self::update_balance_tx(
-                    &mut tx,
+                    tx,
                     refund.user_id,
                     BalanceTransactionType::Income,
                     refund.refund_amount,
@@ -726,13 +2957,12 @@ impl PaymentService {
                     .bind(now)
                     .bind(now)
                     .bind(refund.id.to_string())
-                    .execute(&mut *tx)
+                    .execute(&mut **tx)
                     .await
                     .map_err(|e| AppError::DatabaseError(e.to_string()))?;
             }
             _ => {
-                // TODO: Implement third-party refund API calls
-                // For now, just mark as success
+                // The gateway has already confirmed the refund
                 let query = r#"
                     UPDATE refund_records
                     SET status = 'success', completed_at = ?, updated_at = ?
@@ -743,14 +2973,25 @@ impl PaymentService {
                     .bind(now)
                     .bind(now)
                     .bind(refund.id.to_string())
-                    .execute(&mut *tx)
+                    .execute(&mut **tx)
                     .await
                     .map_err(|e| AppError::DatabaseError(e.to_string()))?;
             }
         }
 
-        // Update order status
-        let new_status = if refund.refund_amount == order.amount {
+        // Update order status from the cumulative amount refunded, this refund included
+        let refunded: Option<Decimal> = sqlx::query_scalar(
+            r#"
+            SELECT SUM(refund_amount) FROM refund_records
+            WHERE order_id = ? AND status = 'success'
+            "#,
+        )
+        .bind(order.id.to_string())
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let new_status = if refunded.unwrap_or(Decimal::ZERO) >= order.amount {
             OrderStatus::Refunded
         } else {
             OrderStatus::PartialRefunded
@@ -763,20 +3004,16 @@ impl PaymentService {
         "#;
 
         sqlx::query(query)
-            .bind(match new_status {
-                OrderStatus::Pending => "pending",
-                OrderStatus::Paid => "paid",
-                OrderStatus::Cancelled => "cancelled",
-                OrderStatus::Refunded => "refunded",
-                OrderStatus::PartialRefunded => "partial_refunded",
-                OrderStatus::Expired => "expired",
-            })
+            .bind(Self::order_status_str(&new_status))
             .bind(now)
             .bind(order.id.to_string())
-            .execute(&mut *tx)
+            .execute(&mut **tx)
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
+        DoctorEarningsService::record_refund_tx(tx, order.id, refund.id, refund.refund_amount)
+            .await?;
+
         // Create refund transaction record
         let refund_transaction_id = Uuid::new_v4();
         let refund_transaction_no = Self::generate_transaction_no();
@@ -801,11 +3038,7 @@ impl PaymentService {
             .bind(refund.refund_amount)
             .bind(now)
             .bind(now)
-            .execute(&mut *tx)
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
-
-        tx.commit()
+            .execute(&mut **tx)
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
@@ -813,6 +3046,85 @@ impl PaymentService {
     }
 
     // Balance management
+    /// 创建充值订单并发起微信/支付宝支付，支付回调成功后金额计入余额
+    pub async fn recharge_balance(
+        db: &DbPool,
+        gateway: &dyn PaymentGateway,
+        user_id: Uuid,
+        dto: RechargeBalanceDto,
+    ) -> Result<PaymentResponse, AppError> {
+        if !matches!(
+            dto.payment_method,
+            PaymentMethod::Wechat | PaymentMethod::Alipay
+        ) {
+            return Err(AppError::BadRequest(
+                "充值仅支持微信或支付宝支付".to_string(),
+            ));
+        }
+
+        let order = Self::create_order(
+            db,
+            CreateOrderDto {
+                user_id,
+                appointment_id: None,
+                order_type: OrderType::Recharge,
+                amount: dto.amount,
+                description: Some("余额充值".to_string()),
+                metadata: None,
+                coupon_code: None,
+                idempotency_key: None,
+            },
+        )
+        .await?;
+
+        Self::initiate_payment(
+            db,
+            gateway,
+            InitiatePaymentDto {
+                order_id: order.id,
+                payment_method: dto.payment_method,
+                return_url: dto.return_url,
+                openid: dto.openid,
+                wap: dto.wap,
+            },
+        )
+        .await
+    }
+
+    /// 将已支付的充值订单金额计入余额，须与订单改为已支付在同一事务中执行
+    async fn credit_recharge_tx(
+        tx: &mut Transaction<'_, MySql>,
+        order: &PaymentOrder,
+    ) -> Result<(), AppError> {
+        // Users who never opened the balance page have no balance row yet
+        sqlx::query(
+            r#"
+            INSERT IGNORE INTO user_balances (
+                id, user_id, balance, frozen_balance,
+                total_income, total_expense, created_at, updated_at
+            ) VALUES (?, ?, 0, 0, 0, 0, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(order.user_id.to_string())
+        .bind(Utc::now())
+        .bind(Utc::now())
+        .execute(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::update_balance_tx(
+            tx,
+            order.user_id,
+            BalanceTransactionType::Income,
+            order.amount,
+            Some("recharge".to_string()),
+            Some(order.id),
+            &format!("余额充值: {}", order.order_no),
+        )
+        .await
+    }
+
     pub async fn get_user_balance(db: &DbPool, user_id: Uuid) -> Result<UserBalance, AppError> {
         Self::parse_user_balance_optional(db, user_id)
             .await?
@@ -990,206 +3302,1445 @@ impl PaymentService {
         Ok(transactions)
     }
 
-    // Configuration management
-    pub async fn get_payment_config(
+    // Withdrawal management
+    /// 申请余额提现，提现金额立即从可用余额转入冻结余额，等待管理员审核
+    pub async fn create_withdrawal(
         db: &DbPool,
-        payment_method: PaymentMethod,
-    ) -> Result<HashMap<String, String>, AppError> {
-        let query = r#"
-            SELECT config_key, config_value FROM payment_configs
-            WHERE payment_method = ?
-        "#;
+        user_id: Uuid,
+        dto: CreateBalanceWithdrawalDto,
+    ) -> Result<BalanceWithdrawal, AppError> {
+        if dto.amount <= Decimal::ZERO {
+            return Err(AppError::BadRequest("提现金额必须大于0".to_string()));
+        }
+        if !matches!(
+            dto.payout_method,
+            PaymentMethod::Wechat | PaymentMethod::Alipay
+        ) {
+            return Err(AppError::BadRequest(
+                "提现仅支持到账微信或支付宝".to_string(),
+            ));
+        }
 
-        let configs: Vec<(String, String)> = sqlx::query_as(query)
-            .bind(match &payment_method {
-                PaymentMethod::Wechat => "wechat",
-                PaymentMethod::Alipay => "alipay",
-                PaymentMethod::BankCard => "bank_card",
-                PaymentMethod::Balance => "balance",
-            })
-            .fetch_all(db)
+        let mut tx = db
+            .begin()
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        Ok(configs.into_iter().collect())
-    }
+        // The balance row stays locked until commit, so concurrent requests are
+        // checked one after another against what is still available
+        let balance = Self::parse_user_balance_tx(&mut tx, user_id).await?;
+        if balance.is_none_or(|b| b.balance < dto.amount) {
+            return Err(AppError::BadRequest("可用余额不足".to_string()));
+        }
+
+        let withdrawal_id = Uuid::new_v4();
+        let withdrawal_no = Self::generate_withdrawal_no();
+        let now = Utc::now();
+
+        Self::update_balance_tx(
+            &mut tx,
+            user_id,
+            BalanceTransactionType::Freeze,
+            dto.amount,
+            Some("withdrawal".to_string()),
+            Some(withdrawal_id),
+            &format!("提现冻结: {}", withdrawal_no),
+        )
+        .await?;
 
-    pub async fn update_payment_config(
-        db: &DbPool,
-        payment_method: PaymentMethod,
-        config_key: &str,
-        config_value: &str,
-        is_encrypted: bool,
-    ) -> Result<(), AppError> {
         let query = r#"
-            INSERT INTO payment_configs (id, payment_method, config_key, config_value, is_encrypted, created_at, updated_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?)
-            ON DUPLICATE KEY UPDATE
-            config_value = VALUES(config_value),
-            is_encrypted = VALUES(is_encrypted),
-            updated_at = VALUES(updated_at)
+            INSERT INTO withdrawal_requests (
+                id, withdrawal_no, user_id, amount, payout_method,
+                payout_account, status, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, 'pending', ?, ?)
         "#;
 
-        let now = Utc::now();
         sqlx::query(query)
-            .bind(Uuid::new_v4().to_string())
-            .bind(match &payment_method {
+            .bind(withdrawal_id.to_string())
+            .bind(&withdrawal_no)
+            .bind(user_id.to_string())
+            .bind(dto.amount)
+            .bind(match dto.payout_method {
                 PaymentMethod::Wechat => "wechat",
-                PaymentMethod::Alipay => "alipay",
-                PaymentMethod::BankCard => "bank_card",
-                PaymentMethod::Balance => "balance",
+                _ => "alipay",
             })
-            .bind(config_key)
-            .bind(config_value)
-            .bind(is_encrypted)
+            .bind(&dto.payout_account)
             .bind(now)
             .bind(now)
-            .execute(db)
+            .execute(&mut *tx)
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        Ok(())
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_withdrawal(db, withdrawal_id).await
+    }
+
+    pub async fn get_withdrawal(
+        db: &DbPool,
+        withdrawal_id: Uuid,
+    ) -> Result<BalanceWithdrawal, AppError> {
+        let query = r#"
+            SELECT * FROM withdrawal_requests WHERE id = ?
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(withdrawal_id.to_string())
+            .fetch_optional(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("提现申请不存在".to_string()))?;
+
+        Self::parse_withdrawal_row(row)
+    }
+
+    /// 审核提现申请：通过时解冻并扣除提现金额，驳回时解冻退回可用余额
+    pub async fn review_withdrawal(
+        db: &DbPool,
+        withdrawal_id: Uuid,
+        dto: ReviewBalanceWithdrawalDto,
+        reviewer_id: Uuid,
+    ) -> Result<BalanceWithdrawal, AppError> {
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // Locked so two reviewers can't both settle the same request
+        let row = sqlx::query("SELECT * FROM withdrawal_requests WHERE id = ? FOR UPDATE")
+            .bind(withdrawal_id.to_string())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("提现申请不存在".to_string()))?;
+        let withdrawal = Self::parse_withdrawal_row(row)?;
+
+        if withdrawal.status != BalanceWithdrawalStatus::Pending {
+            return Err(AppError::BadRequest("提现申请已处理".to_string()));
+        }
+
+        Self::update_balance_tx(
+            &mut tx,
+            withdrawal.user_id,
+            BalanceTransactionType::Unfreeze,
+            withdrawal.amount,
+            Some("withdrawal".to_string()),
+            Some(withdrawal.id),
+            &format!("提现解冻: {}", withdrawal.withdrawal_no),
+        )
+        .await?;
+
+        let new_status = if dto.approved {
+            // The payout itself, recorded against the amount just released
+            Self::update_balance_tx(
+                &mut tx,
+                withdrawal.user_id,
+                BalanceTransactionType::Expense,
+                withdrawal.amount,
+                Some("withdrawal".to_string()),
+                Some(withdrawal.id),
+                &format!("提现: {}", withdrawal.withdrawal_no),
+            )
+            .await?;
+            "approved"
+        } else {
+            "rejected"
+        };
+
+        let query = r#"
+            UPDATE withdrawal_requests
+            SET status = ?, reviewed_by = ?, reviewed_at = ?,
+                review_notes = ?, updated_at = ?
+            WHERE id = ?
+        "#;
+
+        let now = Utc::now();
+        sqlx::query(query)
+            .bind(new_status)
+            .bind(reviewer_id.to_string())
+            .bind(now)
+            .bind(&dto.review_notes)
+            .bind(now)
+            .bind(withdrawal_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        AuditService::log(
+            &mut *tx,
+            reviewer_id,
+            "withdrawal.review",
+            "withdrawal_request",
+            withdrawal_id,
+            Some(serde_json::json!({
+                "approved": dto.approved,
+                "amount": withdrawal.amount,
+                "review_notes": dto.review_notes,
+            })),
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_withdrawal(db, withdrawal_id).await
+    }
+
+    pub async fn list_withdrawals(
+        db: &DbPool,
+        query: BalanceWithdrawalQuery,
+    ) -> Result<BalanceWithdrawalListResponse, AppError> {
+        let page = query.page.unwrap_or(1).max(1);
+        let page_size = query.page_size.unwrap_or(20).min(100);
+        let offset = (page - 1) * page_size;
+
+        let mut where_clauses = vec![];
+        if query.user_id.is_some() {
+            where_clauses.push("user_id = ?");
+        }
+        if query.status.is_some() {
+            where_clauses.push("status = ?");
+        }
+
+        let where_clause = if where_clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", where_clauses.join(" AND "))
+        };
+
+        let count_query = format!(
+            "SELECT COUNT(*) as count FROM withdrawal_requests {}",
+            where_clause
+        );
+        let mut count_query_builder = sqlx::query_scalar::<_, i64>(&count_query);
+        if let Some(user_id) = &query.user_id {
+            count_query_builder = count_query_builder.bind(user_id.to_string());
+        }
+        if let Some(status) = &query.status {
+            count_query_builder = count_query_builder.bind(status);
+        }
+
+        let total = count_query_builder
+            .fetch_one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let list_query = format!(
+            "SELECT * FROM withdrawal_requests {} ORDER BY created_at DESC LIMIT ? OFFSET ?",
+            where_clause
+        );
+        let mut list_query_builder = sqlx::query(&list_query);
+        if let Some(user_id) = &query.user_id {
+            list_query_builder = list_query_builder.bind(user_id.to_string());
+        }
+        if let Some(status) = &query.status {
+            list_query_builder = list_query_builder.bind(status);
+        }
+
+        let rows = list_query_builder
+            .bind(page_size)
+            .bind(offset)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut withdrawals = Vec::new();
+        for row in rows {
+            withdrawals.push(Self::parse_withdrawal_row(row)?);
+        }
+
+        Ok(BalanceWithdrawalListResponse {
+            withdrawals,
+            total,
+            page,
+            page_size,
+        })
+    }
+
+    // Configuration management
+    pub async fn get_payment_config(
+        db: &DbPool,
+        payment_method: PaymentMethod,
+    ) -> Result<HashMap<String, String>, AppError> {
+        let query = r#"
+            SELECT config_key, config_value FROM payment_configs
+            WHERE payment_method = ? AND deleted_at IS NULL
+        "#;
+
+        let configs: Vec<(String, String)> = sqlx::query_as(query)
+            .bind(match &payment_method {
+                PaymentMethod::Wechat => "wechat",
+                PaymentMethod::Alipay => "alipay",
+                PaymentMethod::BankCard => "bank_card",
+                PaymentMethod::Balance => "balance",
+            })
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(configs.into_iter().collect())
+    }
+
+    pub async fn update_payment_config(
+        db: &DbPool,
+        payment_method: PaymentMethod,
+        config_key: &str,
+        config_value: &str,
+        is_encrypted: bool,
+        changed_by: Uuid,
+    ) -> Result<(), AppError> {
+        let method_str = match &payment_method {
+            PaymentMethod::Wechat => "wechat",
+            PaymentMethod::Alipay => "alipay",
+            PaymentMethod::BankCard => "bank_card",
+            PaymentMethod::Balance => "balance",
+        };
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // Capture the previous value for the history record
+        let previous: Option<(String, Option<bool>, Option<DateTime<Utc>>)> = sqlx::query_as(
+            r#"
+            SELECT config_value, is_encrypted, deleted_at FROM payment_configs
+            WHERE payment_method = ? AND config_key = ?
+            FOR UPDATE
+            "#,
+        )
+        .bind(method_str)
+        .bind(config_key)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let query = r#"
+            INSERT INTO payment_configs (id, payment_method, config_key, config_value, is_encrypted, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+            config_value = VALUES(config_value),
+            is_encrypted = VALUES(is_encrypted),
+            deleted_at = NULL,
+            updated_at = VALUES(updated_at)
+        "#;
+
+        let now = Utc::now();
+        sqlx::query(query)
+            .bind(Uuid::new_v4().to_string())
+            .bind(method_str)
+            .bind(config_key)
+            .bind(config_value)
+            .bind(is_encrypted)
+            .bind(now)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let (action, old_value, was_encrypted) = match previous {
+            Some((value, encrypted, None)) => ("update", Some(value), encrypted.unwrap_or(false)),
+            // Re-creating a soft-deleted key starts a fresh value
+            Some((_, encrypted, Some(_))) => ("create", None, encrypted.unwrap_or(false)),
+            None => ("create", None, false),
+        };
+
+        Self::insert_config_history(
+            &mut tx,
+            method_str,
+            config_key,
+            action,
+            old_value.as_deref(),
+            Some(config_value),
+            is_encrypted || was_encrypted,
+            changed_by,
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 软删除支付配置，保留变更历史
+    pub async fn delete_payment_config(
+        db: &DbPool,
+        payment_method: PaymentMethod,
+        config_key: &str,
+        changed_by: Uuid,
+    ) -> Result<(), AppError> {
+        let method_str = match &payment_method {
+            PaymentMethod::Wechat => "wechat",
+            PaymentMethod::Alipay => "alipay",
+            PaymentMethod::BankCard => "bank_card",
+            PaymentMethod::Balance => "balance",
+        };
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let previous: Option<(String, Option<bool>)> = sqlx::query_as(
+            r#"
+            SELECT config_value, is_encrypted FROM payment_configs
+            WHERE payment_method = ? AND config_key = ? AND deleted_at IS NULL
+            FOR UPDATE
+            "#,
+        )
+        .bind(method_str)
+        .bind(config_key)
+        .fetch_optional(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let (old_value, is_encrypted) =
+            previous.ok_or_else(|| AppError::NotFound("支付配置不存在".to_string()))?;
+        let is_encrypted = is_encrypted.unwrap_or(false);
+
+        let now = Utc::now();
+        sqlx::query(
+            r#"
+            UPDATE payment_configs
+            SET deleted_at = ?, updated_at = ?
+            WHERE payment_method = ? AND config_key = ?
+            "#,
+        )
+        .bind(now)
+        .bind(now)
+        .bind(method_str)
+        .bind(config_key)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::insert_config_history(
+            &mut tx,
+            method_str,
+            config_key,
+            "delete",
+            Some(&old_value),
+            None,
+            is_encrypted,
+            changed_by,
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 查询支付配置变更历史，加密配置的值以掩码返回
+    pub async fn get_payment_config_history(
+        db: &DbPool,
+        query: PaymentConfigHistoryQuery,
+    ) -> Result<Vec<PaymentConfigHistory>, AppError> {
+        let page = query.page.unwrap_or(1).max(1);
+        let page_size = query.page_size.unwrap_or(20).min(100);
+        let offset = (page - 1) * page_size;
+
+        let mut sql = String::from("SELECT * FROM payment_config_history WHERE 1=1");
+        if query.payment_method.is_some() {
+            sql.push_str(" AND payment_method = ?");
+        }
+        if query.config_key.is_some() {
+            sql.push_str(" AND config_key = ?");
+        }
+        sql.push_str(" ORDER BY changed_at DESC LIMIT ? OFFSET ?");
+
+        let mut query_builder = sqlx::query(&sql);
+        if let Some(payment_method) = &query.payment_method {
+            query_builder = query_builder.bind(payment_method);
+        }
+        if let Some(config_key) = &query.config_key {
+            query_builder = query_builder.bind(config_key);
+        }
+
+        let rows = query_builder
+            .bind(page_size)
+            .bind(offset)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut history = Vec::new();
+        for row in rows {
+            history.push(Self::parse_config_history_row(row)?);
+        }
+        Ok(history)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn insert_config_history(
+        tx: &mut Transaction<'_, MySql>,
+        payment_method: &str,
+        config_key: &str,
+        action: &str,
+        old_value: Option<&str>,
+        new_value: Option<&str>,
+        is_encrypted: bool,
+        changed_by: Uuid,
+    ) -> Result<(), AppError> {
+        let query = r#"
+            INSERT INTO payment_config_history (
+                id, payment_method, config_key, action, old_value,
+                new_value, is_encrypted, changed_by, changed_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(Uuid::new_v4().to_string())
+            .bind(payment_method)
+            .bind(config_key)
+            .bind(action)
+            .bind(old_value)
+            .bind(new_value)
+            .bind(is_encrypted)
+            .bind(changed_by.to_string())
+            .bind(Utc::now())
+            .execute(&mut **tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    // Price management
+    /// 当前生效的价格配置，多条同时有效时取生效日期最近的一条
+    pub async fn get_price_config(
+        db: &DbPool,
+        service_type: &str,
+    ) -> Result<Option<PriceConfig>, AppError> {
+        let query = r#"
+            SELECT * FROM price_configs
+            WHERE service_type = ? AND is_active = true
+            AND (effective_date IS NULL OR effective_date <= CURDATE())
+            AND (expiry_date IS NULL OR expiry_date >= CURDATE())
+            ORDER BY effective_date DESC, created_at DESC
+            LIMIT 1
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(service_type)
+            .fetch_optional(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        match row {
+            Some(row) => Ok(Some(Self::parse_price_config_row(row)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn list_price_configs(
+        db: &DbPool,
+        is_active: Option<bool>,
+    ) -> Result<Vec<PriceConfig>, AppError> {
+        let query = match is_active {
+            Some(_active) => {
+                r#"
+                SELECT * FROM price_configs
+                WHERE is_active = ?
+                ORDER BY service_type, created_at DESC
+            "#
+            }
+            None => {
+                r#"
+                SELECT * FROM price_configs
+                ORDER BY service_type, created_at DESC
+            "#
+            }
+        };
+
+        let rows = if let Some(active) = is_active {
+            sqlx::query(query).bind(active).fetch_all(db).await
+        } else {
+            sqlx::query(query).fetch_all(db).await
+        }
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut configs = Vec::new();
+        for row in rows {
+            configs.push(Self::parse_price_config_row(row)?);
+        }
+        Ok(configs)
+    }
+
+    pub async fn create_price_config(
+        db: &DbPool,
+        dto: CreatePriceConfigDto,
+        admin_id: Uuid,
+    ) -> Result<PriceConfig, AppError> {
+        Self::validate_price_config(
+            dto.price,
+            dto.discount_price,
+            dto.effective_date,
+            dto.expiry_date,
+        )?;
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::ensure_no_price_overlap(
+            &mut tx,
+            &dto.service_type,
+            None,
+            dto.effective_date,
+            dto.expiry_date,
+        )
+        .await?;
+
+        let config_id = Uuid::new_v4();
+        let now = Utc::now();
+        sqlx::query(
+            r#"
+            INSERT INTO price_configs (
+                id, service_type, service_name, price, discount_price, is_active,
+                effective_date, expiry_date, description, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, true, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(config_id.to_string())
+        .bind(&dto.service_type)
+        .bind(&dto.service_name)
+        .bind(dto.price)
+        .bind(dto.discount_price)
+        .bind(dto.effective_date)
+        .bind(dto.expiry_date)
+        .bind(&dto.description)
+        .bind(now)
+        .bind(now)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        AuditService::log(
+            &mut *tx,
+            admin_id,
+            "price_config.create",
+            "price_config",
+            config_id,
+            Some(serde_json::json!({
+                "service_type": dto.service_type,
+                "price": dto.price,
+            })),
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_price_config_by_id(db, config_id).await
+    }
+
+    pub async fn update_price_config(
+        db: &DbPool,
+        config_id: Uuid,
+        dto: UpdatePriceConfigDto,
+        admin_id: Uuid,
+    ) -> Result<PriceConfig, AppError> {
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let row = sqlx::query("SELECT * FROM price_configs WHERE id = ? FOR UPDATE")
+            .bind(config_id.to_string())
+            .fetch_optional(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("价格配置不存在".to_string()))?;
+        let current = Self::parse_price_config_row(row)?;
+
+        let service_name = dto.service_name.unwrap_or(current.service_name);
+        let price = dto.price.unwrap_or(current.price);
+        let discount_price = dto.discount_price.or(current.discount_price);
+        let effective_date = dto.effective_date.or(current.effective_date);
+        let expiry_date = dto.expiry_date.or(current.expiry_date);
+        let description = dto.description.or(current.description);
+
+        Self::validate_price_config(price, discount_price, effective_date, expiry_date)?;
+        if current.is_active {
+            Self::ensure_no_price_overlap(
+                &mut tx,
+                &current.service_type,
+                Some(config_id),
+                effective_date,
+                expiry_date,
+            )
+            .await?;
+        }
+
+        sqlx::query(
+            r#"
+            UPDATE price_configs
+            SET service_name = ?, price = ?, discount_price = ?, effective_date = ?,
+                expiry_date = ?, description = ?, updated_at = ?
+            WHERE id = ?
+            "#,
+        )
+        .bind(&service_name)
+        .bind(price)
+        .bind(discount_price)
+        .bind(effective_date)
+        .bind(expiry_date)
+        .bind(&description)
+        .bind(Utc::now())
+        .bind(config_id.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        AuditService::log(
+            &mut *tx,
+            admin_id,
+            "price_config.update",
+            "price_config",
+            config_id,
+            Some(serde_json::json!({
+                "service_type": current.service_type,
+                "from_price": current.price,
+                "to_price": price,
+            })),
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_price_config_by_id(db, config_id).await
+    }
+
+    /// 停用价格配置，保留记录以便追溯历史订单的定价
+    pub async fn deactivate_price_config(
+        db: &DbPool,
+        config_id: Uuid,
+        admin_id: Uuid,
+    ) -> Result<PriceConfig, AppError> {
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let result =
+            sqlx::query("UPDATE price_configs SET is_active = false, updated_at = ? WHERE id = ?")
+                .bind(Utc::now())
+                .bind(config_id.to_string())
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::NotFound("价格配置不存在".to_string()));
+        }
+
+        AuditService::log(
+            &mut *tx,
+            admin_id,
+            "price_config.deactivate",
+            "price_config",
+            config_id,
+            None,
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_price_config_by_id(db, config_id).await
+    }
+
+    async fn get_price_config_by_id(db: &DbPool, config_id: Uuid) -> Result<PriceConfig, AppError> {
+        let row = sqlx::query("SELECT * FROM price_configs WHERE id = ?")
+            .bind(config_id.to_string())
+            .fetch_optional(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .ok_or_else(|| AppError::NotFound("价格配置不存在".to_string()))?;
+
+        Self::parse_price_config_row(row)
+    }
+
+    fn validate_price_config(
+        price: Decimal,
+        discount_price: Option<Decimal>,
+        effective_date: Option<NaiveDate>,
+        expiry_date: Option<NaiveDate>,
+    ) -> Result<(), AppError> {
+        if price <= Decimal::ZERO {
+            return Err(AppError::BadRequest("价格必须大于0".to_string()));
+        }
+        if let Some(discount) = discount_price {
+            if discount < Decimal::ZERO || discount > price {
+                return Err(AppError::BadRequest("折扣价必须在0与原价之间".to_string()));
+            }
+        }
+        if let (Some(effective), Some(expiry)) = (effective_date, expiry_date) {
+            if effective > expiry {
+                return Err(AppError::BadRequest("生效日期不能晚于失效日期".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// 同一服务类型启用中的配置有效期不能重叠，日期为空视为无边界
+    async fn ensure_no_price_overlap(
+        tx: &mut Transaction<'_, MySql>,
+        service_type: &str,
+        exclude_id: Option<Uuid>,
+        effective_date: Option<NaiveDate>,
+        expiry_date: Option<NaiveDate>,
+    ) -> Result<(), AppError> {
+        let overlapping: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT id FROM price_configs
+            WHERE service_type = ? AND is_active = true
+            AND (? IS NULL OR id <> ?)
+            AND (effective_date IS NULL OR ? IS NULL OR effective_date <= ?)
+            AND (expiry_date IS NULL OR ? IS NULL OR expiry_date >= ?)
+            LIMIT 1
+            FOR UPDATE
+            "#,
+        )
+        .bind(service_type)
+        .bind(exclude_id.map(|id| id.to_string()))
+        .bind(exclude_id.map(|id| id.to_string()))
+        .bind(expiry_date)
+        .bind(expiry_date)
+        .bind(effective_date)
+        .bind(effective_date)
+        .fetch_optional(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if overlapping.is_some() {
+            return Err(AppError::BadRequest(
+                "该服务类型已有有效期重叠的启用配置".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    // Statistics
+    pub async fn get_user_payment_summary(
+        db: &DbPool,
+        user_id: Uuid,
+    ) -> Result<UserPaymentSummary, AppError> {
+        let statistics = Self::get_payment_statistics(db, Some(user_id), None, None).await?;
+
+        let total_refunded: Option<Decimal> = sqlx::query_scalar(
+            r#"
+            SELECT SUM(r.refund_amount)
+            FROM refund_records r
+            JOIN payment_orders o ON o.id = r.order_id
+            WHERE o.user_id = ? AND r.status = 'success'
+            "#,
+        )
+        .bind(user_id.to_string())
+        .fetch_one(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let balance = Self::parse_user_balance_optional(db, user_id).await?;
+
+        Ok(UserPaymentSummary {
+            user_id,
+            total_spent: statistics.paid_amount + statistics.refunded_amount,
+            total_refunded: total_refunded.unwrap_or(Decimal::ZERO),
+            balance: balance.as_ref().map(|b| b.balance).unwrap_or(Decimal::ZERO),
+            frozen_balance: balance
+                .as_ref()
+                .map(|b| b.frozen_balance)
+                .unwrap_or(Decimal::ZERO),
+            statistics,
+        })
+    }
+
+    pub async fn get_payment_statistics(
+        db: &DbPool,
+        user_id: Option<Uuid>,
+        start_date: Option<chrono::DateTime<Utc>>,
+        end_date: Option<chrono::DateTime<Utc>>,
+    ) -> Result<PaymentStatistics, AppError> {
+        let query = format!(
+            r#"
+            SELECT 
+                COUNT(*) as total_orders,
+                COALESCE(SUM(amount), 0) as total_amount,
+                COUNT(CASE WHEN status = 'paid' THEN 1 END) as paid_orders,
+                COALESCE(SUM(CASE WHEN status = 'paid' THEN amount END), 0) as paid_amount,
+                COUNT(CASE WHEN status IN ('refunded', 'partial_refunded') THEN 1 END) as refunded_orders,
+                COALESCE(SUM(CASE WHEN status IN ('refunded', 'partial_refunded') THEN amount END), 0) as refunded_amount
+            FROM payment_orders
+            {}
+            "#,
+            Self::statistics_where(user_id, start_date, end_date, &[])
+        );
+
+        let row = Self::bind_statistics_filter(sqlx::query(&query), user_id, start_date, end_date)
+            .fetch_one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        use sqlx::Row;
+        Ok(PaymentStatistics {
+            total_orders: row.get::<Option<i64>, _>("total_orders").unwrap_or(0),
+            total_amount: row
+                .get::<Option<Decimal>, _>("total_amount")
+                .unwrap_or(Decimal::ZERO),
+            paid_orders: row.get::<Option<i64>, _>("paid_orders").unwrap_or(0),
+            paid_amount: row
+                .get::<Option<Decimal>, _>("paid_amount")
+                .unwrap_or(Decimal::ZERO),
+            refunded_orders: row.get::<Option<i64>, _>("refunded_orders").unwrap_or(0),
+            refunded_amount: row
+                .get::<Option<Decimal>, _>("refunded_amount")
+                .unwrap_or(Decimal::ZERO),
+            buckets: None,
+        })
+    }
+
+    /// 按周期拆分的支付统计，口径与 get_payment_statistics 一致，周期按时间升序
+    pub async fn get_payment_statistics_buckets(
+        db: &DbPool,
+        user_id: Option<Uuid>,
+        start_date: Option<chrono::DateTime<Utc>>,
+        end_date: Option<chrono::DateTime<Utc>>,
+        group_by: StatisticsGroupBy,
+    ) -> Result<Vec<PaymentStatisticsBucket>, AppError> {
+        use sqlx::Row;
+
+        let period = group_by.period_expr();
+        let query = format!(
+            r#"
+            SELECT
+                {period} as period_start,
+                COUNT(*) as total_orders,
+                COUNT(CASE WHEN status = 'paid' THEN 1 END) as paid_orders,
+                COALESCE(SUM(CASE WHEN status = 'paid' THEN amount END), 0) as paid_amount,
+                COUNT(CASE WHEN status IN ('refunded', 'partial_refunded') THEN 1 END) as refunded_orders,
+                COALESCE(SUM(CASE WHEN status IN ('refunded', 'partial_refunded') THEN amount END), 0) as refunded_amount
+            FROM payment_orders
+            {filter}
+            GROUP BY period_start
+            ORDER BY period_start
+            "#,
+            period = period,
+            filter = Self::statistics_where(user_id, start_date, end_date, &[])
+        );
+
+        let rows = Self::bind_statistics_filter(sqlx::query(&query), user_id, start_date, end_date)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut buckets = Vec::with_capacity(rows.len());
+        for row in rows {
+            let period_start: String = row.get("period_start");
+            buckets.push(PaymentStatisticsBucket {
+                period_start: Self::parse_period_start(&period_start)?,
+                total_orders: row.get::<Option<i64>, _>("total_orders").unwrap_or(0),
+                paid_orders: row.get::<Option<i64>, _>("paid_orders").unwrap_or(0),
+                paid_amount: row
+                    .get::<Option<Decimal>, _>("paid_amount")
+                    .unwrap_or(Decimal::ZERO),
+                refunded_orders: row.get::<Option<i64>, _>("refunded_orders").unwrap_or(0),
+                refunded_amount: row
+                    .get::<Option<Decimal>, _>("refunded_amount")
+                    .unwrap_or(Decimal::ZERO),
+                method_amounts: BTreeMap::new(),
+            });
+        }
+
+        let method_query = format!(
+            r#"
+            SELECT
+                {period} as period_start,
+                payment_method,
+                SUM(amount) as amount
+            FROM payment_orders
+            {filter}
+            GROUP BY period_start, payment_method
+            "#,
+            period = period,
+            filter = Self::statistics_where(
+                user_id,
+                start_date,
+                end_date,
+                &["status = 'paid'", "payment_method IS NOT NULL"],
+            )
+        );
+
+        let method_rows =
+            Self::bind_statistics_filter(sqlx::query(&method_query), user_id, start_date, end_date)
+                .fetch_all(db)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        for row in method_rows {
+            let period_start: String = row.get("period_start");
+            let period_start = Self::parse_period_start(&period_start)?;
+            if let Some(bucket) = buckets
+                .iter_mut()
+                .find(|bucket| bucket.period_start == period_start)
+            {
+                bucket.method_amounts.insert(
+                    row.get("payment_method"),
+                    row.get::<Option<Decimal>, _>("amount")
+                        .unwrap_or(Decimal::ZERO),
+                );
+            }
+        }
+
+        Ok(buckets)
+    }
+
+    /// 支付统计共用的筛选条件，绑定顺序见 bind_statistics_filter
+    fn statistics_where(
+        user_id: Option<Uuid>,
+        start_date: Option<chrono::DateTime<Utc>>,
+        end_date: Option<chrono::DateTime<Utc>>,
+        extra: &[&str],
+    ) -> String {
+        let mut where_clauses = vec![];
+
+        if user_id.is_some() {
+            where_clauses.push("user_id = ?");
+        }
+
+        if start_date.is_some() {
+            where_clauses.push("created_at >= ?");
+        }
+
+        if end_date.is_some() {
+            where_clauses.push("created_at <= ?");
+        }
+
+        where_clauses.extend_from_slice(extra);
+
+        if where_clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", where_clauses.join(" AND "))
+        }
+    }
+
+    fn bind_statistics_filter<'q>(
+        mut query: Query<'q, MySql, MySqlArguments>,
+        user_id: Option<Uuid>,
+        start_date: Option<chrono::DateTime<Utc>>,
+        end_date: Option<chrono::DateTime<Utc>>,
+    ) -> Query<'q, MySql, MySqlArguments> {
+        if let Some(uid) = user_id {
+            query = query.bind(uid.to_string());
+        }
+
+        if let Some(start) = start_date {
+            query = query.bind(start);
+        }
+
+        if let Some(end) = end_date {
+            query = query.bind(end);
+        }
+
+        query
+    }
+
+    fn parse_period_start(value: &str) -> Result<NaiveDate, AppError> {
+        NaiveDate::parse_from_str(value, "%Y-%m-%d")
+            .map_err(|e| AppError::DatabaseError(format!("统计周期格式错误: {}", e)))
+    }
+
+    // Helper methods
+    /// 对账某一天（北京时间）的渠道支付：自动下载已配置渠道的交易账单，
+    /// 与本地成功支付流水逐笔比对并写入对账报告，重跑同一天会覆盖原报告
+    pub async fn reconcile(
+        db: &DbPool,
+        gateway: &dyn PaymentGateway,
+        date: NaiveDate,
+    ) -> Result<ReconciliationReport, AppError> {
+        Self::run_reconciliation(
+            db,
+            gateway,
+            RunReconciliationDto {
+                date,
+                wechat_bill: None,
+                alipay_bill: None,
+            },
+        )
+        .await
     }
 
-    // Price management
-    pub async fn get_price_config(
+    /// 按请求对账，优先使用请求中提供的账单明细，未提供的渠道自动下载
+    pub async fn run_reconciliation(
         db: &DbPool,
-        service_type: &str,
-    ) -> Result<Option<PriceConfig>, AppError> {
+        gateway: &dyn PaymentGateway,
+        dto: RunReconciliationDto,
+    ) -> Result<ReconciliationReport, AppError> {
+        if dto.date >= Utc::now().with_timezone(&Self::beijing()).date_naive() {
+            return Err(AppError::BadRequest("只能对账已结束的日期".to_string()));
+        }
+
+        let (status, local, remote, comparison, error_message) =
+            match Self::fetch_remote_bills(db, gateway, &dto).await {
+                Ok((methods, remote)) => {
+                    let local = Self::local_bill_entries(db, dto.date, &methods).await?;
+                    let comparison = Self::compare_bills(&local, &remote);
+                    let status = if comparison.is_balanced() {
+                        ReconciliationStatus::Matched
+                    } else {
+                        ReconciliationStatus::Mismatched
+                    };
+                    (status, local, remote, comparison, None)
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to fetch payment bills for {}: {}", dto.date, e);
+                    (
+                        ReconciliationStatus::Failed,
+                        Vec::new(),
+                        Vec::new(),
+                        BillComparison::default(),
+                        Some(e.to_string()),
+                    )
+                }
+            };
+
+        let total =
+            |entries: &[BillEntry]| entries.iter().map(|entry| entry.amount).sum::<Decimal>();
+
         let query = r#"
-            SELECT * FROM price_configs
-            WHERE service_type = ? AND is_active = true
-            AND (effective_date IS NULL OR effective_date <= CURDATE())
-            AND (expiry_date IS NULL OR expiry_date >= CURDATE())
-            ORDER BY created_at DESC
-            LIMIT 1
+            INSERT INTO reconciliation_reports (
+                id, bill_date, status, local_count, local_amount, remote_count, remote_amount,
+                missing_local, missing_remote, amount_mismatches, error_message
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON DUPLICATE KEY UPDATE
+                status = VALUES(status),
+                local_count = VALUES(local_count),
+                local_amount = VALUES(local_amount),
+                remote_count = VALUES(remote_count),
+                remote_amount = VALUES(remote_amount),
+                missing_local = VALUES(missing_local),
+                missing_remote = VALUES(missing_remote),
+                amount_mismatches = VALUES(amount_mismatches),
+                error_message = VALUES(error_message),
+                updated_at = CURRENT_TIMESTAMP
         "#;
 
-        let row = sqlx::query(query)
-            .bind(service_type)
-            .fetch_optional(db)
+        sqlx::query(query)
+            .bind(Uuid::new_v4().to_string())
+            .bind(dto.date)
+            .bind(Self::reconciliation_status_str(status))
+            .bind(local.len() as i64)
+            .bind(total(&local))
+            .bind(remote.len() as i64)
+            .bind(total(&remote))
+            .bind(serde_json::json!(comparison.missing_local))
+            .bind(serde_json::json!(comparison.missing_remote))
+            .bind(serde_json::json!(comparison.amount_mismatches))
+            .bind(error_message.map(|message| message.chars().take(500).collect::<String>()))
+            .execute(db)
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        match row {
-            Some(row) => Ok(Some(Self::parse_price_config_row(row)?)),
-            None => Ok(None),
-        }
+        Self::get_reconciliation_report(db, dto.date).await
     }
 
-    pub async fn list_price_configs(
+    pub async fn get_reconciliation_report(
         db: &DbPool,
-        is_active: Option<bool>,
-    ) -> Result<Vec<PriceConfig>, AppError> {
-        let query = match is_active {
-            Some(_active) => {
-                r#"
-                SELECT * FROM price_configs
-                WHERE is_active = ?
-                ORDER BY service_type, created_at DESC
-            "#
-            }
-            None => {
-                r#"
-                SELECT * FROM price_configs
-                ORDER BY service_type, created_at DESC
-            "#
-            }
+        date: NaiveDate,
+    ) -> Result<ReconciliationReport, AppError> {
+        let row = sqlx::query("SELECT * FROM reconciliation_reports WHERE bill_date = ?")
+            .bind(date)
+            .fetch_one(db)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => AppError::NotFound("该日期暂无对账报告".to_string()),
+                _ => AppError::DatabaseError(e.to_string()),
+            })?;
+
+        Self::parse_reconciliation_row(row)
+    }
+
+    /// 逐笔比对本地流水与渠道账单，按支付方式和商户订单号匹配
+    pub fn compare_bills(local: &[BillEntry], remote: &[BillEntry]) -> BillComparison {
+        let key = |entry: &BillEntry| {
+            (
+                Self::payment_method_str(&entry.payment_method),
+                entry.order_no.clone(),
+            )
         };
 
-        let rows = if let Some(active) = is_active {
-            sqlx::query(query).bind(active).fetch_all(db).await
-        } else {
-            sqlx::query(query).fetch_all(db).await
+        let mut unmatched: BTreeMap<(&str, String), Vec<&BillEntry>> = BTreeMap::new();
+        for entry in local {
+            unmatched.entry(key(entry)).or_default().push(entry);
         }
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        let mut configs = Vec::new();
-        for row in rows {
-            configs.push(Self::parse_price_config_row(row)?);
+        let mut comparison = BillComparison::default();
+        for entry in remote {
+            let matched = unmatched
+                .get_mut(&key(entry))
+                .and_then(|entries| entries.pop());
+            match matched {
+                Some(local_entry) if local_entry.amount != entry.amount => {
+                    comparison.amount_mismatches.push(AmountMismatch {
+                        payment_method: entry.payment_method.clone(),
+                        order_no: entry.order_no.clone(),
+                        external_transaction_id: entry
+                            .external_transaction_id
+                            .clone()
+                            .or_else(|| local_entry.external_transaction_id.clone()),
+                        local_amount: local_entry.amount,
+                        remote_amount: entry.amount,
+                    });
+                }
+                Some(_) => {}
+                None => comparison.missing_local.push(entry.clone()),
+            }
         }
-        Ok(configs)
+
+        comparison.missing_remote = unmatched.into_values().flatten().cloned().collect();
+
+        comparison
     }
 
-    // Statistics
-    pub async fn get_payment_statistics(
+    /// 获取参与对账的渠道账单，返回实际对账的支付方式与账单明细；
+    /// 未提供账单且未配置商户信息的渠道不参与对账
+    async fn fetch_remote_bills(
         db: &DbPool,
-        user_id: Option<Uuid>,
-        start_date: Option<chrono::DateTime<Utc>>,
-        end_date: Option<chrono::DateTime<Utc>>,
-    ) -> Result<PaymentStatistics, AppError> {
-        let mut where_clauses = vec![];
+        gateway: &dyn PaymentGateway,
+        dto: &RunReconciliationDto,
+    ) -> Result<(Vec<PaymentMethod>, Vec<BillEntry>), AppError> {
+        let mut methods = Vec::new();
+        let mut entries = Vec::new();
+
+        let wechat_bill = match &dto.wechat_bill {
+            Some(bill) => Some(bill.clone()),
+            None => {
+                let config = Self::get_payment_config(db, PaymentMethod::Wechat).await?;
+                if config.is_empty() {
+                    None
+                } else {
+                    let config = WechatPayConfig::from_map(&config)?;
+                    match WechatPayService::trade_bill_url(gateway, &config, dto.date).await? {
+                        Some(url) => {
+                            Some(WechatPayService::download_bill(gateway, &config, &url).await?)
+                        }
+                        None => Some(String::new()),
+                    }
+                }
+            }
+        };
+        if let Some(bill) = wechat_bill {
+            entries.extend(WechatPayService::parse_trade_bill(&bill)?);
+            methods.push(PaymentMethod::Wechat);
+        }
 
-        if user_id.is_some() {
-            where_clauses.push("user_id = ?");
+        let alipay_bill = match &dto.alipay_bill {
+            Some(bill) => Some(bill.clone()),
+            None => {
+                let config = Self::get_payment_config(db, PaymentMethod::Alipay).await?;
+                if config.is_empty() {
+                    None
+                } else {
+                    Some(Self::download_alipay_bill(gateway, &config, dto.date).await?)
+                }
+            }
+        };
+        if let Some(bill) = alipay_bill {
+            entries.extend(AlipayService::parse_trade_bill(&bill)?);
+            methods.push(PaymentMethod::Alipay);
         }
 
-        if start_date.is_some() {
-            where_clauses.push("created_at >= ?");
+        Ok((methods, entries))
+    }
+
+    /// 查询支付宝账单下载地址并下载；当天无账单时视为空账单
+    async fn download_alipay_bill(
+        gateway: &dyn PaymentGateway,
+        config: &HashMap<String, String>,
+        date: NaiveDate,
+    ) -> Result<String, AppError> {
+        let config = AlipayConfig::from_map(config)?;
+        let params = AlipayService::sign_params(
+            AlipayService::bill_url_params(&config, date, Utc::now()),
+            &Rsa2Signer::new(config.private_key.clone()),
+        )?;
+        let response = match AlipayService::execute(gateway, &params).await {
+            Ok(response) => response,
+            Err(AppError::PaymentGatewayError { code, .. }) if code == "isp.bill_not_exist" => {
+                return Ok(String::new())
+            }
+            Err(e) => return Err(e),
+        };
+        let url = Self::gateway_field(&response, "bill_download_url")?;
+
+        let bill = gateway
+            .send(GatewayRequest {
+                method: reqwest::Method::GET,
+                url,
+                headers: Vec::new(),
+                body: None,
+            })
+            .await?;
+        if !bill.is_success() {
+            return Err(AppError::PaymentGatewayError {
+                code: format!("HTTP_{}", bill.status),
+                message: bill.body,
+            });
+        }
+        // The download is a zip archive; only the unpacked detail CSV can be parsed here
+        if bill.body.starts_with("PK") {
+            return Err(AppError::PaymentGatewayError {
+                code: "UNSUPPORTED_BILL".to_string(),
+                message: "支付宝账单为压缩包，请解压后通过 alipay_bill 提交业务明细".to_string(),
+            });
         }
 
-        if end_date.is_some() {
-            where_clauses.push("created_at <= ?");
+        Ok(bill.body)
+    }
+
+    /// 本地在该日（北京时间）成功的渠道支付流水
+    async fn local_bill_entries(
+        db: &DbPool,
+        date: NaiveDate,
+        methods: &[PaymentMethod],
+    ) -> Result<Vec<BillEntry>, AppError> {
+        if methods.is_empty() {
+            return Ok(Vec::new());
         }
 
-        let where_clause = if where_clauses.is_empty() {
-            String::new()
-        } else {
-            format!("WHERE {}", where_clauses.join(" AND "))
-        };
+        let start = date
+            .and_time(NaiveTime::MIN)
+            .and_local_timezone(Self::beijing())
+            .single()
+            .expect("fixed offset has no gaps")
+            .with_timezone(&Utc);
+        let end = start + Duration::days(1);
 
+        let placeholders = vec!["?"; methods.len()].join(", ");
         let query = format!(
             r#"
-            SELECT 
-                COUNT(*) as total_orders,
-                COALESCE(SUM(amount), 0) as total_amount,
-                COUNT(CASE WHEN status = 'paid' THEN 1 END) as paid_orders,
-                COALESCE(SUM(CASE WHEN status = 'paid' THEN amount END), 0) as paid_amount,
-                COUNT(CASE WHEN status IN ('refunded', 'partial_refunded') THEN 1 END) as refunded_orders,
-                COALESCE(SUM(CASE WHEN status IN ('refunded', 'partial_refunded') THEN amount END), 0) as refunded_amount
-            FROM payment_orders
-            {}
+            SELECT t.payment_method, o.order_no, t.external_transaction_id, t.amount
+            FROM payment_transactions t
+            JOIN payment_orders o ON o.id = t.order_id
+            WHERE t.transaction_type = 'payment' AND t.status = 'success'
+              AND t.completed_at >= ? AND t.completed_at < ?
+              AND t.payment_method IN ({})
+            ORDER BY t.completed_at
             "#,
-            where_clause
+            placeholders
         );
 
-        let mut query_builder = sqlx::query(&query);
-
-        if let Some(uid) = user_id {
-            query_builder = query_builder.bind(uid.to_string());
+        let mut query_builder =
+            sqlx::query_as::<_, (String, String, Option<String>, Decimal)>(&query)
+                .bind(start)
+                .bind(end);
+        for method in methods {
+            query_builder = query_builder.bind(Self::payment_method_str(method));
         }
 
-        if let Some(start) = start_date {
-            query_builder = query_builder.bind(start);
-        }
+        let rows = query_builder
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        if let Some(end) = end_date {
-            query_builder = query_builder.bind(end);
+        Ok(rows
+            .into_iter()
+            .map(
+                |(payment_method, order_no, external_transaction_id, amount)| BillEntry {
+                    // Only wechat and alipay rows are selected
+                    payment_method: if payment_method == "wechat" {
+                        PaymentMethod::Wechat
+                    } else {
+                        PaymentMethod::Alipay
+                    },
+                    order_no,
+                    external_transaction_id,
+                    amount,
+                },
+            )
+            .collect())
+    }
+
+    /// 渠道账单按北京时间自然日出具
+    fn beijing() -> FixedOffset {
+        FixedOffset::east_opt(8 * 3600).expect("valid offset")
+    }
+
+    fn payment_method_str(method: &PaymentMethod) -> &'static str {
+        match method {
+            PaymentMethod::Wechat => "wechat",
+            PaymentMethod::Alipay => "alipay",
+            PaymentMethod::BankCard => "bank_card",
+            PaymentMethod::Balance => "balance",
         }
+    }
 
-        let row = query_builder
-            .fetch_one(db)
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+    fn reconciliation_status_str(status: ReconciliationStatus) -> &'static str {
+        match status {
+            ReconciliationStatus::Matched => "matched",
+            ReconciliationStatus::Mismatched => "mismatched",
+            ReconciliationStatus::Failed => "failed",
+        }
+    }
 
+    fn parse_reconciliation_row(
+        row: sqlx::mysql::MySqlRow,
+    ) -> Result<ReconciliationReport, AppError> {
         use sqlx::Row;
-        Ok(PaymentStatistics {
-            total_orders: row.get::<Option<i64>, _>("total_orders").unwrap_or(0),
-            total_amount: row
-                .get::<Option<Decimal>, _>("total_amount")
-                .unwrap_or(Decimal::ZERO),
-            paid_orders: row.get::<Option<i64>, _>("paid_orders").unwrap_or(0),
-            paid_amount: row
-                .get::<Option<Decimal>, _>("paid_amount")
-                .unwrap_or(Decimal::ZERO),
-            refunded_orders: row.get::<Option<i64>, _>("refunded_orders").unwrap_or(0),
-            refunded_amount: row
-                .get::<Option<Decimal>, _>("refunded_amount")
-                .unwrap_or(Decimal::ZERO),
+
+        let status_str: String = row.get("status");
+        let status = match status_str.as_str() {
+            "matched" => ReconciliationStatus::Matched,
+            "mismatched" => ReconciliationStatus::Mismatched,
+            "failed" => ReconciliationStatus::Failed,
+            _ => {
+                return Err(AppError::BadRequest(
+                    "Invalid reconciliation status".to_string(),
+                ))
+            }
+        };
+        Ok(ReconciliationReport {
+            id: Uuid::parse_str(row.get("id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            bill_date: row.get("bill_date"),
+            status,
+            local_count: row.get::<i32, _>("local_count") as i64,
+            local_amount: row.get("local_amount"),
+            remote_count: row.get::<i32, _>("remote_count") as i64,
+            remote_amount: row.get("remote_amount"),
+            missing_local: Self::json_list(&row, "missing_local")?,
+            missing_remote: Self::json_list(&row, "missing_remote")?,
+            amount_mismatches: Self::json_list(&row, "amount_mismatches")?,
+            error_message: row.get("error_message"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
         })
     }
 
-    // Helper methods
+    fn json_list<T: serde::de::DeserializeOwned>(
+        row: &sqlx::mysql::MySqlRow,
+        column: &str,
+    ) -> Result<Vec<T>, AppError> {
+        use sqlx::Row;
+
+        serde_json::from_value(row.get::<serde_json::Value, _>(column))
+            .map_err(|e| AppError::InternalServerError(e.to_string()))
+    }
+
     async fn get_transaction(
         db: &DbPool,
         transaction_id: Uuid,
@@ -1210,22 +4761,40 @@ impl PaymentService {
         Self::parse_transaction_row(row)
     }
 
-    fn generate_order_no() -> String {
+    fn order_status_str(status: &OrderStatus) -> &'static str {
+        match status {
+            OrderStatus::Pending => "pending",
+            OrderStatus::Paid => "paid",
+            OrderStatus::Cancelled => "cancelled",
+            OrderStatus::Refunded => "refunded",
+            OrderStatus::PartialRefunded => "partial_refunded",
+            OrderStatus::Expired => "expired",
+        }
+    }
+
+    /// 业务单号：前缀 + 秒级时间戳 + 进程内递增序号 + 随机后缀。
+    /// 序号保证同一进程内不重复，随机后缀避免多实例部署时撞号；总长度不超过微信商户单号的 32 位限制
+    fn generate_serial_no(prefix: &str) -> String {
         let timestamp = Utc::now().format("%Y%m%d%H%M%S");
-        let random = chrono::Utc::now().timestamp_subsec_millis() % 10000;
-        format!("ORD{}{:04}", timestamp, random)
+        let sequence = SERIAL_SEQUENCE.fetch_add(1, Ordering::Relaxed) % 10_000;
+        let random = rand::thread_rng().gen_range(0..1_000_000u32);
+        format!("{}{}{:04}{:06}", prefix, timestamp, sequence, random)
+    }
+
+    fn generate_order_no() -> String {
+        Self::generate_serial_no("ORD")
     }
 
     fn generate_transaction_no() -> String {
-        let timestamp = Utc::now().format("%Y%m%d%H%M%S");
-        let random = chrono::Utc::now().timestamp_subsec_millis() % 10000;
-        format!("TXN{}{:04}", timestamp, random)
+        Self::generate_serial_no("TXN")
     }
 
     fn generate_refund_no() -> String {
-        let timestamp = Utc::now().format("%Y%m%d%H%M%S");
-        let random = chrono::Utc::now().timestamp_subsec_millis() % 10000;
-        format!("RFD{}{:04}", timestamp, random)
+        Self::generate_serial_no("RFD")
+    }
+
+    fn generate_withdrawal_no() -> String {
+        Self::generate_serial_no("WDR")
     }
 
     fn parse_order_row(row: sqlx::mysql::MySqlRow) -> Result<PaymentOrder, AppError> {
@@ -1236,6 +4805,7 @@ impl PaymentService {
             "appointment" => OrderType::Appointment,
             "consultation" => OrderType::Consultation,
             "prescription" => OrderType::Prescription,
+            "recharge" => OrderType::Recharge,
             "other" => OrderType::Other,
             _ => return Err(AppError::BadRequest("Invalid order type".to_string())),
         };
@@ -1279,6 +4849,20 @@ impl PaymentService {
             expire_time: row.get("expire_time"),
             description: row.get("description"),
             metadata: row.get("metadata"),
+            coupon_id: row
+                .get::<Option<String>, _>("coupon_id")
+                .and_then(|s| Uuid::parse_str(&s).ok()),
+            discount_amount: row.get("discount_amount"),
+            original_amount: row.get("original_amount"),
+            balance_hold_status: match row
+                .get::<Option<String>, _>("balance_hold_status")
+                .as_deref()
+            {
+                Some("held") => Some(BalanceHoldStatus::Held),
+                Some("settled") => Some(BalanceHoldStatus::Settled),
+                Some("released") => Some(BalanceHoldStatus::Released),
+                _ => None,
+            },
             created_at: row.get("created_at"),
             updated_at: row.get("updated_at"),
         })
@@ -1338,6 +4922,41 @@ impl PaymentService {
         })
     }
 
+    fn coupon_discount_type_str(discount_type: CouponDiscountType) -> &'static str {
+        match discount_type {
+            CouponDiscountType::Fixed => "fixed",
+            CouponDiscountType::Percent => "percent",
+        }
+    }
+
+    fn parse_coupon_row(row: sqlx::mysql::MySqlRow) -> Result<Coupon, AppError> {
+        use sqlx::Row;
+
+        let discount_type_str: String = row.get("discount_type");
+        let discount_type = match discount_type_str.as_str() {
+            "fixed" => CouponDiscountType::Fixed,
+            "percent" => CouponDiscountType::Percent,
+            _ => return Err(AppError::BadRequest("Invalid discount type".to_string())),
+        };
+
+        Ok(Coupon {
+            id: Uuid::parse_str(row.get("id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            code: row.get("code"),
+            discount_type,
+            discount_amount: row.get("discount_amount"),
+            discount_percent: row.get("discount_percent"),
+            min_order_amount: row.get("min_order_amount"),
+            usage_limit: row.get("usage_limit"),
+            used: row.get("used"),
+            is_active: row.get("is_active"),
+            starts_at: row.get("starts_at"),
+            expires_at: row.get("expires_at"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
     fn parse_price_config_row(row: sqlx::mysql::MySqlRow) -> Result<PriceConfig, AppError> {
         use sqlx::Row;
 
@@ -1414,28 +5033,28 @@ impl PaymentService {
         }
     }
 
-    async fn get_transaction_by_order(
-        db: &DbPool,
+    /// 锁定订单在该支付方式下最近一笔支付流水
+    async fn lock_payment_transaction(
+        tx: &mut Transaction<'_, MySql>,
         order_id: Uuid,
         payment_method: &PaymentMethod,
     ) -> Result<PaymentTransaction, AppError> {
         let query = r#"
             SELECT * FROM payment_transactions
-            WHERE order_id = ? AND payment_method = ? AND status = 'pending'
+            WHERE order_id = ? AND payment_method = ? AND transaction_type = 'payment'
             ORDER BY initiated_at DESC LIMIT 1
+            FOR UPDATE
         "#;
 
         let row = sqlx::query(query)
             .bind(order_id.to_string())
-            .bind(match payment_method {
-                PaymentMethod::Wechat => "wechat",
-                PaymentMethod::Alipay => "alipay",
-                PaymentMethod::BankCard => "bank_card",
-                PaymentMethod::Balance => "balance",
-            })
-            .fetch_one(db)
+            .bind(payment_method)
+            .fetch_one(&mut **tx)
             .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => AppError::NotFound("支付流水不存在".to_string()),
+                _ => AppError::DatabaseError(e.to_string()),
+            })?;
 
         Self::parse_transaction_row(row)
     }
@@ -1497,6 +5116,42 @@ impl PaymentService {
         })
     }
 
+    fn parse_config_history_row(
+        row: sqlx::mysql::MySqlRow,
+    ) -> Result<PaymentConfigHistory, AppError> {
+        use sqlx::Row;
+
+        let payment_method_str: String = row.get("payment_method");
+        let payment_method = match payment_method_str.as_str() {
+            "alipay" => PaymentMethod::Alipay,
+            "wechat" => PaymentMethod::Wechat,
+            _ => return Err(AppError::BadRequest("Invalid payment method".to_string())),
+        };
+
+        let is_encrypted: bool = row.get("is_encrypted");
+        let mask = |value: Option<String>| {
+            if is_encrypted {
+                value.map(|_| "******".to_string())
+            } else {
+                value
+            }
+        };
+
+        Ok(PaymentConfigHistory {
+            id: Uuid::parse_str(row.get("id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            payment_method,
+            config_key: row.get("config_key"),
+            action: row.get("action"),
+            old_value: mask(row.get("old_value")),
+            new_value: mask(row.get("new_value")),
+            is_encrypted,
+            changed_by: Uuid::parse_str(row.get("changed_by"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            changed_at: row.get("changed_at"),
+        })
+    }
+
     fn parse_refund_row(row: sqlx::mysql::MySqlRow) -> Result<RefundRecord, AppError> {
         use sqlx::Row;
 
@@ -1528,6 +5183,7 @@ impl PaymentService {
                 .and_then(|s| Uuid::parse_str(&s).ok()),
             reviewed_at: row.get("reviewed_at"),
             review_notes: row.get("review_notes"),
+            failure_reason: row.get("failure_reason"),
             external_refund_id: row.get("external_refund_id"),
             refund_response: row.get("refund_response"),
             created_at: row.get("created_at"),
@@ -1535,4 +5191,46 @@ impl PaymentService {
             completed_at: row.get("completed_at"),
         })
     }
+
+    fn parse_withdrawal_row(row: sqlx::mysql::MySqlRow) -> Result<BalanceWithdrawal, AppError> {
+        use sqlx::Row;
+
+        let status_str: String = row.get("status");
+        let status = match status_str.as_str() {
+            "pending" => BalanceWithdrawalStatus::Pending,
+            "approved" => BalanceWithdrawalStatus::Approved,
+            "rejected" => BalanceWithdrawalStatus::Rejected,
+            _ => {
+                return Err(AppError::BadRequest(
+                    "Invalid withdrawal status".to_string(),
+                ))
+            }
+        };
+
+        let payout_method_str: String = row.get("payout_method");
+        let payout_method = match payout_method_str.as_str() {
+            "wechat" => PaymentMethod::Wechat,
+            "alipay" => PaymentMethod::Alipay,
+            _ => return Err(AppError::BadRequest("Invalid payout method".to_string())),
+        };
+
+        Ok(BalanceWithdrawal {
+            id: Uuid::parse_str(row.get("id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            withdrawal_no: row.get("withdrawal_no"),
+            user_id: Uuid::parse_str(row.get("user_id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            amount: row.get("amount"),
+            payout_method,
+            payout_account: row.get("payout_account"),
+            status,
+            reviewed_by: row
+                .get::<Option<String>, _>("reviewed_by")
+                .and_then(|s| Uuid::parse_str(&s).ok()),
+            reviewed_at: row.get("reviewed_at"),
+            review_notes: row.get("review_notes"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
 }
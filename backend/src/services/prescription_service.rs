@@ -1,6 +1,7 @@
 use crate::{
     config::database::DbPool,
     models::{doctor::Doctor, prescription::*},
+    services::patient_profile_service,
 };
 use anyhow::{anyhow, Result};
 use chrono::Utc;
@@ -17,7 +18,7 @@ pub async fn list_prescriptions(
 
     let mut query = String::from(
         r#"
-        SELECT id, code, doctor_id, patient_id, patient_name, diagnosis, 
+        SELECT id, code, doctor_id, patient_id, patient_profile_id, patient_name, diagnosis, 
                medicines, instructions, prescription_date, created_at
         FROM prescriptions
         WHERE 1=1
@@ -51,7 +52,7 @@ pub async fn list_prescriptions(
 
 pub async fn get_prescription_by_id(pool: &DbPool, id: Uuid) -> Result<Prescription> {
     let query = r#"
-        SELECT id, code, doctor_id, patient_id, patient_name, diagnosis, 
+        SELECT id, code, doctor_id, patient_id, patient_profile_id, patient_name, diagnosis, 
                medicines, instructions, prescription_date, created_at
         FROM prescriptions
         WHERE id = ?
@@ -68,7 +69,7 @@ pub async fn get_prescription_by_id(pool: &DbPool, id: Uuid) -> Result<Prescript
 
 pub async fn get_prescription_by_code(pool: &DbPool, code: &str) -> Result<Prescription> {
     let query = r#"
-        SELECT id, code, doctor_id, patient_id, patient_name, diagnosis, 
+        SELECT id, code, doctor_id, patient_id, patient_profile_id, patient_name, diagnosis, 
                medicines, instructions, prescription_date, created_at
         FROM prescriptions
         WHERE code = ?
@@ -85,17 +86,25 @@ pub async fn get_prescription_by_code(pool: &DbPool, code: &str) -> Result<Presc
 
 pub async fn create_prescription(
     pool: &DbPool,
-    dto: CreatePrescriptionDto,
+    mut dto: CreatePrescriptionDto,
 ) -> Result<Prescription> {
+    // Prescriptions for a family member are issued under their own name
+    if let Some(profile_id) = dto.patient_profile_id {
+        let profile = patient_profile_service::get_profile_by_id(pool, profile_id, dto.patient_id)
+            .await
+            .map_err(|_| anyhow!("Patient profile not found or access denied"))?;
+        dto.patient_name = profile.name;
+    }
+
     let prescription_id = Uuid::new_v4();
     let code = generate_prescription_code();
     let now = Utc::now();
     let medicines_json = serde_json::to_string(&dto.medicines)?;
 
     let query = r#"
-        INSERT INTO prescriptions (id, code, doctor_id, patient_id, patient_name, 
+        INSERT INTO prescriptions (id, code, doctor_id, patient_id, patient_profile_id, patient_name, 
                                  diagnosis, medicines, instructions, prescription_date, created_at)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
     "#;
 
     sqlx::query(query)
@@ -103,6 +112,7 @@ pub async fn create_prescription(
         .bind(&code)
         .bind(dto.doctor_id.to_string())
         .bind(dto.patient_id.to_string())
+        .bind(dto.patient_profile_id.map(|id| id.to_string()))
         .bind(&dto.patient_name)
         .bind(&dto.diagnosis)
         .bind(&medicines_json)
@@ -126,7 +136,7 @@ pub async fn get_doctor_prescriptions(
 
     let query = format!(
         r#"
-        SELECT id, code, doctor_id, patient_id, patient_name, diagnosis, 
+        SELECT id, code, doctor_id, patient_id, patient_profile_id, patient_name, diagnosis, 
                medicines, instructions, prescription_date, created_at
         FROM prescriptions
         WHERE doctor_id = '{}'
@@ -158,7 +168,7 @@ pub async fn get_patient_prescriptions(
 
     let query = format!(
         r#"
-        SELECT id, code, doctor_id, patient_id, patient_name, diagnosis, 
+        SELECT id, code, doctor_id, patient_id, patient_profile_id, patient_name, diagnosis, 
                medicines, instructions, prescription_date, created_at
         FROM prescriptions
         WHERE patient_id = '{}'
@@ -254,6 +264,9 @@ fn parse_prescription_row(row: sqlx::mysql::MySqlRow) -> Result<Prescription> {
         code: row.get("code"),
         doctor_id: Uuid::parse_str(row.get("doctor_id")).unwrap(),
         patient_id: Uuid::parse_str(row.get("patient_id")).unwrap(),
+        patient_profile_id: row
+            .get::<Option<String>, _>("patient_profile_id")
+            .and_then(|id| Uuid::parse_str(&id).ok()),
         patient_name: row.get("patient_name"),
         diagnosis: row.get("diagnosis"),
         medicines,
@@ -1,9 +1,11 @@
 use crate::config::database::DbPool;
 use crate::models::{
     CreateReviewDto, CreateTagDto, DoctorReviewStatistics, PatientReview, RatingDistribution,
-    ReplyReviewDto, ReviewDetail, ReviewTag, TagCategory, UpdateReviewDto,
+    ReplyReviewDto, ReviewDetail, ReviewTag, TagCategory, UnrepliedReviewSort, UpdateReviewDto,
     UpdateReviewVisibilityDto,
 };
+use crate::services::system_config_service::SystemConfigService;
+use crate::utils::content_filter::{filter_text, ContentKind};
 use anyhow::{anyhow, Result};
 use chrono::Utc;
 use sqlx::{MySql, Row, Transaction};
@@ -20,6 +22,18 @@ pub struct ReviewQueryParams {
     pub page_size: i64,
 }
 
+/// 含逗号、引号或换行的字段按 RFC 4180 加引号转义
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// 未配置 review.rating_prior_weight 时的先验评价数
+const DEFAULT_RATING_PRIOR_WEIGHT: f64 = 10.0;
+
 pub struct ReviewService;
 
 impl ReviewService {
@@ -28,26 +42,45 @@ impl ReviewService {
     pub async fn create_review(
         pool: &DbPool,
         patient_id: Uuid,
-        dto: CreateReviewDto,
+        mut dto: CreateReviewDto,
     ) -> Result<PatientReview> {
+        // 屏蔽词过滤
+        if let Some(comment) = dto.comment.take() {
+            dto.comment = Some(filter_text(pool, ContentKind::Review, &comment).await?);
+        }
+
         let mut tx = pool.begin().await?;
 
         // 验证预约是否存在且属于该患者
-        let appointment =
-            sqlx::query("SELECT patient_id, doctor_id, status FROM appointments WHERE id = ?")
-                .bind(dto.appointment_id.to_string())
-                .fetch_optional(&mut *tx)
-                .await?
-                .ok_or_else(|| anyhow!("Appointment not found"))?;
+        let appointment = sqlx::query(
+            r#"
+            SELECT a.patient_id, a.doctor_id, a.status, p.relationship AS profile_relationship
+            FROM appointments a
+            LEFT JOIN patient_profiles p ON p.id = a.patient_profile_id
+            WHERE a.id = ?
+            "#,
+        )
+        .bind(dto.appointment_id.to_string())
+        .fetch_optional(&mut *tx)
+        .await?
+        .ok_or_else(|| anyhow!("Appointment not found"))?;
 
         let appointment_patient_id: String = appointment.get("patient_id");
         let doctor_id: String = appointment.get("doctor_id");
         let status: String = appointment.get("status");
+        let profile_relationship: Option<String> = appointment.get("profile_relationship");
 
         if appointment_patient_id != patient_id.to_string() {
             return Err(anyhow!("You can only review your own appointments"));
         }
 
+        // 为家人代约的就诊不能由管理账号评价
+        if profile_relationship.is_some_and(|r| r != "self") {
+            return Err(anyhow!(
+                "Appointments booked for a family profile cannot be reviewed"
+            ));
+        }
+
         if status != "completed" {
             return Err(anyhow!("Can only review completed appointments"));
         }
@@ -219,6 +252,147 @@ impl ReviewService {
         Ok((reviews, total))
     }
 
+    /// 医生尚未回复的可见评价队列
+    pub async fn get_unreplied_reviews(
+        pool: &DbPool,
+        doctor_user_id: Uuid,
+        sort_by: UnrepliedReviewSort,
+        page: i64,
+        page_size: i64,
+    ) -> Result<(Vec<ReviewDetail>, i64)> {
+        let doctor_id: String = sqlx::query_scalar("SELECT id FROM doctors WHERE user_id = ?")
+            .bind(doctor_user_id.to_string())
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| anyhow!("Doctor not found"))?;
+
+        let total: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM patient_reviews
+            WHERE doctor_id = ? AND is_visible = TRUE AND reply IS NULL
+            "#,
+        )
+        .bind(&doctor_id)
+        .fetch_one(pool)
+        .await?;
+
+        let order_by = match sort_by {
+            UnrepliedReviewSort::Oldest => "pr.created_at ASC",
+            UnrepliedReviewSort::Newest => "pr.created_at DESC",
+            UnrepliedReviewSort::LowestRating => "pr.rating ASC, pr.created_at ASC",
+            UnrepliedReviewSort::HighestRating => "pr.rating DESC, pr.created_at ASC",
+        };
+
+        let query = format!(
+            r#"
+            SELECT pr.*,
+                   d.user_id as doctor_user_id,
+                   du.name as doctor_name,
+                   p.name as patient_name,
+                   a.appointment_date
+            FROM patient_reviews pr
+            JOIN doctors d ON pr.doctor_id = d.id
+            JOIN users du ON d.user_id = du.id
+            JOIN users p ON pr.patient_id = p.id
+            JOIN appointments a ON pr.appointment_id = a.id
+            WHERE pr.doctor_id = ? AND pr.is_visible = TRUE AND pr.reply IS NULL
+            ORDER BY {}, pr.id
+            LIMIT ? OFFSET ?
+            "#,
+            order_by
+        );
+
+        let rows = sqlx::query(&query)
+            .bind(&doctor_id)
+            .bind(page_size)
+            .bind((page - 1) * page_size)
+            .fetch_all(pool)
+            .await?;
+
+        let mut reviews = vec![];
+        for row in rows {
+            let review_id: String = row.get("id");
+            let tags = Self::get_review_tags(pool, Uuid::parse_str(&review_id)?).await?;
+            reviews.push(Self::parse_review_detail_row(&row, tags)?);
+        }
+
+        Ok((reviews, total))
+    }
+
+    /// 导出医生的可见评价为 CSV，仅医生本人或管理员可导出；匿名评价隐藏患者姓名
+    pub async fn export_doctor_reviews_csv(
+        pool: &DbPool,
+        doctor_id: Uuid,
+        requester_id: Uuid,
+        requester_role: &str,
+    ) -> Result<String> {
+        let doctor_user_id: String = sqlx::query_scalar("SELECT user_id FROM doctors WHERE id = ?")
+            .bind(doctor_id.to_string())
+            .fetch_optional(pool)
+            .await?
+            .ok_or_else(|| anyhow!("Doctor not found"))?;
+
+        if requester_role != "admin" && doctor_user_id != requester_id.to_string() {
+            return Err(anyhow!("Insufficient permissions"));
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT pr.*,
+                   d.user_id as doctor_user_id,
+                   du.name as doctor_name,
+                   p.name as patient_name,
+                   a.appointment_date
+            FROM patient_reviews pr
+            JOIN doctors d ON pr.doctor_id = d.id
+            JOIN users du ON d.user_id = du.id
+            JOIN users p ON pr.patient_id = p.id
+            JOIN appointments a ON pr.appointment_id = a.id
+            WHERE pr.doctor_id = ? AND pr.is_visible = TRUE AND pr.deleted_at IS NULL
+            ORDER BY pr.created_at DESC, pr.id
+            "#,
+        )
+        .bind(doctor_id.to_string())
+        .fetch_all(pool)
+        .await?;
+
+        let mut csv_data = String::from(
+            "Date,Patient,Rating,Attitude Rating,Professionalism Rating,Efficiency Rating,Comment,Tags,Reply,Reply At\n",
+        );
+
+        for row in rows {
+            let review_id: String = row.get("id");
+            let tags = Self::get_review_tags(pool, Uuid::parse_str(&review_id)?).await?;
+            let review = Self::parse_review_detail_row(&row, tags)?;
+
+            let tag_names = review
+                .tags
+                .iter()
+                .map(|tag| tag.name.as_str())
+                .collect::<Vec<_>>()
+                .join(";");
+
+            csv_data.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{},{}\n",
+                review.created_at.format("%Y-%m-%d %H:%M:%S"),
+                csv_field(&review.patient_name),
+                review.rating,
+                review.attitude_rating,
+                review.professionalism_rating,
+                review.efficiency_rating,
+                csv_field(review.comment.as_deref().unwrap_or_default()),
+                csv_field(&tag_names),
+                csv_field(review.reply.as_deref().unwrap_or_default()),
+                review
+                    .reply_at
+                    .map(|at| at.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default(),
+            ));
+        }
+
+        Ok(csv_data)
+    }
+
     pub async fn get_review_by_id(pool: &DbPool, id: Uuid) -> Result<PatientReview> {
         let row = sqlx::query(
             r#"
@@ -246,7 +420,7 @@ impl ReviewService {
             JOIN users du ON d.user_id = du.id
             JOIN users p ON pr.patient_id = p.id
             JOIN appointments a ON pr.appointment_id = a.id
-            WHERE pr.id = ?
+            WHERE pr.id = ? AND pr.deleted_at IS NULL
             "#,
         )
         .bind(id.to_string())
@@ -262,8 +436,12 @@ impl ReviewService {
         pool: &DbPool,
         id: Uuid,
         patient_id: Uuid,
-        dto: UpdateReviewDto,
+        mut dto: UpdateReviewDto,
     ) -> Result<PatientReview> {
+        if let Some(comment) = dto.comment.take() {
+            dto.comment = Some(filter_text(pool, ContentKind::Review, &comment).await?);
+        }
+
         let mut tx = pool.begin().await?;
 
         // 验证所有权
@@ -278,6 +456,15 @@ impl ReviewService {
             return Err(anyhow!("Reviews can only be updated within 24 hours"));
         }
 
+        // 医生已回复的评价默认锁定，避免修改后的内容与回复对不上
+        if review.reply.is_some()
+            && SystemConfigService::get_bool(pool, "review", "lock_after_reply", true).await?
+        {
+            return Err(anyhow!(
+                "Reviews cannot be updated after the doctor has replied"
+            ));
+        }
+
         // 构建动态更新查询
         let mut updates = vec![];
 
@@ -360,6 +547,45 @@ impl ReviewService {
         Self::get_review_by_id(pool, id).await
     }
 
+    pub async fn delete_review(pool: &DbPool, id: Uuid, patient_id: Uuid) -> Result<()> {
+        let review = Self::get_review_by_id(pool, id).await?;
+        if review.patient_id != patient_id {
+            return Err(anyhow!("You can only delete your own reviews"));
+        }
+
+        // 与修改一致，只能在24小时内删除
+        let hours_since_creation = (Utc::now() - review.created_at).num_hours();
+        if hours_since_creation > 24 {
+            return Err(anyhow!("Reviews can only be deleted within 24 hours"));
+        }
+
+        Self::soft_delete_review(pool, &review).await
+    }
+
+    pub async fn admin_delete_review(pool: &DbPool, id: Uuid) -> Result<()> {
+        let review = Self::get_review_by_id(pool, id).await?;
+        Self::soft_delete_review(pool, &review).await
+    }
+
+    async fn soft_delete_review(pool: &DbPool, review: &PatientReview) -> Result<()> {
+        let mut tx = pool.begin().await?;
+
+        // 软删除：隐藏评价并记录删除时间，统计只计入可见评价
+        sqlx::query(
+            "UPDATE patient_reviews SET is_visible = FALSE, deleted_at = CURRENT_TIMESTAMP WHERE id = ?",
+        )
+        .bind(review.id.to_string())
+        .execute(&mut *tx)
+        .await?;
+
+        // 更新医生统计
+        Self::update_doctor_statistics(&mut tx, review.doctor_id).await?;
+
+        tx.commit().await?;
+
+        Ok(())
+    }
+
     pub async fn reply_to_review(
         pool: &DbPool,
         id: Uuid,
@@ -592,22 +818,49 @@ impl ReviewService {
         .fetch_one(&mut **tx)
         .await?;
 
+        let total = stats.try_get::<i64, _>("total")?;
+        let average_rating = stats
+            .try_get::<sqlx::types::Decimal, _>("avg_rating")?
+            .to_string()
+            .parse::<f64>()
+            .unwrap_or(0.0);
+
+        // 平台平均分和先验评价数，用于计算排序用的加权评分
+        let platform_mean: sqlx::types::Decimal = sqlx::query_scalar(
+            "SELECT COALESCE(AVG(rating), 0) FROM patient_reviews WHERE is_visible = TRUE",
+        )
+        .fetch_one(&mut **tx)
+        .await?;
+        let prior_weight: Option<String> = sqlx::query_scalar(
+            "SELECT config_value FROM system_configs WHERE category = 'review' AND config_key = 'rating_prior_weight'",
+        )
+        .fetch_optional(&mut **tx)
+        .await?;
+
+        let weighted_rating = Self::bayesian_rating(
+            average_rating,
+            total,
+            platform_mean.to_string().parse::<f64>().unwrap_or(0.0),
+            prior_weight
+                .and_then(|v| v.trim().parse::<f64>().ok())
+                .unwrap_or(DEFAULT_RATING_PRIOR_WEIGHT),
+        );
+
         sqlx::query(
             r#"
             UPDATE doctors 
             SET total_reviews = ?,
                 average_rating = ?,
+                weighted_rating = ?,
                 average_attitude = ?,
                 average_professionalism = ?,
                 average_efficiency = ?
             WHERE id = ?
             "#,
         )
-        .bind(stats.try_get::<i64, _>("total")?)
-        .bind(stats.try_get::<sqlx::types::Decimal, _>("avg_rating")?
-            .to_string()
-            .parse::<f64>()
-            .unwrap_or(0.0))
+        .bind(total)
+        .bind(average_rating)
+        .bind(weighted_rating)
         .bind(stats.try_get::<sqlx::types::Decimal, _>("avg_attitude")?
             .to_string()
             .parse::<f64>()
@@ -627,6 +880,23 @@ impl ReviewService {
         Ok(())
     }
 
+    /// 贝叶斯加权评分 (v·R + m·C) / (v + m)：v 为评价数，R 为医生平均分，
+    /// m 为先验评价数，C 为平台平均分。没有评价时为 0
+    pub fn bayesian_rating(
+        average_rating: f64,
+        review_count: i64,
+        platform_mean: f64,
+        prior_weight: f64,
+    ) -> f64 {
+        if review_count <= 0 {
+            return 0.0;
+        }
+
+        let count = review_count as f64;
+        let prior_weight = prior_weight.max(0.0);
+        (count * average_rating + prior_weight * platform_mean) / (count + prior_weight)
+    }
+
     fn parse_review_row(row: &sqlx::mysql::MySqlRow) -> Result<PatientReview> {
         let id_str: String = row.get("id");
         let appointment_id_str: String = row.get("appointment_id");
@@ -0,0 +1,346 @@
+use crate::config::database::DbPool;
+use crate::config::redis::RedisPool;
+use crate::services::content_service;
+use crate::services::file_upload_service::FileUploadService;
+use crate::services::job_lock_service::JobLockService;
+use crate::services::notification_service::NotificationService;
+use crate::services::payment_gateway::PaymentGateway;
+use crate::services::payment_service::PaymentService;
+use crate::services::system_config_service::SystemConfigService;
+use crate::services::video_consultation_service::VideoConsultationService;
+use crate::services::websocket_service::WebSocketManager;
+use crate::utils::errors::AppError;
+use aws_sdk_s3::Client as S3Client;
+use chrono::{DateTime, Days, FixedOffset, NaiveTime, Utc};
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::MissedTickBehavior;
+
+pub struct SchedulerService;
+
+impl SchedulerService {
+    /// 数据清理任务的默认运行间隔，可由 `cleanup.interval_seconds` 配置覆盖
+    const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+    /// 文章浏览量缓冲写回数据库的间隔
+    const ARTICLE_VIEW_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+    /// 任务锁的有效期，须大于任务的预期运行时间；运行中由心跳续期，
+    /// 持有者崩溃后锁在到期后自动失效
+    pub const LOCK_TTL: Duration = Duration::from_secs(60);
+
+    /// 每日对账的运行时间（北京时间），渠道通常在次日 9 点后生成前一天的账单
+    const RECONCILIATION_HOUR: u32 = 10;
+
+    /// 对账每天运行一次
+    const RECONCILIATION_PERIOD: Duration = Duration::from_secs(24 * 60 * 60);
+
+    /// 启动所有后台定时任务，`order_expiry_interval` 为未支付订单过期检查的间隔，
+    /// `s3_client` 用于清除已删除文件在对象存储中的对象，`payment_gateway` 用于下载对账单
+    pub fn start(
+        pool: DbPool,
+        redis: Option<RedisPool>,
+        ws_manager: Arc<WebSocketManager>,
+        order_expiry_interval: Duration,
+        s3_client: Option<S3Client>,
+        payment_gateway: Arc<dyn PaymentGateway>,
+    ) {
+        // 视频问诊开始前提醒
+        {
+            let job_pool = pool.clone();
+            let ws_manager = ws_manager.clone();
+            Self::spawn_job(
+                "consultation_reminders",
+                Duration::from_secs(60),
+                pool.clone(),
+                redis.clone(),
+                move || {
+                    let pool = job_pool.clone();
+                    let ws_manager = ws_manager.clone();
+                    async move {
+                        VideoConsultationService::send_consultation_reminders(&pool, &ws_manager)
+                            .await
+                    }
+                },
+            );
+        }
+
+        // 进行中的视频问诊超过预计时长时提醒医生
+        {
+            let job_pool = pool.clone();
+            let ws_manager = ws_manager.clone();
+            Self::spawn_job(
+                "consultation_overtime",
+                Duration::from_secs(60),
+                pool.clone(),
+                redis.clone(),
+                move || {
+                    let pool = job_pool.clone();
+                    let ws_manager = ws_manager.clone();
+                    async move {
+                        VideoConsultationService::flag_overtime_consultations(&pool, &ws_manager)
+                            .await
+                    }
+                },
+            );
+        }
+
+        // 超过预约时间仍未开始的视频问诊标记为爽约
+        {
+            let job_pool = pool.clone();
+            Self::spawn_job(
+                "consultation_no_show",
+                Duration::from_secs(60),
+                pool.clone(),
+                redis.clone(),
+                move || {
+                    let pool = job_pool.clone();
+                    async move { VideoConsultationService::mark_no_shows(&pool).await }
+                },
+            );
+        }
+
+        // 超过支付期限的订单置为已过期，释放关联预约
+        {
+            let job_pool = pool.clone();
+            Self::spawn_job(
+                "order_expiry",
+                order_expiry_interval,
+                pool.clone(),
+                redis.clone(),
+                move || {
+                    let pool = job_pool.clone();
+                    async move { PaymentService::expire_stale_orders(&pool).await }
+                },
+            );
+        }
+
+        // 每天对账前一天的渠道支付，差异记入对账报告
+        {
+            let pool = pool.clone();
+            let redis = redis.clone();
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(Self::until_next_reconciliation(Utc::now())).await;
+
+                    let beijing = FixedOffset::east_opt(8 * 3600).expect("valid offset");
+                    let Some(bill_date) = Utc::now()
+                        .with_timezone(&beijing)
+                        .date_naive()
+                        .checked_sub_days(Days::new(1))
+                    else {
+                        continue;
+                    };
+
+                    let result = Self::run_exclusive(
+                        &pool,
+                        &redis,
+                        "payment_reconciliation",
+                        Self::RECONCILIATION_PERIOD,
+                        &|| async {
+                            PaymentService::reconcile(&pool, payment_gateway.as_ref(), bill_date)
+                                .await
+                                .map(|report| report.discrepancy_count())
+                        },
+                    )
+                    .await;
+                    Self::log_result("payment_reconciliation", result);
+                }
+            });
+        }
+
+        // 定时发布到期的文章、视频
+        {
+            let job_pool = pool.clone();
+            Self::spawn_job(
+                "content_scheduled_publish",
+                Duration::from_secs(60),
+                pool.clone(),
+                redis.clone(),
+                move || {
+                    let pool = job_pool.clone();
+                    async move {
+                        content_service::publish_scheduled(&pool)
+                            .await
+                            .map_err(|e| AppError::DatabaseError(e.to_string()))
+                    }
+                },
+            );
+        }
+
+        // 文章浏览量缓冲在各实例内存中，每个实例都要写回自己的计数，因此不经任务锁
+        {
+            let pool = pool.clone();
+            tokio::spawn(async move {
+                let mut interval = tokio::time::interval(Self::ARTICLE_VIEW_FLUSH_INTERVAL);
+                interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+                loop {
+                    interval.tick().await;
+
+                    let result = content_service::flush_article_views(&pool)
+                        .await
+                        .map(Some)
+                        .map_err(|e| AppError::DatabaseError(e.to_string()));
+                    Self::log_result("article_view_flush", result);
+                }
+            });
+        }
+
+        // 数据清理任务共用一个循环依次运行，避免同时对多张表做大批量删除
+        tokio::spawn(async move {
+            loop {
+                let interval = SystemConfigService::get_i64(
+                    &pool,
+                    "cleanup",
+                    "interval_seconds",
+                    Self::CLEANUP_INTERVAL.as_secs() as i64,
+                )
+                .await
+                .unwrap_or(Self::CLEANUP_INTERVAL.as_secs() as i64);
+                let interval = Duration::from_secs(interval.max(60) as u64);
+
+                Self::run_cleanup_round(&pool, &redis, s3_client.as_ref(), interval).await;
+                tokio::time::sleep(interval).await;
+            }
+        });
+    }
+
+    /// 依次运行一轮所有数据清理任务，每个任务各自持锁，一个失败不影响后续任务；
+    /// `period` 为清理间隔，同一间隔内每个任务只在一个实例上运行
+    pub async fn run_cleanup_round(
+        pool: &DbPool,
+        redis: &Option<RedisPool>,
+        s3_client: Option<&S3Client>,
+        period: Duration,
+    ) {
+        let result = Self::run_exclusive(pool, redis, "notification_cleanup", period, &|| {
+            NotificationService::clean_old_notifications(pool)
+        })
+        .await;
+        Self::log_result("notification_cleanup", result);
+
+        let result = Self::run_exclusive(pool, redis, "signal_cleanup", period, &|| {
+            VideoConsultationService::clean_expired_signals(pool)
+        })
+        .await;
+        Self::log_result("signal_cleanup", result);
+
+        let result = Self::run_exclusive(pool, redis, "upload_cleanup", period, &|| {
+            FileUploadService::clean_expired_uploads(pool, s3_client)
+        })
+        .await;
+        Self::log_result("upload_cleanup", result);
+
+        let result = Self::run_exclusive(pool, redis, "deleted_file_cleanup", period, &|| {
+            FileUploadService::clean_deleted_files(pool, s3_client)
+        })
+        .await;
+        Self::log_result("deleted_file_cleanup", result);
+    }
+
+    /// 距离下一次每日对账（北京时间 `RECONCILIATION_HOUR` 点）的等待时间
+    pub fn until_next_reconciliation(now: DateTime<Utc>) -> Duration {
+        let beijing = FixedOffset::east_opt(8 * 3600).expect("valid offset");
+        let local_now = now.with_timezone(&beijing);
+        let run_time =
+            NaiveTime::from_hms_opt(Self::RECONCILIATION_HOUR, 0, 0).expect("valid time");
+
+        let mut next_run = local_now.date_naive().and_time(run_time);
+        if next_run <= local_now.naive_local() {
+            next_run += chrono::Duration::days(1);
+        }
+
+        (next_run - local_now.naive_local())
+            .to_std()
+            .unwrap_or_default()
+    }
+
+    /// 以固定间隔运行任务，任务返回本次处理的记录数
+    fn spawn_job<F, Fut>(
+        name: &'static str,
+        period: Duration,
+        pool: DbPool,
+        redis: Option<RedisPool>,
+        job: F,
+    ) where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<u64, AppError>> + Send + 'static,
+    {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+            loop {
+                interval.tick().await;
+
+                let result = Self::run_exclusive(&pool, &redis, name, period, &job).await;
+                Self::log_result(name, result);
+            }
+        });
+    }
+
+    fn log_result(name: &str, result: Result<Option<u64>, AppError>) {
+        match result {
+            Ok(None) => {
+                tracing::info!(
+                    "Background job {} skipped: lock held by another instance",
+                    name
+                )
+            }
+            Ok(Some(0)) => {}
+            Ok(Some(count)) => {
+                tracing::info!("Background job {} processed {} records", name, count)
+            }
+            Err(e) => tracing::error!("Background job {} failed: {}", name, e),
+        }
+    }
+
+    /// 每个周期只在一个实例上运行一次任务：先占用本周期的标记 `job:{name}:{周期序号}`，
+    /// 标记不主动释放，到期前其他实例在同一周期内的触发都会跳过；运行期间另持有
+    /// `job:{name}` 锁，防止上一周期尚未结束的任务与本次重叠。未获得运行权时返回 None
+    pub async fn run_exclusive<F, Fut>(
+        pool: &DbPool,
+        redis: &Option<RedisPool>,
+        name: &str,
+        period: Duration,
+        job: &F,
+    ) -> Result<Option<u64>, AppError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<u64, AppError>>,
+    {
+        let key = format!("job:{}", name);
+
+        let period_ms = period.as_millis().max(1) as i64;
+        let period_key = format!("{}:{}", key, Utc::now().timestamp_millis() / period_ms);
+        if JobLockService::acquire(pool, redis, &period_key, period)
+            .await?
+            .is_none()
+        {
+            return Ok(None);
+        }
+
+        let lock = match JobLockService::acquire(pool, redis, &key, Self::LOCK_TTL).await? {
+            Some(lock) => lock,
+            None => return Ok(None),
+        };
+
+        let heartbeat = JobLockService::spawn_heartbeat(
+            pool.clone(),
+            redis.clone(),
+            lock.clone(),
+            Self::LOCK_TTL,
+        );
+
+        let result = job().await;
+
+        heartbeat.abort();
+        if let Err(e) = JobLockService::release(pool, redis, &lock).await {
+            tracing::warn!("Failed to release job lock {}: {}", key, e);
+        }
+
+        result.map(Some)
+    }
+}
@@ -176,6 +176,107 @@ impl StatisticsService {
             .collect())
     }
 
+    /// 分页查询审计日志，支持按操作人、操作类型、对象类型和时间范围过滤
+    pub async fn list_audit_logs(
+        pool: &DbPool,
+        query: AuditLogQuery,
+    ) -> Result<AuditLogListResponse, sqlx::Error> {
+        let page = query.page.unwrap_or(1).max(1);
+        let page_size = query.page_size.unwrap_or(20).clamp(1, 100);
+        let offset = (page - 1) * page_size;
+
+        let mut where_clauses = vec![];
+        if query.actor_id.is_some() {
+            where_clauses.push("a.actor_id = ?");
+        }
+        if query.action.is_some() {
+            where_clauses.push("a.action = ?");
+        }
+        if query.target_type.is_some() {
+            where_clauses.push("a.target_type = ?");
+        }
+        if query.start_date.is_some() {
+            where_clauses.push("a.created_at >= ?");
+        }
+        if query.end_date.is_some() {
+            where_clauses.push("a.created_at <= ?");
+        }
+
+        let where_clause = if where_clauses.is_empty() {
+            String::new()
+        } else {
+            format!("WHERE {}", where_clauses.join(" AND "))
+        };
+
+        let count_query = format!("SELECT COUNT(*) FROM audit_logs a {}", where_clause);
+        let list_query = format!(
+            r#"
+            SELECT a.id, a.actor_id, u.name as actor_name, a.action, a.target_type,
+                   a.target_id, a.details, a.created_at
+            FROM audit_logs a
+            LEFT JOIN users u ON u.id = a.actor_id
+            {}
+            ORDER BY a.created_at DESC, a.id
+            LIMIT ? OFFSET ?
+            "#,
+            where_clause
+        );
+
+        let mut count_builder = sqlx::query_scalar::<_, i64>(&count_query);
+        let mut list_builder = sqlx::query(&list_query);
+
+        // Bind filters in the same order as the WHERE clauses
+        if let Some(actor_id) = &query.actor_id {
+            count_builder = count_builder.bind(actor_id.to_string());
+            list_builder = list_builder.bind(actor_id.to_string());
+        }
+        if let Some(action) = &query.action {
+            count_builder = count_builder.bind(action);
+            list_builder = list_builder.bind(action);
+        }
+        if let Some(target_type) = &query.target_type {
+            count_builder = count_builder.bind(target_type);
+            list_builder = list_builder.bind(target_type);
+        }
+        if let Some(start_date) = &query.start_date {
+            count_builder = count_builder.bind(start_date);
+            list_builder = list_builder.bind(start_date);
+        }
+        if let Some(end_date) = &query.end_date {
+            count_builder = count_builder.bind(end_date);
+            list_builder = list_builder.bind(end_date);
+        }
+
+        let total = count_builder.fetch_one(pool).await?;
+        let rows = list_builder
+            .bind(page_size)
+            .bind(offset)
+            .fetch_all(pool)
+            .await?;
+
+        use sqlx::Row;
+        let logs = rows
+            .into_iter()
+            .map(|row| AuditLogEntry {
+                id: Uuid::parse_str(row.get("id")).unwrap(),
+                actor_id: Uuid::parse_str(row.get("actor_id")).unwrap(),
+                actor_name: row.get("actor_name"),
+                action: row.get("action"),
+                target_type: row.get("target_type"),
+                target_id: Uuid::parse_str(row.get("target_id")).unwrap(),
+                details: row.get("details"),
+                created_at: row.get("created_at"),
+            })
+            .collect();
+
+        Ok(AuditLogListResponse {
+            logs,
+            total,
+            page,
+            page_size,
+        })
+    }
+
     /// 获取科室统计数据
     pub async fn get_department_stats(pool: &DbPool) -> Result<Vec<DepartmentStats>, sqlx::Error> {
         let query = r#"
@@ -0,0 +1,152 @@
+use crate::config::database::DbPool;
+use crate::utils::errors::AppError;
+use sqlx::Row;
+use std::collections::HashMap;
+
+pub struct SystemConfigService;
+
+/// 允许客户端读取的配置项白名单，新增项需确认不含密钥、内部策略等敏感信息
+const PUBLIC_CONFIGS: &[(&str, &str)] = &[
+    ("file_upload", "max_file_size"),
+    ("file_upload", "allowed_mime_types"),
+    ("file_upload", "max_image_size"),
+    ("file_upload", "max_video_size"),
+    ("file_upload", "allowed_image_types"),
+    ("file_upload", "allowed_video_types"),
+    ("file_upload", "enable_image_watermark"),
+    ("video_call", "max_duration"),
+    ("video_call", "recording_enabled"),
+    ("video_call", "video_codec"),
+    ("video_call", "audio_codec"),
+    ("video_call", "max_video_bitrate"),
+    ("video_call", "max_audio_bitrate"),
+    ("video_call", "require_questionnaire_before_start"),
+    ("order_amount", "appointment_min"),
+    ("order_amount", "appointment_max"),
+    ("order_amount", "consultation_min"),
+    ("order_amount", "consultation_max"),
+    ("order_amount", "prescription_min"),
+    ("order_amount", "prescription_max"),
+    ("order_amount", "other_min"),
+    ("order_amount", "other_max"),
+    ("appointment_price", "online_video"),
+    ("appointment_price", "offline"),
+    ("refund", "window_days"),
+];
+
+impl SystemConfigService {
+    /// 读取某一分类下的全部配置项
+    pub async fn get_category(
+        db: &DbPool,
+        category: &str,
+    ) -> Result<HashMap<String, String>, AppError> {
+        let query = r#"
+            SELECT config_key, config_value
+            FROM system_configs
+            WHERE category = ?
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(category)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut configs = HashMap::new();
+        for row in rows {
+            let key: String = row.get("config_key");
+            let value: String = row.get("config_value");
+            configs.insert(key, value);
+        }
+
+        Ok(configs)
+    }
+
+    /// 读取单个配置项，不存在时返回 None
+    pub async fn get_value(
+        db: &DbPool,
+        category: &str,
+        key: &str,
+    ) -> Result<Option<String>, AppError> {
+        let query = r#"
+            SELECT config_value
+            FROM system_configs
+            WHERE category = ? AND config_key = ?
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(category)
+            .bind(key)
+            .fetch_optional(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(row.map(|r| r.get("config_value")))
+    }
+
+    /// 读取数值配置，缺失或无法解析时使用默认值
+    pub async fn get_i64(
+        db: &DbPool,
+        category: &str,
+        key: &str,
+        default: i64,
+    ) -> Result<i64, AppError> {
+        Ok(Self::get_value(db, category, key)
+            .await?
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(default))
+    }
+
+    /// 客户端可见的配置，按分类分组并按 value_type 转换为 JSON 值。
+    /// 只返回白名单内且未加密的配置项。
+    pub async fn get_public_configs(
+        db: &DbPool,
+    ) -> Result<HashMap<String, HashMap<String, serde_json::Value>>, AppError> {
+        let query = r#"
+            SELECT category, config_key, config_value, value_type
+            FROM system_configs
+            WHERE is_encrypted IS NOT TRUE
+        "#;
+
+        let rows = sqlx::query(query)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut configs: HashMap<String, HashMap<String, serde_json::Value>> = HashMap::new();
+        for row in rows {
+            let category: String = row.get("category");
+            let key: String = row.get("config_key");
+            if !PUBLIC_CONFIGS.contains(&(category.as_str(), key.as_str())) {
+                continue;
+            }
+
+            let raw: String = row.get("config_value");
+            let value_type: String = row.get("value_type");
+            let value = match value_type.as_str() {
+                // 未设置（空值）或无法解析的配置返回 null
+                "number" | "boolean" | "json" => {
+                    serde_json::from_str(raw.trim()).unwrap_or(serde_json::Value::Null)
+                }
+                _ => serde_json::Value::String(raw),
+            };
+
+            configs.entry(category).or_default().insert(key, value);
+        }
+
+        Ok(configs)
+    }
+
+    /// 读取布尔配置，缺失或无法解析时使用默认值
+    pub async fn get_bool(
+        db: &DbPool,
+        category: &str,
+        key: &str,
+        default: bool,
+    ) -> Result<bool, AppError> {
+        Ok(Self::get_value(db, category, key)
+            .await?
+            .and_then(|v| v.trim().parse().ok())
+            .unwrap_or(default))
+    }
+}
@@ -1,4 +1,6 @@
-use crate::{config::database::DbPool, models::user::*};
+use crate::{
+    config::database::DbPool, models::user::*, services::notification_service::NotificationService,
+};
 use anyhow::{anyhow, Result};
 use chrono::Utc;
 use uuid::Uuid;
@@ -144,9 +146,23 @@ pub async fn create_user(pool: &DbPool, dto: CreateUserDto) -> Result<User> {
         .await
         .map_err(|e| anyhow!("Failed to create user: {}", e))?;
 
+    on_user_registered(pool, user_id).await;
+
     get_user_by_id(pool, user_id).await
 }
 
+/// 新用户创建后的初始化：按默认模板写入通知设置。
+/// 失败时只记录日志，缺失的设置在发送通知时仍会回退到同一模板
+pub async fn on_user_registered(pool: &DbPool, user_id: Uuid) {
+    if let Err(e) = NotificationService::seed_default_settings(pool, user_id).await {
+        tracing::warn!(
+            "Failed to seed notification settings for user {}: {}",
+            user_id,
+            e
+        );
+    }
+}
+
 pub async fn update_user(pool: &DbPool, id: Uuid, dto: UpdateUserDto) -> Result<User> {
     let mut update_fields = Vec::new();
     let mut bindings = Vec::new();
@@ -1,11 +1,26 @@
 use crate::config::database::DbPool;
 use crate::models::appointment::{Appointment, AppointmentStatus, VisitType};
+use crate::models::notification::{CreateNotificationDto, NotificationType};
 use crate::models::video_consultation::*;
+use crate::services::cleanup_service::{CleanupService, SweepSettings};
+use crate::services::notification_service::NotificationService;
+use crate::services::payment_service::PaymentService;
+use crate::services::system_config_service::SystemConfigService;
+use crate::services::websocket_service::{WebSocketManager, WsMessage};
 use crate::utils::errors::AppError;
+use crate::utils::jwt::{self, RoomClaims};
 use chrono::{DateTime, Duration, Utc};
+use rand::Rng;
+use sha2::{Digest, Sha256};
 use sqlx::{MySql, Transaction};
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+const ROOM_CODE_LENGTH: usize = 6;
+const ROOM_CODE_MAX_ATTEMPTS: usize = 5;
+/// 房间令牌有效期（秒）
+const ROOM_TOKEN_TTL_SECONDS: i64 = 2 * 60 * 60;
+
 pub struct VideoConsultationService;
 
 impl VideoConsultationService {
@@ -20,25 +35,30 @@ impl VideoConsultationService {
             return Err(AppError::BadRequest("预约未确认".to_string()));
         }
 
+        let expected_duration = Self::expected_duration_minutes(db, dto.doctor_id).await?;
+
         let consultation_id = Uuid::new_v4();
         let room_id = format!("room_{}", Uuid::new_v4().to_string().replace("-", ""));
         let now = Utc::now();
 
         let query = r#"
             INSERT INTO video_consultations (
-                id, appointment_id, doctor_id, patient_id, room_id,
-                status, scheduled_start_time, chief_complaint,
+                id, appointment_id, doctor_id, patient_id, patient_profile_id, room_id,
+                status, scheduled_start_time, expected_duration, chief_complaint,
                 created_at, updated_at
-            ) VALUES (?, ?, ?, ?, ?, 'waiting', ?, ?, ?, ?)
+            ) VALUES (?, ?, ?, ?, ?, ?, 'waiting', ?, ?, ?, ?, ?)
         "#;
 
+        // The visit is for whoever the appointment was booked for
         sqlx::query(query)
             .bind(consultation_id.to_string())
             .bind(dto.appointment_id.to_string())
             .bind(dto.doctor_id.to_string())
             .bind(dto.patient_id.to_string())
+            .bind(appointment.patient_profile_id.map(|id| id.to_string()))
             .bind(&room_id)
             .bind(dto.scheduled_start_time)
+            .bind(expected_duration)
             .bind(&dto.chief_complaint)
             .bind(now)
             .bind(now)
@@ -46,9 +66,88 @@ impl VideoConsultationService {
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
+        Self::assign_room_code(db, consultation_id, Self::generate_room_code).await?;
+
         Self::get_consultation(db, consultation_id).await
     }
 
+    /// 医生设置的问诊预计时长，未设置时使用系统默认值
+    async fn expected_duration_minutes(db: &DbPool, doctor_id: Uuid) -> Result<i32, AppError> {
+        let doctor_minutes: Option<i32> =
+            sqlx::query_scalar("SELECT consultation_duration_minutes FROM doctors WHERE id = ?")
+                .bind(doctor_id.to_string())
+                .fetch_optional(db)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?
+                .flatten();
+
+        match doctor_minutes {
+            Some(minutes) => Ok(minutes),
+            None => Ok(SystemConfigService::get_i64(
+                db,
+                "video_call",
+                "default_consultation_minutes",
+                30,
+            )
+            .await? as i32),
+        }
+    }
+
+    /// 为问诊分配短房间码，与进行中房间的房间码冲突时重新生成
+    pub async fn assign_room_code<F>(
+        db: &DbPool,
+        consultation_id: Uuid,
+        mut generate: F,
+    ) -> Result<String, AppError>
+    where
+        F: FnMut() -> String,
+    {
+        for _ in 0..ROOM_CODE_MAX_ATTEMPTS {
+            let code = generate();
+
+            let result = sqlx::query(
+                "UPDATE video_consultations SET room_code = ?, updated_at = ? WHERE id = ?",
+            )
+            .bind(&code)
+            .bind(Utc::now())
+            .bind(consultation_id.to_string())
+            .execute(db)
+            .await;
+
+            match result {
+                Ok(_) => return Ok(code),
+                Err(e) if e.to_string().contains("Duplicate entry") => continue,
+                Err(e) => return Err(AppError::DatabaseError(e.to_string())),
+            }
+        }
+
+        Err(AppError::InternalServerError(
+            "无法生成唯一的房间码".to_string(),
+        ))
+    }
+
+    /// 通过短房间码查找进行中的问诊，仅限问诊双方和管理员
+    pub async fn get_consultation_by_room_code(
+        db: &DbPool,
+        room_code: &str,
+    ) -> Result<VideoConsultation, AppError> {
+        let query = r#"
+            SELECT * FROM video_consultations
+            WHERE room_code = ? AND status IN ('waiting', 'in_progress')
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(room_code.trim().to_uppercase())
+            .fetch_one(db)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => AppError::NotFound("房间码无效或已过期".to_string()),
+                _ => AppError::DatabaseError(e.to_string()),
+            })?;
+
+        Self::parse_consultation_row(row)
+    }
+
     pub async fn get_consultation(
         db: &DbPool,
         consultation_id: Uuid,
@@ -89,6 +188,149 @@ impl VideoConsultationService {
         Self::parse_consultation_row(row)
     }
 
+    /// 按预约查找对应的视频问诊（不含已取消的），仅限预约的患者、医生和管理员
+    pub async fn get_consultation_by_appointment(
+        db: &DbPool,
+        appointment_id: Uuid,
+        requester_id: Uuid,
+        is_admin: bool,
+    ) -> Result<VideoConsultation, AppError> {
+        let appointment = Self::get_appointment(db, appointment_id).await?;
+
+        if !is_admin && requester_id != appointment.patient_id {
+            let doctor_user_id = Self::get_doctor_user_id(db, appointment.doctor_id).await?;
+            if Some(requester_id) != doctor_user_id {
+                return Err(AppError::Forbidden);
+            }
+        }
+
+        let query = r#"
+            SELECT * FROM video_consultations
+            WHERE appointment_id = ? AND status != 'cancelled'
+            ORDER BY created_at DESC
+            LIMIT 1
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(appointment_id.to_string())
+            .fetch_one(db)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => AppError::NotFound("该预约暂无视频问诊".to_string()),
+                _ => AppError::DatabaseError(e.to_string()),
+            })?;
+
+        Self::parse_consultation_row(row)
+    }
+
+    /// 医生的待办问诊，按预约时间先后排列
+    pub async fn get_doctor_action_items(
+        db: &DbPool,
+        doctor_id: Uuid,
+    ) -> Result<DoctorActionItems, AppError> {
+        let waiting_query = r#"
+            SELECT * FROM video_consultations
+            WHERE doctor_id = ? AND status = 'waiting'
+            ORDER BY scheduled_start_time ASC
+        "#;
+
+        let waiting = sqlx::query(waiting_query)
+            .bind(doctor_id.to_string())
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .map(Self::parse_consultation_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let missing_query = r#"
+            SELECT * FROM video_consultations
+            WHERE doctor_id = ? AND status = 'completed'
+              AND (diagnosis IS NULL OR diagnosis = ''
+                   OR treatment_plan IS NULL OR treatment_plan = '')
+            ORDER BY scheduled_start_time ASC
+        "#;
+
+        let missing_records = sqlx::query(missing_query)
+            .bind(doctor_id.to_string())
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?
+            .into_iter()
+            .map(Self::parse_consultation_row)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(DoctorActionItems {
+            waiting,
+            missing_records,
+        })
+    }
+
+    /// 按患者汇总医生的问诊记录（取消的不计），最近就诊的患者排在前面
+    pub async fn get_patient_consultation_summary(
+        db: &DbPool,
+        doctor_id: Uuid,
+        page: i64,
+        page_size: i64,
+    ) -> Result<(Vec<PatientConsultationSummary>, i64), AppError> {
+        let offset = (page - 1) * page_size;
+
+        let total: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(DISTINCT patient_id) FROM video_consultations
+            WHERE doctor_id = ? AND status != 'cancelled'
+            "#,
+        )
+        .bind(doctor_id.to_string())
+        .fetch_one(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let query = r#"
+            SELECT
+                vc.patient_id,
+                u.name as patient_name,
+                COUNT(*) as consultation_count,
+                COUNT(CASE WHEN vc.status = 'completed' THEN 1 END) as completed_count,
+                MAX(CASE WHEN vc.status = 'completed'
+                         THEN COALESCE(vc.actual_start_time, vc.scheduled_start_time) END) as last_visit_at,
+                CAST(AVG(vc.patient_rating) AS DOUBLE) as average_rating
+            FROM video_consultations vc
+            LEFT JOIN users u ON u.id = vc.patient_id
+            WHERE vc.doctor_id = ? AND vc.status != 'cancelled'
+            GROUP BY vc.patient_id, u.name
+            ORDER BY last_visit_at IS NULL, last_visit_at DESC, vc.patient_id
+            LIMIT ? OFFSET ?
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(doctor_id.to_string())
+            .bind(page_size)
+            .bind(offset)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        use sqlx::Row;
+        let items = rows
+            .into_iter()
+            .map(|row| {
+                let patient_id: String = row.get("patient_id");
+                Ok(PatientConsultationSummary {
+                    patient_id: Uuid::parse_str(&patient_id)
+                        .map_err(|e| AppError::InternalServerError(e.to_string()))?,
+                    patient_name: row.get("patient_name"),
+                    consultation_count: row.get("consultation_count"),
+                    completed_count: row.get("completed_count"),
+                    last_visit_at: row.get("last_visit_at"),
+                    average_rating: row.get("average_rating"),
+                })
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        Ok((items, total))
+    }
+
     pub async fn list_consultations(
         db: &DbPool,
         query: ConsultationListQuery,
@@ -97,81 +339,194 @@ impl VideoConsultationService {
         let page_size = query.page_size.unwrap_or(20).min(100);
         let offset = (page - 1) * page_size;
 
-        // Build query dynamically based on filters
-        let rows = match (&query.doctor_id, &query.patient_id, &query.status, &query.date_from, &query.date_to) {
-            (Some(doctor_id), None, None, None, None) => {
-                sqlx::query(
-                    "SELECT * FROM video_consultations WHERE doctor_id = ? ORDER BY scheduled_start_time DESC LIMIT ? OFFSET ?"
-                )
-                .bind(doctor_id.to_string())
-                .bind(page_size)
-                .bind(offset)
-                .fetch_all(db)
-                .await
-            },
-            (None, Some(patient_id), None, None, None) => {
-                sqlx::query(
-                    "SELECT * FROM video_consultations WHERE patient_id = ? ORDER BY scheduled_start_time DESC LIMIT ? OFFSET ?"
-                )
-                .bind(patient_id.to_string())
-                .bind(page_size)
-                .bind(offset)
-                .fetch_all(db)
-                .await
-            },
-            (Some(doctor_id), Some(patient_id), None, None, None) => {
-                sqlx::query(
-                    "SELECT * FROM video_consultations WHERE doctor_id = ? AND patient_id = ? ORDER BY scheduled_start_time DESC LIMIT ? OFFSET ?"
-                )
-                .bind(doctor_id.to_string())
-                .bind(patient_id.to_string())
-                .bind(page_size)
-                .bind(offset)
-                .fetch_all(db)
-                .await
-            },
-            _ => {
-                // For complex queries, use a simpler approach
-                let mut conditions = vec![];
-                if query.doctor_id.is_some() { conditions.push("doctor_id = ?"); }
-                if query.patient_id.is_some() { conditions.push("patient_id = ?"); }
-                if query.status.is_some() { conditions.push("status = ?"); }
-                if query.date_from.is_some() { conditions.push("scheduled_start_time >= ?"); }
-                if query.date_to.is_some() { conditions.push("scheduled_start_time <= ?"); }
-
-                let where_clause = if conditions.is_empty() {
-                    String::new()
-                } else {
-                    format!(" WHERE {}", conditions.join(" AND "))
-                };
-
-                let _sql = format!(
-                    "SELECT * FROM video_consultations{} ORDER BY scheduled_start_time DESC LIMIT ? OFFSET ?",
-                    where_clause
-                );
-
-                // For now, just return all consultations if filters are complex
-                sqlx::query(
-                    "SELECT * FROM video_consultations ORDER BY scheduled_start_time DESC LIMIT ? OFFSET ?"
-                )
-                .bind(page_size)
-                .bind(offset)
-                .fetch_all(db)
-                .await
-            }
+        // Every filter is optional; binds follow the order the conditions are pushed
+        let mut conditions: Vec<&str> = vec![];
+        let mut binds: Vec<String> = vec![];
+        if let Some(doctor_id) = query.doctor_id {
+            conditions.push("doctor_id = ?");
+            binds.push(doctor_id.to_string());
         }
-        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        if let Some(patient_id) = query.patient_id {
+            conditions.push("patient_id = ?");
+            binds.push(patient_id.to_string());
+        }
+        if let Some(status) = &query.status {
+            conditions.push("status = ?");
+            binds.push(Self::consultation_status_str(status).to_string());
+        }
+        if query.date_from.is_some() {
+            conditions.push("scheduled_start_time >= ?");
+        }
+        if query.date_to.is_some() {
+            conditions.push("scheduled_start_time <= ?");
+        }
+
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+
+        let sql = format!(
+            "SELECT * FROM video_consultations{} ORDER BY scheduled_start_time DESC LIMIT ? OFFSET ?",
+            where_clause
+        );
+
+        let mut list_query = sqlx::query(&sql);
+        for value in &binds {
+            list_query = list_query.bind(value);
+        }
+        if let Some(date_from) = query.date_from {
+            list_query = list_query.bind(date_from);
+        }
+        if let Some(date_to) = query.date_to {
+            list_query = list_query.bind(date_to);
+        }
+
+        let rows = list_query
+            .bind(page_size)
+            .bind(offset)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         rows.into_iter()
             .map(|row| Self::parse_consultation_row(row))
             .collect::<Result<Vec<_>, _>>()
     }
 
+    /// 管理员查看全部问诊，筛选条件全部参数化绑定，返回当前页及总数
+    pub async fn admin_list_consultations(
+        db: &DbPool,
+        filters: AdminConsultationFilters,
+        page: i64,
+        page_size: i64,
+    ) -> Result<(Vec<AdminConsultationListItem>, i64), AppError> {
+        let offset = (page - 1) * page_size;
+
+        let mut conditions: Vec<&str> = vec![];
+        let mut binds: Vec<String> = vec![];
+        if let Some(doctor_id) = filters.doctor_id {
+            conditions.push("vc.doctor_id = ?");
+            binds.push(doctor_id.to_string());
+        }
+        if let Some(patient_id) = filters.patient_id {
+            conditions.push("vc.patient_id = ?");
+            binds.push(patient_id.to_string());
+        }
+        if let Some(status) = &filters.status {
+            conditions.push("vc.status = ?");
+            binds.push(Self::consultation_status_str(status).to_string());
+        }
+
+        if filters.date_from.is_some() {
+            conditions.push("vc.scheduled_start_time >= ?");
+        }
+        if filters.date_to.is_some() {
+            conditions.push("vc.scheduled_start_time <= ?");
+        }
+        if filters.min_rating.is_some() {
+            conditions.push("vc.patient_rating >= ?");
+        }
+        if filters.max_rating.is_some() {
+            conditions.push("vc.patient_rating <= ?");
+        }
+
+        // 绑定顺序须与上面条件的拼接顺序一致：先字符串条件，再日期，再评分
+        let where_clause = if conditions.is_empty() {
+            String::new()
+        } else {
+            format!(" WHERE {}", conditions.join(" AND "))
+        };
+
+        let count_sql = format!(
+            "SELECT COUNT(*) FROM video_consultations vc{}",
+            where_clause
+        );
+        let mut count_query = sqlx::query_scalar::<_, i64>(&count_sql);
+        for value in &binds {
+            count_query = count_query.bind(value);
+        }
+        if let Some(date_from) = filters.date_from {
+            count_query = count_query.bind(date_from);
+        }
+        if let Some(date_to) = filters.date_to {
+            count_query = count_query.bind(date_to);
+        }
+        if let Some(min_rating) = filters.min_rating {
+            count_query = count_query.bind(min_rating);
+        }
+        if let Some(max_rating) = filters.max_rating {
+            count_query = count_query.bind(max_rating);
+        }
+        let total = count_query
+            .fetch_one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let list_sql = format!(
+            r#"
+            SELECT vc.*, du.name as doctor_name, d.department, pu.name as patient_name
+            FROM video_consultations vc
+            LEFT JOIN doctors d ON d.id = vc.doctor_id
+            LEFT JOIN users du ON du.id = d.user_id
+            LEFT JOIN users pu ON pu.id = vc.patient_id{}
+            ORDER BY vc.scheduled_start_time DESC, vc.id
+            LIMIT ? OFFSET ?
+            "#,
+            where_clause
+        );
+        let mut list_query = sqlx::query(&list_sql);
+        for value in &binds {
+            list_query = list_query.bind(value);
+        }
+        if let Some(date_from) = filters.date_from {
+            list_query = list_query.bind(date_from);
+        }
+        if let Some(date_to) = filters.date_to {
+            list_query = list_query.bind(date_to);
+        }
+        if let Some(min_rating) = filters.min_rating {
+            list_query = list_query.bind(min_rating);
+        }
+        if let Some(max_rating) = filters.max_rating {
+            list_query = list_query.bind(max_rating);
+        }
+        let rows = list_query
+            .bind(page_size)
+            .bind(offset)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        use sqlx::Row;
+        let items = rows
+            .into_iter()
+            .map(|row| {
+                let doctor_name: Option<String> = row.get("doctor_name");
+                let department: Option<String> = row.get("department");
+                let patient_name: Option<String> = row.get("patient_name");
+                Ok(AdminConsultationListItem {
+                    consultation: Self::parse_consultation_row(row)?,
+                    doctor_name,
+                    department,
+                    patient_name,
+                })
+            })
+            .collect::<Result<Vec<_>, AppError>>()?;
+
+        Ok((items, total))
+    }
+
     // Room Management
+    /// 加入房间并签发新的房间令牌。携带已有令牌（如入会邀请中的令牌）时，
+    /// 令牌必须是为该问诊和当前用户签发且未过期
     pub async fn join_room(
         db: &DbPool,
+        jwt_secret: &str,
         room_id: &str,
         user_id: Uuid,
+        room_token: Option<&str>,
     ) -> Result<JoinRoomResponse, AppError> {
         let mut tx = db
             .begin()
@@ -181,6 +536,10 @@ impl VideoConsultationService {
         // Get consultation
         let consultation = Self::get_consultation_by_room_id(db, room_id).await?;
 
+        if let Some(room_token) = room_token {
+            Self::verify_room_token(jwt_secret, room_token, consultation.id, user_id)?;
+        }
+
         // Check if user is authorized
         // For doctors, we need to check if the user_id corresponds to the doctor_id
         let mut is_doctor = false;
@@ -188,19 +547,14 @@ impl VideoConsultationService {
             is_doctor = doctor.id == consultation.doctor_id;
         }
 
-        let (role, token) = if is_doctor {
-            (
-                "doctor",
-                Self::generate_token(&consultation.id, &user_id, "doctor"),
-            )
+        let role = if is_doctor {
+            "doctor"
         } else if user_id == consultation.patient_id {
-            (
-                "patient",
-                Self::generate_token(&consultation.id, &user_id, "patient"),
-            )
+            "patient"
         } else {
             return Err(AppError::Forbidden);
         };
+        let token = Self::generate_token(jwt_secret, &consultation.id, &user_id, role)?;
 
         // Update token in database
         let update_query = if role == "doctor" {
@@ -245,6 +599,108 @@ impl VideoConsultationService {
         })
     }
 
+    /// 重新向患者发送入会邀请（通知 + WebSocket 推送），附带当前房间号和新生成的患者令牌。
+    /// 医生和患者均可触发，按配置的间隔限频。
+    pub async fn resend_invite(
+        db: &DbPool,
+        ws_manager: &WebSocketManager,
+        jwt_secret: &str,
+        consultation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<ResendInviteResponse, AppError> {
+        let consultation = Self::get_consultation(db, consultation_id).await?;
+
+        let mut is_doctor = false;
+        if let Ok(doctor) =
+            crate::services::doctor_service::get_doctor_by_user_id(db, user_id).await
+        {
+            is_doctor = doctor.id == consultation.doctor_id;
+        }
+        if !is_doctor && user_id != consultation.patient_id {
+            return Err(AppError::Forbidden);
+        }
+
+        if !matches!(
+            consultation.status,
+            ConsultationStatus::Waiting | ConsultationStatus::InProgress
+        ) {
+            return Err(AppError::BadRequest(
+                "问诊已结束，无法重新发送邀请".to_string(),
+            ));
+        }
+
+        let cooldown_seconds =
+            SystemConfigService::get_i64(db, "video_call", "invite_resend_cooldown_seconds", 60)
+                .await?;
+        let now = Utc::now();
+        let token = Self::generate_token(
+            jwt_secret,
+            &consultation.id,
+            &consultation.patient_id,
+            "patient",
+        )?;
+
+        // Claiming the send slot and rotating the token in one statement keeps
+        // concurrent resends from slipping past the cooldown
+        let result = sqlx::query(
+            r#"
+            UPDATE video_consultations
+            SET invite_sent_at = ?, patient_token = ?, updated_at = ?
+            WHERE id = ? AND (invite_sent_at IS NULL OR invite_sent_at <= ?)
+            "#,
+        )
+        .bind(now)
+        .bind(&token)
+        .bind(now)
+        .bind(consultation.id.to_string())
+        .bind(now - Duration::seconds(cooldown_seconds))
+        .execute(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::TooManyRequests(
+                "邀请发送过于频繁，请稍后再试".to_string(),
+            ));
+        }
+
+        let join_url = format!(
+            "/api/v1/video-consultations/room/{}/join",
+            consultation.room_id
+        );
+
+        let notification = NotificationService::create_notification(
+            db,
+            CreateNotificationDto {
+                user_id: consultation.patient_id,
+                notification_type: NotificationType::AppointmentReminder,
+                title: "视频问诊入会邀请".to_string(),
+                content: "请点击链接进入视频诊室".to_string(),
+                related_id: Some(consultation.id),
+                metadata: Some(serde_json::json!({
+                    "consultation_id": consultation.id,
+                    "room_id": consultation.room_id,
+                    "join_url": join_url,
+                    "token": token,
+                    "scheduled_start_time": consultation.scheduled_start_time,
+                })),
+            },
+        )
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        ws_manager
+            .send_notification(consultation.patient_id, notification)
+            .await;
+
+        Ok(ResendInviteResponse {
+            consultation_id: consultation.id,
+            room_id: consultation.room_id,
+            join_url,
+            sent_at: now,
+        })
+    }
+
     pub async fn start_consultation(
         db: &DbPool,
         consultation_id: Uuid,
@@ -267,6 +723,32 @@ impl VideoConsultationService {
             return Err(AppError::BadRequest("问诊状态不正确".to_string()));
         }
 
+        if SystemConfigService::get_bool(
+            db,
+            "video_call",
+            "require_questionnaire_before_start",
+            false,
+        )
+        .await?
+        {
+            let pending: i64 = sqlx::query_scalar(
+                r#"
+                SELECT COUNT(*) FROM consultation_questionnaires
+                WHERE consultation_id = ? AND is_required = TRUE AND submitted_at IS NULL
+                "#,
+            )
+            .bind(consultation_id.to_string())
+            .fetch_one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            if pending > 0 {
+                return Err(AppError::BadRequest(
+                    "患者尚未填写必填的问诊前问卷".to_string(),
+                ));
+            }
+        }
+
         let now = Utc::now();
         let query = r#"
             UPDATE video_consultations
@@ -338,7 +820,7 @@ impl VideoConsultationService {
         let query = r#"
             UPDATE video_consultations
             SET status = 'completed', end_time = ?, duration = ?,
-                diagnosis = ?, treatment_plan = ?, notes = ?, updated_at = ?
+                diagnosis = ?, treatment_plan = ?, notes = ?, room_code = NULL, updated_at = ?
             WHERE id = ? AND status = 'in_progress'
         "#;
 
@@ -368,6 +850,9 @@ impl VideoConsultationService {
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
+        // Settle a balance payment held for this appointment
+        PaymentService::settle_balance_hold_tx(db, &mut tx, consultation.appointment_id).await?;
+
         // Log event
         Self::log_event_tx(
             &mut tx,
@@ -380,100 +865,735 @@ impl VideoConsultationService {
         )
         .await?;
 
-        tx.commit()
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    pub async fn update_consultation(
+        db: &DbPool,
+        consultation_id: Uuid,
+        user_id: Uuid,
+        dto: UpdateConsultationDto,
+    ) -> Result<(), AppError> {
+        let consultation = Self::get_consultation(db, consultation_id).await?;
+
+        // Verify doctor - check if the user_id corresponds to the doctor_id
+        let doctor = match crate::services::doctor_service::get_doctor_by_user_id(db, user_id).await {
+            Ok(doctor) => doctor,
+            Err(_) => return Err(AppError::NotFound("医生信息不存在".to_string())),
+        };
+        
+        if consultation.doctor_id != doctor.id {
+            return Err(AppError::Forbidden);
+        }
+
+        let query = r#"
+            UPDATE video_consultations
+            SET chief_complaint = COALESCE(?, chief_complaint),
+                diagnosis = COALESCE(?, diagnosis),
+                treatment_plan = COALESCE(?, treatment_plan),
+                notes = COALESCE(?, notes),
+                updated_at = ?
+            WHERE id = ?
+        "#;
+
+        sqlx::query(query)
+            .bind(&dto.chief_complaint)
+            .bind(&dto.diagnosis)
+            .bind(&dto.treatment_plan)
+            .bind(&dto.notes)
+            .bind(Utc::now())
+            .bind(consultation_id.to_string())
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 医生在问诊中或问诊后建议线下就诊：为患者生成一条待确认的线下预约并通知患者
+    pub async fn refer_to_offline(
+        db: &DbPool,
+        consultation_id: Uuid,
+        doctor_id: Uuid,
+        notes: String,
+    ) -> Result<Appointment, AppError> {
+        let consultation = Self::get_consultation(db, consultation_id).await?;
+
+        if consultation.doctor_id != doctor_id {
+            return Err(AppError::Forbidden);
+        }
+
+        if !matches!(
+            consultation.status,
+            ConsultationStatus::InProgress | ConsultationStatus::Completed
+        ) {
+            return Err(AppError::BadRequest(
+                "问诊未开始，无法转线下就诊".to_string(),
+            ));
+        }
+
+        let existing: Option<String> = sqlx::query_scalar(
+            "SELECT id FROM appointments WHERE referred_from_consultation_id = ?",
+        )
+        .bind(consultation_id.to_string())
+        .fetch_optional(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if existing.is_some() {
+            return Err(AppError::BadRequest("该问诊已转线下就诊".to_string()));
+        }
+
+        // The suggested visit is pending until the patient picks an actual slot
+        let appointment_id = Uuid::new_v4();
+        let now = Utc::now();
+        let symptoms: String = consultation
+            .chief_complaint
+            .as_deref()
+            .unwrap_or(&notes)
+            .chars()
+            .take(100)
+            .collect();
+
+        let query = r#"
+            INSERT INTO appointments (
+                id, patient_id, patient_profile_id, doctor_id, appointment_date, time_slot,
+                visit_type, symptoms, has_visited_before, status,
+                referred_from_consultation_id, referral_notes, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, '待定', 'offline', ?, TRUE, 'pending', ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(appointment_id.to_string())
+            .bind(consultation.patient_id.to_string())
+            .bind(consultation.patient_profile_id.map(|id| id.to_string()))
+            .bind(consultation.doctor_id.to_string())
+            .bind(now + Duration::days(1))
+            .bind(&symptoms)
+            .bind(consultation_id.to_string())
+            .bind(&notes)
+            .bind(now)
+            .bind(now)
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        NotificationService::create_notification(
+            db,
+            CreateNotificationDto {
+                user_id: consultation.patient_id,
+                notification_type: NotificationType::AppointmentReminder,
+                title: "医生建议线下就诊".to_string(),
+                content: format!("医生建议您到院线下就诊：{}", notes),
+                related_id: Some(appointment_id),
+                metadata: Some(serde_json::json!({
+                    "appointment_id": appointment_id,
+                    "consultation_id": consultation_id,
+                })),
+            },
+        )
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_appointment(db, appointment_id).await
+    }
+
+    /// 将未结束的问诊转交给其他医生：更换接诊医生、作废原医生令牌、记录事件并通知患者。
+    /// `is_admin` 为 false 时调用方必须是当前接诊医生（`from_doctor_id`）。
+    pub async fn handover_consultation(
+        db: &DbPool,
+        consultation_id: Uuid,
+        from_doctor_id: Uuid,
+        to_doctor_id: Uuid,
+        acting_user_id: Uuid,
+        is_admin: bool,
+        reason: Option<String>,
+    ) -> Result<VideoConsultation, AppError> {
+        let consultation = Self::get_consultation(db, consultation_id).await?;
+
+        if !is_admin && consultation.doctor_id != from_doctor_id {
+            return Err(AppError::Forbidden);
+        }
+
+        if !matches!(
+            consultation.status,
+            ConsultationStatus::Waiting | ConsultationStatus::InProgress
+        ) {
+            return Err(AppError::BadRequest("问诊已结束，无法转交".to_string()));
+        }
+
+        if to_doctor_id == consultation.doctor_id {
+            return Err(AppError::BadRequest("不能转交给当前接诊医生".to_string()));
+        }
+
+        let to_doctor = crate::services::doctor_service::get_doctor_by_id(db, to_doctor_id)
+            .await
+            .map_err(|_| AppError::NotFound("目标医生不存在".to_string()))?;
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // Guarding on the current doctor and status keeps two concurrent
+        // handovers (or a handover racing the end of the call) from both applying
+        let result = sqlx::query(
+            r#"
+            UPDATE video_consultations
+            SET doctor_id = ?, doctor_token = NULL, updated_at = ?
+            WHERE id = ? AND doctor_id = ? AND status IN ('waiting', 'in_progress')
+            "#,
+        )
+        .bind(to_doctor.id.to_string())
+        .bind(Utc::now())
+        .bind(consultation_id.to_string())
+        .bind(consultation.doctor_id.to_string())
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if result.rows_affected() == 0 {
+            return Err(AppError::BadRequest(
+                "问诊状态已变化，请刷新后重试".to_string(),
+            ));
+        }
+
+        Self::log_event_tx(
+            &mut tx,
+            LogEventDto {
+                consultation_id,
+                event_type: VideoEventType::Handover,
+                event_data: Some(serde_json::json!({
+                    "from_doctor_id": consultation.doctor_id,
+                    "to_doctor_id": to_doctor.id,
+                    "reason": reason,
+                })),
+            },
+            acting_user_id,
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        NotificationService::create_notification(
+            db,
+            CreateNotificationDto {
+                user_id: consultation.patient_id,
+                notification_type: NotificationType::AppointmentReminder,
+                title: "接诊医生已变更".to_string(),
+                content: "您的视频问诊已转由其他医生接诊".to_string(),
+                related_id: Some(consultation_id),
+                metadata: Some(serde_json::json!({
+                    "consultation_id": consultation_id,
+                    "from_doctor_id": consultation.doctor_id,
+                    "to_doctor_id": to_doctor.id,
+                })),
+            },
+        )
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_consultation(db, consultation_id).await
+    }
+
+    pub async fn rate_consultation(
+        db: &DbPool,
+        consultation_id: Uuid,
+        patient_id: Uuid,
+        dto: RateConsultationDto,
+    ) -> Result<(), AppError> {
+        let consultation = Self::get_consultation(db, consultation_id).await?;
+
+        // Verify patient
+        if consultation.patient_id != patient_id {
+            return Err(AppError::Forbidden);
+        }
+
+        // Check if consultation is completed
+        if consultation.status != ConsultationStatus::Completed {
+            return Err(AppError::BadRequest("问诊未完成".to_string()));
+        }
+
+        let query = r#"
+            UPDATE video_consultations
+            SET patient_rating = ?, patient_feedback = ?, updated_at = ?
+            WHERE id = ?
+        "#;
+
+        sqlx::query(query)
+            .bind(dto.rating)
+            .bind(&dto.feedback)
+            .bind(Utc::now())
+            .bind(consultation_id.to_string())
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    /// 医生对患者的反馈（爽约、辱骂等），仅医生和管理员可见，不会出现在患者端数据中
+    pub async fn submit_patient_feedback(
+        db: &DbPool,
+        consultation_id: Uuid,
+        doctor_id: Uuid,
+        rating: i32,
+        notes: Option<String>,
+    ) -> Result<PatientFeedback, AppError> {
+        let consultation = Self::get_consultation(db, consultation_id).await?;
+
+        // Only the consultation's doctor may comment on the patient
+        if consultation.doctor_id != doctor_id {
+            return Err(AppError::Forbidden);
+        }
+
+        if !matches!(
+            consultation.status,
+            ConsultationStatus::Completed | ConsultationStatus::NoShow
+        ) {
+            return Err(AppError::BadRequest(
+                "问诊结束后才能提交患者反馈".to_string(),
+            ));
+        }
+
+        if !(1..=5).contains(&rating) {
+            return Err(AppError::BadRequest("评分必须在1-5之间".to_string()));
+        }
+
+        let existing: Option<String> =
+            sqlx::query_scalar("SELECT id FROM doctor_patient_feedback WHERE consultation_id = ?")
+                .bind(consultation_id.to_string())
+                .fetch_optional(db)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if existing.is_some() {
+            return Err(AppError::BadRequest("已提交过该问诊的患者反馈".to_string()));
+        }
+
+        let feedback = PatientFeedback {
+            id: Uuid::new_v4(),
+            consultation_id,
+            doctor_id,
+            patient_id: consultation.patient_id,
+            rating,
+            notes,
+            created_at: Utc::now(),
+        };
+
+        let query = r#"
+            INSERT INTO doctor_patient_feedback (
+                id, consultation_id, doctor_id, patient_id, rating, notes, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(feedback.id.to_string())
+            .bind(feedback.consultation_id.to_string())
+            .bind(feedback.doctor_id.to_string())
+            .bind(feedback.patient_id.to_string())
+            .bind(feedback.rating)
+            .bind(&feedback.notes)
+            .bind(feedback.created_at)
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(feedback)
+    }
+
+    /// 汇总医生反馈得到患者可靠度，供医生和管理员在接诊前参考
+    pub async fn get_patient_reliability(
+        db: &DbPool,
+        patient_id: Uuid,
+    ) -> Result<PatientReliability, AppError> {
+        use sqlx::Row;
+
+        let query = r#"
+            SELECT
+                COUNT(*) as feedback_count,
+                AVG(rating) as average_rating,
+                COALESCE(SUM(CASE WHEN rating <= 2 THEN 1 ELSE 0 END), 0) as low_rating_count
+            FROM doctor_patient_feedback
+            WHERE patient_id = ?
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(patient_id.to_string())
+            .fetch_one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let average_rating: Option<f64> = row
+            .get::<Option<sqlx::types::Decimal>, _>("average_rating")
+            .and_then(|avg| avg.to_string().parse().ok());
+        let low_rating_count: i64 = row
+            .get::<sqlx::types::Decimal, _>("low_rating_count")
+            .to_string()
+            .parse()
+            .unwrap_or(0);
+
+        Ok(PatientReliability {
+            patient_id,
+            feedback_count: row.get("feedback_count"),
+            average_rating,
+            low_rating_count,
+            // Map the 1-5 average onto 0-100
+            reliability_score: average_rating.map(|avg| ((avg - 1.0) * 25.0).round() as i32),
+        })
+    }
+
+    // Consultation Chat
+    // Pre-visit Questionnaires
+    pub async fn attach_questionnaire(
+        db: &DbPool,
+        consultation_id: Uuid,
+        doctor_id: Uuid,
+        dto: AttachQuestionnaireDto,
+    ) -> Result<ConsultationQuestionnaire, AppError> {
+        let consultation = Self::get_consultation(db, consultation_id).await?;
+
+        if consultation.doctor_id != doctor_id {
+            return Err(AppError::Forbidden);
+        }
+
+        if consultation.status != ConsultationStatus::Waiting {
+            return Err(AppError::BadRequest("问诊开始后不能再添加问卷".to_string()));
+        }
+
+        let mut keys = HashSet::new();
+        for question in &dto.questions {
+            if question.key.trim().is_empty() || question.question.trim().is_empty() {
+                return Err(AppError::BadRequest("问卷题目不能为空".to_string()));
+            }
+            if !keys.insert(question.key.as_str()) {
+                return Err(AppError::BadRequest(format!(
+                    "问卷题目 key 重复: {}",
+                    question.key
+                )));
+            }
+        }
+
+        let questionnaire = ConsultationQuestionnaire {
+            id: Uuid::new_v4(),
+            consultation_id,
+            doctor_id,
+            title: dto.title,
+            questions: dto.questions,
+            is_required: dto.is_required.unwrap_or(true),
+            answers: None,
+            submitted_at: None,
+            created_at: Utc::now(),
+        };
+
+        let query = r#"
+            INSERT INTO consultation_questionnaires (
+                id, consultation_id, doctor_id, title, questions, is_required, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(questionnaire.id.to_string())
+            .bind(consultation_id.to_string())
+            .bind(doctor_id.to_string())
+            .bind(&questionnaire.title)
+            .bind(serde_json::json!(questionnaire.questions))
+            .bind(questionnaire.is_required)
+            .bind(questionnaire.created_at)
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(questionnaire)
+    }
+
+    /// 患者在问诊开始前提交问卷答案，开始前可重复提交覆盖
+    pub async fn submit_questionnaire_answers(
+        db: &DbPool,
+        consultation_id: Uuid,
+        questionnaire_id: Uuid,
+        patient_id: Uuid,
+        answers: HashMap<String, String>,
+    ) -> Result<ConsultationQuestionnaire, AppError> {
+        let consultation = Self::get_consultation(db, consultation_id).await?;
+
+        if consultation.patient_id != patient_id {
+            return Err(AppError::Forbidden);
+        }
+
+        if consultation.status != ConsultationStatus::Waiting {
+            return Err(AppError::BadRequest("问诊开始后不能再提交问卷".to_string()));
+        }
+
+        let questionnaire = Self::get_consultation_questionnaires(db, consultation_id)
+            .await?
+            .into_iter()
+            .find(|q| q.id == questionnaire_id)
+            .ok_or_else(|| AppError::NotFound("问卷不存在".to_string()))?;
+
+        if let Some(key) = answers
+            .keys()
+            .find(|key| !questionnaire.questions.iter().any(|q| &q.key == *key))
+        {
+            return Err(AppError::BadRequest(format!("问卷中没有该题目: {}", key)));
+        }
+
+        for question in questionnaire.questions.iter().filter(|q| q.required) {
+            let answered = answers
+                .get(&question.key)
+                .map(|answer| !answer.trim().is_empty())
+                .unwrap_or(false);
+            if !answered {
+                return Err(AppError::BadRequest(format!(
+                    "请回答必答题: {}",
+                    question.question
+                )));
+            }
+        }
+
+        let now = Utc::now();
+        sqlx::query(
+            "UPDATE consultation_questionnaires SET answers = ?, submitted_at = ? WHERE id = ?",
+        )
+        .bind(serde_json::json!(answers))
+        .bind(now)
+        .bind(questionnaire_id.to_string())
+        .execute(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(ConsultationQuestionnaire {
+            answers: Some(answers),
+            submitted_at: Some(now),
+            ..questionnaire
+        })
+    }
+
+    pub async fn get_consultation_questionnaires(
+        db: &DbPool,
+        consultation_id: Uuid,
+    ) -> Result<Vec<ConsultationQuestionnaire>, AppError> {
+        use sqlx::Row;
+
+        let rows = sqlx::query(
+            r#"
+            SELECT * FROM consultation_questionnaires
+            WHERE consultation_id = ?
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(consultation_id.to_string())
+        .fetch_all(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        Ok(())
+        rows.into_iter()
+            .map(|row| {
+                let questions: serde_json::Value = row.get("questions");
+                let answers: Option<serde_json::Value> = row.get("answers");
+                Ok(ConsultationQuestionnaire {
+                    id: Uuid::parse_str(row.get("id"))
+                        .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+                    consultation_id: Uuid::parse_str(row.get("consultation_id"))
+                        .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+                    doctor_id: Uuid::parse_str(row.get("doctor_id"))
+                        .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+                    title: row.get("title"),
+                    questions: serde_json::from_value(questions)
+                        .map_err(|e| AppError::InternalServerError(e.to_string()))?,
+                    is_required: row.get("is_required"),
+                    answers: answers
+                        .map(serde_json::from_value)
+                        .transpose()
+                        .map_err(|e| AppError::InternalServerError(e.to_string()))?,
+                    submitted_at: row.get("submitted_at"),
+                    created_at: row.get("created_at"),
+                })
+            })
+            .collect()
     }
 
-    pub async fn update_consultation(
+    pub async fn send_message(
         db: &DbPool,
         consultation_id: Uuid,
-        user_id: Uuid,
-        dto: UpdateConsultationDto,
-    ) -> Result<(), AppError> {
+        sender_id: Uuid,
+        content: String,
+    ) -> Result<ConsultationMessage, AppError> {
         let consultation = Self::get_consultation(db, consultation_id).await?;
+        let doctor_user_id = Self::get_doctor_user_id(db, consultation.doctor_id).await?;
 
-        // Verify doctor - check if the user_id corresponds to the doctor_id
-        let doctor = match crate::services::doctor_service::get_doctor_by_user_id(db, user_id).await {
-            Ok(doctor) => doctor,
-            Err(_) => return Err(AppError::NotFound("医生信息不存在".to_string())),
-        };
-        
-        if consultation.doctor_id != doctor.id {
+        if sender_id != consultation.patient_id && Some(sender_id) != doctor_user_id {
             return Err(AppError::Forbidden);
         }
 
-        let query = r#"
-            UPDATE video_consultations
-            SET chief_complaint = COALESCE(?, chief_complaint),
-                diagnosis = COALESCE(?, diagnosis),
-                treatment_plan = COALESCE(?, treatment_plan),
-                notes = COALESCE(?, notes),
-                updated_at = ?
-            WHERE id = ?
-        "#;
+        if !matches!(
+            consultation.status,
+            ConsultationStatus::Waiting | ConsultationStatus::InProgress
+        ) {
+            return Err(AppError::BadRequest("问诊已结束，无法发送消息".to_string()));
+        }
 
-        sqlx::query(query)
-            .bind(&dto.chief_complaint)
-            .bind(&dto.diagnosis)
-            .bind(&dto.treatment_plan)
-            .bind(&dto.notes)
-            .bind(Utc::now())
-            .bind(consultation_id.to_string())
-            .execute(db)
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let message = ConsultationMessage {
+            id: Uuid::new_v4(),
+            consultation_id,
+            sender_id,
+            content,
+            created_at: Utc::now(),
+        };
 
-        Ok(())
+        sqlx::query(
+            r#"
+            INSERT INTO consultation_messages (id, consultation_id, sender_id, content, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(message.id.to_string())
+        .bind(message.consultation_id.to_string())
+        .bind(message.sender_id.to_string())
+        .bind(&message.content)
+        .bind(message.created_at)
+        .execute(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(message)
     }
 
-    pub async fn rate_consultation(
+    /// 合并聊天消息和通话事件，按时间顺序生成问诊记录，仅限已完成问诊的参与者和管理员
+    pub async fn render_transcript(
         db: &DbPool,
         consultation_id: Uuid,
-        patient_id: Uuid,
-        dto: RateConsultationDto,
-    ) -> Result<(), AppError> {
+        requester_id: Uuid,
+        is_admin: bool,
+    ) -> Result<ConsultationTranscript, AppError> {
+        use sqlx::Row;
+
         let consultation = Self::get_consultation(db, consultation_id).await?;
+        let doctor_user_id = Self::get_doctor_user_id(db, consultation.doctor_id).await?;
 
-        // Verify patient
-        if consultation.patient_id != patient_id {
+        let is_participant =
+            requester_id == consultation.patient_id || Some(requester_id) == doctor_user_id;
+        if !is_admin && !is_participant {
             return Err(AppError::Forbidden);
         }
 
-        // Check if consultation is completed
         if consultation.status != ConsultationStatus::Completed {
             return Err(AppError::BadRequest("问诊未完成".to_string()));
         }
 
-        let query = r#"
-            UPDATE video_consultations
-            SET patient_rating = ?, patient_feedback = ?, updated_at = ?
-            WHERE id = ?
-        "#;
+        let role_of = |user_id: Uuid| -> String {
+            if user_id == consultation.patient_id {
+                "patient".to_string()
+            } else if Some(user_id) == doctor_user_id {
+                "doctor".to_string()
+            } else {
+                "other".to_string()
+            }
+        };
 
-        sqlx::query(query)
-            .bind(dto.rating)
-            .bind(&dto.feedback)
-            .bind(Utc::now())
-            .bind(consultation_id.to_string())
-            .execute(db)
-            .await
-            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        let mut entries = Vec::new();
 
-        Ok(())
+        let messages = sqlx::query(
+            r#"
+            SELECT sender_id, content, created_at
+            FROM consultation_messages
+            WHERE consultation_id = ?
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(consultation_id.to_string())
+        .fetch_all(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        for row in messages {
+            let user_id = Uuid::parse_str(row.get("sender_id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?;
+            entries.push(TranscriptEntry {
+                timestamp: row.get("created_at"),
+                kind: "message".to_string(),
+                user_id,
+                role: role_of(user_id),
+                content: Some(row.get("content")),
+                event_type: None,
+                event_data: None,
+            });
+        }
+
+        let events = sqlx::query(
+            r#"
+            SELECT user_id, event_type, event_data, created_at
+            FROM video_call_events
+            WHERE consultation_id = ?
+            ORDER BY created_at ASC
+            "#,
+        )
+        .bind(consultation_id.to_string())
+        .fetch_all(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        for row in events {
+            let user_id = Uuid::parse_str(row.get("user_id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?;
+            entries.push(TranscriptEntry {
+                timestamp: row.get("created_at"),
+                kind: "event".to_string(),
+                user_id,
+                role: role_of(user_id),
+                content: None,
+                event_type: Some(row.get("event_type")),
+                event_data: row.get("event_data"),
+            });
+        }
+
+        // Stable sort keeps the per-source order for entries in the same instant
+        entries.sort_by_key(|entry| entry.timestamp);
+
+        Ok(ConsultationTranscript {
+            consultation_id,
+            doctor_id: consultation.doctor_id,
+            patient_id: consultation.patient_id,
+            started_at: consultation.actual_start_time,
+            ended_at: consultation.end_time,
+            entries,
+        })
+    }
+
+    async fn get_doctor_user_id(db: &DbPool, doctor_id: Uuid) -> Result<Option<Uuid>, AppError> {
+        let user_id: Option<String> =
+            sqlx::query_scalar("SELECT user_id FROM doctors WHERE id = ?")
+                .bind(doctor_id.to_string())
+                .fetch_optional(db)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(user_id.and_then(|id| Uuid::parse_str(&id).ok()))
     }
 
     // WebRTC Signaling
+    /// 转发 WebRTC 信令。与同一接收方尚未投递的信令完全相同时不再存储，返回 false；
+    /// 同一用户在单个房间内的发送频率受 `signal_rate_limit_per_minute` 限制。
+    /// 接收方在线时通过 WebSocket 直接推送并标记为已投递，否则保留记录供轮询获取。
+    /// 必须携带加入房间时签发的房间令牌，过期或被篡改的令牌会被拒绝。
     pub async fn send_signal(
         db: &DbPool,
+        ws_manager: &WebSocketManager,
+        jwt_secret: &str,
         from_user_id: Uuid,
         dto: SendSignalDto,
-    ) -> Result<(), AppError> {
+    ) -> Result<bool, AppError> {
         // Verify user is in the room
         let consultation = Self::get_consultation_by_room_id(db, &dto.room_id).await?;
 
+        Self::verify_room_token(jwt_secret, &dto.token, consultation.id, from_user_id)?;
+
         // Check if from_user is authorized (doctor or patient)
         let mut is_authorized = false;
         if from_user_id == consultation.patient_id {
@@ -498,14 +1618,6 @@ impl VideoConsultationService {
             return Err(AppError::BadRequest("目标用户不在房间内".to_string()));
         }
 
-        let signal_id = Uuid::new_v4();
-        let query = r#"
-            INSERT INTO webrtc_signals (
-                id, room_id, from_user_id, to_user_id,
-                signal_type, payload, delivered, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, false, ?)
-        "#;
-
         // Convert signal_type enum to string for database
         let signal_type_str = match dto.signal_type {
             SignalType::Offer => "offer",
@@ -516,19 +1628,99 @@ impl VideoConsultationService {
             SignalType::Error => "error",
         };
 
-        sqlx::query(query)
+        let mut hasher = Sha256::new();
+        hasher.update(signal_type_str.as_bytes());
+        hasher.update(b":");
+        hasher.update(dto.payload.to_string().as_bytes());
+        let payload_hash = format!("{:x}", hasher.finalize());
+
+        let limit_per_minute =
+            SystemConfigService::get_i64(db, "video_call", "signal_rate_limit_per_minute", 300)
+                .await?;
+        let now = Utc::now();
+
+        let recent: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM webrtc_signals
+            WHERE room_id = ? AND from_user_id = ? AND created_at > ?
+            "#,
+        )
+        .bind(&dto.room_id)
+        .bind(from_user_id.to_string())
+        .bind(now - Duration::minutes(1))
+        .fetch_one(db)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if limit_per_minute > 0 && recent >= limit_per_minute {
+            return Err(AppError::TooManyRequests(
+                "信令发送过于频繁，请稍后再试".to_string(),
+            ));
+        }
+
+        // An identical signal still waiting for the recipient is redundant; the
+        // existence check is part of the insert so concurrent resends can't both land
+        let signal_id = Uuid::new_v4();
+        let query = r#"
+            INSERT INTO webrtc_signals (
+                id, room_id, from_user_id, to_user_id,
+                signal_type, payload, payload_hash, delivered, created_at
+            )
+            SELECT ?, ?, ?, ?, ?, ?, ?, false, ?
+            FROM DUAL
+            WHERE NOT EXISTS (
+                SELECT 1 FROM webrtc_signals
+                WHERE room_id = ? AND from_user_id = ? AND to_user_id = ?
+                  AND payload_hash = ? AND delivered = false
+            )
+        "#;
+
+        let result = sqlx::query(query)
             .bind(signal_id.to_string())
             .bind(&dto.room_id)
             .bind(from_user_id.to_string())
             .bind(dto.to_user_id.to_string())
             .bind(signal_type_str)
             .bind(&dto.payload)
-            .bind(Utc::now())
+            .bind(&payload_hash)
+            .bind(now)
+            .bind(&dto.room_id)
+            .bind(from_user_id.to_string())
+            .bind(dto.to_user_id.to_string())
+            .bind(&payload_hash)
             .execute(db)
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
-        Ok(())
+        if result.rows_affected() == 0 {
+            return Ok(false);
+        }
+
+        // The row stays undelivered for polling when the recipient is offline
+        let pushed = ws_manager
+            .send_to_user(
+                dto.to_user_id,
+                WsMessage::Signal {
+                    room_id: dto.room_id.clone(),
+                    from_user_id: from_user_id.to_string(),
+                    to_user_id: dto.to_user_id.to_string(),
+                    signal_type: dto.signal_type,
+                    payload: dto.payload,
+                    token: String::new(),
+                },
+            )
+            .await
+            .is_ok();
+
+        if pushed {
+            sqlx::query("UPDATE webrtc_signals SET delivered = true WHERE id = ?")
+                .bind(signal_id.to_string())
+                .execute(db)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+        }
+
+        Ok(true)
     }
 
     pub async fn receive_signals(
@@ -828,52 +2020,44 @@ impl VideoConsultationService {
         start_date: Option<DateTime<Utc>>,
         end_date: Option<DateTime<Utc>>,
     ) -> Result<ConsultationStatistics, AppError> {
-        let query = match (doctor_id, start_date, end_date) {
-            (Some(doc_id), None, None) => {
-                sqlx::query(
-                    r#"
-                    SELECT
-                        COUNT(*) as total_consultations,
-                        COUNT(CASE WHEN status = 'completed' THEN 1 END) as completed_consultations,
-                        CAST(AVG(CASE WHEN status = 'completed' THEN duration END) AS DOUBLE) as average_duration,
-                        CAST(AVG(patient_rating) AS DOUBLE) as average_rating,
-                        CAST(COUNT(CASE WHEN status = 'no_show' THEN 1 END) * 100.0 / COUNT(*) AS DOUBLE) as no_show_rate
-                    FROM video_consultations
-                    WHERE doctor_id = ? AND status != 'cancelled'
-                    "#
-                )
-                .bind(doc_id.to_string())
-            },
-            (None, None, None) => {
-                sqlx::query(
-                    r#"
-                    SELECT
-                        COUNT(*) as total_consultations,
-                        COUNT(CASE WHEN status = 'completed' THEN 1 END) as completed_consultations,
-                        CAST(AVG(CASE WHEN status = 'completed' THEN duration END) AS DOUBLE) as average_duration,
-                        CAST(AVG(patient_rating) AS DOUBLE) as average_rating,
-                        CAST(COUNT(CASE WHEN status = 'no_show' THEN 1 END) * 100.0 / COUNT(*) AS DOUBLE) as no_show_rate
-                    FROM video_consultations
-                    WHERE status != 'cancelled'
-                    "#
-                )
-            },
-            _ => {
-                // For complex filters, just return simple stats
-                sqlx::query(
-                    r#"
-                    SELECT
-                        COUNT(*) as total_consultations,
-                        COUNT(CASE WHEN status = 'completed' THEN 1 END) as completed_consultations,
-                        CAST(AVG(CASE WHEN status = 'completed' THEN duration END) AS DOUBLE) as average_duration,
-                        CAST(AVG(patient_rating) AS DOUBLE) as average_rating,
-                        CAST(COUNT(CASE WHEN status = 'no_show' THEN 1 END) * 100.0 / COUNT(*) AS DOUBLE) as no_show_rate
-                    FROM video_consultations
-                    WHERE status != 'cancelled'
-                    "#
-                )
-            }
-        };
+        // Cancelled consultations never count; the other filters are optional
+        let mut conditions = vec!["status != 'cancelled'"];
+        if doctor_id.is_some() {
+            conditions.push("doctor_id = ?");
+        }
+        if start_date.is_some() {
+            conditions.push("scheduled_start_time >= ?");
+        }
+        if end_date.is_some() {
+            conditions.push("scheduled_start_time <= ?");
+        }
+
+        let sql = format!(
+            r#"
+            SELECT
+                COUNT(*) as total_consultations,
+                COUNT(CASE WHEN status = 'completed' THEN 1 END) as completed_consultations,
+                CAST(AVG(CASE WHEN status = 'completed' THEN duration END) AS DOUBLE) as average_duration,
+                CAST(AVG(CASE WHEN status = 'completed' THEN expected_duration * 60 END) AS DOUBLE) as average_expected_duration,
+                COUNT(CASE WHEN status = 'completed' AND duration > expected_duration * 60 THEN 1 END) as overtime_count,
+                CAST(AVG(patient_rating) AS DOUBLE) as average_rating,
+                CAST(COUNT(CASE WHEN status = 'no_show' THEN 1 END) * 100.0 / COUNT(*) AS DOUBLE) as no_show_rate
+            FROM video_consultations
+            WHERE {}
+            "#,
+            conditions.join(" AND ")
+        );
+
+        let mut query = sqlx::query(&sql);
+        if let Some(doctor_id) = doctor_id {
+            query = query.bind(doctor_id.to_string());
+        }
+        if let Some(start_date) = start_date {
+            query = query.bind(start_date);
+        }
+        if let Some(end_date) = end_date {
+            query = query.bind(end_date);
+        }
 
         let row = query
             .fetch_one(db)
@@ -889,6 +2073,8 @@ impl VideoConsultationService {
                 .get::<Option<i64>, _>("completed_consultations")
                 .unwrap_or(0),
             average_duration: row.get("average_duration"),
+            average_expected_duration: row.get("average_expected_duration"),
+            overtime_count: row.get::<Option<i64>, _>("overtime_count").unwrap_or(0),
             average_rating: row.get("average_rating"),
             no_show_rate: row.get::<Option<f64>, _>("no_show_rate").unwrap_or(0.0),
         })
@@ -897,8 +2083,9 @@ impl VideoConsultationService {
     // Helper methods
     async fn get_appointment(db: &DbPool, appointment_id: Uuid) -> Result<Appointment, AppError> {
         let query = r#"
-            SELECT id, patient_id, doctor_id, appointment_date, time_slot, visit_type, 
-                   symptoms, has_visited_before, status, created_at, updated_at
+            SELECT id, patient_id, patient_profile_id, doctor_id, appointment_date, time_slot, visit_type, 
+                   symptoms, has_visited_before, status, referred_from_consultation_id, referral_notes,
+                   series_id, address_id, created_at, updated_at
             FROM appointments WHERE id = ?
         "#;
 
@@ -916,6 +2103,7 @@ impl VideoConsultationService {
         let visit_type = match visit_type_str.as_str() {
             "online_video" => VisitType::OnlineVideo,
             "offline" => VisitType::Offline,
+            "home_visit" => VisitType::HomeVisit,
             _ => return Err(AppError::BadRequest("Invalid visit type".to_string())),
         };
 
@@ -925,6 +2113,7 @@ impl VideoConsultationService {
             "confirmed" => AppointmentStatus::Confirmed,
             "completed" => AppointmentStatus::Completed,
             "cancelled" => AppointmentStatus::Cancelled,
+            "no_show" => AppointmentStatus::NoShow,
             _ => {
                 return Err(AppError::BadRequest(
                     "Invalid appointment status".to_string(),
@@ -937,14 +2126,27 @@ impl VideoConsultationService {
                 .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
             patient_id: Uuid::parse_str(row.get("patient_id"))
                 .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            patient_profile_id: row
+                .get::<Option<String>, _>("patient_profile_id")
+                .and_then(|id| Uuid::parse_str(&id).ok()),
             doctor_id: Uuid::parse_str(row.get("doctor_id"))
                 .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
             appointment_date: row.get("appointment_date"),
             time_slot: row.get("time_slot"),
             visit_type,
+            address_id: row
+                .get::<Option<String>, _>("address_id")
+                .and_then(|id| Uuid::parse_str(&id).ok()),
             symptoms: row.get("symptoms"),
             has_visited_before: row.get("has_visited_before"),
             status,
+            referred_from_consultation_id: row
+                .get::<Option<String>, _>("referred_from_consultation_id")
+                .and_then(|id| Uuid::parse_str(&id).ok()),
+            referral_notes: row.get("referral_notes"),
+            series_id: row
+                .get::<Option<String>, _>("series_id")
+                .and_then(|id| Uuid::parse_str(&id).ok()),
             created_at: row.get("created_at"),
             updated_at: row.get("updated_at"),
         })
@@ -996,12 +2198,17 @@ impl VideoConsultationService {
                 .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
             patient_id: Uuid::parse_str(row.get("patient_id"))
                 .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            patient_profile_id: row
+                .get::<Option<String>, _>("patient_profile_id")
+                .and_then(|id| Uuid::parse_str(&id).ok()),
             room_id: row.get("room_id"),
+            room_code: row.get("room_code"),
             status,
             scheduled_start_time: row.get("scheduled_start_time"),
             actual_start_time: row.get("actual_start_time"),
             end_time: row.get("end_time"),
             duration: row.get("duration"),
+            expected_duration: row.get("expected_duration"),
             doctor_token: row.get("doctor_token"),
             patient_token: row.get("patient_token"),
             ice_servers: row.get("ice_servers"),
@@ -1124,6 +2331,8 @@ impl VideoConsultationService {
             VideoEventType::RecordingEnd => "recording_end",
             VideoEventType::NetworkPoor => "network_poor",
             VideoEventType::NetworkRecovered => "network_recovered",
+            VideoEventType::Handover => "handover",
+            VideoEventType::Overtime => "overtime",
         };
 
         sqlx::query(query)
@@ -1169,6 +2378,8 @@ impl VideoConsultationService {
             VideoEventType::RecordingEnd => "recording_end",
             VideoEventType::NetworkPoor => "network_poor",
             VideoEventType::NetworkRecovered => "network_recovered",
+            VideoEventType::Handover => "handover",
+            VideoEventType::Overtime => "overtime",
         };
 
         sqlx::query(query)
@@ -1194,24 +2405,317 @@ impl VideoConsultationService {
             .map_err(|e| AppError::InternalServerError(format!("解析ICE服务器配置失败: {}", e)))
     }
 
-    fn generate_token(consultation_id: &Uuid, user_id: &Uuid, role: &str) -> String {
-        // In production, this should generate a proper JWT or secure token
-        format!("{}_{}_{}", consultation_id, user_id, role)
+    fn consultation_status_str(status: &ConsultationStatus) -> &'static str {
+        match status {
+            ConsultationStatus::Waiting => "waiting",
+            ConsultationStatus::InProgress => "in_progress",
+            ConsultationStatus::Completed => "completed",
+            ConsultationStatus::Cancelled => "cancelled",
+            ConsultationStatus::NoShow => "no_show",
+        }
+    }
+
+        /// 去掉了易混淆字符（0/O、1/I/L）的大写字母数字
+    pub fn generate_room_code() -> String {
+        const ALPHABET: &[u8] = b"ABCDEFGHJKMNPQRSTUVWXYZ23456789";
+        let mut rng = rand::thread_rng();
+        (0..ROOM_CODE_LENGTH)
+            .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+            .collect()
+    }
+
+    /// 签发短期有效的房间令牌（JWT），绑定问诊、用户和角色
+    fn generate_token(
+        jwt_secret: &str,
+        consultation_id: &Uuid,
+        user_id: &Uuid,
+        role: &str,
+    ) -> Result<String, AppError> {
+        jwt::create_room_token(
+            *consultation_id,
+            *user_id,
+            role.to_string(),
+            jwt_secret,
+            ROOM_TOKEN_TTL_SECONDS,
+        )
+        .map_err(|e| AppError::InternalServerError(format!("生成房间令牌失败: {}", e)))
+    }
+
+    /// 校验房间令牌：签名无效返回 InvalidToken，过期返回 TokenExpired，
+    /// 为其他问诊或其他用户签发的令牌返回 Forbidden
+    pub fn verify_room_token(
+        jwt_secret: &str,
+        token: &str,
+        consultation_id: Uuid,
+        user_id: Uuid,
+    ) -> Result<RoomClaims, AppError> {
+        let claims = jwt::decode_room_token(token, jwt_secret)?;
+
+        if claims.consultation_id != consultation_id || claims.sub != user_id {
+            return Err(AppError::Forbidden);
+        }
+
+        Ok(claims)
     }
 
     pub async fn clean_expired_signals(db: &DbPool) -> Result<u64, AppError> {
         let query = r#"
             DELETE FROM webrtc_signals
             WHERE created_at < ? OR delivered = true
+            LIMIT ?
         "#;
 
         let one_hour_ago = Utc::now() - Duration::hours(1);
-        let result = sqlx::query(query)
-            .bind(one_hour_ago)
-            .execute(db)
+        let settings = SweepSettings::load(db).await?;
+        CleanupService::run_batched("expired_signals", &settings, |limit| async move {
+            let result = sqlx::query(query)
+                .bind(one_hour_ago)
+                .bind(limit)
+                .execute(db)
+                .await
+                .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            Ok(result.rows_affected())
+        })
+        .await
+    }
+
+    /// 将超过预约开始时间 `no_show_grace_minutes` 分钟仍在等待的视频问诊标记为爽约，
+    /// 对应预约一并标记；配置为 0 时不处理。返回标记的问诊数
+    pub async fn mark_no_shows(db: &DbPool) -> Result<u64, AppError> {
+        let grace_minutes =
+            SystemConfigService::get_i64(db, "video_call", "no_show_grace_minutes", 15).await?;
+        if grace_minutes <= 0 {
+            return Ok(0);
+        }
+
+        let now = Utc::now();
+        let cutoff = now - Duration::minutes(grace_minutes);
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // Appointments first, while the consultations still read as waiting; the join
+        // locks those consultation rows so a call starting now waits for this sweep
+        sqlx::query(
+            r#"
+            UPDATE appointments a
+            JOIN video_consultations vc ON vc.appointment_id = a.id
+            SET a.status = 'no_show', a.updated_at = ?
+            WHERE vc.status = 'waiting' AND vc.scheduled_start_time < ?
+              AND a.status IN ('pending', 'confirmed')
+            "#,
+        )
+        .bind(now)
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let result = sqlx::query(
+            r#"
+            UPDATE video_consultations
+            SET status = 'no_show', updated_at = ?
+            WHERE status = 'waiting' AND scheduled_start_time < ?
+            "#,
+        )
+        .bind(now)
+        .bind(cutoff)
+        .execute(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        tx.commit()
             .await
             .map_err(|e| AppError::DatabaseError(e.to_string()))?;
 
         Ok(result.rows_affected())
     }
+
+    /// 为即将开始的视频问诊向患者发送提醒（通知 + WebSocket 推送）
+    pub async fn send_consultation_reminders(
+        db: &DbPool,
+        ws_manager: &WebSocketManager,
+    ) -> Result<u64, AppError> {
+        let window_minutes =
+            SystemConfigService::get_i64(db, "video_call", "reminder_window_minutes", 10).await?;
+
+        let now = Utc::now();
+        let query = r#"
+            SELECT * FROM video_consultations
+            WHERE status = 'waiting' AND reminded = false
+            AND scheduled_start_time BETWEEN ? AND ?
+            ORDER BY scheduled_start_time ASC
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(now)
+            .bind(now + Duration::minutes(window_minutes))
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut reminded_count = 0;
+        for row in rows {
+            let consultation = Self::parse_consultation_row(row)?;
+
+            // Claim the reminder first so overlapping sweeps never send it twice
+            let result = sqlx::query(
+                "UPDATE video_consultations SET reminded = true WHERE id = ? AND reminded = false",
+            )
+            .bind(consultation.id.to_string())
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            if result.rows_affected() == 0 {
+                continue;
+            }
+
+            let (enabled, _, _, _) = NotificationService::should_send_notification(
+                db,
+                consultation.patient_id,
+                &NotificationType::AppointmentReminder,
+            )
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            if !enabled {
+                continue;
+            }
+
+            let join_url = format!(
+                "/api/v1/video-consultations/room/{}/join",
+                consultation.room_id
+            );
+            let minutes_left = (consultation.scheduled_start_time - now).num_minutes().max(0);
+
+            let notification = NotificationService::create_notification(
+                db,
+                CreateNotificationDto {
+                    user_id: consultation.patient_id,
+                    notification_type: NotificationType::AppointmentReminder,
+                    title: "视频问诊即将开始".to_string(),
+                    content: format!(
+                        "您的视频问诊将在{}分钟后开始，请准时进入诊室",
+                        minutes_left
+                    ),
+                    related_id: Some(consultation.id),
+                    metadata: Some(serde_json::json!({
+                        "consultation_id": consultation.id,
+                        "room_id": consultation.room_id,
+                        "join_url": join_url,
+                        "scheduled_start_time": consultation.scheduled_start_time,
+                    })),
+                },
+            )
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            ws_manager
+                .send_notification(consultation.patient_id, notification)
+                .await;
+
+            reminded_count += 1;
+        }
+
+        Ok(reminded_count)
+    }
+
+    /// 进行中的问诊超过预计时长后记录超时事件并提醒医生，每个问诊只提醒一次
+    pub async fn flag_overtime_consultations(
+        db: &DbPool,
+        ws_manager: &WebSocketManager,
+    ) -> Result<u64, AppError> {
+        let now = Utc::now();
+        let query = r#"
+            SELECT * FROM video_consultations
+            WHERE status = 'in_progress' AND overtime_notified = false
+            AND expected_duration IS NOT NULL AND actual_start_time IS NOT NULL
+            AND DATE_ADD(actual_start_time, INTERVAL expected_duration MINUTE) < ?
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(now)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut flagged_count = 0;
+        for row in rows {
+            let consultation = Self::parse_consultation_row(row)?;
+
+            // Claim first so overlapping sweeps never flag the same consultation twice
+            let result = sqlx::query(
+                r#"
+                UPDATE video_consultations SET overtime_notified = true
+                WHERE id = ? AND overtime_notified = false
+                "#,
+            )
+            .bind(consultation.id.to_string())
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            if result.rows_affected() == 0 {
+                continue;
+            }
+
+            let Some(doctor_user_id) = Self::get_doctor_user_id(db, consultation.doctor_id).await?
+            else {
+                continue;
+            };
+
+            let elapsed_minutes = consultation
+                .actual_start_time
+                .map(|start| (now - start).num_minutes())
+                .unwrap_or_default();
+
+            Self::log_event(
+                db,
+                LogEventDto {
+                    consultation_id: consultation.id,
+                    event_type: VideoEventType::Overtime,
+                    event_data: Some(serde_json::json!({
+                        "expected_duration": consultation.expected_duration,
+                        "elapsed_minutes": elapsed_minutes,
+                    })),
+                },
+                doctor_user_id,
+            )
+            .await?;
+
+            let notification = NotificationService::create_notification(
+                db,
+                CreateNotificationDto {
+                    user_id: doctor_user_id,
+                    notification_type: NotificationType::AppointmentReminder,
+                    title: "视频问诊已超时".to_string(),
+                    content: format!(
+                        "当前问诊已进行{}分钟，超过预计时长{}分钟",
+                        elapsed_minutes,
+                        consultation.expected_duration.unwrap_or_default()
+                    ),
+                    related_id: Some(consultation.id),
+                    metadata: Some(serde_json::json!({
+                        "consultation_id": consultation.id,
+                        "expected_duration": consultation.expected_duration,
+                        "elapsed_minutes": elapsed_minutes,
+                    })),
+                },
+            )
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+            ws_manager
+                .send_notification(doctor_user_id, notification)
+                .await;
+
+            flagged_count += 1;
+        }
+
+        Ok(flagged_count)
+    }
 }
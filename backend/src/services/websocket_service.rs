@@ -1,4 +1,10 @@
-use crate::AppState;
+use crate::{
+    config::database::DbPool,
+    models::video_consultation::{SendSignalDto, SignalType},
+    services::{doctor_service, video_consultation_service::VideoConsultationService},
+    utils::errors::AppError,
+    AppState,
+};
 use axum::{
     extract::{
         ws::{Message, WebSocket},
@@ -11,6 +17,7 @@ use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::{broadcast, RwLock};
 use uuid::Uuid;
+use validator::Validate;
 
 #[derive(Debug, Clone)]
 pub struct WsConnection {
@@ -67,6 +74,18 @@ pub enum WsMessage {
     VideoCallEnded {
         consultation_id: String,
     },
+    // WebRTC signaling; the sender is taken from the authenticated connection
+    Signal {
+        room_id: String,
+        #[serde(default)]
+        from_user_id: String,
+        to_user_id: String,
+        signal_type: SignalType,
+        payload: serde_json::Value,
+        // Required from clients; never echoed back on the pushed signal
+        #[serde(skip_serializing_if = "String::is_empty")]
+        token: String,
+    },
 
     // Live stream events
     LiveStreamStarted {
@@ -253,13 +272,24 @@ async fn websocket_connection(socket: WebSocket, app_state: AppState) {
 
     // Spawn task to handle incoming messages
     let user_id = user_info.0;
+    let role = user_info.1.clone();
     let ws_manager_clone = ws_manager.clone();
+    let pool = app_state.pool.clone();
+    let jwt_secret = app_state.config.jwt_secret.clone();
     let mut recv_task = tokio::spawn(async move {
         while let Some(Ok(msg)) = receiver.next().await {
             match msg {
                 Message::Text(text) => {
                     if let Ok(ws_msg) = serde_json::from_str::<WsMessage>(&text) {
-                        handle_ws_message(ws_msg, user_id, &ws_manager_clone).await;
+                        handle_ws_message(
+                            ws_msg,
+                            user_id,
+                            &role,
+                            &ws_manager_clone,
+                            &pool,
+                            &jwt_secret,
+                        )
+                        .await;
                     }
                 }
                 Message::Close(_) => break,
@@ -298,7 +328,14 @@ async fn validate_ws_token(app_state: &AppState, token: &str) -> Result<(Uuid, S
     }
 }
 
-async fn handle_ws_message(msg: WsMessage, user_id: Uuid, ws_manager: &WebSocketManager) {
+async fn handle_ws_message(
+    msg: WsMessage,
+    user_id: Uuid,
+    role: &str,
+    ws_manager: &WebSocketManager,
+    pool: &DbPool,
+    jwt_secret: &str,
+) {
     match msg {
         WsMessage::Heartbeat => {
             let _ = ws_manager
@@ -326,6 +363,54 @@ async fn handle_ws_message(msg: WsMessage, user_id: Uuid, ws_manager: &WebSocket
 
                 // Echo back to sender
                 let _ = ws_manager.send_to_user(user_id, chat_msg).await;
+
+                // Patients writing to an out-of-office doctor get an automatic reply
+                if role == "patient" {
+                    match doctor_service::send_out_of_office_reply(pool, receiver_uuid, user_id)
+                        .await
+                    {
+                        Ok(Some(notification)) => {
+                            ws_manager.send_notification(user_id, notification).await
+                        }
+                        Ok(None) => {}
+                        Err(e) => tracing::warn!("Failed to send out-of-office reply: {}", e),
+                    }
+                }
+            }
+        }
+        WsMessage::Signal {
+            room_id,
+            to_user_id,
+            signal_type,
+            payload,
+            token,
+            ..
+        } => {
+            let result = async {
+                let to_user_id = Uuid::parse_str(&to_user_id)
+                    .map_err(|_| AppError::BadRequest("Invalid target user id".to_string()))?;
+                let dto = SendSignalDto {
+                    room_id,
+                    to_user_id,
+                    signal_type,
+                    payload,
+                    token,
+                };
+                dto.validate()?;
+                VideoConsultationService::send_signal(pool, ws_manager, jwt_secret, user_id, dto)
+                    .await
+            }
+            .await;
+
+            if let Err(e) = result {
+                let _ = ws_manager
+                    .send_to_user(
+                        user_id,
+                        WsMessage::Error {
+                            message: e.to_string(),
+                        },
+                    )
+                    .await;
             }
         }
         _ => {
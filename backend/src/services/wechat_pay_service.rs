@@ -0,0 +1,484 @@
+use crate::models::payment::{BillEntry, PaymentMethod, PaymentOrder, RefundRecord};
+use crate::services::payment_gateway::{GatewayRequest, GatewayResponse, PaymentGateway};
+use crate::utils::{crypto, errors::AppError};
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{NaiveDate, SecondsFormat, Utc};
+use reqwest::Method;
+use rust_decimal::{prelude::ToPrimitive, Decimal};
+use serde_json::json;
+use std::collections::HashMap;
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// 微信支付 v3 商户配置，取自 payment_configs
+#[derive(Debug, Clone)]
+pub struct WechatPayConfig {
+    pub app_id: String,
+    pub mch_id: String,
+    /// 商户 API 证书序列号
+    pub serial_no: String,
+    /// 商户 API 证书私钥（PEM）
+    pub private_key: String,
+    pub notify_url: String,
+    /// 退款结果通知地址，未配置时只能依赖同步结果
+    pub refund_notify_url: Option<String>,
+}
+
+impl WechatPayConfig {
+    pub fn from_map(config: &HashMap<String, String>) -> Result<Self, AppError> {
+        let optional = |key: &str| {
+            config
+                .get(key)
+                .filter(|value| !value.trim().is_empty())
+                .cloned()
+        };
+        let get = |key: &str| {
+            optional(key)
+                .ok_or_else(|| AppError::InternalServerError(format!("微信支付未配置 {}", key)))
+        };
+
+        Ok(Self {
+            app_id: get("app_id")?,
+            mch_id: get("mch_id")?,
+            serial_no: get("serial_no")?,
+            private_key: get("private_key")?,
+            notify_url: get("notify_url")?,
+            refund_notify_url: optional("refund_notify_url"),
+        })
+    }
+}
+
+/// 下单方式：JSAPI（公众号/小程序，需要 openid）或 Native（扫码）
+#[derive(Debug, Clone)]
+pub enum WechatTradeType {
+    Jsapi { openid: String },
+    Native,
+}
+
+impl WechatTradeType {
+    fn path(&self) -> &'static str {
+        match self {
+            WechatTradeType::Jsapi { .. } => "/v3/pay/transactions/jsapi",
+            WechatTradeType::Native => "/v3/pay/transactions/native",
+        }
+    }
+}
+
+pub struct WechatPayService;
+
+impl WechatPayService {
+    pub const API_BASE: &'static str = "https://api.mch.weixin.qq.com";
+    const AUTH_SCHEMA: &'static str = "WECHATPAY2-SHA256-RSA2048";
+    /// 回调通知时间戳允许的最大偏差（秒），防止重放
+    const NOTIFY_MAX_SKEW_SECS: i64 = 300;
+
+    /// 签名串：每个字段后跟一个换行符
+    pub fn sign_message(parts: &[&str]) -> String {
+        parts.iter().map(|part| format!("{}\n", part)).collect()
+    }
+
+    /// 生成 APIv3 请求的 Authorization 头
+    pub fn authorization(
+        config: &WechatPayConfig,
+        method: &Method,
+        path: &str,
+        timestamp: i64,
+        nonce: &str,
+        body: &str,
+    ) -> Result<String, AppError> {
+        let message =
+            Self::sign_message(&[method.as_str(), path, &timestamp.to_string(), nonce, body]);
+        let signature = crypto::rsa_sign_sha256(&config.private_key, &message)?;
+
+        Ok(format!(
+            r#"{} mchid="{}",nonce_str="{}",signature="{}",timestamp="{}",serial_no="{}""#,
+            Self::AUTH_SCHEMA,
+            config.mch_id,
+            nonce,
+            signature,
+            timestamp,
+            config.serial_no
+        ))
+    }
+
+    /// 金额换算为分
+    pub fn amount_in_fen(amount: Decimal) -> Result<i64, AppError> {
+        (amount * Decimal::ONE_HUNDRED)
+            .round()
+            .to_i64()
+            .ok_or_else(|| AppError::BadRequest("订单金额无效".to_string()))
+    }
+
+    /// 组装下单请求体
+    pub fn transaction_request(
+        config: &WechatPayConfig,
+        order: &PaymentOrder,
+        trade_type: &WechatTradeType,
+    ) -> Result<serde_json::Value, AppError> {
+        let mut request = json!({
+            "appid": config.app_id,
+            "mchid": config.mch_id,
+            "description": order
+                .description
+                .clone()
+                .unwrap_or_else(|| format!("订单{}", order.order_no)),
+            "out_trade_no": order.order_no,
+            "time_expire": order.expire_time.to_rfc3339_opts(SecondsFormat::Secs, false),
+            "notify_url": config.notify_url,
+            "amount": {
+                "total": Self::amount_in_fen(order.amount)?,
+                "currency": order.currency,
+            },
+        });
+
+        if let WechatTradeType::Jsapi { openid } = trade_type {
+            request["payer"] = json!({ "openid": openid });
+        }
+
+        Ok(request)
+    }
+
+    /// 调用下单接口，返回微信的响应报文（JSAPI 含 prepay_id，Native 含 code_url）
+    pub async fn create_transaction(
+        gateway: &dyn PaymentGateway,
+        config: &WechatPayConfig,
+        trade_type: &WechatTradeType,
+        request: &serde_json::Value,
+    ) -> Result<serde_json::Value, AppError> {
+        Self::request(
+            gateway,
+            config,
+            Method::POST,
+            trade_type.path(),
+            Some(request),
+        )
+        .await
+    }
+
+    /// JSAPI 调起支付所需参数，paySign 对 appId、时间戳、随机串、package 签名
+    pub fn jsapi_prepay_data(
+        config: &WechatPayConfig,
+        prepay_id: &str,
+    ) -> Result<serde_json::Value, AppError> {
+        let timestamp = Utc::now().timestamp().to_string();
+        let nonce = Uuid::new_v4().simple().to_string();
+        let package = format!("prepay_id={}", prepay_id);
+        let message = Self::sign_message(&[&config.app_id, &timestamp, &nonce, &package]);
+        let pay_sign = crypto::rsa_sign_sha256(&config.private_key, &message)?;
+
+        Ok(json!({
+            "appId": config.app_id,
+            "timeStamp": timestamp,
+            "nonceStr": nonce,
+            "package": package,
+            "signType": "RSA",
+            "paySign": pay_sign,
+        }))
+    }
+
+    /// 按商户订单号查询交易，响应的 trade_state 为 SUCCESS 时表示已支付，
+    /// 报文字段与支付通知解密后的资源一致
+    pub async fn query_transaction(
+        gateway: &dyn PaymentGateway,
+        config: &WechatPayConfig,
+        order_no: &str,
+    ) -> Result<serde_json::Value, AppError> {
+        let path = format!(
+            "/v3/pay/transactions/out-trade-no/{}?mchid={}",
+            urlencoding::encode(order_no),
+            urlencoding::encode(&config.mch_id)
+        );
+        Self::request(gateway, config, Method::GET, &path, None).await
+    }
+
+    /// 组装申请退款请求体，优先使用微信支付订单号定位原交易
+    pub fn refund_request(
+        config: &WechatPayConfig,
+        order: &PaymentOrder,
+        transaction_id: Option<&str>,
+        refund: &RefundRecord,
+    ) -> Result<serde_json::Value, AppError> {
+        let mut request = json!({
+            "out_refund_no": refund.refund_no,
+            "reason": refund.refund_reason,
+            "amount": {
+                "refund": Self::amount_in_fen(refund.refund_amount)?,
+                "total": Self::amount_in_fen(order.amount)?,
+                "currency": order.currency,
+            },
+        });
+
+        match transaction_id {
+            Some(transaction_id) => request["transaction_id"] = json!(transaction_id),
+            None => request["out_trade_no"] = json!(order.order_no),
+        }
+        if let Some(notify_url) = &config.refund_notify_url {
+            request["notify_url"] = json!(notify_url);
+        }
+
+        Ok(request)
+    }
+
+    /// 调用申请退款接口，响应中的 status 为 SUCCESS、PROCESSING、ABNORMAL 或 CLOSED
+    pub async fn create_refund(
+        gateway: &dyn PaymentGateway,
+        config: &WechatPayConfig,
+        request: &serde_json::Value,
+    ) -> Result<serde_json::Value, AppError> {
+        Self::request(
+            gateway,
+            config,
+            Method::POST,
+            "/v3/refund/domestic/refunds",
+            Some(request),
+        )
+        .await
+    }
+
+    /// 申请交易账单（仅成功支付的交易），返回账单下载地址；当天没有交易时返回 None
+    pub async fn trade_bill_url(
+        gateway: &dyn PaymentGateway,
+        config: &WechatPayConfig,
+        bill_date: NaiveDate,
+    ) -> Result<Option<String>, AppError> {
+        let path = format!(
+            "/v3/bill/tradebill?bill_date={}&bill_type=SUCCESS",
+            bill_date.format("%Y-%m-%d")
+        );
+        match Self::request(gateway, config, Method::GET, &path, None).await {
+            Ok(response) => response["download_url"]
+                .as_str()
+                .map(|url| Some(url.to_string()))
+                .ok_or_else(|| AppError::PaymentGatewayError {
+                    code: "INVALID_RESPONSE".to_string(),
+                    message: "账单响应缺少 download_url".to_string(),
+                }),
+            Err(AppError::PaymentGatewayError { code, .. }) if code == "NO_STATEMENT_EXIST" => {
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// 下载账单文件。下载地址同样需要 APIv3 签名，响应为 CSV 文本
+    pub async fn download_bill(
+        gateway: &dyn PaymentGateway,
+        config: &WechatPayConfig,
+        download_url: &str,
+    ) -> Result<String, AppError> {
+        let path = download_url
+            .strip_prefix(Self::API_BASE)
+            .unwrap_or(download_url);
+        let response = Self::send_signed(gateway, config, Method::GET, path, None).await?;
+        if !response.is_success() {
+            return Err(AppError::PaymentGatewayError {
+                code: format!("HTTP_{}", response.status),
+                message: response.body,
+            });
+        }
+
+        Ok(response.body)
+    }
+
+    /// 解析交易账单 CSV：首行为表头，明细字段带 ` 前缀，"总交易单数" 之后为汇总行。
+    /// 只保留交易状态为 SUCCESS 的记录，金额取订单金额
+    pub fn parse_trade_bill(content: &str) -> Result<Vec<BillEntry>, AppError> {
+        let invalid = |message: String| AppError::PaymentGatewayError {
+            code: "INVALID_BILL".to_string(),
+            message,
+        };
+
+        let mut lines = content
+            .trim_start_matches('\u{feff}')
+            .lines()
+            .filter(|line| !line.trim().is_empty());
+        let Some(header) = lines.next() else {
+            return Ok(Vec::new());
+        };
+        let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+        let column = |name: &str| {
+            columns
+                .iter()
+                .position(|column| *column == name)
+                .ok_or_else(|| invalid(format!("账单缺少列 {}", name)))
+        };
+        let transaction_col = column("微信订单号")?;
+        let order_col = column("商户订单号")?;
+        let state_col = column("交易状态")?;
+        let amount_col = column("订单金额")?;
+
+        let mut entries = Vec::new();
+        for line in lines {
+            if line.starts_with("总交易单数") {
+                break;
+            }
+
+            let fields: Vec<&str> = line
+                .split(',')
+                .map(|field| field.trim().trim_start_matches('`'))
+                .collect();
+            let field = |index: usize| {
+                fields
+                    .get(index)
+                    .copied()
+                    .ok_or_else(|| invalid(format!("账单行格式错误: {}", line)))
+            };
+            if field(state_col)? != "SUCCESS" {
+                continue;
+            }
+
+            let amount = field(amount_col)?;
+            entries.push(BillEntry {
+                payment_method: PaymentMethod::Wechat,
+                order_no: field(order_col)?.to_string(),
+                external_transaction_id: Some(field(transaction_col)?.to_string()),
+                amount: Decimal::from_str(amount)
+                    .map_err(|_| invalid(format!("账单金额格式错误: {}", amount)))?,
+            });
+        }
+
+        Ok(entries)
+    }
+
+    /// 发送签名后的 APIv3 请求，非 2xx 响应转为 PaymentGatewayError
+    pub async fn request(
+        gateway: &dyn PaymentGateway,
+        config: &WechatPayConfig,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<serde_json::Value, AppError> {
+        let response = Self::send_signed(gateway, config, method, path, body).await?;
+
+        let payload: serde_json::Value = if response.body.trim().is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_str(&response.body).unwrap_or(serde_json::Value::Null)
+        };
+
+        if !response.is_success() {
+            let code = payload["code"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or_else(|| format!("HTTP_{}", response.status));
+            let message = payload["message"]
+                .as_str()
+                .map(str::to_string)
+                .unwrap_or(response.body);
+            return Err(AppError::PaymentGatewayError { code, message });
+        }
+
+        Ok(payload)
+    }
+
+    /// 对请求签名后发送，返回原始响应
+    async fn send_signed(
+        gateway: &dyn PaymentGateway,
+        config: &WechatPayConfig,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<GatewayResponse, AppError> {
+        let body = body.map(|body| body.to_string());
+        let timestamp = Utc::now().timestamp();
+        let nonce = Uuid::new_v4().simple().to_string();
+        let authorization = Self::authorization(
+            config,
+            &method,
+            path,
+            timestamp,
+            &nonce,
+            body.as_deref().unwrap_or(""),
+        )?;
+
+        gateway
+            .send(GatewayRequest {
+                method,
+                url: format!("{}{}", Self::API_BASE, path),
+                headers: vec![
+                    ("Authorization".to_string(), authorization),
+                    ("Accept".to_string(), "application/json".to_string()),
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                ],
+                body,
+            })
+            .await
+    }
+
+    /// 校验回调通知签名：使用微信支付平台公钥验证 Wechatpay-Signature，
+    /// 签名串为 时间戳\n随机串\n报文主体\n。`headers` 的键为小写请求头名
+    pub fn verify_notification(
+        platform_public_key: &str,
+        platform_serial_no: Option<&str>,
+        headers: &HashMap<String, String>,
+        body: &str,
+        now: i64,
+    ) -> Result<bool, AppError> {
+        let (Some(timestamp), Some(nonce), Some(signature)) = (
+            headers.get("wechatpay-timestamp"),
+            headers.get("wechatpay-nonce"),
+            headers.get("wechatpay-signature"),
+        ) else {
+            return Ok(false);
+        };
+
+        if let Some(expected_serial) = platform_serial_no {
+            if headers.get("wechatpay-serial").map(String::as_str) != Some(expected_serial) {
+                return Ok(false);
+            }
+        }
+
+        match timestamp.parse::<i64>() {
+            Ok(sent_at) if (now - sent_at).abs() <= Self::NOTIFY_MAX_SKEW_SECS => {}
+            _ => return Ok(false),
+        }
+
+        let message = Self::sign_message(&[timestamp, nonce, body]);
+        crypto::rsa_verify_sha256(platform_public_key, &message, signature)
+    }
+
+    /// 解密回调通知的 resource：AEAD_AES_256_GCM，密钥为商户 APIv3 密钥，
+    /// 附加数据为 associated_data，返回解密后的业务报文（支付或退款结果）
+    pub fn decrypt_resource(
+        api_v3_key: &str,
+        resource: &serde_json::Value,
+    ) -> Result<serde_json::Value, AppError> {
+        let invalid = || AppError::BadRequest("回调报文解密失败".to_string());
+
+        if resource["algorithm"].as_str() != Some("AEAD_AES_256_GCM") {
+            return Err(AppError::BadRequest("不支持的回调加密算法".to_string()));
+        }
+        if api_v3_key.len() != 32 {
+            return Err(AppError::InternalServerError(
+                "微信支付 api_v3_key 须为 32 字节".to_string(),
+            ));
+        }
+
+        let nonce = resource["nonce"].as_str().ok_or_else(invalid)?;
+        if nonce.len() != 12 {
+            return Err(invalid());
+        }
+        let ciphertext = resource["ciphertext"]
+            .as_str()
+            .and_then(|ciphertext| STANDARD.decode(ciphertext).ok())
+            .ok_or_else(invalid)?;
+        let associated_data = resource["associated_data"].as_str().unwrap_or_default();
+
+        let cipher = Aes256Gcm::new_from_slice(api_v3_key.as_bytes()).map_err(|_| invalid())?;
+        let plaintext = cipher
+            .decrypt(
+                Nonce::from_slice(nonce.as_bytes()),
+                Payload {
+                    msg: &ciphertext,
+                    aad: associated_data.as_bytes(),
+                },
+            )
+            .map_err(|_| invalid())?;
+
+        serde_json::from_slice(&plaintext).map_err(|_| invalid())
+    }
+}
@@ -0,0 +1,670 @@
+use crate::config::database::DbPool;
+use crate::models::notification::{CreateNotificationDto, NotificationType};
+use crate::models::withdrawal::*;
+use crate::services::audit_service::AuditService;
+use crate::services::notification_service::NotificationService;
+use crate::services::system_config_service::SystemConfigService;
+use crate::utils::crypto;
+use crate::utils::errors::AppError;
+use chrono::{DateTime, Datelike, Utc};
+use rust_decimal::Decimal;
+use sqlx::{MySql, Row, Transaction};
+use uuid::Uuid;
+
+pub struct WithdrawalService;
+
+impl WithdrawalService {
+    // Payout accounts
+    pub async fn create_payout_account(
+        db: &DbPool,
+        doctor_id: Uuid,
+        dto: CreatePayoutAccountDto,
+    ) -> Result<PayoutAccount, AppError> {
+        let account_number: String = dto
+            .account_number
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+
+        if !account_number.chars().all(|c| c.is_ascii_digit()) {
+            return Err(AppError::ValidationError("银行账号格式不正确".to_string()));
+        }
+
+        let account_id = Uuid::new_v4();
+        let encrypted = crypto::encrypt(&account_number)?;
+        let last4: String = account_number
+            .chars()
+            .skip(account_number.len().saturating_sub(4))
+            .collect();
+
+        let query = r#"
+            INSERT INTO doctor_payout_accounts (
+                id, doctor_id, bank_name, account_holder,
+                account_number_encrypted, account_number_last4,
+                verification_status, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, 'pending', ?, ?)
+        "#;
+
+        let now = Utc::now();
+        sqlx::query(query)
+            .bind(account_id.to_string())
+            .bind(doctor_id.to_string())
+            .bind(&dto.bank_name)
+            .bind(&dto.account_holder)
+            .bind(&encrypted)
+            .bind(&last4)
+            .bind(now)
+            .bind(now)
+            .execute(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::get_payout_account(db, account_id).await
+    }
+
+    pub async fn get_payout_account(
+        db: &DbPool,
+        account_id: Uuid,
+    ) -> Result<PayoutAccount, AppError> {
+        let query = r#"
+            SELECT * FROM doctor_payout_accounts WHERE id = ?
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(account_id.to_string())
+            .fetch_one(db)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => AppError::NotFound("收款账户不存在".to_string()),
+                _ => AppError::DatabaseError(e.to_string()),
+            })?;
+
+        Self::parse_payout_account_row(row)
+    }
+
+    pub async fn list_payout_accounts(
+        db: &DbPool,
+        doctor_id: Uuid,
+    ) -> Result<Vec<PayoutAccount>, AppError> {
+        let query = r#"
+            SELECT * FROM doctor_payout_accounts
+            WHERE doctor_id = ?
+            ORDER BY created_at DESC
+        "#;
+
+        let rows = sqlx::query(query)
+            .bind(doctor_id.to_string())
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut accounts = Vec::new();
+        for row in rows {
+            accounts.push(Self::parse_payout_account_row(row)?);
+        }
+        Ok(accounts)
+    }
+
+    /// 解密完整银行账号，仅供管理员打款时使用
+    pub async fn reveal_account_number(db: &DbPool, account_id: Uuid) -> Result<String, AppError> {
+        let encrypted: String = sqlx::query_scalar(
+            "SELECT account_number_encrypted FROM doctor_payout_accounts WHERE id = ?",
+        )
+        .bind(account_id.to_string())
+        .fetch_one(db)
+        .await
+        .map_err(|e| match e {
+            sqlx::Error::RowNotFound => AppError::NotFound("收款账户不存在".to_string()),
+            _ => AppError::DatabaseError(e.to_string()),
+        })?;
+
+        crypto::decrypt(&encrypted)
+    }
+
+    pub async fn verify_payout_account(
+        db: &DbPool,
+        account_id: Uuid,
+        dto: VerifyPayoutAccountDto,
+        admin_id: Uuid,
+    ) -> Result<PayoutAccount, AppError> {
+        let account = Self::get_payout_account(db, account_id).await?;
+        let status = if dto.approved { "verified" } else { "rejected" };
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let query = r#"
+            UPDATE doctor_payout_accounts
+            SET verification_status = ?, verified_by = ?, verified_at = ?, updated_at = ?
+            WHERE id = ?
+        "#;
+
+        let now = Utc::now();
+        sqlx::query(query)
+            .bind(status)
+            .bind(admin_id.to_string())
+            .bind(now)
+            .bind(now)
+            .bind(account_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        AuditService::log(
+            &mut *tx,
+            admin_id,
+            "payout_account.verify",
+            "doctor_payout_account",
+            account_id,
+            Some(serde_json::json!({ "status": status })),
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::notify_doctor(
+            db,
+            account.doctor_id,
+            "收款账户审核结果",
+            if dto.approved {
+                format!(
+                    "您尾号{}的收款账户已通过审核",
+                    account
+                        .masked_account_number
+                        .rsplit(' ')
+                        .next()
+                        .unwrap_or_default()
+                )
+            } else {
+                "您的收款账户未通过审核，请核对后重新提交".to_string()
+            },
+            account_id,
+        )
+        .await?;
+
+        Self::get_payout_account(db, account_id).await
+    }
+
+    // Earnings
+    pub async fn get_earnings_summary(
+        db: &DbPool,
+        doctor_id: Uuid,
+    ) -> Result<DoctorEarningsSummary, AppError> {
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let summary = Self::get_earnings_summary_tx(&mut tx, doctor_id).await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(summary)
+    }
+
+    /// 已结算收益 = 预约已完成的收益明细净额（已扣除平台抽成和退款冲减）；冻结 = 待处理提现；
+    /// 可提现 = 已结算 - 冻结 - 已提现
+    async fn get_earnings_summary_tx(
+        tx: &mut Transaction<'_, MySql>,
+        doctor_id: Uuid,
+    ) -> Result<DoctorEarningsSummary, AppError> {
+        let settled_earnings: Decimal = sqlx::query_scalar(
+            r#"
+            SELECT COALESCE(SUM(e.net_amount), 0)
+            FROM doctor_earnings e
+            JOIN appointments a ON e.appointment_id = a.id
+            WHERE e.doctor_id = ? AND a.status = 'completed'
+            "#,
+        )
+        .bind(doctor_id.to_string())
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let row = sqlx::query(
+            r#"
+            SELECT
+                COALESCE(SUM(CASE WHEN status = 'pending' THEN amount END), 0) as frozen_amount,
+                COALESCE(SUM(CASE WHEN status = 'paid' THEN amount END), 0) as withdrawn_amount
+            FROM doctor_withdrawals
+            WHERE doctor_id = ?
+            "#,
+        )
+        .bind(doctor_id.to_string())
+        .fetch_one(&mut **tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let frozen_amount: Decimal = row.get("frozen_amount");
+        let withdrawn_amount: Decimal = row.get("withdrawn_amount");
+
+        Ok(DoctorEarningsSummary {
+            settled_earnings,
+            frozen_amount,
+            withdrawn_amount,
+            available_amount: (settled_earnings - frozen_amount - withdrawn_amount)
+                .max(Decimal::ZERO),
+        })
+    }
+
+    // Withdrawals
+    pub async fn create_withdrawal(
+        db: &DbPool,
+        doctor_id: Uuid,
+        actor_id: Uuid,
+        dto: CreateWithdrawalDto,
+    ) -> Result<DoctorWithdrawal, AppError> {
+        if dto.amount <= Decimal::ZERO {
+            return Err(AppError::ValidationError("提现金额必须大于0".to_string()));
+        }
+
+        let min_amount = SystemConfigService::get_value(db, "withdrawal", "min_amount")
+            .await?
+            .and_then(|v| v.trim().parse::<Decimal>().ok())
+            .unwrap_or_else(|| Decimal::from(100));
+        if dto.amount < min_amount {
+            return Err(AppError::BadRequest(format!(
+                "单笔提现金额不能低于{}元",
+                min_amount
+            )));
+        }
+
+        let monthly_limit =
+            SystemConfigService::get_i64(db, "withdrawal", "monthly_limit", 2).await?;
+
+        let account = Self::get_payout_account(db, dto.payout_account_id).await?;
+        if account.doctor_id != doctor_id {
+            return Err(AppError::Forbidden);
+        }
+        if account.verification_status != PayoutAccountStatus::Verified {
+            return Err(AppError::BadRequest("收款账户尚未通过审核".to_string()));
+        }
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        // Serialize withdrawal requests per doctor so concurrent requests can't overdraw
+        sqlx::query("SELECT id FROM doctors WHERE id = ? FOR UPDATE")
+            .bind(doctor_id.to_string())
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => AppError::NotFound("医生不存在".to_string()),
+                _ => AppError::DatabaseError(e.to_string()),
+            })?;
+
+        let monthly_count: i64 = sqlx::query_scalar(
+            r#"
+            SELECT COUNT(*) FROM doctor_withdrawals
+            WHERE doctor_id = ? AND status != 'rejected' AND created_at >= ?
+            "#,
+        )
+        .bind(doctor_id.to_string())
+        .bind(Self::month_start(Utc::now()))
+        .fetch_one(&mut *tx)
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        if monthly_count >= monthly_limit {
+            return Err(AppError::BadRequest(format!(
+                "每月最多提现{}次",
+                monthly_limit
+            )));
+        }
+
+        let summary = Self::get_earnings_summary_tx(&mut tx, doctor_id).await?;
+        if dto.amount > summary.available_amount {
+            return Err(AppError::BadRequest("可提现金额不足".to_string()));
+        }
+
+        let withdrawal_id = Uuid::new_v4();
+        let withdrawal_no = Self::generate_withdrawal_no();
+        let now = Utc::now();
+
+        let query = r#"
+            INSERT INTO doctor_withdrawals (
+                id, withdrawal_no, doctor_id, payout_account_id,
+                amount, status, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, 'pending', ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(withdrawal_id.to_string())
+            .bind(&withdrawal_no)
+            .bind(doctor_id.to_string())
+            .bind(dto.payout_account_id.to_string())
+            .bind(dto.amount)
+            .bind(now)
+            .bind(now)
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        AuditService::log(
+            &mut *tx,
+            actor_id,
+            "withdrawal.create",
+            "doctor_withdrawal",
+            withdrawal_id,
+            Some(serde_json::json!({
+                "withdrawal_no": withdrawal_no,
+                "amount": dto.amount,
+                "payout_account_id": dto.payout_account_id,
+            })),
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Self::notify_doctor(
+            db,
+            doctor_id,
+            "提现申请已提交",
+            format!(
+                "您的提现申请 {} 已提交，金额 {} 元，等待审核",
+                withdrawal_no, dto.amount
+            ),
+            withdrawal_id,
+        )
+        .await?;
+
+        Self::get_withdrawal(db, withdrawal_id).await
+    }
+
+    pub async fn get_withdrawal(
+        db: &DbPool,
+        withdrawal_id: Uuid,
+    ) -> Result<DoctorWithdrawal, AppError> {
+        let query = r#"
+            SELECT * FROM doctor_withdrawals WHERE id = ?
+        "#;
+
+        let row = sqlx::query(query)
+            .bind(withdrawal_id.to_string())
+            .fetch_one(db)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => AppError::NotFound("提现申请不存在".to_string()),
+                _ => AppError::DatabaseError(e.to_string()),
+            })?;
+
+        Self::parse_withdrawal_row(row)
+    }
+
+    pub async fn list_withdrawals(
+        db: &DbPool,
+        doctor_id: Option<Uuid>,
+        query: WithdrawalListQuery,
+    ) -> Result<Vec<DoctorWithdrawal>, AppError> {
+        let page = query.page.unwrap_or(1).max(1);
+        let page_size = query.page_size.unwrap_or(20).min(100);
+        let offset = (page - 1) * page_size;
+
+        let mut sql = String::from("SELECT * FROM doctor_withdrawals WHERE 1=1");
+        if doctor_id.is_some() {
+            sql.push_str(" AND doctor_id = ?");
+        }
+        if query.status.is_some() {
+            sql.push_str(" AND status = ?");
+        }
+        // Admin queue is processed oldest first
+        sql.push_str(" ORDER BY created_at ASC LIMIT ? OFFSET ?");
+
+        let mut query_builder = sqlx::query(&sql);
+        if let Some(doctor_id) = doctor_id {
+            query_builder = query_builder.bind(doctor_id.to_string());
+        }
+        if let Some(status) = &query.status {
+            query_builder = query_builder.bind(status);
+        }
+
+        let rows = query_builder
+            .bind(page_size)
+            .bind(offset)
+            .fetch_all(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let mut withdrawals = Vec::new();
+        for row in rows {
+            withdrawals.push(Self::parse_withdrawal_row(row)?);
+        }
+        Ok(withdrawals)
+    }
+
+    /// 管理员处理提现：打款（记录转账流水号）或驳回（释放冻结金额）
+    pub async fn process_withdrawal(
+        db: &DbPool,
+        withdrawal_id: Uuid,
+        dto: ProcessWithdrawalDto,
+        admin_id: Uuid,
+    ) -> Result<DoctorWithdrawal, AppError> {
+        if dto.approved && dto.transfer_reference.is_none() {
+            return Err(AppError::ValidationError(
+                "打款需要填写转账流水号".to_string(),
+            ));
+        }
+
+        let mut tx = db
+            .begin()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let row = sqlx::query("SELECT * FROM doctor_withdrawals WHERE id = ? FOR UPDATE")
+            .bind(withdrawal_id.to_string())
+            .fetch_one(&mut *tx)
+            .await
+            .map_err(|e| match e {
+                sqlx::Error::RowNotFound => AppError::NotFound("提现申请不存在".to_string()),
+                _ => AppError::DatabaseError(e.to_string()),
+            })?;
+        let withdrawal = Self::parse_withdrawal_row(row)?;
+
+        if withdrawal.status != WithdrawalStatus::Pending {
+            return Err(AppError::BadRequest("提现申请已处理".to_string()));
+        }
+
+        let status = if dto.approved { "paid" } else { "rejected" };
+        let query = r#"
+            UPDATE doctor_withdrawals
+            SET status = ?, transfer_reference = ?, reject_reason = ?,
+                processed_by = ?, processed_at = ?, updated_at = ?
+            WHERE id = ?
+        "#;
+
+        let now = Utc::now();
+        sqlx::query(query)
+            .bind(status)
+            .bind(if dto.approved {
+                dto.transfer_reference.as_deref()
+            } else {
+                None
+            })
+            .bind(if dto.approved {
+                None
+            } else {
+                dto.reject_reason.as_deref()
+            })
+            .bind(admin_id.to_string())
+            .bind(now)
+            .bind(now)
+            .bind(withdrawal_id.to_string())
+            .execute(&mut *tx)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        AuditService::log(
+            &mut *tx,
+            admin_id,
+            if dto.approved {
+                "withdrawal.pay"
+            } else {
+                "withdrawal.reject"
+            },
+            "doctor_withdrawal",
+            withdrawal_id,
+            Some(serde_json::json!({
+                "withdrawal_no": withdrawal.withdrawal_no,
+                "amount": withdrawal.amount,
+                "transfer_reference": dto.transfer_reference,
+                "reject_reason": dto.reject_reason,
+            })),
+        )
+        .await?;
+
+        tx.commit()
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let (title, content) = if dto.approved {
+            (
+                "提现已到账",
+                format!(
+                    "您的提现申请 {} 已打款，金额 {} 元",
+                    withdrawal.withdrawal_no, withdrawal.amount
+                ),
+            )
+        } else {
+            (
+                "提现申请被驳回",
+                format!(
+                    "您的提现申请 {} 已被驳回，冻结金额已退回可提现余额{}",
+                    withdrawal.withdrawal_no,
+                    dto.reject_reason
+                        .as_deref()
+                        .map(|r| format!("，原因：{}", r))
+                        .unwrap_or_default()
+                ),
+            )
+        };
+
+        Self::notify_doctor(db, withdrawal.doctor_id, title, content, withdrawal_id).await?;
+
+        Self::get_withdrawal(db, withdrawal_id).await
+    }
+
+    // Helper methods
+    async fn notify_doctor(
+        db: &DbPool,
+        doctor_id: Uuid,
+        title: &str,
+        content: String,
+        related_id: Uuid,
+    ) -> Result<(), AppError> {
+        let user_id: String = sqlx::query_scalar("SELECT user_id FROM doctors WHERE id = ?")
+            .bind(doctor_id.to_string())
+            .fetch_one(db)
+            .await
+            .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        let user_id = Uuid::parse_str(&user_id)
+            .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?;
+
+        NotificationService::create_notification(
+            db,
+            CreateNotificationDto {
+                user_id,
+                notification_type: NotificationType::PaymentUpdate,
+                title: title.to_string(),
+                content,
+                related_id: Some(related_id),
+                metadata: None,
+            },
+        )
+        .await
+        .map_err(|e| AppError::DatabaseError(e.to_string()))?;
+
+        Ok(())
+    }
+
+    fn month_start(now: DateTime<Utc>) -> DateTime<Utc> {
+        now.date_naive()
+            .with_day(1)
+            .and_then(|d| d.and_hms_opt(0, 0, 0))
+            .map(|d| d.and_utc())
+            .unwrap_or(now)
+    }
+
+    fn generate_withdrawal_no() -> String {
+        let timestamp = Utc::now().format("%Y%m%d%H%M%S");
+        let random = Uuid::new_v4().simple().to_string();
+        format!("WDR{}{}", timestamp, &random[..6].to_uppercase())
+    }
+
+    fn parse_payout_account_row(row: sqlx::mysql::MySqlRow) -> Result<PayoutAccount, AppError> {
+        let status_str: String = row.get("verification_status");
+        let verification_status = match status_str.as_str() {
+            "pending" => PayoutAccountStatus::Pending,
+            "verified" => PayoutAccountStatus::Verified,
+            "rejected" => PayoutAccountStatus::Rejected,
+            _ => {
+                return Err(AppError::BadRequest(
+                    "Invalid payout account status".to_string(),
+                ))
+            }
+        };
+
+        let last4: String = row.get("account_number_last4");
+
+        Ok(PayoutAccount {
+            id: Uuid::parse_str(row.get("id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            doctor_id: Uuid::parse_str(row.get("doctor_id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            bank_name: row.get("bank_name"),
+            account_holder: row.get("account_holder"),
+            masked_account_number: crypto::mask_account_number(&last4),
+            verification_status,
+            verified_by: row
+                .get::<Option<String>, _>("verified_by")
+                .and_then(|s| Uuid::parse_str(&s).ok()),
+            verified_at: row.get("verified_at"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+
+    fn parse_withdrawal_row(row: sqlx::mysql::MySqlRow) -> Result<DoctorWithdrawal, AppError> {
+        let status_str: String = row.get("status");
+        let status = match status_str.as_str() {
+            "pending" => WithdrawalStatus::Pending,
+            "paid" => WithdrawalStatus::Paid,
+            "rejected" => WithdrawalStatus::Rejected,
+            _ => {
+                return Err(AppError::BadRequest(
+                    "Invalid withdrawal status".to_string(),
+                ))
+            }
+        };
+
+        Ok(DoctorWithdrawal {
+            id: Uuid::parse_str(row.get("id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            withdrawal_no: row.get("withdrawal_no"),
+            doctor_id: Uuid::parse_str(row.get("doctor_id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            payout_account_id: Uuid::parse_str(row.get("payout_account_id"))
+                .map_err(|_| AppError::BadRequest("Invalid UUID".to_string()))?,
+            amount: row.get("amount"),
+            status,
+            transfer_reference: row.get("transfer_reference"),
+            reject_reason: row.get("reject_reason"),
+            processed_by: row
+                .get::<Option<String>, _>("processed_by")
+                .and_then(|s| Uuid::parse_str(&s).ok()),
+            processed_at: row.get("processed_at"),
+            created_at: row.get("created_at"),
+            updated_at: row.get("updated_at"),
+        })
+    }
+}
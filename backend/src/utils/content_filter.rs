@@ -0,0 +1,139 @@
+use crate::config::database::DbPool;
+use crate::services::system_config_service::SystemConfigService;
+use anyhow::{anyhow, Result};
+use sqlx::Row;
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::{Duration, Instant};
+
+/// 屏蔽词列表的缓存时间，管理员修改词库后最迟在该时间后生效
+const KEYWORD_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// 用户内容类型，每种类型可单独配置命中屏蔽词后的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContentKind {
+    Review,
+    CirclePost,
+    Comment,
+}
+
+impl ContentKind {
+    fn config_key(&self) -> &'static str {
+        match self {
+            ContentKind::Review => "review",
+            ContentKind::CirclePost => "circle_post",
+            ContentKind::Comment => "comment",
+        }
+    }
+
+    fn default_action(&self) -> FilterAction {
+        match self {
+            ContentKind::Review => FilterAction::Mask,
+            ContentKind::CirclePost | ContentKind::Comment => FilterAction::Reject,
+        }
+    }
+}
+
+/// 命中屏蔽词后的处理方式
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterAction {
+    /// 拒绝提交
+    Reject,
+    /// 将命中的词替换为 `*` 后保存
+    Mask,
+}
+
+impl FilterAction {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim() {
+            "reject" => Some(FilterAction::Reject),
+            "mask" => Some(FilterAction::Mask),
+            _ => None,
+        }
+    }
+}
+
+type KeywordCache = RwLock<Option<(Instant, Arc<Vec<String>>)>>;
+
+static KEYWORDS: OnceLock<KeywordCache> = OnceLock::new();
+
+fn keyword_cache() -> &'static KeywordCache {
+    KEYWORDS.get_or_init(|| RwLock::new(None))
+}
+
+/// 按内容类型的配置过滤文本：拒绝模式下命中即报错，屏蔽模式下返回打码后的文本
+pub async fn filter_text(pool: &DbPool, kind: ContentKind, text: &str) -> Result<String> {
+    let keywords = active_keywords(pool).await?;
+    let masked = mask_keywords(text, &keywords);
+    if masked == text {
+        return Ok(masked);
+    }
+
+    let action = SystemConfigService::get_value(pool, "content_filter", kind.config_key())
+        .await
+        .map_err(|e| anyhow!("Failed to load content filter config: {}", e))?
+        .and_then(|value| FilterAction::parse(&value))
+        .unwrap_or_else(|| kind.default_action());
+
+    match action {
+        FilterAction::Reject => Err(anyhow!("Content contains sensitive words")),
+        FilterAction::Mask => Ok(masked),
+    }
+}
+
+/// 丢弃缓存的屏蔽词，下次过滤时从数据库重新加载
+pub fn reload_keywords() {
+    *keyword_cache().write().unwrap() = None;
+}
+
+async fn active_keywords(pool: &DbPool) -> Result<Arc<Vec<String>>> {
+    if let Some((loaded_at, keywords)) = keyword_cache().read().unwrap().as_ref() {
+        if loaded_at.elapsed() < KEYWORD_CACHE_TTL {
+            return Ok(keywords.clone());
+        }
+    }
+
+    let keywords: Vec<String> =
+        sqlx::query("SELECT word FROM banned_keywords WHERE is_active = TRUE")
+            .fetch_all(pool)
+            .await?
+            .into_iter()
+            .map(|row| row.get::<String, _>("word"))
+            .filter(|word| !word.trim().is_empty())
+            .collect();
+
+    let keywords = Arc::new(keywords);
+    *keyword_cache().write().unwrap() = Some((Instant::now(), keywords.clone()));
+
+    Ok(keywords)
+}
+
+/// 将文本中所有命中的屏蔽词（不区分大小写）逐字替换为 `*`
+pub fn mask_keywords(text: &str, keywords: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let lowered: Vec<char> = chars.iter().map(|c| lower_char(*c)).collect();
+    let mut masked = vec![false; chars.len()];
+
+    for keyword in keywords {
+        let needle: Vec<char> = keyword.chars().map(lower_char).collect();
+        if needle.is_empty() || needle.len() > lowered.len() {
+            continue;
+        }
+
+        for start in 0..=(lowered.len() - needle.len()) {
+            if lowered[start..start + needle.len()] == needle[..] {
+                masked[start..start + needle.len()].fill(true);
+            }
+        }
+    }
+
+    chars
+        .iter()
+        .zip(masked)
+        .map(|(c, hit)| if hit { '*' } else { *c })
+        .collect()
+}
+
+/// 逐字符比较，保证打码后的字符位置与原文一一对应
+fn lower_char(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
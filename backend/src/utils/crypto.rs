@@ -0,0 +1,148 @@
+use crate::utils::errors::AppError;
+use aes_gcm::{
+    aead::{Aead, AeadCore, KeyInit, OsRng},
+    Aes256Gcm, Key, Nonce,
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rsa::{
+    pkcs1::{DecodeRsaPrivateKey, DecodeRsaPublicKey},
+    pkcs1v15::{Signature, SigningKey, VerifyingKey},
+    pkcs8::{DecodePrivateKey, DecodePublicKey},
+    signature::{SignatureEncoding, Signer, Verifier},
+    RsaPrivateKey, RsaPublicKey,
+};
+use sha2::Sha256;
+
+const NONCE_LEN: usize = 12;
+
+/// 解析敏感字段加密密钥，须为 Base64 编码的 32 字节随机密钥（AES-256）
+pub fn parse_data_encryption_key(encoded: &str) -> Result<Key<Aes256Gcm>, AppError> {
+    let raw = STANDARD
+        .decode(encoded.trim())
+        .map_err(|_| AppError::InternalServerError("DATA_ENCRYPTION_KEY 格式错误".to_string()))?;
+
+    if raw.len() != 32 {
+        return Err(AppError::InternalServerError(
+            "DATA_ENCRYPTION_KEY 须为 32 字节".to_string(),
+        ));
+    }
+
+    Ok(*Key::<Aes256Gcm>::from_slice(&raw))
+}
+
+/// 从环境变量 DATA_ENCRYPTION_KEY 读取加密密钥，未配置时拒绝加解密，不回退到其他密钥
+pub fn data_encryption_key() -> Result<Key<Aes256Gcm>, AppError> {
+    let encoded = std::env::var("DATA_ENCRYPTION_KEY")
+        .map_err(|_| AppError::InternalServerError("未配置 DATA_ENCRYPTION_KEY".to_string()))?;
+
+    parse_data_encryption_key(&encoded)
+}
+
+/// 使用 AES-256-GCM 加密敏感字段（如银行卡号），返回 base64(nonce || ciphertext || tag)
+pub fn encrypt(plaintext: &str) -> Result<String, AppError> {
+    let cipher = Aes256Gcm::new(&data_encryption_key()?);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| AppError::InternalServerError("加密失败".to_string()))?;
+
+    let mut out = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+    out.extend_from_slice(&nonce);
+    out.extend_from_slice(&ciphertext);
+
+    Ok(STANDARD.encode(out))
+}
+
+/// 解密由 encrypt 生成的密文，密文被篡改或密钥不匹配时返回错误
+pub fn decrypt(encoded: &str) -> Result<String, AppError> {
+    let raw = STANDARD
+        .decode(encoded)
+        .map_err(|_| AppError::InternalServerError("密文格式错误".to_string()))?;
+
+    if raw.len() < NONCE_LEN {
+        return Err(AppError::InternalServerError("密文格式错误".to_string()));
+    }
+
+    let cipher = Aes256Gcm::new(&data_encryption_key()?);
+    let (nonce, ciphertext) = raw.split_at(NONCE_LEN);
+
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| AppError::InternalServerError("密文校验失败".to_string()))?;
+
+    String::from_utf8(plaintext)
+        .map_err(|_| AppError::InternalServerError("密文校验失败".to_string()))
+}
+
+/// 账号脱敏，仅保留末四位
+pub fn mask_account_number(account_number: &str) -> String {
+    let digits: Vec<char> = account_number
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+    let tail: String = digits.iter().skip(digits.len().saturating_sub(4)).collect();
+    format!("**** **** **** {}", tail)
+}
+
+fn decode_key_der(key: &str) -> Result<Vec<u8>, AppError> {
+    let body: String = key.chars().filter(|c| !c.is_whitespace()).collect();
+    STANDARD
+        .decode(body)
+        .map_err(|_| AppError::InternalServerError("RSA 密钥格式错误".to_string()))
+}
+
+/// 解析 RSA 私钥，支持 PKCS#8 / PKCS#1 的 PEM，以及支付宝常见的不带头尾的 Base64
+fn parse_rsa_private_key(key: &str) -> Result<RsaPrivateKey, AppError> {
+    let key = key.trim();
+    let parsed = if key.starts_with("-----BEGIN") {
+        RsaPrivateKey::from_pkcs8_pem(key)
+            .ok()
+            .or_else(|| RsaPrivateKey::from_pkcs1_pem(key).ok())
+    } else {
+        let der = decode_key_der(key)?;
+        RsaPrivateKey::from_pkcs8_der(&der)
+            .ok()
+            .or_else(|| RsaPrivateKey::from_pkcs1_der(&der).ok())
+    };
+    parsed.ok_or_else(|| AppError::InternalServerError("RSA 私钥格式错误".to_string()))
+}
+
+/// 解析 RSA 公钥，支持 SPKI / PKCS#1 的 PEM，以及不带头尾的 Base64
+fn parse_rsa_public_key(key: &str) -> Result<RsaPublicKey, AppError> {
+    let key = key.trim();
+    let parsed = if key.starts_with("-----BEGIN") {
+        RsaPublicKey::from_public_key_pem(key)
+            .ok()
+            .or_else(|| RsaPublicKey::from_pkcs1_pem(key).ok())
+    } else {
+        let der = decode_key_der(key)?;
+        RsaPublicKey::from_public_key_der(&der)
+            .ok()
+            .or_else(|| RsaPublicKey::from_pkcs1_der(&der).ok())
+    };
+    parsed.ok_or_else(|| AppError::InternalServerError("RSA 公钥格式错误".to_string()))
+}
+
+/// SHA256withRSA 签名，返回 Base64 编码的签名（微信支付 v3 与支付宝 RSA2 通用）
+pub fn rsa_sign_sha256(private_key: &str, message: &str) -> Result<String, AppError> {
+    let signing_key = SigningKey::<Sha256>::new(parse_rsa_private_key(private_key)?);
+    let signature = signing_key.sign(message.as_bytes());
+    Ok(STANDARD.encode(signature.to_bytes()))
+}
+
+/// 校验 SHA256withRSA 签名，签名格式错误或不匹配时返回 false
+pub fn rsa_verify_sha256(
+    public_key: &str,
+    message: &str,
+    signature: &str,
+) -> Result<bool, AppError> {
+    let verifying_key = VerifyingKey::<Sha256>::new(parse_rsa_public_key(public_key)?);
+    let Ok(raw) = STANDARD.decode(signature.trim()) else {
+        return Ok(false);
+    };
+    let Ok(signature) = Signature::try_from(raw.as_slice()) else {
+        return Ok(false);
+    };
+    Ok(verifying_key.verify(message.as_bytes(), &signature).is_ok())
+}
@@ -12,9 +12,20 @@ pub enum AppError {
     NotFound(String),
     BadRequest(String),
     Unauthorized,
+    /// 令牌签名有效但已过期，客户端应刷新令牌
+    TokenExpired,
+    /// 令牌格式错误或被篡改，客户端应重新登录
+    InvalidToken,
     Forbidden,
     InternalServerError(String),
     ValidationError(String),
+    /// 请求过于频繁
+    TooManyRequests(String),
+    /// 支付渠道拒绝请求或调用失败，code 为渠道返回的错误码
+    PaymentGatewayError {
+        code: String,
+        message: String,
+    },
 }
 
 impl fmt::Display for AppError {
@@ -24,15 +35,33 @@ impl fmt::Display for AppError {
             AppError::NotFound(msg) => write!(f, "Not found: {}", msg),
             AppError::BadRequest(msg) => write!(f, "Bad request: {}", msg),
             AppError::Unauthorized => write!(f, "Unauthorized"),
+            AppError::TokenExpired => write!(f, "Token expired"),
+            AppError::InvalidToken => write!(f, "Invalid token"),
             AppError::Forbidden => write!(f, "Forbidden"),
             AppError::InternalServerError(msg) => write!(f, "Internal server error: {}", msg),
             AppError::ValidationError(msg) => write!(f, "Validation error: {}", msg),
+            AppError::TooManyRequests(msg) => write!(f, "Too many requests: {}", msg),
+            AppError::PaymentGatewayError { code, message } => {
+                write!(f, "Payment gateway error: [{}] {}", code, message)
+            }
         }
     }
 }
 
 impl std::error::Error for AppError {}
 
+impl AppError {
+    /// 供客户端区分处理方式的错误码
+    pub fn code(&self) -> Option<&'static str> {
+        match self {
+            AppError::TokenExpired => Some("TOKEN_EXPIRED"),
+            AppError::InvalidToken => Some("INVALID_TOKEN"),
+            AppError::PaymentGatewayError { .. } => Some("PAYMENT_GATEWAY_ERROR"),
+            _ => None,
+        }
+    }
+}
+
 impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         let (status, error_message) = match &self {
@@ -40,17 +69,32 @@ impl IntoResponse for AppError {
             AppError::NotFound(msg) => (StatusCode::NOT_FOUND, msg.clone()),
             AppError::BadRequest(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
             AppError::Unauthorized => (StatusCode::UNAUTHORIZED, "未授权".to_string()),
+            AppError::TokenExpired => (
+                StatusCode::UNAUTHORIZED,
+                "登录已过期，请刷新令牌".to_string(),
+            ),
+            AppError::InvalidToken => {
+                (StatusCode::UNAUTHORIZED, "令牌无效，请重新登录".to_string())
+            }
             AppError::Forbidden => (StatusCode::FORBIDDEN, "禁止访问".to_string()),
             AppError::InternalServerError(msg) => (StatusCode::INTERNAL_SERVER_ERROR, msg.clone()),
             AppError::ValidationError(msg) => (StatusCode::BAD_REQUEST, msg.clone()),
+            AppError::TooManyRequests(msg) => (StatusCode::TOO_MANY_REQUESTS, msg.clone()),
+            AppError::PaymentGatewayError { message, .. } => (
+                StatusCode::BAD_GATEWAY,
+                format!("支付渠道错误：{}", message),
+            ),
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "success": false,
             "message": error_message,
-        }));
+        });
+        if let Some(code) = self.code() {
+            body["code"] = json!(code);
+        }
 
-        (status, body).into_response()
+        (status, Json(body)).into_response()
     }
 }
 
@@ -68,3 +112,12 @@ impl From<validator::ValidationErrors> for AppError {
         AppError::ValidationError(err.to_string())
     }
 }
+
+impl From<jsonwebtoken::errors::Error> for AppError {
+    fn from(err: jsonwebtoken::errors::Error) -> Self {
+        match err.kind() {
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature => AppError::TokenExpired,
+            _ => AppError::InvalidToken,
+        }
+    }
+}
@@ -44,3 +44,62 @@ pub fn decode_token(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::e
     let token_data = decode::<Claims>(token, &decoding_key, &validation)?;
     Ok(token_data.claims)
 }
+
+/// 视频问诊房间令牌的受众，登录令牌的校验会拒绝携带 aud 的令牌
+pub const ROOM_TOKEN_AUDIENCE: &str = "video_room";
+
+/// 视频问诊房间令牌，只对签发时的问诊和用户有效
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RoomClaims {
+    pub consultation_id: Uuid,
+    pub sub: Uuid,
+    pub role: String,
+    pub aud: String,
+    pub exp: i64,
+    pub iat: i64,
+}
+
+impl RoomClaims {
+    pub fn new(
+        consultation_id: Uuid,
+        user_id: Uuid,
+        role: String,
+        expiration_seconds: i64,
+    ) -> Self {
+        let now = Utc::now();
+
+        Self {
+            consultation_id,
+            sub: user_id,
+            role,
+            aud: ROOM_TOKEN_AUDIENCE.to_string(),
+            exp: (now + Duration::seconds(expiration_seconds)).timestamp(),
+            iat: now.timestamp(),
+        }
+    }
+}
+
+pub fn create_room_token(
+    consultation_id: Uuid,
+    user_id: Uuid,
+    role: String,
+    secret: &str,
+    expiration: i64,
+) -> Result<String, jsonwebtoken::errors::Error> {
+    let claims = RoomClaims::new(consultation_id, user_id, role, expiration);
+    let encoding_key = EncodingKey::from_secret(secret.as_ref());
+
+    encode(&Header::default(), &claims, &encoding_key)
+}
+
+pub fn decode_room_token(
+    token: &str,
+    secret: &str,
+) -> Result<RoomClaims, jsonwebtoken::errors::Error> {
+    let decoding_key = DecodingKey::from_secret(secret.as_ref());
+    let mut validation = Validation::default();
+    validation.set_audience(&[ROOM_TOKEN_AUDIENCE]);
+
+    let token_data = decode::<RoomClaims>(token, &decoding_key, &validation)?;
+    Ok(token_data.claims)
+}
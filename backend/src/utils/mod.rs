@@ -1,3 +1,5 @@
+pub mod content_filter;
+pub mod crypto;
 pub mod errors;
 pub mod jwt;
 pub mod password;
@@ -1,5 +1,8 @@
 use backend::{
-    config::database, routes, services::websocket_service::WebSocketManager, AppState, Config,
+    config::database,
+    routes,
+    services::{payment_gateway::HttpPaymentGateway, websocket_service::WebSocketManager},
+    AppState, Config,
 };
 
 use axum::Router;
@@ -18,6 +21,7 @@ async fn main() {
         redis: None,
         s3_client: None,
         ws_manager: Arc::new(WebSocketManager::new()),
+        payment_gateway: Arc::new(HttpPaymentGateway::new()),
     };
 
     let _app: Router<AppState> = Router::new()
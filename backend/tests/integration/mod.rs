@@ -2,21 +2,27 @@ pub mod test_appointment;
 pub mod test_auth;
 pub mod test_circle;
 pub mod test_circle_post;
+pub mod test_cleanup;
 pub mod test_content;
 pub mod test_department;
 pub mod test_doctor;
+pub mod test_doctor_earnings;
 pub mod test_file_storage;
 pub mod test_file_upload;
 pub mod test_file_upload_simple;
+pub mod test_health;
+pub mod test_job_lock;
 pub mod test_live_stream;
 pub mod test_notification;
 pub mod test_patient_group;
 pub mod test_patient_profile;
 pub mod test_payment;
+pub mod test_payment_gateway;
 pub mod test_prescription;
 pub mod test_redis_cache;
 pub mod test_review;
 pub mod test_statistics;
+pub mod test_system_config;
 pub mod test_template;
 pub mod test_user;
 pub mod test_video_consultation;
@@ -36,10 +36,12 @@ async fn test_create_appointment() {
     let tomorrow = Utc::now() + Duration::days(1);
     let appointment_dto = CreateAppointmentDto {
         patient_id: patient_user_id,
+        patient_profile_id: None,
         doctor_id,
         appointment_date: tomorrow,
         time_slot: "09:00-10:00".to_string(),
         visit_type: VisitType::Offline,
+        address_id: None,
         symptoms: "头痛、失眠".to_string(),
         has_visited_before: false,
     };
@@ -72,10 +74,12 @@ async fn test_list_appointments() {
     for i in 0..3 {
         let appointment = CreateAppointmentDto {
             patient_id: patient_user_id,
+            patient_profile_id: None,
             doctor_id,
             appointment_date: Utc::now() + Duration::days(i + 1),
             time_slot: format!("{}:00-{}:00", 9 + i, 10 + i),
             visit_type: VisitType::Offline,
+            address_id: None,
             symptoms: "测试症状".to_string(),
             has_visited_before: false,
         };
@@ -110,10 +114,12 @@ async fn test_get_appointment_by_id() {
 
     let appointment_dto = CreateAppointmentDto {
         patient_id: patient_user_id,
+        patient_profile_id: None,
         doctor_id,
         appointment_date: Utc::now() + Duration::days(1),
         time_slot: "09:00-10:00".to_string(),
         visit_type: VisitType::Offline,
+        address_id: None,
         symptoms: "测试症状".to_string(),
         has_visited_before: false,
     };
@@ -151,10 +157,12 @@ async fn test_update_appointment() {
 
     let appointment_dto = CreateAppointmentDto {
         patient_id: patient_user_id,
+        patient_profile_id: None,
         doctor_id,
         appointment_date: Utc::now() + Duration::days(1),
         time_slot: "09:00-10:00".to_string(),
         visit_type: VisitType::Offline,
+        address_id: None,
         symptoms: "原始症状".to_string(),
         has_visited_before: false,
     };
@@ -199,10 +207,12 @@ async fn test_cancel_appointment() {
 
     let appointment_dto = CreateAppointmentDto {
         patient_id: patient_user_id,
+        patient_profile_id: None,
         doctor_id,
         appointment_date: Utc::now() + Duration::days(1),
         time_slot: "09:00-10:00".to_string(),
         visit_type: VisitType::Offline,
+        address_id: None,
         symptoms: "测试症状".to_string(),
         has_visited_before: false,
     };
@@ -245,10 +255,12 @@ async fn test_get_doctor_appointments() {
     for i in 0..3 {
         let appointment_dto = CreateAppointmentDto {
             patient_id: patient_user_id,
+            patient_profile_id: None,
             doctor_id,
             appointment_date: Utc::now() + Duration::days(i + 1),
             time_slot: format!("{}:00-{}:00", 9 + i, 10 + i),
             visit_type: VisitType::Offline,
+            address_id: None,
             symptoms: "测试症状".to_string(),
             has_visited_before: false,
         };
@@ -292,10 +304,12 @@ async fn test_get_patient_appointments() {
     for i in 0..2 {
         let appointment_dto = CreateAppointmentDto {
             patient_id: patient_user_id,
+            patient_profile_id: None,
             doctor_id,
             appointment_date: Utc::now() + Duration::days(i + 1),
             time_slot: format!("{}:00-{}:00", 9 + i, 10 + i),
             visit_type: VisitType::Offline,
+            address_id: None,
             symptoms: "测试症状".to_string(),
             has_visited_before: false,
         };
@@ -372,10 +386,12 @@ async fn test_appointment_authorization() {
     // Patient 1 creates an appointment
     let appointment_dto = CreateAppointmentDto {
         patient_id: patient1_user_id,
+        patient_profile_id: None,
         doctor_id,
         appointment_date: Utc::now() + Duration::days(1),
         time_slot: "09:00-10:00".to_string(),
         visit_type: VisitType::Offline,
+        address_id: None,
         symptoms: "测试症状".to_string(),
         has_visited_before: false,
     };
@@ -430,10 +446,12 @@ async fn test_appointment_conflict() {
     // Create first appointment
     let appointment_dto = CreateAppointmentDto {
         patient_id: patient_user_id,
+        patient_profile_id: None,
         doctor_id,
         appointment_date,
         time_slot: time_slot.clone(),
         visit_type: VisitType::Offline,
+        address_id: None,
         symptoms: "测试症状1".to_string(),
         has_visited_before: false,
     };
@@ -447,10 +465,12 @@ async fn test_appointment_conflict() {
     // Try to create conflicting appointment (same doctor, date, and time)
     let conflicting_appointment = CreateAppointmentDto {
         patient_id: patient_user_id,
+        patient_profile_id: None,
         doctor_id,
         appointment_date,
         time_slot,
         visit_type: VisitType::Offline,
+        address_id: None,
         symptoms: "测试症状2".to_string(),
         has_visited_before: false,
     };
@@ -472,3 +492,731 @@ async fn test_appointment_conflict() {
         .unwrap()
         .contains("Time slot is not available"));
 }
+
+async fn create_family_profile(app: &mut TestApp, token: &str, name: &str) -> String {
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/patient-profiles",
+            json!({
+                "name": name,
+                "phone": "13900139000",
+                "gender": "女",
+                "birthday": "1950-03-01",
+                "relationship": "family"
+            }),
+            token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "create profile failed: {:?}", body);
+    assert!(body["data"]["id_number"].is_null());
+    body["data"]["id"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_book_for_family_profile_flows_into_consultation() {
+    let mut app = TestApp::new().await;
+
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (other_user_id, other_account, other_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let other_token = get_auth_token(&mut app, &other_account, &other_password).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    let profile_id = create_family_profile(&mut app, &patient_token, "王奶奶").await;
+
+    // Another account cannot book against someone else's profile
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/appointments",
+            json!({
+                "patient_id": other_user_id,
+                "profile_id": profile_id,
+                "doctor_id": doctor_id,
+                "appointment_date": (Utc::now() + Duration::days(1)).to_rfc3339(),
+                "time_slot": "14:00-15:00",
+                "visit_type": "online_video",
+                "symptoms": "头晕",
+                "has_visited_before": false
+            }),
+            &other_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/appointments",
+            json!({
+                "patient_id": patient_user_id,
+                "profile_id": profile_id,
+                "doctor_id": doctor_id,
+                "appointment_date": (Utc::now() + Duration::days(1)).to_rfc3339(),
+                "time_slot": "09:00-10:00",
+                "visit_type": "online_video",
+                "symptoms": "头晕",
+                "has_visited_before": false
+            }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["patient_profile_id"], profile_id.as_str());
+    let appointment_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // The doctor sees who the visit is for, labeled with the managing account
+    let (status, body) = app
+        .get_with_auth(
+            &format!("/api/v1/appointments/{}/patient", appointment_id),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["name"], "王奶奶");
+    assert_eq!(body["data"]["is_managed"], true);
+    assert_eq!(body["data"]["relationship"], "family");
+    assert_eq!(
+        body["data"]["managing_account"]["user_id"],
+        patient_user_id.to_string()
+    );
+    assert!(body["data"]["managing_account"]["phone"].is_string());
+
+    let (status, _) = app
+        .get_with_auth(
+            &format!("/api/v1/appointments/{}/patient", appointment_id),
+            &other_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // The consultation created from the appointment carries the profile
+    sqlx::query("UPDATE appointments SET status = 'confirmed' WHERE id = ?")
+        .bind(&appointment_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/video-consultations",
+            json!({
+                "appointment_id": appointment_id,
+                "doctor_id": doctor_id,
+                "patient_id": patient_user_id,
+                "scheduled_start_time": (Utc::now() + Duration::days(1)).to_rfc3339(),
+                "chief_complaint": "头晕"
+            }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED, "{:?}", body);
+    assert_eq!(body["data"]["patient_profile_id"], profile_id.as_str());
+}
+
+#[tokio::test]
+async fn test_transfer_appointment_to_family_profile() {
+    let mut app = TestApp::new().await;
+
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (_, other_account, other_password) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let other_token = get_auth_token(&mut app, &other_account, &other_password).await;
+
+    let mother_id = create_family_profile(&mut app, &patient_token, "李妈妈").await;
+    let father_id = create_family_profile(&mut app, &patient_token, "李爸爸").await;
+
+    // Booked under the account owner first
+    let appointment_dto = CreateAppointmentDto {
+        patient_id: patient_user_id,
+        patient_profile_id: None,
+        doctor_id,
+        appointment_date: Utc::now() + Duration::days(2),
+        time_slot: "10:00-11:00".to_string(),
+        visit_type: VisitType::Offline,
+        address_id: None,
+        symptoms: "腰痛".to_string(),
+        has_visited_before: false,
+    };
+    let (_, body) = app
+        .post_with_auth("/api/v1/appointments", appointment_dto, &patient_token)
+        .await;
+    let appointment_id = body["data"]["id"].as_str().unwrap().to_string();
+    let transfer_path = format!("/api/v1/appointments/{}/transfer", appointment_id);
+
+    // Only the managing account may transfer
+    let (status, _) = app
+        .put_with_auth(
+            &transfer_path,
+            json!({ "patient_profile_id": mother_id }),
+            &other_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = app
+        .put_with_auth(
+            &transfer_path,
+            json!({ "patient_profile_id": mother_id }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["patient_profile_id"], mother_id.as_str());
+
+    let audited: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM audit_logs WHERE action = 'appointment.transfer' AND target_id = ?",
+    )
+    .bind(&appointment_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(audited, 1);
+
+    // Once the visit time has passed the appointment can no longer move
+    sqlx::query("UPDATE appointments SET appointment_date = ? WHERE id = ?")
+        .bind((Utc::now() - Duration::minutes(5)).naive_utc())
+        .bind(&appointment_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, _) = app
+        .put_with_auth(
+            &transfer_path,
+            json!({ "patient_profile_id": father_id }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, body) = app
+        .get_with_auth(
+            &format!("/api/v1/appointments/{}", appointment_id),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(body["data"]["patient_profile_id"], mother_id.as_str());
+}
+
+#[tokio::test]
+async fn test_doctor_private_notes_hidden_from_patient() {
+    let mut app = TestApp::new().await;
+
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (other_doctor_user_id, other_doctor_account, other_doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    create_test_doctor(&app.pool, other_doctor_user_id).await;
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+    let other_doctor_token =
+        get_auth_token(&mut app, &other_doctor_account, &other_doctor_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    let appointment_dto = CreateAppointmentDto {
+        patient_id: patient_user_id,
+        patient_profile_id: None,
+        doctor_id,
+        appointment_date: Utc::now() + Duration::days(2),
+        time_slot: "09:00-10:00".to_string(),
+        visit_type: VisitType::Offline,
+        address_id: None,
+        symptoms: "反复头痛".to_string(),
+        has_visited_before: false,
+    };
+    let (status, body) = app
+        .post_with_auth("/api/v1/appointments", appointment_dto, &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let appointment_id = body["data"]["id"].as_str().unwrap().to_string();
+    let notes_path = format!("/api/v1/appointments/{}/private-notes", appointment_id);
+    let notes = json!({ "doctor_private_notes": "疑似偏头痛，待复诊确认" });
+
+    // Neither the patient nor another doctor can write notes
+    let (status, _) = app
+        .put_with_auth(&notes_path, notes.clone(), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = app
+        .put_with_auth(&notes_path, notes.clone(), &other_doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = app.put_with_auth(&notes_path, notes, &doctor_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["data"]["doctor_private_notes"],
+        "疑似偏头痛，待复诊确认"
+    );
+
+    // The treating doctor sees the note
+    let appointment_path = format!("/api/v1/appointments/{}", appointment_id);
+    let (status, body) = app.get_with_auth(&appointment_path, &doctor_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(
+        body["data"]["doctor_private_notes"],
+        "疑似偏头痛，待复诊确认"
+    );
+    assert_eq!(body["data"]["id"], appointment_id);
+
+    // No patient-facing response carries it
+    let (status, body) = app.get_with_auth(&appointment_path, &patient_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["id"], appointment_id);
+    assert!(body["data"].get("doctor_private_notes").is_none());
+
+    let (_, body) = app
+        .get_with_auth("/api/v1/appointments?page=1&page_size=10", &patient_token)
+        .await;
+    assert!(!body.to_string().contains("doctor_private_notes"));
+    assert!(!body.to_string().contains("疑似偏头痛"));
+
+    let (_, body) = app
+        .get_with_auth(
+            &format!("/api/v1/appointments/patient/{}", patient_user_id),
+            &patient_token,
+        )
+        .await;
+    assert!(!body.to_string().contains("疑似偏头痛"));
+}
+
+#[tokio::test]
+async fn test_doctor_day_schedule_ordering_and_scoping() {
+    let mut app = TestApp::new().await;
+
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (other_doctor_user_id, other_account, other_password) =
+        create_test_user(&app.pool, "doctor").await;
+    create_test_doctor(&app.pool, other_doctor_user_id).await;
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (_, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+
+    let day = (Utc::now() + Duration::days(3)).date_naive();
+    let at_noon = day.and_hms_opt(12, 0, 0).unwrap();
+
+    // Inserted out of order, plus rows that must not appear on the agenda
+    let appointments = vec![
+        ("14:00-15:00", "confirmed", "offline", at_noon),
+        ("09:00-10:00", "pending", "online_video", at_noon),
+        ("10:30-11:30", "confirmed", "offline", at_noon),
+        ("11:00-12:00", "cancelled", "offline", at_noon),
+        (
+            "09:30-10:30",
+            "confirmed",
+            "offline",
+            at_noon + Duration::days(1),
+        ),
+    ];
+
+    let mut online_appointment_id = None;
+    for (time_slot, status, visit_type, date) in appointments {
+        let appointment_id = uuid::Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO appointments (
+                id, patient_id, doctor_id, appointment_date, time_slot,
+                visit_type, symptoms, has_visited_before, status, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, '测试症状', false, ?, NOW(), NOW())
+            "#,
+        )
+        .bind(appointment_id.to_string())
+        .bind(patient_user_id.to_string())
+        .bind(doctor_id.to_string())
+        .bind(date)
+        .bind(time_slot)
+        .bind(visit_type)
+        .bind(status)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+        if visit_type == "online_video" {
+            online_appointment_id = Some(appointment_id);
+        }
+    }
+
+    let consultation_id = uuid::Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'waiting', ?, NOW(), NOW())
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(online_appointment_id.unwrap().to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_user_id.to_string())
+    .bind(format!("room_{}", consultation_id.simple()))
+    .bind(at_noon)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let schedule_path = format!(
+        "/api/v1/appointments/doctor/{}/schedule?date={}",
+        doctor_id, day
+    );
+
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+    let (status, body) = app.get_with_auth(&schedule_path, &doctor_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let items = body["data"].as_array().unwrap();
+    let slots: Vec<&str> = items
+        .iter()
+        .map(|item| item["time_slot"].as_str().unwrap())
+        .collect();
+    assert_eq!(slots, vec!["09:00-10:00", "10:30-11:30", "14:00-15:00"]);
+
+    assert_eq!(items[0]["visit_type"], "online_video");
+    assert_eq!(items[0]["consultation_id"], consultation_id.to_string());
+    assert_eq!(items[0]["consultation_status"], "waiting");
+    assert!(items[0]["patient_name"].is_string());
+    assert!(items[1]["consultation_id"].is_null());
+
+    // Other doctors and patients cannot see this doctor's agenda
+    let other_token = get_auth_token(&mut app, &other_account, &other_password).await;
+    let (status, _) = app.get_with_auth(&schedule_path, &other_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let (status, _) = app.get_with_auth(&schedule_path, &patient_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (status, body) = app.get_with_auth(&schedule_path, &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 3);
+}
+
+#[tokio::test]
+async fn test_appointment_series_skips_conflicts_and_cancels_future() {
+    let mut app = TestApp::new().await;
+
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (_, other_account, other_password) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (second_doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (second_doctor_id, _) = create_test_doctor(&app.pool, second_doctor_user_id).await;
+
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let other_token = get_auth_token(&mut app, &other_account, &other_password).await;
+
+    let start = (Utc::now() + Duration::days(7))
+        .date_naive()
+        .and_hms_opt(10, 0, 0)
+        .unwrap()
+        .and_utc();
+    let time_slot = "10:00-11:00";
+
+    let book = |patient_id, doctor_id, appointment_date| CreateAppointmentDto {
+        patient_id,
+        patient_profile_id: None,
+        doctor_id,
+        appointment_date,
+        time_slot: time_slot.to_string(),
+        visit_type: VisitType::Offline,
+        address_id: None,
+        symptoms: "复诊".to_string(),
+        has_visited_before: true,
+    };
+
+    // Week 2: the doctor is already booked by someone else
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/appointments",
+            book(patient_user_id, doctor_id, start + Duration::days(7)),
+            &other_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Week 3: the patient already sees another doctor in the same slot
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/appointments",
+            book(
+                patient_user_id,
+                second_doctor_id,
+                start + Duration::days(14),
+            ),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/appointments/series",
+            json!({
+                "patient_id": patient_user_id,
+                "doctor_id": doctor_id,
+                "start_date": start,
+                "time_slot": time_slot,
+                "visit_type": "offline",
+                "symptoms": "慢性胃炎复诊",
+                "has_visited_before": true,
+                "recurrence": "weekly",
+                "count": 4
+            }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let series_id = body["data"]["series_id"].as_str().unwrap().to_string();
+    let created: Vec<_> = body["data"]["created"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| {
+            assert_eq!(a["series_id"], series_id.as_str());
+            a["appointment_date"]
+                .as_str()
+                .unwrap()
+                .parse::<chrono::DateTime<Utc>>()
+                .unwrap()
+        })
+        .collect();
+    assert_eq!(created, vec![start, start + Duration::days(21)]);
+
+    let skipped = body["data"]["skipped"].as_array().unwrap();
+    assert_eq!(skipped.len(), 2);
+    assert_eq!(skipped[0]["reason"], "doctor_unavailable");
+    assert_eq!(
+        skipped[0]["appointment_date"]
+            .as_str()
+            .unwrap()
+            .parse::<chrono::DateTime<Utc>>()
+            .unwrap(),
+        start + Duration::days(7)
+    );
+    assert_eq!(skipped[1]["reason"], "patient_conflict");
+
+    // Only the patient who booked the series (or an admin) may cancel it
+    let (status, _) = app
+        .put_with_auth(
+            &format!("/api/v1/appointments/series/{}/cancel", series_id),
+            json!({}),
+            &other_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Pretend the first occurrence already happened; it must be left alone
+    sqlx::query(
+        "UPDATE appointments SET appointment_date = ?, status = 'completed' WHERE series_id = ? AND appointment_date = ?",
+    )
+    .bind(Utc::now() - Duration::days(1))
+    .bind(&series_id)
+    .bind(start)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let (status, body) = app
+        .put_with_auth(
+            &format!("/api/v1/appointments/series/{}/cancel", series_id),
+            json!({}),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let statuses: Vec<&str> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|a| a["status"].as_str().unwrap())
+        .collect();
+    assert_eq!(statuses, vec!["completed", "cancelled"]);
+}
+
+async fn set_appointment_price(app: &TestApp, visit_type: &str, price: &str) {
+    sqlx::query(
+        "UPDATE system_configs SET config_value = ? WHERE category = 'appointment_price' AND config_key = ?",
+    )
+    .bind(price)
+    .bind(visit_type)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_free_appointment_is_confirmed_without_payment() {
+    let mut app = TestApp::new().await;
+
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    // 线上问诊免费，线下门诊收费
+    set_appointment_price(&app, "online_video", "0").await;
+    set_appointment_price(&app, "offline", "50").await;
+
+    let mut statuses = vec![];
+    for (visit_type, time_slot) in [
+        (VisitType::OnlineVideo, "09:00-10:00"),
+        (VisitType::Offline, "10:00-11:00"),
+    ] {
+        let appointment_dto = CreateAppointmentDto {
+            patient_id: patient_user_id,
+            patient_profile_id: None,
+            doctor_id,
+            appointment_date: Utc::now() + Duration::days(1),
+            time_slot: time_slot.to_string(),
+            visit_type,
+            address_id: None,
+            symptoms: "咳嗽".to_string(),
+            has_visited_before: false,
+        };
+        let (status, body) = app
+            .post_with_auth("/api/v1/appointments", appointment_dto, &patient_token)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        statuses.push(body["data"]["status"].as_str().unwrap().to_string());
+    }
+
+    set_appointment_price(&app, "online_video", "").await;
+    set_appointment_price(&app, "offline", "").await;
+
+    assert_eq!(statuses, vec!["confirmed", "pending"]);
+
+    // 免费预约不会产生支付订单
+    let (orders,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM payment_orders WHERE user_id = ?")
+        .bind(patient_user_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(orders, 0);
+}
+
+#[tokio::test]
+async fn test_home_visit_appointment_address() {
+    let mut app = TestApp::new().await;
+
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    let book = |time_slot: &str, visit_type: VisitType, address_id| CreateAppointmentDto {
+        patient_id: patient_user_id,
+        patient_profile_id: None,
+        doctor_id,
+        appointment_date: Utc::now() + Duration::days(1),
+        time_slot: time_slot.to_string(),
+        visit_type,
+        address_id,
+        symptoms: "腰痛".to_string(),
+        has_visited_before: false,
+    };
+
+    // No saved address yet
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/appointments",
+            book("09:00-10:00", VisitType::HomeVisit, None),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let mut address_ids = vec![];
+    for detail in ["建国路1号", "建国路2号"] {
+        let (status, body) = app
+            .post_with_auth(
+                "/api/v1/patient-profiles/addresses",
+                json!({
+                    "recipient_name": "张三",
+                    "phone": "13800138000",
+                    "province": "北京市",
+                    "city": "北京市",
+                    "district": "朝阳区",
+                    "detail": detail
+                }),
+                &patient_token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        address_ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+
+    // An explicit address is attached as given
+    let chosen = address_ids[1].parse().unwrap();
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/appointments",
+            book("09:00-10:00", VisitType::HomeVisit, Some(chosen)),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["visit_type"], "home_visit");
+    assert_eq!(body["data"]["address_id"], address_ids[1]);
+
+    // Without one, the default address is used
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/appointments",
+            book("10:00-11:00", VisitType::HomeVisit, None),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["address_id"], address_ids[0]);
+
+    // Other visit types take no address
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/appointments",
+            book("11:00-12:00", VisitType::Offline, Some(chosen)),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Another patient's address is refused
+    let (_, other_account, other_password) = create_test_user(&app.pool, "patient").await;
+    let other_token = get_auth_token(&mut app, &other_account, &other_password).await;
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/appointments",
+            book("14:00-15:00", VisitType::HomeVisit, Some(chosen)),
+            &other_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // An address used by an appointment cannot be deleted
+    let (status, _) = app
+        .delete_with_auth(
+            &format!("/api/v1/patient-profiles/addresses/{}", address_ids[1]),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
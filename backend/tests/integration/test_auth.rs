@@ -159,3 +159,47 @@ async fn test_register_validation_errors() {
         .unwrap()
         .contains("Validation error"));
 }
+
+#[tokio::test]
+async fn test_expired_and_invalid_tokens_have_distinct_codes() {
+    use backend::utils::jwt::{create_token, Claims};
+    use chrono::{Duration, Utc};
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use uuid::Uuid;
+
+    let mut app = TestApp::new().await;
+    let secret = app.config.jwt_secret.clone();
+
+    // Correctly signed, but expired beyond the validation leeway
+    let mut claims = Claims::new(Uuid::new_v4(), "patient".to_string(), 3600);
+    claims.exp = (Utc::now() - Duration::hours(1)).timestamp();
+    let expired_token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(secret.as_ref()),
+    )
+    .unwrap();
+
+    let (status, body) = app
+        .get_with_auth("/api/v1/appointments", &expired_token)
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["success"], false);
+    assert_eq!(body["code"], "TOKEN_EXPIRED");
+
+    // Garbage token
+    let (status, body) = app
+        .get_with_auth("/api/v1/appointments", "not.a.token")
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "INVALID_TOKEN");
+
+    // Unexpired token signed with another secret is tampered, not expired
+    let forged_token =
+        create_token(Uuid::new_v4(), "admin".to_string(), "forged_secret", 3600).unwrap();
+    let (status, body) = app
+        .get_with_auth("/api/v1/appointments", &forged_token)
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    assert_eq!(body["code"], "INVALID_TOKEN");
+}
@@ -323,3 +323,120 @@ async fn test_circle_permissions() {
     assert_eq!(status, StatusCode::OK);
     assert_eq!(body["data"]["name"].as_str().unwrap(), "Updated Name");
 }
+
+#[tokio::test]
+async fn test_bulk_approve_join_requests() {
+    let mut app = TestApp::new().await;
+
+    let (_owner_id, owner_account, owner_password) = create_test_user(&app.pool, "patient").await;
+    let owner_token = get_auth_token(&mut app, &owner_account, &owner_password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/circles",
+            json!({
+                "name": "Private Circle",
+                "description": "Join requires approval",
+                "category": "测试",
+                "requires_approval": true
+            }),
+            &owner_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"]["requires_approval"].as_bool().unwrap());
+    let circle_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // 三个用户申请加入，申请不会直接入圈
+    let mut applicant_tokens = vec![];
+    for _ in 0..3 {
+        let (_id, account, password) = create_test_user(&app.pool, "patient").await;
+        let token = get_auth_token(&mut app, &account, &password).await;
+        let (status, body) = app
+            .post_with_auth(
+                &format!("/api/v1/circles/{}/join", circle_id),
+                json!({}),
+                &token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["message"], "Join request submitted");
+        applicant_tokens.push(token);
+    }
+
+    let requests_path = format!("/api/v1/circles/{}/join-requests", circle_id);
+    let (status, body) = app.get_with_auth(&requests_path, &owner_token).await;
+    assert_eq!(status, StatusCode::OK);
+    let request_ids: Vec<String> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(request_ids.len(), 3);
+
+    // 普通用户无权审批
+    let approve_path = format!("{}/approve", requests_path);
+    let (status, _) = app
+        .post_with_auth(
+            &approve_path,
+            json!({ "request_ids": [request_ids[0]] }),
+            &applicant_tokens[0],
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // 先批准第一个申请
+    let (status, body) = app
+        .post_with_auth(
+            &approve_path,
+            json!({ "request_ids": [request_ids[0]] }),
+            &owner_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"][0]["approved"].as_bool().unwrap());
+
+    // 批量审批时已处理的申请被跳过，其余正常通过
+    let (status, body) = app
+        .post_with_auth(
+            &approve_path,
+            json!({ "request_ids": request_ids }),
+            &owner_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let results = body["data"].as_array().unwrap();
+    assert_eq!(results.len(), 3);
+    assert!(!results[0]["approved"].as_bool().unwrap());
+    assert!(results[0]["reason"]
+        .as_str()
+        .unwrap()
+        .contains("already approved"));
+    assert!(results[1]["approved"].as_bool().unwrap());
+    assert!(results[2]["approved"].as_bool().unwrap());
+
+    let (status, body) = app
+        .get_with_auth(&format!("/api/v1/circles/{}", circle_id), &owner_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["circle"]["member_count"].as_i64().unwrap(), 4);
+
+    let (_, body) = app.get_with_auth(&requests_path, &owner_token).await;
+    assert!(body["data"].as_array().unwrap().is_empty());
+
+    // 已入圈的申请人可以直接发帖
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/posts",
+            json!({
+                "circle_id": circle_id,
+                "title": "Hello",
+                "content": "Approved member post",
+                "images": []
+            }),
+            &applicant_tokens[2],
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
@@ -402,3 +402,268 @@ async fn test_non_member_cannot_post() {
         .unwrap()
         .contains("must be a member"));
 }
+
+#[tokio::test]
+async fn test_post_edit_history() {
+    let mut app = TestApp::new().await;
+
+    let (_owner_id, owner_account, owner_password) = create_test_user(&app.pool, "patient").await;
+    let owner_token = get_auth_token(&mut app, &owner_account, &owner_password).await;
+
+    let (author_id, author_account, author_password) = create_test_user(&app.pool, "patient").await;
+    let author_token = get_auth_token(&mut app, &author_account, &author_password).await;
+
+    let (_member_id, member_account, member_password) =
+        create_test_user(&app.pool, "patient").await;
+    let member_token = get_auth_token(&mut app, &member_account, &member_password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/circles",
+            json!({
+                "name": "Edit History Circle",
+                "description": "Testing post edit history",
+                "category": "测试"
+            }),
+            &owner_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let circle_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    for token in [&author_token, &member_token] {
+        app.post_with_auth(
+            &format!("/api/v1/circles/{}/join", circle_id),
+            json!({}),
+            token,
+        )
+        .await;
+    }
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/posts",
+            json!({
+                "circle_id": circle_id,
+                "title": "Original Title",
+                "content": "Original content",
+                "images": ["original.jpg"]
+            }),
+            &author_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let post_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // Not edited yet
+    let (_, body) = app
+        .get_with_auth(
+            &format!("/api/v1/circles/{}/posts", circle_id),
+            &member_token,
+        )
+        .await;
+    assert_eq!(body["data"]["posts"][0]["is_edited"], false);
+    assert!(body["data"]["posts"][0]["edited_at"].is_null());
+
+    let (status, _) = app
+        .put_with_auth(
+            &format!("/api/v1/posts/{}", post_id),
+            json!({ "content": "Completely different content" }),
+            &author_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // The feed marks the post as edited with a timestamp
+    let (_, body) = app
+        .get_with_auth(
+            &format!("/api/v1/circles/{}/posts", circle_id),
+            &member_token,
+        )
+        .await;
+    let post = &body["data"]["posts"][0];
+    assert_eq!(post["content"], "Completely different content");
+    assert_eq!(post["is_edited"], true);
+    assert!(post["edited_at"].is_string());
+
+    // The author sees the original content in the history
+    let history_path = format!("/api/v1/posts/{}/history", post_id);
+    let (status, body) = app.get_with_auth(&history_path, &author_token).await;
+    assert_eq!(status, StatusCode::OK);
+    let history = body["data"].as_array().unwrap();
+    assert_eq!(history.len(), 1);
+    assert_eq!(history[0]["title"], "Original Title");
+    assert_eq!(history[0]["content"], "Original content");
+    assert_eq!(history[0]["images"][0], "original.jpg");
+    assert_eq!(history[0]["editor_id"], author_id.to_string());
+
+    // Circle owner can review it, ordinary members cannot
+    let (status, body) = app.get_with_auth(&history_path, &owner_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+
+    let (status, _) = app.get_with_auth(&history_path, &member_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_banned_keywords_reload_and_reject_posts() {
+    let mut app = TestApp::new().await;
+
+    let (_user_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let token = get_auth_token(&mut app, &account, &password).await;
+
+    let (_, body) = app
+        .post_with_auth(
+            "/api/v1/circles",
+            json!({
+                "name": "Keyword Test Circle",
+                "description": "Testing banned keywords",
+                "category": "测试"
+            }),
+            &token,
+        )
+        .await;
+    let circle_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // A keyword added at runtime takes effect once the list is reloaded
+    let keyword = format!("banned{}", &uuid::Uuid::new_v4().simple().to_string()[..8]);
+    sqlx::query("INSERT INTO banned_keywords (word, category) VALUES (?, '测试')")
+        .bind(&keyword)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    backend::utils::content_filter::reload_keywords();
+
+    // Circle posts are configured to reject banned keywords, matched case-insensitively
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/posts",
+            json!({
+                "circle_id": circle_id,
+                "title": "Normal Title",
+                "content": format!("This mentions {}", keyword.to_uppercase()),
+                "images": []
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"]
+        .as_str()
+        .unwrap()
+        .contains("sensitive words"));
+
+    sqlx::query("DELETE FROM banned_keywords WHERE word = ?")
+        .bind(&keyword)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    backend::utils::content_filter::reload_keywords();
+
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/posts",
+            json!({
+                "circle_id": circle_id,
+                "title": "Normal Title",
+                "content": format!("This mentions {}", keyword),
+                "images": []
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_get_member_posts_visibility() {
+    let mut app = TestApp::new().await;
+
+    let (_owner_id, owner_account, owner_password) = create_test_user(&app.pool, "patient").await;
+    let owner_token = get_auth_token(&mut app, &owner_account, &owner_password).await;
+
+    let (member_id, member_account, member_password) = create_test_user(&app.pool, "patient").await;
+    let member_token = get_auth_token(&mut app, &member_account, &member_password).await;
+
+    let (_other_id, other_account, other_password) = create_test_user(&app.pool, "patient").await;
+    let other_token = get_auth_token(&mut app, &other_account, &other_password).await;
+
+    let (_admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/circles",
+            json!({
+                "name": "Member Posts Circle",
+                "description": "Testing member post list",
+                "category": "测试"
+            }),
+            &owner_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let circle_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    for token in [&member_token, &other_token] {
+        let (status, _) = app
+            .post_with_auth(
+                &format!("/api/v1/circles/{}/join", circle_id),
+                json!({}),
+                token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    // 成员发两帖，删掉其中一帖
+    let mut post_ids = vec![];
+    for title in ["Kept Post", "Removed Post"] {
+        let (status, body) = app
+            .post_with_auth(
+                "/api/v1/posts",
+                json!({
+                    "circle_id": circle_id,
+                    "title": title,
+                    "content": "member post content",
+                    "images": []
+                }),
+                &member_token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        post_ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+
+    let (status, _) = app
+        .delete_with_auth(&format!("/api/v1/posts/{}", post_ids[1]), &member_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let path = format!("/api/v1/circles/{}/members/{}/posts", circle_id, member_id);
+
+    // 圈主可以看到包括已删除在内的全部帖子
+    let (status, body) = app.get_with_auth(&path, &owner_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["pagination"]["total"].as_i64().unwrap(), 2);
+    let posts = body["data"]["posts"].as_array().unwrap();
+    assert!(posts
+        .iter()
+        .any(|p| p["title"] == "Removed Post" && p["status"] == "deleted"));
+
+    // 平台管理员同样可以查看
+    let (status, body) = app.get_with_auth(&path, &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["pagination"]["total"].as_i64().unwrap(), 2);
+
+    // 成员本人只能看到正常帖子
+    let (status, body) = app.get_with_auth(&path, &member_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["pagination"]["total"].as_i64().unwrap(), 1);
+    assert_eq!(body["data"]["posts"][0]["title"], "Kept Post");
+
+    // 普通成员不能查看他人的帖子列表
+    let (status, _) = app.get_with_auth(&path, &other_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
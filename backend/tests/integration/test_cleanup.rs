@@ -0,0 +1,112 @@
+use crate::common::TestApp;
+use backend::services::{
+    cleanup_service::CleanupService, notification_service::NotificationService,
+    video_consultation_service::VideoConsultationService,
+};
+use backend::utils::test_helpers::create_test_user;
+use uuid::Uuid;
+
+async fn set_cleanup_config(app: &TestApp, key: &str, value: &str) {
+    sqlx::query(
+        "UPDATE system_configs SET config_value = ? WHERE category = 'cleanup' AND config_key = ?",
+    )
+    .bind(value)
+    .bind(key)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+async fn use_small_batches(app: &TestApp) {
+    set_cleanup_config(app, "batch_size", "10").await;
+    set_cleanup_config(app, "batch_pause_ms", "0").await;
+}
+
+async fn restore_batches(app: &TestApp) {
+    set_cleanup_config(app, "batch_size", "500").await;
+    set_cleanup_config(app, "batch_pause_ms", "100").await;
+}
+
+#[tokio::test]
+async fn test_old_notifications_cleaned_in_batches() {
+    let app = TestApp::new().await;
+    let (user_id, _, _) = create_test_user(&app.pool, "patient").await;
+
+    for _ in 0..25 {
+        sqlx::query(
+            r#"
+            INSERT INTO notifications (id, user_id, type, title, content, status, metadata, created_at)
+            VALUES (?, ?, 'system_announcement', '测试通知', '测试内容', 'read', '{}', DATE_SUB(NOW(), INTERVAL 400 DAY))
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    use_small_batches(&app).await;
+    let purged = NotificationService::clean_old_notifications(&app.pool).await;
+    restore_batches(&app).await;
+
+    let purged = purged.unwrap();
+    assert!(purged >= 25);
+
+    // Ten rows per statement; the last, short batch ends the sweep
+    let metrics = CleanupService::last_sweep("old_notifications").unwrap();
+    assert_eq!(metrics.batch_size, 10);
+    assert_eq!(metrics.rows, purged);
+    assert_eq!(metrics.batches, purged / 10 + 1);
+
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM notifications WHERE user_id = ?")
+        .bind(user_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+}
+
+#[tokio::test]
+async fn test_expired_signals_cleaned_in_batches() {
+    let app = TestApp::new().await;
+    let (from_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (to_user_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let room_id = format!("room_{}", Uuid::new_v4().simple());
+
+    for _ in 0..25 {
+        sqlx::query(
+            r#"
+            INSERT INTO webrtc_signals (id, room_id, from_user_id, to_user_id, signal_type, payload, delivered)
+            VALUES (?, ?, ?, ?, 'ice_candidate', '{}', TRUE)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(&room_id)
+        .bind(from_user_id.to_string())
+        .bind(to_user_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    use_small_batches(&app).await;
+    let removed = VideoConsultationService::clean_expired_signals(&app.pool).await;
+    restore_batches(&app).await;
+
+    let removed = removed.unwrap();
+    assert!(removed >= 25);
+
+    let metrics = CleanupService::last_sweep("expired_signals").unwrap();
+    assert_eq!(metrics.rows, removed);
+    assert_eq!(metrics.batches, removed / 10 + 1);
+    assert!(metrics.batches >= 3);
+
+    let remaining: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM webrtc_signals WHERE room_id = ?")
+            .bind(&room_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(remaining, 0);
+}
@@ -2,9 +2,13 @@ use crate::common::TestApp;
 use axum::http::StatusCode;
 use backend::{
     models::user::LoginDto,
+    services::content_service,
     utils::test_helpers::{create_test_doctor, create_test_user},
 };
+use chrono::{Duration, Utc};
 use serde_json::json;
+use sqlx::MySqlPool;
+use uuid::Uuid;
 
 async fn get_auth_token(app: &mut TestApp, account: &str, password: &str) -> String {
     let login_dto = LoginDto {
@@ -443,3 +447,766 @@ async fn test_view_count_increment() {
         assert_eq!(body["data"]["view_count"], i);
     }
 }
+
+#[tokio::test]
+async fn test_article_preview_restricted_to_author() {
+    let mut app = TestApp::new().await;
+
+    let (doctor_id, doctor_account, doctor_password) = create_test_user(&app.pool, "doctor").await;
+    let (_doctor_record_id, _) = create_test_doctor(&app.pool, doctor_id).await;
+    let (_patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    let create_dto = json!({
+        "title": "草稿预览",
+        "content": "尚未发布的内容",
+        "category": "健康科普"
+    });
+
+    let (status, body) = app
+        .post_with_auth("/api/v1/content/articles", create_dto, &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let article_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // Author can preview the draft
+    let (status, body) = app
+        .get_with_auth(
+            &format!("/api/v1/content/articles/{}/preview", article_id),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "draft");
+    assert_eq!(body["data"]["content"], "尚未发布的内容");
+
+    // Other users cannot
+    let (status, _) = app
+        .get_with_auth(
+            &format!("/api/v1/content/articles/{}/preview", article_id),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Previews are not counted as views
+    let view_count: i32 = sqlx::query_scalar("SELECT view_count FROM articles WHERE id = ?")
+        .bind(&article_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(view_count, 0);
+}
+
+async fn insert_published_article(
+    pool: &MySqlPool,
+    author_id: Uuid,
+    category: &str,
+    view_count: i32,
+) -> Uuid {
+    let article_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO articles (id, title, content, author_id, author_name, author_type,
+                              category, view_count, status, published_at, created_at, updated_at)
+        VALUES (?, ?, '测试内容', ?, '测试作者', 'doctor', ?, ?, 'published', NOW(), NOW(), NOW())
+        "#,
+    )
+    .bind(article_id.to_string())
+    .bind(format!("推荐测试文章 {}", category))
+    .bind(author_id.to_string())
+    .bind(category)
+    .bind(view_count)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    article_id
+}
+
+async fn insert_patient_history(
+    pool: &MySqlPool,
+    patient_id: Uuid,
+    doctor_record_id: Uuid,
+    diagnosis: &str,
+) {
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot,
+                                  visit_type, symptoms, has_visited_before, status, created_at, updated_at)
+        VALUES (?, ?, ?, NOW(), '09:00-10:00', 'offline', '测试症状', false, 'completed', NOW(), NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_record_id.to_string())
+    .execute(pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+        INSERT INTO prescriptions (id, code, doctor_id, patient_id, patient_name, diagnosis,
+                                   medicines, instructions, prescription_date)
+        VALUES (?, ?, ?, ?, '测试患者', ?, '[]', '遵医嘱', NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(format!("RX{}", Uuid::new_v4().simple()))
+    .bind(doctor_record_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(diagnosis)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+fn find_item(items: &[serde_json::Value], id: Uuid) -> &serde_json::Value {
+    items
+        .iter()
+        .find(|item| item["id"].as_str() == Some(&id.to_string()))
+        .expect("article missing from feed")
+}
+
+#[tokio::test]
+async fn test_recommended_feed_reasons() {
+    let mut app = TestApp::new().await;
+
+    let (admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_record_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    insert_patient_history(
+        &app.pool,
+        patient_id,
+        doctor_record_id,
+        "长期失眠，心脾两虚",
+    )
+    .await;
+
+    let by_doctor = insert_published_article(&app.pool, doctor_user_id, "活动动态", 0).await;
+    let by_category = insert_published_article(&app.pool, admin_id, "中医养生", 0).await;
+    let unrelated = insert_published_article(&app.pool, admin_id, "官网新闻", 1000).await;
+
+    let (status, body) = app
+        .get_with_auth("/api/v1/content/recommended", &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"]["personalized"].as_bool().unwrap());
+
+    let items = body["data"]["items"].as_array().unwrap();
+    assert_eq!(
+        find_item(items, by_doctor)["reason"],
+        "因为您关注了Test doctor User医生"
+    );
+    assert_eq!(
+        find_item(items, by_category)["reason"],
+        "与您的就诊记录相关"
+    );
+    assert_eq!(find_item(items, unrelated)["reason"], "热门推荐");
+
+    // Relevant content outranks merely popular content
+    let position = |id: Uuid| {
+        items
+            .iter()
+            .position(|item| item["id"].as_str() == Some(&id.to_string()))
+            .unwrap()
+    };
+    assert!(position(by_doctor) < position(unrelated));
+    assert!(position(by_category) < position(unrelated));
+}
+
+#[tokio::test]
+async fn test_recommended_feed_cold_start_falls_back_to_trending() {
+    let mut app = TestApp::new().await;
+
+    let (admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (_patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    let quiet = insert_published_article(&app.pool, admin_id, "中医养生", 1).await;
+    let popular = insert_published_article(&app.pool, admin_id, "健康科普", 5000).await;
+
+    let (status, body) = app
+        .get_with_auth("/api/v1/content/recommended", &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!body["data"]["personalized"].as_bool().unwrap());
+
+    let items = body["data"]["items"].as_array().unwrap();
+    assert!(items.iter().all(|item| item["reason"] == "热门推荐"));
+
+    let popular_pos = items
+        .iter()
+        .position(|item| item["id"].as_str() == Some(&popular.to_string()))
+        .unwrap();
+    let quiet_pos = items
+        .iter()
+        .position(|item| item["id"].as_str() == Some(&quiet.to_string()))
+        .unwrap();
+    assert!(popular_pos < quiet_pos);
+
+    // Recommendations are patient-only
+    let (_doctor_id, doctor_account, doctor_password) = create_test_user(&app.pool, "doctor").await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+    let (status, _) = app
+        .get_with_auth("/api/v1/content/recommended", &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_recommended_feed_isolated_between_patients() {
+    let mut app = TestApp::new().await;
+
+    let (admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_record_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (other_doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (other_doctor_record_id, _) = create_test_doctor(&app.pool, other_doctor_user_id).await;
+
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (other_patient_id, other_account, other_password) =
+        create_test_user(&app.pool, "patient").await;
+    let other_token = get_auth_token(&mut app, &other_account, &other_password).await;
+
+    // First patient's history points at the doctor and the 中医养生 category
+    insert_patient_history(&app.pool, patient_id, doctor_record_id, "失眠").await;
+    // Second patient has unrelated history, so they are not a cold-start user
+    insert_patient_history(
+        &app.pool,
+        other_patient_id,
+        other_doctor_record_id,
+        "腰肌劳损",
+    )
+    .await;
+
+    let by_doctor = insert_published_article(&app.pool, doctor_user_id, "活动动态", 0).await;
+    let by_category = insert_published_article(&app.pool, admin_id, "中医养生", 0).await;
+
+    let (status, body) = app
+        .get_with_auth("/api/v1/content/recommended", &other_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"]["personalized"].as_bool().unwrap());
+
+    let items = body["data"]["items"].as_array().unwrap();
+    assert_eq!(find_item(items, by_doctor)["reason"], "热门推荐");
+    assert_eq!(find_item(items, by_category)["reason"], "热门推荐");
+}
+
+async fn set_publish_approval(app: &TestApp, required: bool) {
+    sqlx::query(
+        "UPDATE system_configs SET config_value = ? WHERE category = 'content' AND config_key = 'require_publish_approval'",
+    )
+    .bind(if required { "true" } else { "false" })
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_content_publish_approval() {
+    let mut app = TestApp::new().await;
+
+    let (_admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let (doctor_id, doctor_account, doctor_password) = create_test_user(&app.pool, "doctor").await;
+    let (_doctor_record_id, _) = create_test_doctor(&app.pool, doctor_id).await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    let (_, body) = app
+        .post_with_auth(
+            "/api/v1/content/articles",
+            json!({
+                "title": "待审核文章",
+                "content": "需要审核的内容",
+                "category": "健康科普"
+            }),
+            &doctor_token,
+        )
+        .await;
+    let article_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (_, body) = app
+        .post_with_auth(
+            "/api/v1/content/videos",
+            json!({
+                "title": "待审核视频",
+                "video_url": "https://example.com/videos/review.mp4",
+                "category": "专家讲座"
+            }),
+            &doctor_token,
+        )
+        .await;
+    let video_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // With approval required, publishing only submits the content for review
+    set_publish_approval(&app, true).await;
+    let (article_status, article_body) = app
+        .post_with_auth(
+            &format!("/api/v1/content/articles/{}/publish", article_id),
+            json!({ "publish_channels": ["官网新闻"] }),
+            &doctor_token,
+        )
+        .await;
+    let (video_status, video_body) = app
+        .post_with_auth(
+            &format!("/api/v1/content/videos/{}/publish", video_id),
+            json!({ "publish_channels": ["手机端"] }),
+            &doctor_token,
+        )
+        .await;
+    set_publish_approval(&app, false).await;
+
+    assert_eq!(article_status, StatusCode::OK);
+    assert_eq!(article_body["data"]["status"], "pending_review");
+    assert!(article_body["data"]["published_at"].is_null());
+    assert_eq!(video_status, StatusCode::OK);
+    assert_eq!(video_body["data"]["status"], "pending_review");
+
+    // Only admins review
+    let approve_path = format!("/api/v1/content/articles/{}/approve", article_id);
+    let (status, _) = app
+        .post_with_auth(&approve_path, json!({}), &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Approval publishes the article
+    let (status, _) = app
+        .post_with_auth(&approve_path, json!({}), &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (_, body) = app
+        .get_with_auth(
+            &format!("/api/v1/content/articles/{}/preview", article_id),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(body["data"]["status"], "published");
+    assert!(!body["data"]["published_at"].is_null());
+
+    // Content no longer pending cannot be reviewed again
+    let (status, _) = app
+        .post_with_auth(&approve_path, json!({}), &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Rejection needs notes and returns the video to draft with them
+    let reject_path = format!("/api/v1/content/videos/{}/reject", video_id);
+    let (status, _) = app
+        .post_with_auth(&reject_path, json!({}), &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .post_with_auth(
+            &reject_path,
+            json!({ "notes": "视频画质不清晰" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, review_notes): (String, Option<String>) =
+        sqlx::query_as("SELECT status, review_notes FROM videos WHERE id = ?")
+            .bind(&video_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(status, "draft");
+    assert_eq!(review_notes.as_deref(), Some("视频画质不清晰"));
+
+    // The author is told about both decisions
+    let titles: Vec<String> = sqlx::query_scalar(
+        "SELECT title FROM notifications WHERE user_id = ? AND related_id IN (?, ?)",
+    )
+    .bind(doctor_id.to_string())
+    .bind(&article_id)
+    .bind(&video_id)
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(titles.len(), 2);
+    assert!(titles.contains(&"内容审核通过".to_string()));
+    assert!(titles.contains(&"内容审核未通过".to_string()));
+}
+
+#[tokio::test]
+async fn test_content_search_with_quote() {
+    let mut app = TestApp::new().await;
+
+    let (doctor_id, doctor_account, doctor_password) = create_test_user(&app.pool, "doctor").await;
+    let (_doctor_record_id, _) = create_test_doctor(&app.pool, doctor_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/content/articles",
+            json!({
+                "title": "Doctor's advice on sleep",
+                "content": "Keep a regular schedule...",
+                "category": "健康科普",
+                "summary": "Sleep tips"
+            }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let article_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // A single quote in the search term must be treated as data, not SQL
+    let (status, body) = app
+        .get("/api/v1/content/articles?search=Doctor%27s%20advice")
+        .await;
+    assert_eq!(status, StatusCode::OK, "Search failed: {:?}", body);
+    let results = body["data"].as_array().unwrap();
+    assert_eq!(results.len(), 1);
+    assert_eq!(results[0]["id"], article_id);
+
+    // Injection-shaped input matches nothing instead of widening the filter
+    let (status, body) = app
+        .get("/api/v1/content/articles?category=%27%20OR%20%271%27%3D%271")
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"].as_array().unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_article_relevance_sort() {
+    let mut app = TestApp::new().await;
+
+    let (doctor_id, doctor_account, doctor_password) = create_test_user(&app.pool, "doctor").await;
+    let (_doctor_record_id, _) = create_test_doctor(&app.pool, doctor_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    let articles = vec![
+        // Most relevant, but the oldest
+        json!({
+            "title": "失眠的中医调理",
+            "content": "失眠多因心脾两虚，失眠患者宜规律作息，长期失眠需就医。",
+            "category": "健康科普",
+            "summary": "失眠调理方法"
+        }),
+        // Mentions the term once, newest
+        json!({
+            "title": "秋季养生要点",
+            "content": "秋季宜润燥养肺。",
+            "category": "健康科普",
+            "summary": "附带谈及失眠"
+        }),
+        // Unrelated, keeps the term from appearing in every row
+        json!({
+            "title": "夏季饮食建议",
+            "content": "夏季饮食宜清淡。",
+            "category": "健康科普",
+            "summary": "清淡饮食"
+        }),
+    ];
+
+    let mut article_ids = Vec::new();
+    for article in articles {
+        let (status, body) = app
+            .post_with_auth("/api/v1/content/articles", article, &doctor_token)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        article_ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+
+    sqlx::query("UPDATE articles SET created_at = DATE_SUB(NOW(), INTERVAL 1 DAY) WHERE id = ?")
+        .bind(&article_ids[0])
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    // Default ordering is by recency
+    let (status, body) = app.get("/api/v1/content/articles?search=失眠").await;
+    assert_eq!(status, StatusCode::OK);
+    let results = body["data"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["id"], article_ids[1]);
+    assert!(results[0].get("relevance_score").is_none());
+
+    // Relevance ordering floats the article that mentions the term most
+    let (status, body) = app
+        .get("/api/v1/content/articles?search=失眠&sort=relevance")
+        .await;
+    assert_eq!(
+        status,
+        StatusCode::OK,
+        "Relevance search failed: {:?}",
+        body
+    );
+    let results = body["data"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0]["id"], article_ids[0]);
+    assert_eq!(results[1]["id"], article_ids[1]);
+    let top_score = results[0]["relevance_score"].as_f64().unwrap();
+    let second_score = results[1]["relevance_score"].as_f64().unwrap();
+    assert!(top_score > second_score);
+
+    // Terms too short for fulltext fall back to LIKE matching
+    let (status, body) = app
+        .get("/api/v1/content/articles?search=眠&sort=relevance")
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let results = body["data"].as_array().unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results[0].get("relevance_score").is_none());
+}
+
+#[tokio::test]
+async fn test_admin_edit_does_not_count_views() {
+    let mut app = TestApp::new().await;
+
+    let (_admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let (doctor_id, doctor_account, doctor_password) = create_test_user(&app.pool, "doctor").await;
+    let (_doctor_record_id, _) = create_test_doctor(&app.pool, doctor_id).await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/content/articles",
+            json!({
+                "title": "浏览量统计",
+                "content": "测试内容",
+                "category": "健康科普"
+            }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let article_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // Admin loads the article for editing and saves a change
+    let (status, _) = app
+        .get_with_auth(
+            &format!("/api/v1/content/articles/{}/preview", article_id),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .put_with_auth(
+            &format!("/api/v1/content/articles/{}", article_id),
+            json!({"title": "浏览量统计（修订）"}),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["view_count"], 0);
+
+    content_service::flush_article_views(&app.pool)
+        .await
+        .unwrap();
+    let view_count: i32 = sqlx::query_scalar("SELECT view_count FROM articles WHERE id = ?")
+        .bind(&article_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(view_count, 0);
+
+    // Reader views are buffered and written back on flush
+    for _ in 0..2 {
+        let (status, _) = app
+            .get(&format!("/api/v1/content/articles/{}", article_id))
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    content_service::flush_article_views(&app.pool)
+        .await
+        .unwrap();
+    let view_count: i32 = sqlx::query_scalar("SELECT view_count FROM articles WHERE id = ?")
+        .bind(&article_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(view_count, 2);
+}
+
+#[tokio::test]
+async fn test_scheduled_publish() {
+    let mut app = TestApp::new().await;
+
+    let (doctor_id, doctor_account, doctor_password) = create_test_user(&app.pool, "doctor").await;
+    let (_doctor_record_id, _) = create_test_doctor(&app.pool, doctor_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    let scheduled_at = Utc::now() + Duration::hours(1);
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/content/articles",
+            json!({
+                "title": "定时发布文章",
+                "content": "到点上线的内容",
+                "category": "健康科普",
+                "scheduled_publish_at": scheduled_at.to_rfc3339()
+            }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "draft");
+    assert!(body["data"]["scheduled_publish_at"].is_string());
+    let article_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // Not due yet: the sweeper leaves it in draft
+    content_service::publish_scheduled(&app.pool).await.unwrap();
+    let status: String = sqlx::query_scalar("SELECT status FROM articles WHERE id = ?")
+        .bind(&article_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "draft");
+
+    // Once the scheduled time has passed the sweeper publishes it
+    sqlx::query(
+        "UPDATE articles SET scheduled_publish_at = DATE_SUB(NOW(), INTERVAL 1 MINUTE) WHERE id = ?",
+    )
+    .bind(&article_id)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    let processed = content_service::publish_scheduled(&app.pool).await.unwrap();
+    assert!(processed >= 1);
+    let (status, scheduled): (String, Option<chrono::NaiveDateTime>) =
+        sqlx::query_as("SELECT status, scheduled_publish_at FROM articles WHERE id = ?")
+            .bind(&article_id)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(status, "published");
+    assert!(scheduled.is_none());
+
+    // Scheduling a video through publish keeps it in draft, publishing again
+    // without a time goes live immediately and clears the schedule
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/content/videos",
+            json!({
+                "title": "定时发布视频",
+                "video_url": "https://example.com/videos/scheduled.mp4",
+                "category": "专家讲座"
+            }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let video_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = app
+        .post_with_auth(
+            &format!("/api/v1/content/videos/{}/publish", video_id),
+            json!({
+                "publish_channels": ["官网"],
+                "scheduled_publish_at": scheduled_at.to_rfc3339()
+            }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "draft");
+    assert!(body["data"]["scheduled_publish_at"].is_string());
+
+    let (status, body) = app
+        .post_with_auth(
+            &format!("/api/v1/content/videos/{}/publish", video_id),
+            json!({"publish_channels": ["官网"]}),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "published");
+    assert!(body["data"]["scheduled_publish_at"].is_null());
+}
+
+#[tokio::test]
+async fn test_article_like_and_unlike() {
+    let mut app = TestApp::new().await;
+
+    let (doctor_id, doctor_account, doctor_password) = create_test_user(&app.pool, "doctor").await;
+    let (_doctor_record_id, _) = create_test_doctor(&app.pool, doctor_id).await;
+    let (_patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/content/articles",
+            json!({
+                "title": "点赞测试",
+                "content": "测试内容",
+                "category": "健康科普"
+            }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let article_id = body["data"]["id"].as_str().unwrap().to_string();
+    let like_path = format!("/api/v1/content/articles/{}/like", article_id);
+    let article_path = format!("/api/v1/content/articles/{}", article_id);
+
+    // Liking twice only counts once
+    for _ in 0..2 {
+        let (status, body) = app
+            .post_with_auth(&like_path, json!({}), &patient_token)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["liked"], true);
+        assert_eq!(body["data"]["like_count"], 1);
+    }
+
+    let (status, body) = app
+        .post_with_auth(&like_path, json!({}), &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["like_count"], 2);
+
+    let (status, body) = app.get_with_auth(&article_path, &patient_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["like_count"], 2);
+    assert_eq!(body["data"]["has_liked"], true);
+
+    // Anonymous readers get no like flag
+    let (status, body) = app.get(&article_path).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"].get("has_liked").is_none());
+
+    // Unliking twice only removes one like
+    for _ in 0..2 {
+        let (status, body) = app.delete_with_auth(&like_path, &patient_token).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["liked"], false);
+        assert_eq!(body["data"]["like_count"], 1);
+    }
+
+    let (status, body) = app.get_with_auth(&article_path, &patient_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["has_liked"], false);
+
+    let like_count: i32 = sqlx::query_scalar("SELECT like_count FROM articles WHERE id = ?")
+        .bind(&article_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(like_count, 1);
+
+    // Liking a missing article is a 404
+    let (status, _) = app
+        .post_with_auth(
+            &format!("/api/v1/content/articles/{}/like", Uuid::new_v4()),
+            json!({}),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
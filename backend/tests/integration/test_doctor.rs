@@ -1,9 +1,13 @@
 use crate::common::TestApp;
 use axum::http::StatusCode;
 use backend::{
-    models::{doctor::*, user::LoginDto},
+    models::{doctor::*, user::LoginDto, CreateReviewDto},
+    services::{doctor_service, review_service::ReviewService},
     utils::test_helpers::{create_test_doctor, create_test_user},
 };
+use chrono::{Duration, Utc};
+use serde_json::json;
+use uuid::Uuid;
 
 async fn get_auth_token(app: &mut TestApp, account: &str, password: &str) -> String {
     let login_dto = LoginDto {
@@ -103,6 +107,7 @@ async fn test_update_doctor_profile() {
         introduction: Some("更新后的简介".to_string()),
         specialties: Some(vec!["针灸".to_string(), "推拿".to_string()]),
         experience: Some("从医15年".to_string()),
+        consultation_duration_minutes: None,
     };
 
     let (status, body) = app
@@ -176,3 +181,524 @@ async fn test_get_doctor_by_user_id() {
     assert_eq!(body["data"]["id"], doctor_id.to_string());
     assert_eq!(body["data"]["user_id"], doctor_user_id.to_string());
 }
+
+#[tokio::test]
+async fn test_get_treating_doctors_only_returns_related_doctors() {
+    let mut app = TestApp::new().await;
+
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (other_patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+
+    let (treating_user_id, treating_account, treating_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (treating_doctor_id, _) = create_test_doctor(&app.pool, treating_user_id).await;
+    let (completed_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (completed_doctor_id, _) = create_test_doctor(&app.pool, completed_user_id).await;
+    let (cancelled_user_id, cancelled_account, cancelled_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (cancelled_doctor_id, _) = create_test_doctor(&app.pool, cancelled_user_id).await;
+    let (unrelated_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (unrelated_doctor_id, _) = create_test_doctor(&app.pool, unrelated_user_id).await;
+
+    let date = (Utc::now() + Duration::days(2)).naive_utc();
+    let appointments = vec![
+        (patient_user_id, treating_doctor_id, "pending"),
+        (patient_user_id, treating_doctor_id, "confirmed"),
+        (patient_user_id, completed_doctor_id, "completed"),
+        (patient_user_id, cancelled_doctor_id, "cancelled"),
+        (other_patient_id, unrelated_doctor_id, "confirmed"),
+    ];
+    for (patient_id, doctor_id, status) in appointments {
+        sqlx::query(
+            r#"
+            INSERT INTO appointments (
+                id, patient_id, doctor_id, appointment_date, time_slot,
+                visit_type, symptoms, has_visited_before, status, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, '09:00-10:00', 'offline', '测试症状', false, ?, NOW(), NOW())
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(patient_id.to_string())
+        .bind(doctor_id.to_string())
+        .bind(date)
+        .bind(status)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    // Distinct doctors, excluding cancelled-only and unrelated ones
+    let doctors = doctor_service::get_treating_doctors(&app.pool, patient_user_id)
+        .await
+        .unwrap();
+    let mut ids: Vec<Uuid> = doctors.iter().map(|d| d.id).collect();
+    ids.sort();
+    let mut expected = vec![treating_doctor_id, completed_doctor_id];
+    expected.sort();
+    assert_eq!(ids, expected);
+
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let (status, body) = app
+        .get_with_auth(
+            &format!("/api/v1/doctors/treating/{}", patient_user_id),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"].as_array().unwrap().len(), 2);
+    // Identity documents are never exposed to patients
+    for doctor in body["data"].as_array().unwrap() {
+        for field in ["id_number", "id_card_front", "id_card_back"] {
+            assert!(doctor.get(field).is_none());
+        }
+    }
+
+    // Other patients cannot list someone else's doctors
+    let (status, _) = app
+        .get_with_auth(
+            &format!("/api/v1/doctors/treating/{}", other_patient_id),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // A treating doctor may view the patient's history, a cancelled-only doctor may not
+    let treating_token = get_auth_token(&mut app, &treating_account, &treating_password).await;
+    let (status, _) = app
+        .get_with_auth(
+            &format!("/api/v1/appointments/patient/{}", patient_user_id),
+            &treating_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let cancelled_token = get_auth_token(&mut app, &cancelled_account, &cancelled_password).await;
+    let (status, _) = app
+        .get_with_auth(
+            &format!("/api/v1/appointments/patient/{}", patient_user_id),
+            &cancelled_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_out_of_office_blocks_booking_and_hides_doctor() {
+    let mut app = TestApp::new().await;
+
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+
+    // A unique hospital name lets the search target just this doctor
+    let hospital = format!("ooo-hospital-{}", &Uuid::new_v4().simple().to_string()[..8]);
+    sqlx::query("UPDATE doctors SET hospital = ? WHERE id = ?")
+        .bind(&hospital)
+        .bind(doctor_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let ooo_path = format!("/api/v1/doctors/{}/out-of-office", doctor_id);
+    let search_path = format!("/api/v1/doctors?search={}", hospital);
+
+    // Patients cannot change a doctor's availability, and the range must be valid
+    let (status, _) = app
+        .put_with_auth(
+            &ooo_path,
+            json!({
+                "start": Utc::now() - Duration::hours(1),
+                "end": Utc::now() + Duration::days(3),
+                "message": "休假中"
+            }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = app
+        .put_with_auth(
+            &ooo_path,
+            json!({
+                "start": Utc::now() + Duration::days(3),
+                "end": Utc::now(),
+                "message": "休假中"
+            }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let message = "本人休假至下周一，急症请前往急诊科就诊";
+    let (status, body) = app
+        .put_with_auth(
+            &ooo_path,
+            json!({
+                "start": Utc::now() - Duration::hours(1),
+                "end": Utc::now() + Duration::days(3),
+                "message": message
+            }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["message"], message);
+
+    // Hidden from search while away
+    let (status, body) = app.get(&search_path).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"].as_array().unwrap().is_empty());
+
+    // Bookings are refused with the doctor's message
+    let booking = json!({
+        "patient_id": patient_user_id,
+        "doctor_id": doctor_id,
+        "appointment_date": Utc::now() + Duration::days(7),
+        "time_slot": "09:00-10:00",
+        "visit_type": "offline",
+        "symptoms": "头痛",
+        "has_visited_before": false
+    });
+    let (status, body) = app
+        .post_with_auth("/api/v1/appointments", booking.clone(), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains(message));
+
+    // Messages from patients get an automatic reply
+    let reply =
+        doctor_service::send_out_of_office_reply(&app.pool, doctor_user_id, patient_user_id)
+            .await
+            .unwrap()
+            .expect("out-of-office doctor should auto-reply");
+    assert_eq!(reply.user_id, patient_user_id);
+    assert_eq!(reply.content, message);
+
+    // Back in office: visible and bookable again
+    let (status, _) = app.delete_with_auth(&ooo_path, &doctor_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = app.get(&search_path).await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"][0]["id"], doctor_id.to_string());
+
+    let (status, _) = app
+        .post_with_auth("/api/v1/appointments", booking, &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    assert!(
+        doctor_service::send_out_of_office_reply(&app.pool, doctor_user_id, patient_user_id)
+            .await
+            .unwrap()
+            .is_none()
+    );
+}
+
+async fn add_completed_review(
+    pool: &sqlx::MySqlPool,
+    doctor_id: Uuid,
+    patient_id: Uuid,
+    rating: i32,
+) {
+    let appointment_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot,
+                                  visit_type, symptoms, has_visited_before, status, created_at, updated_at)
+        VALUES (?, ?, ?, NOW(), '09:00-10:00', 'offline', 'test symptoms', false, 'completed', NOW(), NOW())
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .execute(pool)
+    .await
+    .unwrap();
+
+    ReviewService::create_review(
+        pool,
+        patient_id,
+        CreateReviewDto {
+            appointment_id,
+            rating,
+            attitude_rating: rating,
+            professionalism_rating: rating,
+            efficiency_rating: rating,
+            comment: None,
+            tag_ids: None,
+            is_anonymous: None,
+        },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_rating_sort_ranks_established_doctor_above_single_review() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let mut doctors = Vec::new();
+    for _ in 0..3 {
+        let (user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+        let (doctor_id, _) = create_test_doctor(&app.pool, user_id).await;
+        doctors.push(doctor_id);
+    }
+    let (newcomer, established, struggling) = (doctors[0], doctors[1], doctors[2]);
+
+    let hospital = format!(
+        "rating-hospital-{}",
+        &Uuid::new_v4().simple().to_string()[..8]
+    );
+    for doctor_id in &doctors {
+        sqlx::query("UPDATE doctors SET hospital = ? WHERE id = ?")
+            .bind(&hospital)
+            .bind(doctor_id.to_string())
+            .execute(&app.pool)
+            .await
+            .unwrap();
+    }
+
+    // One perfect review vs. a 4.8 average over 40 reviews
+    add_completed_review(&app.pool, newcomer, patient_id, 5).await;
+    for i in 0..40 {
+        let rating = if i % 5 == 0 { 4 } else { 5 };
+        add_completed_review(&app.pool, established, patient_id, rating).await;
+    }
+    for _ in 0..10 {
+        add_completed_review(&app.pool, struggling, patient_id, 2).await;
+    }
+
+    // The raw average is kept for display
+    let raw: f64 =
+        sqlx::query_scalar("SELECT CAST(average_rating AS DOUBLE) FROM doctors WHERE id = ?")
+            .bind(newcomer.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(raw, 5.0);
+
+    let (status, body) = app
+        .get(&format!("/api/v1/doctors?search={}&sort=rating", hospital))
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let ranked: Vec<String> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|d| d["id"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        ranked,
+        vec![
+            established.to_string(),
+            newcomer.to_string(),
+            struggling.to_string()
+        ]
+    );
+}
+
+async fn insert_appointment_on(
+    pool: &sqlx::MySqlPool,
+    doctor_id: Uuid,
+    patient_id: Uuid,
+    appointment_date: &str,
+    status: &str,
+) -> Uuid {
+    let appointment_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot,
+                                  visit_type, symptoms, has_visited_before, status, created_at, updated_at)
+        VALUES (?, ?, ?, ?, '09:00-10:00', 'online_video', 'test symptoms', false, ?, NOW(), NOW())
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(appointment_date)
+    .bind(status)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    appointment_id
+}
+
+#[tokio::test]
+async fn test_monthly_report_only_counts_target_month() {
+    let mut app = TestApp::new().await;
+
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+    let (patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    // March 2023 is the report month; the rest sit just outside it
+    let first = insert_appointment_on(
+        &app.pool,
+        doctor_id,
+        patient_id,
+        "2023-03-01 00:00:00",
+        "completed",
+    )
+    .await;
+    let second = insert_appointment_on(
+        &app.pool,
+        doctor_id,
+        patient_id,
+        "2023-03-31 23:00:00",
+        "completed",
+    )
+    .await;
+    insert_appointment_on(
+        &app.pool,
+        doctor_id,
+        patient_id,
+        "2023-03-15 10:00:00",
+        "cancelled",
+    )
+    .await;
+    let february = insert_appointment_on(
+        &app.pool,
+        doctor_id,
+        patient_id,
+        "2023-02-28 23:59:59",
+        "completed",
+    )
+    .await;
+    let april = insert_appointment_on(
+        &app.pool,
+        doctor_id,
+        patient_id,
+        "2023-04-01 00:00:00",
+        "completed",
+    )
+    .await;
+
+    for (appointment_id, end_time, duration, rating) in [
+        (first, "2023-03-01 00:30:00", 600, 4),
+        (second, "2023-03-31 23:30:00", 1200, 5),
+        (april, "2023-04-01 00:30:00", 3000, 1),
+    ] {
+        sqlx::query(
+            r#"
+            INSERT INTO video_consultations (
+                id, appointment_id, doctor_id, patient_id, room_id, status,
+                scheduled_start_time, end_time, duration, patient_rating, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, 'completed', ?, ?, ?, ?, NOW(), NOW())
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(appointment_id.to_string())
+        .bind(doctor_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(format!("room-{}", Uuid::new_v4().simple()))
+        .bind(end_time)
+        .bind(end_time)
+        .bind(duration)
+        .bind(rating)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    for (transaction_type, amount, created_at) in [
+        ("income", "100.00", "2023-03-02 10:00:00"),
+        ("income", "50.50", "2023-03-20 10:00:00"),
+        ("expense", "30.00", "2023-03-21 10:00:00"),
+        ("income", "70.00", "2023-04-01 00:00:00"),
+    ] {
+        sqlx::query(
+            r#"
+            INSERT INTO balance_transactions (
+                id, user_id, transaction_type, amount, balance_before, balance_after,
+                description, created_at
+            ) VALUES (?, ?, ?, ?, 0, 0, '测试流水', ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(doctor_user_id.to_string())
+        .bind(transaction_type)
+        .bind(amount)
+        .bind(created_at)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    for (appointment_id, rating, created_at) in [
+        (first, 5, "2023-03-05 10:00:00"),
+        (february, 1, "2023-02-28 23:59:59"),
+    ] {
+        sqlx::query(
+            r#"
+            INSERT INTO patient_reviews (
+                id, appointment_id, doctor_id, patient_id, rating, attitude_rating,
+                professionalism_rating, efficiency_rating, created_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(appointment_id.to_string())
+        .bind(doctor_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(rating)
+        .bind(rating)
+        .bind(rating)
+        .bind(rating)
+        .bind(created_at)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    let (status, body) = app
+        .get_with_auth("/api/v1/doctors/me/report?year=2023&month=3", &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let report = &body["data"];
+    assert_eq!(report["doctor_id"], doctor_id.to_string());
+    assert_eq!(report["total_appointments"], 3);
+    assert_eq!(report["appointments_by_status"]["completed"], 2);
+    assert_eq!(report["appointments_by_status"]["cancelled"], 1);
+    assert_eq!(report["appointments_by_status"]["pending"], 0);
+    assert_eq!(report["completed_consultations"], 2);
+    assert_eq!(report["average_consultation_duration"], 900.0);
+    assert_eq!(report["average_consultation_rating"], 4.5);
+    let earnings: rust_decimal::Decimal =
+        serde_json::from_value(report["earnings"].clone()).unwrap();
+    assert_eq!(earnings, rust_decimal::Decimal::new(15050, 2));
+    assert_eq!(report["new_reviews"], 1);
+    assert_eq!(report["average_review_rating"], 5.0);
+
+    // An empty month reports zeros
+    let (status, body) = app
+        .get_with_auth("/api/v1/doctors/me/report?year=2023&month=5", &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["total_appointments"], 0);
+    assert!(body["data"]["average_consultation_duration"].is_null());
+
+    let (status, _) = app
+        .get_with_auth(
+            "/api/v1/doctors/me/report?year=2023&month=13",
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .get_with_auth("/api/v1/doctors/me/report", &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
@@ -0,0 +1,311 @@
+use crate::common::{configure_alipay, MockGateway, TestApp};
+use axum::http::StatusCode;
+use backend::{
+    models::{payment::*, user::LoginDto},
+    services::{
+        doctor_earnings_service::DoctorEarningsService, payment_service::PaymentService,
+        withdrawal_service::WithdrawalService,
+    },
+    utils::test_helpers::{create_test_doctor, create_test_user},
+};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+use uuid::Uuid;
+
+async fn get_auth_token(app: &mut TestApp, account: &str, password: &str) -> String {
+    let login_dto = LoginDto {
+        account: account.to_string(),
+        password: password.to_string(),
+    };
+
+    let (_, body) = app.post("/api/v1/auth/login", login_dto).await;
+    body["data"]["token"].as_str().unwrap().to_string()
+}
+
+fn dec(value: &str) -> Decimal {
+    Decimal::from_str(value).unwrap()
+}
+
+#[tokio::test]
+async fn test_commission_rounding() {
+    // Rounded to the cent, half away from zero so refunds mirror income
+    assert_eq!(
+        DoctorEarningsService::commission_for(dec("99.99"), dec("20")),
+        dec("20.00")
+    );
+    assert_eq!(
+        DoctorEarningsService::commission_for(dec("33.33"), dec("20")),
+        dec("6.67")
+    );
+    assert_eq!(
+        DoctorEarningsService::commission_for(dec("-33.33"), dec("20")),
+        dec("-6.67")
+    );
+    assert_eq!(
+        DoctorEarningsService::commission_for(dec("0.25"), dec("10")),
+        dec("0.03")
+    );
+    assert_eq!(
+        DoctorEarningsService::commission_for(dec("-0.25"), dec("10")),
+        dec("-0.03")
+    );
+    assert_eq!(
+        DoctorEarningsService::commission_for(dec("88.88"), dec("12.5")),
+        dec("11.11")
+    );
+    assert_eq!(
+        DoctorEarningsService::commission_for(dec("50.00"), Decimal::ZERO),
+        Decimal::ZERO
+    );
+}
+
+#[tokio::test]
+async fn test_doctor_earnings_ledger_and_refund_adjustments() {
+    let mut app = TestApp::new().await;
+    let (admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    let rate = DoctorEarningsService::commission_rate(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(rate, dec("20"));
+
+    let appointment_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot,
+                                  visit_type, symptoms, has_visited_before, status, created_at, updated_at)
+        VALUES (?, ?, ?, DATE_ADD(NOW(), INTERVAL 1 DAY), '09:00-10:00', 'online_video', '测试症状', false, 'pending', NOW(), NOW())
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let order_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO payment_orders (
+            id, order_no, user_id, appointment_id, order_type, amount, currency,
+            status, expire_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, 'appointment', ?, 'CNY', 'pending', DATE_ADD(NOW(), INTERVAL 2 HOUR), NOW(), NOW())
+        "#,
+    )
+    .bind(order_id.to_string())
+    .bind(format!("ORD{}", Uuid::new_v4().simple()))
+    .bind(patient_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(dec("99.99"))
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+        INSERT INTO payment_transactions (
+            id, transaction_no, order_id, payment_method,
+            transaction_type, amount, status, initiated_at
+        ) VALUES (?, ?, ?, 'alipay', 'payment', ?, 'pending', NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(format!("TXN{}", Uuid::new_v4().simple()))
+    .bind(order_id.to_string())
+    .bind(dec("99.99"))
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    // Payment books the income with the platform commission taken off
+    PaymentService::adjust_order_status(
+        &app.pool,
+        order_id,
+        OrderStatus::Paid,
+        "网关回调丢失",
+        admin_id,
+    )
+    .await
+    .unwrap();
+
+    let (gross, commission, net): (Decimal, Decimal, Decimal) = sqlx::query_as(
+        r#"
+        SELECT gross_amount, commission_amount, net_amount FROM doctor_earnings
+        WHERE order_id = ? AND entry_type = 'income'
+        "#,
+    )
+    .bind(order_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(gross, dec("99.99"));
+    assert_eq!(commission, dec("20.00"));
+    assert_eq!(net, dec("79.99"));
+
+    // A partial refund is booked as a negative adjustment at the same rate
+    configure_alipay(&app.pool).await;
+    let gateway = MockGateway::new(vec![(
+        200,
+        serde_json::json!({
+            "alipay_trade_refund_response": { "code": "10000", "msg": "Success", "fund_change": "Y" }
+        }),
+    )]);
+    let refund = PaymentService::create_refund(
+        &app.pool,
+        CreateRefundDto {
+            order_id,
+            refund_amount: dec("33.33"),
+            refund_reason: "部分服务未提供".to_string(),
+        },
+        patient_id,
+        false,
+    )
+    .await
+    .unwrap();
+    PaymentService::review_refund(
+        &app.pool,
+        &gateway,
+        refund.id,
+        ReviewRefundDto {
+            approved: true,
+            review_notes: None,
+        },
+        admin_id,
+    )
+    .await
+    .unwrap();
+
+    let (gross, commission, net): (Decimal, Decimal, Decimal) = sqlx::query_as(
+        r#"
+        SELECT gross_amount, commission_amount, net_amount FROM doctor_earnings
+        WHERE source_id = ? AND entry_type = 'refund_adjustment'
+        "#,
+    )
+    .bind(refund.id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(gross, dec("-33.33"));
+    assert_eq!(commission, dec("-6.67"));
+    assert_eq!(net, dec("-26.66"));
+
+    // Only doctors have an earnings report
+    let (status, _) = app
+        .get_with_auth("/api/v1/doctors/me/earnings", &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Not settled until the appointment is completed
+    let (status, body) = app
+        .get_with_auth("/api/v1/doctors/me/earnings", &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["pending_amount"].as_f64().unwrap(), 53.33);
+    assert_eq!(body["data"]["settled_amount"].as_f64().unwrap(), 0.0);
+    assert_eq!(body["data"]["total"], 2);
+    assert_eq!(body["data"]["daily"].as_array().unwrap().len(), 1);
+    assert_eq!(
+        body["data"]["daily"][0]["gross_amount"].as_f64().unwrap(),
+        66.66
+    );
+    assert_eq!(
+        body["data"]["daily"][0]["commission_amount"]
+            .as_f64()
+            .unwrap(),
+        13.33
+    );
+    assert_eq!(
+        body["data"]["daily"][0]["net_amount"].as_f64().unwrap(),
+        53.33
+    );
+
+    let (_, body) = app
+        .get_with_auth(
+            "/api/v1/doctors/me/earnings?page=1&page_size=1",
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(body["data"]["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(body["data"]["total"], 2);
+
+    sqlx::query("UPDATE appointments SET status = 'completed' WHERE id = ?")
+        .bind(appointment_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (_, body) = app
+        .get_with_auth("/api/v1/doctors/me/earnings", &doctor_token)
+        .await;
+    assert_eq!(body["data"]["pending_amount"].as_f64().unwrap(), 0.0);
+    assert_eq!(body["data"]["settled_amount"].as_f64().unwrap(), 53.33);
+    assert!(body["data"]["entries"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|entry| entry["settled"] == true));
+
+    // Withdrawable earnings come from the same ledger, net of commission and refunds
+    let summary = WithdrawalService::get_earnings_summary(&app.pool, doctor_id)
+        .await
+        .unwrap();
+    assert_eq!(summary.settled_earnings, dec("53.33"));
+    assert_eq!(summary.available_amount, dec("53.33"));
+
+    // Refunding the rest reverses whatever the order still contributes
+    PaymentService::adjust_order_status(
+        &app.pool,
+        order_id,
+        OrderStatus::Refunded,
+        "线下全额退款",
+        admin_id,
+    )
+    .await
+    .unwrap();
+
+    let (gross, commission, net): (Decimal, Decimal, Decimal) = sqlx::query_as(
+        r#"
+        SELECT gross_amount, commission_amount, net_amount FROM doctor_earnings
+        WHERE source_id = ? AND entry_type = 'refund_adjustment'
+        "#,
+    )
+    .bind(order_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(gross, dec("-66.66"));
+    assert_eq!(commission, dec("-13.33"));
+    assert_eq!(net, dec("-53.33"));
+
+    let (_, body) = app
+        .get_with_auth("/api/v1/doctors/me/earnings", &doctor_token)
+        .await;
+    assert_eq!(body["data"]["settled_amount"].as_f64().unwrap(), 0.0);
+    assert_eq!(
+        body["data"]["daily"][0]["net_amount"].as_f64().unwrap(),
+        0.0
+    );
+    assert_eq!(body["data"]["total"], 3);
+    let summary = WithdrawalService::get_earnings_summary(&app.pool, doctor_id)
+        .await
+        .unwrap();
+    assert_eq!(summary.settled_earnings, Decimal::ZERO);
+
+    // Entries outside the requested range are left out
+    let (_, body) = app
+        .get_with_auth(
+            "/api/v1/doctors/me/earnings?start_date=2000-01-01&end_date=2000-01-31",
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(body["data"]["total"], 0);
+    assert!(body["data"]["daily"].as_array().unwrap().is_empty());
+}
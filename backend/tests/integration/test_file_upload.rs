@@ -1,7 +1,19 @@
 use crate::common::TestApp;
-use axum::http::StatusCode;
-use backend::utils::test_helpers::create_test_user;
+use aws_config::{BehaviorVersion, Region};
+use aws_sdk_s3::{config::Credentials, Client as S3Client};
+use axum::{
+    http::{Method, StatusCode, Uri},
+    response::IntoResponse,
+    Router,
+};
+use backend::{
+    config::storage::StorageConfig,
+    models::file_upload::{MultipartUploadResponse, UploadStatus},
+    services::file_upload_service::FileUploadService,
+    utils::test_helpers::create_test_user,
+};
 use chrono::Utc;
+use std::sync::{Arc, Mutex};
 //use serial_test::serial;
 use serde_json::json;
 
@@ -435,6 +447,14 @@ async fn test_admin_access() {
         .await;
 
     assert_eq!(status, StatusCode::OK);
+
+    // Restore the default so image size checks elsewhere aren't affected
+    sqlx::query(
+        "UPDATE system_configs SET config_value = '10485760' WHERE category = 'file_upload' AND config_key = 'max_image_size'",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
 }
 
 #[tokio::test]
@@ -475,3 +495,907 @@ async fn test_public_file_access() {
     assert!(body["success"].as_bool().unwrap());
     assert!(body["data"]["is_public"].as_bool().unwrap());
 }
+
+#[tokio::test]
+//#[serial]
+async fn test_top_storage_users_ranking() {
+    let mut app = TestApp::new().await;
+
+    let (admin_id, admin_email, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_email, &admin_password).await;
+    let (light_user, light_email, light_password) = create_test_user(&app.pool, "patient").await;
+    let (heavy_user, _, _) = create_test_user(&app.pool, "patient").await;
+    let light_token = get_auth_token(&mut app, &light_email, &light_password).await;
+
+    // Sizes are large enough to outrank files seeded by other tests
+    let gb = 1_073_741_824i64;
+    let files = vec![
+        (heavy_user, "video", 300 * gb, "completed"),
+        (heavy_user, "image", 10 * gb, "completed"),
+        (heavy_user, "image", 10 * gb, "completed"),
+        (light_user, "document", 200 * gb, "completed"),
+        // Deleted and unfinished uploads don't count
+        (light_user, "video", 500 * gb, "deleted"),
+        (admin_id, "video", 1000 * gb, "uploading"),
+    ];
+
+    for (user_id, file_type, size, status) in files {
+        let file_id = uuid::Uuid::new_v4();
+
+        let query = r#"
+            INSERT INTO file_uploads (
+                id, user_id, file_type, file_name, file_path, file_url,
+                file_size, status, uploaded_at, deleted_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(file_id.to_string())
+            .bind(user_id.to_string())
+            .bind(file_type)
+            .bind(format!("{}.ext", file_id))
+            .bind(format!("{}/2024/01/{}.ext", file_type, file_id))
+            .bind(format!(
+                "https://cdn.example.com/{}/2024/01/{}.ext",
+                file_type, file_id
+            ))
+            .bind(size)
+            .bind(status)
+            .bind(Utc::now())
+            .bind((status == "deleted").then(Utc::now))
+            .execute(&app.pool)
+            .await
+            .unwrap();
+    }
+
+    // Admin only
+    let (status, _) = app
+        .get_with_auth("/api/v1/files/storage/top", &light_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = app
+        .get_with_auth("/api/v1/files/storage/top?limit=2", &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let users = body["data"].as_array().unwrap();
+    assert_eq!(users.len(), 2);
+
+    assert_eq!(users[0]["user_id"], heavy_user.to_string());
+    assert_eq!(users[0]["total_files"], 3);
+    assert_eq!(users[0]["total_size"].as_i64().unwrap(), 320 * gb);
+    let image_stats = users[0]["by_type"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|t| t["file_type"] == "image")
+        .unwrap();
+    assert_eq!(image_stats["count"], 2);
+    assert_eq!(image_stats["total_size"].as_i64().unwrap(), 20 * gb);
+
+    assert_eq!(users[1]["user_id"], light_user.to_string());
+    assert_eq!(users[1]["total_files"], 1);
+    assert_eq!(users[1]["total_size"].as_i64().unwrap(), 200 * gb);
+}
+
+async fn set_image_watermark(app: &TestApp, enabled: bool) {
+    sqlx::query(
+        "UPDATE system_configs SET config_value = ? WHERE category = 'file_upload' AND config_key = 'enable_image_watermark'",
+    )
+    .bind(enabled.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+async fn complete_image_upload(
+    app: &mut TestApp,
+    user_id: uuid::Uuid,
+    token: &str,
+) -> (String, String) {
+    let upload_id = uuid::Uuid::new_v4();
+    let file_path = format!("image/2024/01/{}_1705766400.jpg", upload_id);
+
+    sqlx::query(
+        r#"
+        INSERT INTO file_uploads (
+            id, user_id, file_type, file_name, file_path,
+            file_url, file_size, mime_type, status, uploaded_at
+        ) VALUES (?, ?, 'image', 'tongue.jpg', ?, '', 524288, 'image/jpeg', 'uploading', ?)
+        "#,
+    )
+    .bind(upload_id.to_string())
+    .bind(user_id.to_string())
+    .bind(&file_path)
+    .bind(Utc::now())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let input_url = format!("https://cdn.example.com/{}", file_path);
+    let (status, body) = app
+        .put_with_auth(
+            &format!("/api/v1/files/upload/{}/complete", upload_id),
+            json!({
+                "file_path": file_path,
+                "file_url": input_url,
+                "object_key": file_path
+            }),
+            token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK, "Complete upload failed: {:?}", body);
+    assert!(body["data"].get("original_url").is_none());
+
+    (
+        input_url,
+        body["data"]["file_url"].as_str().unwrap().to_string(),
+    )
+}
+
+#[tokio::test]
+async fn test_image_watermark_follows_config_flag() {
+    let mut app = TestApp::new().await;
+
+    let (user_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let token = get_auth_token(&mut app, &account, &password).await;
+
+    // 开启水印：对外地址带水印处理参数，原图地址只保存在服务端
+    set_image_watermark(&app, true).await;
+    let (input_url, served_url) = complete_image_upload(&mut app, user_id, &token).await;
+    set_image_watermark(&app, false).await;
+
+    assert_ne!(served_url, input_url);
+    assert!(served_url.starts_with(&input_url));
+    assert!(served_url.contains("x-oss-process=image/watermark"));
+
+    let original_url: Option<String> =
+        sqlx::query_scalar("SELECT original_url FROM file_uploads WHERE file_url = ?")
+            .bind(&served_url)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(original_url.as_deref(), Some(input_url.as_str()));
+
+    // 关闭水印：地址保持不变
+    let (input_url, served_url) = complete_image_upload(&mut app, user_id, &token).await;
+    assert_eq!(served_url, input_url);
+}
+
+#[tokio::test]
+async fn test_refresh_upload_url_keeps_object_key() {
+    let mut app = TestApp::new().await;
+
+    let (user_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let token = get_auth_token(&mut app, &account, &password).await;
+    let (_other_id, other_account, other_password) = create_test_user(&app.pool, "patient").await;
+    let other_token = get_auth_token(&mut app, &other_account, &other_password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/files/upload",
+            json!({
+                "file_name": "report.pdf",
+                "file_type": "document",
+                "file_size": 1048576,
+                "mime_type": "application/pdf"
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let upload_id = body["data"]["upload_id"].as_str().unwrap().to_string();
+    let upload_url = body["data"]["upload_url"].as_str().unwrap().to_string();
+    let object_url = upload_url.split('?').next().unwrap().to_string();
+
+    let refresh_path = format!("/api/v1/files/upload/{}/refresh", upload_id);
+
+    // 只有上传者本人可以续期
+    let (status, _) = app
+        .post_with_auth(&refresh_path, json!({}), &other_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = app.post_with_auth(&refresh_path, json!({}), &token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["upload_id"].as_str().unwrap(), upload_id);
+    // 重新签名后查询参数会变化，对象地址保持不变
+    let refreshed_url = body["data"]["upload_url"].as_str().unwrap();
+    assert_eq!(refreshed_url.split('?').next().unwrap(), object_url);
+    assert!(body["data"]["expires_at"].as_str().is_some());
+
+    // 不会新建上传记录
+    let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM file_uploads WHERE user_id = ?")
+        .bind(user_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    let file_path: String = sqlx::query_scalar("SELECT file_path FROM file_uploads WHERE id = ?")
+        .bind(&upload_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert!(object_url.ends_with(&file_path));
+
+    // 续期后仍可正常完成上传
+    let (status, body) = app
+        .put_with_auth(
+            &format!("/api/v1/files/upload/{}/complete", upload_id),
+            json!({
+                "file_path": file_path,
+                "file_url": format!("https://cdn.example.com/{}", file_path),
+                "object_key": file_path
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"].as_str().unwrap(), "completed");
+
+    // 已完成的上传不能再续期
+    let (status, _) = app.post_with_auth(&refresh_path, json!({}), &token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_batch_delete_files_reports_per_file_outcome() {
+    let mut app = TestApp::new().await;
+
+    let (owner_id, owner_email, owner_password) = create_test_user(&app.pool, "patient").await;
+    let (other_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (_, admin_email, admin_password) = create_test_user(&app.pool, "admin").await;
+    let owner_token = get_auth_token(&mut app, &owner_email, &owner_password).await;
+    let admin_token = get_auth_token(&mut app, &admin_email, &admin_password).await;
+
+    let mut file_ids = vec![];
+    for user_id in [owner_id, other_id, owner_id] {
+        let file_id = uuid::Uuid::new_v4();
+
+        let query = r#"
+            INSERT INTO file_uploads (
+                id, user_id, file_type, file_name, file_path, file_url,
+                file_size, status, uploaded_at
+            ) VALUES (?, ?, 'document', 'test.pdf', 'document/test.pdf', 'https://cdn.example.com/test.pdf',
+                1048576, 'completed', ?)
+        "#;
+
+        sqlx::query(query)
+            .bind(file_id.to_string())
+            .bind(user_id.to_string())
+            .bind(Utc::now())
+            .execute(&app.pool)
+            .await
+            .unwrap();
+        file_ids.push(file_id);
+    }
+    let missing_id = uuid::Uuid::new_v4();
+
+    // One forbidden or missing file doesn't fail the rest of the batch
+    let (status, body) = app
+        .delete_with_auth_body(
+            "/api/v1/files/batch/delete",
+            json!({ "file_ids": [file_ids[0], file_ids[1], missing_id, file_ids[2]] }),
+            &owner_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let outcomes: Vec<(String, String)> = body["data"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| {
+            (
+                r["file_id"].as_str().unwrap().to_string(),
+                r["outcome"].as_str().unwrap().to_string(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        outcomes,
+        vec![
+            (file_ids[0].to_string(), "deleted".to_string()),
+            (file_ids[1].to_string(), "forbidden".to_string()),
+            (missing_id.to_string(), "not_found".to_string()),
+            (file_ids[2].to_string(), "deleted".to_string()),
+        ]
+    );
+
+    let other_status: String = sqlx::query_scalar("SELECT status FROM file_uploads WHERE id = ?")
+        .bind(file_ids[1].to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(other_status, "completed");
+
+    // Admins may delete anyone's file; already deleted files are reported as missing
+    let (status, body) = app
+        .delete_with_auth_body(
+            "/api/v1/files/batch/delete",
+            json!({ "file_ids": [file_ids[1], file_ids[0]] }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"][0]["outcome"], "deleted");
+    assert_eq!(body["data"][1]["outcome"], "not_found");
+}
+
+/// Stand-in for S3 that records every request as "METHOD /path". Keys
+/// containing "missing" answer NoSuchKey and keys containing "denied" fail.
+async fn spawn_fake_s3() -> (S3Client, Arc<Mutex<Vec<String>>>) {
+    let requests = Arc::new(Mutex::new(Vec::new()));
+    let recorded = requests.clone();
+    let app = Router::new().fallback(move |method: Method, uri: Uri| {
+        let recorded = recorded.clone();
+        async move {
+            recorded
+                .lock()
+                .unwrap()
+                .push(format!("{} {}", method, uri.path()));
+            let query = uri.query().unwrap_or_default();
+            let initiates_multipart = query.split('&').any(|p| p.trim_end_matches('=') == "uploads");
+
+            if uri.path().contains("missing") {
+                (
+                    StatusCode::NOT_FOUND,
+                    "<Error><Code>NoSuchKey</Code><Message>The specified key does not exist.</Message></Error>",
+                )
+                    .into_response()
+            } else if uri.path().contains("denied") {
+                (
+                    StatusCode::FORBIDDEN,
+                    "<Error><Code>AccessDenied</Code><Message>Access Denied</Message></Error>",
+                )
+                    .into_response()
+            } else if method == Method::POST && initiates_multipart {
+                (
+                    StatusCode::OK,
+                    "<InitiateMultipartUploadResult><UploadId>fake-upload-id</UploadId></InitiateMultipartUploadResult>",
+                )
+                    .into_response()
+            } else if method == Method::POST && query.contains("uploadId=") {
+                (
+                    StatusCode::OK,
+                    "<CompleteMultipartUploadResult><ETag>&quot;final-etag&quot;</ETag></CompleteMultipartUploadResult>",
+                )
+                    .into_response()
+            } else {
+                StatusCode::NO_CONTENT.into_response()
+            }
+        }
+    });
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move {
+        axum::serve(listener, app).await.unwrap();
+    });
+
+    let config = aws_sdk_s3::Config::builder()
+        .behavior_version(BehaviorVersion::latest())
+        .region(Region::new("us-east-1"))
+        .credentials_provider(Credentials::new("test", "test", None, None, "fake-s3"))
+        .endpoint_url(format!("http://{}", addr))
+        .force_path_style(true)
+        .build();
+
+    (S3Client::from_conf(config), requests)
+}
+
+async fn insert_file(
+    app: &TestApp,
+    user_id: uuid::Uuid,
+    file_path: &str,
+    bucket_name: Option<&str>,
+    object_key: Option<&str>,
+    deleted_days_ago: Option<i64>,
+) -> uuid::Uuid {
+    let file_id = uuid::Uuid::new_v4();
+    let deleted_at = deleted_days_ago.map(|days| Utc::now() - chrono::Duration::days(days));
+
+    sqlx::query(
+        r#"
+        INSERT INTO file_uploads (
+            id, user_id, file_type, file_name, file_path, file_url,
+            file_size, bucket_name, object_key, status, uploaded_at, deleted_at
+        ) VALUES (?, ?, 'document', 'test.pdf', ?, 'https://cdn.example.com/test.pdf',
+            1048576, ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(file_id.to_string())
+    .bind(user_id.to_string())
+    .bind(file_path)
+    .bind(bucket_name)
+    .bind(object_key)
+    .bind(if deleted_at.is_some() {
+        "deleted"
+    } else {
+        "completed"
+    })
+    .bind(Utc::now())
+    .bind(deleted_at)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    file_id
+}
+
+async fn file_exists(app: &TestApp, file_id: uuid::Uuid) -> bool {
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM file_uploads WHERE id = ?")
+        .bind(file_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    count > 0
+}
+
+#[tokio::test]
+async fn test_delete_file_removes_stored_object() {
+    let app = TestApp::new().await;
+    let (user_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (s3_client, requests) = spawn_fake_s3().await;
+
+    let file_id = insert_file(
+        &app,
+        user_id,
+        "document/2024/01/report.pdf",
+        Some("tcm-files"),
+        Some("document/2024/01/report-key.pdf"),
+        None,
+    )
+    .await;
+
+    FileUploadService::delete_file(&app.pool, Some(&s3_client), file_id, user_id, false)
+        .await
+        .unwrap();
+
+    // The object key recorded at upload completion wins over the file path
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec!["DELETE /tcm-files/document/2024/01/report-key.pdf".to_string()]
+    );
+
+    let status: String = sqlx::query_scalar("SELECT status FROM file_uploads WHERE id = ?")
+        .bind(file_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "deleted");
+
+    // A storage failure doesn't block the user's delete; the sweep retries it
+    let denied_id = insert_file(
+        &app,
+        user_id,
+        "document/2024/01/denied.pdf",
+        Some("tcm-files"),
+        Some("document/2024/01/denied.pdf"),
+        None,
+    )
+    .await;
+    FileUploadService::delete_file(&app.pool, Some(&s3_client), denied_id, user_id, false)
+        .await
+        .unwrap();
+    assert!(requests
+        .lock()
+        .unwrap()
+        .contains(&"DELETE /tcm-files/document/2024/01/denied.pdf".to_string()));
+
+    let status: String = sqlx::query_scalar("SELECT status FROM file_uploads WHERE id = ?")
+        .bind(denied_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "deleted");
+}
+
+#[tokio::test]
+async fn test_clean_deleted_files_purges_storage_first() {
+    let app = TestApp::new().await;
+    let (user_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (s3_client, requests) = spawn_fake_s3().await;
+
+    let purged_id = insert_file(
+        &app,
+        user_id,
+        "document/2024/01/old.pdf",
+        Some("tcm-files"),
+        Some("document/2024/01/old.pdf"),
+        Some(31),
+    )
+    .await;
+    let missing_id = insert_file(
+        &app,
+        user_id,
+        "document/2024/01/missing.pdf",
+        Some("tcm-files"),
+        Some("document/2024/01/missing.pdf"),
+        Some(31),
+    )
+    .await;
+    let denied_id = insert_file(
+        &app,
+        user_id,
+        "document/2024/01/denied.pdf",
+        Some("tcm-files"),
+        Some("document/2024/01/denied.pdf"),
+        Some(31),
+    )
+    .await;
+    let recent_id = insert_file(
+        &app,
+        user_id,
+        "document/2024/01/recent.pdf",
+        Some("tcm-files"),
+        Some("document/2024/01/recent.pdf"),
+        Some(1),
+    )
+    .await;
+
+    // Without a storage client nothing is purged, so no object is orphaned
+    let removed = FileUploadService::clean_deleted_files(&app.pool, None)
+        .await
+        .unwrap();
+    assert_eq!(removed, 0);
+    assert!(file_exists(&app, purged_id).await);
+
+    let removed = FileUploadService::clean_deleted_files(&app.pool, Some(&s3_client))
+        .await
+        .unwrap();
+    assert_eq!(removed, 2);
+
+    let recorded = requests.lock().unwrap().clone();
+    assert!(recorded.contains(&"DELETE /tcm-files/document/2024/01/old.pdf".to_string()));
+    assert!(recorded.contains(&"DELETE /tcm-files/document/2024/01/missing.pdf".to_string()));
+    assert!(recorded.contains(&"DELETE /tcm-files/document/2024/01/denied.pdf".to_string()));
+    assert!(!recorded.iter().any(|r| r.contains("recent")));
+
+    // Already gone from storage counts as deleted; a failed delete keeps the record
+    assert!(!file_exists(&app, purged_id).await);
+    assert!(!file_exists(&app, missing_id).await);
+    assert!(file_exists(&app, denied_id).await);
+    assert!(file_exists(&app, recent_id).await);
+}
+
+async fn set_file_upload_config(app: &TestApp, key: &str, value: &str) {
+    sqlx::query(
+        "UPDATE system_configs SET config_value = ? WHERE category = 'file_upload' AND config_key = ?",
+    )
+    .bind(value)
+    .bind(key)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_upload_limits_follow_config() {
+    let mut app = TestApp::new().await;
+    let (_admin_id, admin_email, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_email, &admin_password).await;
+    let (_user_id, email, password) = create_test_user(&app.pool, "patient").await;
+    let token = get_auth_token(&mut app, &email, &password).await;
+
+    let large_document = json!({
+        "file_name": "scan.pdf",
+        "file_type": "document",
+        "file_size": 157286400, // 150MB, over the 100MB default
+        "mime_type": "application/pdf"
+    });
+
+    let (status, body) = app
+        .post_with_auth("/api/v1/files/upload", large_document.clone(), &token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"]
+        .as_str()
+        .unwrap()
+        .contains("文件大小超过限制"));
+
+    // Raising the limit takes effect on the next upload
+    let (status, _) = app
+        .put_with_auth(
+            "/api/v1/files/config/file_upload/max_file_size",
+            json!({
+                "config_value": "209715200",
+                "description": "单个文件最大大小（字节），图片、视频另受各自上限限制"
+            }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .post_with_auth("/api/v1/files/upload", large_document, &token)
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Videos are also bound by their own size limit and format list
+    let large_video = json!({
+        "file_name": "consultation.mkv",
+        "file_type": "video",
+        "file_size": 157286400,
+        "mime_type": "video/mp4"
+    });
+
+    let (status, body) = app
+        .post_with_auth("/api/v1/files/upload", large_video.clone(), &token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"]
+        .as_str()
+        .unwrap()
+        .contains("文件大小超过限制"));
+
+    set_file_upload_config(&app, "max_video_size", "209715200").await;
+    let (status, body) = app
+        .post_with_auth("/api/v1/files/upload", large_video.clone(), &token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"]
+        .as_str()
+        .unwrap()
+        .contains("不支持的文件格式"));
+
+    set_file_upload_config(&app, "allowed_video_types", r#"["mp4","webm","mov","mkv"]"#).await;
+    let (status, _) = app
+        .post_with_auth("/api/v1/files/upload", large_video, &token)
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // MIME types outside the configured allowlist are rejected
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/files/upload",
+            json!({
+                "file_name": "records.zip",
+                "file_type": "document",
+                "file_size": 1048576,
+                "mime_type": "application/zip"
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"]
+        .as_str()
+        .unwrap()
+        .contains("不支持的文件类型"));
+
+    set_file_upload_config(&app, "max_file_size", "104857600").await;
+    set_file_upload_config(&app, "max_video_size", "104857600").await;
+    set_file_upload_config(&app, "allowed_video_types", r#"["mp4","webm","mov"]"#).await;
+}
+#[tokio::test]
+async fn test_upload_url_is_presigned_and_time_bounded() {
+    let mut app = TestApp::new().await;
+
+    let (user_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let token = get_auth_token(&mut app, &account, &password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/files/upload",
+            json!({
+                "file_name": "scan.png",
+                "file_type": "image",
+                "file_size": 2048,
+                "mime_type": "image/png"
+            }),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    let upload_url = body["data"]["upload_url"].as_str().unwrap();
+    assert!(!upload_url.contains("oss.example.com"));
+    assert!(upload_url.contains("X-Amz-Signature="));
+    assert!(upload_url.contains("X-Amz-Expires=1800"));
+    // Content-Type 参与签名，客户端需按声明的类型上传
+    assert!(upload_url.contains("content-type"));
+    assert_eq!(
+        body["data"]["upload_headers"]["Content-Type"]
+            .as_str()
+            .unwrap(),
+        "image/png"
+    );
+
+    let file_path: String =
+        sqlx::query_scalar("SELECT file_path FROM file_uploads WHERE user_id = ?")
+            .bind(user_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert!(upload_url.split('?').next().unwrap().ends_with(&file_path));
+
+    // 未配置对象存储时不再返回伪造地址
+    let dto = serde_json::from_value(json!({
+        "file_name": "scan.png",
+        "file_type": "image",
+        "file_size": 2048,
+        "mime_type": "image/png"
+    }))
+    .unwrap();
+    let result = FileUploadService::create_upload(&app.pool, None, user_id, dto).await;
+    assert!(matches!(
+        result,
+        Err(backend::utils::errors::AppError::InternalServerError(_))
+    ));
+}
+
+async fn init_video_multipart(
+    app: &TestApp,
+    s3_client: &S3Client,
+    user_id: uuid::Uuid,
+) -> MultipartUploadResponse {
+    let dto = serde_json::from_value(json!({
+        "file_name": "consultation.mp4",
+        "file_type": "video",
+        "file_size": 15 * 1024 * 1024,
+        "mime_type": "video/mp4"
+    }))
+    .unwrap();
+
+    FileUploadService::init_multipart_upload(&app.pool, Some(s3_client), user_id, dto)
+        .await
+        .unwrap()
+}
+
+async fn count_upload_parts(app: &TestApp, upload_id: uuid::Uuid) -> i64 {
+    sqlx::query_scalar("SELECT COUNT(*) FROM file_upload_parts WHERE upload_id = ?")
+        .bind(upload_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_multipart_upload_resumes_and_completes() {
+    let mut app = TestApp::new().await;
+    let (user_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let token = get_auth_token(&mut app, &account, &password).await;
+    let (s3_client, requests) = spawn_fake_s3().await;
+
+    let upload = init_video_multipart(&app, &s3_client, user_id).await;
+    assert_eq!(upload.part_count, 2);
+    assert_eq!(upload.parts[0].size, 10 * 1024 * 1024);
+    assert_eq!(upload.parts[1].size, 5 * 1024 * 1024);
+    let part_url = upload.parts[1].upload_url.as_deref().unwrap();
+    assert!(part_url.contains("partNumber=2"));
+    assert!(part_url.contains("uploadId=fake-upload-id"));
+    assert!(part_url.contains("X-Amz-Signature="));
+
+    // 第一片上传后中断，续传时只为剩余分片签发地址
+    let part_path = format!("/api/v1/files/multipart/{}/parts", upload.upload_id);
+    let (status, body) = app
+        .put_with_auth(
+            &format!("{}/1", part_path),
+            json!({ "etag": "\"etag-1\"" }),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["uploaded"], true);
+
+    let resumed = FileUploadService::get_multipart_upload(
+        &app.pool,
+        Some(&s3_client),
+        upload.upload_id,
+        user_id,
+    )
+    .await
+    .unwrap();
+    assert!(resumed.parts[0].uploaded);
+    assert!(resumed.parts[0].upload_url.is_none());
+    assert!(!resumed.parts[1].uploaded);
+    assert!(resumed.parts[1].upload_url.is_some());
+
+    let early = FileUploadService::complete_multipart_upload(
+        &app.pool,
+        Some(&s3_client),
+        upload.upload_id,
+        user_id,
+    )
+    .await;
+    assert!(matches!(
+        early,
+        Err(backend::utils::errors::AppError::BadRequest(_))
+    ));
+
+    let (status, _) = app
+        .put_with_auth(
+            &format!("{}/2", part_path),
+            json!({ "etag": "\"etag-2\"" }),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let file = FileUploadService::complete_multipart_upload(
+        &app.pool,
+        Some(&s3_client),
+        upload.upload_id,
+        user_id,
+    )
+    .await
+    .unwrap();
+    assert_eq!(file.status, UploadStatus::Completed);
+    assert_eq!(file.etag.as_deref(), Some("\"final-etag\""));
+    assert_eq!(file.object_key.as_deref(), Some(file.file_path.as_str()));
+    assert!(file.file_url.ends_with(&file.file_path));
+    assert_eq!(count_upload_parts(&app, upload.upload_id).await, 0);
+
+    let object = format!(
+        "/{}/{}",
+        StorageConfig::from_env().bucket_name,
+        file.file_path
+    );
+    assert_eq!(
+        *requests.lock().unwrap(),
+        vec![format!("POST {}", object), format!("POST {}", object)]
+    );
+}
+
+#[tokio::test]
+async fn test_clean_expired_uploads_aborts_abandoned_multipart() {
+    let app = TestApp::new().await;
+    let (user_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (s3_client, requests) = spawn_fake_s3().await;
+
+    let upload = init_video_multipart(&app, &s3_client, user_id).await;
+    FileUploadService::record_multipart_part(
+        &app.pool,
+        upload.upload_id,
+        user_id,
+        1,
+        serde_json::from_value(json!({ "etag": "\"etag-1\"" })).unwrap(),
+    )
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+        UPDATE file_uploads
+        SET uploaded_at = DATE_SUB(NOW(), INTERVAL 2 HOUR),
+            expires_at = DATE_SUB(NOW(), INTERVAL 1 HOUR)
+        WHERE id = ?
+        "#,
+    )
+    .bind(upload.upload_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    // Without a storage client the uploaded parts can't be discarded, so the upload is kept
+    FileUploadService::clean_expired_uploads(&app.pool, None)
+        .await
+        .unwrap();
+    let file = FileUploadService::get_file(&app.pool, upload.upload_id)
+        .await
+        .unwrap();
+    assert_eq!(file.status, UploadStatus::Uploading);
+
+    FileUploadService::clean_expired_uploads(&app.pool, Some(&s3_client))
+        .await
+        .unwrap();
+    let file = FileUploadService::get_file(&app.pool, upload.upload_id)
+        .await
+        .unwrap();
+    assert_eq!(file.status, UploadStatus::Failed);
+    assert_eq!(file.error_message.as_deref(), Some("上传超时"));
+    assert_eq!(count_upload_parts(&app, upload.upload_id).await, 0);
+
+    let object = format!(
+        "/{}/{}",
+        StorageConfig::from_env().bucket_name,
+        file.file_path
+    );
+    assert!(requests
+        .lock()
+        .unwrap()
+        .contains(&format!("DELETE {}", object)));
+}
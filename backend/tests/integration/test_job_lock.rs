@@ -0,0 +1,256 @@
+use backend::{
+    config::redis,
+    services::{job_lock_service::JobLockService, scheduler_service::SchedulerService},
+    utils::test_helpers::create_test_pool,
+};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use uuid::Uuid;
+
+/// 测试中的任务周期，足够长以保证同一测试内的几次触发落在同一周期
+const PERIOD: Duration = Duration::from_secs(60 * 60);
+
+fn unique_job_name() -> String {
+    format!("test_{}", Uuid::new_v4().simple())
+}
+
+#[tokio::test]
+async fn test_job_runs_on_only_one_runner() {
+    let pool = create_test_pool().await;
+    let redis = redis::create_redis_pool_optional().await;
+    let name = unique_job_name();
+    let runs = Arc::new(AtomicU64::new(0));
+
+    let job = {
+        let runs = runs.clone();
+        move || {
+            let runs = runs.clone();
+            async move {
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                runs.fetch_add(1, Ordering::SeqCst);
+                Ok(1)
+            }
+        }
+    };
+
+    // Two instances tick at the same time
+    let (first, second) = tokio::join!(
+        SchedulerService::run_exclusive(&pool, &redis, &name, PERIOD, &job),
+        SchedulerService::run_exclusive(&pool, &redis, &name, PERIOD, &job),
+    );
+
+    let results = [first.unwrap(), second.unwrap()];
+    assert_eq!(results.iter().filter(|r| r.is_some()).count(), 1);
+    assert_eq!(results.iter().filter(|r| r.is_none()).count(), 1);
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    // A runner whose tick lands later in the same period must not run the job again
+    let late = SchedulerService::run_exclusive(&pool, &redis, &name, PERIOD, &job)
+        .await
+        .unwrap();
+    assert_eq!(late, None);
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+}
+
+#[tokio::test]
+async fn test_job_runs_again_in_next_period() {
+    let pool = create_test_pool().await;
+    let redis = redis::create_redis_pool_optional().await;
+    let name = unique_job_name();
+    let period = Duration::from_millis(500);
+    let runs = Arc::new(AtomicU64::new(0));
+
+    let job = {
+        let runs = runs.clone();
+        move || {
+            let runs = runs.clone();
+            async move { Ok(runs.fetch_add(1, Ordering::SeqCst) + 1) }
+        }
+    };
+
+    let first = SchedulerService::run_exclusive(&pool, &redis, &name, period, &job)
+        .await
+        .unwrap();
+    assert_eq!(first, Some(1));
+
+    tokio::time::sleep(period + Duration::from_millis(100)).await;
+
+    let next = SchedulerService::run_exclusive(&pool, &redis, &name, period, &job)
+        .await
+        .unwrap();
+    assert_eq!(next, Some(2));
+}
+
+#[tokio::test]
+async fn test_redis_release_does_not_release_foreign_lock() {
+    let redis = redis::create_redis_pool()
+        .await
+        .expect("Redis is required for this test");
+    let key = format!("job:{}", unique_job_name());
+
+    // Runner A's lock expires while it is still "working"
+    let lock_a = redis::acquire_lock(&redis, &key, Duration::from_millis(200))
+        .await
+        .unwrap()
+        .unwrap();
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let lock_b = redis::acquire_lock(&redis, &key, Duration::from_secs(10))
+        .await
+        .unwrap()
+        .unwrap();
+
+    // A's late release and heartbeat must not touch B's lock
+    assert!(!redis::release_lock(&redis, &lock_a).await.unwrap());
+    assert!(
+        !redis::extend_lock(&redis, &lock_a, Duration::from_secs(10))
+            .await
+            .unwrap()
+    );
+    assert!(redis::acquire_lock(&redis, &key, Duration::from_secs(10))
+        .await
+        .unwrap()
+        .is_none());
+
+    assert!(redis::extend_lock(&redis, &lock_b, Duration::from_secs(10))
+        .await
+        .unwrap());
+    assert!(redis::release_lock(&redis, &lock_b).await.unwrap());
+
+    let lock_c = redis::acquire_lock(&redis, &key, Duration::from_secs(10))
+        .await
+        .unwrap()
+        .expect("released lock should be acquirable");
+    assert!(redis::release_lock(&redis, &lock_c).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_redis_lock_expires_after_crash() {
+    let redis = redis::create_redis_pool()
+        .await
+        .expect("Redis is required for this test");
+    let key = format!("job:{}", unique_job_name());
+
+    // The holder crashes without releasing
+    let crashed = redis::acquire_lock(&redis, &key, Duration::from_millis(200))
+        .await
+        .unwrap();
+    assert!(crashed.is_some());
+    assert!(
+        redis::acquire_lock(&redis, &key, Duration::from_millis(200))
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let lock = redis::acquire_lock(&redis, &key, Duration::from_secs(10))
+        .await
+        .unwrap()
+        .expect("expired lock should be acquirable");
+    assert!(redis::release_lock(&redis, &lock).await.unwrap());
+}
+
+#[tokio::test]
+async fn test_database_lock_fallback() {
+    let pool = create_test_pool().await;
+    let name = unique_job_name();
+    let key = format!("job:{}", name);
+    let runs = Arc::new(AtomicU64::new(0));
+
+    // Without Redis the runner holds a row in job_locks while the job runs
+    let job = {
+        let pool = pool.clone();
+        let key = key.clone();
+        let runs = runs.clone();
+        move || {
+            let pool = pool.clone();
+            let key = key.clone();
+            let runs = runs.clone();
+            async move {
+                let held: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM job_locks WHERE name = ?")
+                    .bind(&key)
+                    .fetch_one(&pool)
+                    .await
+                    .unwrap();
+                assert_eq!(held, 1);
+                tokio::time::sleep(Duration::from_millis(300)).await;
+                runs.fetch_add(1, Ordering::SeqCst);
+                Ok(0)
+            }
+        }
+    };
+
+    let (first, second) = tokio::join!(
+        SchedulerService::run_exclusive(&pool, &None, &name, PERIOD, &job),
+        SchedulerService::run_exclusive(&pool, &None, &name, PERIOD, &job),
+    );
+    let results = [first.unwrap(), second.unwrap()];
+    assert_eq!(results.iter().filter(|r| r.is_some()).count(), 1);
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+
+    let remaining: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM job_locks WHERE name = ?")
+        .bind(&key)
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(remaining, 0);
+
+    // The period marker outlives the run so later ticks in this period are skipped
+    let markers: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM job_locks WHERE name LIKE ?")
+        .bind(format!("{}:%", key))
+        .fetch_one(&pool)
+        .await
+        .unwrap();
+    assert_eq!(markers, 1);
+}
+
+#[tokio::test]
+async fn test_database_lock_release_and_expiry() {
+    let pool = create_test_pool().await;
+    let key = format!("job:{}", unique_job_name());
+
+    // A crashed holder's lock blocks others until it expires
+    let crashed = JobLockService::acquire_db_lock(&pool, &key, Duration::from_millis(200))
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(
+        JobLockService::acquire_db_lock(&pool, &key, Duration::from_secs(10))
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    let lock = JobLockService::acquire_db_lock(&pool, &key, Duration::from_secs(10))
+        .await
+        .unwrap()
+        .expect("expired lock should be acquirable");
+
+    // The crashed holder's token no longer releases or extends anything
+    assert!(!JobLockService::release(&pool, &None, &crashed)
+        .await
+        .unwrap());
+    assert!(
+        !JobLockService::extend(&pool, &None, &crashed, Duration::from_secs(10))
+            .await
+            .unwrap()
+    );
+    assert!(
+        JobLockService::acquire_db_lock(&pool, &key, Duration::from_secs(10))
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    assert!(
+        JobLockService::extend(&pool, &None, &lock, Duration::from_secs(10))
+            .await
+            .unwrap()
+    );
+    assert!(JobLockService::release(&pool, &None, &lock).await.unwrap());
+}
@@ -1,7 +1,20 @@
 use crate::common::TestApp;
 use axum::http::StatusCode;
-use backend::{models::user::LoginDto, utils::test_helpers::create_test_user};
+use backend::{
+    models::{
+        broadcast::{BroadcastStatus, CreateBroadcastDto, SegmentDefinition},
+        notification::{CreateNotificationDto, NotificationType},
+        user::{CreateUserDto, LoginDto, UserRole},
+    },
+    services::{
+        broadcast_service::{BroadcastLimits, BroadcastService},
+        notification_service::NotificationService,
+    },
+    utils::test_helpers::{create_test_doctor, create_test_user},
+};
 use serde_json::json;
+use sqlx::MySqlPool;
+use uuid::Uuid;
 
 async fn get_auth_token(app: &mut TestApp, account: &str, password: &str) -> String {
     let login_dto = LoginDto {
@@ -410,3 +423,489 @@ async fn test_notification_authorization() {
     assert_eq!(status, StatusCode::NOT_FOUND);
     assert_eq!(body["success"], false);
 }
+
+/// 创建一个独立科室的医生及若干近期就诊过的患者，避免与其他测试的数据互相干扰
+async fn seed_department_patients(pool: &MySqlPool, count: usize) -> (String, Vec<Uuid>) {
+    let department = format!("广播测试-{}", &Uuid::new_v4().simple().to_string()[..8]);
+    let (doctor_user_id, _, _) = create_test_user(pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(pool, doctor_user_id).await;
+    sqlx::query("UPDATE doctors SET department = ? WHERE id = ?")
+        .bind(&department)
+        .bind(doctor_id.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+
+    let mut patient_ids = Vec::new();
+    for _ in 0..count {
+        let (patient_id, _, _) = create_test_user(pool, "patient").await;
+        sqlx::query(
+            r#"
+            INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot,
+                                      visit_type, symptoms, has_visited_before, status)
+            VALUES (?, ?, ?, NOW(), '09:00-10:00', 'offline', '头痛', false, 'completed')
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(patient_id.to_string())
+        .bind(doctor_id.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+        patient_ids.push(patient_id);
+    }
+
+    (department, patient_ids)
+}
+
+fn department_segment(department: &str) -> SegmentDefinition {
+    SegmentDefinition {
+        roles: Some(vec!["patient".to_string()]),
+        last_appointment_within_days: Some(90),
+        doctor_departments: Some(vec![department.to_string()]),
+        ..Default::default()
+    }
+}
+
+async fn count_broadcast_notifications(pool: &MySqlPool, broadcast_id: &str) -> i64 {
+    use sqlx::Row;
+    sqlx::query(
+        "SELECT COUNT(*) AS count FROM notifications WHERE JSON_UNQUOTE(JSON_EXTRACT(metadata, '$.broadcast_id')) = ?",
+    )
+    .bind(broadcast_id)
+    .fetch_one(pool)
+    .await
+    .unwrap()
+    .get("count")
+}
+
+#[tokio::test]
+async fn test_segment_preview_matches_broadcast_delivery() {
+    let mut app = TestApp::new().await;
+
+    let (_, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+
+    let (department, patient_ids) = seed_department_patients(&app.pool, 3).await;
+
+    // One patient has turned off system announcements
+    sqlx::query(
+        r#"
+        INSERT INTO notification_settings (id, user_id, notification_type, enabled, email_enabled, sms_enabled, push_enabled)
+        VALUES (?, ?, 'system_announcement', false, false, false, false)
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(patient_ids[0].to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    // Save the segment and preview it
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/notifications/segments",
+            json!({ "name": "近期就诊患者", "definition": department_segment(&department) }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let segment_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/notifications/segments/preview",
+            json!({ "segment_id": segment_id }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let preview_size = body["data"]["size"].as_i64().unwrap();
+    assert_eq!(preview_size, 3);
+    assert_eq!(body["data"]["sample"].as_array().unwrap().len(), 3);
+
+    // Broadcast to the saved segment
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/notifications/broadcasts",
+            json!({
+                "title": "健康讲座",
+                "content": "本周六举办中医养生讲座",
+                "segment_id": segment_id
+            }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["status"], "sent");
+    assert_eq!(
+        body["data"]["targeted_count"].as_i64().unwrap(),
+        preview_size
+    );
+    assert_eq!(body["data"]["delivered_count"], 2);
+    assert_eq!(body["data"]["skipped_count"], 1);
+
+    let broadcast_id = body["data"]["id"].as_str().unwrap();
+    assert_eq!(
+        count_broadcast_notifications(&app.pool, broadcast_id).await,
+        2
+    );
+}
+
+#[tokio::test]
+async fn test_segment_validation_and_permissions() {
+    let mut app = TestApp::new().await;
+
+    let (_, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (_, patient_account, patient_password) = create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/notifications/segments/preview",
+            json!({ "definition": { "roles": ["superuser"] } }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/notifications/segments/preview",
+            json!({ "definition": { "roles": ["patient"] } }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_large_broadcast_requires_second_admin() {
+    let app = TestApp::new().await;
+
+    let (admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (second_admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (department, _) = seed_department_patients(&app.pool, 2).await;
+
+    let limits = BroadcastLimits {
+        max_segment_size: 100,
+        approval_threshold: 1,
+    };
+    let dto = || CreateBroadcastDto {
+        title: "平台公告".to_string(),
+        content: "系统将于今晚维护".to_string(),
+        related_id: None,
+        segment_id: None,
+        definition: Some(department_segment(&department)),
+    };
+
+    // Exceeding the hard cap is rejected outright
+    let capped = BroadcastLimits {
+        max_segment_size: 1,
+        approval_threshold: 1,
+    };
+    assert!(
+        BroadcastService::create_broadcast(&app.pool, admin_id, dto(), &capped)
+            .await
+            .is_err()
+    );
+
+    let broadcast = BroadcastService::create_broadcast(&app.pool, admin_id, dto(), &limits)
+        .await
+        .unwrap();
+    assert_eq!(broadcast.status, BroadcastStatus::PendingApproval);
+    assert_eq!(broadcast.targeted_count, 2);
+    assert_eq!(
+        count_broadcast_notifications(&app.pool, &broadcast.id.to_string()).await,
+        0
+    );
+
+    // The requesting admin cannot approve their own broadcast
+    assert!(
+        BroadcastService::approve_broadcast(&app.pool, broadcast.id, admin_id, &limits)
+            .await
+            .is_err()
+    );
+
+    let approved =
+        BroadcastService::approve_broadcast(&app.pool, broadcast.id, second_admin_id, &limits)
+            .await
+            .unwrap();
+    assert_eq!(approved.status, BroadcastStatus::Sent);
+    assert_eq!(approved.reviewed_by, Some(second_admin_id));
+    assert_eq!(approved.delivered_count, 2);
+    assert_eq!(
+        count_broadcast_notifications(&app.pool, &broadcast.id.to_string()).await,
+        2
+    );
+
+    // Already sent broadcasts cannot be approved again
+    assert!(
+        BroadcastService::approve_broadcast(&app.pool, broadcast.id, second_admin_id, &limits)
+            .await
+            .is_err()
+    );
+}
+
+async fn insert_notification(pool: &MySqlPool, user_id: Uuid, status: &str, age_days: i64) -> Uuid {
+    let notification_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO notifications (id, user_id, type, title, content, status, metadata, created_at)
+        VALUES (?, ?, 'system_announcement', '测试通知', '测试内容', ?, '{}', DATE_SUB(NOW(), INTERVAL ? DAY))
+        "#,
+    )
+    .bind(notification_id.to_string())
+    .bind(user_id.to_string())
+    .bind(status)
+    .bind(age_days)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    notification_id
+}
+
+async fn notification_exists(pool: &MySqlPool, notification_id: Uuid) -> bool {
+    sqlx::query("SELECT id FROM notifications WHERE id = ?")
+        .bind(notification_id.to_string())
+        .fetch_optional(pool)
+        .await
+        .unwrap()
+        .is_some()
+}
+
+#[tokio::test]
+async fn test_clean_old_notifications_keeps_unread_and_recent() {
+    let app = TestApp::new().await;
+    let (user_id, _, _) = create_test_user(&app.pool, "patient").await;
+
+    let old_read = insert_notification(&app.pool, user_id, "read", 400).await;
+    let old_deleted = insert_notification(&app.pool, user_id, "deleted", 400).await;
+    let old_unread = insert_notification(&app.pool, user_id, "unread", 400).await;
+    let recent_read = insert_notification(&app.pool, user_id, "read", 1).await;
+
+    // Retention counts from when it was read, not when it was sent
+    let old_but_just_read = insert_notification(&app.pool, user_id, "read", 400).await;
+    sqlx::query("UPDATE notifications SET read_at = DATE_SUB(NOW(), INTERVAL 1 DAY) WHERE id = ?")
+        .bind(old_but_just_read.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let purged = NotificationService::clean_old_notifications(&app.pool)
+        .await
+        .unwrap();
+    assert!(purged >= 2);
+
+    assert!(!notification_exists(&app.pool, old_read).await);
+    assert!(!notification_exists(&app.pool, old_deleted).await);
+    assert!(notification_exists(&app.pool, old_unread).await);
+    assert!(notification_exists(&app.pool, recent_read).await);
+    assert!(notification_exists(&app.pool, old_but_just_read).await);
+}
+
+#[tokio::test]
+async fn test_clear_all_read_notifications() {
+    let mut app = TestApp::new().await;
+
+    let (user_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let token = get_auth_token(&mut app, &account, &password).await;
+    let (other_user_id, _, _) = create_test_user(&app.pool, "patient").await;
+
+    insert_notification(&app.pool, user_id, "read", 1).await;
+    insert_notification(&app.pool, user_id, "read", 2).await;
+    let unread = insert_notification(&app.pool, user_id, "unread", 1).await;
+    let other_read = insert_notification(&app.pool, other_user_id, "read", 1).await;
+
+    let (status, body) = app
+        .delete_with_auth("/api/v1/notifications/read", &token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["count"], 2);
+
+    let (status, body) = app.get_with_auth("/api/v1/notifications", &token).await;
+    assert_eq!(status, StatusCode::OK);
+    let items = body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], unread.to_string());
+
+    // Other users' notifications are untouched
+    let other_status: String = {
+        use sqlx::Row;
+        sqlx::query("SELECT status FROM notifications WHERE id = ?")
+            .bind(other_read.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap()
+            .get("status")
+    };
+    assert_eq!(other_status, "read");
+}
+
+#[tokio::test]
+async fn test_new_users_get_templated_notification_defaults() {
+    let mut app = TestApp::new().await;
+
+    // Registration seeds one settings row per type from the template
+    let account = format!("ns_{}", &Uuid::new_v4().to_string()[..8]);
+    let user_dto = CreateUserDto {
+        account: account.clone(),
+        name: "通知默认".to_string(),
+        password: "password123".to_string(),
+        gender: "女".to_string(),
+        phone: format!("139{}", &Uuid::new_v4().as_u128().to_string()[..8]),
+        email: None,
+        birthday: None,
+        role: UserRole::Patient,
+    };
+    let (status, _) = app.post("/api/v1/auth/register", user_dto).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let token = get_auth_token(&mut app, &account, "password123").await;
+    let (status, body) = app
+        .get_with_auth("/api/v1/notifications/settings", &token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let settings = body["data"].as_array().unwrap();
+    assert_eq!(settings.len(), NotificationType::ALL.len());
+
+    let setting_for = |notification_type: &str| {
+        settings
+            .iter()
+            .find(|s| s["notification_type"] == notification_type)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(setting_for("live_stream_reminder")["enabled"], false);
+    assert_eq!(setting_for("appointment_reminder")["enabled"], true);
+    assert_eq!(setting_for("appointment_reminder")["sms_enabled"], true);
+
+    // Users without settings rows fall back to the same template
+    let (user_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let dto = |notification_type: NotificationType| CreateNotificationDto {
+        user_id,
+        notification_type,
+        title: "title".to_string(),
+        content: "content".to_string(),
+        related_id: None,
+        metadata: None,
+    };
+
+    let (enabled, _, sms_enabled, _) = NotificationService::should_send_notification(
+        &app.pool,
+        user_id,
+        &NotificationType::AppointmentReminder,
+    )
+    .await
+    .unwrap();
+    assert!(enabled);
+    assert!(sms_enabled);
+
+    let skipped = NotificationService::create_notification_if_enabled(
+        &app.pool,
+        dto(NotificationType::LiveStreamReminder),
+    )
+    .await
+    .unwrap();
+    assert!(skipped.is_none());
+
+    let created = NotificationService::create_notification_if_enabled(
+        &app.pool,
+        dto(NotificationType::AppointmentConfirmed),
+    )
+    .await
+    .unwrap();
+    assert!(created.is_some());
+
+    // Opting in only overrides the given field; the rest comes from the template
+    let token = get_auth_token(&mut app, &account, &password).await;
+    let (status, body) = app
+        .put_with_auth(
+            "/api/v1/notifications/settings",
+            json!({ "notification_type": "live_stream_reminder", "enabled": true }),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["enabled"], true);
+    assert_eq!(body["data"]["push_enabled"], false);
+
+    let created = NotificationService::create_notification_if_enabled(
+        &app.pool,
+        dto(NotificationType::LiveStreamReminder),
+    )
+    .await
+    .unwrap();
+    assert!(created.is_some());
+}
+
+#[tokio::test]
+async fn test_filter_notifications_by_related_id() {
+    let mut app = TestApp::new().await;
+
+    let (user_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let token = get_auth_token(&mut app, &account, &password).await;
+
+    let order_id = Uuid::new_v4();
+    let appointment_id = Uuid::new_v4();
+    for (notification_type, related_id, status) in [
+        ("payment_update", order_id, "unread"),
+        ("payment_update", order_id, "read"),
+        ("appointment_confirmed", order_id, "unread"),
+        ("appointment_reminder", appointment_id, "unread"),
+    ] {
+        sqlx::query(
+            r#"
+            INSERT INTO notifications (id, user_id, type, title, content, related_id, status, metadata, created_at)
+            VALUES (?, ?, ?, '测试通知', '测试内容', ?, ?, '{}', NOW())
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(user_id.to_string())
+        .bind(notification_type)
+        .bind(related_id.to_string())
+        .bind(status)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    // 只返回该订单相关的通知
+    let (status, body) = app
+        .get_with_auth(
+            &format!("/api/v1/notifications?related_id={}", order_id),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["total"].as_i64().unwrap(), 3);
+    let items = body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 3);
+    assert!(items
+        .iter()
+        .all(|n| n["related_id"] == order_id.to_string()));
+
+    // 可以与类型、状态条件组合
+    let (status, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/notifications?related_id={}&type=payment_update&status=unread",
+                order_id
+            ),
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["total"].as_i64().unwrap(), 1);
+    assert_eq!(body["data"]["items"][0]["type"], "payment_update");
+
+    let (_, body) = app
+        .get_with_auth(
+            &format!("/api/v1/notifications?related_id={}", appointment_id),
+            &token,
+        )
+        .await;
+    assert_eq!(body["data"]["total"].as_i64().unwrap(), 1);
+}
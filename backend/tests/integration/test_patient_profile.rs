@@ -397,3 +397,238 @@ async fn test_patient_profile_first_default() {
         }
     }
 }
+
+#[tokio::test]
+async fn test_family_profile_limit() {
+    let mut app = TestApp::new().await;
+
+    let (_patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    // ID number is optional for managed profiles
+    for i in 0..5 {
+        let (status, body) = app
+            .post_with_auth(
+                "/api/v1/patient-profiles",
+                json!({
+                    "name": format!("家人{}", i),
+                    "phone": "13900139000",
+                    "gender": "女",
+                    "relationship": "family"
+                }),
+                &patient_token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK, "profile {} failed: {:?}", i, body);
+    }
+
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/patient-profiles",
+            json!({
+                "name": "家人6",
+                "phone": "13900139000",
+                "gender": "男",
+                "relationship": "other"
+            }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // The account owner's own profile does not count towards the cap
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/patient-profiles",
+            json!({
+                "name": "本人",
+                "id_number": "110101900101131",
+                "phone": "13800138000",
+                "gender": "男",
+                "relationship": "self"
+            }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = app
+        .get_with_auth("/api/v1/patient-profiles", &patient_token)
+        .await;
+    assert_eq!(body["data"].as_array().unwrap().len(), 6);
+}
+
+#[tokio::test]
+async fn test_patient_profile_partial_update() {
+    let mut app = TestApp::new().await;
+
+    let (_patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/patient-profiles",
+            json!({
+                "name": "王五",
+                "phone": "13900139000",
+                "gender": "女",
+                "birthday": "1960-05-20",
+                "relationship": "family"
+            }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let profile_id = body["data"]["id"].as_str().unwrap().to_string();
+    let profile_path = format!("/api/v1/patient-profiles/{}", profile_id);
+
+    // Only the provided field changes; omitted fields are preserved
+    let (status, body) = app
+        .patch_with_auth(&profile_path, json!({ "name": "王小五" }), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["name"], "王小五");
+    assert_eq!(body["data"]["phone"], "13900139000");
+    assert_eq!(body["data"]["gender"], "女");
+    assert_eq!(body["data"]["birthday"], "1960-05-20");
+    assert_eq!(body["data"]["relationship"], "family");
+
+    // A birthday in the future is rejected and nothing changes
+    let tomorrow = (chrono::Utc::now() + chrono::Duration::days(1)).date_naive();
+    let (status, _) = app
+        .patch_with_auth(
+            &profile_path,
+            json!({ "name": "王五五", "birthday": tomorrow.to_string() }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .patch_with_auth(
+            &profile_path,
+            json!({ "phone": "2390013900a" }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (_, body) = app.get_with_auth(&profile_path, &patient_token).await;
+    assert_eq!(body["data"]["name"], "王小五");
+    assert_eq!(body["data"]["birthday"], "1960-05-20");
+    assert_eq!(body["data"]["phone"], "13900139000");
+}
+
+#[tokio::test]
+async fn test_patient_address_default() {
+    let mut app = TestApp::new().await;
+
+    let (_patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    let address = |detail: &str, is_default: bool| {
+        json!({
+            "recipient_name": "张三",
+            "phone": "13800138000",
+            "province": "北京市",
+            "city": "北京市",
+            "district": "朝阳区",
+            "detail": detail,
+            "is_default": is_default
+        })
+    };
+
+    // The first address becomes the default even when not asked to
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/patient-profiles/addresses",
+            address("建国路1号", false),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"]["is_default"].as_bool().unwrap());
+    let home_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/patient-profiles/addresses",
+            address("建国路2号", false),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!body["data"]["is_default"].as_bool().unwrap());
+    let office_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    // The default cannot be deleted while another address exists
+    let (status, _) = app
+        .delete_with_auth(
+            &format!("/api/v1/patient-profiles/addresses/{}", home_id),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .put_with_auth(
+            &format!("/api/v1/patient-profiles/addresses/{}/default", office_id),
+            json!({}),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .get_with_auth("/api/v1/patient-profiles/addresses", &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let addresses = body["data"].as_array().unwrap();
+    assert_eq!(addresses.len(), 2);
+    assert_eq!(addresses[0]["id"], office_id);
+    assert!(addresses[0]["is_default"].as_bool().unwrap());
+    assert!(!addresses[1]["is_default"].as_bool().unwrap());
+
+    // Once reassigned, the old default can go, and so can the last address
+    for id in [&home_id, &office_id] {
+        let (status, _) = app
+            .delete_with_auth(
+                &format!("/api/v1/patient-profiles/addresses/{}", id),
+                &patient_token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (_, body) = app
+        .get_with_auth("/api/v1/patient-profiles/addresses", &patient_token)
+        .await;
+    assert!(body["data"].as_array().unwrap().is_empty());
+
+    // Addresses belong to their owner
+    let (_, other_account, other_password) = create_test_user(&app.pool, "patient").await;
+    let other_token = get_auth_token(&mut app, &other_account, &other_password).await;
+    let (_, body) = app
+        .post_with_auth(
+            "/api/v1/patient-profiles/addresses",
+            address("望京街3号", true),
+            &other_token,
+        )
+        .await;
+    let other_address_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, _) = app
+        .put_with_auth(
+            &format!(
+                "/api/v1/patient-profiles/addresses/{}/default",
+                other_address_id
+            ),
+            json!({}),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -1,13 +1,28 @@
-use crate::common::TestApp;
+use crate::common::{configure_alipay, MockGateway, TestApp, TEST_PRIVATE_KEY};
 use axum::http::StatusCode;
 use backend::{
-    models::{payment::*, user::LoginDto},
-    utils::test_helpers::create_test_user,
+    models::{
+        appointment::{AppointmentStatus, UpdateAppointmentDto},
+        payment::*,
+        user::LoginDto,
+        video_consultation::CompleteConsultationDto,
+        withdrawal::CreateWithdrawalDto,
+    },
+    services::{
+        alipay_service::AlipayService, appointment_service, payment_service::PaymentService,
+        video_consultation_service::VideoConsultationService,
+        withdrawal_service::WithdrawalService,
+    },
+    utils::{
+        crypto,
+        test_helpers::{create_test_doctor, create_test_user},
+    },
 };
 use chrono;
 use rust_decimal::Decimal;
 use serde_json::json;
 use sqlx;
+use std::collections::BTreeMap;
 use std::str::FromStr;
 use uuid::Uuid;
 
@@ -36,6 +51,8 @@ async fn test_create_order() {
         amount: Decimal::from_str("30.00").unwrap(),
         description: Some("图文咨询服务".to_string()),
         metadata: None,
+        coupon_code: None,
+        idempotency_key: None,
     };
 
     let (status, body) = app
@@ -50,6 +67,62 @@ async fn test_create_order() {
     assert_eq!(body["data"]["status"].as_str().unwrap(), "pending");
 }
 
+#[tokio::test]
+async fn test_create_order_outside_amount_limits_rejected() {
+    let mut app = TestApp::new().await;
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    // Consultation orders are limited to 1 ~ 5000 yuan
+    for amount in ["0.00", "0.50", "100000.00"] {
+        let order_dto = CreateOrderDto {
+            user_id: patient_user_id,
+            appointment_id: None,
+            order_type: OrderType::Consultation,
+            amount: Decimal::from_str(amount).unwrap(),
+            description: None,
+            metadata: None,
+            coupon_code: None,
+            idempotency_key: None,
+        };
+
+        let (status, body) = app
+            .post_with_auth("/api/v1/payment/orders", order_dto, &patient_token)
+            .await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST, "amount {}", amount);
+        assert!(!body["success"].as_bool().unwrap());
+    }
+
+    let order_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM payment_orders WHERE user_id = ?")
+            .bind(patient_user_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(order_count, 0);
+
+    // The boundaries themselves are accepted
+    for amount in ["1.00", "5000.00"] {
+        let order_dto = CreateOrderDto {
+            user_id: patient_user_id,
+            appointment_id: None,
+            order_type: OrderType::Consultation,
+            amount: Decimal::from_str(amount).unwrap(),
+            description: None,
+            metadata: None,
+            coupon_code: None,
+            idempotency_key: None,
+        };
+
+        let (status, _) = app
+            .post_with_auth("/api/v1/payment/orders", order_dto, &patient_token)
+            .await;
+        assert_eq!(status, StatusCode::CREATED, "amount {}", amount);
+    }
+}
+
 #[tokio::test]
 async fn test_get_order() {
     let mut app = TestApp::new().await;
@@ -65,6 +138,8 @@ async fn test_get_order() {
         amount: Decimal::from_str("30.00").unwrap(),
         description: Some("图文咨询服务".to_string()),
         metadata: None,
+        coupon_code: None,
+        idempotency_key: None,
     };
 
     let (_, create_body) = app
@@ -101,6 +176,8 @@ async fn test_list_orders() {
             amount: Decimal::from_str(&format!("{}.00", (i + 1) * 10)).unwrap(),
             description: Some(format!("订单 {}", i + 1)),
             metadata: None,
+            coupon_code: None,
+            idempotency_key: None,
         };
 
         app.post_with_auth("/api/v1/payment/orders", order_dto, &patient_token)
@@ -133,6 +210,8 @@ async fn test_cancel_order() {
         amount: Decimal::from_str("30.00").unwrap(),
         description: Some("图文咨询服务".to_string()),
         metadata: None,
+        coupon_code: None,
+        idempotency_key: None,
     };
 
     let (_, create_body) = app
@@ -175,6 +254,8 @@ async fn test_initiate_payment() {
         amount: Decimal::from_str("30.00").unwrap(),
         description: Some("图文咨询服务".to_string()),
         metadata: None,
+        coupon_code: None,
+        idempotency_key: None,
     };
 
     let (_, create_body) = app
@@ -183,10 +264,13 @@ async fn test_initiate_payment() {
     let order_id = Uuid::parse_str(create_body["data"]["id"].as_str().unwrap()).unwrap();
 
     // Initiate payment
+    configure_alipay(&app.pool).await;
     let payment_dto = InitiatePaymentDto {
         order_id,
         payment_method: PaymentMethod::Alipay,
         return_url: Some("https://example.com/return".to_string()),
+        openid: None,
+        wap: false,
     };
 
     let (status, body) = app
@@ -243,6 +327,8 @@ async fn test_create_refund() {
         amount,
         description: Some("图文咨询服务".to_string()),
         metadata: None,
+        coupon_code: None,
+        idempotency_key: None,
     };
 
     let (_, create_body) = app
@@ -331,7 +417,19 @@ async fn test_get_user_balance() {
 
 #[tokio::test]
 async fn test_admin_review_refund() {
-    let mut app = TestApp::new().await;
+    let gateway = std::sync::Arc::new(MockGateway::new(vec![(
+        200,
+        json!({
+            "alipay_trade_refund_response": {
+                "code": "10000",
+                "msg": "Success",
+                "trade_no": "2024012222001400000000000001",
+                "fund_change": "Y",
+            }
+        }),
+    )]));
+    let mut app = TestApp::with_gateway(gateway.clone()).await;
+    configure_alipay(&app.pool).await;
     let (_admin_user_id, admin_account, admin_password) =
         create_test_user(&app.pool, "admin").await;
     let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
@@ -412,6 +510,23 @@ async fn test_admin_review_refund() {
         println!("Admin review refund failed: status={:?}, body={:?}", status, body);
     }
     assert_eq!(status, StatusCode::OK);
+
+    // Alipay confirmed the refund synchronously
+    let requests = gateway.requests();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0]
+        .body
+        .as_deref()
+        .unwrap()
+        .contains("method=alipay.trade.refund"));
+    let refund = PaymentService::get_refund(&app.pool, refund_id)
+        .await
+        .unwrap();
+    assert_eq!(refund.status, RefundStatus::Success);
+    assert_eq!(
+        refund.external_refund_id.as_deref(),
+        Some("2024012222001400000000000001")
+    );
 }
 
 #[tokio::test]
@@ -477,6 +592,8 @@ async fn test_unauthorized_access() {
         amount: Decimal::from_str("30.00").unwrap(),
         description: Some("图文咨询服务".to_string()),
         metadata: None,
+        coupon_code: None,
+        idempotency_key: None,
     };
 
     let (_, create_body) = app
@@ -494,3 +611,3038 @@ async fn test_unauthorized_access() {
 
     assert_eq!(status, StatusCode::FORBIDDEN);
 }
+
+async fn seed_doctor_earnings(pool: &sqlx::MySqlPool, doctor_id: Uuid, amount: &str) {
+    let (patient_id, _, _) = create_test_user(pool, "patient").await;
+
+    let appointment_id = Uuid::new_v4();
+    let order_id = Uuid::new_v4().to_string();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot,
+                                  visit_type, symptoms, has_visited_before, status, created_at, updated_at)
+        VALUES (?, ?, ?, NOW(), '09:00-10:00', 'online_video', '测试症状', false, 'completed', NOW(), NOW())
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .execute(pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+        INSERT INTO payment_orders (
+            id, order_no, user_id, appointment_id, order_type, amount, currency,
+            status, payment_method, payment_time, expire_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, 'appointment', ?, 'CNY', 'paid', 'alipay', NOW(), DATE_ADD(NOW(), INTERVAL 2 HOUR), NOW(), NOW())
+        "#,
+    )
+    .bind(&order_id)
+    .bind(format!("ORD{}", Uuid::new_v4().simple()))
+    .bind(patient_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(Decimal::from_str(amount).unwrap())
+    .execute(pool)
+    .await
+    .unwrap();
+
+    // Booked without commission so the whole amount is withdrawable
+    sqlx::query(
+        r#"
+        INSERT INTO doctor_earnings (
+            id, doctor_id, order_id, appointment_id, entry_type, source_id,
+            gross_amount, commission_rate, commission_amount, net_amount
+        ) VALUES (?, ?, ?, ?, 'income', ?, ?, 0, 0, ?)
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(doctor_id.to_string())
+    .bind(&order_id)
+    .bind(appointment_id.to_string())
+    .bind(&order_id)
+    .bind(Decimal::from_str(amount).unwrap())
+    .bind(Decimal::from_str(amount).unwrap())
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+async fn create_verified_payout_account(
+    app: &mut TestApp,
+    doctor_token: &str,
+    admin_token: &str,
+) -> Uuid {
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/payment/payout-accounts",
+            json!({
+                "bank_name": "中国工商银行",
+                "account_holder": "张医生",
+                "account_number": "6222021234567890123"
+            }),
+            doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let account_id = Uuid::parse_str(body["data"]["id"].as_str().unwrap()).unwrap();
+
+    let (status, _) = app
+        .put_with_auth(
+            &format!("/api/v1/payment/admin/payout-accounts/{}/verify", account_id),
+            json!({ "approved": true }),
+            admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    account_id
+}
+
+#[tokio::test]
+async fn test_payout_account_encrypted_storage() {
+    let mut app = TestApp::new().await;
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    create_test_doctor(&app.pool, doctor_user_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/payment/payout-accounts",
+            json!({
+                "bank_name": "中国工商银行",
+                "account_holder": "张医生",
+                "account_number": "6222 0212 3456 7890 123"
+            }),
+            &doctor_token,
+        )
+        .await;
+
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(
+        body["data"]["masked_account_number"].as_str().unwrap(),
+        "**** **** **** 0123"
+    );
+    assert_eq!(
+        body["data"]["verification_status"].as_str().unwrap(),
+        "pending"
+    );
+    assert!(body["data"]["account_number_encrypted"].is_null());
+
+    let account_id = Uuid::parse_str(body["data"]["id"].as_str().unwrap()).unwrap();
+
+    // The stored value must not contain the plain account number
+    let stored: String = sqlx::query_scalar(
+        "SELECT account_number_encrypted FROM doctor_payout_accounts WHERE id = ?",
+    )
+    .bind(account_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert!(!stored.contains("6222021234567890123"));
+
+    let revealed = WithdrawalService::reveal_account_number(&app.pool, account_id)
+        .await
+        .unwrap();
+    assert_eq!(revealed, "6222021234567890123");
+}
+
+#[tokio::test]
+async fn test_withdrawal_freeze_and_unfreeze() {
+    let mut app = TestApp::new().await;
+    let (_admin_user_id, admin_account, admin_password) =
+        create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    seed_doctor_earnings(&app.pool, doctor_id, "500.00").await;
+    let account_id = create_verified_payout_account(&mut app, &doctor_token, &admin_token).await;
+
+    // Request freezes the amount
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/payment/withdrawals",
+            json!({ "payout_account_id": account_id, "amount": 200 }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["status"].as_str().unwrap(), "pending");
+    let first_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (_, body) = app
+        .get_with_auth("/api/v1/payment/earnings", &doctor_token)
+        .await;
+    assert_eq!(body["data"]["frozen_amount"].as_f64().unwrap(), 200.0);
+    assert_eq!(body["data"]["available_amount"].as_f64().unwrap(), 300.0);
+
+    // Rejection releases the frozen amount
+    let (status, body) = app
+        .put_with_auth(
+            &format!("/api/v1/payment/admin/withdrawals/{}/process", first_id),
+            json!({ "approved": false, "reject_reason": "账户信息有误" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"].as_str().unwrap(), "rejected");
+
+    let (_, body) = app
+        .get_with_auth("/api/v1/payment/earnings", &doctor_token)
+        .await;
+    assert_eq!(body["data"]["frozen_amount"].as_f64().unwrap(), 0.0);
+    assert_eq!(body["data"]["available_amount"].as_f64().unwrap(), 500.0);
+
+    // Payout moves the amount from frozen to withdrawn
+    let (_, body) = app
+        .post_with_auth(
+            "/api/v1/payment/withdrawals",
+            json!({ "payout_account_id": account_id, "amount": 300 }),
+            &doctor_token,
+        )
+        .await;
+    let second_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = app
+        .put_with_auth(
+            &format!("/api/v1/payment/admin/withdrawals/{}/process", second_id),
+            json!({ "approved": true, "transfer_reference": "BANK20240122001" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"].as_str().unwrap(), "paid");
+
+    let (_, body) = app
+        .get_with_auth("/api/v1/payment/earnings", &doctor_token)
+        .await;
+    assert_eq!(body["data"]["frozen_amount"].as_f64().unwrap(), 0.0);
+    assert_eq!(body["data"]["withdrawn_amount"].as_f64().unwrap(), 300.0);
+    assert_eq!(body["data"]["available_amount"].as_f64().unwrap(), 200.0);
+
+    // Processing twice is rejected
+    let (status, _) = app
+        .put_with_auth(
+            &format!("/api/v1/payment/admin/withdrawals/{}/process", second_id),
+            json!({ "approved": false }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Every state change is audited
+    let audit_count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM audit_logs WHERE target_type = 'doctor_withdrawal'",
+    )
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(audit_count, 4);
+}
+
+#[tokio::test]
+async fn test_concurrent_withdrawals_cannot_overdraw() {
+    let mut app = TestApp::new().await;
+    let (_admin_user_id, admin_account, admin_password) =
+        create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    seed_doctor_earnings(&app.pool, doctor_id, "300.00").await;
+    let account_id = create_verified_payout_account(&mut app, &doctor_token, &admin_token).await;
+
+    let dto = || CreateWithdrawalDto {
+        payout_account_id: account_id,
+        amount: Decimal::from(200),
+    };
+
+    let (first, second) = tokio::join!(
+        WithdrawalService::create_withdrawal(&app.pool, doctor_id, doctor_user_id, dto()),
+        WithdrawalService::create_withdrawal(&app.pool, doctor_id, doctor_user_id, dto()),
+    );
+
+    assert_eq!(
+        [first.is_ok(), second.is_ok()].iter().filter(|ok| **ok).count(),
+        1
+    );
+
+    let summary = WithdrawalService::get_earnings_summary(&app.pool, doctor_id)
+        .await
+        .unwrap();
+    assert_eq!(summary.frozen_amount, Decimal::from(200));
+    assert_eq!(summary.available_amount, Decimal::from(100));
+}
+
+#[tokio::test]
+async fn test_withdrawal_monthly_cap() {
+    let mut app = TestApp::new().await;
+    let (_admin_user_id, admin_account, admin_password) =
+        create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    seed_doctor_earnings(&app.pool, doctor_id, "1000.00").await;
+    let account_id = create_verified_payout_account(&mut app, &doctor_token, &admin_token).await;
+
+    // Default cap is two withdrawals per month
+    for _ in 0..2 {
+        let (status, _) = app
+            .post_with_auth(
+                "/api/v1/payment/withdrawals",
+                json!({ "payout_account_id": account_id, "amount": 100 }),
+                &doctor_token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/payment/withdrawals",
+            json!({ "payout_account_id": account_id, "amount": 100 }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains("每月最多提现"));
+
+    // Below the minimum amount is rejected as well
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/payment/withdrawals",
+            json!({ "payout_account_id": account_id, "amount": 10 }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_update_payment_config_records_history() {
+    let mut app = TestApp::new().await;
+    let (_admin_user_id, admin_account, admin_password) =
+        create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let config_key = format!("test_notify_{}", Uuid::new_v4().simple());
+
+    for value in ["https://a.example.com/notify", "https://b.example.com/notify"] {
+        let (status, _) = app
+            .put_with_auth(
+                "/api/v1/payment/admin/config/wechat",
+                json!({ "config_key": config_key, "config_value": value, "is_encrypted": false }),
+                &admin_token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let (status, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/payment/admin/config-history?payment_method=wechat&config_key={}",
+                config_key
+            ),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let history = body["data"].as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    // Most recent first
+    assert_eq!(history[0]["action"].as_str().unwrap(), "update");
+    assert_eq!(
+        history[0]["old_value"].as_str().unwrap(),
+        "https://a.example.com/notify"
+    );
+    assert_eq!(
+        history[0]["new_value"].as_str().unwrap(),
+        "https://b.example.com/notify"
+    );
+    assert_eq!(history[1]["action"].as_str().unwrap(), "create");
+
+    // Soft delete keeps the row and records the removed value
+    let (status, _) = app
+        .delete_with_auth(
+            &format!("/api/v1/payment/admin/config/wechat/{}", config_key),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let deleted_at: Option<chrono::DateTime<chrono::Utc>> = sqlx::query_scalar(
+        "SELECT deleted_at FROM payment_configs WHERE payment_method = 'wechat' AND config_key = ?",
+    )
+    .bind(&config_key)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert!(deleted_at.is_some());
+
+    let config = backend::services::payment_service::PaymentService::get_payment_config(
+        &app.pool,
+        PaymentMethod::Wechat,
+    )
+    .await
+    .unwrap();
+    assert!(!config.contains_key(&config_key));
+
+    let (_, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/payment/admin/config-history?config_key={}",
+                config_key
+            ),
+            &admin_token,
+        )
+        .await;
+    let history = body["data"].as_array().unwrap();
+    assert_eq!(history.len(), 3);
+    assert_eq!(history[0]["action"].as_str().unwrap(), "delete");
+    assert_eq!(
+        history[0]["old_value"].as_str().unwrap(),
+        "https://b.example.com/notify"
+    );
+}
+
+#[tokio::test]
+async fn test_payment_config_history_masks_encrypted_values() {
+    let mut app = TestApp::new().await;
+    let (_admin_user_id, admin_account, admin_password) =
+        create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (_patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let config_key = format!("test_secret_{}", Uuid::new_v4().simple());
+
+    for value in ["old-secret-value", "new-secret-value"] {
+        app.put_with_auth(
+            "/api/v1/payment/admin/config/alipay",
+            json!({ "config_key": config_key, "config_value": value, "is_encrypted": true }),
+            &admin_token,
+        )
+        .await;
+    }
+
+    let (status, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/payment/admin/config-history?config_key={}",
+                config_key
+            ),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let history = body["data"].as_array().unwrap();
+    assert_eq!(history.len(), 2);
+    for entry in history {
+        assert!(entry["is_encrypted"].as_bool().unwrap());
+        assert!(!entry.to_string().contains("secret-value"));
+    }
+    assert_eq!(history[0]["old_value"].as_str().unwrap(), "******");
+
+    // The raw value is still preserved in storage
+    let stored_old: String = sqlx::query_scalar(
+        "SELECT old_value FROM payment_config_history WHERE config_key = ? AND action = 'update'",
+    )
+    .bind(&config_key)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(stored_old, "old-secret-value");
+
+    // Non-admins cannot read the history
+    let (status, _) = app
+        .get_with_auth("/api/v1/payment/admin/config-history", &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+async fn seed_refund(
+    pool: &sqlx::MySqlPool,
+    user_id: Uuid,
+    payment_method: &str,
+    refund_status: &str,
+) -> Uuid {
+    let order_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO payment_orders (
+            id, order_no, user_id, order_type, amount, currency,
+            status, payment_method, payment_time, expire_time, created_at, updated_at
+        ) VALUES (?, ?, ?, 'consultation', 30.00, 'CNY', 'paid', ?, NOW(), DATE_ADD(NOW(), INTERVAL 2 HOUR), NOW(), NOW())
+        "#,
+    )
+    .bind(order_id.to_string())
+    .bind(format!("ORD{}", order_id.simple()))
+    .bind(user_id.to_string())
+    .bind(payment_method)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let transaction_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO payment_transactions (
+            id, transaction_no, order_id, payment_method,
+            transaction_type, amount, status, initiated_at, completed_at
+        ) VALUES (?, ?, ?, ?, 'payment', 30.00, 'success', NOW(), NOW())
+        "#,
+    )
+    .bind(transaction_id.to_string())
+    .bind(format!("TXN{}", transaction_id.simple()))
+    .bind(order_id.to_string())
+    .bind(payment_method)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let refund_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO refund_records (
+            id, refund_no, order_id, transaction_id, user_id,
+            refund_amount, refund_reason, status, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 30.00, '服务未提供', ?, NOW(), NOW())
+        "#,
+    )
+    .bind(refund_id.to_string())
+    .bind(format!("RFD{}", refund_id.simple()))
+    .bind(order_id.to_string())
+    .bind(transaction_id.to_string())
+    .bind(user_id.to_string())
+    .bind(refund_status)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    refund_id
+}
+
+#[tokio::test]
+async fn test_retry_failed_refund() {
+    let mut app = TestApp::new().await;
+    let (_admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    let refund_id = seed_refund(&app.pool, patient_id, "balance", "failed").await;
+    let retry_path = format!("/api/v1/payment/admin/refunds/{}/retry", refund_id);
+
+    // Only admins may retry
+    let (status, _) = app
+        .put_with_auth(&retry_path, json!({}), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // The patient has no balance account yet, so crediting fails and the refund stays failed
+    let (status, _) = app
+        .put_with_auth(&retry_path, json!({}), &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let refund_status: String =
+        sqlx::query_scalar("SELECT status FROM refund_records WHERE id = ?")
+            .bind(refund_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(refund_status, "failed");
+
+    // Once the balance account exists the retry succeeds
+    backend::services::payment_service::PaymentService::create_user_balance(&app.pool, patient_id)
+        .await
+        .unwrap();
+
+    let (status, body) = app
+        .put_with_auth(&retry_path, json!({}), &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "success");
+    assert!(!body["data"]["completed_at"].is_null());
+
+    let balance: Decimal =
+        sqlx::query_scalar("SELECT balance FROM user_balances WHERE user_id = ?")
+            .bind(patient_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(balance, Decimal::from_str("30.00").unwrap());
+
+    let order_status: String = sqlx::query_scalar(
+        "SELECT o.status FROM payment_orders o JOIN refund_records r ON r.order_id = o.id WHERE r.id = ?",
+    )
+    .bind(refund_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(order_status, "refunded");
+
+    // Both attempts are audited
+    let results: Vec<serde_json::Value> = sqlx::query_scalar(
+        "SELECT details FROM audit_logs WHERE action = 'refund.retry' AND target_id = ? ORDER BY created_at",
+    )
+    .bind(refund_id.to_string())
+    .fetch_all(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(results.len(), 2);
+    assert!(results.iter().any(|d| d["result"] == "failed"));
+    assert!(results.iter().any(|d| d["result"] == "success"));
+}
+
+#[tokio::test]
+async fn test_retry_refund_rejects_non_failed_refunds() {
+    let mut app = TestApp::new().await;
+    let (_admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+
+    for refund_status in ["success", "pending"] {
+        let refund_id = seed_refund(&app.pool, patient_id, "alipay", refund_status).await;
+
+        let (status, _) = app
+            .put_with_auth(
+                &format!("/api/v1/payment/admin/refunds/{}/retry", refund_id),
+                json!({}),
+                &admin_token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+
+        let current: String = sqlx::query_scalar("SELECT status FROM refund_records WHERE id = ?")
+            .bind(refund_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+        assert_eq!(current, refund_status);
+    }
+}
+
+#[tokio::test]
+async fn test_get_order_by_external_transaction() {
+    let mut app = TestApp::new().await;
+    let (_admin_user_id, admin_account, admin_password) =
+        create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    // Two paid orders; only the second carries the gateway id we look up
+    let mut order_ids = Vec::new();
+    for external_id in ["4200001234202401010000000001", "4200001234202401010000000002"] {
+        let order_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO payment_orders (
+                id, order_no, user_id, order_type, amount, currency,
+                status, payment_method, payment_time, expire_time, created_at, updated_at
+            ) VALUES (?, ?, ?, 'consultation', 30.00, 'CNY', 'paid', 'wechat', NOW(), DATE_ADD(NOW(), INTERVAL 2 HOUR), NOW(), NOW())
+            "#,
+        )
+        .bind(order_id.to_string())
+        .bind(format!("ORD{}", order_id.simple()))
+        .bind(patient_user_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+        let transaction_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO payment_transactions (
+                id, transaction_no, order_id, payment_method, transaction_type,
+                amount, status, external_transaction_id, initiated_at, completed_at
+            ) VALUES (?, ?, ?, 'wechat', 'payment', 30.00, 'success', ?, NOW(), NOW())
+            "#,
+        )
+        .bind(transaction_id.to_string())
+        .bind(format!("TXN{}", transaction_id.simple()))
+        .bind(order_id.to_string())
+        .bind(external_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+        order_ids.push(order_id);
+    }
+
+    let (status, body) = app
+        .get_with_auth(
+            "/api/v1/payment/admin/orders/by-external/4200001234202401010000000002",
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["id"], order_ids[1].to_string());
+    assert_eq!(body["data"]["user_id"], patient_user_id.to_string());
+    let transactions = body["data"]["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(
+        transactions[0]["external_transaction_id"],
+        "4200001234202401010000000002"
+    );
+
+    // Unknown gateway ids are a clean 404
+    let (status, body) = app
+        .get_with_auth(
+            "/api/v1/payment/admin/orders/by-external/does-not-exist",
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    assert_eq!(body["success"], false);
+
+    // Admin only
+    let (status, _) = app
+        .get_with_auth(
+            "/api/v1/payment/admin/orders/by-external/4200001234202401010000000002",
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_admin_adjust_order_status() {
+    let mut app = TestApp::new().await;
+    let (admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+
+    let appointment_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot,
+                                  visit_type, symptoms, has_visited_before, status, created_at, updated_at)
+        VALUES (?, ?, ?, DATE_ADD(NOW(), INTERVAL 1 DAY), '09:00-10:00', 'online_video', '测试症状', false, 'pending', NOW(), NOW())
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    // An order stuck in pending after the gateway charged the patient
+    let order_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO payment_orders (
+            id, order_no, user_id, appointment_id, order_type, amount, currency,
+            status, expire_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, 'appointment', ?, 'CNY', 'pending', DATE_ADD(NOW(), INTERVAL 2 HOUR), NOW(), NOW())
+        "#,
+    )
+    .bind(order_id.to_string())
+    .bind(format!("ORD{}", Uuid::new_v4().simple()))
+    .bind(patient_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(Decimal::from_str("50.00").unwrap())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let path = format!("/api/v1/payment/admin/orders/{}/status", order_id);
+
+    // Only admins may adjust orders
+    let (status, _) = app
+        .put_with_auth(
+            &path,
+            json!({ "status": "paid", "reason": "网关回调丢失" }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // A justification is required
+    let (status, _) = app
+        .put_with_auth(
+            &path,
+            json!({ "status": "paid", "reason": "" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app
+        .put_with_auth(
+            &path,
+            json!({ "status": "paid", "reason": "网关回调丢失，已核对支付宝流水" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "paid");
+    assert!(!body["data"]["payment_time"].is_null());
+
+    // Same side effect as a real payment: the appointment is confirmed
+    let appointment_status: String =
+        sqlx::query_scalar("SELECT status FROM appointments WHERE id = ?")
+            .bind(appointment_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(appointment_status, "confirmed");
+
+    let (actor_id, details): (String, serde_json::Value) = sqlx::query_as(
+        "SELECT actor_id, details FROM audit_logs WHERE action = 'order.adjust_status' AND target_id = ?",
+    )
+    .bind(order_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(actor_id, admin_id.to_string());
+    assert_eq!(details["from"], "pending");
+    assert_eq!(details["to"], "paid");
+    assert_eq!(details["reason"], "网关回调丢失，已核对支付宝流水");
+
+    // A paid order cannot go back to pending or be cancelled
+    for target in ["pending", "cancelled"] {
+        let (status, _) = app
+            .put_with_auth(
+                &path,
+                json!({ "status": target, "reason": "误操作" }),
+                &admin_token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let order_status: String = sqlx::query_scalar("SELECT status FROM payment_orders WHERE id = ?")
+        .bind(order_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(order_status, "paid");
+}
+
+async fn seed_order_with_transaction(
+    pool: &sqlx::MySqlPool,
+    user_id: Uuid,
+    order_status: &str,
+    transaction_status: &str,
+) -> (Uuid, String) {
+    let order_id = Uuid::new_v4();
+    let order_no = format!("ORD{}", Uuid::new_v4().simple());
+    let amount = Decimal::from_str("30.00").unwrap();
+
+    sqlx::query(
+        r#"
+        INSERT INTO payment_orders (
+            id, order_no, user_id, order_type, amount, currency,
+            status, expire_time, created_at, updated_at
+        ) VALUES (?, ?, ?, 'consultation', ?, 'CNY', ?, DATE_ADD(NOW(), INTERVAL 2 HOUR), NOW(), NOW())
+        "#,
+    )
+    .bind(order_id.to_string())
+    .bind(&order_no)
+    .bind(user_id.to_string())
+    .bind(amount)
+    .bind(order_status)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+        INSERT INTO payment_transactions (
+            id, transaction_no, order_id, payment_method,
+            transaction_type, amount, status, initiated_at
+        ) VALUES (?, ?, ?, 'alipay', 'payment', ?, ?, NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(format!("TXN{}", Uuid::new_v4().simple()))
+    .bind(order_id.to_string())
+    .bind(amount)
+    .bind(transaction_status)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    (order_id, order_no)
+}
+
+#[tokio::test]
+async fn test_concurrent_refund_and_cancel_keep_order_consistent() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+
+    // Two full refunds and a cancel race on the same paid order
+    let (order_id, _) = seed_order_with_transaction(&app.pool, patient_id, "paid", "success").await;
+    let refund = || CreateRefundDto {
+        order_id,
+        refund_amount: Decimal::from_str("30.00").unwrap(),
+        refund_reason: "服务未提供".to_string(),
+    };
+
+    let (first, cancelled, second) = tokio::join!(
+        PaymentService::create_refund(&app.pool, refund(), patient_id, false),
+        PaymentService::cancel_order(&app.pool, order_id),
+        PaymentService::create_refund(&app.pool, refund(), patient_id, false),
+    );
+
+    assert!(cancelled.is_err());
+    assert_eq!(
+        [first.is_ok(), second.is_ok()]
+            .iter()
+            .filter(|ok| **ok)
+            .count(),
+        1
+    );
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+
+    let refund_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM refund_records WHERE order_id = ?")
+            .bind(order_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(refund_count, 1);
+
+    // A cancel racing the payment callback: whichever locks the order first wins
+    let (order_id, order_no) =
+        seed_order_with_transaction(&app.pool, patient_id, "pending", "pending").await;
+    let callback = PaymentCallbackData {
+        order_no,
+        external_transaction_id: format!("ALI{}", Uuid::new_v4().simple()),
+        amount: Decimal::from_str("30.00").unwrap(),
+        status: "success".to_string(),
+        payment_time: chrono::Utc::now(),
+        raw_data: json!({}),
+    };
+
+    let (cancelled, paid) = tokio::join!(
+        PaymentService::cancel_order(&app.pool, order_id),
+        PaymentService::handle_payment_callback(&app.pool, PaymentMethod::Alipay, callback),
+    );
+    assert!(paid.is_ok());
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    if cancelled.is_ok() {
+        assert_eq!(order.status, OrderStatus::Cancelled);
+    } else {
+        assert_eq!(order.status, OrderStatus::Paid);
+    }
+}
+
+#[tokio::test]
+async fn test_quote_order_matches_created_order() {
+    let mut app = TestApp::new().await;
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    let order_dto = || CreateOrderDto {
+        user_id: patient_user_id,
+        appointment_id: None,
+        order_type: OrderType::Consultation,
+        amount: Decimal::from_str("88.50").unwrap(),
+        description: Some("图文咨询服务".to_string()),
+        metadata: None,
+        coupon_code: None,
+        idempotency_key: None,
+    };
+
+    let (status, quote) = app
+        .post_with_auth("/api/v1/payment/orders/quote", order_dto(), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(quote["data"]["currency"], "CNY");
+
+    // Quoting does not create an order
+    let order_count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM payment_orders WHERE user_id = ?")
+            .bind(patient_user_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(order_count, 0);
+
+    let (status, order) = app
+        .post_with_auth("/api/v1/payment/orders", order_dto(), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(quote["data"]["amount"].as_f64().unwrap(), 88.5);
+    assert_eq!(
+        quote["data"]["amount"].as_f64(),
+        order["data"]["amount"].as_f64()
+    );
+
+    // An amount the order would reject is rejected by the quote with the same error
+    let invalid = CreateOrderDto {
+        amount: Decimal::from_str("0.50").unwrap(),
+        ..order_dto()
+    };
+    let (quote_status, quote_body) = app
+        .post_with_auth("/api/v1/payment/orders/quote", &invalid, &patient_token)
+        .await;
+    let (create_status, create_body) = app
+        .post_with_auth("/api/v1/payment/orders", &invalid, &patient_token)
+        .await;
+    assert_eq!(quote_status, StatusCode::BAD_REQUEST);
+    assert_eq!(create_status, StatusCode::BAD_REQUEST);
+    assert_eq!(quote_body["message"], create_body["message"]);
+}
+
+#[tokio::test]
+async fn test_refund_window_admin_override() {
+    let mut app = TestApp::new().await;
+    let (patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (_admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+
+    // Paid 40 days ago, past the default 30-day refund window
+    let (order_id, _) = seed_order_with_transaction(&app.pool, patient_id, "paid", "success").await;
+    sqlx::query(
+        "UPDATE payment_orders SET payment_time = DATE_SUB(NOW(), INTERVAL 40 DAY) WHERE id = ?",
+    )
+    .bind(order_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let refund_dto = || CreateRefundDto {
+        order_id,
+        refund_amount: Decimal::from_str("30.00").unwrap(),
+        refund_reason: "服务未提供".to_string(),
+    };
+
+    let (status, body) = app
+        .post_with_auth("/api/v1/payment/refunds", refund_dto(), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains("退款期限"));
+
+    let (status, body) = app
+        .post_with_auth("/api/v1/payment/refunds", refund_dto(), &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["status"], "pending");
+}
+
+#[tokio::test]
+async fn test_user_payment_summary() {
+    let mut app = TestApp::new().await;
+    let (patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    // No orders and no balance record yet
+    let (status, body) = app
+        .get_with_auth("/api/v1/payment/summary", &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["total_spent"].as_f64().unwrap(), 0.0);
+    assert_eq!(body["data"]["balance"].as_f64().unwrap(), 0.0);
+
+    // One paid order, one refunded order and one unpaid order, 30.00 each
+    seed_order_with_transaction(&app.pool, patient_id, "paid", "success").await;
+    let (refunded_order_id, _) =
+        seed_order_with_transaction(&app.pool, patient_id, "refunded", "success").await;
+    seed_order_with_transaction(&app.pool, patient_id, "pending", "pending").await;
+
+    let transaction_id: String =
+        sqlx::query_scalar("SELECT id FROM payment_transactions WHERE order_id = ?")
+            .bind(refunded_order_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO refund_records (
+            id, refund_no, order_id, transaction_id, user_id,
+            refund_amount, refund_reason, status, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 30.00, '服务未提供', 'success', NOW(), NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(format!("RFD{}", Uuid::new_v4().simple()))
+    .bind(refunded_order_id.to_string())
+    .bind(&transaction_id)
+    .bind(patient_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+        INSERT INTO user_balances (
+            id, user_id, balance, frozen_balance,
+            total_income, total_expense, created_at, updated_at
+        ) VALUES (?, ?, 12.50, 0, 12.50, 0, NOW(), NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(patient_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let (status, body) = app
+        .get_with_auth("/api/v1/payment/summary", &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let summary = &body["data"];
+    assert_eq!(summary["user_id"], patient_id.to_string());
+    assert_eq!(summary["total_spent"].as_f64().unwrap(), 60.0);
+    assert_eq!(summary["total_refunded"].as_f64().unwrap(), 30.0);
+    assert_eq!(summary["balance"].as_f64().unwrap(), 12.5);
+    assert_eq!(summary["statistics"]["total_orders"], 3);
+    assert_eq!(summary["statistics"]["paid_orders"], 1);
+}
+
+#[tokio::test]
+async fn test_concurrent_coupon_redemption_is_not_over_used() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+
+    let code = format!("ONCE{}", Uuid::new_v4().simple());
+    sqlx::query(
+        "INSERT INTO coupons (id, code, discount_amount, usage_limit) VALUES (?, ?, 10.00, 1)",
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(&code)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let order_dto = || CreateOrderDto {
+        user_id: patient_id,
+        appointment_id: None,
+        order_type: OrderType::Consultation,
+        amount: Decimal::from_str("30.00").unwrap(),
+        description: None,
+        metadata: None,
+        coupon_code: Some(code.clone()),
+        idempotency_key: None,
+    };
+
+    // Two orders race to redeem the same one-use coupon
+    let (first, second) = tokio::join!(
+        PaymentService::create_order(&app.pool, order_dto()),
+        PaymentService::create_order(&app.pool, order_dto()),
+    );
+
+    let discounted: Vec<_> = [first, second].into_iter().filter_map(|r| r.ok()).collect();
+    assert_eq!(discounted.len(), 1);
+    assert_eq!(discounted[0].amount, Decimal::from_str("20.00").unwrap());
+    assert_eq!(
+        discounted[0].discount_amount,
+        Decimal::from_str("10.00").unwrap()
+    );
+
+    let used: i32 = sqlx::query_scalar("SELECT used FROM coupons WHERE code = ?")
+        .bind(&code)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(used, 1);
+
+    // The exhausted coupon is rejected rather than silently ignored
+    let result = PaymentService::create_order(&app.pool, order_dto()).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn test_verify_stored_payment_callback() {
+    let mut app = TestApp::new().await;
+    let (_admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    configure_alipay(&app.pool).await;
+
+    // The gateway charged the patient after the order was cancelled, so the
+    // callback was stored but the order kept its status
+    let (order_id, order_no) =
+        seed_order_with_transaction(&app.pool, patient_id, "cancelled", "pending").await;
+    let trade_no = format!("ALI{}", Uuid::new_v4().simple());
+    let mut params: BTreeMap<String, String> = [
+        ("out_trade_no", order_no.as_str()),
+        ("trade_no", trade_no.as_str()),
+        ("total_amount", "30.00"),
+        ("trade_status", "TRADE_SUCCESS"),
+        ("sign_type", "RSA2"),
+    ]
+    .into_iter()
+    .map(|(key, value)| (key.to_string(), value.to_string()))
+    .collect();
+    let sign = crypto::rsa_sign_sha256(
+        TEST_PRIVATE_KEY,
+        &AlipayService::sign_content(&params, &["sign", "sign_type"]),
+    )
+    .unwrap();
+    params.insert("sign".to_string(), sign);
+    let raw_data = json!(params);
+    let callback = PaymentService::parse_callback(&PaymentMethod::Alipay, &raw_data).unwrap();
+    PaymentService::handle_payment_callback(&app.pool, PaymentMethod::Alipay, callback)
+        .await
+        .unwrap();
+
+    let path = format!("/api/v1/payment/admin/orders/{}/verify-callback", order_id);
+
+    // Dry run reports a valid callback without touching the order
+    let (status, body) = app.post_with_auth(&path, json!({}), &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["valid"], true);
+    assert_eq!(body["data"]["callback_status"], "success");
+    assert_eq!(body["data"]["order_status"], "cancelled");
+    assert_eq!(body["data"]["would_mark_paid"], true);
+    assert_eq!(body["data"]["applied"], false);
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Cancelled);
+
+    // Editing the stored amount breaks the signature and the amount check, so it is never applied
+    let mut tampered = raw_data.clone();
+    tampered["total_amount"] = json!("1.00");
+    sqlx::query("UPDATE payment_transactions SET callback_data = ? WHERE order_id = ?")
+        .bind(&tampered)
+        .bind(order_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    for apply in [false, true] {
+        let (status, body) = app
+            .post_with_auth(&path, json!({ "apply": apply }), &admin_token)
+            .await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["valid"], false);
+        assert_eq!(body["data"]["issues"].as_array().unwrap().len(), 2);
+        assert_eq!(body["data"]["issues"][0], "回调签名校验失败");
+        assert_eq!(body["data"]["would_mark_paid"], false);
+        assert_eq!(body["data"]["applied"], false);
+    }
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Cancelled);
+
+    // Re-applying the genuine callback marks the order paid
+    sqlx::query("UPDATE payment_transactions SET callback_data = ? WHERE order_id = ?")
+        .bind(&raw_data)
+        .bind(order_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let (status, body) = app
+        .post_with_auth(&path, json!({ "apply": true }), &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["applied"], true);
+    assert_eq!(body["data"]["order_status"], "paid");
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+
+    // Orders without a stored callback are a clean 404
+    let (pending_order_id, _) =
+        seed_order_with_transaction(&app.pool, patient_id, "pending", "pending").await;
+    let (status, _) = app
+        .post_with_auth(
+            &format!(
+                "/api/v1/payment/admin/orders/{}/verify-callback",
+                pending_order_id
+            ),
+            json!({}),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Admin only
+    let (status, _) = app.post_with_auth(&path, json!({}), &patient_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_expire_stale_orders_releases_appointment() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+
+    let appointment_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot,
+                                  visit_type, symptoms, has_visited_before, status, created_at, updated_at)
+        VALUES (?, ?, ?, DATE_ADD(NOW(), INTERVAL 1 DAY), '09:00-10:00', 'online_video', '测试症状', false, 'confirmed', NOW(), NOW())
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    // An order that was never paid within its payment window
+    let (overdue_id, overdue_no) =
+        seed_order_with_transaction(&app.pool, patient_id, "pending", "pending").await;
+    sqlx::query(
+        "UPDATE payment_orders SET appointment_id = ?, expire_time = DATE_SUB(NOW(), INTERVAL 1 MINUTE) WHERE id = ?",
+    )
+    .bind(appointment_id.to_string())
+    .bind(overdue_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    let (fresh_id, _) =
+        seed_order_with_transaction(&app.pool, patient_id, "pending", "pending").await;
+
+    let expired = PaymentService::expire_stale_orders(&app.pool)
+        .await
+        .unwrap();
+    assert!(expired >= 1);
+
+    let order = PaymentService::get_order(&app.pool, overdue_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Expired);
+    let fresh = PaymentService::get_order(&app.pool, fresh_id)
+        .await
+        .unwrap();
+    assert_eq!(fresh.status, OrderStatus::Pending);
+
+    let (transaction_status, error_code): (String, Option<String>) =
+        sqlx::query_as("SELECT status, error_code FROM payment_transactions WHERE order_id = ?")
+            .bind(overdue_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(transaction_status, "failed");
+    assert_eq!(error_code.as_deref(), Some("ORDER_EXPIRED"));
+
+    let appointment_status: String =
+        sqlx::query_scalar("SELECT status FROM appointments WHERE id = ?")
+            .bind(appointment_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(appointment_status, "pending");
+
+    // A charge reported after expiry is recorded but leaves the order expired
+    let callback = PaymentCallbackData {
+        order_no: overdue_no,
+        external_transaction_id: format!("ALI{}", Uuid::new_v4().simple()),
+        amount: Decimal::from_str("30.00").unwrap(),
+        status: "success".to_string(),
+        payment_time: chrono::Utc::now(),
+        raw_data: json!({}),
+    };
+    PaymentService::handle_payment_callback(&app.pool, PaymentMethod::Alipay, callback)
+        .await
+        .unwrap();
+    let order = PaymentService::get_order(&app.pool, overdue_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Expired);
+}
+
+#[tokio::test]
+async fn test_balance_recharge_credits_after_callback() {
+    let mut app = TestApp::new().await;
+    let (patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    // Amounts outside the recharge price configs and balance payments are refused
+    for (amount, method) in [(5, "alipay"), (10000, "alipay"), (100, "balance")] {
+        let (status, _) = app
+            .post_with_auth(
+                "/api/v1/payment/balance/recharge",
+                json!({ "amount": amount, "payment_method": method }),
+                &patient_token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    configure_alipay(&app.pool).await;
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/payment/balance/recharge",
+            json!({ "amount": 100, "payment_method": "alipay" }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"]["payment_url"].is_string());
+    let order_id = Uuid::parse_str(body["data"]["order_id"].as_str().unwrap()).unwrap();
+    let order_no = body["data"]["order_no"].as_str().unwrap().to_string();
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert!(matches!(order.order_type, OrderType::Recharge));
+    assert_eq!(order.user_id, patient_id);
+
+    // The gateway confirms the payment; a repeated callback must not credit twice
+    let raw_data = json!({
+        "out_trade_no": order_no,
+        "trade_no": format!("ALI{}", Uuid::new_v4().simple()),
+        "total_amount": "100.00",
+        "trade_status": "TRADE_SUCCESS",
+    });
+    for _ in 0..2 {
+        let callback = PaymentService::parse_callback(&PaymentMethod::Alipay, &raw_data).unwrap();
+        PaymentService::handle_payment_callback(&app.pool, PaymentMethod::Alipay, callback)
+            .await
+            .unwrap();
+    }
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+
+    let balance = PaymentService::get_user_balance(&app.pool, patient_id)
+        .await
+        .unwrap();
+    assert_eq!(balance.balance, Decimal::from(100));
+    assert_eq!(balance.total_income, Decimal::from(100));
+
+    let transactions = PaymentService::get_balance_transactions(&app.pool, patient_id, 1, 20)
+        .await
+        .unwrap();
+    assert_eq!(transactions.len(), 1);
+    assert!(matches!(
+        transactions[0].transaction_type,
+        BalanceTransactionType::Income
+    ));
+    assert_eq!(transactions[0].amount, Decimal::from(100));
+    assert_eq!(transactions[0].balance_before, Decimal::ZERO);
+    assert_eq!(transactions[0].balance_after, Decimal::from(100));
+    assert_eq!(transactions[0].related_type.as_deref(), Some("recharge"));
+    assert_eq!(transactions[0].related_id, Some(order_id));
+
+    // The money now lives in the balance, so the order cannot be refunded
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/payment/refunds",
+            json!({
+                "order_id": order_id,
+                "refund_amount": 100,
+                "refund_reason": "不想充了",
+            }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_balance_withdrawal_review() {
+    let mut app = TestApp::new().await;
+    let (_admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (doctor_user_id, doctor_account, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let doctor_token = get_auth_token(&mut app, &doctor_account, &doctor_password).await;
+
+    seed_user_balance(&app.pool, doctor_user_id, 200).await;
+
+    let request = |amount: i64| {
+        json!({
+            "amount": amount,
+            "payout_method": "alipay",
+            "payout_account": "doctor@example.com",
+        })
+    };
+
+    // Requesting a withdrawal moves the amount into the frozen balance
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/payment/withdrawals/balance",
+            request(150),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["status"], "pending");
+    let rejected_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let balance = PaymentService::get_user_balance(&app.pool, doctor_user_id)
+        .await
+        .unwrap();
+    assert_eq!(balance.balance, Decimal::from(50));
+    assert_eq!(balance.frozen_balance, Decimal::from(150));
+
+    // Frozen money is no longer available
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/payment/withdrawals/balance",
+            request(100),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Only admins review, and rejecting releases the frozen amount
+    let review_path = format!("/api/v1/payment/withdrawals/balance/{}/review", rejected_id);
+    let (status, _) = app
+        .put_with_auth(&review_path, json!({ "approved": true }), &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = app
+        .put_with_auth(
+            &review_path,
+            json!({ "approved": false, "review_notes": "账户信息有误" }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "rejected");
+
+    let balance = PaymentService::get_user_balance(&app.pool, doctor_user_id)
+        .await
+        .unwrap();
+    assert_eq!(balance.balance, Decimal::from(200));
+    assert_eq!(balance.frozen_balance, Decimal::ZERO);
+
+    // A request can only be reviewed once
+    let (status, _) = app
+        .put_with_auth(&review_path, json!({ "approved": true }), &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Approving pays out of the frozen balance
+    let (_, body) = app
+        .post_with_auth(
+            "/api/v1/payment/withdrawals/balance",
+            request(120),
+            &doctor_token,
+        )
+        .await;
+    let approved_id = body["data"]["id"].as_str().unwrap().to_string();
+    let (status, body) = app
+        .put_with_auth(
+            &format!("/api/v1/payment/withdrawals/balance/{}/review", approved_id),
+            json!({ "approved": true }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "approved");
+
+    let balance = PaymentService::get_user_balance(&app.pool, doctor_user_id)
+        .await
+        .unwrap();
+    assert_eq!(balance.balance, Decimal::from(80));
+    assert_eq!(balance.frozen_balance, Decimal::ZERO);
+    assert_eq!(balance.total_expense, Decimal::from(120));
+
+    let payouts: i64 = sqlx::query_scalar(
+        r#"
+        SELECT COUNT(*) FROM balance_transactions
+        WHERE user_id = ? AND transaction_type = 'expense'
+          AND related_type = 'withdrawal' AND related_id = ?
+        "#,
+    )
+    .bind(doctor_user_id.to_string())
+    .bind(&approved_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(payouts, 1);
+
+    // Users see their own requests; admins can filter by user and status
+    let (_, body) = app
+        .get_with_auth("/api/v1/payment/withdrawals/balance", &doctor_token)
+        .await;
+    assert_eq!(body["data"]["total"], 2);
+
+    let (_, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/payment/withdrawals/balance?user_id={}&status=approved",
+                doctor_user_id
+            ),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(body["data"]["total"], 1);
+    assert_eq!(body["data"]["withdrawals"][0]["id"], approved_id);
+}
+
+#[tokio::test]
+async fn test_concurrent_balance_withdrawals_cannot_over_freeze() {
+    let app = TestApp::new().await;
+    let (user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    seed_user_balance(&app.pool, user_id, 100).await;
+
+    let dto = || CreateBalanceWithdrawalDto {
+        amount: Decimal::from(80),
+        payout_method: PaymentMethod::Wechat,
+        payout_account: "wx_doctor".to_string(),
+    };
+
+    let (first, second) = tokio::join!(
+        PaymentService::create_withdrawal(&app.pool, user_id, dto()),
+        PaymentService::create_withdrawal(&app.pool, user_id, dto()),
+    );
+
+    assert_eq!(
+        [first.is_ok(), second.is_ok()]
+            .iter()
+            .filter(|ok| **ok)
+            .count(),
+        1
+    );
+
+    let balance = PaymentService::get_user_balance(&app.pool, user_id)
+        .await
+        .unwrap();
+    assert_eq!(balance.balance, Decimal::from(20));
+    assert_eq!(balance.frozen_balance, Decimal::from(80));
+}
+
+#[tokio::test]
+async fn test_concurrent_balance_payments_charge_order_once() {
+    let app = TestApp::new().await;
+    let (user_id, _, _) = create_test_user(&app.pool, "patient").await;
+    seed_user_balance(&app.pool, user_id, 100).await;
+
+    let order = PaymentService::create_order(
+        &app.pool,
+        CreateOrderDto {
+            user_id,
+            appointment_id: None,
+            order_type: OrderType::Consultation,
+            amount: Decimal::from(30),
+            description: None,
+            metadata: None,
+            coupon_code: None,
+            idempotency_key: None,
+        },
+    )
+    .await
+    .unwrap();
+    let gateway = MockGateway::new(Vec::new());
+    let dto = || InitiatePaymentDto {
+        order_id: order.id,
+        payment_method: PaymentMethod::Balance,
+        return_url: None,
+        openid: None,
+        wap: false,
+    };
+
+    let (first, second) = tokio::join!(
+        PaymentService::initiate_payment(&app.pool, &gateway, dto()),
+        PaymentService::initiate_payment(&app.pool, &gateway, dto()),
+    );
+
+    assert_eq!(
+        [first.is_ok(), second.is_ok()]
+            .iter()
+            .filter(|ok| **ok)
+            .count(),
+        1
+    );
+
+    let balance = PaymentService::get_user_balance(&app.pool, user_id)
+        .await
+        .unwrap();
+    assert_eq!(balance.balance, Decimal::from(70));
+    assert_eq!(balance.total_expense, Decimal::from(30));
+}
+
+async fn seed_transaction(
+    pool: &sqlx::MySqlPool,
+    order_id: Uuid,
+    payment_method: &str,
+    transaction_type: &str,
+    status: &str,
+    initiated_at: &str,
+) {
+    sqlx::query(
+        r#"
+        INSERT INTO payment_transactions (
+            id, transaction_no, order_id, payment_method,
+            transaction_type, amount, status, initiated_at
+        ) VALUES (?, ?, ?, ?, ?, 30.00, ?, ?)
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(format!("TXN{}", Uuid::new_v4().simple()))
+    .bind(order_id.to_string())
+    .bind(payment_method)
+    .bind(transaction_type)
+    .bind(status)
+    .bind(initiated_at)
+    .execute(pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_admin_list_transactions_filters() {
+    let mut app = TestApp::new().await;
+    let (_admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (patient_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    // The seeded order already has a pending alipay payment initiated now
+    let (order_id, _) = seed_order_with_transaction(&app.pool, patient_id, "paid", "pending").await;
+    for (method, transaction_type, status, initiated_at) in [
+        ("alipay", "payment", "success", "2024-01-10 10:00:00"),
+        ("alipay", "payment", "failed", "2024-01-11 10:00:00"),
+        ("wechat", "payment", "pending", "2024-01-12 10:00:00"),
+        ("alipay", "refund", "success", "2024-01-13 10:00:00"),
+    ] {
+        seed_transaction(
+            &app.pool,
+            order_id,
+            method,
+            transaction_type,
+            status,
+            initiated_at,
+        )
+        .await;
+    }
+
+    let base = format!("/api/v1/payment/transactions?order_id={}", order_id);
+    for (filters, expected) in [
+        ("", 5),
+        ("&payment_method=alipay", 4),
+        ("&payment_method=wechat", 1),
+        ("&transaction_type=refund", 1),
+        ("&status=success", 2),
+        ("&status=pending", 2),
+        (
+            "&payment_method=alipay&transaction_type=payment&status=success",
+            1,
+        ),
+        ("&payment_method=alipay&status=pending", 1),
+        ("&start_date=2024-01-11T00:00:00Z", 4),
+        ("&end_date=2024-01-11T23:59:59Z", 2),
+        (
+            "&start_date=2024-01-11T00:00:00Z&end_date=2024-01-12T23:59:59Z",
+            2,
+        ),
+        (
+            "&payment_method=alipay&start_date=2024-01-11T00:00:00Z&end_date=2024-01-13T23:59:59Z",
+            2,
+        ),
+    ] {
+        let (status, body) = app
+            .get_with_auth(&format!("{}{}", base, filters), &admin_token)
+            .await;
+        assert_eq!(status, StatusCode::OK, "filters: {}", filters);
+        assert_eq!(body["data"]["total"], expected, "filters: {}", filters);
+        assert_eq!(
+            body["data"]["transactions"].as_array().unwrap().len(),
+            expected,
+            "filters: {}",
+            filters
+        );
+    }
+
+    // Newest first
+    let (_, body) = app
+        .get_with_auth(&format!("{}&page=2&page_size=2", base), &admin_token)
+        .await;
+    assert_eq!(body["data"]["page"], 2);
+    let page = body["data"]["transactions"].as_array().unwrap();
+    assert_eq!(page.len(), 2);
+    assert_eq!(page[0]["payment_method"], "wechat");
+    assert_eq!(page[1]["status"], "failed");
+
+    // Filters apply without an order too
+    let (_, body) = app
+        .get_with_auth(
+            "/api/v1/payment/transactions?payment_method=wechat&status=pending",
+            &admin_token,
+        )
+        .await;
+    let transactions = body["data"]["transactions"].as_array().unwrap();
+    assert!(!transactions.is_empty());
+    assert!(transactions
+        .iter()
+        .all(|t| t["payment_method"] == "wechat" && t["status"] == "pending"));
+
+    // Admin only
+    let (status, _) = app.get_with_auth(&base, &patient_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_list_transactions_page_size_capped() {
+    let mut app = TestApp::new().await;
+    let (_admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+
+    let (order_id, _) = seed_order_with_transaction(&app.pool, patient_id, "paid", "success").await;
+    for _ in 0..100 {
+        seed_transaction(
+            &app.pool,
+            order_id,
+            "alipay",
+            "refund",
+            "success",
+            "2024-01-10 10:00:00",
+        )
+        .await;
+    }
+
+    let (status, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/payment/transactions?order_id={}&page_size=500",
+                order_id
+            ),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["total"], 101);
+    assert_eq!(body["data"]["page_size"], 100);
+    assert_eq!(body["data"]["transactions"].as_array().unwrap().len(), 100);
+
+    let (_, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/payment/transactions?order_id={}&page=2&page_size=500",
+                order_id
+            ),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(body["data"]["transactions"].as_array().unwrap().len(), 1);
+}
+#[tokio::test]
+async fn test_create_order_idempotency_key_returns_existing_order() {
+    let mut app = TestApp::new().await;
+    let (patient_user_id, patient_account, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let (other_user_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let key = format!("retry-{}", Uuid::new_v4().simple());
+
+    let order_dto = |user_id: Uuid| CreateOrderDto {
+        user_id,
+        appointment_id: None,
+        order_type: OrderType::Consultation,
+        amount: Decimal::from_str("30.00").unwrap(),
+        description: Some("图文咨询服务".to_string()),
+        metadata: None,
+        coupon_code: None,
+        idempotency_key: Some(key.clone()),
+    };
+
+    let first = PaymentService::create_order(&app.pool, order_dto(patient_user_id))
+        .await
+        .unwrap();
+    let retried = PaymentService::create_order(&app.pool, order_dto(patient_user_id))
+        .await
+        .unwrap();
+    assert_eq!(retried.id, first.id);
+    assert_eq!(retried.order_no, first.order_no);
+
+    // The client retrying over HTTP after a timeout gets the same order back
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/payment/orders",
+            order_dto(patient_user_id),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["id"].as_str().unwrap(), first.id.to_string());
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM payment_orders WHERE user_id = ?")
+        .bind(patient_user_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 1);
+
+    // Keys are scoped to the user
+    let other = PaymentService::create_order(&app.pool, order_dto(other_user_id))
+        .await
+        .unwrap();
+    assert_ne!(other.id, first.id);
+
+    // Concurrent retries still produce a single order
+    let concurrent_key = format!("retry-{}", Uuid::new_v4().simple());
+    let concurrent_dto = || CreateOrderDto {
+        idempotency_key: Some(concurrent_key.clone()),
+        ..order_dto(patient_user_id)
+    };
+    let (a, b) = tokio::join!(
+        PaymentService::create_order(&app.pool, concurrent_dto()),
+        PaymentService::create_order(&app.pool, concurrent_dto())
+    );
+    assert_eq!(a.unwrap().id, b.unwrap().id);
+
+    // Past the 24 hour window the key can be used for a new order
+    sqlx::query(
+        "UPDATE payment_orders SET created_at = DATE_SUB(NOW(), INTERVAL 25 HOUR) WHERE id = ?",
+    )
+    .bind(first.id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    let renewed = PaymentService::create_order(&app.pool, order_dto(patient_user_id))
+        .await
+        .unwrap();
+    assert_ne!(renewed.id, first.id);
+
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM payment_orders WHERE user_id = ?")
+        .bind(patient_user_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(count, 3);
+}
+
+#[tokio::test]
+async fn test_expire_stale_orders_skips_settled_orders() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+
+    // Paid before the deadline; the sweep must not touch it afterwards
+    let (paid_id, _) = seed_order_with_transaction(&app.pool, patient_id, "paid", "success").await;
+    let (overdue_id, _) =
+        seed_order_with_transaction(&app.pool, patient_id, "pending", "pending").await;
+    sqlx::query(
+        "UPDATE payment_orders SET expire_time = DATE_SUB(NOW(), INTERVAL 1 HOUR) WHERE id IN (?, ?)",
+    )
+    .bind(paid_id.to_string())
+    .bind(overdue_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    PaymentService::expire_stale_orders(&app.pool)
+        .await
+        .unwrap();
+
+    let paid = PaymentService::get_order(&app.pool, paid_id).await.unwrap();
+    assert_eq!(paid.status, OrderStatus::Paid);
+    let transaction_status: String =
+        sqlx::query_scalar("SELECT status FROM payment_transactions WHERE order_id = ?")
+            .bind(paid_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(transaction_status, "success");
+
+    let overdue = PaymentService::get_order(&app.pool, overdue_id)
+        .await
+        .unwrap();
+    assert_eq!(overdue.status, OrderStatus::Expired);
+}
+async fn callback_outcomes(pool: &sqlx::MySqlPool, order_no: &str) -> Vec<String> {
+    let mut outcomes: Vec<String> =
+        sqlx::query_scalar("SELECT outcome FROM payment_callback_logs WHERE order_no = ?")
+            .bind(order_no)
+            .fetch_all(pool)
+            .await
+            .unwrap();
+    outcomes.sort();
+    outcomes
+}
+
+#[tokio::test]
+async fn test_payment_callback_is_idempotent_and_logged() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (order_id, order_no) =
+        seed_order_with_transaction(&app.pool, patient_id, "pending", "pending").await;
+    let callback = || PaymentCallbackData {
+        order_no: order_no.clone(),
+        external_transaction_id: format!("ALI{}", Uuid::new_v4().simple()),
+        amount: Decimal::from_str("30.00").unwrap(),
+        status: "success".to_string(),
+        payment_time: chrono::Utc::now(),
+        raw_data: json!({ "out_trade_no": order_no }),
+    };
+
+    // Concurrent deliveries of the same notification settle the order once
+    let (first, second) = tokio::join!(
+        PaymentService::handle_payment_callback(&app.pool, PaymentMethod::Alipay, callback()),
+        PaymentService::handle_payment_callback(&app.pool, PaymentMethod::Alipay, callback()),
+    );
+    assert!(first.is_ok());
+    assert!(second.is_ok());
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+    let success_count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM payment_transactions WHERE order_id = ? AND status = 'success'",
+    )
+    .bind(order_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(success_count, 1);
+    assert_eq!(
+        callback_outcomes(&app.pool, &order_no).await,
+        vec!["duplicate", "processed"]
+    );
+
+    // A retry arriving after a refund must not flip the order back to paid
+    sqlx::query("UPDATE payment_orders SET status = 'refunded' WHERE id = ?")
+        .bind(order_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    PaymentService::handle_payment_callback(&app.pool, PaymentMethod::Alipay, callback())
+        .await
+        .unwrap();
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Refunded);
+    assert_eq!(
+        callback_outcomes(&app.pool, &order_no).await,
+        vec!["duplicate", "duplicate", "processed"]
+    );
+
+    // Deliveries that fail to process are logged with the error
+    let unknown = PaymentCallbackData {
+        order_no: format!("ORD{}", Uuid::new_v4().simple()),
+        ..callback()
+    };
+    let unknown_no = unknown.order_no.clone();
+    assert!(
+        PaymentService::handle_payment_callback(&app.pool, PaymentMethod::Alipay, unknown)
+            .await
+            .is_err()
+    );
+    assert_eq!(
+        callback_outcomes(&app.pool, &unknown_no).await,
+        vec!["error"]
+    );
+}
+
+async fn seed_user_balance(pool: &sqlx::MySqlPool, user_id: Uuid, amount: i64) {
+    PaymentService::create_user_balance(pool, user_id)
+        .await
+        .unwrap();
+    sqlx::query("UPDATE user_balances SET balance = ? WHERE user_id = ?")
+        .bind(Decimal::from(amount))
+        .bind(user_id.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_sequential_partial_refunds_fully_refund_order() {
+    let app = TestApp::new().await;
+    let (admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (order_id, _) = seed_order_with_transaction(&app.pool, patient_id, "paid", "success").await;
+    sqlx::query("UPDATE payment_transactions SET payment_method = 'balance' WHERE order_id = ?")
+        .bind(order_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    seed_user_balance(&app.pool, patient_id, 0).await;
+    // Balance refunds never reach the gateway
+    let gateway = MockGateway::new(Vec::new());
+
+    let refund = |amount: &str| CreateRefundDto {
+        order_id,
+        refund_amount: Decimal::from_str(amount).unwrap(),
+        refund_reason: "部分服务未提供".to_string(),
+    };
+    let approve = || ReviewRefundDto {
+        approved: true,
+        review_notes: None,
+    };
+
+    let first = PaymentService::create_refund(&app.pool, refund("10.00"), patient_id, false)
+        .await
+        .unwrap();
+    PaymentService::review_refund(&app.pool, &gateway, first.id, approve(), admin_id)
+        .await
+        .unwrap();
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::PartialRefunded);
+
+    // Only the remaining 20.00 can still be refunded
+    assert!(
+        PaymentService::create_refund(&app.pool, refund("25.00"), patient_id, false)
+            .await
+            .is_err()
+    );
+
+    let second = PaymentService::create_refund(&app.pool, refund("20.00"), patient_id, false)
+        .await
+        .unwrap();
+    PaymentService::review_refund(&app.pool, &gateway, second.id, approve(), admin_id)
+        .await
+        .unwrap();
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Refunded);
+
+    let balance = PaymentService::get_user_balance(&app.pool, patient_id)
+        .await
+        .unwrap();
+    assert_eq!(balance.balance, Decimal::from(30));
+
+    assert!(
+        PaymentService::create_refund(&app.pool, refund("0.01"), patient_id, false)
+            .await
+            .is_err()
+    );
+}
+#[tokio::test]
+async fn test_partial_refunds_cannot_exceed_order_amount() {
+    let app = TestApp::new().await;
+    let (admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (order_id, _) = seed_order_with_transaction(&app.pool, patient_id, "paid", "success").await;
+    sqlx::query("UPDATE payment_transactions SET payment_method = 'balance' WHERE order_id = ?")
+        .bind(order_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    seed_user_balance(&app.pool, patient_id, 0).await;
+    let gateway = MockGateway::new(Vec::new());
+
+    let refund = |amount: &str| CreateRefundDto {
+        order_id,
+        refund_amount: Decimal::from_str(amount).unwrap(),
+        refund_reason: "重复扣费".to_string(),
+    };
+
+    // 60% of the order is still pending review, so another 60% must be rejected
+    let first = PaymentService::create_refund(&app.pool, refund("18.00"), patient_id, false)
+        .await
+        .unwrap();
+    assert!(
+        PaymentService::create_refund(&app.pool, refund("18.00"), patient_id, false)
+            .await
+            .is_err()
+    );
+
+    // Approving the same refund twice concurrently only credits it once
+    let approve = || ReviewRefundDto {
+        approved: true,
+        review_notes: None,
+    };
+    let (a, b) = tokio::join!(
+        PaymentService::review_refund(&app.pool, &gateway, first.id, approve(), admin_id),
+        PaymentService::review_refund(&app.pool, &gateway, first.id, approve(), admin_id),
+    );
+    assert_eq!([a.is_ok(), b.is_ok()].iter().filter(|ok| **ok).count(), 1);
+
+    let balance = PaymentService::get_user_balance(&app.pool, patient_id)
+        .await
+        .unwrap();
+    assert_eq!(balance.balance, Decimal::from(18));
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::PartialRefunded);
+}
+#[tokio::test]
+async fn test_payment_statistics_grouped_by_period_and_method() {
+    let mut app = TestApp::new().await;
+    let (patient_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let token = get_auth_token(&mut app, &account, &password).await;
+
+    // 2024-03-04 is a Monday
+    let orders = [
+        ("2024-03-04 10:00:00", "paid", Some("wechat"), 30),
+        ("2024-03-04 15:00:00", "paid", Some("alipay"), 20),
+        ("2024-03-05 09:00:00", "paid", Some("wechat"), 50),
+        ("2024-03-05 11:00:00", "refunded", Some("alipay"), 40),
+        ("2024-03-12 08:00:00", "pending", None, 10),
+    ];
+    for (created_at, status, method, amount) in orders {
+        sqlx::query(
+            r#"
+            INSERT INTO payment_orders (
+                id, order_no, user_id, order_type, amount, currency,
+                status, payment_method, expire_time, created_at, updated_at
+            ) VALUES (?, ?, ?, 'consultation', ?, 'CNY', ?, ?, DATE_ADD(?, INTERVAL 2 HOUR), ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(format!("ORD{}", Uuid::new_v4().simple()))
+        .bind(patient_id.to_string())
+        .bind(Decimal::from(amount))
+        .bind(status)
+        .bind(method)
+        .bind(created_at)
+        .bind(created_at)
+        .bind(created_at)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    let buckets = |group_by, start| {
+        PaymentService::get_payment_statistics_buckets(
+            &app.pool,
+            Some(patient_id),
+            start,
+            None,
+            group_by,
+        )
+    };
+
+    let days = buckets(StatisticsGroupBy::Day, None).await.unwrap();
+    let periods: Vec<String> = days.iter().map(|b| b.period_start.to_string()).collect();
+    assert_eq!(periods, ["2024-03-04", "2024-03-05", "2024-03-12"]);
+    assert_eq!(days[0].total_orders, 2);
+    assert_eq!(days[0].paid_amount, Decimal::from(50));
+    assert_eq!(days[0].method_amounts["wechat"], Decimal::from(30));
+    assert_eq!(days[0].method_amounts["alipay"], Decimal::from(20));
+    assert_eq!(days[1].paid_orders, 1);
+    assert_eq!(days[1].refunded_amount, Decimal::from(40));
+    // Refunded orders are not counted as paid revenue per method
+    assert_eq!(days[1].method_amounts.len(), 1);
+    assert_eq!(days[1].method_amounts["wechat"], Decimal::from(50));
+    assert!(days[2].method_amounts.is_empty());
+
+    let weeks = buckets(StatisticsGroupBy::Week, None).await.unwrap();
+    let periods: Vec<String> = weeks.iter().map(|b| b.period_start.to_string()).collect();
+    assert_eq!(periods, ["2024-03-04", "2024-03-11"]);
+    assert_eq!(weeks[0].total_orders, 4);
+    assert_eq!(weeks[0].method_amounts["wechat"], Decimal::from(80));
+
+    let months = buckets(StatisticsGroupBy::Month, None).await.unwrap();
+    assert_eq!(months.len(), 1);
+    assert_eq!(months[0].period_start.to_string(), "2024-03-01");
+    assert_eq!(months[0].total_orders, 5);
+
+    let start = chrono::DateTime::parse_from_rfc3339("2024-03-05T00:00:00Z")
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    let windowed = buckets(StatisticsGroupBy::Day, Some(start)).await.unwrap();
+    assert_eq!(windowed.len(), 2);
+    assert_eq!(windowed[0].period_start.to_string(), "2024-03-05");
+
+    let (status, body) = app
+        .get_with_auth(
+            "/api/v1/payment/statistics?group_by=day&start_date=2024-03-04T00:00:00Z&end_date=2024-03-06T00:00:00Z",
+            &token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["total_orders"].as_i64().unwrap(), 4);
+    let buckets = body["data"]["buckets"].as_array().unwrap();
+    assert_eq!(buckets.len(), 2);
+    assert_eq!(buckets[0]["period_start"], "2024-03-04");
+    assert!(!buckets[0]["method_amounts"]["alipay"].is_null());
+
+    // Without group_by the response keeps its original shape
+    let (_, body) = app
+        .get_with_auth("/api/v1/payment/statistics", &token)
+        .await;
+    assert!(body["data"].get("buckets").is_none());
+}
+#[tokio::test]
+async fn test_concurrent_orders_get_unique_order_numbers() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+
+    let orders = futures_util::future::join_all((0..100).map(|_| {
+        PaymentService::create_order(
+            &app.pool,
+            CreateOrderDto {
+                user_id: patient_id,
+                appointment_id: None,
+                order_type: OrderType::Consultation,
+                amount: Decimal::from_str("30.00").unwrap(),
+                description: None,
+                metadata: None,
+                coupon_code: None,
+                idempotency_key: None,
+            },
+        )
+    }))
+    .await;
+
+    let order_nos: std::collections::HashSet<String> = orders
+        .into_iter()
+        .map(|order| order.expect("every concurrent order succeeds").order_no)
+        .collect();
+    assert_eq!(order_nos.len(), 100);
+    // WeChat Pay caps out_trade_no at 32 characters
+    assert!(order_nos
+        .iter()
+        .all(|no| no.starts_with("ORD") && no.len() <= 32));
+}
+fn price_config_dto(
+    service_type: &str,
+    price: i64,
+    effective_date: Option<chrono::NaiveDate>,
+    expiry_date: Option<chrono::NaiveDate>,
+) -> CreatePriceConfigDto {
+    CreatePriceConfigDto {
+        service_type: service_type.to_string(),
+        service_name: "测试服务".to_string(),
+        price: Decimal::from(price),
+        discount_price: None,
+        effective_date,
+        expiry_date,
+        description: None,
+    }
+}
+
+#[tokio::test]
+async fn test_price_config_overlap_is_rejected() {
+    let app = TestApp::new().await;
+    let (admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let service_type = format!("svc_{}", &Uuid::new_v4().simple().to_string()[..12]);
+    let today = chrono::Utc::now().date_naive();
+    let days = chrono::Duration::days;
+
+    // Effective date must not be after expiry date
+    assert!(PaymentService::create_price_config(
+        &app.pool,
+        price_config_dto(&service_type, 50, Some(today), Some(today - days(1))),
+        admin_id,
+    )
+    .await
+    .is_err());
+
+    let current = PaymentService::create_price_config(
+        &app.pool,
+        price_config_dto(&service_type, 50, Some(today), None),
+        admin_id,
+    )
+    .await
+    .unwrap();
+
+    // An open-ended config overlaps anything scheduled after it
+    assert!(PaymentService::create_price_config(
+        &app.pool,
+        price_config_dto(&service_type, 60, Some(today + days(10)), None),
+        admin_id,
+    )
+    .await
+    .is_err());
+
+    // Closing the current window frees the later dates
+    PaymentService::update_price_config(
+        &app.pool,
+        current.id,
+        UpdatePriceConfigDto {
+            service_name: None,
+            price: None,
+            discount_price: None,
+            effective_date: None,
+            expiry_date: Some(today + days(9)),
+            description: None,
+        },
+        admin_id,
+    )
+    .await
+    .unwrap();
+    let next = PaymentService::create_price_config(
+        &app.pool,
+        price_config_dto(&service_type, 60, Some(today + days(10)), None),
+        admin_id,
+    )
+    .await
+    .unwrap();
+
+    // Stretching the current window back over the next one is rejected too
+    assert!(PaymentService::update_price_config(
+        &app.pool,
+        current.id,
+        UpdatePriceConfigDto {
+            service_name: None,
+            price: None,
+            discount_price: None,
+            effective_date: None,
+            expiry_date: Some(today + days(20)),
+            description: None,
+        },
+        admin_id,
+    )
+    .await
+    .is_err());
+
+    // Deactivated configs no longer block new ones
+    PaymentService::deactivate_price_config(&app.pool, next.id, admin_id)
+        .await
+        .unwrap();
+    PaymentService::create_price_config(
+        &app.pool,
+        price_config_dto(&service_type, 70, Some(today + days(15)), None),
+        admin_id,
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_price_config_prefers_latest_effective_date() {
+    let mut app = TestApp::new().await;
+    let (_, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let (_, patient_account, patient_password) = create_test_user(&app.pool, "patient").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let service_type = format!("svc_{}", &Uuid::new_v4().simple().to_string()[..12]);
+    let today = chrono::Utc::now().date_naive();
+
+    // Legacy rows that predate overlap validation: the newer effective date wins
+    // even though the older one was inserted later
+    for (price, effective_days_ago, created_offset) in [(80, 1, "- INTERVAL 1 DAY"), (40, 30, "")] {
+        sqlx::query(&format!(
+            r#"
+            INSERT INTO price_configs (
+                id, service_type, service_name, price, is_active, effective_date, created_at
+            ) VALUES (?, ?, '测试服务', ?, true, ?, NOW() {})
+            "#,
+            created_offset
+        ))
+        .bind(Uuid::new_v4().to_string())
+        .bind(&service_type)
+        .bind(Decimal::from(price))
+        .bind(today - chrono::Duration::days(effective_days_ago))
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    let config = PaymentService::get_price_config(&app.pool, &service_type)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(config.price, Decimal::from(80));
+
+    // Only admins may manage prices over HTTP
+    let body = json!({
+        "service_type": service_type,
+        "service_name": "测试服务",
+        "price": "90.00",
+        "effective_date": today.to_string(),
+    });
+    let (status, _) = app
+        .post_with_auth("/api/v1/payment/price-configs", &body, &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = app
+        .post_with_auth("/api/v1/payment/price-configs", &body, &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app
+        .delete_with_auth(
+            &format!("/api/v1/payment/price-configs/{}", config.id),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["is_active"], false);
+
+    let fallback = PaymentService::get_price_config(&app.pool, &service_type)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(fallback.price, Decimal::from(40));
+}
+async fn insert_coupon(pool: &sqlx::MySqlPool, columns: &str, values: &str) -> String {
+    let code = format!("CPN{}", &Uuid::new_v4().simple().to_string()[..12]);
+    sqlx::query(&format!(
+        "INSERT INTO coupons (id, code, {}) VALUES (?, ?, {})",
+        columns, values
+    ))
+    .bind(Uuid::new_v4().to_string())
+    .bind(&code)
+    .execute(pool)
+    .await
+    .unwrap();
+    code
+}
+
+#[tokio::test]
+async fn test_percent_coupon_rounds_discount_down_to_cents() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let code = insert_coupon(
+        &app.pool,
+        "discount_type, discount_percent, min_order_amount, usage_limit",
+        "'percent', 15.00, 20.00, 5",
+    )
+    .await;
+
+    // 33.33 * 15% = 4.9995, never rounded up in the customer's favour
+    let preview = PaymentService::validate_coupon(
+        &app.pool,
+        &ValidateCouponDto {
+            code: code.clone(),
+            order_type: OrderType::Consultation,
+            amount: Decimal::from_str("33.33").unwrap(),
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(preview.discount_amount, Decimal::from_str("4.99").unwrap());
+    assert_eq!(preview.final_amount, Decimal::from_str("28.34").unwrap());
+
+    let order = PaymentService::create_order(
+        &app.pool,
+        CreateOrderDto {
+            user_id: patient_id,
+            appointment_id: None,
+            order_type: OrderType::Consultation,
+            amount: Decimal::from_str("33.33").unwrap(),
+            description: None,
+            metadata: None,
+            coupon_code: Some(code.clone()),
+            idempotency_key: None,
+        },
+    )
+    .await
+    .unwrap();
+    assert_eq!(order.original_amount, Decimal::from_str("33.33").unwrap());
+    assert_eq!(order.discount_amount, Decimal::from_str("4.99").unwrap());
+    assert_eq!(order.amount, Decimal::from_str("28.34").unwrap());
+
+    // Below the coupon threshold
+    let below_threshold = PaymentService::validate_coupon(
+        &app.pool,
+        &ValidateCouponDto {
+            code,
+            order_type: OrderType::Consultation,
+            amount: Decimal::from_str("19.99").unwrap(),
+        },
+    )
+    .await;
+    assert!(below_threshold.is_err());
+}
+
+#[tokio::test]
+async fn test_expired_and_exhausted_coupons_are_rejected() {
+    let mut app = TestApp::new().await;
+    let (patient_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let token = get_auth_token(&mut app, &account, &password).await;
+
+    let expired = insert_coupon(
+        &app.pool,
+        "discount_amount, usage_limit, expires_at",
+        "5.00, 10, DATE_SUB(NOW(), INTERVAL 1 HOUR)",
+    )
+    .await;
+    let exhausted = insert_coupon(
+        &app.pool,
+        "discount_amount, usage_limit, used",
+        "5.00, 1, 1",
+    )
+    .await;
+    let not_started = insert_coupon(
+        &app.pool,
+        "discount_amount, usage_limit, starts_at",
+        "5.00, 10, DATE_ADD(NOW(), INTERVAL 1 DAY)",
+    )
+    .await;
+
+    for (code, message) in [
+        (&expired, "优惠券已过期"),
+        (&exhausted, "优惠券已被用完"),
+        (&not_started, "优惠券尚未生效"),
+    ] {
+        let (status, body) = app
+            .post_with_auth(
+                "/api/v1/payment/coupons/validate",
+                json!({ "code": code, "order_type": "consultation", "amount": "30.00" }),
+                &token,
+            )
+            .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["message"], message);
+
+        let result = PaymentService::create_order(
+            &app.pool,
+            CreateOrderDto {
+                user_id: patient_id,
+                appointment_id: None,
+                order_type: OrderType::Consultation,
+                amount: Decimal::from_str("30.00").unwrap(),
+                description: None,
+                metadata: None,
+                coupon_code: Some(code.clone()),
+                idempotency_key: None,
+            },
+        )
+        .await;
+        assert!(result.is_err());
+    }
+
+    let used: i32 = sqlx::query_scalar("SELECT used FROM coupons WHERE code = ?")
+        .bind(&exhausted)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(used, 1);
+}
+
+#[tokio::test]
+async fn test_cancelled_and_expired_orders_release_coupon() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let code = insert_coupon(&app.pool, "discount_amount, usage_limit", "10.00, 2").await;
+    let coupon_used = || async {
+        sqlx::query_scalar::<_, i32>("SELECT used FROM coupons WHERE code = ?")
+            .bind(&code)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap()
+    };
+    let order_dto = || CreateOrderDto {
+        user_id: patient_id,
+        appointment_id: None,
+        order_type: OrderType::Consultation,
+        amount: Decimal::from_str("30.00").unwrap(),
+        description: None,
+        metadata: None,
+        coupon_code: Some(code.clone()),
+        idempotency_key: None,
+    };
+
+    let cancelled = PaymentService::create_order(&app.pool, order_dto())
+        .await
+        .unwrap();
+    let overdue = PaymentService::create_order(&app.pool, order_dto())
+        .await
+        .unwrap();
+    assert_eq!(coupon_used().await, 2);
+
+    // Cancelling an unpaid order gives its redemption back
+    PaymentService::cancel_order(&app.pool, cancelled.id)
+        .await
+        .unwrap();
+    assert_eq!(coupon_used().await, 1);
+    assert!(PaymentService::cancel_order(&app.pool, cancelled.id)
+        .await
+        .is_err());
+    assert_eq!(coupon_used().await, 1);
+
+    // So does an order the sweep expires
+    sqlx::query(
+        "UPDATE payment_orders SET expire_time = DATE_SUB(NOW(), INTERVAL 1 MINUTE) WHERE id = ?",
+    )
+    .bind(overdue.id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+    PaymentService::expire_stale_orders(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(coupon_used().await, 0);
+
+    // The released redemptions can be used again
+    PaymentService::create_order(&app.pool, order_dto())
+        .await
+        .unwrap();
+    assert_eq!(coupon_used().await, 1);
+}
+
+#[tokio::test]
+async fn test_admin_manages_coupons() {
+    let mut app = TestApp::new().await;
+    let (_, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let (_, patient_account, patient_password) = create_test_user(&app.pool, "patient").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+    let code = format!("CPN{}", &Uuid::new_v4().simple().to_string()[..12]);
+
+    let coupon = json!({
+        "code": code,
+        "discount_type": "percent",
+        "discount_percent": "120",
+        "usage_limit": 10,
+    });
+    let (status, _) = app
+        .post_with_auth("/api/v1/payment/coupons", &coupon, &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    // Percentages above 100 are rejected
+    let (status, _) = app
+        .post_with_auth("/api/v1/payment/coupons", &coupon, &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/payment/coupons",
+            json!({
+                "code": code,
+                "discount_type": "fixed",
+                "discount_amount": "8.00",
+                "usage_limit": 10,
+            }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let coupon_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (status, body) = app
+        .put_with_auth(
+            &format!("/api/v1/payment/coupons/{}", coupon_id),
+            json!({ "usage_limit": 20 }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["usage_limit"], 20);
+
+    let (status, body) = app
+        .delete_with_auth(
+            &format!("/api/v1/payment/coupons/{}", coupon_id),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["is_active"], false);
+
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/payment/coupons/validate",
+            json!({ "code": code, "order_type": "consultation", "amount": "30.00" }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+/// 预约 + 进行中的问诊，并用余额支付该预约的订单，返回 (预约ID, 问诊ID, 订单ID)
+async fn seed_held_appointment_payment(
+    pool: &sqlx::MySqlPool,
+    patient_id: Uuid,
+    doctor_id: Uuid,
+) -> (Uuid, Uuid, Uuid) {
+    let appointment_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot,
+                                  visit_type, symptoms, has_visited_before, status, created_at, updated_at)
+        VALUES (?, ?, ?, DATE_ADD(NOW(), INTERVAL 1 DAY), '09:00-10:00', 'online_video', '测试症状', false, 'pending', NOW(), NOW())
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let consultation_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, actual_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'in_progress', NOW(), NOW(), NOW(), NOW())
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(format!("room_{}", Uuid::new_v4().simple()))
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let order = PaymentService::create_order(
+        pool,
+        CreateOrderDto {
+            user_id: patient_id,
+            appointment_id: Some(appointment_id),
+            order_type: OrderType::Appointment,
+            amount: Decimal::from(50),
+            description: None,
+            metadata: None,
+            coupon_code: None,
+            idempotency_key: None,
+        },
+    )
+    .await
+    .unwrap();
+    PaymentService::initiate_payment(
+        pool,
+        &MockGateway::new(Vec::new()),
+        InitiatePaymentDto {
+            order_id: order.id,
+            payment_method: PaymentMethod::Balance,
+            return_url: None,
+            openid: None,
+            wap: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    (appointment_id, consultation_id, order.id)
+}
+
+/// 可用余额 + 冻结余额 + 累计支出，托管流程中任何时刻都应保持不变
+async fn balance_total(pool: &sqlx::MySqlPool, user_id: Uuid) -> (Decimal, Decimal, Decimal) {
+    let balance = PaymentService::get_user_balance(pool, user_id)
+        .await
+        .unwrap();
+    (
+        balance.balance,
+        balance.frozen_balance,
+        balance.balance + balance.frozen_balance + balance.total_expense,
+    )
+}
+
+#[tokio::test]
+async fn test_appointment_balance_payment_is_held_until_settled_or_released() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    seed_user_balance(&app.pool, patient_id, 200).await;
+    let complete = || CompleteConsultationDto {
+        diagnosis: "气血不足".to_string(),
+        treatment_plan: None,
+        notes: None,
+    };
+    let income_rows = |order_id: Uuid| {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM doctor_earnings WHERE order_id = ? AND entry_type = 'income'",
+        )
+        .bind(order_id.to_string())
+        .fetch_one(&app.pool)
+    };
+
+    // Paying freezes the amount instead of spending it
+    let (_, consultation_id, order_id) =
+        seed_held_appointment_payment(&app.pool, patient_id, doctor_id).await;
+    assert_eq!(
+        balance_total(&app.pool, patient_id).await,
+        (Decimal::from(150), Decimal::from(50), Decimal::from(200))
+    );
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+    assert_eq!(order.balance_hold_status, Some(BalanceHoldStatus::Held));
+    assert_eq!(income_rows(order_id).await.unwrap(), 0);
+
+    // Held money cannot also be refunded into the balance
+    let refund = CreateRefundDto {
+        order_id,
+        refund_amount: Decimal::from(50),
+        refund_reason: "不想看了".to_string(),
+    };
+    assert!(
+        PaymentService::create_refund(&app.pool, refund, patient_id, false)
+            .await
+            .is_err()
+    );
+
+    // Completing the consultation settles the hold and credits the doctor
+    VideoConsultationService::end_consultation(
+        &app.pool,
+        consultation_id,
+        doctor_user_id,
+        complete(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        balance_total(&app.pool, patient_id).await,
+        (Decimal::from(150), Decimal::ZERO, Decimal::from(200))
+    );
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.balance_hold_status, Some(BalanceHoldStatus::Settled));
+    assert_eq!(income_rows(order_id).await.unwrap(), 1);
+
+    // Settling or releasing again is a no-op
+    let appointment_id = order.appointment_id.unwrap();
+    let mut tx = app.pool.begin().await.unwrap();
+    PaymentService::settle_balance_hold_tx(&app.pool, &mut tx, appointment_id)
+        .await
+        .unwrap();
+    PaymentService::release_balance_hold_tx(&mut tx, appointment_id)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+    appointment_service::cancel_appointment(&app.pool, appointment_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        balance_total(&app.pool, patient_id).await,
+        (Decimal::from(150), Decimal::ZERO, Decimal::from(200))
+    );
+    assert_eq!(income_rows(order_id).await.unwrap(), 1);
+
+    // Cancelling before the visit returns the frozen amount
+    let (appointment_id, _, order_id) =
+        seed_held_appointment_payment(&app.pool, patient_id, doctor_id).await;
+    appointment_service::cancel_appointment(&app.pool, appointment_id)
+        .await
+        .unwrap();
+    assert_eq!(
+        balance_total(&app.pool, patient_id).await,
+        (Decimal::from(150), Decimal::ZERO, Decimal::from(200))
+    );
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Refunded);
+    assert_eq!(order.balance_hold_status, Some(BalanceHoldStatus::Released));
+    assert_eq!(income_rows(order_id).await.unwrap(), 0);
+
+    // Completion racing a cancellation resolves the hold exactly once
+    let (appointment_id, consultation_id, order_id) =
+        seed_held_appointment_payment(&app.pool, patient_id, doctor_id).await;
+    let _ = tokio::join!(
+        VideoConsultationService::end_consultation(
+            &app.pool,
+            consultation_id,
+            doctor_user_id,
+            complete(),
+        ),
+        appointment_service::cancel_appointment(&app.pool, appointment_id),
+    );
+    let (balance, frozen, total) = balance_total(&app.pool, patient_id).await;
+    assert_eq!(frozen, Decimal::ZERO);
+    assert_eq!(total, Decimal::from(200));
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    match order.balance_hold_status {
+        Some(BalanceHoldStatus::Settled) => {
+            assert_eq!(balance, Decimal::from(100));
+            assert_eq!(income_rows(order_id).await.unwrap(), 1);
+        }
+        Some(BalanceHoldStatus::Released) => {
+            assert_eq!(balance, Decimal::from(150));
+            assert_eq!(income_rows(order_id).await.unwrap(), 0);
+        }
+        other => panic!("hold not resolved: {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_appointment_status_update_settles_or_releases_hold() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    seed_user_balance(&app.pool, patient_id, 200).await;
+    let set_status = |appointment_id: Uuid, status: AppointmentStatus| {
+        appointment_service::update_appointment(
+            &app.pool,
+            appointment_id,
+            UpdateAppointmentDto {
+                appointment_date: None,
+                time_slot: None,
+                status: Some(status),
+            },
+        )
+    };
+    let income_rows = |order_id: Uuid| {
+        sqlx::query_scalar::<_, i64>(
+            "SELECT COUNT(*) FROM doctor_earnings WHERE order_id = ? AND entry_type = 'income'",
+        )
+        .bind(order_id.to_string())
+        .fetch_one(&app.pool)
+    };
+
+    // An in-person visit marked completed without a video call still settles the hold
+    let (appointment_id, _, order_id) =
+        seed_held_appointment_payment(&app.pool, patient_id, doctor_id).await;
+    let appointment = set_status(appointment_id, AppointmentStatus::Completed)
+        .await
+        .unwrap();
+    assert_eq!(appointment.status, AppointmentStatus::Completed);
+    assert_eq!(
+        balance_total(&app.pool, patient_id).await,
+        (Decimal::from(150), Decimal::ZERO, Decimal::from(200))
+    );
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+    assert_eq!(order.balance_hold_status, Some(BalanceHoldStatus::Settled));
+    assert_eq!(income_rows(order_id).await.unwrap(), 1);
+
+    // Cancelling through the status update returns the frozen amount
+    let (appointment_id, _, order_id) =
+        seed_held_appointment_payment(&app.pool, patient_id, doctor_id).await;
+    set_status(appointment_id, AppointmentStatus::Cancelled)
+        .await
+        .unwrap();
+    assert_eq!(
+        balance_total(&app.pool, patient_id).await,
+        (Decimal::from(150), Decimal::ZERO, Decimal::from(200))
+    );
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Refunded);
+    assert_eq!(order.balance_hold_status, Some(BalanceHoldStatus::Released));
+
+    // So does a no-show
+    let (appointment_id, _, order_id) =
+        seed_held_appointment_payment(&app.pool, patient_id, doctor_id).await;
+    set_status(appointment_id, AppointmentStatus::NoShow)
+        .await
+        .unwrap();
+    assert_eq!(
+        balance_total(&app.pool, patient_id).await,
+        (Decimal::from(150), Decimal::ZERO, Decimal::from(200))
+    );
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.balance_hold_status, Some(BalanceHoldStatus::Released));
+    assert_eq!(income_rows(order_id).await.unwrap(), 0);
+}
@@ -0,0 +1,1310 @@
+use crate::common::{
+    configure_alipay, configure_wechat, MockGateway, TestApp, TEST_PRIVATE_KEY, TEST_PUBLIC_KEY,
+    TEST_WECHAT_API_V3_KEY,
+};
+use aes_gcm::{
+    aead::{Aead, KeyInit, Payload},
+    Aes256Gcm, Nonce,
+};
+use axum::http::StatusCode;
+use backend::{
+    config::database::DbPool,
+    models::payment::*,
+    services::{
+        alipay_service::{AlipayService, AlipaySigner},
+        payment_gateway::GatewayRequest,
+        payment_service::PaymentService,
+        wechat_pay_service::WechatPayService,
+    },
+    utils::{
+        crypto,
+        errors::AppError,
+        test_helpers::{create_test_doctor, create_test_user},
+    },
+};
+use base64::{engine::general_purpose::STANDARD, Engine};
+use rust_decimal::Decimal;
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Mutex;
+use uuid::Uuid;
+
+async fn create_pending_order(pool: &DbPool, amount: &str) -> Uuid {
+    let (user_id, _, _) = create_test_user(pool, "patient").await;
+    let order_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO payment_orders (
+            id, order_no, user_id, order_type, amount, currency, status,
+            description, expire_time, created_at, updated_at
+        ) VALUES (?, ?, ?, 'consultation', ?, 'CNY', 'pending', '在线问诊', DATE_ADD(NOW(), INTERVAL 2 HOUR), NOW(), NOW())
+        "#,
+    )
+    .bind(order_id.to_string())
+    .bind(format!("ORD{}", Uuid::new_v4().simple()))
+    .bind(user_id.to_string())
+    .bind(Decimal::from_str(amount).unwrap())
+    .execute(pool)
+    .await
+    .unwrap();
+    order_id
+}
+
+fn header<'a>(request: &'a GatewayRequest, name: &str) -> &'a str {
+    request
+        .headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+        .unwrap()
+}
+
+/// 解析 Authorization 头中的 key="value" 字段
+fn auth_field(authorization: &str, field: &str) -> String {
+    let prefix = format!("{}=\"", field);
+    let start = authorization.find(&prefix).unwrap() + prefix.len();
+    let end = authorization[start..].find('"').unwrap() + start;
+    authorization[start..end].to_string()
+}
+
+#[tokio::test]
+async fn test_wechat_jsapi_payment_signs_request_and_returns_prepay_data() {
+    let app = TestApp::new().await;
+    configure_wechat(&app.pool).await;
+    let order_id = create_pending_order(&app.pool, "88.80").await;
+    let gateway = MockGateway::new(vec![(
+        200,
+        serde_json::json!({ "prepay_id": "wx201410272009395522657a690389285100" }),
+    )]);
+
+    let response = PaymentService::initiate_payment(
+        &app.pool,
+        &gateway,
+        InitiatePaymentDto {
+            order_id,
+            payment_method: PaymentMethod::Wechat,
+            return_url: None,
+            openid: Some("oUpF8uMuAJO_M2pxb1Q9zNjWeS6o".to_string()),
+            wap: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    let requests = gateway.requests();
+    assert_eq!(requests.len(), 1);
+    let request = &requests[0];
+    assert_eq!(request.method, reqwest::Method::POST);
+    assert_eq!(
+        request.url,
+        format!("{}/v3/pay/transactions/jsapi", WechatPayService::API_BASE)
+    );
+
+    let body: serde_json::Value = serde_json::from_str(request.body.as_deref().unwrap()).unwrap();
+    assert_eq!(body["appid"], "wxtestappid");
+    assert_eq!(body["mchid"], "1900000001");
+    assert_eq!(body["amount"]["total"], 8880);
+    assert_eq!(body["payer"]["openid"], "oUpF8uMuAJO_M2pxb1Q9zNjWeS6o");
+
+    // The Authorization header signs method, path, timestamp, nonce and body
+    let authorization = header(request, "Authorization");
+    assert!(authorization.starts_with("WECHATPAY2-SHA256-RSA2048 "));
+    assert_eq!(auth_field(authorization, "mchid"), "1900000001");
+    assert_eq!(auth_field(authorization, "serial_no"), "TESTSERIALNO");
+    let message = WechatPayService::sign_message(&[
+        "POST",
+        "/v3/pay/transactions/jsapi",
+        &auth_field(authorization, "timestamp"),
+        &auth_field(authorization, "nonce_str"),
+        request.body.as_deref().unwrap(),
+    ]);
+    assert!(crypto::rsa_verify_sha256(
+        TEST_PUBLIC_KEY,
+        &message,
+        &auth_field(authorization, "signature")
+    )
+    .unwrap());
+
+    assert!(response.qr_code.is_none());
+    let prepay_data = response.prepay_data.unwrap();
+    assert_eq!(prepay_data["appId"], "wxtestappid");
+    assert_eq!(
+        prepay_data["package"],
+        "prepay_id=wx201410272009395522657a690389285100"
+    );
+    assert_eq!(prepay_data["signType"], "RSA");
+    let pay_message = WechatPayService::sign_message(&[
+        "wxtestappid",
+        prepay_data["timeStamp"].as_str().unwrap(),
+        prepay_data["nonceStr"].as_str().unwrap(),
+        prepay_data["package"].as_str().unwrap(),
+    ]);
+    assert!(crypto::rsa_verify_sha256(
+        TEST_PUBLIC_KEY,
+        &pay_message,
+        prepay_data["paySign"].as_str().unwrap()
+    )
+    .unwrap());
+
+    let (prepay_id, response_data): (Option<String>, Option<serde_json::Value>) = sqlx::query_as(
+        "SELECT prepay_id, response_data FROM payment_transactions WHERE order_id = ?",
+    )
+    .bind(order_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        prepay_id.as_deref(),
+        Some("wx201410272009395522657a690389285100")
+    );
+    assert_eq!(
+        response_data.unwrap()["prepay_id"],
+        "wx201410272009395522657a690389285100"
+    );
+}
+
+#[tokio::test]
+async fn test_wechat_gateway_rejection_is_recorded() {
+    let app = TestApp::new().await;
+    configure_wechat(&app.pool).await;
+    let order_id = create_pending_order(&app.pool, "10.00").await;
+    let gateway = MockGateway::new(vec![(
+        400,
+        serde_json::json!({ "code": "PARAM_ERROR", "message": "参数错误" }),
+    )]);
+
+    let result = PaymentService::initiate_payment(
+        &app.pool,
+        &gateway,
+        InitiatePaymentDto {
+            order_id,
+            payment_method: PaymentMethod::Wechat,
+            return_url: None,
+            openid: None,
+            wap: false,
+        },
+    )
+    .await;
+
+    match result {
+        Err(AppError::PaymentGatewayError { code, message }) => {
+            assert_eq!(code, "PARAM_ERROR");
+            assert_eq!(message, "参数错误");
+        }
+        other => panic!("expected gateway error, got {:?}", other),
+    }
+
+    let (status, error_code, error_message): (String, Option<String>, Option<String>) =
+        sqlx::query_as(
+            "SELECT status, error_code, error_message FROM payment_transactions WHERE order_id = ?",
+        )
+        .bind(order_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(status, "failed");
+    assert_eq!(error_code.as_deref(), Some("PARAM_ERROR"));
+    assert_eq!(error_message.as_deref(), Some("参数错误"));
+
+    // The order stays payable so the patient can retry
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Pending);
+}
+
+#[tokio::test]
+async fn test_wechat_native_payment_returns_code_url() {
+    let app = TestApp::new().await;
+    configure_wechat(&app.pool).await;
+    let order_id = create_pending_order(&app.pool, "0.01").await;
+    let gateway = MockGateway::new(vec![(
+        200,
+        serde_json::json!({ "code_url": "weixin://wxpay/bizpayurl?pr=p4lpSuKzz" }),
+    )]);
+
+    let response = PaymentService::initiate_payment(
+        &app.pool,
+        &gateway,
+        InitiatePaymentDto {
+            order_id,
+            payment_method: PaymentMethod::Wechat,
+            return_url: None,
+            openid: None,
+            wap: false,
+        },
+    )
+    .await
+    .unwrap();
+
+    let requests = gateway.requests();
+    assert_eq!(
+        requests[0].url,
+        format!("{}/v3/pay/transactions/native", WechatPayService::API_BASE)
+    );
+    let body: serde_json::Value =
+        serde_json::from_str(requests[0].body.as_deref().unwrap()).unwrap();
+    assert_eq!(body["amount"]["total"], 1);
+    assert!(body.get("payer").is_none());
+
+    assert_eq!(
+        response.qr_code.as_deref(),
+        Some("weixin://wxpay/bizpayurl?pr=p4lpSuKzz")
+    );
+    assert!(response.prepay_data.is_none());
+
+    let prepay_id: Option<String> =
+        sqlx::query_scalar("SELECT prepay_id FROM payment_transactions WHERE order_id = ?")
+            .bind(order_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(
+        prepay_id.as_deref(),
+        Some("weixin://wxpay/bizpayurl?pr=p4lpSuKzz")
+    );
+}
+
+#[tokio::test]
+async fn test_wechat_response_without_code_url_is_gateway_error() {
+    let app = TestApp::new().await;
+    configure_wechat(&app.pool).await;
+    let order_id = create_pending_order(&app.pool, "20.00").await;
+    let gateway = MockGateway::new(vec![(200, serde_json::json!({}))]);
+
+    let result = PaymentService::initiate_payment(
+        &app.pool,
+        &gateway,
+        InitiatePaymentDto {
+            order_id,
+            payment_method: PaymentMethod::Wechat,
+            return_url: None,
+            openid: None,
+            wap: false,
+        },
+    )
+    .await;
+
+    assert!(matches!(
+        result,
+        Err(AppError::PaymentGatewayError { ref code, .. }) if code == "INVALID_RESPONSE"
+    ));
+
+    let (status, error_code): (String, Option<String>) =
+        sqlx::query_as("SELECT status, error_code FROM payment_transactions WHERE order_id = ?")
+            .bind(order_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(status, "failed");
+    assert_eq!(error_code.as_deref(), Some("INVALID_RESPONSE"));
+}
+
+/// 记录待签名字符串并返回固定签名
+struct RecordingSigner {
+    contents: Mutex<Vec<String>>,
+}
+
+impl AlipaySigner for RecordingSigner {
+    fn sign_type(&self) -> &'static str {
+        "RSA2"
+    }
+
+    fn sign(&self, content: &str) -> Result<String, AppError> {
+        self.contents.lock().unwrap().push(content.to_string());
+        Ok("stub-signature".to_string())
+    }
+}
+
+fn query_params(url: &str) -> BTreeMap<String, String> {
+    reqwest::Url::parse(url)
+        .unwrap()
+        .query_pairs()
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect()
+}
+
+#[tokio::test]
+async fn test_alipay_sign_content_is_sorted_and_skips_empty_values() {
+    let mut params = BTreeMap::new();
+    params.insert("method".to_string(), "alipay.trade.page.pay".to_string());
+    params.insert("app_id".to_string(), "2021000000000001".to_string());
+    params.insert("return_url".to_string(), String::new());
+    params.insert(
+        "biz_content".to_string(),
+        r#"{"out_trade_no":"ORD1"}"#.to_string(),
+    );
+    let signer = RecordingSigner {
+        contents: Mutex::new(Vec::new()),
+    };
+
+    let signed = AlipayService::sign_params(params, &signer).unwrap();
+
+    assert_eq!(
+        signer.contents.lock().unwrap().as_slice(),
+        [
+            r#"app_id=2021000000000001&biz_content={"out_trade_no":"ORD1"}&method=alipay.trade.page.pay&sign_type=RSA2"#
+        ]
+    );
+    assert_eq!(signed["sign"], "stub-signature");
+    assert_eq!(signed["sign_type"], "RSA2");
+}
+
+#[tokio::test]
+async fn test_alipay_page_and_wap_pay_urls_are_signed() {
+    let app = TestApp::new().await;
+    configure_alipay(&app.pool).await;
+    let gateway = MockGateway::new(Vec::new());
+
+    for (wap, method, product_code) in [
+        (false, "alipay.trade.page.pay", "FAST_INSTANT_TRADE_PAY"),
+        (true, "alipay.trade.wap.pay", "QUICK_WAP_WAY"),
+    ] {
+        let order_id = create_pending_order(&app.pool, "88.80").await;
+        let response = PaymentService::initiate_payment(
+            &app.pool,
+            &gateway,
+            InitiatePaymentDto {
+                order_id,
+                payment_method: PaymentMethod::Alipay,
+                return_url: Some("https://example.com/return".to_string()),
+                openid: None,
+                wap,
+            },
+        )
+        .await
+        .unwrap();
+
+        let payment_url = response.payment_url.unwrap();
+        assert!(payment_url.starts_with(AlipayService::GATEWAY_URL));
+        let params = query_params(&payment_url);
+        assert_eq!(params["app_id"], "2021000000000001");
+        assert_eq!(params["method"], method);
+        assert_eq!(params["sign_type"], "RSA2");
+        assert_eq!(params["return_url"], "https://example.com/return");
+
+        let biz_content: serde_json::Value = serde_json::from_str(&params["biz_content"]).unwrap();
+        assert_eq!(biz_content["out_trade_no"], response.order_no);
+        assert_eq!(biz_content["total_amount"], "88.80");
+        assert_eq!(biz_content["product_code"], product_code);
+
+        assert!(crypto::rsa_verify_sha256(
+            TEST_PUBLIC_KEY,
+            &AlipayService::sign_content(&params, &["sign"]),
+            &params["sign"]
+        )
+        .unwrap());
+
+        let (request_data, response_data): (serde_json::Value, serde_json::Value) = sqlx::query_as(
+            "SELECT request_data, response_data FROM payment_transactions WHERE order_id = ?",
+        )
+        .bind(order_id.to_string())
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+        assert_eq!(request_data["sign"], params["sign"].as_str());
+        assert_eq!(response_data["payment_url"], payment_url.as_str());
+    }
+
+    // Page/wap pay is a redirect, nothing is sent to Alipay up front
+    assert!(gateway.requests().is_empty());
+}
+
+async fn start_payment(app: &TestApp, order_id: Uuid, payment_method: PaymentMethod) -> String {
+    let gateway = MockGateway::new(vec![(
+        200,
+        serde_json::json!({ "code_url": "weixin://wxpay/bizpayurl?pr=callback" }),
+    )]);
+    PaymentService::initiate_payment(
+        &app.pool,
+        &gateway,
+        InitiatePaymentDto {
+            order_id,
+            payment_method,
+            return_url: None,
+            openid: None,
+            wap: false,
+        },
+    )
+    .await
+    .unwrap()
+    .order_no
+}
+
+async fn stored_callback(pool: &DbPool, order_id: Uuid) -> serde_json::Value {
+    sqlx::query_scalar("SELECT callback_data FROM payment_transactions WHERE order_id = ?")
+        .bind(order_id.to_string())
+        .fetch_one(pool)
+        .await
+        .unwrap()
+}
+
+fn wechat_callback_headers(
+    body: &str,
+    timestamp: i64,
+    serial: &str,
+) -> Vec<(&'static str, String)> {
+    let nonce = Uuid::new_v4().simple().to_string();
+    let message = WechatPayService::sign_message(&[&timestamp.to_string(), &nonce, body]);
+    vec![
+        ("Wechatpay-Timestamp", timestamp.to_string()),
+        ("Wechatpay-Nonce", nonce),
+        ("Wechatpay-Serial", serial.to_string()),
+        (
+            "Wechatpay-Signature",
+            crypto::rsa_sign_sha256(TEST_PRIVATE_KEY, &message).unwrap(),
+        ),
+    ]
+}
+
+/// 按微信支付 v3 的格式加密业务报文，生成回调通知主体
+fn wechat_notification(event_type: &str, resource: &serde_json::Value) -> String {
+    let nonce = &Uuid::new_v4().simple().to_string()[..12];
+    let associated_data = "transaction";
+    let ciphertext = Aes256Gcm::new_from_slice(TEST_WECHAT_API_V3_KEY.as_bytes())
+        .unwrap()
+        .encrypt(
+            Nonce::from_slice(nonce.as_bytes()),
+            Payload {
+                msg: resource.to_string().as_bytes(),
+                aad: associated_data.as_bytes(),
+            },
+        )
+        .unwrap();
+
+    serde_json::json!({
+        "id": Uuid::new_v4().to_string(),
+        "event_type": event_type,
+        "resource_type": "encrypt-resource",
+        "resource": {
+            "algorithm": "AEAD_AES_256_GCM",
+            "ciphertext": STANDARD.encode(ciphertext),
+            "associated_data": associated_data,
+            "nonce": nonce,
+        },
+    })
+    .to_string()
+}
+
+/// 按支付宝异步通知的方式编码为 application/x-www-form-urlencoded 报文
+fn alipay_form(params: &BTreeMap<String, String>) -> (Vec<(&'static str, String)>, String) {
+    let body = params
+        .iter()
+        .map(|(key, value)| format!("{}={}", key, urlencoding::encode(value).replace("%20", "+")))
+        .collect::<Vec<_>>()
+        .join("&");
+    let headers = vec![(
+        "Content-Type",
+        "application/x-www-form-urlencoded; charset=utf-8".to_string(),
+    )];
+    (headers, body)
+}
+
+#[tokio::test]
+async fn test_alipay_callback_requires_valid_signature() {
+    let mut app = TestApp::new().await;
+    configure_alipay(&app.pool).await;
+    let order_id = create_pending_order(&app.pool, "30.00").await;
+    let order_no = start_payment(&app, order_id, PaymentMethod::Alipay).await;
+
+    let mut params = BTreeMap::new();
+    params.insert("app_id".to_string(), "2021000000000001".to_string());
+    params.insert("out_trade_no".to_string(), order_no.clone());
+    params.insert(
+        "trade_no".to_string(),
+        format!("ALI{}", Uuid::new_v4().simple()),
+    );
+    params.insert("total_amount".to_string(), "30.00".to_string());
+    params.insert("trade_status".to_string(), "TRADE_SUCCESS".to_string());
+    // Spaces, colons, non-ASCII text and JSON are percent-encoded in the form body
+    params.insert("gmt_payment".to_string(), "2024-01-15 10:30:00".to_string());
+    params.insert("subject".to_string(), "中医问诊 & 复诊".to_string());
+    params.insert(
+        "fund_bill_list".to_string(),
+        r#"[{"amount":"30.00","fundChannel":"ALIPAYACCOUNT"}]"#.to_string(),
+    );
+    params.insert("sign_type".to_string(), "RSA2".to_string());
+    let sign = crypto::rsa_sign_sha256(
+        TEST_PRIVATE_KEY,
+        &AlipayService::sign_content(&params, &["sign", "sign_type"]),
+    )
+    .unwrap();
+    params.insert("sign".to_string(), sign);
+
+    // Changing a signed field invalidates the notification
+    let mut tampered = params.clone();
+    tampered.insert("total_amount".to_string(), "0.01".to_string());
+    let (headers, body) = alipay_form(&tampered);
+    let (status, _) = app
+        .post_raw(
+            "/api/v1/payment/payment/callback?method=alipay",
+            &headers,
+            body,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Pending);
+    let callback = stored_callback(&app.pool, order_id).await;
+    assert_eq!(callback["signature_verified"], false);
+    assert_eq!(callback["payload"]["total_amount"], "0.01");
+
+    let (headers, body) = alipay_form(&params);
+    let (status, _) = app
+        .post_raw(
+            "/api/v1/payment/payment/callback?method=alipay",
+            &headers,
+            body,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+    // gmt_payment is Beijing time
+    assert_eq!(
+        order.payment_time.unwrap().to_rfc3339(),
+        "2024-01-15T02:30:00+00:00"
+    );
+}
+
+#[tokio::test]
+async fn test_wechat_callback_requires_valid_signature() {
+    let mut app = TestApp::new().await;
+    configure_wechat(&app.pool).await;
+    let order_id = create_pending_order(&app.pool, "10.00").await;
+    let order_no = start_payment(&app, order_id, PaymentMethod::Wechat).await;
+    let path = "/api/v1/payment/payment/callback?method=wechat";
+
+    let body = wechat_notification(
+        "TRANSACTION.SUCCESS",
+        &serde_json::json!({
+            "out_trade_no": order_no,
+            "transaction_id": format!("4200{}", Uuid::new_v4().simple()),
+            "trade_state": "SUCCESS",
+            "success_time": "2024-01-15T10:30:00+08:00",
+            "amount": { "total": 1000, "payer_total": 1000, "currency": "CNY" },
+        }),
+    );
+    let now = chrono::Utc::now().timestamp();
+
+    // Body changed after signing, unknown platform serial, replayed timestamp, no headers
+    let signed = wechat_callback_headers(&body, now, "TESTPLATFORMSERIAL");
+    let tampered_body = body.replace("TRANSACTION.SUCCESS", "TRANSACTION.SUCCESS ");
+    let rejected = [
+        (signed.clone(), tampered_body),
+        (
+            wechat_callback_headers(&body, now, "OTHERSERIAL"),
+            body.clone(),
+        ),
+        (
+            wechat_callback_headers(&body, now - 3600, "TESTPLATFORMSERIAL"),
+            body.clone(),
+        ),
+        (Vec::new(), body.clone()),
+    ];
+    for (headers, request_body) in rejected {
+        let (status, _) = app.post_raw(path, &headers, request_body).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Pending);
+    assert_eq!(
+        stored_callback(&app.pool, order_id).await["signature_verified"],
+        false
+    );
+
+    let (status, body) = app.post_raw(path, &signed, body).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["code"], "SUCCESS");
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+    assert_eq!(
+        order.payment_time.unwrap().to_rfc3339(),
+        "2024-01-15T02:30:00+00:00"
+    );
+
+    // The decrypted transaction is what gets stored, along with the signed notification
+    let callback = stored_callback(&app.pool, order_id).await;
+    assert!(callback.get("signature_verified").is_none());
+    assert_eq!(callback["out_trade_no"], order_no.as_str());
+
+    let verification =
+        PaymentService::verify_stored_callback(&app.pool, order_id, false, Uuid::new_v4())
+            .await
+            .unwrap();
+    assert!(verification.valid, "{:?}", verification.issues);
+
+    // A stored callback edited after delivery no longer matches the signed resource
+    let mut tampered = callback.clone();
+    tampered["trade_state"] = serde_json::json!("CLOSED");
+    sqlx::query("UPDATE payment_transactions SET callback_data = ? WHERE order_id = ?")
+        .bind(&tampered)
+        .bind(order_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    let verification =
+        PaymentService::verify_stored_callback(&app.pool, order_id, false, Uuid::new_v4())
+            .await
+            .unwrap();
+    assert!(!verification.valid);
+    assert_eq!(
+        verification.issues,
+        vec!["保存的回调数据与签名报文不一致".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn test_wechat_callback_rejects_undecryptable_or_mismatched_amount() {
+    let mut app = TestApp::new().await;
+    configure_wechat(&app.pool).await;
+    let order_id = create_pending_order(&app.pool, "10.00").await;
+    let order_no = start_payment(&app, order_id, PaymentMethod::Wechat).await;
+    let path = "/api/v1/payment/payment/callback?method=wechat";
+    let now = chrono::Utc::now().timestamp();
+
+    // Correctly signed, but the plaintext fields are not trusted without the encrypted resource
+    let plaintext = serde_json::json!({
+        "out_trade_no": order_no,
+        "transaction_id": format!("4200{}", Uuid::new_v4().simple()),
+        "trade_state": "SUCCESS",
+        "amount": { "total": 1000 },
+    })
+    .to_string();
+    let headers = wechat_callback_headers(&plaintext, now, "TESTPLATFORMSERIAL");
+    let (status, _) = app.post_raw(path, &headers, plaintext).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // A paid amount of one fen does not settle a 10.00 order
+    let underpaid = wechat_notification(
+        "TRANSACTION.SUCCESS",
+        &serde_json::json!({
+            "out_trade_no": order_no,
+            "transaction_id": format!("4200{}", Uuid::new_v4().simple()),
+            "trade_state": "SUCCESS",
+            "amount": { "total": 1 },
+        }),
+    );
+    let headers = wechat_callback_headers(&underpaid, now, "TESTPLATFORMSERIAL");
+    let (status, _) = app.post_raw(path, &headers, underpaid).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Pending);
+}
+
+/// 已支付订单及其成功的支付流水，返回订单ID与下单用户
+async fn create_paid_order(
+    pool: &DbPool,
+    payment_method: &str,
+    external_transaction_id: &str,
+) -> (Uuid, Uuid) {
+    let order_id = create_pending_order(pool, "30.00").await;
+    sqlx::query(
+        "UPDATE payment_orders SET status = 'paid', payment_method = ?, payment_time = NOW() WHERE id = ?",
+    )
+    .bind(payment_method)
+    .bind(order_id.to_string())
+    .execute(pool)
+    .await
+    .unwrap();
+    sqlx::query(
+        r#"
+        INSERT INTO payment_transactions (
+            id, transaction_no, order_id, payment_method, transaction_type, amount,
+            status, external_transaction_id, initiated_at, completed_at
+        ) VALUES (?, ?, ?, ?, 'payment', 30.00, 'success', ?, NOW(), NOW())
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(format!("TXN{}", Uuid::new_v4().simple()))
+    .bind(order_id.to_string())
+    .bind(payment_method)
+    .bind(external_transaction_id)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let order = PaymentService::get_order(pool, order_id).await.unwrap();
+    (order_id, order.user_id)
+}
+
+#[tokio::test]
+async fn test_wechat_refund_completes_on_notification() {
+    let mut app = TestApp::new().await;
+    configure_wechat(&app.pool).await;
+    let (admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (order_id, user_id) = create_paid_order(&app.pool, "wechat", "4200000000000001").await;
+    let refund = PaymentService::create_refund(
+        &app.pool,
+        CreateRefundDto {
+            order_id,
+            refund_amount: Decimal::from_str("12.50").unwrap(),
+            refund_reason: "医生未接诊".to_string(),
+        },
+        user_id,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let gateway = MockGateway::new(vec![(
+        200,
+        serde_json::json!({
+            "refund_id": "50000000382019052709732678859",
+            "out_refund_no": refund.refund_no,
+            "status": "PROCESSING",
+        }),
+    )]);
+    PaymentService::review_refund(
+        &app.pool,
+        &gateway,
+        refund.id,
+        ReviewRefundDto {
+            approved: true,
+            review_notes: None,
+        },
+        admin_id,
+    )
+    .await
+    .unwrap();
+
+    let request = &gateway.requests()[0];
+    assert_eq!(
+        request.url,
+        "https://api.mch.weixin.qq.com/v3/refund/domestic/refunds"
+    );
+    let body: serde_json::Value = serde_json::from_str(request.body.as_deref().unwrap()).unwrap();
+    assert_eq!(body["transaction_id"], "4200000000000001");
+    assert_eq!(body["out_refund_no"], refund.refund_no.as_str());
+    assert_eq!(body["amount"]["refund"], 1250);
+    assert_eq!(body["amount"]["total"], 3000);
+
+    // Accepted but not yet refunded: the order is untouched until WeChat confirms
+    let pending = PaymentService::get_refund(&app.pool, refund.id)
+        .await
+        .unwrap();
+    assert_eq!(pending.status, RefundStatus::Processing);
+    assert_eq!(
+        pending.external_refund_id.as_deref(),
+        Some("50000000382019052709732678859")
+    );
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+
+    let notification = wechat_notification(
+        "REFUND.SUCCESS",
+        &serde_json::json!({
+            "out_trade_no": order.order_no,
+            "out_refund_no": refund.refund_no,
+            "refund_id": "50000000382019052709732678859",
+            "refund_status": "SUCCESS",
+        }),
+    );
+    for _ in 0..2 {
+        let headers = wechat_callback_headers(
+            &notification,
+            chrono::Utc::now().timestamp(),
+            "TESTPLATFORMSERIAL",
+        );
+        let (status, _) = app
+            .post_raw(
+                "/api/v1/payment/refund/callback?method=wechat",
+                &headers,
+                notification.clone(),
+            )
+            .await;
+        assert_eq!(status, StatusCode::OK);
+    }
+
+    let refunded = PaymentService::get_refund(&app.pool, refund.id)
+        .await
+        .unwrap();
+    assert_eq!(refunded.status, RefundStatus::Success);
+    assert!(refunded.completed_at.is_some());
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::PartialRefunded);
+
+    // The repeated notification did not book a second refund transaction
+    let refund_transactions: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM payment_transactions WHERE order_id = ? AND transaction_type = 'refund'",
+    )
+    .bind(order_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(refund_transactions, 1);
+}
+
+#[tokio::test]
+async fn test_failed_gateway_refund_keeps_order_status() {
+    let app = TestApp::new().await;
+    configure_alipay(&app.pool).await;
+    let (admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (order_id, user_id) =
+        create_paid_order(&app.pool, "alipay", "2024012222001400000000000002").await;
+    let refund = PaymentService::create_refund(
+        &app.pool,
+        CreateRefundDto {
+            order_id,
+            refund_amount: Decimal::from_str("30.00").unwrap(),
+            refund_reason: "医生未接诊".to_string(),
+        },
+        user_id,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let gateway = MockGateway::new(vec![(
+        200,
+        serde_json::json!({
+            "alipay_trade_refund_response": {
+                "code": "40004",
+                "msg": "Business Failed",
+                "sub_code": "ACQ.TRADE_NOT_EXIST",
+                "sub_msg": "交易不存在",
+            }
+        }),
+    )]);
+    let result = PaymentService::review_refund(
+        &app.pool,
+        &gateway,
+        refund.id,
+        ReviewRefundDto {
+            approved: true,
+            review_notes: None,
+        },
+        admin_id,
+    )
+    .await;
+    match result {
+        Err(AppError::PaymentGatewayError { code, .. }) => {
+            assert_eq!(code, "ACQ.TRADE_NOT_EXIST")
+        }
+        other => panic!("expected gateway error, got {:?}", other),
+    }
+
+    let request_body = gateway.requests()[0].body.clone().unwrap();
+    assert!(request_body.contains("method=alipay.trade.refund"));
+    assert!(request_body.contains(&format!("out_request_no%22%3A%22{}", refund.refund_no)));
+
+    let failed = PaymentService::get_refund(&app.pool, refund.id)
+        .await
+        .unwrap();
+    assert_eq!(failed.status, RefundStatus::Failed);
+    assert!(failed
+        .failure_reason
+        .as_deref()
+        .unwrap()
+        .contains("交易不存在"));
+
+    let order = PaymentService::get_order(&app.pool, order_id)
+        .await
+        .unwrap();
+    assert_eq!(order.status, OrderStatus::Paid);
+}
+
+async fn login(app: &mut TestApp, account: &str, password: &str) -> String {
+    let (status, body) = app
+        .post(
+            "/api/v1/auth/login",
+            serde_json::json!({ "account": account, "password": password }),
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    body["data"]["token"].as_str().unwrap().to_string()
+}
+
+#[tokio::test]
+async fn test_status_poll_reconciles_order_paid_at_gateway() {
+    let gateway = std::sync::Arc::new(MockGateway::new(Vec::new()));
+    let mut app = TestApp::with_gateway(gateway.clone()).await;
+    configure_wechat(&app.pool).await;
+    let (patient_id, account, password) = create_test_user(&app.pool, "patient").await;
+    let token = login(&mut app, &account, &password).await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+
+    let appointment_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot,
+                                  visit_type, symptoms, has_visited_before, status, created_at, updated_at)
+        VALUES (?, ?, ?, DATE_ADD(NOW(), INTERVAL 1 DAY), '09:00-10:00', 'online_video', '测试症状', false, 'pending', NOW(), NOW())
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let order = PaymentService::create_order(
+        &app.pool,
+        CreateOrderDto {
+            user_id: patient_id,
+            appointment_id: Some(appointment_id),
+            order_type: OrderType::Appointment,
+            amount: Decimal::from_str("45.50").unwrap(),
+            description: None,
+            metadata: None,
+            coupon_code: None,
+            idempotency_key: None,
+        },
+    )
+    .await
+    .unwrap();
+    let order_no = start_payment(&app, order.id, PaymentMethod::Wechat).await;
+
+    // The callback never arrived, but the gateway already captured the payment
+    gateway.push_response(
+        200,
+        serde_json::json!({
+            "appid": "wxtestappid",
+            "mchid": "1900000001",
+            "out_trade_no": order_no,
+            "transaction_id": "4200000000202401220000000001",
+            "trade_state": "SUCCESS",
+            "success_time": "2024-01-22T10:00:00+08:00",
+            "amount": { "total": 4550, "currency": "CNY" },
+        }),
+    );
+
+    let status_path = format!("/api/v1/payment/orders/{}/status", order_no);
+    let (status, body) = app.get_with_auth(&status_path, &token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "paid");
+    assert_eq!(body["data"]["payment_method"], "wechat");
+    assert_eq!(body["data"]["gateway_checked"], true);
+
+    let requests = gateway.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].method, reqwest::Method::GET);
+    assert_eq!(
+        requests[0].url,
+        format!(
+            "{}/v3/pay/transactions/out-trade-no/{}?mchid=1900000001",
+            WechatPayService::API_BASE,
+            order_no
+        )
+    );
+
+    let transaction: (String, Option<String>) = sqlx::query_as(
+        "SELECT status, external_transaction_id FROM payment_transactions WHERE order_id = ?",
+    )
+    .bind(order.id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(transaction.0, "success");
+    assert_eq!(
+        transaction.1.as_deref(),
+        Some("4200000000202401220000000001")
+    );
+
+    let appointment_status: String =
+        sqlx::query_scalar("SELECT status FROM appointments WHERE id = ?")
+            .bind(appointment_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(appointment_status, "confirmed");
+
+    // Settled orders answer from the database without another gateway query
+    let (status, body) = app.get_with_auth(&status_path, &token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "paid");
+    assert_eq!(body["data"]["gateway_checked"], false);
+    assert_eq!(gateway.requests().len(), 1);
+
+    // Only the order owner may poll it
+    let (_, other_account, other_password) = create_test_user(&app.pool, "patient").await;
+    let other_token = login(&mut app, &other_account, &other_password).await;
+    let (status, _) = app.get_with_auth(&status_path, &other_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_status_poll_leaves_unpaid_orders_pending() {
+    let gateway = std::sync::Arc::new(MockGateway::new(vec![(
+        200,
+        serde_json::json!({
+            "alipay_trade_query_response": {
+                "code": "10000",
+                "msg": "Success",
+                "trade_no": "2024012222001400000000000002",
+                "trade_status": "WAIT_BUYER_PAY",
+                "total_amount": "0.01",
+            }
+        }),
+    )]));
+    let app = TestApp::with_gateway(gateway.clone()).await;
+    configure_alipay(&app.pool).await;
+
+    let order_id = create_pending_order(&app.pool, "0.01").await;
+    let order_no = start_payment(&app, order_id, PaymentMethod::Alipay).await;
+
+    let status = PaymentService::query_order_status(&app.pool, gateway.as_ref(), &order_no)
+        .await
+        .unwrap();
+    assert_eq!(status.status, OrderStatus::Pending);
+    assert!(status.gateway_checked);
+    let request_body = gateway.requests()[0].body.clone().unwrap();
+    assert!(request_body.contains("method=alipay.trade.query"));
+
+    // An order without a gateway transaction is never sent to a gateway
+    let unpaid_id = create_pending_order(&app.pool, "0.01").await;
+    let unpaid = PaymentService::get_order(&app.pool, unpaid_id)
+        .await
+        .unwrap();
+    let status = PaymentService::query_order_status(&app.pool, gateway.as_ref(), &unpaid.order_no)
+        .await
+        .unwrap();
+    assert_eq!(status.status, OrderStatus::Pending);
+    assert!(!status.gateway_checked);
+    assert_eq!(gateway.requests().len(), 1);
+}
+/// 插入一笔微信支付订单及其支付流水，`completed_at` 为 UTC 时间
+async fn seed_wechat_payment(
+    pool: &DbPool,
+    user_id: Uuid,
+    amount: &str,
+    status: &str,
+    completed_at: &str,
+) -> String {
+    let order_id = Uuid::new_v4();
+    let order_no = format!("ORD{}", Uuid::new_v4().simple());
+    let order_status = if status == "success" {
+        "paid"
+    } else {
+        "pending"
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO payment_orders (
+            id, order_no, user_id, order_type, amount, currency, status,
+            expire_time, created_at, updated_at
+        ) VALUES (?, ?, ?, 'consultation', ?, 'CNY', ?, DATE_ADD(NOW(), INTERVAL 2 HOUR), NOW(), NOW())
+        "#,
+    )
+    .bind(order_id.to_string())
+    .bind(&order_no)
+    .bind(user_id.to_string())
+    .bind(Decimal::from_str(amount).unwrap())
+    .bind(order_status)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    sqlx::query(
+        r#"
+        INSERT INTO payment_transactions (
+            id, transaction_no, order_id, payment_method, transaction_type,
+            amount, status, external_transaction_id, initiated_at, completed_at
+        ) VALUES (?, ?, ?, 'wechat', 'payment', ?, ?, ?, ?, ?)
+        "#,
+    )
+    .bind(Uuid::new_v4().to_string())
+    .bind(format!("TXN{}", Uuid::new_v4().simple()))
+    .bind(order_id.to_string())
+    .bind(Decimal::from_str(amount).unwrap())
+    .bind(status)
+    .bind(format!("42{}", Uuid::new_v4().simple()))
+    .bind(completed_at)
+    .bind(if status == "success" {
+        Some(completed_at)
+    } else {
+        None
+    })
+    .execute(pool)
+    .await
+    .unwrap();
+
+    order_no
+}
+
+fn wechat_bill(rows: &[(&str, &str)]) -> String {
+    let mut bill = String::from("交易时间,公众账号ID,商户号,特约商户号,设备号,微信订单号,商户订单号,用户标识,交易类型,交易状态,付款银行,货币种类,应结订单金额,代金券金额,商品名称,商户数据包,手续费,费率,订单金额,费率备注\n");
+    for (index, (order_no, amount)) in rows.iter().enumerate() {
+        bill.push_str(&format!(
+            "`2020-02-03 10:00:00,`wxtestappid,`1900000001,`0,`,`42000000{:02},`{},`oUser,`NATIVE,`SUCCESS,`OTHERS,`CNY,`{},`0.00,`在线问诊,`,`0.00000,`0.60%,`{},`\n",
+            index, order_no, amount, amount
+        ));
+    }
+    bill.push_str("总交易单数,应结订单总金额,退款总金额,充值券退款总金额,手续费总金额,订单总金额,申请退款总金额\n");
+    bill
+}
+
+#[tokio::test]
+async fn test_reconciliation_report_lists_discrepancies() {
+    let gateway = std::sync::Arc::new(MockGateway::new(Vec::new()));
+    let mut app = TestApp::with_gateway(gateway.clone()).await;
+    configure_wechat(&app.pool).await;
+    let (user_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (_, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = login(&mut app, &admin_account, &admin_password).await;
+    let (_, patient_account, patient_password) = create_test_user(&app.pool, "patient").await;
+    let patient_token = login(&mut app, &patient_account, &patient_password).await;
+    let date = chrono::NaiveDate::from_ymd_opt(2020, 2, 3).unwrap();
+
+    // 10:00 in Beijing on the bill date; the last one falls on the next Beijing day
+    let matched = seed_wechat_payment(
+        &app.pool,
+        user_id,
+        "30.00",
+        "success",
+        "2020-02-03 02:00:00",
+    )
+    .await;
+    let mismatched = seed_wechat_payment(
+        &app.pool,
+        user_id,
+        "45.50",
+        "success",
+        "2020-02-03 02:00:00",
+    )
+    .await;
+    let unconfirmed = seed_wechat_payment(
+        &app.pool,
+        user_id,
+        "20.00",
+        "pending",
+        "2020-02-03 02:00:00",
+    )
+    .await;
+    let unbilled = seed_wechat_payment(
+        &app.pool,
+        user_id,
+        "12.00",
+        "success",
+        "2020-02-03 03:00:00",
+    )
+    .await;
+    seed_wechat_payment(&app.pool, user_id, "8.00", "success", "2020-02-03 16:30:00").await;
+
+    gateway.push_response(
+        200,
+        serde_json::json!({
+            "hash_type": "SHA1",
+            "hash_value": "79bb0f45fc4c42234a918000b2668d689e2bde04",
+            "download_url": "https://api.mch.weixin.qq.com/v3/billdownload/file?token=6XIv5TUPto7pByrTQKhd6kwvyKLG2uY2wMMR8cNXqaA_Cv_isgaUtBzp4QtiozLO",
+        }),
+    );
+    gateway.push_text_response(
+        200,
+        &wechat_bill(&[
+            (matched.as_str(), "30.00"),
+            (mismatched.as_str(), "40.50"),
+            (unconfirmed.as_str(), "20.00"),
+        ]),
+    );
+
+    let report = PaymentService::run_reconciliation(
+        &app.pool,
+        gateway.as_ref(),
+        RunReconciliationDto {
+            date,
+            wechat_bill: None,
+            // Supplied directly so the test does not depend on the Alipay configuration
+            alipay_bill: Some(String::new()),
+        },
+    )
+    .await
+    .unwrap();
+
+    let requests = gateway.requests();
+    assert_eq!(
+        requests[0].url,
+        format!(
+            "{}/v3/bill/tradebill?bill_date=2020-02-03&bill_type=SUCCESS",
+            WechatPayService::API_BASE
+        )
+    );
+    assert!(requests[1].url.ends_with("/v3/billdownload/file?token=6XIv5TUPto7pByrTQKhd6kwvyKLG2uY2wMMR8cNXqaA_Cv_isgaUtBzp4QtiozLO"));
+    assert!(header(&requests[1], "Authorization").starts_with("WECHATPAY2-SHA256-RSA2048"));
+
+    assert_eq!(report.status, ReconciliationStatus::Mismatched);
+    assert_eq!(report.local_count, 3);
+    assert_eq!(report.local_amount, Decimal::from_str("87.50").unwrap());
+    assert_eq!(report.remote_count, 3);
+    assert_eq!(report.remote_amount, Decimal::from_str("90.50").unwrap());
+    assert_eq!(report.missing_local.len(), 1);
+    assert_eq!(report.missing_local[0].order_no, unconfirmed);
+    assert_eq!(report.missing_remote.len(), 1);
+    assert_eq!(report.missing_remote[0].order_no, unbilled);
+    assert_eq!(report.amount_mismatches.len(), 1);
+    assert_eq!(report.amount_mismatches[0].order_no, mismatched);
+    assert_eq!(report.discrepancy_count(), 3);
+
+    // Only admins can read the report
+    let path = "/api/v1/payment/reconciliation?date=2020-02-03";
+    let (status, _) = app.get_with_auth(path, &patient_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, body) = app.get_with_auth(path, &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "mismatched");
+    assert_eq!(
+        body["data"]["missing_local"][0]["order_no"],
+        unconfirmed.as_str()
+    );
+
+    // Re-running the day with a corrected bill replaces the report
+    let corrected_bill = wechat_bill(&[
+        (matched.as_str(), "30.00"),
+        (mismatched.as_str(), "45.50"),
+        (unbilled.as_str(), "12.00"),
+    ]);
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/payment/reconciliation/run",
+            serde_json::json!({
+                "date": "2020-02-03",
+                "wechat_bill": corrected_bill,
+                "alipay_bill": "",
+            }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["status"], "matched");
+    assert_eq!(body["data"]["id"], report.id.to_string());
+    assert_eq!(gateway.requests().len(), 2);
+
+    let count: i64 =
+        sqlx::query_scalar("SELECT COUNT(*) FROM reconciliation_reports WHERE bill_date = ?")
+            .bind(date)
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(count, 1);
+
+    // Days that have not ended cannot be reconciled
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/payment/reconciliation/run",
+            serde_json::json!({ "date": chrono::Utc::now().date_naive() + chrono::Duration::days(1) }),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
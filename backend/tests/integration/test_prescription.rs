@@ -31,6 +31,7 @@ async fn test_create_prescription() {
     let prescription_dto = CreatePrescriptionDto {
         doctor_id,
         patient_id: patient_user_id,
+        patient_profile_id: None,
         patient_name: "测试患者".to_string(),
         diagnosis: "风寒感冒".to_string(),
         medicines: vec![
@@ -83,6 +84,7 @@ async fn test_list_prescriptions() {
         let prescription_dto = CreatePrescriptionDto {
             doctor_id,
             patient_id: patient_user_id,
+            patient_profile_id: None,
             patient_name: format!("患者{}", i + 1),
             diagnosis: format!("诊断{}", i + 1),
             medicines: vec![Medicine {
@@ -129,6 +131,7 @@ async fn test_get_prescription_by_id() {
     let prescription_dto = CreatePrescriptionDto {
         doctor_id,
         patient_id: patient_user_id,
+        patient_profile_id: None,
         patient_name: "测试患者".to_string(),
         diagnosis: "测试诊断".to_string(),
         medicines: vec![Medicine {
@@ -190,6 +193,7 @@ async fn test_get_prescription_by_code() {
     let prescription_dto = CreatePrescriptionDto {
         doctor_id,
         patient_id: patient_user_id,
+        patient_profile_id: None,
         patient_name: "测试患者".to_string(),
         diagnosis: "测试诊断".to_string(),
         medicines: vec![Medicine {
@@ -238,6 +242,7 @@ async fn test_get_doctor_prescriptions() {
         let prescription_dto = CreatePrescriptionDto {
             doctor_id,
             patient_id: patient_user_id,
+            patient_profile_id: None,
             patient_name: format!("患者{}", i + 1),
             diagnosis: format!("诊断{}", i + 1),
             medicines: vec![Medicine {
@@ -291,6 +296,7 @@ async fn test_get_patient_prescriptions() {
         let prescription_dto = CreatePrescriptionDto {
             doctor_id,
             patient_id: patient_user_id,
+            patient_profile_id: None,
             patient_name: "测试患者".to_string(),
             diagnosis: format!("诊断{}", i + 1),
             medicines: vec![Medicine {
@@ -346,6 +352,7 @@ async fn test_prescription_authorization() {
     let prescription_dto = CreatePrescriptionDto {
         doctor_id: doctor1_id,
         patient_id: patient_user_id,
+        patient_profile_id: None,
         patient_name: "测试患者".to_string(),
         diagnosis: "测试诊断".to_string(),
         medicines: vec![Medicine {
@@ -392,6 +399,7 @@ async fn test_patient_cannot_create_prescription() {
     let prescription_dto = CreatePrescriptionDto {
         doctor_id,
         patient_id: patient_user_id,
+        patient_profile_id: None,
         patient_name: "测试患者".to_string(),
         diagnosis: "测试诊断".to_string(),
         medicines: vec![Medicine {
@@ -428,6 +436,7 @@ async fn test_prescription_with_multiple_medicines() {
     let prescription_dto = CreatePrescriptionDto {
         doctor_id,
         patient_id: patient_user_id,
+        patient_profile_id: None,
         patient_name: "复方测试患者".to_string(),
         diagnosis: "肝肾阴虚，虚火上炎".to_string(),
         medicines: vec![
@@ -491,6 +500,7 @@ async fn test_prescription_code_uniqueness() {
         let prescription_dto = CreatePrescriptionDto {
             doctor_id,
             patient_id: patient_user_id,
+            patient_profile_id: None,
             patient_name: format!("患者{}", i + 1),
             diagnosis: "测试诊断".to_string(),
             medicines: vec![Medicine {
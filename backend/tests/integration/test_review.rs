@@ -368,3 +368,418 @@ async fn test_admin_manage_review_visibility() {
 
     assert_eq!(status, StatusCode::OK);
 }
+
+#[tokio::test]
+async fn test_review_banned_keywords_are_masked() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_token) =
+        create_test_user_with_token(&mut app, "patient_filter", UserRole::Patient).await;
+    let (doctor_user_id, _doctor_token) =
+        create_test_user_with_token(&mut app, "doctor_filter", UserRole::Doctor).await;
+    let doctor_id = create_doctor_profile(&mut app, doctor_user_id).await;
+
+    let appointment_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot, symptoms, status)
+        VALUES (?, ?, ?, DATE_ADD(NOW(), INTERVAL 1 DAY), 'morning', '测试症状', 'completed')
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    // Reviews are configured to mask banned keywords rather than reject them
+    let create_review = json!({
+        "appointment_id": appointment_id,
+        "rating": 2,
+        "attitude_rating": 2,
+        "professionalism_rating": 2,
+        "efficiency_rating": 2,
+        "comment": "隔壁诊所涉嫌诈骗，这里还不错",
+        "is_anonymous": false
+    });
+
+    let (status, body) = app
+        .post_with_auth("/api/v1/reviews", create_review, &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(
+        body["data"]["comment"].as_str().unwrap(),
+        "隔壁诊所涉嫌**，这里还不错"
+    );
+
+    let stored: String =
+        sqlx::query_scalar("SELECT comment FROM patient_reviews WHERE appointment_id = ?")
+            .bind(appointment_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert_eq!(stored, "隔壁诊所涉嫌**，这里还不错");
+}
+
+#[tokio::test]
+async fn test_patient_delete_review_within_window_updates_statistics() {
+    let mut app = TestApp::new().await;
+
+    let (_admin_id, admin_token) =
+        create_test_user_with_token(&mut app, "admin_del", UserRole::Admin).await;
+    let (patient_id, patient_token) =
+        create_test_user_with_token(&mut app, "patient_del", UserRole::Patient).await;
+    let (doctor_user_id, _doctor_token) =
+        create_test_user_with_token(&mut app, "doctor_del", UserRole::Doctor).await;
+    let doctor_id = create_doctor_profile(&mut app, doctor_user_id).await;
+
+    // 两个已完成的预约，各写一条评价
+    let mut review_ids = vec![];
+    for rating in [5, 1] {
+        let appointment_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot, symptoms, status)
+            VALUES (?, ?, ?, DATE_ADD(NOW(), INTERVAL 1 DAY), 'morning', '测试症状', 'completed')
+            "#,
+        )
+        .bind(appointment_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(doctor_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+        let create_review = json!({
+            "appointment_id": appointment_id,
+            "rating": rating,
+            "attitude_rating": rating,
+            "professionalism_rating": rating,
+            "efficiency_rating": rating
+        });
+        let (status, body) = app
+            .post_with_auth("/api/v1/reviews", create_review, &patient_token)
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        review_ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+
+    let stats_path = format!("/api/v1/reviews/doctor/{}/statistics", doctor_id);
+    let (_, body) = app.get(&stats_path).await;
+    assert_eq!(body["data"]["total_reviews"].as_i64().unwrap(), 2);
+
+    // 24小时内可以删除差评，统计随之更新
+    let (status, _body) = app
+        .delete_with_auth(
+            &format!("/api/v1/reviews/{}", review_ids[1]),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = app.get(&stats_path).await;
+    assert_eq!(body["data"]["total_reviews"].as_i64().unwrap(), 1);
+    assert_eq!(body["data"]["average_rating"].as_f64().unwrap(), 5.0);
+
+    let (status, _body) = app
+        .get_with_auth(
+            &format!("/api/v1/reviews/{}", review_ids[1]),
+            &patient_token,
+        )
+        .await;
+    assert_ne!(status, StatusCode::OK);
+
+    // 超过24小时后患者不能再删除
+    sqlx::query(
+        "UPDATE patient_reviews SET created_at = DATE_SUB(NOW(), INTERVAL 25 HOUR) WHERE id = ?",
+    )
+    .bind(&review_ids[0])
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let (status, body) = app
+        .delete_with_auth(
+            &format!("/api/v1/reviews/{}", review_ids[0]),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body["message"].as_str().unwrap().contains("24 hours"));
+
+    let (_, body) = app.get(&stats_path).await;
+    assert_eq!(body["data"]["total_reviews"].as_i64().unwrap(), 1);
+
+    // 管理员不受时限限制
+    let (status, _body) = app
+        .delete_with_auth(&format!("/api/v1/reviews/{}", review_ids[0]), &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (_, body) = app.get(&stats_path).await;
+    assert_eq!(body["data"]["total_reviews"].as_i64().unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_doctor_unreplied_reviews_queue() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_token) =
+        create_test_user_with_token(&mut app, "patient_unreplied", UserRole::Patient).await;
+    let (doctor_user_id, doctor_token) =
+        create_test_user_with_token(&mut app, "doctor_unreplied", UserRole::Doctor).await;
+    let doctor_id = create_doctor_profile(&mut app, doctor_user_id).await;
+
+    // 三条评价，分别写于 3 天前、1 天前、2 天前
+    let mut review_ids = vec![];
+    for (rating, days_ago) in [(4, 3), (2, 1), (5, 2)] {
+        let appointment_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot, symptoms, status)
+            VALUES (?, ?, ?, DATE_ADD(NOW(), INTERVAL 1 DAY), 'morning', '测试症状', 'completed')
+            "#,
+        )
+        .bind(appointment_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(doctor_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+        let create_review = json!({
+            "appointment_id": appointment_id,
+            "rating": rating,
+            "attitude_rating": rating,
+            "professionalism_rating": rating,
+            "efficiency_rating": rating
+        });
+        let (status, body) = app
+            .post_with_auth("/api/v1/reviews", create_review, &patient_token)
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        let review_id = body["data"]["id"].as_str().unwrap().to_string();
+
+        sqlx::query(
+            "UPDATE patient_reviews SET created_at = DATE_SUB(NOW(), INTERVAL ? DAY) WHERE id = ?",
+        )
+        .bind(days_ago)
+        .bind(&review_id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+        review_ids.push(review_id);
+    }
+
+    // 已回复的评价不再出现在队列中
+    let (status, _) = app
+        .post_with_auth(
+            &format!("/api/v1/reviews/{}/reply", review_ids[2]),
+            json!({ "reply": "感谢您的认可" }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .get_with_auth("/api/v1/reviews/doctor/unreplied", &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["pagination"]["total"].as_i64().unwrap(), 2);
+    let ids: Vec<&str> = body["data"]["reviews"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|r| r["id"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec![review_ids[0].as_str(), review_ids[1].as_str()]);
+
+    let (_, body) = app
+        .get_with_auth(
+            "/api/v1/reviews/doctor/unreplied?sort_by=lowest_rating",
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(body["data"]["reviews"][0]["id"], review_ids[1]);
+
+    let (status, _) = app
+        .get_with_auth("/api/v1/reviews/doctor/unreplied", &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_export_doctor_reviews_csv() {
+    let mut app = TestApp::new().await;
+
+    let (named_patient_id, named_patient_token) =
+        create_test_user_with_token(&mut app, "patient_export", UserRole::Patient).await;
+    let (anonymous_patient_id, anonymous_patient_token) =
+        create_test_user_with_token(&mut app, "patient_export_anon", UserRole::Patient).await;
+    let (doctor_user_id, doctor_token) =
+        create_test_user_with_token(&mut app, "doctor_export", UserRole::Doctor).await;
+    let doctor_id = create_doctor_profile(&mut app, doctor_user_id).await;
+    let (other_doctor_user_id, other_doctor_token) =
+        create_test_user_with_token(&mut app, "doctor_export_other", UserRole::Doctor).await;
+    create_doctor_profile(&mut app, other_doctor_user_id).await;
+    let (_, admin_token) =
+        create_test_user_with_token(&mut app, "admin_export", UserRole::Admin).await;
+
+    for (patient_id, patient_token, is_anonymous, comment) in [
+        (
+            named_patient_id,
+            &named_patient_token,
+            false,
+            "医生很耐心, 讲解清楚",
+        ),
+        (
+            anonymous_patient_id,
+            &anonymous_patient_token,
+            true,
+            "效果不错",
+        ),
+    ] {
+        let appointment_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot, symptoms, status)
+            VALUES (?, ?, ?, DATE_ADD(NOW(), INTERVAL 1 DAY), 'morning', '测试症状', 'completed')
+            "#,
+        )
+        .bind(appointment_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(doctor_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+        let create_review = json!({
+            "appointment_id": appointment_id,
+            "rating": 5,
+            "attitude_rating": 5,
+            "professionalism_rating": 4,
+            "efficiency_rating": 3,
+            "comment": comment,
+            "is_anonymous": is_anonymous
+        });
+        let (status, _) = app
+            .post_with_auth("/api/v1/reviews", create_review, patient_token)
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+    }
+
+    let export_path = format!("/api/v1/reviews/doctor/{}/export", doctor_id);
+
+    let (status, csv) = app.get_text_with_auth(&export_path, &doctor_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines[0],
+        "Date,Patient,Rating,Attitude Rating,Professionalism Rating,Efficiency Rating,Comment,Tags,Reply,Reply At"
+    );
+    assert_eq!(lines.len(), 3);
+
+    // 匿名评价隐藏患者姓名，含逗号的评论加引号
+    assert!(csv.contains("测试patient_export,"));
+    assert!(csv.contains(",匿名用户,5,5,4,3,效果不错,"));
+    assert!(!csv.contains("测试patient_export_anon"));
+    assert!(csv.contains("\"医生很耐心, 讲解清楚\""));
+
+    let (status, _) = app.get_text_with_auth(&export_path, &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .get_text_with_auth(&export_path, &other_doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = app
+        .get_text_with_auth(&export_path, &named_patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+async fn set_reply_lock(app: &TestApp, enabled: bool) {
+    sqlx::query(
+        "UPDATE system_configs SET config_value = ? WHERE category = 'review' AND config_key = 'lock_after_reply'",
+    )
+    .bind(if enabled { "true" } else { "false" })
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_review_locked_after_doctor_reply() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_token) =
+        create_test_user_with_token(&mut app, "patient_reply_lock", UserRole::Patient).await;
+    let (doctor_user_id, doctor_token) =
+        create_test_user_with_token(&mut app, "doctor_reply_lock", UserRole::Doctor).await;
+    let doctor_id = create_doctor_profile(&mut app, doctor_user_id).await;
+
+    let appointment_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (id, patient_id, doctor_id, appointment_date, time_slot, symptoms, status)
+        VALUES (?, ?, ?, DATE_ADD(NOW(), INTERVAL 1 DAY), 'morning', '测试症状', 'completed')
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let create_review = json!({
+        "appointment_id": appointment_id,
+        "rating": 5,
+        "attitude_rating": 5,
+        "professionalism_rating": 5,
+        "efficiency_rating": 5,
+        "comment": "非常满意"
+    });
+    let (status, body) = app
+        .post_with_auth("/api/v1/reviews", create_review, &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let review_path = format!("/api/v1/reviews/{}", body["data"]["id"].as_str().unwrap());
+
+    // 回复前可以正常修改
+    let (status, _) = app
+        .put_with_auth(&review_path, json!({ "rating": 4 }), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .post_with_auth(
+            &format!("{}/reply", review_path),
+            json!({ "reply": "感谢您的认可" }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    set_reply_lock(&app, true).await;
+    let (locked_status, locked_body) = app
+        .put_with_auth(&review_path, json!({ "rating": 1 }), &patient_token)
+        .await;
+
+    set_reply_lock(&app, false).await;
+    let (unlocked_status, unlocked_body) = app
+        .put_with_auth(&review_path, json!({ "rating": 2 }), &patient_token)
+        .await;
+    set_reply_lock(&app, true).await;
+
+    assert_eq!(locked_status, StatusCode::BAD_REQUEST);
+    assert_eq!(
+        locked_body["message"],
+        "Reviews cannot be updated after the doctor has replied"
+    );
+    assert_eq!(unlocked_status, StatusCode::OK);
+    assert_eq!(unlocked_body["data"]["rating"], 2);
+}
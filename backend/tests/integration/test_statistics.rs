@@ -352,3 +352,90 @@ async fn test_user_growth_statistics() {
     assert!(body["data"].is_array());
     assert!(!body["data"].as_array().unwrap().is_empty());
 }
+
+#[tokio::test]
+async fn test_audit_logs_filters_and_pagination() {
+    let mut app = TestApp::new().await;
+
+    let (admin_id, admin_account, admin_password) = create_test_user(&app.pool, "admin").await;
+    let admin_token = get_auth_token(&mut app, &admin_account, &admin_password).await;
+    let (other_admin_id, _, _) = create_test_user(&app.pool, "admin").await;
+    let (_, patient_account, patient_password) = create_test_user(&app.pool, "patient").await;
+    let patient_token = get_auth_token(&mut app, &patient_account, &patient_password).await;
+
+    // Three recent refund retries and an old one by the admin, plus one action by someone else
+    let entries = vec![
+        (admin_id, "refund.retry", Duration::minutes(1)),
+        (admin_id, "refund.retry", Duration::minutes(2)),
+        (admin_id, "refund.retry", Duration::minutes(3)),
+        (admin_id, "refund.retry", Duration::days(10)),
+        (admin_id, "order.adjust_status", Duration::minutes(4)),
+        (other_admin_id, "refund.retry", Duration::minutes(5)),
+    ];
+    for (actor_id, action, age) in entries {
+        sqlx::query(
+            r#"
+            INSERT INTO audit_logs (id, actor_id, action, target_type, target_id, details, created_at)
+            VALUES (?, ?, ?, 'payment_order', ?, NULL, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(actor_id.to_string())
+        .bind(action)
+        .bind(Uuid::new_v4().to_string())
+        .bind(chrono::Utc::now() - age)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    let (status, _) = app
+        .get_with_auth("/api/v1/statistics/audit-logs", &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Actor filter
+    let (status, body) = app
+        .get_with_auth(
+            &format!("/api/v1/statistics/audit-logs?actor_id={}", admin_id),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["total"], 5);
+
+    // Actor + action filter, paged two at a time
+    let path = format!(
+        "/api/v1/statistics/audit-logs?actor_id={}&action=refund.retry&page_size=2",
+        admin_id
+    );
+    let (_, body) = app.get_with_auth(&path, &admin_token).await;
+    assert_eq!(body["data"]["total"], 4);
+    assert_eq!(body["data"]["page_size"], 2);
+    assert_eq!(body["data"]["logs"].as_array().unwrap().len(), 2);
+    assert!(body["data"]["logs"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .all(|log| log["action"] == "refund.retry" && log["actor_id"] == admin_id.to_string()));
+
+    let (_, body) = app
+        .get_with_auth(&format!("{}&page=2", path), &admin_token)
+        .await;
+    assert_eq!(body["data"]["total"], 4);
+    assert_eq!(body["data"]["logs"].as_array().unwrap().len(), 2);
+
+    // Date filter drops the old entry
+    let since = (chrono::Utc::now() - Duration::days(1)).format("%Y-%m-%dT%H:%M:%SZ");
+    let (_, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/statistics/audit-logs?actor_id={}&action=refund.retry&start_date={}",
+                admin_id, since
+            ),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(body["data"]["total"], 3);
+    assert_eq!(body["data"]["logs"].as_array().unwrap().len(), 3);
+}
@@ -0,0 +1,43 @@
+use crate::common::TestApp;
+use axum::http::StatusCode;
+
+async fn set_encrypted(app: &TestApp, category: &str, key: &str, encrypted: bool) {
+    sqlx::query("UPDATE system_configs SET is_encrypted = ? WHERE category = ? AND config_key = ?")
+        .bind(encrypted)
+        .bind(category)
+        .bind(key)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_public_config_excludes_secret_configs() {
+    let mut app = TestApp::new().await;
+
+    // 临时将一个白名单内的配置标记为加密，验证加密配置永远不会被公开
+    set_encrypted(&app, "video_call", "video_codec", true).await;
+    let (status, body) = app.get("/api/v1/config/public").await;
+    set_encrypted(&app, "video_call", "video_codec", false).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let data = &body["data"];
+
+    // 公开子集可匿名读取
+    assert!(data["file_upload"]["max_image_size"].is_number());
+    assert!(data["file_upload"]["allowed_image_types"].is_string());
+    // 上传校验使用的通用限制同样公开，客户端可提前校验
+    assert!(data["file_upload"]["max_file_size"].is_number());
+    assert!(data["file_upload"]["allowed_mime_types"].is_array());
+    assert!(data["video_call"]["max_duration"].is_number());
+    assert!(data["refund"]["window_days"].is_number());
+
+    // 加密配置与非白名单配置不会出现
+    assert!(data["video_call"].get("video_codec").is_none());
+    assert!(data["video_call"].get("ice_servers").is_none());
+    assert!(data.get("content_filter").is_none());
+
+    // 恢复后重新出现在公开配置中
+    let (_, body) = app.get("/api/v1/config/public").await;
+    assert!(body["data"]["video_call"]["video_codec"].is_string());
+}
@@ -1,5 +1,7 @@
 use crate::common::TestApp;
 use axum::http::StatusCode;
+use backend::services::video_consultation_service::VideoConsultationService;
+use backend::utils::jwt;
 use backend::utils::test_helpers::{create_test_doctor, create_test_user};
 use chrono::{Duration, Utc};
 //use serial_test::serial;
@@ -624,6 +626,14 @@ async fn test_webrtc_signaling() {
 
     // Doctor sends signal
     let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let room_token = jwt::create_room_token(
+        consultation_id,
+        doctor_user_id,
+        "doctor".to_string(),
+        &app.config.jwt_secret,
+        3600,
+    )
+    .unwrap();
 
     let signal_dto = json!({
         "room_id": room_id,
@@ -631,7 +641,8 @@ async fn test_webrtc_signaling() {
         "signal_type": "offer",
         "payload": {
             "sdp": "v=0\r\no=- 123456789 2 IN IP4 127.0.0.1\r\ns=-\r\nt=0 0\r\n..."
-        }
+        },
+        "token": room_token,
     });
 
     let (status, body) = app
@@ -951,3 +962,2366 @@ async fn test_authorization() {
 
     assert_eq!(status, StatusCode::FORBIDDEN);
 }
+
+#[tokio::test]
+//#[serial]
+async fn test_consultation_reminder_sent_once() {
+    use backend::services::video_consultation_service::VideoConsultationService;
+    use backend::services::websocket_service::WebSocketManager;
+
+    let app = TestApp::new().await;
+
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+
+    let appointment_id = uuid::Uuid::new_v4();
+    let now = Utc::now();
+    let scheduled_time = now + Duration::minutes(5);
+
+    let appointment_query = r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+    "#;
+
+    sqlx::query(appointment_query)
+        .bind(appointment_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(doctor_id.to_string())
+        .bind(scheduled_time.naive_utc())
+        .bind("09:00-10:00")
+        .bind("test symptoms")
+        .bind(now)
+        .bind(now)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    // Waiting consultation starting within the reminder window
+    let consultation_id = uuid::Uuid::new_v4();
+    let room_id = format!("room_{}", uuid::Uuid::new_v4().to_string().replace("-", ""));
+
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'waiting', ?, ?, ?)
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(&room_id)
+    .bind(scheduled_time)
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let ws_manager = WebSocketManager::new();
+    let mut rx = ws_manager
+        .add_connection(patient_id, "patient".to_string())
+        .await;
+
+    // Run the sweeper twice; the second run must not send a duplicate
+    VideoConsultationService::send_consultation_reminders(&app.pool, &ws_manager)
+        .await
+        .unwrap();
+    VideoConsultationService::send_consultation_reminders(&app.pool, &ws_manager)
+        .await
+        .unwrap();
+
+    let reminder_count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = ? AND related_id = ?",
+    )
+    .bind(patient_id.to_string())
+    .bind(consultation_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(reminder_count, 1);
+
+    let reminded: bool =
+        sqlx::query_scalar("SELECT reminded FROM video_consultations WHERE id = ?")
+            .bind(consultation_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert!(reminded);
+
+    // Exactly one WebSocket push was delivered
+    assert!(rx.try_recv().is_ok());
+    assert!(rx.try_recv().is_err());
+}
+
+#[tokio::test]
+//#[serial]
+async fn test_refer_consultation_to_offline() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, _patient_email, _patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+
+    let appointment_id = Uuid::new_v4();
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(now.naive_utc())
+    .bind("09:00-10:00")
+    .bind("test symptoms")
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let consultation_id = Uuid::new_v4();
+    let room_id = format!("room_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'waiting', ?, ?, ?)
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(&room_id)
+    .bind(now)
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let refer_path = format!(
+        "/api/v1/video-consultations/{}/refer-offline",
+        consultation_id
+    );
+    let referral = json!({ "notes": "需要到院进行脉诊及舌诊" });
+
+    // A consultation that has not started cannot be referred
+    let (status, _) = app
+        .post_with_auth(&refer_path, referral.clone(), &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = app
+        .put_with_auth(
+            &format!("/api/v1/video-consultations/{}/start", consultation_id),
+            json!({}),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, body) = app
+        .post_with_auth(&refer_path, referral.clone(), &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["status"], "pending");
+    assert_eq!(body["data"]["visit_type"], "offline");
+    assert_eq!(
+        body["data"]["referred_from_consultation_id"],
+        consultation_id.to_string()
+    );
+    assert_eq!(body["data"]["referral_notes"], "需要到院进行脉诊及舌诊");
+
+    let referred_id = body["data"]["id"].as_str().unwrap().to_string();
+
+    let (referred_status, visit_type, patient): (String, String, String) = sqlx::query_as(
+        "SELECT status, visit_type, patient_id FROM appointments WHERE referred_from_consultation_id = ?",
+    )
+    .bind(consultation_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(referred_status, "pending");
+    assert_eq!(visit_type, "offline");
+    assert_eq!(patient, patient_id.to_string());
+
+    // The patient is notified about the referral
+    let notification_count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM notifications WHERE user_id = ? AND related_id = ?",
+    )
+    .bind(patient_id.to_string())
+    .bind(&referred_id)
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(notification_count, 1);
+
+    // A consultation can only be referred once
+    let (status, _) = app
+        .post_with_auth(&refer_path, referral, &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+//#[serial]
+async fn test_doctor_feedback_about_patient_is_private() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (other_user_id, other_email, other_password) = create_test_user(&app.pool, "doctor").await;
+    create_test_doctor(&app.pool, other_user_id).await;
+
+    let appointment_id = Uuid::new_v4();
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'completed', ?, ?)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(now.naive_utc())
+    .bind("09:00-10:00")
+    .bind("test symptoms")
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let consultation_id = Uuid::new_v4();
+    let room_id = format!("room_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'completed', ?, ?, ?)
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(&room_id)
+    .bind(now)
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let other_token = get_auth_token(&mut app, &other_email, &other_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+
+    let feedback_path = format!(
+        "/api/v1/video-consultations/{}/patient-feedback",
+        consultation_id
+    );
+    let feedback = json!({ "rating": 2, "notes": "问诊中态度恶劣" });
+
+    // Only the consultation's doctor may submit feedback
+    let (status, _) = app
+        .post_with_auth(&feedback_path, feedback.clone(), &other_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = app
+        .post_with_auth(&feedback_path, feedback.clone(), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = app
+        .post_with_auth(&feedback_path, feedback.clone(), &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!(body["data"]["rating"], 2);
+    assert_eq!(body["data"]["patient_id"], patient_id.to_string());
+
+    let (rating, notes): (i32, Option<String>) = sqlx::query_as(
+        "SELECT rating, notes FROM doctor_patient_feedback WHERE consultation_id = ?",
+    )
+    .bind(consultation_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(rating, 2);
+    assert_eq!(notes.as_deref(), Some("问诊中态度恶劣"));
+
+    // Feedback can only be submitted once per consultation
+    let (status, _) = app
+        .post_with_auth(&feedback_path, feedback, &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // Doctors see the aggregated reliability score
+    let reliability_path = format!(
+        "/api/v1/video-consultations/patients/{}/reliability",
+        patient_id
+    );
+    let (status, body) = app.get_with_auth(&reliability_path, &other_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["feedback_count"], 1);
+    assert_eq!(body["data"]["low_rating_count"], 1);
+    assert_eq!(body["data"]["reliability_score"], 25);
+
+    // Patients never see the feedback or the score
+    let (status, _) = app.get_with_auth(&reliability_path, &patient_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = app
+        .get_with_auth(
+            &format!("/api/v1/video-consultations/{}", consultation_id),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(!body.to_string().contains("问诊中态度恶劣"));
+}
+
+#[tokio::test]
+//#[serial]
+async fn test_resend_consultation_invite() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (_, outsider_email, outsider_password) = create_test_user(&app.pool, "patient").await;
+
+    let appointment_id = Uuid::new_v4();
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(now.naive_utc())
+    .bind("09:00-10:00")
+    .bind("test symptoms")
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let consultation_id = Uuid::new_v4();
+    let room_id = format!("room_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'waiting', ?, ?, ?)
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(&room_id)
+    .bind(now + Duration::minutes(30))
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let outsider_token = get_auth_token(&mut app, &outsider_email, &outsider_password).await;
+
+    let resend_path = format!(
+        "/api/v1/video-consultations/{}/resend-invite",
+        consultation_id
+    );
+
+    // Non-participants cannot trigger an invite
+    let (status, _) = app
+        .post_with_auth(&resend_path, json!({}), &outsider_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) = app
+        .post_with_auth(&resend_path, json!({}), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["room_id"], room_id);
+
+    let metadata: serde_json::Value = sqlx::query_scalar(
+        "SELECT metadata FROM notifications WHERE user_id = ? AND related_id = ?",
+    )
+    .bind(patient_id.to_string())
+    .bind(consultation_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(metadata["room_id"], room_id);
+    assert!(metadata["token"].as_str().is_some());
+
+    // A second resend inside the cooldown window is rate-limited, whoever asks
+    let (status, _) = app
+        .post_with_auth(&resend_path, json!({}), &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+
+    // Once the cooldown has passed the doctor can resend as well
+    sqlx::query("UPDATE video_consultations SET invite_sent_at = ? WHERE id = ?")
+        .bind(Utc::now() - Duration::hours(1))
+        .bind(consultation_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, _) = app
+        .post_with_auth(&resend_path, json!({}), &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+}
+
+#[tokio::test]
+//#[serial]
+async fn test_consultation_transcript_interleaves_chat_and_events() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (other_user_id, other_email, other_password) = create_test_user(&app.pool, "doctor").await;
+    create_test_doctor(&app.pool, other_user_id).await;
+    let (_, admin_email, admin_password) = create_test_user(&app.pool, "admin").await;
+
+    let appointment_id = Uuid::new_v4();
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(now.naive_utc())
+    .bind("09:00-10:00")
+    .bind("test symptoms")
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let consultation_id = Uuid::new_v4();
+    let room_id = format!("room_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, actual_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'in_progress', ?, ?, ?, ?)
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(&room_id)
+    .bind(now)
+    .bind(now)
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let insert_event = |user_id: Uuid, event_type: &'static str, at: chrono::DateTime<Utc>| {
+        sqlx::query(
+            r#"
+            INSERT INTO video_call_events (id, consultation_id, user_id, event_type, created_at)
+            VALUES (?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(consultation_id.to_string())
+        .bind(user_id.to_string())
+        .bind(event_type)
+        .bind(at)
+    };
+
+    // Both sides joined before any chat; the doctor leaves after it
+    insert_event(doctor_user_id, "joined", now - Duration::minutes(10))
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    insert_event(patient_id, "joined", now - Duration::minutes(9))
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    insert_event(doctor_user_id, "left", now + Duration::minutes(10))
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+    let other_token = get_auth_token(&mut app, &other_email, &other_password).await;
+    let admin_token = get_auth_token(&mut app, &admin_email, &admin_password).await;
+
+    let messages_path = format!("/api/v1/video-consultations/{}/messages", consultation_id);
+    let transcript_path = format!("/api/v1/video-consultations/{}/transcript", consultation_id);
+
+    let (status, _) = app
+        .post_with_auth(
+            &messages_path,
+            json!({ "content": "头痛三天" }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+
+    // Outsiders cannot write into the consultation chat
+    let (status, _) = app
+        .post_with_auth(&messages_path, json!({ "content": "hello" }), &other_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // The transcript is only available once the consultation is completed
+    let (status, _) = app.get_with_auth(&transcript_path, &doctor_token).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    sqlx::query("UPDATE video_consultations SET status = 'completed', end_time = ? WHERE id = ?")
+        .bind(now + Duration::minutes(10))
+        .bind(consultation_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, _) = app
+        .post_with_auth(
+            &messages_path,
+            json!({ "content": "还在吗" }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, body) = app.get_with_auth(&transcript_path, &doctor_token).await;
+    assert_eq!(status, StatusCode::OK);
+    let entries = body["data"]["entries"].as_array().unwrap();
+    assert_eq!(entries.len(), 4);
+
+    let order: Vec<(&str, &str)> = entries
+        .iter()
+        .map(|e| (e["kind"].as_str().unwrap(), e["role"].as_str().unwrap()))
+        .collect();
+    assert_eq!(
+        order,
+        vec![
+            ("event", "doctor"),
+            ("event", "patient"),
+            ("message", "patient"),
+            ("event", "doctor"),
+        ]
+    );
+    assert_eq!(entries[0]["event_type"], "joined");
+    assert_eq!(entries[2]["content"], "头痛三天");
+    assert_eq!(entries[3]["event_type"], "left");
+
+    // Participants and admins only
+    let (status, _) = app.get_with_auth(&transcript_path, &patient_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app.get_with_auth(&transcript_path, &admin_token).await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app.get_with_auth(&transcript_path, &other_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+//#[serial]
+async fn test_get_consultation_by_appointment() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (other_user_id, other_email, other_password) = create_test_user(&app.pool, "doctor").await;
+    create_test_doctor(&app.pool, other_user_id).await;
+    let (_, admin_email, admin_password) = create_test_user(&app.pool, "admin").await;
+
+    let appointment_id = Uuid::new_v4();
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(now.naive_utc())
+    .bind("09:00-10:00")
+    .bind("test symptoms")
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let insert_consultation = |status: &'static str, created_at: chrono::DateTime<Utc>| {
+        let consultation_id = Uuid::new_v4();
+        let room_id = format!("room_{}", Uuid::new_v4().to_string().replace("-", ""));
+        let query = sqlx::query(
+            r#"
+            INSERT INTO video_consultations (
+                id, appointment_id, doctor_id, patient_id, room_id,
+                status, scheduled_start_time, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(consultation_id.to_string())
+        .bind(appointment_id.to_string())
+        .bind(doctor_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(room_id)
+        .bind(status)
+        .bind(now)
+        .bind(created_at)
+        .bind(created_at);
+        (consultation_id, query)
+    };
+
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+    let other_token = get_auth_token(&mut app, &other_email, &other_password).await;
+    let admin_token = get_auth_token(&mut app, &admin_email, &admin_password).await;
+
+    let path = format!("/api/v1/video-consultations/appointment/{}", appointment_id);
+
+    // No consultation yet
+    let (status, _) = app.get_with_auth(&path, &doctor_token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    // Cancelled consultations are ignored
+    let (_, query) = insert_consultation("cancelled", now - Duration::hours(1));
+    query.execute(&app.pool).await.unwrap();
+
+    let (status, _) = app.get_with_auth(&path, &doctor_token).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (consultation_id, query) = insert_consultation("waiting", now);
+    query.execute(&app.pool).await.unwrap();
+
+    for token in [&doctor_token, &patient_token, &admin_token] {
+        let (status, body) = app.get_with_auth(&path, token).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body["data"]["id"], consultation_id.to_string());
+    }
+
+    // Doctors unrelated to the appointment cannot look it up
+    let (status, _) = app.get_with_auth(&path, &other_token).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Unknown appointment
+    let (status, _) = app
+        .get_with_auth(
+            &format!("/api/v1/video-consultations/appointment/{}", Uuid::new_v4()),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_doctor_action_items() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (other_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (other_doctor_id, _) = create_test_doctor(&app.pool, other_user_id).await;
+
+    let now = Utc::now();
+    let pool = app.pool.clone();
+    let insert_consultation = |doctor_id: Uuid,
+                               status: &'static str,
+                               diagnosis: Option<&'static str>,
+                               treatment_plan: Option<&'static str>| {
+        let consultation_id = Uuid::new_v4();
+        let appointment_id = Uuid::new_v4();
+        let pool = pool.clone();
+        async move {
+            sqlx::query(
+                r#"
+                INSERT INTO appointments (
+                    id, patient_id, doctor_id, appointment_date, time_slot,
+                    visit_type, symptoms, has_visited_before, status,
+                    created_at, updated_at
+                ) VALUES (?, ?, ?, ?, '09:00-10:00', 'online_video', 'test symptoms', false, 'confirmed', ?, ?)
+                "#,
+            )
+            .bind(appointment_id.to_string())
+            .bind(patient_id.to_string())
+            .bind(doctor_id.to_string())
+            .bind(now.naive_utc())
+            .bind(now)
+            .bind(now)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+            sqlx::query(
+                r#"
+                INSERT INTO video_consultations (
+                    id, appointment_id, doctor_id, patient_id, room_id,
+                    status, scheduled_start_time, diagnosis, treatment_plan,
+                    created_at, updated_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(consultation_id.to_string())
+            .bind(appointment_id.to_string())
+            .bind(doctor_id.to_string())
+            .bind(patient_id.to_string())
+            .bind(format!("room_{}", consultation_id.simple()))
+            .bind(status)
+            .bind(now)
+            .bind(diagnosis)
+            .bind(treatment_plan)
+            .bind(now)
+            .bind(now)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+            consultation_id
+        }
+    };
+
+    let waiting_id = insert_consultation(doctor_id, "waiting", None, None).await;
+    let incomplete_id = insert_consultation(doctor_id, "completed", Some("风寒感冒"), None).await;
+    // 记录完整的已完成问诊和其他医生的问诊都不应出现
+    insert_consultation(doctor_id, "completed", Some("风寒感冒"), Some("桂枝汤")).await;
+    insert_consultation(other_doctor_id, "waiting", None, None).await;
+
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+
+    let (status, body) = app
+        .get_with_auth(
+            "/api/v1/video-consultations/doctor/action-items",
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let waiting = body["data"]["waiting"].as_array().unwrap();
+    assert_eq!(waiting.len(), 1);
+    assert_eq!(waiting[0]["id"], waiting_id.to_string());
+
+    let missing = body["data"]["missing_records"].as_array().unwrap();
+    assert_eq!(missing.len(), 1);
+    assert_eq!(missing[0]["id"], incomplete_id.to_string());
+
+    let (status, _) = app
+        .get_with_auth(
+            "/api/v1/video-consultations/doctor/action-items",
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn test_patient_consultation_summary() {
+    let mut app = TestApp::new().await;
+
+    let (patient_a, _, _) = create_test_user(&app.pool, "patient").await;
+    let (patient_b, patient_b_email, patient_b_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (other_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (other_doctor_id, _) = create_test_doctor(&app.pool, other_user_id).await;
+
+    let now = Utc::now();
+    let pool = app.pool.clone();
+    let insert_consultation = |doctor_id: Uuid,
+                               patient_id: Uuid,
+                               status: &'static str,
+                               start: chrono::DateTime<Utc>,
+                               rating: Option<i32>| {
+        let consultation_id = Uuid::new_v4();
+        let appointment_id = Uuid::new_v4();
+        let pool = pool.clone();
+        async move {
+            sqlx::query(
+                r#"
+                INSERT INTO appointments (
+                    id, patient_id, doctor_id, appointment_date, time_slot,
+                    visit_type, symptoms, has_visited_before, status,
+                    created_at, updated_at
+                ) VALUES (?, ?, ?, ?, '09:00-10:00', 'online_video', 'test symptoms', false, 'confirmed', ?, ?)
+                "#,
+            )
+            .bind(appointment_id.to_string())
+            .bind(patient_id.to_string())
+            .bind(doctor_id.to_string())
+            .bind(start.naive_utc())
+            .bind(now)
+            .bind(now)
+            .execute(&pool)
+            .await
+            .unwrap();
+
+            sqlx::query(
+                r#"
+                INSERT INTO video_consultations (
+                    id, appointment_id, doctor_id, patient_id, room_id,
+                    status, scheduled_start_time, patient_rating,
+                    created_at, updated_at
+                ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#,
+            )
+            .bind(consultation_id.to_string())
+            .bind(appointment_id.to_string())
+            .bind(doctor_id.to_string())
+            .bind(patient_id.to_string())
+            .bind(format!("room_{}", consultation_id.simple()))
+            .bind(status)
+            .bind(start)
+            .bind(rating)
+            .bind(now)
+            .bind(now)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+    };
+
+    // 患者 A：两次已完成问诊和一次待开始问诊，最近一次就诊是两天前
+    insert_consultation(
+        doctor_id,
+        patient_a,
+        "completed",
+        now - Duration::days(10),
+        Some(4),
+    )
+    .await;
+    insert_consultation(
+        doctor_id,
+        patient_a,
+        "completed",
+        now - Duration::days(2),
+        Some(2),
+    )
+    .await;
+    insert_consultation(
+        doctor_id,
+        patient_a,
+        "waiting",
+        now + Duration::days(1),
+        None,
+    )
+    .await;
+    // 患者 B：五天前就诊一次
+    insert_consultation(
+        doctor_id,
+        patient_b,
+        "completed",
+        now - Duration::days(5),
+        Some(5),
+    )
+    .await;
+    // 其他医生的问诊不计入
+    insert_consultation(other_doctor_id, patient_a, "completed", now, Some(1)).await;
+
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let (status, body) = app
+        .get_with_auth("/api/v1/video-consultations/doctor/patients", &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["total"], 2);
+
+    let items = body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 2);
+    assert_eq!(items[0]["patient_id"], patient_a.to_string());
+    assert_eq!(items[0]["consultation_count"], 3);
+    assert_eq!(items[0]["completed_count"], 2);
+    assert_eq!(items[0]["average_rating"].as_f64().unwrap(), 3.0);
+    let last_visit: chrono::DateTime<Utc> =
+        items[0]["last_visit_at"].as_str().unwrap().parse().unwrap();
+    assert!((last_visit - (now - Duration::days(2))).num_seconds().abs() <= 1);
+
+    assert_eq!(items[1]["patient_id"], patient_b.to_string());
+    assert_eq!(items[1]["consultation_count"], 1);
+
+    let patient_token = get_auth_token(&mut app, &patient_b_email, &patient_b_password).await;
+    let (status, _) = app
+        .get_with_auth(
+            "/api/v1/video-consultations/doctor/patients",
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+}
+
+async fn set_questionnaire_gate(app: &TestApp, enabled: bool) {
+    sqlx::query(
+        "UPDATE system_configs SET config_value = ? WHERE category = 'video_call' AND config_key = 'require_questionnaire_before_start'",
+    )
+    .bind(enabled.to_string())
+    .execute(&app.pool)
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_pre_visit_questionnaire() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+
+    let now = Utc::now();
+    let appointment_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, '09:00-10:00', 'online_video', 'test symptoms', false, 'confirmed', ?, ?)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(now.naive_utc())
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let consultation_id = Uuid::new_v4();
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'waiting', ?, ?, ?)
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(format!("room_{}", consultation_id.simple()))
+    .bind(now)
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+
+    let (status, body) = app
+        .post_with_auth(
+            &format!(
+                "/api/v1/video-consultations/{}/questionnaires",
+                consultation_id
+            ),
+            json!({
+                "title": "问诊前症状问卷",
+                "questions": [
+                    { "key": "onset", "question": "症状持续多久了？", "required": true },
+                    { "key": "allergy", "question": "有无药物过敏史？" }
+                ]
+            }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let questionnaire_id = body["data"]["id"].as_str().unwrap().to_string();
+    let answers_path = format!(
+        "/api/v1/video-consultations/{}/questionnaires/{}/answers",
+        consultation_id, questionnaire_id
+    );
+    let start_path = format!("/api/v1/video-consultations/{}/start", consultation_id);
+
+    // 必答题未回答时不能提交
+    let (status, _) = app
+        .post_with_auth(
+            &answers_path,
+            json!({ "answers": { "allergy": "无" } }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // 开启开始前校验后，问卷未填写时不能开始问诊
+    set_questionnaire_gate(&app, true).await;
+    let (blocked_status, _) = app
+        .put_with_auth(&start_path, json!({}), &doctor_token)
+        .await;
+
+    let (submit_status, _) = app
+        .post_with_auth(
+            &answers_path,
+            json!({ "answers": { "onset": "三天", "allergy": "青霉素过敏" } }),
+            &patient_token,
+        )
+        .await;
+
+    // 医生查看问诊时能看到患者的答案
+    let (detail_status, detail) = app
+        .get_with_auth(
+            &format!("/api/v1/video-consultations/{}", consultation_id),
+            &doctor_token,
+        )
+        .await;
+
+    let (started_status, _) = app
+        .put_with_auth(&start_path, json!({}), &doctor_token)
+        .await;
+    set_questionnaire_gate(&app, false).await;
+
+    assert_eq!(blocked_status, StatusCode::BAD_REQUEST);
+    assert_eq!(submit_status, StatusCode::OK);
+    assert_eq!(detail_status, StatusCode::OK);
+    assert_eq!(detail["data"]["id"], consultation_id.to_string());
+    let questionnaire = &detail["data"]["questionnaires"][0];
+    assert_eq!(questionnaire["answers"]["onset"], "三天");
+    assert_eq!(questionnaire["answers"]["allergy"], "青霉素过敏");
+    assert!(questionnaire["submitted_at"].is_string());
+    assert_eq!(started_status, StatusCode::OK);
+}
+
+#[tokio::test]
+async fn test_consultation_handover() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (colleague_user_id, colleague_email, colleague_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (colleague_id, _) = create_test_doctor(&app.pool, colleague_user_id).await;
+
+    let appointment_id = Uuid::new_v4();
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(now.naive_utc())
+    .bind("09:00-10:00")
+    .bind("test symptoms")
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let consultation_id = Uuid::new_v4();
+    let room_id = format!("room_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id, doctor_token,
+            status, scheduled_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'old_doctor_token', 'waiting', ?, ?, ?)
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(&room_id)
+    .bind(now + Duration::minutes(30))
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let colleague_token = get_auth_token(&mut app, &colleague_email, &colleague_password).await;
+
+    let handover_path = format!("/api/v1/video-consultations/{}/handover", consultation_id);
+
+    // Only the scheduled doctor (or an admin) can hand the consultation over
+    let (status, _) = app
+        .post_with_auth(
+            &handover_path,
+            json!({ "to_doctor_id": colleague_id }),
+            &colleague_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // The target has to be an existing doctor
+    let (status, _) = app
+        .post_with_auth(
+            &handover_path,
+            json!({ "to_doctor_id": Uuid::new_v4() }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = app
+        .post_with_auth(
+            &handover_path,
+            json!({ "to_doctor_id": colleague_id, "reason": "临时手术" }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["doctor_id"], colleague_id.to_string());
+
+    // The previous doctor's room token no longer works
+    let old_token: Option<String> =
+        sqlx::query_scalar("SELECT doctor_token FROM video_consultations WHERE id = ?")
+            .bind(consultation_id.to_string())
+            .fetch_one(&app.pool)
+            .await
+            .unwrap();
+    assert!(old_token.is_none());
+
+    let metadata: serde_json::Value = sqlx::query_scalar(
+        "SELECT metadata FROM notifications WHERE user_id = ? AND related_id = ?",
+    )
+    .bind(patient_id.to_string())
+    .bind(consultation_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(metadata["from_doctor_id"], doctor_id.to_string());
+    assert_eq!(metadata["to_doctor_id"], colleague_id.to_string());
+
+    let events: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM video_call_events WHERE consultation_id = ? AND event_type = 'handover'",
+    )
+    .bind(consultation_id.to_string())
+    .fetch_one(&app.pool)
+    .await
+    .unwrap();
+    assert_eq!(events, 1);
+
+    // The previous doctor lost access, the colleague now runs the consultation
+    let start_path = format!("/api/v1/video-consultations/{}/start", consultation_id);
+    let (status, _) = app
+        .put_with_auth(&start_path, json!({}), &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = app
+        .put_with_auth(&start_path, json!({}), &colleague_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    // Finished consultations can no longer be handed over
+    sqlx::query("UPDATE video_consultations SET status = 'completed' WHERE id = ?")
+        .bind(consultation_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+    let (status, _) = app
+        .post_with_auth(
+            &handover_path,
+            json!({ "to_doctor_id": doctor_id }),
+            &colleague_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_duplicate_signals_are_not_relayed() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+
+    let appointment_id = Uuid::new_v4();
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(now.naive_utc())
+    .bind("09:00-10:00")
+    .bind("test symptoms")
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let consultation_id = Uuid::new_v4();
+    let room_id = format!("room_{}", Uuid::new_v4().to_string().replace("-", ""));
+
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'in_progress', ?, ?, ?)
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(&room_id)
+    .bind(now)
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+    let room_token = jwt::create_room_token(
+        consultation_id,
+        doctor_user_id,
+        "doctor".to_string(),
+        &app.config.jwt_secret,
+        3600,
+    )
+    .unwrap();
+
+    let candidate = |n: u32| {
+        json!({
+            "room_id": room_id,
+            "to_user_id": patient_id,
+            "signal_type": "ice_candidate",
+            "payload": {
+                "candidate": format!("candidate:{} 1 udp 2122260223 192.168.1.2 5400{} typ host", n, n),
+                "sdpMid": "0",
+            },
+            "token": room_token,
+        })
+    };
+
+    // The same candidate sent twice is only stored once
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/video-consultations/signal",
+            candidate(1),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["duplicate"], false);
+
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/video-consultations/signal",
+            candidate(1),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["duplicate"], true);
+
+    // A distinct candidate still goes through
+    let (status, body) = app
+        .post_with_auth(
+            "/api/v1/video-consultations/signal",
+            candidate(2),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["duplicate"], false);
+
+    let (status, body) = app
+        .get_with_auth(
+            &format!("/api/v1/video-consultations/signal/{}", room_id),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let signals = body["data"].as_array().unwrap();
+    assert_eq!(signals.len(), 2);
+    assert_ne!(signals[0]["payload"], signals[1]["payload"]);
+
+    // Sends beyond the per-minute limit are rejected
+    sqlx::query(
+        "UPDATE system_configs SET config_value = '2' WHERE category = 'video_call' AND config_key = 'signal_rate_limit_per_minute'",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/video-consultations/signal",
+            candidate(3),
+            &doctor_token,
+        )
+        .await;
+
+    sqlx::query(
+        "UPDATE system_configs SET config_value = '300' WHERE category = 'video_call' AND config_key = 'signal_rate_limit_per_minute'",
+    )
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    assert_eq!(status, StatusCode::TOO_MANY_REQUESTS);
+}
+
+#[tokio::test]
+async fn test_short_room_code() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (_, outsider_email, outsider_password) = create_test_user(&app.pool, "patient").await;
+
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+    let outsider_token = get_auth_token(&mut app, &outsider_email, &outsider_password).await;
+
+    let mut consultations = vec![];
+    for _ in 0..2 {
+        let appointment_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO appointments (
+                id, patient_id, doctor_id, appointment_date, time_slot,
+                visit_type, symptoms, has_visited_before, status,
+                created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', NOW(), NOW())
+            "#,
+        )
+        .bind(appointment_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(doctor_id.to_string())
+        .bind((Utc::now() + Duration::hours(2)).naive_utc())
+        .bind("09:00-10:00")
+        .bind("test symptoms")
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+        let create_dto = json!({
+            "appointment_id": appointment_id,
+            "doctor_id": doctor_id,
+            "patient_id": patient_id,
+            "scheduled_start_time": (Utc::now() + Duration::hours(1)).to_rfc3339(),
+        });
+        let (status, body) = app
+            .post_with_auth("/api/v1/video-consultations", create_dto, &doctor_token)
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        consultations.push(body["data"].clone());
+    }
+
+    let room_code = consultations[0]["room_code"].as_str().unwrap().to_string();
+    assert_eq!(room_code.len(), 6);
+    assert!(room_code.chars().all(|c| c.is_ascii_alphanumeric()));
+    assert_ne!(consultations[1]["room_code"], room_code.as_str());
+
+    // The code resolves to the right room, case-insensitively, for participants only
+    let (status, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/video-consultations/room-code/{}",
+                room_code.to_lowercase()
+            ),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["room_id"], consultations[0]["room_id"]);
+    assert_eq!(body["data"]["consultation_id"], consultations[0]["id"]);
+
+    let (status, _) = app
+        .get_with_auth(
+            &format!("/api/v1/video-consultations/room-code/{}", room_code),
+            &outsider_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // A generated code that is already taken is regenerated instead of failing
+    let second_id = Uuid::parse_str(consultations[1]["id"].as_str().unwrap()).unwrap();
+    let fresh_code = loop {
+        let code = VideoConsultationService::generate_room_code();
+        if code != room_code {
+            break code;
+        }
+    };
+    let mut candidates = vec![fresh_code.clone(), room_code.clone()];
+    let mut attempts = 0;
+    let assigned = VideoConsultationService::assign_room_code(&app.pool, second_id, || {
+        attempts += 1;
+        candidates.pop().unwrap()
+    })
+    .await
+    .unwrap();
+    assert_eq!(attempts, 2);
+    assert_eq!(assigned, fresh_code);
+
+    let (_, body) = app
+        .get_with_auth(
+            &format!("/api/v1/video-consultations/room-code/{}", room_code),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(body["data"]["consultation_id"], consultations[0]["id"]);
+
+    // Ending the consultation expires its code
+    let consultation_path = format!(
+        "/api/v1/video-consultations/{}",
+        consultations[0]["id"].as_str().unwrap()
+    );
+    let (status, _) = app
+        .put_with_auth(
+            &format!("{}/start", consultation_path),
+            json!({}),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = app
+        .put_with_auth(
+            &format!("{}/end", consultation_path),
+            json!({ "diagnosis": "失眠症" }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let (status, _) = app
+        .get_with_auth(
+            &format!("/api/v1/video-consultations/room-code/{}", room_code),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_consultation_overtime_flagged_once() {
+    use backend::services::websocket_service::WebSocketManager;
+
+    let mut app = TestApp::new().await;
+
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+
+    // The doctor's own setting becomes the consultation's expected duration
+    let (status, _) = app
+        .put_with_auth(
+            &format!("/api/v1/doctors/{}", doctor_id),
+            json!({ "consultation_duration_minutes": 20 }),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+
+    let now = Utc::now();
+    let mut consultation_ids = vec![];
+    for _ in 0..3 {
+        let appointment_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO appointments (
+                id, patient_id, doctor_id, appointment_date, time_slot,
+                visit_type, symptoms, has_visited_before, status,
+                created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+            "#,
+        )
+        .bind(appointment_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(doctor_id.to_string())
+        .bind(now.naive_utc())
+        .bind("09:00-10:00")
+        .bind("test symptoms")
+        .bind(now)
+        .bind(now)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+        let create_dto = json!({
+            "appointment_id": appointment_id,
+            "doctor_id": doctor_id,
+            "patient_id": patient_id,
+            "scheduled_start_time": now.to_rfc3339(),
+        });
+        let (status, body) = app
+            .post_with_auth("/api/v1/video-consultations", create_dto, &doctor_token)
+            .await;
+        assert_eq!(status, StatusCode::CREATED);
+        assert_eq!(body["data"]["expected_duration"], 20);
+        consultation_ids.push(body["data"]["id"].as_str().unwrap().to_string());
+    }
+
+    // One call has run past its 20 minutes, one is still within it,
+    // one already finished late
+    for (id, status, started_minutes_ago, duration) in [
+        (&consultation_ids[0], "in_progress", 30, None),
+        (&consultation_ids[1], "in_progress", 5, None),
+        (&consultation_ids[2], "completed", 60, Some(1800)),
+    ] {
+        sqlx::query(
+            "UPDATE video_consultations SET status = ?, actual_start_time = ?, duration = ? WHERE id = ?",
+        )
+        .bind(status)
+        .bind(now - Duration::minutes(started_minutes_ago))
+        .bind(duration)
+        .bind(id)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    let ws_manager = WebSocketManager::new();
+    let mut rx = ws_manager
+        .add_connection(doctor_user_id, "doctor".to_string())
+        .await;
+
+    // Run the sweeper twice; the overrun is flagged only once
+    let flagged = VideoConsultationService::flag_overtime_consultations(&app.pool, &ws_manager)
+        .await
+        .unwrap();
+    assert!(flagged >= 1);
+    VideoConsultationService::flag_overtime_consultations(&app.pool, &ws_manager)
+        .await
+        .unwrap();
+
+    for (id, expected) in [(&consultation_ids[0], 1), (&consultation_ids[1], 0)] {
+        let events: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM video_call_events WHERE consultation_id = ? AND event_type = 'overtime'",
+        )
+        .bind(id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+        assert_eq!(events, expected);
+
+        let notifications: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM notifications WHERE user_id = ? AND related_id = ?",
+        )
+        .bind(doctor_user_id.to_string())
+        .bind(id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+        assert_eq!(notifications, expected);
+    }
+
+    assert!(rx.try_recv().is_ok());
+    assert!(rx.try_recv().is_err());
+
+    // Statistics compare the expected length with the actual one
+    let stats = VideoConsultationService::get_consultation_statistics(
+        &app.pool,
+        Some(doctor_id),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.completed_consultations, 1);
+    assert_eq!(stats.average_duration, Some(1800.0));
+    assert_eq!(stats.average_expected_duration, Some(1200.0));
+    assert_eq!(stats.overtime_count, 1);
+}
+
+#[tokio::test]
+async fn test_admin_list_consultations_filters() {
+    let mut app = TestApp::new().await;
+
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (other_patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, department) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (_, admin_email, admin_password) = create_test_user(&app.pool, "admin").await;
+
+    let now = Utc::now();
+    let pool = app.pool.clone();
+    let insert_consultation =
+        |patient_id: Uuid, status: &'static str, rating: Option<i32>, hours_ago: i64| {
+            let pool = pool.clone();
+            async move {
+                let appointment_id = Uuid::new_v4();
+                sqlx::query(
+                    r#"
+                    INSERT INTO appointments (
+                        id, patient_id, doctor_id, appointment_date, time_slot,
+                        visit_type, symptoms, has_visited_before, status,
+                        created_at, updated_at
+                    ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+                    "#,
+                )
+                .bind(appointment_id.to_string())
+                .bind(patient_id.to_string())
+                .bind(doctor_id.to_string())
+                .bind(now.naive_utc())
+                .bind("09:00-10:00")
+                .bind("test symptoms")
+                .bind(now)
+                .bind(now)
+                .execute(&pool)
+                .await
+                .unwrap();
+
+                let consultation_id = Uuid::new_v4();
+                sqlx::query(
+                    r#"
+                    INSERT INTO video_consultations (
+                        id, appointment_id, doctor_id, patient_id, room_id,
+                        status, scheduled_start_time, patient_rating, created_at, updated_at
+                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                    "#,
+                )
+                .bind(consultation_id.to_string())
+                .bind(appointment_id.to_string())
+                .bind(doctor_id.to_string())
+                .bind(patient_id.to_string())
+                .bind(format!("room_{}", consultation_id.simple()))
+                .bind(status)
+                .bind(now - Duration::hours(hours_ago))
+                .bind(rating)
+                .bind(now)
+                .bind(now)
+                .execute(&pool)
+                .await
+                .unwrap();
+                consultation_id.to_string()
+            }
+        };
+
+    let recent_top = insert_consultation(patient_id, "completed", Some(4), 2).await;
+    let older_top = insert_consultation(patient_id, "completed", Some(5), 24).await;
+    let low_rated = insert_consultation(patient_id, "completed", Some(2), 3).await;
+    insert_consultation(patient_id, "waiting", None, 0).await;
+    let old_other = insert_consultation(other_patient_id, "completed", Some(5), 120).await;
+
+    let admin_token = get_auth_token(&mut app, &admin_email, &admin_password).await;
+    let date_from = (now - Duration::hours(48)).format("%Y-%m-%dT%H:%M:%SZ");
+    let base = format!(
+        "/api/v1/video-consultations/admin/list?doctor_id={}&status=completed&min_rating=4&date_from={}",
+        doctor_id, date_from
+    );
+
+    // All filters apply together, newest first, one row per page
+    let (status, body) = app
+        .get_with_auth(&format!("{}&page=1&page_size=1", base), &admin_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["data"]["total"], 2);
+    let items = body["data"]["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+    assert_eq!(items[0]["id"], recent_top);
+    assert_eq!(items[0]["department"], department);
+    assert!(items[0]["doctor_name"].is_string());
+    assert!(items[0]["patient_name"].is_string());
+
+    let (_, body) = app
+        .get_with_auth(&format!("{}&page=2&page_size=1", base), &admin_token)
+        .await;
+    assert_eq!(body["data"]["total"], 2);
+    assert_eq!(body["data"]["items"][0]["id"], older_top);
+
+    let (_, body) = app
+        .get_with_auth(&format!("{}&page=3&page_size=1", base), &admin_token)
+        .await;
+    assert_eq!(body["data"]["items"].as_array().unwrap().len(), 0);
+
+    let (_, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/video-consultations/admin/list?doctor_id={}&max_rating=3",
+                doctor_id
+            ),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(body["data"]["total"], 1);
+    assert_eq!(body["data"]["items"][0]["id"], low_rated);
+
+    let (_, body) = app
+        .get_with_auth(
+            &format!(
+                "/api/v1/video-consultations/admin/list?doctor_id={}&patient_id={}",
+                doctor_id, other_patient_id
+            ),
+            &admin_token,
+        )
+        .await;
+    assert_eq!(body["data"]["total"], 1);
+    assert_eq!(body["data"]["items"][0]["id"], old_other);
+
+    // Patients and doctors cannot use the admin view
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+    for token in [&patient_token, &doctor_token] {
+        let (status, _) = app
+            .get_with_auth("/api/v1/video-consultations/admin/list", token)
+            .await;
+        assert_eq!(status, StatusCode::FORBIDDEN);
+    }
+}
+
+#[tokio::test]
+//#[serial]
+async fn test_signal_pushed_to_connected_peer() {
+    use backend::models::video_consultation::{SendSignalDto, SignalType};
+    use backend::services::websocket_service::{WebSocketManager, WsMessage};
+
+    let app = TestApp::new().await;
+
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+
+    let appointment_id = Uuid::new_v4();
+    let now = Utc::now();
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(now.naive_utc())
+    .bind("09:00-10:00")
+    .bind("test symptoms")
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let consultation_id = Uuid::new_v4();
+    let room_id = format!("room_{}", Uuid::new_v4().to_string().replace("-", ""));
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'in_progress', ?, ?, ?)
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(&room_id)
+    .bind(now)
+    .bind(now)
+    .bind(now)
+    .execute(&app.pool)
+    .await
+    .unwrap();
+
+    let room_token = |user_id: Uuid, role: &str| {
+        jwt::create_room_token(
+            consultation_id,
+            user_id,
+            role.to_string(),
+            &app.config.jwt_secret,
+            3600,
+        )
+        .unwrap()
+    };
+
+    let ws_manager = WebSocketManager::new();
+    let mut rx = ws_manager
+        .add_connection(patient_id, "patient".to_string())
+        .await;
+
+    // The patient is connected, so the offer arrives over the socket
+    let stored = VideoConsultationService::send_signal(
+        &app.pool,
+        &ws_manager,
+        &app.config.jwt_secret,
+        doctor_user_id,
+        SendSignalDto {
+            room_id: room_id.clone(),
+            to_user_id: patient_id,
+            signal_type: SignalType::Offer,
+            payload: json!({ "sdp": "v=0" }),
+            token: room_token(doctor_user_id, "doctor"),
+        },
+    )
+    .await
+    .unwrap();
+    assert!(stored);
+
+    match rx.try_recv().unwrap() {
+        WsMessage::Signal {
+            room_id: pushed_room,
+            from_user_id,
+            signal_type,
+            payload,
+            ..
+        } => {
+            assert_eq!(pushed_room, room_id);
+            assert_eq!(from_user_id, doctor_user_id.to_string());
+            assert!(matches!(signal_type, SignalType::Offer));
+            assert_eq!(payload["sdp"], "v=0");
+        }
+        other => panic!("unexpected message: {:?}", other),
+    }
+
+    // Nothing is left for the patient to poll
+    let pending = VideoConsultationService::receive_signals(&app.pool, &room_id, patient_id)
+        .await
+        .unwrap();
+    assert!(pending.is_empty());
+
+    // The doctor is offline, so the answer falls back to the stored row
+    VideoConsultationService::send_signal(
+        &app.pool,
+        &ws_manager,
+        &app.config.jwt_secret,
+        patient_id,
+        SendSignalDto {
+            room_id: room_id.clone(),
+            to_user_id: doctor_user_id,
+            signal_type: SignalType::Answer,
+            payload: json!({ "sdp": "v=0" }),
+            token: room_token(patient_id, "patient"),
+        },
+    )
+    .await
+    .unwrap();
+
+    let pending = VideoConsultationService::receive_signals(&app.pool, &room_id, doctor_user_id)
+        .await
+        .unwrap();
+    assert_eq!(pending.len(), 1);
+    assert!(matches!(pending[0].signal_type, SignalType::Answer));
+}
+
+#[tokio::test]
+//#[serial]
+async fn test_mark_no_shows_flips_stale_waiting_consultations() {
+    let app = TestApp::new().await;
+
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let now = Utc::now();
+
+    // One call was due two hours ago, the other only started to be due five minutes ago
+    let mut seeded = Vec::new();
+    for scheduled_minutes_ago in [120, 5] {
+        let appointment_id = Uuid::new_v4();
+        let scheduled = now - Duration::minutes(scheduled_minutes_ago);
+        sqlx::query(
+            r#"
+            INSERT INTO appointments (
+                id, patient_id, doctor_id, appointment_date, time_slot,
+                visit_type, symptoms, has_visited_before, status,
+                created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, 'online_video', ?, false, 'confirmed', ?, ?)
+            "#,
+        )
+        .bind(appointment_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(doctor_id.to_string())
+        .bind(scheduled.naive_utc())
+        .bind("09:00-10:00")
+        .bind("test symptoms")
+        .bind(now)
+        .bind(now)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+        let consultation_id = Uuid::new_v4();
+        sqlx::query(
+            r#"
+            INSERT INTO video_consultations (
+                id, appointment_id, doctor_id, patient_id, room_id,
+                status, scheduled_start_time, created_at, updated_at
+            ) VALUES (?, ?, ?, ?, ?, 'waiting', ?, ?, ?)
+            "#,
+        )
+        .bind(consultation_id.to_string())
+        .bind(appointment_id.to_string())
+        .bind(doctor_id.to_string())
+        .bind(patient_id.to_string())
+        .bind(format!("room_{}", Uuid::new_v4().simple()))
+        .bind(scheduled)
+        .bind(now)
+        .bind(now)
+        .execute(&app.pool)
+        .await
+        .unwrap();
+
+        seeded.push((consultation_id, appointment_id));
+    }
+
+    let marked = VideoConsultationService::mark_no_shows(&app.pool)
+        .await
+        .unwrap();
+    assert!(marked >= 1);
+
+    for ((consultation_id, appointment_id), expected) in seeded.iter().zip(["no_show", "waiting"]) {
+        let consultation_status: String =
+            sqlx::query_scalar("SELECT status FROM video_consultations WHERE id = ?")
+                .bind(consultation_id.to_string())
+                .fetch_one(&app.pool)
+                .await
+                .unwrap();
+        assert_eq!(consultation_status, expected);
+
+        let appointment_status: String =
+            sqlx::query_scalar("SELECT status FROM appointments WHERE id = ?")
+                .bind(appointment_id.to_string())
+                .fetch_one(&app.pool)
+                .await
+                .unwrap();
+        let expected_appointment = if expected == "no_show" {
+            "no_show"
+        } else {
+            "confirmed"
+        };
+        assert_eq!(appointment_status, expected_appointment);
+    }
+}
+/// 为患者和医生创建一场待开始的视频问诊，返回问诊ID和房间号
+async fn seed_waiting_consultation(
+    pool: &sqlx::MySqlPool,
+    patient_id: Uuid,
+    doctor_id: Uuid,
+) -> (Uuid, String) {
+    let appointment_id = Uuid::new_v4();
+    let now = Utc::now();
+    let scheduled_time = now + Duration::hours(1);
+
+    sqlx::query(
+        r#"
+        INSERT INTO appointments (
+            id, patient_id, doctor_id, appointment_date, time_slot,
+            visit_type, symptoms, has_visited_before, status,
+            created_at, updated_at
+        ) VALUES (?, ?, ?, ?, '09:00-10:00', 'online_video', 'test symptoms', false, 'confirmed', ?, ?)
+        "#,
+    )
+    .bind(appointment_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(scheduled_time.naive_utc())
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    let consultation_id = Uuid::new_v4();
+    let room_id = format!("room_{}", Uuid::new_v4().simple());
+    sqlx::query(
+        r#"
+        INSERT INTO video_consultations (
+            id, appointment_id, doctor_id, patient_id, room_id,
+            status, scheduled_start_time, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, 'waiting', ?, ?, ?)
+        "#,
+    )
+    .bind(consultation_id.to_string())
+    .bind(appointment_id.to_string())
+    .bind(doctor_id.to_string())
+    .bind(patient_id.to_string())
+    .bind(&room_id)
+    .bind(scheduled_time)
+    .bind(now)
+    .bind(now)
+    .execute(pool)
+    .await
+    .unwrap();
+
+    (consultation_id, room_id)
+}
+
+#[tokio::test]
+async fn test_room_token_is_bound_to_its_consultation() {
+    let mut app = TestApp::new().await;
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (consultation_a, room_a) =
+        seed_waiting_consultation(&app.pool, patient_id, doctor_id).await;
+    let (_, room_b) = seed_waiting_consultation(&app.pool, patient_id, doctor_id).await;
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+    let join_path = |room_id: &str, token: &str| {
+        format!(
+            "/api/v1/video-consultations/room/{}/join?token={}",
+            room_id, token
+        )
+    };
+
+    let (status, body) = app
+        .post_with_auth(
+            &format!("/api/v1/video-consultations/room/{}/join", room_a),
+            json!({}),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    let token_a = body["data"]["token"].as_str().unwrap().to_string();
+    let claims = jwt::decode_room_token(&token_a, &app.config.jwt_secret).unwrap();
+    assert_eq!(claims.consultation_id, consultation_a);
+    assert_eq!(claims.sub, patient_id);
+    assert_eq!(claims.role, "patient");
+
+    // The token for consultation A does not open consultation B
+    let (status, _) = app
+        .post_with_auth(&join_path(&room_b, &token_a), json!({}), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/video-consultations/signal",
+            json!({
+                "room_id": room_b,
+                "to_user_id": doctor_user_id,
+                "signal_type": "offer",
+                "payload": { "sdp": "v=0" },
+                "token": token_a,
+            }),
+            &patient_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    // Tampered and expired tokens are rejected even for the right room
+    let tampered = format!("{}x", token_a);
+    let (status, _) = app
+        .post_with_auth(&join_path(&room_a, &tampered), json!({}), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let mut expired_claims =
+        jwt::RoomClaims::new(consultation_a, patient_id, "patient".to_string(), 60);
+    expired_claims.exp = (Utc::now() - Duration::hours(1)).timestamp();
+    let expired = jsonwebtoken::encode(
+        &jsonwebtoken::Header::default(),
+        &expired_claims,
+        &jsonwebtoken::EncodingKey::from_secret(app.config.jwt_secret.as_bytes()),
+    )
+    .unwrap();
+    let (status, _) = app
+        .post_with_auth(&join_path(&room_a, &expired), json!({}), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // A valid token still joins and returns the ICE servers
+    let (status, body) = app
+        .post_with_auth(&join_path(&room_a, &token_a), json!({}), &patient_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["data"]["ice_servers"].is_array());
+}
+
+#[tokio::test]
+async fn test_signal_without_room_token_is_rejected() {
+    let mut app = TestApp::new().await;
+    let (patient_id, patient_email, patient_password) =
+        create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let (_, room_id) = seed_waiting_consultation(&app.pool, patient_id, doctor_id).await;
+    let patient_token = get_auth_token(&mut app, &patient_email, &patient_password).await;
+    let signal = json!({
+        "room_id": room_id,
+        "to_user_id": doctor_user_id,
+        "signal_type": "offer",
+        "payload": { "sdp": "v=0" },
+    });
+
+    // Being a participant is not enough without the room token
+    let (status, _) = app
+        .post_with_auth(
+            "/api/v1/video-consultations/signal",
+            signal.clone(),
+            &patient_token,
+        )
+        .await;
+    assert!(status.is_client_error());
+
+    let mut empty = signal;
+    empty["token"] = json!("");
+    let (status, _) = app
+        .post_with_auth("/api/v1/video-consultations/signal", empty, &patient_token)
+        .await;
+    assert!(status.is_client_error());
+
+    let stored: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM webrtc_signals WHERE room_id = ?")
+        .bind(&room_id)
+        .fetch_one(&app.pool)
+        .await
+        .unwrap();
+    assert_eq!(stored, 0);
+}
+#[tokio::test]
+async fn test_list_consultations_applies_status_and_date_filters() {
+    let mut app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_user_id, doctor_email, doctor_password) =
+        create_test_user(&app.pool, "doctor").await;
+    let (doctor_id, _) = create_test_doctor(&app.pool, doctor_user_id).await;
+    let doctor_token = get_auth_token(&mut app, &doctor_email, &doctor_password).await;
+
+    let now = Utc::now();
+    let mut seeded = vec![];
+    for (status, start) in [
+        ("completed", now - Duration::days(2)),
+        ("waiting", now + Duration::hours(1)),
+        ("waiting", now + Duration::days(5)),
+    ] {
+        let (consultation_id, _) =
+            seed_waiting_consultation(&app.pool, patient_id, doctor_id).await;
+        sqlx::query(
+            "UPDATE video_consultations SET status = ?, scheduled_start_time = ? WHERE id = ?",
+        )
+        .bind(status)
+        .bind(start)
+        .bind(consultation_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+        seeded.push(consultation_id.to_string());
+    }
+
+    let list = |query: String| {
+        format!(
+            "/api/v1/video-consultations?doctor_id={}&{}",
+            doctor_id, query
+        )
+    };
+    let ids = |body: &serde_json::Value| -> Vec<String> {
+        body["data"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["id"].as_str().unwrap().to_string())
+            .collect()
+    };
+
+    let (status, body) = app
+        .get_with_auth(&list("status=completed".to_string()), &doctor_token)
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ids(&body), vec![seeded[0].clone()]);
+
+    let window = format!(
+        "date_from={}&date_to={}",
+        (now - Duration::days(1)).to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+        (now + Duration::days(1)).to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    );
+    let (status, body) = app.get_with_auth(&list(window), &doctor_token).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ids(&body), vec![seeded[1].clone()]);
+
+    let (status, body) = app
+        .get_with_auth(
+            &list(format!(
+                "status=waiting&date_from={}",
+                now.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)
+            )),
+            &doctor_token,
+        )
+        .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(ids(&body), vec![seeded[2].clone(), seeded[1].clone()]);
+}
+#[tokio::test]
+async fn test_consultation_statistics_scoped_by_doctor_and_dates() {
+    let app = TestApp::new().await;
+    let (patient_id, _, _) = create_test_user(&app.pool, "patient").await;
+    let (doctor_a_user, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_a, _) = create_test_doctor(&app.pool, doctor_a_user).await;
+    let (doctor_b_user, _, _) = create_test_user(&app.pool, "doctor").await;
+    let (doctor_b, _) = create_test_doctor(&app.pool, doctor_b_user).await;
+
+    let now = Utc::now();
+    for (doctor_id, status, start, duration) in [
+        (doctor_a, "completed", now - Duration::days(1), Some(600)),
+        (doctor_a, "no_show", now - Duration::days(1), None),
+        (doctor_a, "completed", now - Duration::days(10), Some(1200)),
+        (doctor_b, "completed", now - Duration::days(1), Some(900)),
+    ] {
+        let (consultation_id, _) =
+            seed_waiting_consultation(&app.pool, patient_id, doctor_id).await;
+        sqlx::query(
+            "UPDATE video_consultations SET status = ?, scheduled_start_time = ?, duration = ? WHERE id = ?",
+        )
+        .bind(status)
+        .bind(start)
+        .bind(duration)
+        .bind(consultation_id.to_string())
+        .execute(&app.pool)
+        .await
+        .unwrap();
+    }
+
+    let window_start = Some(now - Duration::days(3));
+    let window_end = Some(now);
+
+    let stats = VideoConsultationService::get_consultation_statistics(
+        &app.pool,
+        Some(doctor_a),
+        window_start,
+        window_end,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.total_consultations, 2);
+    assert_eq!(stats.completed_consultations, 1);
+    assert_eq!(stats.average_duration, Some(600.0));
+    assert_eq!(stats.no_show_rate, 50.0);
+
+    let stats = VideoConsultationService::get_consultation_statistics(
+        &app.pool,
+        Some(doctor_b),
+        window_start,
+        window_end,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.total_consultations, 1);
+    assert_eq!(stats.average_duration, Some(900.0));
+
+    // Only a start date: the older consultation is still excluded
+    let stats = VideoConsultationService::get_consultation_statistics(
+        &app.pool,
+        Some(doctor_a),
+        window_start,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.total_consultations, 2);
+
+    let stats = VideoConsultationService::get_consultation_statistics(
+        &app.pool,
+        Some(doctor_a),
+        None,
+        None,
+    )
+    .await
+    .unwrap();
+    assert_eq!(stats.total_consultations, 3);
+    assert_eq!(stats.average_duration, Some(900.0));
+}
@@ -1,3 +1,9 @@
+mod test_broadcast_segment;
 mod test_cache_service;
+mod test_content_filter;
+mod test_crypto;
 mod test_jwt;
+mod test_openapi;
 mod test_password;
+mod test_payment_metrics;
+mod test_reconciliation;
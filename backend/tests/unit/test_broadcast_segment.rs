@@ -0,0 +1,111 @@
+#[cfg(test)]
+mod tests {
+    use backend::models::broadcast::SegmentDefinition;
+    use backend::services::broadcast_service::{BroadcastService, SegmentParam};
+    use chrono::{Duration, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn test_empty_definition_rejected() {
+        let definition = SegmentDefinition::default();
+        assert!(BroadcastService::validate_definition(&definition).is_err());
+    }
+
+    #[test]
+    fn test_invalid_values_rejected() {
+        let invalid = [
+            json!({ "roles": [] }),
+            json!({ "roles": ["superuser"] }),
+            json!({ "last_appointment_within_days": 0 }),
+            json!({ "last_appointment_within_days": 100000 }),
+            json!({ "doctor_departments": ["  "] }),
+            json!({ "channels": ["fax"] }),
+            json!({
+                "registered_after": "2024-06-01T00:00:00Z",
+                "registered_before": "2024-01-01T00:00:00Z"
+            }),
+        ];
+
+        for value in invalid {
+            let definition: SegmentDefinition = serde_json::from_value(value.clone()).unwrap();
+            assert!(
+                BroadcastService::validate_definition(&definition).is_err(),
+                "expected {} to be rejected",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn test_unknown_condition_rejected() {
+        let result = serde_json::from_value::<SegmentDefinition>(json!({ "city": "北京" }));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_valid_definition_accepted() {
+        let definition: SegmentDefinition = serde_json::from_value(json!({
+            "roles": ["patient"],
+            "last_appointment_within_days": 90,
+            "channels": ["push"]
+        }))
+        .unwrap();
+
+        assert!(BroadcastService::validate_definition(&definition).is_ok());
+    }
+
+    #[test]
+    fn test_compose_multi_condition_segment() {
+        let after = Utc::now() - Duration::days(365);
+        let before = Utc::now();
+        let definition = SegmentDefinition {
+            roles: Some(vec!["patient".to_string()]),
+            last_appointment_within_days: Some(90),
+            doctor_departments: Some(vec!["内科".to_string(), "针灸科".to_string()]),
+            channels: Some(vec!["push".to_string(), "sms".to_string()]),
+            registered_after: Some(after),
+            registered_before: Some(before),
+        };
+
+        let query = BroadcastService::compose_segment_query(&definition);
+
+        assert!(query
+            .from_where
+            .starts_with("FROM users u WHERE u.status = 'active'"));
+        assert!(query.from_where.contains("u.role IN (?)"));
+        assert!(query.from_where.contains("INTERVAL ? DAY"));
+        assert!(query.from_where.contains("dd.department IN (?, ?)"));
+        assert!(query.from_where.contains("ns.push_enabled"));
+        assert!(query.from_where.contains("ns.sms_enabled"));
+        assert!(!query.from_where.contains("ns.email_enabled"));
+        assert!(query.from_where.contains("u.created_at >= ?"));
+        assert!(query.from_where.contains("u.created_at < ?"));
+
+        // Placeholders and parameters line up in order
+        assert_eq!(query.from_where.matches('?').count(), query.params.len());
+        assert_eq!(
+            query.params,
+            vec![
+                SegmentParam::Text("patient".to_string()),
+                SegmentParam::Int(90),
+                SegmentParam::Text("内科".to_string()),
+                SegmentParam::Text("针灸科".to_string()),
+                SegmentParam::Time(after),
+                SegmentParam::Time(before),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_user_input_never_inlined() {
+        let definition = SegmentDefinition {
+            doctor_departments: Some(vec!["内科') OR 1=1 --".to_string()]),
+            ..Default::default()
+        };
+
+        let query = BroadcastService::compose_segment_query(&definition);
+
+        assert!(!query.from_where.contains("OR 1=1"));
+        assert_eq!(query.params.len(), 1);
+    }
+}
@@ -0,0 +1,40 @@
+#[cfg(test)]
+mod tests {
+    use backend::utils::content_filter::{mask_keywords, FilterAction};
+
+    fn words(list: &[&str]) -> Vec<String> {
+        list.iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn test_mask_keywords_replaces_each_char() {
+        let masked = mask_keywords("隔壁诊所涉嫌诈骗", &words(&["诈骗"]));
+        assert_eq!(masked, "隔壁诊所涉嫌**");
+    }
+
+    #[test]
+    fn test_mask_keywords_is_case_insensitive() {
+        let masked = mask_keywords("Buy SPAM now, spam again", &words(&["spam"]));
+        assert_eq!(masked, "Buy **** now, **** again");
+    }
+
+    #[test]
+    fn test_mask_keywords_overlapping_matches() {
+        let masked = mask_keywords("赌博网站", &words(&["赌博", "博网"]));
+        assert_eq!(masked, "***站");
+    }
+
+    #[test]
+    fn test_mask_keywords_without_match() {
+        let text = "今天天气不错";
+        assert_eq!(mask_keywords(text, &words(&["诈骗", ""])), text);
+        assert_eq!(mask_keywords(text, &[]), text);
+    }
+
+    #[test]
+    fn test_filter_action_parse() {
+        assert_eq!(FilterAction::parse("reject"), Some(FilterAction::Reject));
+        assert_eq!(FilterAction::parse(" mask "), Some(FilterAction::Mask));
+        assert_eq!(FilterAction::parse("block"), None);
+    }
+}
@@ -0,0 +1,59 @@
+#[cfg(test)]
+mod tests {
+    use backend::utils::crypto::{
+        decrypt, encrypt, mask_account_number, parse_data_encryption_key,
+    };
+
+    const TEST_KEY: &str = "MDEyMzQ1Njc4OWFiY2RlZjAxMjM0NTY3ODlhYmNkZWY=";
+
+    fn set_test_key() {
+        std::env::set_var("DATA_ENCRYPTION_KEY", TEST_KEY);
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_round_trip() {
+        set_test_key();
+        let account_number = "6222021234567890123";
+        let encrypted = encrypt(account_number).unwrap();
+
+        assert!(!encrypted.contains(account_number));
+        assert_eq!(decrypt(&encrypted).unwrap(), account_number);
+    }
+
+    #[test]
+    fn test_encrypt_uses_random_nonce() {
+        set_test_key();
+        let first = encrypt("6222021234567890123").unwrap();
+        let second = encrypt("6222021234567890123").unwrap();
+
+        assert_ne!(first, second);
+    }
+
+    #[test]
+    fn test_decrypt_rejects_tampered_ciphertext() {
+        set_test_key();
+        let encrypted = encrypt("6222021234567890123").unwrap();
+        let mut chars: Vec<char> = encrypted.chars().collect();
+        chars[20] = if chars[20] == 'A' { 'B' } else { 'A' };
+        let tampered: String = chars.into_iter().collect();
+
+        assert!(decrypt(&tampered).is_err());
+    }
+
+    #[test]
+    fn test_data_encryption_key_must_be_32_bytes() {
+        assert!(parse_data_encryption_key(TEST_KEY).is_ok());
+        // JWT 风格的普通口令不能直接作为密钥
+        assert!(parse_data_encryption_key("default_jwt_secret").is_err());
+        assert!(parse_data_encryption_key("c2hvcnQta2V5").is_err());
+        assert!(parse_data_encryption_key("").is_err());
+    }
+
+    #[test]
+    fn test_mask_account_number() {
+        assert_eq!(
+            mask_account_number("6222 0212 3456 7890 123"),
+            "**** **** **** 0123"
+        );
+    }
+}
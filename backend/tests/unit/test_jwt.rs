@@ -1,6 +1,8 @@
 #[cfg(test)]
 mod tests {
-    use backend::utils::jwt::{create_token, decode_token, Claims};
+    use backend::utils::jwt::{
+        create_room_token, create_token, decode_room_token, decode_token, Claims,
+    };
     use uuid::Uuid;
 
     #[test]
@@ -75,4 +77,24 @@ mod tests {
         assert!(claims.exp > claims.iat);
         assert_eq!(claims.exp - claims.iat, expiration_seconds);
     }
+
+    #[test]
+    fn test_room_token_is_not_a_login_token() {
+        let consultation_id = Uuid::new_v4();
+        let user_id = Uuid::new_v4();
+        let secret = "test_secret_key";
+
+        let token = create_room_token(consultation_id, user_id, "patient".to_string(), secret, 600)
+            .unwrap();
+        let claims = decode_room_token(&token, secret).unwrap();
+        assert_eq!(claims.consultation_id, consultation_id);
+        assert_eq!(claims.sub, user_id);
+        assert_eq!(claims.role, "patient");
+
+        // The audience keeps room tokens and login tokens from standing in for each other
+        assert!(decode_token(&token, secret).is_err());
+        let login_token = create_token(user_id, "patient".to_string(), secret, 600).unwrap();
+        assert!(decode_room_token(&login_token, secret).is_err());
+        assert!(decode_room_token(&token, "wrong_secret_key").is_err());
+    }
 }
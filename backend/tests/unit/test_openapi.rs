@@ -0,0 +1,119 @@
+#[cfg(test)]
+mod tests {
+    use backend::openapi::ApiDoc;
+    use openapiv3::{OpenAPI, Operation, ReferenceOr};
+    use utoipa::OpenApi;
+
+    fn load_spec() -> OpenAPI {
+        let json = ApiDoc::openapi().to_json().expect("OpenAPI 文档序列化失败");
+        serde_json::from_str(&json).expect("OpenAPI 文档不符合规范")
+    }
+
+    fn operation<'a>(spec: &'a OpenAPI, path: &str, method: &str) -> &'a Operation {
+        let item = match spec.paths.paths.get(path) {
+            Some(ReferenceOr::Item(item)) => item,
+            _ => panic!("missing path {}", path),
+        };
+        let operation = match method {
+            "get" => item.get.as_ref(),
+            "post" => item.post.as_ref(),
+            "put" => item.put.as_ref(),
+            "delete" => item.delete.as_ref(),
+            _ => None,
+        };
+        operation.unwrap_or_else(|| panic!("missing {} {}", method, path))
+    }
+
+    #[test]
+    fn test_core_endpoints_documented() {
+        let spec = load_spec();
+
+        let expected = [
+            ("/api/v1/auth/register", "post", 200),
+            ("/api/v1/auth/login", "post", 200),
+            ("/api/v1/auth/login", "post", 401),
+            ("/api/v1/appointments", "get", 200),
+            ("/api/v1/appointments", "post", 200),
+            ("/api/v1/appointments/{id}", "get", 404),
+            ("/api/v1/appointments/available-slots", "get", 200),
+            ("/api/v1/doctors", "get", 200),
+            ("/api/v1/doctors/{id}", "get", 200),
+            ("/api/v1/doctors/{id}/photos", "put", 200),
+            ("/api/v1/payment/orders", "post", 201),
+            ("/api/v1/payment/orders/{id}", "get", 403),
+            ("/api/v1/payment/refunds", "post", 201),
+            ("/api/v1/payment/payment/callback", "post", 200),
+            ("/api/v1/payment/prices/{service_type}", "get", 404),
+            ("/api/v1/content/articles", "get", 200),
+            ("/api/v1/content/articles/{id}", "delete", 200),
+            ("/api/v1/content/recommended", "get", 200),
+        ];
+
+        for (path, method, status) in expected {
+            let op = operation(&spec, path, method);
+            assert!(
+                op.responses
+                    .responses
+                    .contains_key(&openapiv3::StatusCode::Code(status)),
+                "{} {} does not document status {}",
+                method,
+                path,
+                status
+            );
+        }
+    }
+
+    #[test]
+    fn test_request_and_response_schemas_resolve() {
+        let spec = load_spec();
+        let components = spec.components.as_ref().expect("缺少 components");
+
+        let login = operation(&spec, "/api/v1/auth/login", "post");
+        assert!(login.request_body.is_some());
+
+        // Every referenced schema must be declared in components
+        let json = serde_json::to_value(&spec).unwrap();
+        let mut refs = Vec::new();
+        collect_refs(&json, &mut refs);
+        assert!(!refs.is_empty());
+        for reference in refs {
+            let name = reference.trim_start_matches("#/components/schemas/");
+            assert!(
+                components.schemas.contains_key(name),
+                "unresolved schema reference {}",
+                reference
+            );
+        }
+    }
+
+    #[test]
+    fn test_protected_endpoints_require_bearer_token() {
+        let spec = load_spec();
+        let components = spec.components.as_ref().expect("缺少 components");
+        assert!(components.security_schemes.contains_key("bearer_auth"));
+
+        let protected = operation(&spec, "/api/v1/payment/orders", "post");
+        assert!(protected
+            .security
+            .as_ref()
+            .is_some_and(|reqs| reqs.iter().any(|r| r.contains_key("bearer_auth"))));
+
+        let public = operation(&spec, "/api/v1/doctors", "get");
+        assert!(public.security.is_none());
+    }
+
+    fn collect_refs(value: &serde_json::Value, refs: &mut Vec<String>) {
+        match value {
+            serde_json::Value::Object(map) => {
+                for (key, v) in map {
+                    match v {
+                        serde_json::Value::String(s) if key == "$ref" => refs.push(s.clone()),
+                        _ => collect_refs(v, refs),
+                    }
+                }
+            }
+            serde_json::Value::Array(items) => items.iter().for_each(|v| collect_refs(v, refs)),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,85 @@
+#[cfg(test)]
+mod tests {
+    use backend::models::payment::PaymentMethod;
+    use backend::services::payment_metrics_service::{PaymentAlertConfig, PaymentMetrics};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn metrics_with_hook(threshold: f64, min_samples: usize) -> (PaymentMetrics, Arc<AtomicUsize>) {
+        let metrics = PaymentMetrics::new(PaymentAlertConfig {
+            failure_rate_threshold: threshold,
+            window_secs: 60,
+            min_samples,
+            webhook_url: None,
+        });
+
+        let fired = Arc::new(AtomicUsize::new(0));
+        let counter = fired.clone();
+        metrics.add_alert_hook(Arc::new(move |_alert| {
+            counter.fetch_add(1, Ordering::SeqCst);
+        }));
+
+        (metrics, fired)
+    }
+
+    #[test]
+    fn test_counts_by_method() {
+        let (metrics, _) = metrics_with_hook(0.5, 10);
+
+        metrics.record_initiated(&PaymentMethod::Wechat);
+        metrics.record_initiated(&PaymentMethod::Wechat);
+        metrics.record_initiated(&PaymentMethod::Balance);
+        metrics.record_success(&PaymentMethod::Wechat);
+        metrics.record_failure(&PaymentMethod::Wechat);
+        metrics.record_success(&PaymentMethod::Balance);
+
+        let snapshot = metrics.snapshot();
+        let wechat = &snapshot.methods["wechat"];
+        assert_eq!(wechat.initiated, 2);
+        assert_eq!(wechat.succeeded, 1);
+        assert_eq!(wechat.failed, 1);
+        assert_eq!(snapshot.methods["balance"].succeeded, 1);
+        assert_eq!(snapshot.window_total, 3);
+        assert_eq!(snapshot.window_failed, 1);
+    }
+
+    #[test]
+    fn test_alert_fires_when_failure_rate_exceeds_threshold() {
+        let (metrics, fired) = metrics_with_hook(0.5, 4);
+
+        metrics.record_success(&PaymentMethod::Alipay);
+        metrics.record_success(&PaymentMethod::Alipay);
+
+        // 2 failures out of 4 is not above 50%
+        assert!(metrics.record_failure(&PaymentMethod::Alipay).is_none());
+        assert!(metrics.record_failure(&PaymentMethod::Alipay).is_none());
+        assert_eq!(fired.load(Ordering::SeqCst), 0);
+
+        // 3 of 5 crosses the threshold
+        let alert = metrics
+            .record_failure(&PaymentMethod::Alipay)
+            .expect("alert should fire");
+        assert_eq!(alert.total, 5);
+        assert_eq!(alert.failed, 3);
+        assert!(alert.failure_rate > 0.5);
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+
+        // The spike keeps going but the alert is not repeated within the window
+        for _ in 0..5 {
+            assert!(metrics.record_failure(&PaymentMethod::Alipay).is_none());
+        }
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+        assert!(metrics.snapshot().last_alert_at.is_some());
+    }
+
+    #[test]
+    fn test_no_alert_below_min_samples() {
+        let (metrics, fired) = metrics_with_hook(0.5, 10);
+
+        for _ in 0..5 {
+            assert!(metrics.record_failure(&PaymentMethod::Wechat).is_none());
+        }
+        assert_eq!(fired.load(Ordering::SeqCst), 0);
+        assert_eq!(metrics.snapshot().window_failure_rate, 1.0);
+    }
+}
@@ -0,0 +1,155 @@
+#[cfg(test)]
+mod tests {
+    use backend::models::payment::{BillEntry, PaymentMethod};
+    use backend::services::alipay_service::AlipayService;
+    use backend::services::payment_service::PaymentService;
+    use backend::services::scheduler_service::SchedulerService;
+    use backend::services::wechat_pay_service::WechatPayService;
+    use chrono::{TimeZone, Utc};
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    const WECHAT_BILL: &str = "\u{feff}交易时间,公众账号ID,商户号,特约商户号,设备号,微信订单号,商户订单号,用户标识,交易类型,交易状态,付款银行,货币种类,应结订单金额,代金券金额,商品名称,商户数据包,手续费,费率,订单金额,费率备注
+`2024-01-21 10:00:00,`wxtestappid,`1900000001,`0,`,`4200000000202401210000000001,`ORDWX001,`oUser1,`NATIVE,`SUCCESS,`OTHERS,`CNY,`30.00,`0.00,`在线问诊,`,`0.18000,`0.60%,`30.00,`
+`2024-01-21 11:30:00,`wxtestappid,`1900000001,`0,`,`4200000000202401210000000002,`ORDWX002,`oUser2,`JSAPI,`SUCCESS,`CMB_DEBIT,`CNY,`45.50,`0.00,`预约挂号,`,`0.27000,`0.60%,`45.50,`
+`2024-01-21 12:00:00,`wxtestappid,`1900000001,`0,`,`4200000000202401210000000003,`ORDWX003,`oUser3,`NATIVE,`REVOKED,`OTHERS,`CNY,`10.00,`0.00,`在线问诊,`,`0.00000,`0.60%,`10.00,`
+总交易单数,应结订单总金额,退款总金额,充值券退款总金额,手续费总金额,订单总金额,申请退款总金额
+`2,`75.50,`0.00,`0.00,`0.45000,`75.50,`0.00
+";
+
+    const ALIPAY_BILL: &str = "#支付宝业务明细查询
+#账号：[20880000000000000156]
+#起始日期：[2024年01月21日 00:00:00]   终止日期：[2024年01月22日 00:00:00]
+#-----------------------------------------业务明细列表----------------------------------------
+支付宝交易号,商户订单号,业务类型,商品名称,创建时间,完成时间,门店编号,门店名称,操作员,终端号,对方账户,订单金额（元）,商家实收（元）,支付宝红包（元）,集分宝（元）,支付宝优惠（元）,商家优惠（元）,券核销金额（元）,券名称,商家红包消费金额（元）,卡消费金额（元）,退款批次号/请求号,服务费（元）,分润（元）,备注
+2024012122001400000000000001\t,ORDALI001\t,交易\t,在线问诊\t,2024-01-21 09:00:00\t,2024-01-21 09:00:10\t,\t,\t,\t,\t,buyer@example.com\t,88.00\t,88.00\t,0.00\t,0.00\t,0.00\t,0.00\t,0.00\t,\t,0.00\t,0.00\t,\t,-0.53\t,0.00\t,
+2024012122001400000000000001\t,ORDALI001\t,退款\t,在线问诊\t,2024-01-21 09:00:00\t,2024-01-21 15:00:00\t,\t,\t,\t,\t,buyer@example.com\t,-20.00\t,-20.00\t,0.00\t,0.00\t,0.00\t,0.00\t,0.00\t,\t,0.00\t,0.00\t,RF001\t,0.12\t,0.00\t,
+#-----------------------------------------业务明细列表结束------------------------------------
+#交易合计：1笔，商家实收共88.00元，商家优惠共0.00元
+#退款合计：1笔，商家实收退款共-20.00元，商家优惠退款共0.00元
+";
+
+    fn entry(method: PaymentMethod, order_no: &str, amount: &str) -> BillEntry {
+        BillEntry {
+            payment_method: method,
+            order_no: order_no.to_string(),
+            external_transaction_id: None,
+            amount: Decimal::from_str(amount).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_wechat_bill_keeps_successful_trades_and_stops_at_summary() {
+        let entries = WechatPayService::parse_trade_bill(WECHAT_BILL).unwrap();
+
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].order_no, "ORDWX001");
+        assert_eq!(
+            entries[0].external_transaction_id.as_deref(),
+            Some("4200000000202401210000000001")
+        );
+        assert_eq!(entries[0].amount, Decimal::from(30));
+        assert_eq!(entries[1].order_no, "ORDWX002");
+        assert_eq!(entries[1].amount, Decimal::from_str("45.50").unwrap());
+        assert!(entries
+            .iter()
+            .all(|entry| matches!(entry.payment_method, PaymentMethod::Wechat)));
+    }
+
+    #[test]
+    fn test_alipay_bill_skips_comments_and_refunds() {
+        let entries = AlipayService::parse_trade_bill(ALIPAY_BILL).unwrap();
+
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].order_no, "ORDALI001");
+        assert_eq!(
+            entries[0].external_transaction_id.as_deref(),
+            Some("2024012122001400000000000001")
+        );
+        assert_eq!(entries[0].amount, Decimal::from(88));
+        assert!(matches!(entries[0].payment_method, PaymentMethod::Alipay));
+    }
+
+    #[test]
+    fn test_empty_bill_has_no_entries() {
+        assert!(WechatPayService::parse_trade_bill("").unwrap().is_empty());
+        assert!(AlipayService::parse_trade_bill("").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_malformed_bill_is_rejected() {
+        assert!(WechatPayService::parse_trade_bill("交易时间,商户订单号\n`x,`y").is_err());
+
+        let bad_amount = WECHAT_BILL.replace("`30.00,`", "`abc,`");
+        assert!(WechatPayService::parse_trade_bill(&bad_amount).is_err());
+    }
+
+    #[test]
+    fn test_compare_bills_reports_each_kind_of_discrepancy() {
+        let local = vec![
+            entry(PaymentMethod::Wechat, "ORD1", "30.00"),
+            entry(PaymentMethod::Wechat, "ORD2", "45.50"),
+            entry(PaymentMethod::Alipay, "ORD3", "88"),
+            entry(PaymentMethod::Alipay, "ORD4", "12.00"),
+        ];
+        let remote = vec![
+            entry(PaymentMethod::Wechat, "ORD1", "30"),
+            entry(PaymentMethod::Wechat, "ORD2", "40.50"),
+            entry(PaymentMethod::Alipay, "ORD3", "88.00"),
+            entry(PaymentMethod::Wechat, "ORD5", "9.90"),
+            // Same order number under another channel does not match
+            entry(PaymentMethod::Wechat, "ORD4", "12.00"),
+        ];
+
+        let comparison = PaymentService::compare_bills(&local, &remote);
+
+        assert!(!comparison.is_balanced());
+        let missing_local: Vec<&str> = comparison
+            .missing_local
+            .iter()
+            .map(|entry| entry.order_no.as_str())
+            .collect();
+        assert_eq!(missing_local, vec!["ORD5", "ORD4"]);
+
+        assert_eq!(comparison.missing_remote.len(), 1);
+        assert_eq!(comparison.missing_remote[0].order_no, "ORD4");
+        assert!(matches!(
+            comparison.missing_remote[0].payment_method,
+            PaymentMethod::Alipay
+        ));
+
+        assert_eq!(comparison.amount_mismatches.len(), 1);
+        let mismatch = &comparison.amount_mismatches[0];
+        assert_eq!(mismatch.order_no, "ORD2");
+        assert_eq!(mismatch.local_amount, Decimal::from_str("45.50").unwrap());
+        assert_eq!(mismatch.remote_amount, Decimal::from_str("40.50").unwrap());
+    }
+
+    #[test]
+    fn test_compare_bills_balanced() {
+        let local = WechatPayService::parse_trade_bill(WECHAT_BILL).unwrap();
+        let remote = WechatPayService::parse_trade_bill(WECHAT_BILL).unwrap();
+
+        let comparison = PaymentService::compare_bills(&local, &remote);
+        assert!(comparison.is_balanced());
+        assert!(PaymentService::compare_bills(&[], &[]).is_balanced());
+    }
+
+    #[test]
+    fn test_reconciliation_runs_daily_at_ten_beijing_time() {
+        // 01:00 UTC is 09:00 in Beijing
+        let before = Utc.with_ymd_and_hms(2024, 1, 22, 1, 0, 0).unwrap();
+        assert_eq!(
+            SchedulerService::until_next_reconciliation(before),
+            Duration::from_secs(60 * 60)
+        );
+
+        // 02:30 UTC is 10:30 in Beijing, so the next run is tomorrow
+        let after = Utc.with_ymd_and_hms(2024, 1, 22, 2, 30, 0).unwrap();
+        assert_eq!(
+            SchedulerService::until_next_reconciliation(after),
+            Duration::from_secs(23 * 60 * 60 + 30 * 60)
+        );
+    }
+}